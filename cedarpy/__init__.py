@@ -1,10 +1,36 @@
 import json
+import os
+import time
 from copy import copy
 from enum import Enum
-from typing import Union, List, Any
+from typing import Union, List, Any, Optional, Dict, Callable, Tuple
 
 from cedarpy import _internal
 
+# The exceptions cedarpy raises: CedarError, a ValueError, or the subclass for the kind of error.  Each has its
+# 'code', and, when the error is in an input whose text is at hand, e.g. the policies of compile_policies or
+# format_policies, its 'span' in the text, as in parse_policy_partial's diagnostics, and the line it's on as its
+# 'snippet'; both are None otherwise.
+CedarError = _internal.CedarError
+PolicyParseError = _internal.PolicyParseError  # code 'policy_parse_error'
+SchemaParseError = _internal.SchemaParseError  # code 'schema_error'
+EntityError = _internal.EntityError  # codes 'entities_error' and 'unknown_entity'
+RequestError = _internal.RequestError  # code 'request_error'
+ValidationError = _internal.ValidationError  # code 'validation_error'
+InvalidArgumentError = _internal.InvalidArgumentError  # code 'invalid_argument'
+
+# The exception class raised for each code by the strict variants of is_authorized
+_ERROR_CLASSES = {
+    "policy_parse_error": PolicyParseError,
+    "schema_error": SchemaParseError,
+    "entities_error": EntityError,
+    "unknown_entity": EntityError,
+    "request_error": RequestError,
+    "unmodeled_action": RequestError,
+    "validation_error": ValidationError,
+    "invalid_argument": InvalidArgumentError,
+}
+
 
 def echo(s: str) -> str:
     return _internal.echo(s)
@@ -18,25 +44,79 @@ class Decision(Enum):
 
 class Diagnostics:
 
-    def __init__(self, diagnostics: dict) -> None:
+    def __init__(self, diagnostics: dict, warnings: Optional[List[str]] = None) -> None:
         super().__init__()
         self._diagnostics: dict = diagnostics
+        self._warnings: List[str] = warnings if warnings is not None else diagnostics.get('warnings', list())
 
     @property
     def errors(self) -> List[str]:
         return self._diagnostics.get('errors', list())
 
+    @property
+    def error_codes(self) -> List[str]:
+        """a machine-readable code for each of the errors, in the same order, e.g. 'policy_parse_error'"""
+        return self._diagnostics.get('error_codes', list())
+
     @property
     def reasons(self) -> List[str]:
         # (intentionally) map 'reason' key in diagnostics dict to 'reasons' property (plural)
         return self._diagnostics.get('reason', list())
 
+    @property
+    def warnings(self) -> List[str]:
+        """the result's warnings, which are reported apart from the diagnostics; see AuthzResult.warnings"""
+        return self._warnings
+
+    @property
+    def missing_entities(self) -> List[str]:
+        return self._diagnostics.get('missing_entities', list())
+
+    @property
+    def reason_by_engine(self) -> Dict[str, List[str]]:
+        """the determining policies of each engine, for a result of is_authorized_union"""
+        return self._diagnostics.get('reason_by_engine', dict())
+
+    @property
+    def reason_templates(self) -> Dict[str, str]:
+        """the template of each of the reasons that is a template link, by the link's id"""
+        return self._diagnostics.get('reason_templates', dict())
+
+    @property
+    def policy_results(self) -> List[dict]:
+        """the result of each policy, in the order of their ids, when requested with include_policy_results, e.g.
+        {"id": "policy1", "effect": "permit", "result": "errored", "error": "..."}, where the result is 'satisfied',
+        'not_applicable', or 'errored'"""
+        return self._diagnostics.get('policy_results', list())
+
+    @property
+    def reason_guardrails(self) -> List[str]:
+        """the reasons that are guardrails, for a result of a CedarEngine with guardrails"""
+        return self._diagnostics.get('reason_guardrails', list())
+
+    @property
+    def reason_by_layer(self) -> Dict[str, List[str]]:
+        """the determining policies of each layer of a composed policy set: 'guardrails' and 'policies' for a result
+        of a CedarEngine with guardrails, or each engine, and each engine's guardrails as '<engine>.guardrails', for a
+        result of is_authorized_union"""
+        return self._diagnostics.get('reason_by_layer', dict())
+
+    @property
+    def decided_by(self) -> Optional[str]:
+        """a summary of the layers that decided a result with reason_by_layer, e.g. 'denied by guardrails', 'allowed
+        by app', or 'denied by default' when no policy did"""
+        return self._diagnostics.get('decided_by')
+
 
 class AuthzResult:
-    def __init__(self, authz_resp: dict) -> None:
+    def __init__(self, authz_resp: Union[dict, _internal.AuthzResponse]) -> None:
+        """
+        :param authz_resp the result as a dict, or the AuthzResponse is_authorized returns, whose fields are
+        converted to Python objects only when they're read
+        """
         super().__init__()
         self._authz_resp = authz_resp
-        self._diagnostics = Diagnostics(self._authz_resp.get('diagnostics', {}))
+        self._diagnostics: Optional[Diagnostics] = None
 
     @property
     def decision(self) -> Decision:
@@ -52,87 +132,881 @@ class AuthzResult:
 
     @property
     def diagnostics(self) -> Diagnostics:
+        if self._diagnostics is None:
+            diagnostics = self._authz_resp.diagnostics if isinstance(self._authz_resp, _internal.AuthzResponse) \
+                else self._authz_resp.get('diagnostics', {})
+            self._diagnostics = Diagnostics(diagnostics, self.warnings)
         return self._diagnostics
 
+    @property
+    def warnings(self) -> List[str]:
+        """non-fatal conditions operators should know about, reported whether or not diagnostics are included and
+        never changing the decision: e.g. no schema was provided to validate the request, the entities are stale,
+        entities' parents are missing, or inputs use deprecated syntax"""
+        return self._authz_resp.get('warnings', list())
+
+    @property
+    def cache_ttl_seconds(self) -> Optional[int]:
+        """how long the decision may be cached, for a result of a CedarEngine with cache TTLs; see CedarEngine"""
+        return self._authz_resp.get('cache_ttl_seconds', None)
+
+    @property
+    def denial_class(self) -> Optional[str]:
+        """what kind of fix a Deny needs, when requested with include_denial_class and there's a schema: 'forbidden'
+        (a forbid was satisfied), 'structural' (no permit's scope can ever cover the principal's type, the action, and
+        the resource's type), 'unmatched_scope' (a permit's scope covers the types, but not the request's entities), or
+        'conditional' (a permit's scope includes the request, but its conditions weren't satisfied); None otherwise"""
+        return self._authz_resp.get('denial_class', None)
+
+    @property
+    def fingerprints(self) -> dict:
+        """short hashes of the inputs that decided the request, to match a decision log to the exact versions that
+        produced it: {"policy_set": "9f86d081884c7d65"}, plus "entities" and "schema" for a result of a CedarEngine.
+        Empty for a result that no policies decided, e.g. one that failed to parse"""
+        return self._authz_resp.get('fingerprints', {})
+
+    @property
+    def attributes(self) -> Dict[str, dict]:
+        """the attributes of the request's entities selected with include_attributes, by the entity's role in the
+        request, e.g. {"principal": {"department": "eng"}}.  Empty unless requested"""
+        return self._authz_resp.get('attributes', {})
+
+    @property
+    def retryable(self) -> bool:
+        """whether a result with errors may be decided if its request is resubmitted as it is: True when every error
+        is transient, i.e. overloaded, deadline_exceeded, policy_store_unavailable, or stale_version, and False for a
+        result without errors or with any error in the request or inputs, e.g. a parse or validation error, which
+        fails again.
+        Reported whether or not diagnostics are included"""
+        return self._authz_resp.get('retryable', False)
+
     @property
     def metrics(self) -> dict:
         return self._authz_resp.get('metrics', {})
 
+    def to_dict(self) -> dict:
+        """the result as returned by the library, e.g. to write to a decision log"""
+        if isinstance(self._authz_resp, _internal.AuthzResponse):
+            self._authz_resp = self._authz_resp.to_dict()
+        return self._authz_resp
+
     def __getitem__(self, __name: str) -> Any:
         return getattr(self, __name)
 
 
+def defaults() -> dict:
+    """The defaults used for the arguments that callers don't pass, as read from environment variables when cedarpy
+    is imported: CEDARPY_ENTITY_VALIDATION ('strict'), CEDARPY_UNKNOWN_ENTITIES ('ignore'), CEDARPY_POLICY_ERRORS
+    ('skip'), CEDARPY_VERBOSE (false), CEDARPY_THREADS (1), CEDARPY_MAX_THREADS (the number of CPUs), the size of the
+    thread pool every batch shares, CEDARPY_REQUEST_CACHE_SIZE (1024), and
+    CEDARPY_GENERATE_CORRELATION_IDS (false), which gives requests without a correlation_id a generated UUIDv7, returned
+    in their result's correlation_id.  An invalid variable fails the import with a ValueError.
+
+    :returns a dict of the defaults, e.g. {"entity_validation": "strict", ..., "request_cache_size": 1024}
+    """
+    return json.loads(_internal.defaults())
+
+
+def versions() -> dict:
+    """The versions of cedarpy and of the Cedar it's built with
+
+    :returns a dict of the versions, e.g. {"cedarpy": "2.2.0", "cedar": "2.2.0"}
+    """
+    return json.loads(_internal.versions())
+
+
+class PolicySetHandle:
+    """Policies parsed once by compile_policies, which can be passed to is_authorized, is_authorized_batch,
+    is_authorized_expanded, and is_authorized_batch_decisions in place of their text, so that calls with the same
+    policies skip parsing them.  A handle never changes, so it may be shared across calls and threads; link_template,
+    add_policy, and remove_policy make a new handle."""
+
+    def __init__(self, handle) -> None:
+        super().__init__()
+        self._handle = handle
+
+    @property
+    def num_policies(self) -> int:
+        """the number of static policies and template links"""
+        return self._handle.num_policies()
+
+    @property
+    def num_templates(self) -> int:
+        return self._handle.num_templates()
+
+    @property
+    def fingerprint(self) -> str:
+        """the fingerprint of the policies' text, as reported in the fingerprints of the results they decide, or, for
+        a handle with links or edits, of the policies, templates, and links it holds, whatever edits made them"""
+        return self._handle.fingerprint()
+
+    @property
+    def parse_duration_micros(self) -> int:
+        """how long the policies took to parse when they were compiled"""
+        return self._handle.parse_duration_micros()
+
+    def link_template(self, template_id: str, link_id: str, values: Dict[str, Union[str, dict]]) -> 'PolicySetHandle':
+        """Link a template to the entities of its slots, e.g.
+
+            handle.link_template("policy0", "alice-view", {"?principal": 'User::"alice"'})
+
+        :param template_id is the id of the template
+        :param link_id is the id of the policy the link makes, reported in the reasons of the decisions it determines
+        :param values is the entity of each of the template's slots, '?principal' and '?resource', as a uid str or
+        a dict with its 'type' and 'id'
+
+        :returns a new PolicySetHandle of these policies and the link; this handle doesn't change
+        :raises ValueError: if there's no such template, the link_id is taken, or the values don't fill its slots
+        """
+        return PolicySetHandle(self._handle.link_template(template_id, link_id, json.dumps(values)))
+
+    def add_policy(self, text: str, id: Optional[str] = None) -> 'PolicySetHandle':
+        """Add a static policy, parsing only it, e.g. when a tenant adds a policy at runtime.
+
+        :param text is the policy's text, a single static policy
+        :param id (optional) is the policy's id, reported in the reasons of the decisions it determines; defaults to
+        the first policyN that isn't taken
+
+        :returns a new PolicySetHandle of these policies and the policy; this handle doesn't change
+        :raises ValueError: if the text isn't a single static policy, with the code 'policy_parse_error', or the id is
+        taken
+        """
+        return PolicySetHandle(self._handle.add_policy(text, id))
+
+    def remove_policy(self, id: str) -> 'PolicySetHandle':
+        """Remove a static policy or template link, or a template that isn't linked.
+
+        :param id is the id of the policy
+
+        :returns a new PolicySetHandle of these policies without it; this handle doesn't change
+        :raises ValueError: if there's no such policy, or the template is linked
+        """
+        return PolicySetHandle(self._handle.remove_policy(id))
+
+    def get_policy(self, id: str) -> Optional[str]:
+        """the text of the static policy or template link with the id, or None if there is none"""
+        return self._handle.get_policy(id)
+
+    def list_policy_ids(self) -> List[str]:
+        """the ids of the static policies and template links, shortest first and then in lexical order, so the ids of
+        parsed policies, policy0, policy1, and so on, are in the order of the policies' text"""
+        return self._handle.list_policy_ids()
+
+
+class SchemaHandle:
+    """A schema parsed once by compile_schema, which can be passed to is_authorized and its variants,
+    validate_policies, EntityStore, and CedarEngine in place of its JSON, so that calls with the same schema skip
+    parsing it.  A handle never changes, so it may be shared across calls and threads."""
+
+    def __init__(self, handle) -> None:
+        super().__init__()
+        self._handle = handle
+
+    @property
+    def fingerprint(self) -> str:
+        """the fingerprint of the schema's text, as reported in the fingerprints of an engine's results"""
+        return self._handle.fingerprint()
+
+    @property
+    def parse_duration_micros(self) -> int:
+        """how long the schema took to parse when it was compiled"""
+        return self._handle.parse_duration_micros()
+
+
+class EntityStore:
+    """Parsed entities that are kept across calls and changed an entity at a time, which can be passed to
+    is_authorized, is_authorized_batch, is_authorized_expanded, and is_authorized_batch_decisions in place of the
+    entities' JSON, so that calls with a large entity graph that changes little between them skip parsing it.  Each
+    entity is parsed when it's added, and validated against the store's schema, if it has one; the entities'
+    hierarchy is computed on the first call after a change.  A store may be shared across calls and threads."""
+
+    def __init__(self,
+                 entities: Union[str, List[dict], bytes, os.PathLike, None] = None,
+                 schema: Union[str, dict, SchemaHandle, None] = None) -> None:
+        """
+        :param entities (optional) the initial entities, as a list of entities or a json-formatted string of them,
+        or bytes or an os.PathLike path of the json, which may be gzip- or zstd-compressed
+        :param schema (optional) dictionary or json-formatted string containing the Cedar schema the entities are
+        validated against, or a SchemaHandle of it; calls with the store should pass the same schema, whose actions
+        the store includes
+
+        :raises ValueError: if the schema or the entities are invalid, or an entity's uid is repeated
+        """
+        super().__init__()
+        self._store = _internal.EntityStore(entities,
+                                            _to_schema_str(schema))
+
+    def add_entities(self, entities: Union[str, List[dict]]) -> None:
+        """Add entities, none of which may be in the store already; when any is invalid or already in the store,
+        none are added
+
+        :raises ValueError: if an entity is invalid or its uid is in the store already
+        """
+        self._store.add_entities(entities)
+
+    def upsert_entity(self, entity: Union[str, dict]) -> bool:
+        """Add an entity, or replace the entity with its uid
+
+        :returns whether an entity was replaced
+        :raises ValueError: if the entity is invalid
+        """
+        return self._store.upsert_entity(entity)
+
+    def remove_entity(self, uid: str) -> bool:
+        """Remove the entity with the uid, e.g. 'User::"alice"'; the entities it's a parent of keep it as a parent
+
+        :returns whether the entity was in the store
+        :raises ValueError: if the uid is invalid
+        """
+        return self._store.remove_entity(uid)
+
+    def __len__(self) -> int:
+        return len(self._store)
+
+    def __contains__(self, uid: str) -> bool:
+        return uid in self._store
+
+
+def entities_from_table(table: Union[str, bytes, os.PathLike, Dict[str, list], Any],
+                        mapping: dict,
+                        format: Optional[str] = None) -> str:
+    """Convert a table of entities, e.g. a CSV export or a Parquet file of a warehouse table, to Cedar's JSON entities,
+    which can be passed as the entities of is_authorized and its variants, a CedarEngine, or an EntityStore.  The rows
+    are converted in Rust.  A Parquet file or pyarrow.Table is first converted with to_pydict(), which makes a Python
+    object of every cell, so it takes several times its Arrow size in memory while it's converted; convert a large
+    table in slices, or as CSV, which is read in Rust without making Python objects.
+
+    :param table is the table: CSV text, as a str, or bytes or an os.PathLike path of it, which may be gzip- or
+    zstd-compressed; the os.PathLike path of a Parquet file, which is read with pyarrow; a pyarrow.Table; or a dict
+    from each column's name to the list of its values
+    :param mapping is how the columns map to the entities: {"type": "User", "id": "user_id"} for the type of every
+    row's entity and the column of its id, or {"uid": "uid"} for the column of its uid, e.g. 'User::"alice"'; plus
+    "attributes", a dict from each attribute to its column, or a list of columns named like their attributes; "types",
+    how the text of CSV cells is read for each attribute: 'string' (the default), 'long', 'bool', 'entity' (a uid str),
+    or 'json'; and "parents", the column of each row's parent uids, as a list or separated by "parents_separator",
+    ';' by default.  An empty or None cell leaves the attribute out of its entity
+    :param format (optional) 'csv' or 'parquet'; defaults to 'parquet' for a path ending in '.parquet', and 'csv'
+    otherwise
+
+    :returns the entities as Cedar's JSON entities, a json-formatted str
+    :raises ValueError: if the mapping is invalid, with code 'invalid_argument', or a row cannot be converted, with
+    code 'entities_error'
+    :raises ImportError: if the table is a Parquet file and pyarrow, the pyarrow extra, is not installed
+    """
+    if format is None:
+        is_parquet = isinstance(table, os.PathLike) and os.fspath(table).endswith(".parquet")
+        format = "parquet" if is_parquet else "csv"
+    mapping_str = json.dumps(mapping)
+    if isinstance(table, dict):
+        columns = table
+    elif hasattr(table, "to_pydict"):
+        columns = table.to_pydict()
+    elif format == "parquet":
+        try:
+            import pyarrow.parquet
+        except ImportError as e:
+            raise ImportError("reading Parquet requires pyarrow; install it with pip install cedarpy[pyarrow]") from e
+        columns = pyarrow.parquet.read_table(table).to_pydict()
+    elif format == "csv":
+        return _internal.entities_from_csv_table(table, mapping_str)
+    else:
+        raise ValueError(f"invalid format {format}; expected 'csv' or 'parquet'")
+    # values Arrow has no JSON form for, e.g. dates and decimals, are given as their text
+    columns_str = json.dumps({name: list(values) for name, values in columns.items()}, default=str)
+    return _internal.entities_from_column_table(columns_str, mapping_str)
+
+
+def compile_policies(policies: str, template_links: Optional[List[dict]] = None) -> PolicySetHandle:
+    """Parse the policies once, for authorizing many calls' requests against them without parsing them each call.
+
+    :param policies is a str containing all the policies in the Cedar PolicySet, which may include templates, e.g.
+    permit(principal == ?principal, action, resource in ?resource);  each is identified by its position, like the
+    static policies, e.g. 'policy0'
+    :param template_links (optional) a list of links of the templates to the entities of their slots, in the form of
+    the 'templateLinks' of a Cedar JSON policy set: {"templateId": "policy0", "newId": "alice-view",
+    "values": {"?principal": 'User::"alice"'}}; an entity is a uid str or a dict with its 'type' and 'id'
+
+    :returns a PolicySetHandle, to pass as the policies of is_authorized and its batch variants
+    :raises ValueError: if the policies cannot be parsed, with code 'policy_parse_error', or a template link is
+    invalid, with code 'invalid_argument'
+    """
+    template_links_str = None if template_links is None else json.dumps(template_links)
+    return PolicySetHandle(_internal.compile_policies(policies, template_links_str))
+
+
+def compile_schema(schema: Union[str, dict]) -> SchemaHandle:
+    """Parse the schema once, for passing to many calls without parsing it each call.
+
+    :param schema is a str containing the schema in JSON format or a schema dict
+
+    :returns a SchemaHandle, to pass as the schema of is_authorized and its variants, validate_policies, EntityStore,
+    and CedarEngine
+    :raises ValueError: if the schema is invalid, with code 'schema_error'
+    """
+    return SchemaHandle(_internal.compile_schema(_to_schema_str(schema)))
+
+
 def is_authorized(request: dict,
-                  policies: str,
-                  entities: Union[str, List[dict]],
-                  schema: Union[str, dict, None] = None,
-                  verbose: bool = False) -> AuthzResult:
+                  policies: Union[str, PolicySetHandle],
+                  entities: Union[str, List[dict], bytes, os.PathLike, EntityStore],
+                  schema: Union[str, dict, SchemaHandle, None] = None,
+                  verbose: Optional[bool] = None,
+                  include_diagnostics: bool = True,
+                  include_metrics: bool = True,
+                  entity_validation: Optional[str] = None,
+                  unknown_entities: Optional[str] = None,
+                  policy_errors: Optional[str] = None,
+                  include_missing_entities: bool = False,
+                  validate_request: bool = False,
+                  include_policy_results: bool = False,
+                  include_attributes: Optional[Dict[str, List[str]]] = None,
+                  include_denial_class: bool = False) -> AuthzResult:
     """Evaluate whether the request is authorized given the parameters.
 
     :param request is a Cedar-style request object containing a principal, action, resource, and (optional) context;
     context may be a dict (preferred) or a string
-    :param policies is a str containing all the policies in the Cedar PolicySet
+    :param policies is a str containing all the policies in the Cedar PolicySet, or a PolicySetHandle of them from
+    compile_policies
     :param entities a list of entities or a json-formatted string containing the list of entities to
-    include in the evaluation
-    :param schema (optional) dictionary or json-formatted string containing the Cedar schema
+    include in the evaluation, or bytes or an os.PathLike path of the json, which may be gzip- or zstd-compressed
+    and is decompressed in Rust with the GIL released, or an EntityStore
+    :param schema (optional) dictionary or json-formatted string containing the Cedar schema, or a SchemaHandle of it
+    from compile_schema
     :param verbose (optional) boolean determining whether to enable verbose logging output within the library
+    :param include_diagnostics (optional) boolean determining whether to include diagnostics in the result
+    :param include_metrics (optional) boolean determining whether to include metrics in the result
+    :param entity_validation (optional) how entities are validated against the schema: 'strict' (entities must
+    conform to a valid schema), 'warn' (fall back to loading entities unvalidated, with a warning in the diagnostics),
+    'permissive' (entities and contexts are validated against the schema after dropping undeclared attributes, with a
+    warning for each), or 'none' (entities are not validated)
+    :param unknown_entities (optional) how a request whose principal or resource is not in the entities is handled:
+    'ignore' (evaluate it as an entity without attributes or parents), 'warn' (evaluate it, with a warning in the
+    diagnostics), or 'error' (do not evaluate it; the result has no decision and an error in the diagnostics)
+    :param policy_errors (optional) how errors evaluating individual policies are handled: 'skip' (the policy is
+    skipped and the error reported in the diagnostics, as Cedar does) or 'propagate' (any such error fails the whole
+    request; the result has no decision and the errors in the diagnostics)
+    :param include_missing_entities (optional) boolean determining whether to report the entities each request refers
+    to that are not in the entities, i.e. its principal and resource, its action when a schema is provided, since the
+    schema declares the actions, and any entity Cedar could not find while evaluating the policies, in
+    diagnostics.missing_entities
+    :param validate_request (optional) boolean determining whether, when a schema is provided, a request that does not
+    conform to it, i.e. whose action is not declared, whose principal or resource is not of a type the action applies
+    to, or whose context does not have the attributes of the action's context, is not evaluated, and an invalid schema
+    is reported rather than ignored; the result has no decision and the error in the diagnostics
+    :param include_policy_results (optional) boolean determining whether to report, in diagnostics.policy_results,
+    whether each policy was 'satisfied', 'not_applicable' to the request, or 'errored', with its error, e.g. to debug
+    a Deny.  Each policy is evaluated on its own for this, so it's slower, and meant for debugging
+    :param include_attributes (optional) the attributes of the request's entities to copy from the entities into the
+    result's attributes, by the entity's role in the request, e.g. {"principal": ["department"], "resource":
+    ["owner"]}, to build an audit record or a message without looking the entities up again.  An entity that is not in
+    the entities, and an attribute its entity does not have, are left out
+    :param include_denial_class (optional) boolean determining whether, when a schema is provided, a Deny is
+    classified by the kind of fix it needs, in the result's denial_class.  The classification is approximate, since
+    only the policies' scopes are analyzed: it's 'forbidden' when a forbid was satisfied, 'structural' when no permit's
+    scope can ever cover the principal's type, the action, and the resource's type, 'unmatched_scope' when a permit's
+    scope covers the types but not the request's entities, and 'conditional' when a permit's scope includes the
+    request but its conditions weren't satisfied
 
     :returns an AuthzResult
 
     """
-    return is_authorized_batch(requests=[request],
-                               policies=policies,
-                               entities=entities,
-                               schema=schema,
-                               verbose=verbose)[0]
+    return AuthzResult(_internal.is_authorized(request, _to_internal_policies(policies),
+                                               _to_internal_entities(entities), _to_schema_str(schema), verbose,
+                                               include_diagnostics, include_metrics, entity_validation,
+                                               unknown_entities, policy_errors, include_missing_entities,
+                                               validate_request, include_policy_results,
+                                               _to_include_attributes_str(include_attributes),
+                                               include_denial_class))
 
 
-def is_authorized_batch(requests: List[dict],
-                        policies: str,
-                        entities: Union[str, List[dict]],
-                        schema: Union[str, dict, None] = None,
-                        verbose: bool = False) -> List[AuthzResult]:
+def is_authorized_strict(request: dict,
+                         policies: Union[str, PolicySetHandle],
+                         entities: Union[str, List[dict], bytes, os.PathLike, EntityStore],
+                         schema: Union[str, dict, SchemaHandle, None] = None,
+                         verbose: Optional[bool] = None,
+                         include_metrics: bool = True,
+                         entity_validation: Optional[str] = None,
+                         unknown_entities: Optional[str] = None,
+                         policy_errors: Optional[str] = None,
+                         include_missing_entities: bool = False,
+                         validate_request: bool = False,
+                         include_policy_results: bool = False,
+                         include_attributes: Optional[Dict[str, List[str]]] = None,
+                         include_denial_class: bool = False) -> AuthzResult:
+    """Evaluate whether the request is authorized, as is_authorized does, but raise the error of a request that
+    can't be decided rather than return a NoDecision result, e.g. for callers that treat any invalid input as a bug.
+    The result always has diagnostics.
+
+    :param request, policies, entities, schema, and the options are as for is_authorized
+
+    :returns an AuthzResult, which is Allow or Deny
+    :raises CedarError: the subclass for the code of the first error of a request that couldn't be decided, e.g. an
+    EntityError for entities_error or a RequestError for request_error, with all of its errors as its message
+    """
+    return _decided(is_authorized(request, policies, entities, schema, verbose, True, include_metrics,
+                                  entity_validation, unknown_entities, policy_errors, include_missing_entities,
+                                  validate_request, include_policy_results, include_attributes,
+                                  include_denial_class))
+
+
+def _decided(authz_result: AuthzResult) -> AuthzResult:
+    """The result, or the exception for its first error if it has no decision"""
+    if authz_result.decision != Decision.NoDecision:
+        return authz_result
+    diagnostics = authz_result.diagnostics
+    code = diagnostics.error_codes[0] if diagnostics.error_codes else None
+    error = _ERROR_CLASSES.get(code, CedarError)("; ".join(diagnostics.errors) or "the request was not decided")
+    error.code = code
+    error.span = None
+    error.snippet = None
+    raise error
+
+
+def is_authorized_batch(requests: Union[List[dict], bytes, str, os.PathLike],
+                        policies: Union[str, PolicySetHandle],
+                        entities: Union[str, List[dict], bytes, os.PathLike, EntityStore],
+                        schema: Union[str, dict, SchemaHandle, None] = None,
+                        verbose: Optional[bool] = None,
+                        include_diagnostics: bool = True,
+                        include_metrics: bool = True,
+                        entity_validation: Optional[str] = None,
+                        unknown_entities: Optional[str] = None,
+                        policy_errors: Optional[str] = None,
+                        include_missing_entities: bool = False,
+                        on_result: Optional[Callable[[AuthzResult], Any]] = None,
+                        output_path: Optional[str] = None,
+                        output_compression: Optional[str] = None,
+                        context_columns: Optional[Dict[str, list]] = None,
+                        max_workers: Optional[int] = None,
+                        validate_request: bool = False,
+                        include_policy_results: bool = False,
+                        include_attributes: Optional[Dict[str, List[str]]] = None,
+                        include_denial_class: bool = False) -> Union[List[AuthzResult], dict, None]:
     """Evaluate whether a batch of requests are authorized given the other parameters.  Each request is evaluated
-    independently and results in an AuthzResult per request.
+    independently and results in an AuthzResult per request.  Options that are not passed take the defaults
+    configured by CEDARPY_* environment variables; see defaults().
 
     :param requests is list of Cedar-style request objects containing a principal, action, resource, and (optional) context;
-    context may be a dict (preferred) or a string
+    context may be a dict (preferred) or a string.  Or NDJSON requests, one json request object per line, as bytes or
+    the path (a str or os.PathLike) of a file, which may be gzip- or zstd-compressed and are parsed in Rust
+    :param policies is a str containing all the policies in the Cedar PolicySet, or a PolicySetHandle of them from
+    compile_policies
+    :param entities a list of entities or a json-formatted string containing the list of entities to
+    include in the evaluation, or bytes or an os.PathLike path of the json, which may be gzip- or zstd-compressed
+    and is decompressed in Rust with the GIL released, or an EntityStore
+    :param schema (optional) dictionary or json-formatted string containing the Cedar schema, or a SchemaHandle of it
+    from compile_schema
+    :param verbose (optional) boolean determining whether to enable verbose logging output within the library
+    :param include_diagnostics (optional) boolean determining whether to include diagnostics in the results
+    :param include_metrics (optional) boolean determining whether to include metrics in the results
+    :param entity_validation (optional) how entities are validated against the schema: 'strict' (entities must
+    conform to a valid schema), 'warn' (fall back to loading entities unvalidated, with a warning in the diagnostics),
+    'permissive' (entities and contexts are validated against the schema after dropping undeclared attributes, with a
+    warning for each), or 'none' (entities are not validated)
+    :param unknown_entities (optional) how a request whose principal or resource is not in the entities is handled:
+    'ignore' (evaluate it as an entity without attributes or parents), 'warn' (evaluate it, with a warning in the
+    diagnostics), or 'error' (do not evaluate it; the result has no decision and an error in the diagnostics)
+    :param policy_errors (optional) how errors evaluating individual policies are handled: 'skip' (the policy is
+    skipped and the error reported in the diagnostics, as Cedar does) or 'propagate' (any such error fails the whole
+    request; the result has no decision and the errors in the diagnostics)
+    :param include_missing_entities (optional) boolean determining whether to report the entities each request refers
+    to that are not in the entities, i.e. its principal and resource, its action when a schema is provided, since the
+    schema declares the actions, and any entity Cedar could not find while evaluating the policies, in
+    diagnostics.missing_entities
+    :param on_result (optional) callable that is passed each AuthzResult, in the same order as the requests, as soon
+    as it is evaluated, e.g. to write results downstream while the rest of the batch is evaluated; an exception raised
+    by on_result stops the evaluation and is raised to the caller
+    :param output_path (optional) the path of a file to write the results to, one JSON result per line in the same
+    order as the requests, instead of returning them; the results are serialized and written in Rust, with the GIL
+    released, so no Python objects are made for them
+    :param output_compression (optional) how the output file is compressed: 'none' or 'gzip'; defaults to 'gzip'
+    for an output_path ending in '.gz', and 'none' otherwise
+    :param context_columns (optional) the requests' contexts as columns: a dict from each context attribute to a
+    list of its values, one per request, e.g. {"authenticated": [True, False, ...]}, for batches whose contexts all
+    have the same attributes.  The columns are serialized once, instead of a dict per request, and the contexts are
+    assembled in Rust; a None value leaves the attribute out of that request's context.  The requests may not have
+    contexts of their own
+    :param max_workers (optional) the number of threads to evaluate the requests on, of the thread pool every batch
+    shares; defaults to CEDARPY_THREADS.  More than CEDARPY_MAX_THREADS, the size of the pool, uses them all.
+    The results are in the same order as the requests either way; with on_result or output_path, they are passed on
+    a chunk at a time
+    :param validate_request (optional) boolean determining whether, when a schema is provided, requests that do not
+    conform to it are not evaluated, and an invalid schema is reported rather than ignored; see is_authorized
+    :param include_policy_results (optional) boolean determining whether to report the result of each policy for each
+    request in diagnostics.policy_results; see is_authorized
+    :param include_attributes (optional) the attributes of each request's entities to copy into its result, by the
+    entity's role in the request; see is_authorized
+    :param include_denial_class (optional) boolean determining whether each Deny is classified by the kind of fix it
+    needs, in its denial_class, when a schema is provided; see is_authorized
+
+    :returns a list of AuthzResults, in same order as the requests, or None when on_result is provided, or, when
+    output_path is provided, a dict summarizing the results written: the number of requests, how many were allowed,
+    denied, and had no decision, how many decided results had errors, and the bytes_written
+    :raises OSError: if the output file cannot be written, or a requests or entities file cannot be read
+    :raises ValueError: if both on_result and output_path are provided, NDJSON requests or compressed inputs are
+    invalid, the context columns are not lists as long as the batch, or max_workers is less than 1
+
+    """
+    requests_local = _to_internal_requests(requests)
+    policies = _to_internal_policies(policies)
+    entities = _to_internal_entities(entities)
+    schema = _to_schema_str(schema)
+    context_columns_str = _to_context_columns_str(context_columns)
+    include_attributes_str = _to_include_attributes_str(include_attributes)
+
+    if output_path is not None:
+        if on_result is not None:
+            raise ValueError("only one of on_result and output_path may be provided")
+        summary_str = _internal.is_authorized_batch_to_file(requests_local, policies, entities, str(output_path),
+                                                            schema, verbose, include_diagnostics, include_metrics,
+                                                            entity_validation, unknown_entities, policy_errors,
+                                                            include_missing_entities, output_compression,
+                                                            context_columns_str, max_workers, validate_request,
+                                                            include_policy_results, include_attributes_str,
+                                                            include_denial_class)
+        return json.loads(summary_str)
+
+    on_result_str = None
+    if on_result is not None:
+        def on_result_str(authz_result_str: str) -> None:
+            on_result(AuthzResult(json.loads(authz_result_str)))
+
+    authz_result_strs: List[str] = _internal.is_authorized_batch(requests_local, policies, entities, schema, verbose,
+                                                                 include_diagnostics, include_metrics,
+                                                                 entity_validation, unknown_entities, policy_errors,
+                                                                 include_missing_entities, on_result_str,
+                                                                 context_columns_str, max_workers, validate_request,
+                                                                 include_policy_results, include_attributes_str,
+                                                                 include_denial_class)
+    if on_result is not None:
+        return None
+    return _to_authz_results(authz_result_strs)
+
+
+def is_authorized_expanded(principal: str,
+                           actions: List[str],
+                           resources: List[str],
+                           policies: Union[str, PolicySetHandle],
+                           entities: Union[str, List[dict], bytes, os.PathLike, EntityStore],
+                           schema: Union[str, dict, SchemaHandle, None] = None,
+                           context: Union[str, dict, None] = None,
+                           verbose: Optional[bool] = None,
+                           include_diagnostics: bool = True,
+                           include_metrics: bool = True,
+                           entity_validation: Optional[str] = None,
+                           unknown_entities: Optional[str] = None,
+                           policy_errors: Optional[str] = None,
+                           include_missing_entities: bool = False,
+                           max_requests: int = 10000) -> List[AuthzResult]:
+    """Evaluate whether a principal may perform each of the actions on each of the resources.  The cross product
+    of actions and resources is expanded into requests and evaluated in one pass, e.g. to compute a user's
+    permissions for a page render.
+
+    :param principal is the principal of every request, e.g. 'User::"alice"'
+    :param actions is a list of actions, e.g. ['Action::"view"', 'Action::"edit"']
+    :param resources is a list of resources, e.g. ['Photo::"a.jpg"', 'Photo::"b.jpg"']
+    :param policies is a str containing all the policies in the Cedar PolicySet, or a PolicySetHandle of them from
+    compile_policies
+    :param entities a list of entities or a json-formatted string containing the list of entities to
+    include in the evaluation, or an EntityStore
+    :param schema (optional) dictionary or json-formatted string containing the Cedar schema, or a SchemaHandle of it
+    from compile_schema
+    :param context (optional) dict or json-formatted string containing the context shared by every request
+    :param verbose (optional) boolean determining whether to enable verbose logging output within the library
+    :param include_diagnostics (optional) boolean determining whether to include diagnostics in the results
+    :param include_metrics (optional) boolean determining whether to include metrics in the results
+    :param entity_validation (optional) how entities are validated against the schema; see is_authorized_batch
+    :param unknown_entities (optional) how requests naming entities that are not in the entities are handled; see
+    is_authorized_batch
+    :param policy_errors (optional) how errors evaluating individual policies are handled; see is_authorized_batch
+    :param include_missing_entities (optional) boolean determining whether to report missing entities in the
+    diagnostics; see is_authorized_batch
+    :param max_requests (optional) the largest number of combinations to evaluate
+
+    :returns a list of AuthzResults ordered by action and then by resource, i.e. the result for actions[i] and
+    resources[j] is at index i * len(resources) + j
+    :raises ValueError: if the combinations number more than max_requests
+    """
+    if isinstance(context, dict):
+        context = json.dumps(context)
+    authz_result_strs: List[str] = _internal.is_authorized_expanded(principal, actions, resources,
+                                                                    _to_internal_policies(policies),
+                                                                    _to_internal_entities(entities),
+                                                                    _to_schema_str(schema),
+                                                                    context, verbose, include_diagnostics,
+                                                                    include_metrics, entity_validation,
+                                                                    unknown_entities, policy_errors,
+                                                                    include_missing_entities, max_requests)
+    return _to_authz_results(authz_result_strs)
+
+
+def is_authorized_many_resources(principal: str,
+                                 action: str,
+                                 resources: List[str],
+                                 policies: Union[str, PolicySetHandle],
+                                 entities: Union[str, List[dict], bytes, os.PathLike, EntityStore],
+                                 schema: Union[str, dict, SchemaHandle, None] = None,
+                                 context: Union[str, dict, None] = None,
+                                 verbose: Optional[bool] = None,
+                                 include_diagnostics: bool = True,
+                                 include_metrics: bool = True,
+                                 entity_validation: Optional[str] = None,
+                                 unknown_entities: Optional[str] = None,
+                                 policy_errors: Optional[str] = None,
+                                 include_missing_entities: bool = False,
+                                 max_requests: int = 10000) -> List[AuthzResult]:
+    """Evaluate whether a principal may perform an action on each of the resources, e.g. to filter a listing.  The
+    principal and action are parsed once for the whole batch rather than once per resource.
+
+    :param principal is the principal of every request, e.g. 'User::"alice"'
+    :param action is the action of every request, e.g. 'Action::"view"'
+    :param resources is a list of resources, e.g. ['Photo::"a.jpg"', 'Photo::"b.jpg"']
+    :param policies, entities, schema, context, and the remaining options are as for is_authorized_expanded
+
+    :returns a list of AuthzResults, in the same order as the resources
+    :raises ValueError: if the resources number more than max_requests
+    """
+    return is_authorized_expanded(principal, [action], resources, policies, entities, schema, context, verbose,
+                                  include_diagnostics, include_metrics, entity_validation, unknown_entities,
+                                  policy_errors, include_missing_entities, max_requests)
+
+
+def is_authorized_many_actions(principal: str,
+                               actions: List[str],
+                               resource: str,
+                               policies: Union[str, PolicySetHandle],
+                               entities: Union[str, List[dict], bytes, os.PathLike, EntityStore],
+                               schema: Union[str, dict, SchemaHandle, None] = None,
+                               context: Union[str, dict, None] = None,
+                               verbose: Optional[bool] = None,
+                               include_diagnostics: bool = True,
+                               include_metrics: bool = True,
+                               entity_validation: Optional[str] = None,
+                               unknown_entities: Optional[str] = None,
+                               policy_errors: Optional[str] = None,
+                               include_missing_entities: bool = False,
+                               max_requests: int = 10000) -> List[AuthzResult]:
+    """Evaluate whether a principal may perform each of the actions on a resource, e.g. to decide which buttons
+    to show for a document.  The principal and resource are parsed once for the whole batch rather than once per
+    action.
+
+    :param principal is the principal of every request, e.g. 'User::"alice"'
+    :param actions is a list of actions, e.g. ['Action::"view"', 'Action::"edit"']
+    :param resource is the resource of every request, e.g. 'Photo::"a.jpg"'
+    :param policies, entities, schema, context, and the remaining options are as for is_authorized_expanded
+
+    :returns a list of AuthzResults, in the same order as the actions
+    :raises ValueError: if the actions number more than max_requests
+    """
+    return is_authorized_expanded(principal, actions, [resource], policies, entities, schema, context, verbose,
+                                  include_diagnostics, include_metrics, entity_validation, unknown_entities,
+                                  policy_errors, include_missing_entities, max_requests)
+
+
+def unknown(name: str) -> dict:
+    """An unknown value for an attribute of the context of is_authorized_partial's request, e.g.
+    {"age": unknown("age")}, which appears as unknown("age") in the residuals"""
+    return {"__extn": {"fn": "unknown", "arg": name}}
+
+
+class PartialAuthzResult:
+    def __init__(self, result: dict) -> None:
+        super().__init__()
+        self._result = result
+
+    @property
+    def decision(self) -> Optional[Decision]:
+        """the decision, or None when it depends on the unknowns"""
+        decision = self._result['decision']
+        return None if decision is None else Decision[decision]
+
+    @property
+    def residuals(self) -> List[dict]:
+        """the policies that remain to decide the request when there's no decision, each with its 'id', 'effect',
+        the residual 'policy' in Cedar's syntax, and its 'json' in Cedar's JSON policy format"""
+        return self._result['residuals']
+
+    @property
+    def reasons(self) -> List[str]:
+        """the policies that determined the decision, or that are already satisfied when there's none"""
+        return self._result['reasons']
+
+    @property
+    def errors(self) -> List[str]:
+        return self._result['errors']
+
+    def __getitem__(self, __name: str) -> Any:
+        return getattr(self, __name)
+
+
+def is_authorized_partial(request: dict,
+                          policies: Union[str, PolicySetHandle],
+                          entities: Union[str, List[dict], bytes, os.PathLike, EntityStore],
+                          schema: Union[str, dict, SchemaHandle, None] = None,
+                          verbose: Optional[bool] = None) -> PartialAuthzResult:
+    """Authorize a request whose principal, action, resource, or context may be unknown, evaluating the policies as
+    far as the known parts allow.  When the decision depends on the unknowns, the result has the residual policies
+    that remain, e.g. to translate into the filter of a query for the resources a principal may view.
+
+    :param request a dict of the 'principal', 'action', 'resource', and 'context' like is_authorized's request,
+    where a part that's missing or None is unknown; an attribute of the context may be unknown, e.g.
+    {"age": unknown("age")}.  The context's entity references and extension values take their '__entity' and
+    '__extn' forms, as without a schema.
+    :param policies is a str containing the policies, or a PolicySetHandle
+    :param entities is a str containing the entities in JSON format, a list of entity dicts, or an EntityStore
+    :param schema (optional) is a str containing the schema in JSON format, a schema dict, or a SchemaHandle, used
+    for the entities
+    :param verbose (optional) when True will emit verbose logging
+
+    :returns a PartialAuthzResult with the decision, or the residuals
+    :raises ValueError: if the request, policies, entities, or schema are invalid
+    """
+    context = request.get("context")
+    result = _internal.is_authorized_partial(request.get("principal"),
+                                             request.get("action"),
+                                             request.get("resource"),
+                                             None if context is None else json.dumps(context),
+                                             _to_internal_policies(policies),
+                                             _to_internal_entities(entities),
+                                             _to_schema_str(schema),
+                                             verbose)
+    return PartialAuthzResult(json.loads(result))
+
+
+class PermissionMatrix:
+    """Decisions for one principal laid out as a grid of actions (rows) by resources (columns)."""
+
+    def __init__(self, actions: List[str], resources: List[str], allowed: List[List[bool]],
+                 errored: List[tuple]) -> None:
+        super().__init__()
+        self.actions: List[str] = actions
+        self.resources: List[str] = resources
+        self.allowed: List[List[bool]] = allowed
+        """allowed[i][j] is whether actions[i] is allowed on resources[j]"""
+        self.errored: List[tuple] = errored
+        """(action, resource) indices of combinations that could not be evaluated or had evaluation errors"""
+
+    def allows(self, action: str, resource: str) -> bool:
+        return self.allowed[self.actions.index(action)][self.resources.index(resource)]
+
+
+def permission_matrix(principal: str,
+                      actions: List[str],
+                      resources: List[str],
+                      policies: str,
+                      entities: Union[str, List[dict], bytes, os.PathLike],
+                      schema: Union[str, dict, None] = None,
+                      context: Union[str, dict, None] = None,
+                      verbose: Optional[bool] = None,
+                      entity_validation: Optional[str] = None,
+                      unknown_entities: Optional[str] = None,
+                      max_requests: int = 10000) -> PermissionMatrix:
+    """Compute a principal's permissions for each of the actions on each of the resources as a grid, e.g. to
+    render a capability grid in a UI.  The grid is computed and laid out in one pass.
+
+    :param principal is the principal, e.g. 'User::"alice"'
+    :param actions is a list of actions, the rows of the grid
+    :param resources is a list of resources, the columns of the grid
     :param policies is a str containing all the policies in the Cedar PolicySet
     :param entities a list of entities or a json-formatted string containing the list of entities to
     include in the evaluation
     :param schema (optional) dictionary or json-formatted string containing the Cedar schema
+    :param context (optional) dict or json-formatted string containing the context shared by every request
     :param verbose (optional) boolean determining whether to enable verbose logging output within the library
+    :param entity_validation (optional) how entities are validated against the schema; see is_authorized_batch
+    :param unknown_entities (optional) how requests naming entities that are not in the entities are handled; see
+    is_authorized_batch
+    :param max_requests (optional) the largest number of combinations to evaluate
 
-    :returns a list of AuthzResults, in same order as the requests
+    :returns a PermissionMatrix
+    :raises ValueError: if the combinations number more than max_requests
+    """
+    if isinstance(context, dict):
+        context = json.dumps(context)
+    allowed, errored = _internal.permission_matrix(principal, actions, resources, policies,
+                                                   entities, _to_schema_str(schema),
+                                                   context, verbose, entity_validation, unknown_entities,
+                                                   max_requests)
+    return PermissionMatrix(actions, resources, allowed, errored)
+
+
+def profile_batch(requests: List[dict],
+                  policies: str,
+                  entities: Union[str, List[dict], bytes, os.PathLike],
+                  schema: Union[str, dict, None] = None,
+                  verbose: Optional[bool] = None,
+                  entity_validation: Optional[str] = None) -> dict:
+    """Authorize a batch of requests as is_authorized_batch does, and return a profile of the batch rather than its
+    results: the time spent in each stage (converting the input, parsing the policies and schema, loading the
+    entities, building and authorizing the requests, and serializing the results), the distribution of the
+    per-request stages, and the number of requests that errored.  The profile's fields are stable so that profiles
+    from different versions of cedarpy can be diffed to find regressions; fields that cannot be measured, such as
+    allocations, are None.
+
+    :param requests is list of Cedar-style request objects; see is_authorized_batch
+    :param policies is a str containing all the policies in the Cedar PolicySet
+    :param entities a list of entities or a json-formatted string containing the list of entities to
+    include in the evaluation
+    :param schema (optional) dictionary or json-formatted string containing the Cedar schema
+    :param verbose (optional) boolean determining whether to enable verbose logging output within the library
+    :param entity_validation (optional) how entities are validated against the schema; see is_authorized_batch
 
+    :returns the profile as a dict
+    :raises ValueError: if the policies, entities, or schema cannot be parsed or validated
     """
-    requests_local = []
-    for request in requests:
-        if "context" in request:
-            context = request["context"]
-            if isinstance(context, dict):
-                # ok user provided context as a dictionary, lets flatten it for them
-                context_json_str = json.dumps(context)
-                request = copy(request)
-                request["context"] = context_json_str
-            elif context is None:
-                request = copy(request)
-                del request["context"]
-
-        requests_local.append(request)
+    profile_str = _internal.profile_batch(_to_internal_requests(requests), policies, entities,
+                                          _to_schema_str(schema), verbose, entity_validation)
+    return json.loads(profile_str)
 
-    if isinstance(entities, str):
-        pass
-    elif isinstance(entities, list):
-        entities = json.dumps(entities)
 
-    if schema is not None:
-        if isinstance(schema, str):
-            pass
-        elif isinstance(schema, dict):
-            schema = json.dumps(schema)
+def is_authorized_batch_document(document: Union[str, bytes],
+                                 verbose: Optional[bool] = None,
+                                 include_diagnostics: bool = True,
+                                 include_metrics: bool = True,
+                                 entity_validation: Optional[str] = None,
+                                 unknown_entities: Optional[str] = None,
+                                 policy_errors: Optional[str] = None,
+                                 include_missing_entities: bool = False) -> List[AuthzResult]:
+    """Evaluate whether a batch of requests are authorized, where the requests and the policies, entities, and
+    schema they share are provided as a single JSON document.  The document is parsed once in Rust, which avoids
+    converting each request from Python objects and is more efficient for very large batches.
+
+    The document is a JSON object of the form:
+    {"requests": [{"principal": ..., "action": ..., "resource": ..., "context": {...}, "correlation_id": ...}],
+     "policies": "...", "entities": [...], "schema": {...}}
+    where schema is optional and entities, schema, and each request's context may be JSON values or json-formatted
+    strings.
+
+    :param document is a str or bytes containing the JSON document
+    :param verbose (optional) boolean determining whether to enable verbose logging output within the library
+    :param include_diagnostics (optional) boolean determining whether to include diagnostics in the results
+    :param include_metrics (optional) boolean determining whether to include metrics in the results
+    :param entity_validation (optional) how entities are validated against the schema: 'strict' (entities must
+    conform to a valid schema), 'warn' (fall back to loading entities unvalidated, with a warning in the diagnostics),
+    'permissive' (entities and contexts are validated against the schema after dropping undeclared attributes, with a
+    warning for each), or 'none' (entities are not validated)
+    :param unknown_entities (optional) how a request whose principal or resource is not in the entities is handled:
+    'ignore' (evaluate it as an entity without attributes or parents), 'warn' (evaluate it, with a warning in the
+    diagnostics), or 'error' (do not evaluate it; the result has no decision and an error in the diagnostics)
+    :param policy_errors (optional) how errors evaluating individual policies are handled: 'skip' (the policy is
+    skipped and the error reported in the diagnostics, as Cedar does) or 'propagate' (any such error fails the whole
+    request; the result has no decision and the errors in the diagnostics)
+    :param include_missing_entities (optional) boolean determining whether to report the entities each request refers
+    to that are not in the entities, i.e. its principal and resource, its action when a schema is provided, since the
+    schema declares the actions, and any entity Cedar could not find while evaluating the policies, in
+    diagnostics.missing_entities
+
+    :returns a list of AuthzResults, in same order as the requests
+    :raises ValueError: if the document is not a valid batch document
+    """
+    authz_result_strs: List[str] = _internal.is_authorized_batch_document(document, verbose,
+                                                                          include_diagnostics, include_metrics,
+                                                                          entity_validation, unknown_entities,
+                                                                          policy_errors, include_missing_entities)
+    return _to_authz_results(authz_result_strs)
+
 
-    authz_result_strs: List[str] = _internal.is_authorized_batch(requests_local, policies, entities, schema, verbose)
+def _to_authz_results(authz_result_strs: List[str]) -> List[AuthzResult]:
     authz_result_objs: List[dict] = []
 
     for authz_result_str in authz_result_strs:
@@ -145,16 +1019,2269 @@ def is_authorized_batch(requests: List[dict],
     return authz_results
 
 
-def format_policies(policies: str,
-                    line_width: int = 80,
-                    indent_width: int = 2) -> str:
-    """Format the provided policies according to the Cedar conventions.
+class BatchDecisions:
+    """Compact allow/deny decisions for a batch of requests, in the same order as the requests."""
+
+    def __init__(self, bitset: bytes, errored: List[int], num_requests: int) -> None:
+        super().__init__()
+        self._bitset = bitset
+        self._errored = errored
+        self._num_requests = num_requests
+
+    @property
+    def bitset(self) -> bytes:
+        """The decisions as a bitset; bit i (least-significant bit first within each byte) is set if request i
+        was allowed"""
+        return self._bitset
+
+    @property
+    def errored(self) -> List[int]:
+        """Indices of the requests that could not be evaluated or encountered evaluation errors"""
+        return self._errored
+
+    def allowed(self, index: int) -> bool:
+        if not 0 <= index < self._num_requests:
+            raise IndexError(f"request index out of range: {index}")
+        return bool(self._bitset[index // 8] & (1 << (index % 8)))
+
+    def __len__(self) -> int:
+        return self._num_requests
+
+    def __getitem__(self, index: int) -> bool:
+        return self.allowed(index)
+
+
+def is_authorized_batch_decisions(requests: List[dict],
+                                  policies: Union[str, PolicySetHandle],
+                                  entities: Union[str, List[dict], bytes, os.PathLike, EntityStore],
+                                  schema: Union[str, dict, SchemaHandle, None] = None,
+                                  verbose: Optional[bool] = None,
+                                  entity_validation: Optional[str] = None,
+                                  unknown_entities: Optional[str] = None) -> BatchDecisions:
+    """Evaluate whether a batch of requests are authorized, returning only a compact vector of decisions.
+
+    This is intended for very large batches, e.g. access review sweeps, where serializing a full AuthzResult per
+    request would dominate the cost of evaluation.
+
+    :param requests is list of Cedar-style request objects containing a principal, action, resource, and (optional) context;
+    context may be a dict (preferred) or a string
+    :param policies is a str containing all the policies in the Cedar PolicySet, or a PolicySetHandle of them from
+    compile_policies
+    :param entities a list of entities or a json-formatted string containing the list of entities to
+    include in the evaluation, or an EntityStore
+    :param schema (optional) dictionary or json-formatted string containing the Cedar schema, or a SchemaHandle of it
+    from compile_schema
+    :param verbose (optional) boolean determining whether to enable verbose logging output within the library
+    :param entity_validation (optional) how entities are validated against the schema: 'strict' (entities must
+    conform to a valid schema), 'warn' (fall back to loading entities unvalidated, with a warning in the diagnostics),
+    'permissive' (entities and contexts are validated against the schema after dropping undeclared attributes, with a
+    warning for each), or 'none' (entities are not validated)
+    :param unknown_entities (optional) how a request whose principal or resource is not in the entities is handled:
+    'ignore' (evaluate it as an entity without attributes or parents), 'warn' (evaluate it, with a warning in the
+    diagnostics), or 'error' (do not evaluate it; the result has no decision and an error in the diagnostics)
+
+    :returns a BatchDecisions, in same order as the requests
+    """
+    bitset, errored = _internal.is_authorized_batch_decisions(_to_internal_requests(requests),
+                                                              _to_internal_policies(policies),
+                                                              _to_internal_entities(entities),
+                                                              _to_schema_str(schema),
+                                                              verbose,
+                                                              entity_validation,
+                                                              unknown_entities)
+    return BatchDecisions(bitset, errored, len(requests))
+
+
+class MinimalPolicySubset:
+    def __init__(self, subset: dict) -> None:
+        super().__init__()
+        self._subset = subset
+
+    @property
+    def decision(self) -> Decision:
+        return Decision[self._subset['decision']]
+
+    @property
+    def policy_ids(self) -> List[str]:
+        return self._subset['policy_ids']
+
+    @property
+    def policies(self) -> str:
+        """the source text of the policies in the subset"""
+        return self._subset['policies']
+
+    def __getitem__(self, __name: str) -> Any:
+        return getattr(self, __name)
+
+
+def find_minimal_policy_subset(request: dict,
+                               policies: str,
+                               entities: Union[str, List[dict], bytes, os.PathLike],
+                               schema: Union[str, dict, None] = None,
+                               decision: Union[Decision, str, None] = None,
+                               verbose: Optional[bool] = None) -> MinimalPolicySubset:
+    """Find a minimal subset of the policies that reproduces the decision for a request.
+
+    Removing any one policy from the subset changes the decision, so the subset explains it.
+    For an Allow, the subset holds a permit that applies to the request.  For a Deny, the subset
+    holds the forbids that override the policy set's permits, and is empty when no permit applies.
+
+    :param request is the request to evaluate; see is_authorized
+    :param policies is a str containing the policies to search
+    :param entities is a str containing the entities in JSON format or a list of entity dicts
+    :param schema (optional) is a str containing the schema in JSON format or a schema dict
+    :param decision (optional) is the decision to reproduce; defaults to the decision the full
+        policy set produces for the request
+    :param verbose (optional) when True will emit verbose logging
+
+    :returns a MinimalPolicySubset with the decision, ids, and source text of the subset's policies
+    :raises ValueError: if the inputs are invalid or the policies do not produce the decision
+    """
+    if isinstance(decision, Decision):
+        decision = decision.value
+    subset_str = _internal.find_minimal_policy_subset(_to_internal_requests([request])[0],
+                                                      policies,
+                                                      entities,
+                                                      _to_schema_str(schema),
+                                                      decision,
+                                                      verbose)
+    return MinimalPolicySubset(json.loads(subset_str))
+
+
+class NeverAllowedReport:
+    def __init__(self, report: dict) -> None:
+        super().__init__()
+        self._report = report
+
+    @property
+    def holds(self) -> bool:
+        """whether no request the pattern matches is allowed over the entity model"""
+        return self._report['holds']
+
+    @property
+    def requests_checked(self) -> int:
+        return self._report['requests_checked']
+
+    @property
+    def counterexample(self) -> Optional[dict]:
+        """the first request allowed, as {'request': {...}, 'reason': [policy ids]}, or None when the assertion
+        holds; the request can be passed to is_authorized with the model's entities"""
+        return self._report['counterexample']
+
+    @property
+    def entities(self) -> Optional[List[dict]]:
+        """the generated entity model, when no entities were given, to reproduce the counterexample with"""
+        return self._report['entities']
+
+    def __getitem__(self, __name: str) -> Any:
+        return getattr(self, __name)
+
+
+def assert_never_allowed(request_pattern: dict,
+                         policies: str,
+                         schema: Union[str, dict],
+                         entities: Union[str, List[dict], bytes, os.PathLike, None] = None,
+                         entities_per_type: int = 3,
+                         contexts_per_request: int = 4,
+                         seed: int = 0,
+                         max_requests: int = 100000,
+                         verbose: Optional[bool] = None) -> NeverAllowedReport:
+    """Check that the policies never allow a request matching a pattern, e.g. that no principal outside
+    Group::"admins" may perform Action::"deleteTenant".
+
+    The check is exhaustive over a bounded entity model rather than a proof: every request the schema allows
+    that the pattern matches, for the model's principals and resources, is evaluated with generated contexts.
+    The model is the entities given, or entities generated for the schema as generate_load generates them,
+    including the entities the policies name.
+
+    :param request_pattern is a dict with an optional 'principal', 'action', and 'resource', each an entity
+        uid str, e.g. 'Action::"deleteTenant"', or a dict with an optional 'type', e.g. 'User', an 'in' uid the
+        entity must be in, and a 'not_in' uid it must not be in; a variable left out matches any entity
+    :param policies is a str containing the policies to check
+    :param schema is a str containing the schema in JSON format or a schema dict
+    :param entities (optional) the entity model, as a list of entity dicts or the entities in JSON format;
+        defaults to a generated model
+    :param entities_per_type (optional) how many entities of each type a generated model has, besides those
+        the policies name
+    :param contexts_per_request (optional) how many generated contexts each principal, action, and resource is
+        evaluated with, for actions whose context has attributes
+    :param seed (optional) the seed of the generated entities and contexts
+    :param max_requests (optional) the most requests to evaluate; a pattern matching more raises ValueError
+    :param verbose (optional) when True will emit verbose logging
+
+    :returns a NeverAllowedReport, which holds when no request matching the pattern is allowed
+    :raises ValueError: if the pattern, policies, schema, or entities are invalid, or the pattern matches more
+        than max_requests requests
+    """
+    report_str = _internal.assert_never_allowed(json.dumps(request_pattern),
+                                                policies,
+                                                _to_schema_str(schema),
+                                                entities,
+                                                entities_per_type,
+                                                contexts_per_request,
+                                                seed,
+                                                max_requests,
+                                                verbose)
+    return NeverAllowedReport(json.loads(report_str))
+
+
+class DecisionExplanation:
+    def __init__(self, explanation: dict) -> None:
+        super().__init__()
+        self._explanation = explanation
+
+    @property
+    def decision(self) -> Decision:
+        return Decision[self._explanation['decision']]
+
+    @property
+    def expected(self) -> Decision:
+        return Decision[self._explanation['expected']]
+
+    @property
+    def determining_policies(self) -> List[dict]:
+        """the policies that determined the decision, e.g. the forbids that denied the request"""
+        return self._explanation['determining_policies']
+
+    @property
+    def candidate_permits(self) -> List[dict]:
+        """the permits that did not apply to the request with their unsatisfied scope constraints
+        and conditions, nearest (fewest unsatisfied) first"""
+        return self._explanation['candidate_permits']
+
+    @property
+    def suggestion(self) -> Optional[dict]:
+        """request and entity changes that would flip a denial to Allow, if any were found"""
+        return self._explanation['suggestion']
+
+    def __getitem__(self, __name: str) -> Any:
+        return getattr(self, __name)
+
+
+def explain_decision(request: dict,
+                     policies: str,
+                     entities: Union[str, List[dict], bytes, os.PathLike],
+                     schema: Union[str, dict, None] = None,
+                     expected: Union[Decision, str] = Decision.Allow,
+                     verbose: Optional[bool] = None) -> DecisionExplanation:
+    """Explain why a request did not get the expected decision, e.g. in a failing policy test.
+
+    The explanation holds the policies that determined the decision, and the permits that did not
+    apply to the request along with their unsatisfied scope constraints and conditions.  When a
+    schema is provided and a request expected to be allowed was denied by default, it also holds a
+    suggested change to the request and entities that would allow it.  Suggestions are checked by
+    re-authorizing with the schema, and cover scope constraints and conditions such as
+    `principal.attr == value`.
+
+    :param request is the request to evaluate; see is_authorized
+    :param policies is a str containing the policies
+    :param entities is a str containing the entities in JSON format or a list of entity dicts
+    :param schema (optional) is a str containing the schema in JSON format or a schema dict
+    :param expected (optional) is the decision the request was expected to get; defaults to Allow
+    :param verbose (optional) when True will emit verbose logging
+
+    :returns a DecisionExplanation
+    :raises ValueError: if the inputs are invalid
+    """
+    if isinstance(expected, Decision):
+        expected = expected.value
+    explanation_str = _internal.explain_decision(_to_internal_requests([request])[0],
+                                                 policies,
+                                                 entities,
+                                                 _to_schema_str(schema),
+                                                 expected,
+                                                 verbose)
+    return DecisionExplanation(json.loads(explanation_str))
+
+
+def evaluate_expression(expression: str,
+                        request: dict,
+                        entities: Union[str, List[dict], bytes, os.PathLike, EntityStore],
+                        schema: Union[str, dict, None] = None,
+                        verbose: Optional[bool] = None) -> str:
+    """Evaluate a Cedar expression, e.g. a policy's condition, against a request and entities, like the Cedar CLI's
+    evaluate subcommand, so conditions can be unit tested one at a time.
+
+    :param expression is a str containing the Cedar expression, e.g. 'principal.level > 5'
+    :param request is the request whose principal, action, resource, and context the expression refers to; see
+    is_authorized
+    :param entities is a str containing the entities in JSON format, a list of entity dicts, bytes or an
+    os.PathLike path of the json, or an EntityStore
+    :param schema (optional) is a str containing the schema in JSON format or a schema dict
+    :param verbose (optional) when True will emit verbose logging
+
+    :returns a str of the expression's value in JSON, in the format of entity attribute values: an entity is
+    {"__entity": {"type": ..., "id": ...}}, and an extension value {"__extn": {"fn": ..., "arg": ...}}, e.g.
+    {"__extn": {"fn": "ip", "arg": "10.0.0.1/32"}}; Cedar displays a decimal between -1 and 0 without its sign, so
+    its arg has none
+    :raises ValueError: if the expression can't be parsed or evaluated, or the inputs are invalid
+    """
+    return _internal.evaluate_expression(expression,
+                                         _to_internal_requests([request])[0],
+                                         _to_internal_entities(entities),
+                                         _to_schema_str(schema),
+                                         verbose)
+
+
+DEBUG_BUNDLE_VERSION = 1
+
+
+def export_debug_bundle(request: dict,
+                        policies: str,
+                        entities: Union[str, List[dict], bytes, os.PathLike],
+                        schema: Union[str, dict, None] = None,
+                        path: Union[str, os.PathLike, None] = None,
+                        redact: Optional[List[str]] = None) -> dict:
+    """Capture a request's decision in a single JSON artifact, a debug bundle, to attach to a bug report and replay
+    exactly with replay_debug_bundle.  The bundle holds the inputs, the result, the explanation of why the request
+    didn't get the other decision (see explain_decision), the defaults the request was decided with, which
+    replay_debug_bundle decides it with again, and the versions of cedarpy and Cedar that decided it.
+
+    :param request is the request to evaluate; see is_authorized
+    :param policies is a str containing the policies
+    :param entities is a str containing the entities in JSON format, a list of entity dicts, or bytes or an
+    os.PathLike path of the json
+    :param schema (optional) is a str containing the schema in JSON format or a schema dict
+    :param path (optional) the path of the file to write the bundle to, as JSON
+    :param redact (optional) the names of entity and context attributes whose values are replaced with
+    '<redacted>' in the bundle.  The result and explanation are of the inputs as given, so a replay of a redacted
+    bundle may not reproduce them
+
+    :returns the bundle as a dict
+    :raises ValueError: if the inputs are invalid
+    :raises OSError: if the bundle cannot be written
+    """
+    entities_list = entities if isinstance(entities, list) else _internal.load_json(entities)
+    schema_str = _to_schema_str(schema)
+    authz_result = is_authorized(request, policies, entities_list, schema_str, include_metrics=False)
+    try:
+        expected = Decision.Allow if authz_result.decision != Decision.Allow else Decision.Deny
+        trace = explain_decision(request, policies, entities_list, schema_str, expected=expected)._explanation
+    except ValueError:
+        # a request that can't be decided has nothing to explain; its result has the errors
+        trace = None
+
+    redacted = set(redact or [])
+
+    def redact_attrs(attrs: Any) -> Any:
+        if not isinstance(attrs, dict):
+            return attrs
+        return {name: "<redacted>" if name in redacted else value for name, value in attrs.items()}
+
+    bundle_request = copy(request)
+    if isinstance(bundle_request.get("context"), str):
+        bundle_request["context"] = json.loads(bundle_request["context"])
+    if "context" in bundle_request:
+        bundle_request["context"] = redact_attrs(bundle_request["context"])
+    bundle = {
+        "bundle_version": DEBUG_BUNDLE_VERSION,
+        "defaults": defaults(),
+        "versions": versions(),
+        "request": bundle_request,
+        "policies": policies,
+        "entities": [dict(entity, attrs=redact_attrs(entity["attrs"])) if "attrs" in entity else entity
+                     for entity in entities_list],
+        "schema": None if schema_str is None else json.loads(schema_str),
+        "redacted": sorted(redacted),
+        "result": authz_result.to_dict(),
+        "trace": trace,
+    }
+    if path is not None:
+        with open(path, "w") as f:
+            json.dump(bundle, f, indent=2)
+    return bundle
+
+
+class DebugBundleReplay:
+    def __init__(self, recorded: AuthzResult, result: AuthzResult, recorded_versions: Optional[dict]) -> None:
+        super().__init__()
+        self._recorded = recorded
+        self._result = result
+        self._recorded_versions = recorded_versions
+
+    @property
+    def recorded(self) -> AuthzResult:
+        """the result the bundle recorded"""
+        return self._recorded
+
+    @property
+    def result(self) -> AuthzResult:
+        """the result of deciding the bundle's request again"""
+        return self._result
+
+    @property
+    def recorded_versions(self) -> Optional[dict]:
+        """the versions of cedarpy and Cedar that recorded the result, or None for a bundle that didn't record them"""
+        return self._recorded_versions
+
+    @property
+    def versions(self) -> dict:
+        """the versions of cedarpy and Cedar that replayed the bundle"""
+        return versions()
+
+    @property
+    def changes(self) -> List[str]:
+        """what the replay got differently from the recorded result, e.g. "decision: Deny -> Allow", of the
+        decision, then the reasons and the errors"""
+        changes = []
+        if self._recorded.decision != self._result.decision:
+            changes.append(f"decision: {self._recorded.decision.value} -> {self._result.decision.value}")
+        for name in ["reasons", "errors"]:
+            recorded = sorted(getattr(self._recorded.diagnostics, name))
+            replayed = sorted(getattr(self._result.diagnostics, name))
+            if recorded != replayed:
+                changes.append(f"{name}: {recorded} -> {replayed}")
+        return changes
+
+    @property
+    def matches(self) -> bool:
+        """whether the replay got the recorded decision, for the same reasons and with the same errors"""
+        return not self.changes
+
+    def __getitem__(self, __name: str) -> Any:
+        return getattr(self, __name)
+
+
+def replay_debug_bundle(bundle: Union[str, os.PathLike, dict],
+                        expect: Union[Decision, str, None] = None) -> DebugBundleReplay:
+    """Decide the request of a debug bundle from export_debug_bundle again, with its inputs and the defaults it was
+    decided with, to reproduce a decision from a bug report or to check whether an upgrade of cedarpy or Cedar
+    changes it.
+
+    :param bundle the path of the bundle's file, or the bundle as a dict
+    :param expect (optional) 'recorded' to assert that the replay gets the recorded result, with the same reasons
+    and errors, or a Decision, or its name, to assert that the replay gets that decision, e.g. once the bug is fixed
+
+    :returns a DebugBundleReplay of the recorded result and the replay's, and what changed
+    :raises ValueError: if the bundle is not a debug bundle of a version this cedarpy can replay, or expect is
+    invalid
+    :raises AssertionError: if the replay doesn't get what expect says, with the changes from the recorded result
+    :raises OSError: if the bundle's file cannot be read
+    """
+    if expect is not None and expect != "recorded" and not isinstance(expect, Decision):
+        try:
+            expect = Decision(expect)
+        except ValueError:
+            raise ValueError(f"invalid expect {expect!r}; expected 'recorded' or a Decision") from None
+    if not isinstance(bundle, dict):
+        with open(bundle) as f:
+            bundle = json.load(f)
+    if bundle.get("bundle_version") != DEBUG_BUNDLE_VERSION:
+        raise ValueError(f"not a debug bundle of version {DEBUG_BUNDLE_VERSION}: "
+                         f"bundle_version {bundle.get('bundle_version')!r}")
+    recorded_defaults = bundle["defaults"]
+    result = is_authorized(bundle["request"], bundle["policies"], bundle["entities"], bundle["schema"],
+                           include_metrics=False,
+                           entity_validation=recorded_defaults["entity_validation"],
+                           unknown_entities=recorded_defaults["unknown_entities"],
+                           policy_errors=recorded_defaults["policy_errors"])
+    replay = DebugBundleReplay(AuthzResult(bundle["result"]), result, bundle.get("versions"))
+    if expect == "recorded" and not replay.matches:
+        raise AssertionError(f"the replay with cedarpy {replay.versions['cedarpy']} doesn't match the result recorded "
+                             f"with {(replay.recorded_versions or {}).get('cedarpy', 'an unknown version')}: "
+                             + "; ".join(replay.changes))
+    if isinstance(expect, Decision) and result.decision != expect:
+        raise AssertionError(f"expected {expect.value}, but the replay got {result.decision.value}; changes from the "
+                             f"recorded result: " + ("; ".join(replay.changes) or "none"))
+    return replay
+
+
+class InlineTestReport:
+    def __init__(self, report: dict) -> None:
+        super().__init__()
+        self._report = report
+
+    @property
+    def passed(self) -> int:
+        return self._report['passed']
+
+    @property
+    def failed(self) -> int:
+        return self._report['failed']
+
+    @property
+    def ok(self) -> bool:
+        return self._report['failed'] == 0
+
+    @property
+    def tests(self) -> List[dict]:
+        """each test case with its policy_id, annotation, principal, action, resource, context, expected and actual
+        decision, whether it passed, the policies that determined the decision, and any errors"""
+        return self._report['tests']
+
+    @property
+    def failures(self) -> List[dict]:
+        return [test for test in self._report['tests'] if not test['passed']]
+
+    def __getitem__(self, __name: str) -> Any:
+        return getattr(self, __name)
+
+
+def run_inline_tests(policies: str,
+                     entities: Union[str, List[dict], bytes, os.PathLike],
+                     schema: Union[str, dict, None] = None,
+                     verbose: Optional[bool] = None) -> InlineTestReport:
+    """Run the test cases that policies carry in their @test_allow and @test_deny annotations, e.g.
+
+        @test_allow("User::\\"alice\\", Action::\\"view\\", Photo::\\"vacation.jpg\\"")
+        permit(principal == User::"alice", action == Action::"view", resource);
+
+    A case is a principal, action, and resource, and optionally a context as a JSON object, separated by commas;
+    an annotation may hold several cases separated by semicolons.  Annotations named test_allow_ or test_deny_
+    followed by anything are test annotations too, so a policy may have several of each.  Each case is evaluated
+    against all the policies, and passes when it gets the annotation's decision.
+
+    :param policies is a str containing the policies
+    :param entities is a str containing the entities in JSON format or a list of entity dicts
+    :param schema (optional) is a str containing the schema in JSON format or a schema dict
+    :param verbose (optional) when True will emit verbose logging
+
+    :returns an InlineTestReport; a case that cannot be parsed or evaluated fails with the error in its errors
+    :raises ValueError: if the policies, entities, or schema are invalid
+    """
+    report_str = _internal.run_inline_tests(policies,
+                                            entities,
+                                            _to_schema_str(schema),
+                                            verbose)
+    return InlineTestReport(json.loads(report_str))
+
+
+class SeparationOfDutyReport:
+    def __init__(self, report: dict) -> None:
+        super().__init__()
+        self._report = report
+
+    @property
+    def holds(self) -> bool:
+        """whether no principal is allowed both actions of any conflict on the same resource"""
+        return self._report['holds']
+
+    @property
+    def requests_checked(self) -> int:
+        return self._report['requests_checked']
+
+    @property
+    def errored(self) -> int:
+        """how many requests could not be evaluated or encountered errors evaluating policies"""
+        return self._report['errored']
+
+    @property
+    def violations(self) -> List[dict]:
+        """each principal allowed both actions of a conflict on a resource, with the principal, resource, the
+        conflict's actions, and the reasons, the policies that allowed each action"""
+        return self._report['violations']
+
+    @property
+    def violating_principals(self) -> List[str]:
+        """the principals with a violation, in the order of their first violation"""
+        return list(dict.fromkeys(violation['principal'] for violation in self._report['violations']))
+
+    def __getitem__(self, __name: str) -> Any:
+        return getattr(self, __name)
+
+
+def check_separation_of_duty(conflicts: List[Tuple[str, str]],
+                             policies: str,
+                             entities: Union[str, List[dict], bytes, os.PathLike],
+                             schema: Union[str, dict, None] = None,
+                             principal_types: Optional[List[str]] = None,
+                             resource_types: Optional[List[str]] = None,
+                             context: Optional[dict] = None,
+                             max_requests: int = 100000,
+                             verbose: Optional[bool] = None) -> SeparationOfDutyReport:
+    """Check that no principal among the entities is allowed both actions of a conflict, a toxic combination such
+    as (Action::"submitExpense", Action::"approveExpense"), on the same resource.  Every principal and resource among
+    the entities is evaluated with both actions of each conflict, in one batch.
+
+    :param conflicts is a list of pairs of action uids that no principal may both be allowed on a resource
+    :param policies is a str containing the policies
+    :param entities is a str containing the entities in JSON format or a list of entity dicts
+    :param schema (optional) is a str containing the schema in JSON format or a schema dict; when provided, the
+    principals and resources of a conflict are the entities of the types both of its actions apply to
+    :param principal_types (optional) the entity types of the principals to check; defaults to any
+    :param resource_types (optional) the entity types of the resources to check; defaults to any
+    :param context (optional) the context of every request
+    :param max_requests (optional) the most requests the check may evaluate
+    :param verbose (optional) when True will emit verbose logging
+
+    :returns a SeparationOfDutyReport of the violations
+    :raises ValueError: if the conflicts, policies, entities, or schema are invalid, an action of a conflict is not
+    declared in the schema, or the check needs more than max_requests requests
+    """
+    report_str = _internal.check_separation_of_duty(json.dumps([list(conflict) for conflict in conflicts]),
+                                                    policies,
+                                                    entities,
+                                                    _to_schema_str(schema),
+                                                    principal_types,
+                                                    resource_types,
+                                                    None if context is None else json.dumps(context),
+                                                    max_requests,
+                                                    verbose)
+    return SeparationOfDutyReport(json.loads(report_str))
+
+
+def export_entitlements(policies: str,
+                        entities: Union[str, List[dict], bytes, os.PathLike],
+                        schema: Union[str, dict, None] = None,
+                        actions: Optional[List[str]] = None,
+                        principal_types: Optional[List[str]] = None,
+                        resource_types: Optional[List[str]] = None,
+                        context: Optional[dict] = None,
+                        output_format: str = "json",
+                        max_requests: int = 100000,
+                        verbose: Optional[bool] = None) -> Union[dict, str]:
+    """Export what the policies allow among the entities as entitlements, subject/relation/object tuples in the
+    style of Zanzibar's relationships, for governance tools to ingest.  Every principal, action, and resource among
+    the entities is evaluated, in one batch, and each allowed request is an entitlement:
+    {"subject": {"type": "User", "id": "alice"}, "relation": "view", "object": {"type": "Photo", "id": "a.jpg"},
+    "policies": ["policy0"]}, with the action's id as the relation.
+
+    :param policies is a str containing the policies
+    :param entities is a str containing the entities in JSON format or a list of entity dicts
+    :param schema (optional) is a str containing the schema in JSON format or a schema dict; when provided, the
+    principals and resources of an action are the entities of the types it applies to
+    :param actions (optional) the action uids to export; defaults to every action the schema declares, and is
+    required without a schema
+    :param principal_types (optional) the entity types of the principals to export; defaults to any
+    :param resource_types (optional) the entity types of the resources to export; defaults to any
+    :param context (optional) the context of every request
+    :param output_format (optional) 'json' (default) for a dict, or 'zanzibar' for a str with a line for each
+    entitlement, e.g. 'Photo:a.jpg#view@User:alice', with '/' in place of the '::' of namespaced types and
+    ':', '#', '@', '/', '%', and whitespace percent-encoded in ids
+    :param max_requests (optional) the most requests the export may evaluate
+    :param verbose (optional) when True will emit verbose logging
+
+    :returns a dict with the 'entitlements', 'requests_checked', and 'errored', the number of requests that could
+    not be evaluated or encountered errors, or the zanzibar str
+    :raises ValueError: if the policies, entities, or schema are invalid, an action is not declared in the schema,
+    the export needs more than max_requests requests, or the output format is invalid
+    """
+    exported = _internal.export_entitlements(policies,
+                                             entities,
+                                             _to_schema_str(schema),
+                                             actions,
+                                             principal_types,
+                                             resource_types,
+                                             None if context is None else json.dumps(context),
+                                             output_format,
+                                             max_requests,
+                                             verbose)
+    return exported if output_format == "zanzibar" else json.loads(exported)
+
+
+def import_relationships(tuples: List[Union[str, dict]],
+                         mapping: dict,
+                         entities: Union[str, List[dict], bytes, os.PathLike, None] = None) -> dict:
+    """Import the relationship tuples of a relationship-based system, e.g. a Zanzibar-style one, as the parents of
+    Cedar entities and the links of policy templates, to migrate its relationships to Cedar.
+
+    The mapping says how each relation's tuples are imported, by relation, e.g. 'member', or by the Cedar entity
+    type of the object and relation, e.g. 'Doc#viewer', which takes precedence:
+    {"relations": {"member": {"parent": True}, "Doc#viewer": {"template": "policy0"}}, "types": {"user": "User"}}.
+    A 'parent' relation makes the object a parent of the subject.  A 'template' relation links the template with
+    the subject in its ?principal slot and the object in its ?resource slot, or only the 'slots' given, e.g.
+    ["?principal"]; the link's id is the template's id and the slots' entities, e.g. 'policy0/User::"alice"/Doc::"a"'.
+    The optional 'types' map the tuples' types to Cedar entity types.
+
+    :param tuples a list of tuples, each an 'object#relation@subject' str, e.g. 'Doc:plan#viewer@User:alice', as
+    export_entitlements writes with output_format 'zanzibar', or a {"subject", "relation", "object"} dict, as it
+    returns; the relation of a userset subject, e.g. 'Group:eng#member', is dropped
+    :param mapping a dict of the 'relations' and, optionally, 'types'
+    :param entities (optional) the entities the parents are added to; entities the tuples of parent relations
+    refer to that aren't among them are added without attributes
+
+    :returns a dict with the 'entities' and the 'template_links' to pass to compile_policies
+    :raises ValueError: if a tuple, the mapping, or the entities are invalid, or a tuple's relation isn't mapped
+    """
+    imported = _internal.import_relationships(json.dumps(tuples),
+                                              json.dumps(mapping),
+                                              entities)
+    return json.loads(imported)
+
+
+class BatchPage:
+    """The results of the requests of a batch that one call evaluated, and how to resume the batch"""
+
+    def __init__(self, results: List[AuthzResult], start: int, resume_token: Optional[str]) -> None:
+        super().__init__()
+        self._results = results
+        self._start = start
+        self._resume_token = resume_token
+
+    @property
+    def results(self) -> List[AuthzResult]:
+        """the results of the requests evaluated, in order"""
+        return self._results
+
+    @property
+    def start(self) -> int:
+        """the index in the batch of the first request evaluated"""
+        return self._start
+
+    @property
+    def resume_token(self) -> Optional[str]:
+        """the token to pass to resume the batch after these results, or None when the batch is done"""
+        return self._resume_token
+
+    @property
+    def complete(self) -> bool:
+        return self._resume_token is None
+
+    @property
+    def retryable(self) -> List[int]:
+        """the indices in the batch of the results that failed with only transient errors, so a job can resubmit
+        exactly those requests; see AuthzResult.retryable"""
+        return [self._start + i for i, result in enumerate(self._results) if result.retryable]
+
+    def __getitem__(self, __name: str) -> Any:
+        return getattr(self, __name)
+
+
+class CedarEngine:
+    """Holds parsed policies, entities, and schema so that many requests can be authorized against them without
+    re-parsing them for each call.  Parsed request principals, actions, resources, and contexts are kept in a
+    least-recently-used cache, so repeated requests skip request construction too.
+    """
+
+    def __init__(self,
+                 policies: str,
+                 entities: Union[str, List[dict], bytes, os.PathLike, 'EntityStore'],
+                 schema: Union[str, dict, SchemaHandle, None] = None,
+                 entity_validation: Optional[str] = None,
+                 unknown_entities: Optional[str] = None,
+                 policy_errors: Optional[str] = None,
+                 request_cache_size: Optional[int] = None,
+                 verbose: Optional[bool] = None,
+                 response_transform: Union[str, dict, None] = None,
+                 policy_diagnostics: bool = False,
+                 max_concurrency: Optional[int] = None,
+                 max_queue: Optional[int] = None,
+                 degradation: Optional[str] = None,
+                 default_cache_ttl: Union[str, int, None] = None,
+                 max_entities_age: Optional[float] = None,
+                 generate_correlation_ids: Optional[bool] = None,
+                 guardrails: Optional[str] = None,
+                 actor: Optional[str] = None,
+                 allowed_extension_functions: Optional[List[str]] = None,
+                 unmodeled_actions: Optional[Dict[str, str]] = None,
+                 max_workers: Optional[int] = None) -> None:
+        """
+        :param policies is a str containing all the policies in the Cedar PolicySet
+        :param entities a list of entities or a json-formatted string containing the list of entities to
+        include in the evaluation, or bytes or an os.PathLike path of the json, which may be gzip- or
+        zstd-compressed, or an EntityStore, whose entities are copied when the engine is created, so the engine
+        doesn't see later changes to the store until it's reloaded
+        :param schema (optional) dictionary or json-formatted string containing the Cedar schema, or a SchemaHandle
+        of it from compile_schema
+        :param entity_validation (optional) how entities are validated against the schema; see is_authorized
+        :param unknown_entities (optional) how requests naming entities that are not in the entities are handled;
+        see is_authorized
+        :param policy_errors (optional) how errors evaluating individual policies are handled; see is_authorized
+        :param request_cache_size (optional) the number of parsed requests to cache; 0 disables the cache
+        :param verbose (optional) boolean determining whether to enable verbose logging output within the library
+        :param response_transform (optional) dict or json-formatted string describing changes applied to every result
+        before it is returned: {"enrich": [{"from": path, "to": path, "map": {...}, "default": value}],
+        "add": {field: value}, "rename": {path: new_name}, "drop": [path]}, where paths are dot-separated, e.g.
+        "diagnostics.reason"; each enrichment looks up the string, or list of strings, at "from" in "map".  The
+        transformed result is available from AuthzResult.to_dict()
+        :param policy_diagnostics (optional) boolean determining whether to also parse each policy on its own,
+        recording how long it took to parse and its size; see policy_diagnostics()
+        :param max_concurrency (optional) the most calls that may evaluate at once; further calls wait in a queue,
+        and their results' metrics report how long in queue_duration_micros.  Unlimited by default
+        :param max_queue (optional) the most calls that may wait when max_concurrency calls are evaluating; further
+        calls get NoDecision results with an 'overloaded' error.  Unbounded by default
+        :param degradation (optional) how requests are decided after a reload fails, until one succeeds:
+        'fail_closed' (the default) denies every request; 'fail_open' allows requests for the actions named by the
+        action scopes of policies annotated @fail_open("true"), e.g. low-risk reads, and denies the rest; both report
+        the failure in the diagnostics' errors, with the code 'policy_store_unavailable'.  'serve_stale' keeps
+        deciding requests with the last policies, entities, and schema that loaded, with a warning that they're stale
+        :param default_cache_ttl (optional) how long decisions may be cached by the code enforcing them, as seconds or
+        e.g. '30s', '5m', '1h', '1d'.  Policies may set their own with an annotation, e.g. @cache_ttl("30s"); a
+        result's cache_ttl_seconds is the shortest TTL of the policies that determined it, where policies without
+        one have the default, or the default when no policy determined it.  Decisions made while failing closed or
+        open have a TTL of 0
+        :param max_entities_age (optional) how old, in seconds, the entities may get, since the engine was created or
+        last reloaded, before results warn that they are stale
+        :param generate_correlation_ids (optional) boolean determining whether requests without a correlation_id are
+        given a generated UUIDv7, returned in their result's correlation_id; defaults to CEDARPY_GENERATE_CORRELATION_IDS
+        :param guardrails (optional) a str containing org-wide forbid policies evaluated with the policies, whose ids are
+        guardrail0, guardrail1, and so on.  Reloads replace only the policies, so the guardrails hold for the life of
+        the engine, and results' diagnostics list the determining guardrails in reason_guardrails, and attribute the
+        decision to the 'guardrails' or 'policies' layer in reason_by_layer and decided_by
+        :param actor (optional) who or what is creating the engine, e.g. a user or a deploy job, recorded with the
+        engine's load in its changelog()
+        :param allowed_extension_functions (optional) the names of the Cedar extension functions and methods the
+        policies and guardrails may call, e.g. ['decimal', 'lessThan']; policies that call others are rejected when
+        they're loaded, by the engine's creation, reloads, and updates.  Any extension function may be called by
+        default
+        :param unmodeled_actions (optional) how requests are decided whose action no policy references, by naming the
+        action, or one of its action groups, in its scope, or by leaving the action unconstrained, e.g. an action newly
+        added to the schema: a dict from action uids, e.g. 'Action::"export"', or '*' for every such action, to
+        'deny', which denies the request with a warning naming the action, or 'no_decision', which leaves it
+        undecided with an unmodeled_action error naming the action.  By default such requests are denied, as Cedar
+        denies them, with no reasons
+        :param max_workers (optional) the number of threads of the thread pool shared by every batch that each of the
+        engine's batches is split across; defaults to CEDARPY_THREADS.  More than CEDARPY_MAX_THREADS, the size of the
+        pool, uses them all
+
+        :raises ValueError: if the policies, entities, schema, response transform, cache TTLs, guardrails, or unmodeled
+        actions cannot be parsed or validated, a guardrail isn't a forbid, a policy calls an extension function that
+        isn't allowed, or max_workers is less than 1
+        """
+        if isinstance(response_transform, dict):
+            response_transform = json.dumps(response_transform)
+        if isinstance(default_cache_ttl, int):
+            default_cache_ttl = f"{default_cache_ttl}s"
+        self._engine = _internal.CedarEngine(policies,
+                                             _to_internal_entities(entities),
+                                             _to_schema_str(schema),
+                                             entity_validation,
+                                             unknown_entities,
+                                             policy_errors,
+                                             request_cache_size,
+                                             verbose,
+                                             response_transform,
+                                             policy_diagnostics,
+                                             max_concurrency,
+                                             max_queue,
+                                             degradation,
+                                             default_cache_ttl,
+                                             max_entities_age,
+                                             generate_correlation_ids,
+                                             guardrails,
+                                             actor,
+                                             allowed_extension_functions,
+                                             unmodeled_actions,
+                                             max_workers)
+
+    def is_authorized(self,
+                      request: dict,
+                      include_diagnostics: bool = True,
+                      include_metrics: bool = True,
+                      include_missing_entities: bool = False,
+                      priority: Optional[str] = None,
+                      deadline: Optional[float] = None,
+                      include_attributes: Optional[Dict[str, List[str]]] = None,
+                      min_version: Optional[int] = None) -> AuthzResult:
+        """Evaluate whether the request is authorized by the engine's policies and entities.
+
+        :param request is a Cedar-style request object containing a principal, action, resource, and (optional)
+        context; context may be a dict (preferred) or a string
+        :param include_diagnostics (optional) boolean determining whether to include diagnostics in the result
+        :param include_metrics (optional) boolean determining whether to include metrics in the result
+        :param include_missing_entities (optional) boolean determining whether to report missing entities in the
+        diagnostics; see is_authorized
+        :param priority (optional) the lane the call waits in when the engine is at its max_concurrency:
+        'interactive' (the default), or 'batch', which waits while any interactive calls are waiting
+        :param deadline (optional) a time.monotonic() timestamp by which the call must be answered, e.g. the
+        deadline of the upstream request; once it passes, a call still queued, or a request not yet evaluated, gets a
+        NoDecision result with a 'deadline_exceeded' error
+        :param include_attributes (optional) the attributes of the request's entities to copy from the engine's
+        entities into the result's attributes, e.g. {"principal": ["department"]}; see cedarpy.is_authorized
+        :param min_version (optional) the version() the engine must be at, e.g. the one an update's commit() returned,
+        so a caller that just changed the engine's inputs is decided by them; the call waits for the version until
+        its deadline, and without one doesn't wait.  If the engine isn't at the version by then, the result is
+        NoDecision with a retryable 'stale_version' error
+
+        :returns an AuthzResult
+        """
+        return AuthzResult(self._engine.is_authorized(_to_internal_requests([request])[0],
+                                                      include_diagnostics,
+                                                      include_metrics,
+                                                      include_missing_entities,
+                                                      priority,
+                                                      deadline,
+                                                      _to_include_attributes_str(include_attributes),
+                                                      min_version))
+
+    def is_authorized_strict(self,
+                             request: dict,
+                             include_metrics: bool = True,
+                             include_missing_entities: bool = False,
+                             priority: Optional[str] = None,
+                             deadline: Optional[float] = None,
+                             include_attributes: Optional[Dict[str, List[str]]] = None,
+                             min_version: Optional[int] = None) -> AuthzResult:
+        """Evaluate whether the request is authorized, as is_authorized does, but raise the error of a request that
+        can't be decided rather than return a NoDecision result; see cedarpy.is_authorized_strict.  A retryable
+        error, e.g. overloaded, is raised as a CedarError with its code.
+
+        :param request and the options are as for is_authorized
+
+        :returns an AuthzResult, which is Allow or Deny
+        :raises CedarError: the subclass for the code of the first error of a request that couldn't be decided
+        """
+        return _decided(self.is_authorized(request, True, include_metrics, include_missing_entities, priority,
+                                           deadline, include_attributes, min_version))
+
+    def is_authorized_fast(self, request: dict) -> bool:
+        """Whether the request is allowed, for hot paths that need only the decision.  The request is decided as by
+        is_authorized, but no diagnostics, metrics, or result are built, which saves most of the per-call overhead
+        of simple requests.  The engine's hooks are still called with the decision.
+
+        :param request is a Cedar-style request object; see is_authorized
+
+        :returns True if the request is allowed, and False if it's denied or couldn't be decided
+        """
+        return self._engine.is_authorized_fast(request)
+
+    def is_authorized_batch(self,
+                            requests: List[dict],
+                            include_diagnostics: bool = True,
+                            include_metrics: bool = True,
+                            include_missing_entities: bool = False,
+                            priority: Optional[str] = None,
+                            deadline: Optional[float] = None,
+                            context_columns: Optional[Dict[str, list]] = None,
+                            include_attributes: Optional[Dict[str, List[str]]] = None,
+                            min_version: Optional[int] = None) -> List[AuthzResult]:
+        """Evaluate whether each of a batch of requests is authorized by the engine's policies and entities.
+
+        :param requests is list of Cedar-style request objects; see is_authorized.  Or NDJSON requests, as bytes or
+        the path of a file; see cedarpy.is_authorized_batch
+        :param include_diagnostics (optional) boolean determining whether to include diagnostics in the results
+        :param include_metrics (optional) boolean determining whether to include metrics in the results
+        :param include_missing_entities (optional) boolean determining whether to report missing entities in the
+        diagnostics; see is_authorized
+        :param priority (optional) 'interactive' (the default) or 'batch'; see is_authorized.  A batch call gives
+        up its place between requests to interactive calls that are waiting
+        :param deadline (optional) a time.monotonic() timestamp by which the call must be answered; the deadline is
+        checked between requests, and the requests left when it passes get NoDecision results with a
+        'deadline_exceeded' error.  See is_authorized
+        :param context_columns (optional) the requests' contexts as columns, a dict from each context attribute to a
+        list of its values; see cedarpy.is_authorized_batch
+        :param include_attributes (optional) the attributes of each request's entities to copy into its result; see
+        is_authorized
+        :param min_version (optional) the version the engine must be at to evaluate the batch; see is_authorized
+
+        :returns a list of AuthzResults, in same order as the requests
+        """
+        authz_result_strs: List[str] = self._engine.is_authorized_batch(_to_internal_requests(requests),
+                                                                        include_diagnostics,
+                                                                        include_metrics,
+                                                                        include_missing_entities,
+                                                                        priority,
+                                                                        deadline,
+                                                                        _to_context_columns_str(context_columns),
+                                                                        _to_include_attributes_str(include_attributes),
+                                                                        min_version)
+        return _to_authz_results(authz_result_strs)
+
+    def is_authorized_batch_resumable(self,
+                                      requests: List[dict],
+                                      resume_token: Optional[str] = None,
+                                      max_requests: Optional[int] = None,
+                                      deadline: Optional[float] = None,
+                                      include_diagnostics: bool = True,
+                                      include_metrics: bool = True,
+                                      include_missing_entities: bool = False,
+                                      priority: Optional[str] = None) -> BatchPage:
+        """Evaluate a very large batch across several calls, each evaluating the requests it can before max_requests
+        or the deadline runs out.  Pass the same batch to each call, with the resume token of the previous page; the
+        token is checked against the batch and the engine's inputs, so a token for a different batch, or from before a
+        reload, is rejected.  Resuming is idempotent: the same token evaluates the same requests.
+
+        :param requests is list of Cedar-style request objects; see is_authorized
+        :param resume_token (optional) the resume_token of the previous page, or None to start the batch
+        :param max_requests (optional) the most requests to evaluate in this call
+        :param deadline (optional) a time.monotonic() timestamp after which no more requests are evaluated; see
+        is_authorized_batch
+        :param include_diagnostics (optional) see is_authorized_batch
+        :param include_metrics (optional) see is_authorized_batch
+        :param include_missing_entities (optional) see is_authorized_batch
+        :param priority (optional) see is_authorized_batch
+
+        :returns a BatchPage of the results evaluated and the token to resume from.  A call that can't start
+        evaluating, e.g. because the engine is overloaded, returns no results and a token to resume where it started
+
+        :raises ValueError: if the resume token is invalid, or was issued for a different batch or before a reload, or
+        max_requests is less than 1
+        """
+        authz_result_strs, start, next_token = self._engine.is_authorized_batch_resumable(
+            _to_internal_requests(requests),
+            resume_token,
+            max_requests,
+            deadline,
+            include_diagnostics,
+            include_metrics,
+            include_missing_entities,
+            priority)
+        return BatchPage(_to_authz_results(authz_result_strs), start, next_token)
+
+    def reload(self,
+               policies: str,
+               entities: Union[str, List[dict], bytes, os.PathLike, 'EntityStore'],
+               schema: Union[str, dict, SchemaHandle, None] = None,
+               actor: Optional[str] = None) -> None:
+        """Replace the engine's policies, entities, and schema, e.g. when the policy store changes.  Calls already
+        evaluating finish with the inputs they started with.  The engine's guardrails are kept.  The reload is
+        recorded in the engine's changelog(), whether or not it succeeds.
+
+        :param policies is a str containing all the policies in the Cedar PolicySet
+        :param entities a list of entities or a json-formatted string containing the list of entities, or bytes or an
+        os.PathLike path of the json, which may be gzip- or zstd-compressed, or an EntityStore, whose current
+        entities are copied
+        :param schema (optional) dictionary or json-formatted string containing the Cedar schema, or a SchemaHandle
+        of it from compile_schema
+        :param actor (optional) who or what is reloading the engine, e.g. a user or a deploy job, recorded with the
+        reload in the changelog
+
+        :raises ValueError: if the inputs cannot be parsed or validated; the engine then decides requests as its
+        degradation mode says until a reload succeeds
+        """
+        self._engine.reload(policies, _to_internal_entities(entities), _to_schema_str(schema), actor)
+
+    def register_hook(self, event: str, callback: Optional[Callable[[dict], None]]) -> None:
+        """Register a callback for the engine's events, e.g. to update metrics, raise alerts, or invalidate caches,
+        replacing any callback registered for the event before.  Each callback is passed a dict describing the event:
+
+        * 'reload': each reload, with ok, duration_micros, and either num_policies, num_entities, and fingerprints, or
+          error_code and error when it failed
+        * 'error': each failed reload, with source 'reload', and each result with errors, with source 'request' and
+          its correlation_id and whether it's retryable; both with error_codes and errors
+        * 'decision': each result of is_authorized, is_authorized_batch, and is_authorized_batch_resumable, with its
+          decision, correlation_id, reason, and fingerprints, whether or not the result includes diagnostics
+
+        A call that fails before any of its requests is evaluated, e.g. with an 'overloaded' or 'stale_version' error,
+        emits one 'decision' and one 'error' event for all of its requests rather than one per request, with their
+        num_requests and correlation_ids; its correlation_id is the request's when there's only one.
+
+        Callbacks are called once the call's evaluation is done, on the calling thread with the GIL held, so a slow
+        callback delays its caller and any other thread waiting for the GIL; keep them quick.  An exception raised by
+        a callback doesn't affect the call; it's reported to sys.unraisablehook, counted in stats()' hook_failures, and
+        a callback that raises 10 times in a row is unregistered.
+
+        :param event is 'reload', 'error', or 'decision'
+        :param callback a function of the event's dict, or None to unregister the event's callback
+
+        :raises ValueError: if the event is invalid
+        """
+        callback_str = None
+        if callback is not None:
+            def callback_str(payload_str: str) -> None:
+                callback(json.loads(payload_str))
+
+        self._engine.register_hook(event, callback_str)
+
+    def stats(self) -> dict:
+        """Statistics about the engine: the number of policies and entities it holds, and the fingerprints of its
+        current inputs, as in its results; the size, hits, misses,
+        and hit rate of its request cache; and the size of its table of interned entity uids, how often request uids
+        were resolved from it, and how many bytes of uid text that saved parsing and allocating.  An engine with
+        max_concurrency also reports its max_concurrency and max_queue, and the number of active, queued (in all, and
+        by priority), and rejected evaluations.  reload_failed is whether the last reload failed, and when it did,
+        reload_failure is why and reload_failed_seconds_ago when.  hook_failures is how many times the engine's
+        callbacks have raised; see register_hook.
+        """
+        return json.loads(self._engine.stats())
+
+    def view(self, redact: Optional[List[str]] = None) -> "EngineView":
+        """A read-only view of the engine's current policies and entities, to hand to plugin or report code.  The view
+        is a snapshot, unaffected by later reloads, and has no way to change or reach the engine.
+
+        :param redact (optional) the names of entity attributes whose values the view replaces with '<redacted>',
+        including the attributes of records nested in attributes and sets.  Policies aren't redacted, so a secret
+        written in a policy's text is in the view
+
+        :returns an EngineView
+        :raises ValueError: if a name in redact matches no attribute of the schema or the entities
+        """
+        return EngineView(self._engine.view(redact))
+
+    def policy_diagnostics(self) -> Optional[List[dict]]:
+        """The parse duration and size of each of the engine's policies, in the order the policies appear, e.g.
+        {"policy_id": "policy0", "line": 1, "size_bytes": 120, "parse_duration_micros": 35}; sort by
+        parse_duration_micros to find the policies that make loading the policy set slow.
+
+        :returns a list of dicts, or None unless the engine was created with policy_diagnostics=True
+        """
+        return json.loads(self._engine.policy_diagnostics())
+
+    def changelog(self,
+                  since: Optional[float] = None,
+                  actor: Optional[str] = None,
+                  kind: Optional[str] = None) -> List[dict]:
+        """The changes to the engine's policies, entities, and schema, oldest first: its load and each reload since,
+        whether or not it succeeded, and each committed update, e.g. to audit who changed the engine and when.  Each
+        is a dict of its 'seq', its position in the log from 0; its 'kind', 'load', 'reload', or 'update'; the 'actor'
+        passed with it, or None; its 'timestamp', in seconds since the Unix epoch, like time.time(); and whether it
+        was 'ok', with the 'num_policies', 'num_entities', and 'fingerprints' it loaded, or the 'error_code' and 'error' it failed with.
+        A change is logged before results are decided by its inputs, and entries are never changed or removed.
+
+        :param since (optional) only the changes at or after this time, in seconds since the Unix epoch
+        :param actor (optional) only the changes by this actor
+        :param kind (optional) only the changes of this kind, 'load', 'reload', or 'update'
+
+        :returns a list of dicts
+        :raises ValueError: if the kind is invalid
+        """
+        return json.loads(self._engine.changelog(since, actor, kind))
+
+    def begin_update(self, actor: Optional[str] = None) -> "EngineUpdate":
+        """Begin an update of the engine's policies, entities, and schema, which are staged on the update, validated
+        together, and then committed at once, so no request is decided by new policies with the old schema or
+        entities.  Unlike reload(), a failed commit leaves the engine as it was, neither changed nor degraded.
+
+        :param actor (optional) who or what is updating the engine, recorded with the commit in the changelog
+
+        :returns an EngineUpdate
+        """
+        return EngineUpdate(self._engine.begin_update(actor))
+
+    def version(self) -> int:
+        """The engine's version, which its load, each reload, and each committed update add one to, so it's the
+        number of entries in its changelog().  Pass a version as is_authorized's min_version to be decided by the
+        inputs of that version or later."""
+        return self._engine.version()
+
+
+class EngineUpdate:
+    """An update of a CedarEngine's policies, entities, and schema; see CedarEngine.begin_update.  The update begins
+    with the engine's current inputs; stage the ones to replace, then commit() them, e.g.
+
+        update = engine.begin_update(actor="deploy-job")
+        update.set_policies(policies)
+        update.set_entities(entities)
+        update.set_schema(schema)
+        report = update.validate()
+        if report["valid"]:
+            update.commit()
+
+    Inputs that aren't staged are kept, so an update of only the policies keeps the engine's entities and schema,
+    and validates against that schema.  The commit fails, and the engine keeps its inputs, if the engine changed since the update began, e.g. by a
+    reload or another update's commit, so an update never undoes changes it didn't see.
+    """
+
+    def __init__(self, update: _internal.EngineUpdate) -> None:
+        self._update = update
+
+    def set_policies(self, policies: str) -> None:
+        """Stage the policies, a str containing all the policies in the Cedar PolicySet"""
+        self._update.set_policies(policies)
+
+    def set_entities(self, entities: Union[str, List[dict], bytes, os.PathLike, 'EntityStore']) -> None:
+        """Stage the entities, a list of entities or a json-formatted string containing the list of entities, or
+        bytes or an os.PathLike path of the json, which may be gzip- or zstd-compressed, or an EntityStore, whose
+        current entities are copied"""
+        self._update.set_entities(_to_internal_entities(entities))
+
+    def set_schema(self, schema: Union[str, dict, SchemaHandle, None]) -> None:
+        """Stage the schema, a dictionary or json-formatted string containing the Cedar schema, or a SchemaHandle of
+        it from compile_schema, or None to remove the engine's schema; an update that doesn't stage one keeps the
+        engine's"""
+        self._update.set_schema(_to_schema_str(schema))
+
+    def validate(self, mode: str = "strict") -> dict:
+        """Load the staged inputs, as reload() would, and validate the policies against the staged schema, as
+        validate_policies does; without a schema, the policies are valid if they load.
+
+        :param mode (optional) Cedar's validation mode: 'strict' (default) or 'permissive'
+
+        :returns a dict of the errors and warnings, as validate_policies returns
+        :raises ValueError: if the inputs cannot be loaded, or the mode is invalid
+        """
+        return json.loads(self._update.validate(mode))
+
+    def commit(self) -> int:
+        """Replace the engine's policies, entities, and schema with the staged ones at once.  The inputs are
+        validated in strict mode first, unless validate() was called since they were staged.  The commit is
+        recorded in the engine's changelog() with kind 'update'; a failed commit isn't, since the engine is unchanged.
+
+        :returns the engine's version() with the update, to pass as is_authorized's min_version, e.g. from another
+        thread that must be decided by the update's inputs
+
+        :raises ValidationError: if the policies have validation errors
+        :raises InvalidArgumentError: if the engine changed since the update began, or the update was committed already
+        :raises ValueError: if the inputs cannot be loaded
+        """
+        return self._update.commit()
+
+    @property
+    def committed(self) -> bool:
+        """Whether the update has been committed"""
+        return self._update.committed()
+
+
+class EngineView:
+    """A read-only snapshot of a CedarEngine's policies and entities; see CedarEngine.view.  The snapshot is held in
+    Rust, which provides no way to change it, so code given a view can't change the engine's policy store.
+    """
+
+    def __init__(self, view: _internal.EngineView) -> None:
+        self._view = view
+
+    def policies(self) -> List[dict]:
+        """The policies, sorted by id, e.g. {"policy_id": "policy0", "effect": "permit", "annotations": {},
+        "template_id": None, "text": "permit(...);"}; template_id is the template of a template-linked policy.  The
+        text isn't redacted, so it has any literals the policies compare redacted attributes with"""
+        return json.loads(self._view.policies())
+
+    def policy(self, policy_id: str) -> Optional[dict]:
+        """The policy with the id, like those of policies(), or None if there is no such policy"""
+        policy_str = self._view.policy(policy_id)
+        return json.loads(policy_str) if policy_str is not None else None
+
+    def entities(self) -> List[dict]:
+        """The entities, including the schema's actions, in Cedar's entity format, with redacted attributes"""
+        return json.loads(self._view.entities())
+
+    def entity(self, uid: str) -> Optional[dict]:
+        """The entity with the uid, e.g. 'User::"alice"', with redacted attributes, or None if it isn't in the
+        entities
+
+        :raises ValueError: if the uid is invalid
+        """
+        entity_str = self._view.entity(uid)
+        return json.loads(entity_str) if entity_str is not None else None
+
+    def is_in(self, uid: str, ancestor_uid: str) -> bool:
+        """Whether the entity uid is ancestor_uid or one of its descendants; see cedarpy.is_in
+
+        :raises ValueError: if either uid is invalid
+        """
+        return self._view.is_in(uid, ancestor_uid)
+
+    def summary(self) -> dict:
+        """The number of policies and entities, the fingerprints of the inputs, as in CedarEngine.stats(), and the
+        redacted_attributes"""
+        return json.loads(self._view.summary())
+
+
+class FakeEngine:
+    """A test double for CedarEngine that decides requests with scripted rules instead of policies, so unit tests of
+    code that enforces decisions don't need real policies.  Its results are the same AuthzResult classes, with the
+    same fields, as CedarEngine's.
+    """
+
+    def __init__(self, rules: List[dict], default: Optional[dict] = None) -> None:
+        """
+        :param rules is a list of rules; each request is decided by the first rule that matches it.  A rule matches
+        requests by 'principal', 'action', and 'resource' patterns, e.g. 'User::"alice"' or 'Photo::*', where '*'
+        matches anything, and by the attribute values its 'context' dict requires; a rule without a pattern
+        matches any value.  It answers with its 'decision' ('Allow' or 'Deny', default 'Deny'), the policy ids
+        in its 'reason', and the evaluation 'errors' in the diagnostics, or fails the request with its 'error'
+        :param default (optional) what requests that no rule matches are answered, as a rule without patterns;
+        defaults to a Deny
+
+        :raises ValueError: if a rule is invalid
+        """
+        self._engine = _internal.FakeEngine(json.dumps(rules), json.dumps(default) if default is not None else None)
+
+    def is_authorized(self,
+                      request: dict,
+                      include_diagnostics: bool = True,
+                      include_metrics: bool = True,
+                      include_missing_entities: bool = False,
+                      priority: Optional[str] = None,
+                      deadline: Optional[float] = None) -> AuthzResult:
+        """Decide the request with the rules; see CedarEngine.is_authorized.  The priority and deadline are
+        ignored"""
+        authz_result_str = self._engine.is_authorized(_to_internal_requests([request])[0],
+                                                      include_diagnostics,
+                                                      include_metrics,
+                                                      include_missing_entities)
+        return _to_authz_results([authz_result_str])[0]
+
+    def is_authorized_batch(self,
+                            requests: List[dict],
+                            include_diagnostics: bool = True,
+                            include_metrics: bool = True,
+                            include_missing_entities: bool = False,
+                            priority: Optional[str] = None,
+                            deadline: Optional[float] = None) -> List[AuthzResult]:
+        """Decide each of a batch of requests with the rules; see CedarEngine.is_authorized_batch.  The priority and
+        deadline are ignored"""
+        authz_result_strs: List[str] = self._engine.is_authorized_batch(_to_internal_requests(requests),
+                                                                        include_diagnostics,
+                                                                        include_metrics,
+                                                                        include_missing_entities)
+        return _to_authz_results(authz_result_strs)
+
+    def is_authorized_fast(self, request: dict) -> bool:
+        """Whether the rules allow the request; see CedarEngine.is_authorized_fast"""
+        return self.is_authorized(request, include_diagnostics=False, include_metrics=False).allowed
+
+    @property
+    def requests(self) -> List[dict]:
+        """the requests decided so far, in order, each with its principal, action, resource, context, and
+        correlation_id"""
+        return json.loads(self._engine.requests())
+
+
+def is_authorized_union(engines: Dict[str, CedarEngine],
+                        request: dict,
+                        include_diagnostics: bool = True,
+                        include_metrics: bool = True) -> AuthzResult:
+    """Evaluate whether the request is authorized by several engines combined as though their policies were one
+    policy set, e.g. an organization-wide engine of forbids and an application's engine: a satisfied forbid in any
+    engine denies the request, and otherwise a satisfied permit in any engine allows it.  Each engine evaluates the
+    request against its own entities.
+
+    :param engines is a dict of engines by name
+    :param request is a Cedar-style request object; see CedarEngine.is_authorized
+    :param include_diagnostics (optional) boolean determining whether to include diagnostics in the result; the
+    diagnostics report the determining policies of each engine in reason_by_engine, and of each layer in
+    reason_by_layer and decided_by, with each engine's guardrails a layer of their own, and prefix the errors and
+    warnings of each engine with its name
+    :param include_metrics (optional) boolean determining whether to include metrics in the result, which are summed
+    across the engines
+
+    :returns an AuthzResult
+    """
+    authz_result_str = _internal.is_authorized_union([(name, engine._engine) for name, engine in engines.items()],
+                                                     _to_internal_requests([request])[0],
+                                                     include_diagnostics,
+                                                     include_metrics)
+    return _to_authz_results([authz_result_str])[0]
+
+
+def generate_load(schema: Union[str, dict],
+                  policies: str,
+                  n_requests: int,
+                  seed: int = 0,
+                  entities_per_type: int = 10) -> dict:
+    """Generate a reproducible synthetic corpus of requests, and the entities they refer to, for benchmarking.
+
+    Entities have attributes and parents of the types the schema declares, and requests have the principal and
+    resource types and the context the schema declares for their action.  The entities the policies' scopes name
+    are added to the corpus, and about half of the requests are drawn from them so that the policies apply.  The
+    same inputs and seed always produce the same corpus.
+
+    :param schema is a str containing the schema in JSON format or a schema dict
+    :param policies is a str containing the policies to benchmark
+    :param n_requests is the number of requests to generate
+    :param seed (optional) seeds the generator
+    :param entities_per_type (optional) the number of entities to generate for each entity type
+
+    :returns a dict of the 'seed', 'entities', and 'requests'; create an engine with the entities to benchmark it
+    :raises ValueError: if the schema or policies are invalid, or the schema has no actions to make requests for
+    """
+    return json.loads(_internal.generate_load(_to_schema_str(schema), policies, n_requests, seed, entities_per_type))
+
+
+def bench(engine: Union[CedarEngine, FakeEngine], corpus: dict, warmup: int = 10) -> dict:
+    """Authorize each request of a corpus, such as generate_load produces, one at a time, and report the latencies.
+
+    :param engine is the engine to benchmark, e.g. CedarEngine(policies, corpus['entities'], schema)
+    :param corpus is a dict with the 'requests' to authorize
+    :param warmup (optional) the number of requests to authorize first, without measuring them
+
+    :returns a dict of the number of 'requests' measured, the number 'allowed', and their 'mean', 'p50', 'p90',
+    'p99', and 'max' latencies in microseconds
+    """
+    requests = corpus['requests']
+    for request in requests[:warmup]:
+        engine.is_authorized(request, include_diagnostics=False, include_metrics=False)
+    latencies = []
+    allowed = 0
+    for request in requests:
+        t_start = time.perf_counter_ns()
+        authz_result = engine.is_authorized(request, include_diagnostics=False, include_metrics=False)
+        latencies.append((time.perf_counter_ns() - t_start) / 1000)
+        allowed += authz_result.allowed
+    latencies.sort()
+
+    def percentile(p: float) -> float:
+        return latencies[min(len(latencies) - 1, int(p / 100 * len(latencies)))] if latencies else 0.0
+
+    return {
+        'requests': len(latencies),
+        'allowed': allowed,
+        'mean': sum(latencies) / len(latencies) if latencies else 0.0,
+        'p50': percentile(50),
+        'p90': percentile(90),
+        'p99': percentile(99),
+        'max': latencies[-1] if latencies else 0.0,
+    }
+
+
+def _rss_bytes() -> Optional[int]:
+    """The resident set size of the process, or on platforms without /proc its peak resident set size"""
+    try:
+        with open('/proc/self/status') as status:
+            for line in status:
+                if line.startswith('VmRSS:'):
+                    return int(line.split()[1]) * 1024
+    except OSError:
+        pass
+    try:
+        import resource
+        import sys
+        max_rss = resource.getrusage(resource.RUSAGE_SELF).ru_maxrss
+        # macOS reports bytes, other platforms kibibytes
+        return max_rss if sys.platform == 'darwin' else max_rss * 1024
+    except ImportError:
+        return None
+
+
+def soak(engine: Union[CedarEngine, FakeEngine],
+         corpus: dict,
+         duration_minutes: float,
+         sample_interval_seconds: float = 5.0,
+         warmup_fraction: float = 0.25,
+         tolerance_bytes: int = 8 * 1024 * 1024,
+         on_sample: Optional[Callable[[dict], None]] = None) -> dict:
+    """Authorize the requests of a corpus, such as generate_load produces, over and over for a while, sampling the
+    process's memory, to check that memory stays flat under a workload before rolling it out.
+
+    Each sample has the 'elapsed_seconds', the number of 'requests' authorized so far, the process's 'rss_bytes',
+    and for a CedarEngine its 'engine_stats' (see CedarEngine.stats), whose cache and interner sizes should level
+    off.  Memory grows while caches fill, so growth is measured from the first sample after the warmup.
+
+    :param engine is the engine to soak, e.g. CedarEngine(policies, corpus['entities'], schema)
+    :param corpus is a dict with the 'requests' to authorize
+    :param duration_minutes is how long to run for
+    :param sample_interval_seconds (optional) how often to sample memory
+    :param warmup_fraction (optional) the fraction of the run before growth is measured from
+    :param tolerance_bytes (optional) the growth after the warmup above which a leak is suspected
+    :param on_sample (optional) called with each sample as it's taken, e.g. to log it or take a heap snapshot
+
+    :returns a dict of the 'samples', the 'requests' authorized, the 'rss_growth_bytes' after the warmup,
+    'rss_growth_bytes_per_1k_requests', and whether a leak is suspected ('leak_suspected')
+    :raises ValueError: if the corpus has no requests
+    """
+    requests = corpus['requests']
+    if not requests:
+        raise ValueError("the corpus has no requests")
+    samples = []
+
+    def sample(elapsed: float, num_requests: int) -> None:
+        taken = {'elapsed_seconds': elapsed, 'requests': num_requests, 'rss_bytes': _rss_bytes()}
+        if isinstance(engine, CedarEngine):
+            taken['engine_stats'] = engine.stats()
+        samples.append(taken)
+        if on_sample is not None:
+            on_sample(taken)
+
+    duration_seconds = duration_minutes * 60
+    t_start = time.monotonic()
+    next_sample = 0.0
+    num_requests = 0
+    while True:
+        elapsed = time.monotonic() - t_start
+        if elapsed >= next_sample:
+            sample(elapsed, num_requests)
+            next_sample += sample_interval_seconds
+        if elapsed >= duration_seconds:
+            break
+        engine.is_authorized(requests[num_requests % len(requests)], include_diagnostics=False, include_metrics=False)
+        num_requests += 1
+
+    measured = [taken for taken in samples
+                if taken['elapsed_seconds'] >= warmup_fraction * duration_seconds and taken['rss_bytes'] is not None]
+    growth = measured[-1]['rss_bytes'] - measured[0]['rss_bytes'] if len(measured) > 1 else 0
+    measured_requests = measured[-1]['requests'] - measured[0]['requests'] if len(measured) > 1 else 0
+    return {
+        'samples': samples,
+        'requests': num_requests,
+        'rss_growth_bytes': growth,
+        'rss_growth_bytes_per_1k_requests': growth * 1000 / measured_requests if measured_requests else 0.0,
+        'leak_suspected': growth > tolerance_bytes,
+    }
+
+
+def _to_internal_requests(requests: Union[List[dict], bytes, str, os.PathLike]) -> Union[List[dict], bytes, str]:
+    if isinstance(requests, (bytes, str, os.PathLike)):
+        # NDJSON requests, or the path of a file of them, which are parsed in Rust
+        return os.fspath(requests) if isinstance(requests, os.PathLike) else requests
+    # request dicts, and their context dicts, are converted in Rust
+    return requests if isinstance(requests, list) else list(requests)
+
+
+def _to_internal_policies(policies: Union[str, PolicySetHandle]):
+    if isinstance(policies, PolicySetHandle):
+        return policies._handle
+    return policies
+
+
+def _to_internal_entities(entities: Union[str, List[dict], bytes, os.PathLike, 'EntityStore']):
+    if isinstance(entities, EntityStore):
+        return entities._store
+    # lists are converted to JSON in Rust, without serializing them to a str, and bytes and paths are read, and
+    # decompressed, in Rust
+    return entities
+
+
+def _to_context_columns_str(context_columns: Optional[Dict[str, list]]) -> Optional[str]:
+    if context_columns is None:
+        return None
+    # columns may be any sequences, e.g. tuples
+    return json.dumps({name: list(values) for name, values in context_columns.items()})
+
+
+def _to_include_attributes_str(include_attributes: Optional[Dict[str, List[str]]]) -> Optional[str]:
+    if include_attributes is None:
+        return None
+    # attribute names may be any sequences, e.g. tuples
+    return json.dumps({role: list(attrs) for role, attrs in include_attributes.items()})
+
+
+def _to_schema_str(schema: Union[str, dict, SchemaHandle, None]):
+    if isinstance(schema, dict):
+        schema = json.dumps(schema)
+    elif isinstance(schema, SchemaHandle):
+        return schema._handle
+    return schema
+
+
+class FormatterConfig:
+    """Options for format_policies: line_width, the maximum length of a line (1 to 10000, default 80), and
+    indent_width, the number of spaces per level of indentation (0 to 64 and at most line_width, default 2).  The
+    options are validated when the config is created.
+    """
+
+    def __init__(self, line_width: Optional[int] = None, indent_width: Optional[int] = None) -> None:
+        """
+        :raises ValueError: if an option is out of range
+        """
+        self._options: dict = json.loads(_internal.formatter_options(None, line_width, indent_width))
+
+    @property
+    def line_width(self) -> int:
+        return self._options['line_width']
+
+    @property
+    def indent_width(self) -> int:
+        return self._options['indent_width']
+
+    def to_dict(self) -> dict:
+        """all the formatter's options, by name"""
+        return dict(self._options)
+
+    def __eq__(self, other: object) -> bool:
+        return isinstance(other, FormatterConfig) and self._options == other._options
+
+    def __repr__(self) -> str:
+        return f"FormatterConfig({', '.join(f'{name}={value!r}' for name, value in self._options.items())})"
+
+
+def format_policies(policies: Union[str, dict, List[dict]],
+                    line_width: Optional[int] = None,
+                    indent_width: Optional[int] = None,
+                    config: Union[FormatterConfig, dict, None] = None,
+                    input_format: str = "cedar") -> str:
+    """Format the provided policies according to the Cedar conventions.
+
+    :param policies is a str containing the policies to be formatted, or policies in Cedar's JSON form (the EST):
+    a policy dict or a list of them, or a json-formatted string of either with input_format='json'
+    :param line_width (optional) is the desired maximum line length, overriding config's
+    :param indent_width (optional) is the desired indentation width, overriding config's
+    :param config (optional) FormatterConfig or dict of formatter options; options it leaves out take the
+    FormatterConfig defaults
+    :param input_format (optional) 'cedar' (policies are Cedar text) or 'json' (policies are in Cedar's JSON form);
+    dict and list policies are always JSON
+
+    :returns the formatted policy, with JSON policies in the order given
+    :raises ValueError: if the input policies cannot be parsed or an option is invalid
+    """
+    if isinstance(policies, (dict, list)):
+        policies = json.dumps(policies)
+        input_format = "json"
+    if isinstance(config, FormatterConfig):
+        config = config.to_dict()
+    options = json.dumps(config) if config is not None else None
+    return _internal.format_policies(policies, line_width, indent_width, options, input_format)
+
+
+def parse_policy_partial(text: str) -> dict:
+    """Parse policies that may be incomplete, e.g. as they are typed into an editor, as far as they go.
+
+    The result has:
+    * 'policies': per policy, whether it's 'complete' (ends with ';'), its 'effect', 'annotations' as [key, value]
+      pairs, 'scope' constraints with their 'variable' and 'text', and 'conditions' with their 'kind' ('when' or
+      'unless') and 'expression'; an unfinished policy's structure is that of the policy with its brackets and ';'
+      supplied
+    * 'diagnostics': per problem, its 'severity', 'message', and 'span'
+    * 'valid': whether the text is a valid policy set
+
+    Spans have 'start' and 'end' offsets, in characters, so text[start:end] is the span, and the 1-based 'line' and
+    'column' of the start and 'end_line' and 'end_column' of the end.
+
+    :param text is a str containing the policies
+
+    :returns a dict of the policies and diagnostics
+    """
+    return json.loads(_internal.parse_policy_partial(text))
+
+
+class ParseIssue:
+    def __init__(self, issue: dict) -> None:
+        super().__init__()
+        self._issue = issue
+
+    @property
+    def policy_id(self) -> str:
+        """the id Cedar assigns the policy the error is in, e.g. policy0 for the first"""
+        return self._issue['policy_id']
+
+    @property
+    def message(self) -> str:
+        return self._issue['message']
+
+    @property
+    def span(self) -> dict:
+        """where the error is, as for parse_policy_partial: 'start' and 'end' character offsets, and the 'line',
+        'column', 'end_line', and 'end_column'"""
+        return self._issue['span']
+
+    @property
+    def line(self) -> int:
+        return self._issue['span']['line']
+
+    @property
+    def column(self) -> int:
+        return self._issue['span']['column']
+
+    @property
+    def text(self) -> str:
+        """the offending text, policies[span['start']:span['end']]; empty for an error at the end of the input"""
+        return self._issue['text']
+
+    def __getitem__(self, __name: str) -> Any:
+        return getattr(self, __name)
+
+
+def check_parse(policies: str) -> List[ParseIssue]:
+    """Check that policies parse, without authorizing anything, e.g. to show a policy editor's user where their
+    policies' syntax errors are.  Every error is reported, with the policy it's in, its span, and the text of the
+    span, so an error in one policy doesn't hide those of the policies after it.
+
+    :param policies is a str containing the policies
+
+    :returns a list of ParseIssues, in the order of the text, which is empty when the policies parse
+    """
+    return [ParseIssue(issue) for issue in json.loads(_internal.check_parse(policies))]
+
+
+def validate_policies(policies: str,
+                      schema: Union[str, dict, SchemaHandle],
+                      mode: str = "strict",
+                      allowed_extension_functions: Optional[List[str]] = None) -> dict:
+    """Validate policies against a schema with Cedar's validator, finding e.g. entity types and actions that aren't
+    in the schema, actions the policy's scope can't apply to, and type errors, before the policies are deployed.
+
+    The result has:
+    * 'valid': whether the policies have no errors; warnings don't fail validation
+    * 'errors': per error, the 'policy_id' Cedar assigns the policy (e.g. policy0), its 'kind'
+      ('unrecognized_entity_type', 'unrecognized_action_id', 'invalid_action_application', 'type_error', or
+      'unspecified_entity', or 'disallowed_extension_function'), 'message', and the 'span' of the policies' text
+      it's in, as for parse_policy_partial, or None when Cedar doesn't say where it is
+    * 'warnings': for strings and identifiers that could be confused for others, e.g. with mixed scripts, each with
+      its 'policy_id', 'kind', 'message', and a 'span' of None
+
+    Errors and warnings are in the order of the policies.
+
+    :param policies is a str containing the policies
+    :param schema is a str containing the schema in JSON format, a schema dict, or a SchemaHandle from
+    compile_schema
+    :param mode (optional) Cedar's validation mode: 'strict' (default), which also requires policies to have a
+    restricted form amenable to analysis, or 'permissive', which checks only for type errors
+    :param allowed_extension_functions (optional) the names of the Cedar extension functions and methods the policies
+    may call, as for CedarEngine; each call of another is a 'disallowed_extension_function' error
+
+    :returns a dict of the errors and warnings
+    :raises ValueError: if the policies cannot be parsed, the schema is invalid, the mode is invalid, or an allowed
+    extension function isn't one of Cedar's
+    """
+    return json.loads(_internal.validate_policies(policies, _to_schema_str(schema), mode, allowed_extension_functions))
+
+
+def policies_to_json(policies: str, structured: bool = False) -> List[dict]:
+    """Convert Cedar policies to Cedar's JSON form (the EST).
+
+    :param policies is a str containing Cedar policies and templates
+    :param structured (optional) when True returns a dict per policy with its 'id' (the id Cedar assigns it,
+    e.g. policy0), source 'text', and JSON 'policy', so the policies can be told apart after conversion
+
+    :returns the policies in JSON form, in the order they appear
+    :raises ValueError: if the policies cannot be parsed
+    """
+    return json.loads(_internal.policies_to_json(policies, structured))
+
+
+def policies_from_json(policies: Union[str, dict, List[dict]], structured: bool = False) -> Union[str, List[dict]]:
+    """Convert policies in Cedar's JSON form (the EST) to Cedar text.
+
+    :param policies is a policy dict or a list of them, or a json-formatted string of either; the list may instead
+    hold {'id': ..., 'policy': ...} dicts, such as policies_to_json returns with structured=True, to keep each
+    policy's id
+    :param structured (optional) when True returns a dict per policy with its 'id', 'text', and JSON 'policy'
+    instead of the concatenated text; policies without a given id are identified by position, e.g. policy0
+
+    :returns the policies' Cedar text, in the order given
+    :raises ValueError: if the policies are invalid
+    """
+    if not isinstance(policies, str):
+        policies = json.dumps(policies)
+    converted = _internal.policies_from_json(policies, structured)
+    return json.loads(converted) if structured else converted
+
+
+class SplitPolicySource:
+    def __init__(self, split: dict) -> None:
+        super().__init__()
+        self._split = split
+
+    @property
+    def static_policies(self) -> List[dict]:
+        """the static policies, each a dict with its 'id', 'text', and JSON 'policy'"""
+        return self._split['static_policies']
+
+    @property
+    def templates(self) -> List[dict]:
+        """the templates, each a dict with its 'id', 'text', and JSON 'policy'"""
+        return self._split['templates']
+
+    @property
+    def links(self) -> List[dict]:
+        """the template links, each a dict with its 'id', 'template_id', and slot 'values'"""
+        return self._split['links']
+
+    def __getitem__(self, __name: str) -> Any:
+        return getattr(self, __name)
+
+
+def split_policy_source(policies: Union[str, dict, List[dict]], input_format: str = "cedar") -> SplitPolicySource:
+    """Split policies into their static policies, templates, and template links.
+
+    :param policies is a str containing Cedar policies and templates, or policies in Cedar's JSON form: a list of
+    policy dicts, {'id': ..., 'policy': ...} dicts, and template links ({'templateId': ..., 'newId': ...,
+    'values': {...}}), or a policy set dict with 'staticPolicies', 'templates', and 'templateLinks'; or a
+    json-formatted string of either with input_format='json'.  Cedar text can't define template links.
+    :param input_format (optional) 'cedar' (policies are Cedar text) or 'json' (policies are in Cedar's JSON form);
+    dict and list policies are always JSON
+
+    :returns a SplitPolicySource with the static policies, templates, and links, each with its id
+    :raises ValueError: if the policies are invalid or a link doesn't fill its template's slots
+    """
+    if isinstance(policies, (dict, list)):
+        policies = json.dumps(policies)
+        input_format = "json"
+    return SplitPolicySource(json.loads(_internal.split_policy_source(policies, input_format)))
+
+
+class TemplateSuggestion:
+    def __init__(self, suggestion: dict) -> None:
+        super().__init__()
+        self._suggestion = suggestion
+
+    @property
+    def template_id(self) -> str:
+        return self._suggestion['template_id']
+
+    @property
+    def text(self) -> str:
+        """the template's Cedar text"""
+        return self._suggestion['text']
+
+    @property
+    def policy(self) -> dict:
+        """the template in Cedar's JSON form"""
+        return self._suggestion['policy']
+
+    @property
+    def slots(self) -> List[str]:
+        """the template's slots, '?principal' and/or '?resource'"""
+        return self._suggestion['slots']
+
+    @property
+    def links(self) -> List[dict]:
+        """a template link per policy the template replaces, each a dict with its 'templateId', 'newId' (the id
+        of the policy it replaces), and slot 'values', as in the 'templateLinks' of a JSON policy set"""
+        return self._suggestion['links']
+
+    def __getitem__(self, __name: str) -> Any:
+        return getattr(self, __name)
+
+
+def suggest_templates(policies: str, min_cluster_size: int = 2) -> List[TemplateSuggestion]:
+    """Suggest templates to replace static policies that differ only in their principal and resource entities.
+
+    Static policies that are the same but for the entities their principal and resource scopes name are
+    clustered, and each cluster is suggested as a template with a slot for each scope whose entity differs,
+    and a template link per policy.  Annotations aren't compared; a template keeps those its policies share.
+
+    :param policies is a str containing Cedar policies
+    :param min_cluster_size (optional) how many policies a cluster needs to be suggested as a template, at least 2
+
+    :returns a list of TemplateSuggestion, largest cluster first
+    :raises ValueError: if the policies cannot be parsed
+    """
+    return [TemplateSuggestion(suggestion)
+            for suggestion in json.loads(_internal.suggest_templates(policies, min_cluster_size))]
+
+
+class TemplatizedPolicies:
+    def __init__(self, templatized: dict) -> None:
+        super().__init__()
+        self._templatized = templatized
+
+    @property
+    def policy_set(self) -> dict:
+        """the rewritten policies, as a policy set in Cedar's JSON form with 'staticPolicies', 'templates', and
+        'templateLinks'; see split_policy_source"""
+        return self._templatized['policy_set']
+
+    @property
+    def linked(self) -> List[str]:
+        """the ids Cedar assigns the policies rewritten to links, e.g. 'policy0', in the order they appear"""
+        return self._templatized['linked']
+
+    @property
+    def verification(self) -> Optional[dict]:
+        """when requests were given, {'requests': ..., 'equivalent': ..., 'mismatches': [...]}: equivalent is True
+        when the rewritten policies decide every request as the original policies do, and each mismatch has the
+        request's 'index', and the 'original' and 'templatized' outcome, each with its 'decision' and 'reason'"""
+        return self._templatized['verification']
+
+    @property
+    def equivalent(self) -> Optional[bool]:
+        """whether the rewritten policies decide the requests as the original policies do, or None when no
+        requests were given"""
+        verification = self.verification
+        return None if verification is None else verification['equivalent']
+
+    def __getitem__(self, __name: str) -> Any:
+        return getattr(self, __name)
+
+
+def templatize(policies: str,
+               template: Union[str, TemplateSuggestion],
+               requests: Optional[List[dict]] = None,
+               entities: Union[str, List[dict], bytes, os.PathLike, None] = None,
+               schema: Union[str, dict, None] = None,
+               verbose: Optional[bool] = None) -> TemplatizedPolicies:
+    """Rewrite the static policies a template matches to links of it.
+
+    A policy matches when filling the template's slots with the entities of the policy's scope gives the policy,
+    whatever their annotations.  The template's id is its @id annotation, or 'template0', and each policy keeps
+    its @id annotation, or the id Cedar assigns it, as its id in the rewritten policy set, which links can't
+    otherwise annotate.  With requests, the rewritten policies' decisions are checked against the originals'.
+
+    :param policies is a str containing Cedar policies
+    :param template is a str containing one Cedar template, or a TemplateSuggestion from suggest_templates
+    :param requests (optional) a corpus of requests to check the rewritten policies against; see is_authorized
+    :param entities (optional) is a str containing the corpus's entities in JSON format or a list of entity dicts
+    :param schema (optional) is a str containing the schema in JSON format or a schema dict
+    :param verbose (optional) when True will emit verbose logging
+
+    :returns a TemplatizedPolicies with the rewritten policy set, the ids of the policies linked, and, with
+    requests, the verification
+    :raises ValueError: if the policies, template, or corpus are invalid, or two policies have the same @id
+    """
+    if isinstance(template, TemplateSuggestion):
+        template = template.text
+    templatized_str = _internal.templatize(policies,
+                                           template,
+                                           None if requests is None else _to_internal_requests(requests),
+                                           entities,
+                                           _to_schema_str(schema),
+                                           verbose)
+    return TemplatizedPolicies(json.loads(templatized_str))
+
+
+class RoundTripIssue:
+    def __init__(self, issue: dict) -> None:
+        super().__init__()
+        self._issue = issue
+
+    @property
+    def policy_id(self) -> str:
+        return self._issue['policy_id']
+
+    @property
+    def kind(self) -> str:
+        """what's lost: 'comments', 'id', 'annotations', 'json' (the JSON form is written differently),
+        'text' (the policy's meaning changes), or 'conversion' (the policy doesn't convert)"""
+        return self._issue['kind']
+
+    @property
+    def message(self) -> str:
+        return self._issue['message']
+
+    def __getitem__(self, __name: str) -> Any:
+        return getattr(self, __name)
+
+
+class RoundTripReport:
+    def __init__(self, report: dict) -> None:
+        super().__init__()
+        self._report = report
+        self._issues = [RoundTripIssue(issue) for issue in report['issues']]
+
+    @property
+    def lossless(self) -> bool:
+        return self._report['lossless']
+
+    @property
+    def issues(self) -> List[RoundTripIssue]:
+        return self._issues
+
+    def __getitem__(self, __name: str) -> Any:
+        return getattr(self, __name)
+
+
+def check_round_trip(policies: Union[str, dict, List[dict]], input_format: str = "cedar") -> RoundTripReport:
+    """Check that policies convert between Cedar text and Cedar's JSON form (the EST) without loss.
+
+    Cedar text is converted to JSON and back, and JSON to text and back, and the results compared
+    with the originals.  The report lists, per policy, what a conversion loses or changes, such as
+    comments, positional ids, and annotations.
+
+    :param policies is a str containing Cedar policies, or policies in Cedar's JSON form: a policy dict or
+    a list of them, or a json-formatted string of either with input_format='json'
+    :param input_format (optional) 'cedar' (policies are Cedar text) or 'json' (policies are in Cedar's JSON form);
+    dict and list policies are always JSON
+
+    :returns a RoundTripReport, lossless when there are no issues
+    :raises ValueError: if the policies cannot be parsed
+    """
+    if isinstance(policies, (dict, list)):
+        policies = json.dumps(policies)
+        input_format = "json"
+    return RoundTripReport(json.loads(_internal.check_round_trip(policies, input_format)))
+
+
+def schema_to_json_str(cedar_schema: str) -> str:
+    """Translate a schema in Cedar's schema syntax, the human-readable format, to its JSON format.
+
+    Types are resolved as Cedar resolves them: a name is a common type of its namespace, then an entity type of its
+    namespace, then a primitive (Long, String, Bool) or extension type (ipaddr, decimal), and otherwise an entity type
+    of another namespace.
+
+    :param cedar_schema is a str containing the schema in Cedar's schema syntax
+
+    :returns the schema in JSON format, as a str, with the declarations in the order given
+    :raises ValueError: if the schema cannot be parsed or is invalid
+    """
+    return _internal.schema_to_json_str(cedar_schema)
+
+
+def schema_from_json_str(json_schema: Union[str, dict]) -> str:
+    """Translate a schema in JSON format to Cedar's schema syntax, the human-readable format.
+
+    The syntax declares an entity type's shape as a record, so a shape defined as a common type is written as the
+    common type's record.
+
+    :param json_schema is a str containing the schema in JSON format or a schema dict
+
+    :returns the schema in Cedar's schema syntax, with the declarations in the order given
+    :raises ValueError: if the schema is invalid
+    """
+    return _internal.schema_from_json_str(_to_schema_str(json_schema))
+
+
+def schema_docs(schema: Union[str, dict]) -> dict:
+    """Describe a schema as a structured model, for documentation generators and developer portals.
+
+    The model has:
+    * 'entity_types': per entity type, its 'name', 'member_of_types', and 'attributes'
+    * 'actions': per action, its 'name', 'member_of', 'principal_types' and 'resource_types' (None when the schema
+      leaves them unspecified), and 'context' attributes
+    * 'hierarchy': adjacency lists from each entity type ('entity_types') and action ('actions') to its parents
+
+    Attributes are dicts with a 'name', 'type' (e.g. 'Set<Entity<User>>'), and 'required'; record attributes are
+    flattened into rows named by their path, e.g. 'address.city'.  Names are qualified with their namespace and
+    common types are resolved.
+
+    :param schema is a str containing the schema in JSON format or a schema dict
+
+    :returns the model as a dict, with entity types and actions in the order the schema declares them
+    :raises ValueError: if the schema is invalid
+    """
+    return json.loads(_internal.schema_docs(_to_schema_str(schema)))
+
+
+def completion_context(schema: Union[str, dict], cursor_scope: dict) -> dict:
+    """The completions a policy editor may offer at a position in a policy, according to the schema.
+
+    The position is described by the cursor_scope, since a policy being edited is rarely valid:
+    * 'slot': where the cursor is: 'principal', 'action', or 'resource' in the policy's scope, or 'condition'
+    * 'principal_type', 'resource_type', and 'actions' (optional): what the policy's scope constrains them to so far,
+      e.g. 'User' and ['Action::"view"']
+    * 'path' (optional): in a condition, the expression before the cursor's '.', e.g. 'resource.owner' or 'context'
+
+    The completions are:
+    * 'entity_types': in the principal or resource slot, the types the scope's actions apply to; in a condition
+      without a path, every entity type
+    * 'actions': in the action slot or a condition, the actions that apply to the scope's principal and resource types
+    * 'attributes': after a path, the attributes that may follow it, with their 'name', 'type', and 'required'
+    * 'functions': in a condition without a path, the extension functions, e.g. ip; after a path, the methods of its
+      type, e.g. contains for a set; each with its 'name' and 'kind', 'function' or 'method'
+
+    :param schema is a str containing the schema in JSON format or a schema dict
+    :param cursor_scope a dict describing the cursor's position
+
+    :returns a dict of the completions
+    :raises ValueError: if the schema or cursor scope is invalid, or the scope names types or actions that aren't in
+    the schema
+    """
+    return json.loads(_internal.completion_context(_to_schema_str(schema), json.dumps(cursor_scope)))
+
+
+class AuthorizationSurface:
+    def __init__(self, surface: dict) -> None:
+        super().__init__()
+        self._surface = surface
+
+    @property
+    def combinations(self) -> List[dict]:
+        """per combination the schema allows, its 'principal_type', 'action', and 'resource_type', the ids of the
+        'permits' and 'forbids' whose scopes include it, and whether it's 'covered' by any policy"""
+        return self._surface['combinations']
+
+    @property
+    def uncovered(self) -> List[dict]:
+        """the combinations no policy could apply to"""
+        return [combination for combination in self.combinations if not combination['covered']]
+
+    def __getitem__(self, __name: str) -> Any:
+        return getattr(self, __name)
+
+
+def authorization_surface(schema: Union[str, dict], policies: str) -> AuthorizationSurface:
+    """Report every (principal type, action, resource type) combination the schema allows, with the policies that
+    could grant or forbid it.
+
+    Only the policies' scopes are analyzed, so a policy listed for a combination may not apply to every request for
+    it because of its conditions, but a policy that isn't listed never applies.  Templates are listed for each
+    combination their slots could be linked to.  Actions the schema doesn't give principal and resource types have
+    no combinations.
+
+    :param schema is a str containing the schema in JSON format or a schema dict
+    :param policies is a str containing the policies to analyze
+
+    :returns an AuthorizationSurface, listing the combinations in the order the schema declares actions
+    :raises ValueError: if the schema or policies are invalid
+    """
+    return AuthorizationSurface(json.loads(_internal.authorization_surface(_to_schema_str(schema), policies)))
+
+
+def qualify_policies(policies: str, namespace: str) -> str:
+    """Qualify the unqualified entity types and actions referenced by policies with a namespace,
+    e.g. `User::"alice"` becomes `MyApp::User::"alice"`.  Formatting and comments are preserved.
+
+    :param policies is a str containing the policies to qualify
+    :param namespace is the namespace to qualify with, e.g. 'MyApp'
+
+    :returns the qualified policies
+    :raises ValueError: if the policies cannot be parsed or the namespace is invalid
+    """
+    return _internal.qualify_policies(policies, namespace, False)
+
+
+def unqualify_policies(policies: str, namespace: str) -> str:
+    """Strip a namespace from the entity types and actions referenced by policies,
+    e.g. `MyApp::User::"alice"` becomes `User::"alice"`; other namespaces are left alone.
+
+    :param policies is a str containing the policies to unqualify
+    :param namespace is the namespace to strip, e.g. 'MyApp'
+
+    :returns the unqualified policies
+    :raises ValueError: if the policies cannot be parsed or the namespace is invalid
+    """
+    return _internal.qualify_policies(policies, namespace, True)
+
+
+def qualify_entities(entities: Union[str, List[dict]], namespace: str) -> Union[str, List[dict]]:
+    """Qualify the unqualified entity types referenced by entities with a namespace:
+    uids, parents, and entity references (`__entity` or `__expr`) among attributes.
+
+    :param entities is a str containing a JSON list of entities or a list of entity dicts
+    :param namespace is the namespace to qualify with, e.g. 'MyApp'
+
+    :returns the qualified entities, in the same type as provided
+    :raises ValueError: if the entities cannot be parsed or the namespace is invalid
+    """
+    return _qualify_json(_internal.qualify_entities, entities, namespace, False)
+
+
+def unqualify_entities(entities: Union[str, List[dict]], namespace: str) -> Union[str, List[dict]]:
+    """Strip a namespace from the entity types referenced by entities.
+
+    :param entities is a str containing a JSON list of entities or a list of entity dicts
+    :param namespace is the namespace to strip, e.g. 'MyApp'
+
+    :returns the unqualified entities, in the same type as provided
+    :raises ValueError: if the entities cannot be parsed or the namespace is invalid
+    """
+    return _qualify_json(_internal.qualify_entities, entities, namespace, True)
+
+
+def qualify_request(request: dict, namespace: str) -> dict:
+    """Qualify the unqualified entity types of a request's principal, action, resource, and
+    the entity references in its context with a namespace.
+
+    :param request is a dict with the same keys accepted by is_authorized
+    :param namespace is the namespace to qualify with, e.g. 'MyApp'
+
+    :returns a qualified copy of the request
+    :raises ValueError: if an entity uid cannot be parsed or the namespace is invalid
+    """
+    return _qualify_json(_internal.qualify_request, request, namespace, False)
+
+
+def unqualify_request(request: dict, namespace: str) -> dict:
+    """Strip a namespace from the entity types of a request's principal, action, resource, and
+    the entity references in its context.
+
+    :param request is a dict with the same keys accepted by is_authorized
+    :param namespace is the namespace to strip, e.g. 'MyApp'
+
+    :returns an unqualified copy of the request
+    :raises ValueError: if an entity uid cannot be parsed or the namespace is invalid
+    """
+    return _qualify_json(_internal.qualify_request, request, namespace, True)
+
+
+def _qualify_json(qualify, value: Union[str, list, dict], namespace: str, unqualify: bool):
+    if isinstance(value, str):
+        return qualify(value, namespace, unqualify)
+    return json.loads(qualify(json.dumps(value), namespace, unqualify))
+
+
+def rewrite_policies(policies: str, renames: Dict[str, str]) -> str:
+    """Rename entity types and individual entities, such as actions, throughout a policy set.
+
+    References are located by parsing the policies, so strings and comments that merely look like
+    entity references are left alone.  Formatting and comments are preserved.
+
+    :param policies is a str containing the policies to rewrite
+    :param renames is a dict from old to new names.  Keys and values are either entity types,
+        e.g. {"User": "Person"}, or entity uids, e.g. {'Action::"view"': 'Action::"read"'}.
+        Renaming an entity uid takes precedence over renaming its type.
+
+    :returns the rewritten policies
+    :raises ValueError: if the policies cannot be parsed or a rename is invalid
+    """
+    return _internal.rewrite_policies(policies, renames)
+
+
+def rewrite_entities(entities: Union[str, List[dict]], renames: Dict[str, str]) -> Union[str, List[dict]]:
+    """Rename entity types and individual entities throughout a list of entities: in uids,
+    parents, and entity references (`__entity` or `__expr`) among attributes.  Use the same
+    renames with rewrite_policies to apply a schema refactor to data and policies consistently.
+
+    :param entities is a str containing a JSON list of entities or a list of entity dicts
+    :param renames is a dict from old to new entity types or entity uids, as for rewrite_policies
+
+    :returns the rewritten entities, in the same type as provided
+    :raises ValueError: if the entities cannot be parsed or a rename is invalid
+    """
+    if isinstance(entities, str):
+        return _internal.rewrite_entities(entities, renames)
+    return json.loads(_internal.rewrite_entities(json.dumps(entities), renames))
+
+
+class EntityFinding:
+    """A problem found in the entity hierarchy by check_entities"""
+
+    def __init__(self, finding: dict) -> None:
+        super().__init__()
+        self._finding = finding
+
+    @property
+    def kind(self) -> str:
+        """one of 'cycle', 'self_parent', 'duplicate_uid', 'dangling_parent', 'fan_out', or 'invalid_uid'"""
+        return self._finding['kind']
+
+    @property
+    def entity(self) -> str:
+        return self._finding['entity']
+
+    @property
+    def message(self) -> str:
+        return self._finding['message']
+
+    def __getitem__(self, __name: str) -> Any:
+        return getattr(self, __name)
+
+    def __repr__(self) -> str:
+        return f"EntityFinding({self.kind!r}, {self.entity!r}, {self.message!r})"
+
+
+def check_entities(entities: Union[str, List[dict], bytes, os.PathLike],
+                   max_parents: int = 10000) -> List[EntityFinding]:
+    """Check entities for problems in their hierarchy before using them for authorization: hierarchy cycles,
+    entities that are their own parent, duplicate uids, parents that are not in the entities (dangling parents),
+    invalid uids, and entities with an eccentric number of parents.
+
+    :param entities a list of entities or a json-formatted string containing the list of entities
+    :param max_parents (optional) the number of parents above which an entity is reported as a 'fan_out'
+
+    :returns a list of EntityFindings, empty when no problems were found
+    :raises ValueError: if the entities are not a JSON list
+    """
+    findings_str = _internal.check_entities(entities, max_parents)
+    return [EntityFinding(finding) for finding in json.loads(findings_str)]
+
+
+def conformance_report(entities: Union[str, List[dict], bytes, os.PathLike], schema: Union[str, dict]) -> dict:
+    """Summarize how an export of entities conforms to a schema, as a data-quality report.
+
+    Every entity is checked, rather than stopping at the first error as loading entities with a schema does.  The
+    report has the number of 'entities', how many are 'conforming', how many have 'invalid_uids', and
+    'entity_types': per entity type, in the order its first entity appears, its 'entity_type', whether it's
+    'in_schema', its number of 'entities' and 'conforming' entities, the number of entities missing each required
+    attribute ('missing_attributes'), with each undeclared attribute ('extra_attributes'), and with each attribute of
+    the wrong type ('type_mismatches'), and the number with a parent of a type the schema doesn't allow
+    ('invalid_parents').  Attributes of records are named by their path, e.g. 'address.city'.
+
+    :param entities a list of entities or a json-formatted string containing the list of entities
+    :param schema is a str containing the schema in JSON format or a schema dict
+
+    :returns the report as a dict
+    :raises ValueError: if the entities are not a JSON list or the schema is invalid
+    """
+    return json.loads(_internal.conformance_report(entities, _to_schema_str(schema)))
+
+
+def is_in(entities: Union[str, List[dict], bytes, os.PathLike], uid: str, ancestor_uid: str) -> bool:
+    """Determine whether an entity is a member of a group, directly or transitively, with the same semantics as
+    `uid in ancestor_uid` in a Cedar policy, e.g. is_in(entities, 'User::"alice"', 'UserGroup::"admins"').
+
+    :param entities a list of entities or a json-formatted string containing the list of entities
+    :param uid is the uid of the entity, e.g. 'User::"alice"'
+    :param ancestor_uid is the uid of the group, e.g. 'UserGroup::"admins"'
+
+    :returns True if uid is ancestor_uid or one of its descendants
+    :raises ValueError: if the entities or uids cannot be parsed
+    """
+    return _internal.is_in(entities, uid, ancestor_uid)
+
+
+def members_of(entities: Union[str, List[dict], bytes, os.PathLike],
+               group_uid: str,
+               transitive: bool = True) -> List[str]:
+    """List the members of a group.
+
+    :param entities a list of entities or a json-formatted string containing the list of entities
+    :param group_uid is the uid of the group, e.g. 'UserGroup::"admins"'
+    :param transitive (optional) whether to include the members of the group's member groups, and so on; when False,
+    only the entities that list group_uid among their parents are included
+
+    :returns the uids of the members, sorted
+    :raises ValueError: if the entities or uid cannot be parsed
+    """
+    return _internal.members_of(entities, group_uid, transitive)
+
+
+def entity_graph(entities: Union[str, List[dict], bytes, os.PathLike],
+                 output_format: str = "json") -> Union[dict, str]:
+    """Export the entities as a graph, to visualize and sanity-check an entity model with standard graph tooling.
+
+    The graph has a node for each entity, {"id": 'User::"alice"', "type": "User", "missing": False}, in the order
+    the entities appear, followed by a node with "missing" True for each entity that's referred to but isn't in the
+    entities.  It has an edge from each entity to each of its parents, {"source", "target", "kind": "parent",
+    "attribute": None}, and from each entity to each entity an attribute refers to, with "kind" 'attribute' and the
+    attribute's path, e.g. 'owner' or 'department.manager'; references in records and sets are included.
 
-    :param policies is a str containing the policies to be formatted
-    :param line_width (optional) is the desired maximum line length
-    :param indent_width (optional) is the desired indentation width
+    :param entities a list of entities or a json-formatted string containing the list of entities
+    :param output_format (optional) 'json' (default) for the nodes and edges as a dict, or 'dot' for the graph in
+    Graphviz's DOT language as a str, with missing entities and attribute edges dashed
 
-    :returns the formatted policy
-    :raises ValueError: if the input policies cannot be parsed
+    :returns a dict with the 'nodes' and 'edges', or the DOT str
+    :raises ValueError: if the entities or their uids cannot be parsed, or the output format is invalid
     """
-    return _internal.format_policies(policies, line_width, indent_width)
+    graph = _internal.entity_graph(entities, output_format)
+    return graph if output_format == "dot" else json.loads(graph)
@@ -0,0 +1,70 @@
+//! Reduce a policy set to a minimal subset that still reproduces a decision.
+//!
+//! Uses delta debugging (ddmin): repeatedly try subsets and complements of the remaining
+//! policies, keeping any that still reproduce the decision, until removing any single policy
+//! would change it.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use cedar_policy_core::parser::text_to_cst::parse_policies;
+
+/// Find a subset of `items` for which `reproduces` holds, such that removing any one of its
+/// items makes `reproduces` fail.  `reproduces` must hold for all of `items`.
+pub fn minimal_subset<T: Clone>(items: &[T], mut reproduces: impl FnMut(&[T]) -> bool) -> Vec<T> {
+    if reproduces(&[]) {
+        return vec![];
+    }
+    let mut current = items.to_vec();
+    let mut granularity = 2;
+    while current.len() >= 2 {
+        let chunk_len = current.len().div_ceil(granularity);
+        let chunks: Vec<Vec<T>> = current.chunks(chunk_len).map(<[T]>::to_vec).collect();
+
+        let reduced = chunks
+            .iter()
+            .find(|chunk| reproduces(chunk))
+            .cloned()
+            .map(|chunk| (chunk, 2))
+            .or_else(|| {
+                (0..chunks.len())
+                    .map(|skip| complement(&chunks, skip))
+                    .find(|complement| reproduces(complement))
+                    .map(|complement| (complement, (granularity - 1).max(2)))
+            });
+        match reduced {
+            Some((subset, next_granularity)) => {
+                current = subset;
+                granularity = next_granularity;
+            }
+            None if granularity >= current.len() => break,
+            None => granularity = (granularity * 2).min(current.len()),
+        }
+    }
+    current
+}
+
+fn complement<T: Clone>(chunks: &[Vec<T>], skip: usize) -> Vec<T> {
+    chunks
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != skip)
+        .flat_map(|(_, chunk)| chunk.iter().cloned())
+        .collect()
+}
+
+/// The source text of each policy in `policies`, by the id Cedar assigns it when parsing
+/// the policy set, e.g. `policy0`
+pub fn policy_sources(policies: &str) -> Result<HashMap<String, &str>> {
+    let cst = parse_policies(policies).map_err(|errs| anyhow!("failed to parse policies: {:?}", errs))?;
+    Ok(cst
+        .as_inner()
+        .map(|cst| {
+            cst.0
+                .iter()
+                .enumerate()
+                .map(|(i, policy)| (format!("policy{}", i), &policies[policy.info.0.clone()]))
+                .collect()
+        })
+        .unwrap_or_default())
+}
@@ -0,0 +1,171 @@
+//! Caches kept by a `CedarEngine` across calls.
+
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+use anyhow::Result;
+use cedar_policy::{Entities, EntityUid, ParseErrors, Request, Schema};
+use serde_json::{json, Map, Value};
+
+use crate::{RequestArgs, RequestParts};
+
+/// A map holding at most `capacity` entries, evicting the least recently used entry when full
+#[derive(Debug)]
+pub struct LruCache<K, V> {
+    capacity: usize,
+    entries: HashMap<K, (V, u64)>,
+    /// keys by the tick they were last used at, oldest first
+    recency: BTreeMap<u64, K>,
+    tick: u64,
+}
+
+impl<K: Clone + Eq + Hash, V> LruCache<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        LruCache {
+            capacity,
+            entries: HashMap::with_capacity(capacity),
+            recency: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        self.tick += 1;
+        let (value, last_used) = self.entries.get_mut(key)?;
+        let key = self.recency.remove(last_used).expect("cached keys have a recency");
+        self.recency.insert(self.tick, key);
+        *last_used = self.tick;
+        Some(value)
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.tick += 1;
+        if let Some((_, last_used)) = self.entries.remove(&key) {
+            self.recency.remove(&last_used);
+        } else if self.entries.len() >= self.capacity {
+            if let Some((_, oldest)) = self.recency.pop_first() {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.recency.insert(self.tick, key.clone());
+        self.entries.insert(key, (value, self.tick));
+    }
+}
+
+/// Entity uids of the entity store, by their source text, so that requests naming a stored
+/// entity share its type name and id rather than allocating their own
+///
+/// Only uids of stored entities are interned, which bounds the table by the size of the store.
+/// Cedar parses the entity store itself, so interning applies to the uids of requests.
+#[derive(Debug)]
+pub struct UidInterner {
+    uids: HashMap<String, EntityUid>,
+    /// bytes of uid source text held by the table
+    bytes: usize,
+    hits: u64,
+    misses: u64,
+    /// bytes of uid source text that were resolved from the table rather than parsed
+    bytes_deduplicated: usize,
+}
+
+impl UidInterner {
+    pub fn new(entities: &Entities) -> Self {
+        let uids: HashMap<String, EntityUid> = entities
+            .iter()
+            .map(|entity| {
+                let uid = entity.uid();
+                (uid.to_string(), uid)
+            })
+            .collect();
+        let bytes = uids.keys().map(String::len).sum();
+        UidInterner { uids, bytes, hits: 0, misses: 0, bytes_deduplicated: 0 }
+    }
+
+    /// Parse `uid`, sharing the stored entity's uid if there is one
+    pub fn intern(&mut self, uid: &str) -> Result<EntityUid, ParseErrors> {
+        match self.uids.get(uid) {
+            Some(interned) => {
+                self.hits += 1;
+                self.bytes_deduplicated += uid.len();
+                Ok(interned.clone())
+            }
+            None => {
+                self.misses += 1;
+                uid.parse()
+            }
+        }
+    }
+
+    /// Add the interner's statistics to `stats`, e.g. for `CedarEngine.stats()`
+    pub fn add_stats(&self, stats: &mut Map<String, Value>) {
+        stats.insert(String::from("uid_interner_size"), json!(self.uids.len()));
+        stats.insert(String::from("uid_interner_bytes"), json!(self.bytes));
+        stats.insert(String::from("uid_interner_hits"), json!(self.hits));
+        stats.insert(String::from("uid_interner_misses"), json!(self.misses));
+        stats.insert(String::from("uid_interner_bytes_deduplicated"), json!(self.bytes_deduplicated));
+    }
+}
+
+type RequestKey = (Option<String>, Option<String>, Option<String>, Option<String>);
+
+/// Parsed entity uids and contexts of requests, keyed by their principal, action, resource,
+/// and context source, so repeated requests skip uid parsing and context conversion
+#[derive(Debug)]
+pub struct RequestCache {
+    parts: LruCache<RequestKey, RequestParts>,
+    uids: UidInterner,
+    hits: u64,
+    misses: u64,
+}
+
+impl RequestCache {
+    pub fn new(capacity: usize, entities: &Entities) -> Self {
+        RequestCache { parts: LruCache::new(capacity), uids: UidInterner::new(entities), hits: 0, misses: 0 }
+    }
+
+    /// Build the `Request` for `request_args`, reusing its parsed parts if they are cached
+    pub fn get_request(&mut self, request_args: &RequestArgs, schema: Option<&Schema>) -> Result<Request> {
+        if self.parts.capacity() == 0 {
+            return Ok(self.parse_parts(request_args, schema)?.into_request());
+        }
+        let key = (request_args.principal.clone(),
+                   request_args.action.clone(),
+                   request_args.resource.clone(),
+                   request_args.context_json.clone());
+        if let Some(parts) = self.parts.get(&key) {
+            self.hits += 1;
+            return Ok(parts.clone().into_request());
+        }
+        self.misses += 1;
+        let parts = self.parse_parts(request_args, schema)?;
+        self.parts.insert(key, parts.clone());
+        Ok(parts.into_request())
+    }
+
+    fn parse_parts(&mut self, request_args: &RequestArgs, schema: Option<&Schema>) -> Result<RequestParts> {
+        request_args.get_request_parts_with(schema, &mut |uid| self.uids.intern(uid))
+    }
+
+    /// Add the cache's statistics to `stats`, e.g. for `CedarEngine.stats()`
+    pub fn add_stats(&self, stats: &mut Map<String, Value>) {
+        let lookups = self.hits + self.misses;
+        let hit_rate = if lookups == 0 { 0.0 } else { self.hits as f64 / lookups as f64 };
+        stats.insert(String::from("request_cache_capacity"), json!(self.parts.capacity()));
+        stats.insert(String::from("request_cache_size"), json!(self.parts.len()));
+        stats.insert(String::from("request_cache_hits"), json!(self.hits));
+        stats.insert(String::from("request_cache_misses"), json!(self.misses));
+        stats.insert(String::from("request_cache_hit_rate"), json!(hit_rate));
+        self.uids.add_stats(stats);
+    }
+}
@@ -0,0 +1,139 @@
+//! An append-only log of the changes to an engine's state — its initial load, each reload,
+//! whether it succeeded or failed, and each committed update — with the time of each and the
+//! actor the caller names, so an audit can tell who changed the engine's policies, entities, or
+//! schema, and when.
+//!
+//! A change is logged while the engine's state is locked for it, before the new state is visible
+//! to evaluations, so any result decided by a load's inputs has that load in the log already, and
+//! the log's order is the order the changes were applied in.  Entries are never changed or
+//! removed, so the number of entries is the engine's version, which callers can wait for.
+
+use std::str::FromStr;
+use std::sync::{Condvar, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Error, Result};
+use serde::Serialize;
+
+use crate::fingerprint::Fingerprints;
+
+/// What changed the engine's state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// The engine's creation
+    Load,
+    Reload,
+    /// The commit of an update staged with `begin_update`
+    Update,
+}
+
+impl FromStr for ChangeKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "load" => Ok(ChangeKind::Load),
+            "reload" => Ok(ChangeKind::Reload),
+            "update" => Ok(ChangeKind::Update),
+            _ => Err(Error::msg(format!("invalid change kind '{}'; expected one of: load, reload, update", s))),
+        }
+    }
+}
+
+/// What became of a change: the inputs it loaded, or why it failed
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum Outcome {
+    Loaded {
+        num_policies: usize,
+        num_entities: usize,
+        fingerprints: Fingerprints,
+    },
+    Failed {
+        error_code: Option<String>,
+        error: String,
+    },
+}
+
+/// An entry of the log
+#[derive(Debug, Clone, Serialize)]
+pub struct Change {
+    /// the entry's position in the log, from 0
+    pub seq: u64,
+    pub kind: ChangeKind,
+    pub actor: Option<String>,
+    /// seconds since the Unix epoch
+    pub timestamp: f64,
+    pub ok: bool,
+    #[serde(flatten)]
+    pub outcome: Outcome,
+}
+
+/// Which entries to return from the log; an entry is returned when it matches each that is given
+#[derive(Debug, Default)]
+pub struct ChangeQuery {
+    /// entries logged at or after this many seconds since the Unix epoch
+    pub since: Option<f64>,
+    pub actor: Option<String>,
+    pub kind: Option<ChangeKind>,
+}
+
+impl ChangeQuery {
+    fn matches(&self, change: &Change) -> bool {
+        self.since.is_none_or(|since| change.timestamp >= since)
+            && self.actor.as_ref().is_none_or(|actor| change.actor.as_ref() == Some(actor))
+            && self.kind.is_none_or(|kind| change.kind == kind)
+    }
+}
+
+#[derive(Default)]
+pub struct ChangeLog {
+    changes: Mutex<Vec<Change>>,
+    /// notified of each change, for callers waiting for a version
+    appended: Condvar,
+}
+
+impl ChangeLog {
+    pub fn append(&self, kind: ChangeKind, actor: Option<String>, outcome: Outcome) {
+        let mut changes = self.changes.lock().expect("the change log is not poisoned");
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0.0, |elapsed| elapsed.as_secs_f64());
+        let change = Change {
+            seq: changes.len() as u64,
+            kind,
+            actor,
+            timestamp,
+            ok: matches!(outcome, Outcome::Loaded { .. }),
+            outcome,
+        };
+        changes.push(change);
+        self.appended.notify_all();
+    }
+
+    pub fn len(&self) -> u64 {
+        self.changes.lock().expect("the change log is not poisoned").len() as u64
+    }
+
+    /// Wait until the log has `len` entries, or the deadline passes, returning how many it has;
+    /// without a deadline, it doesn't wait
+    pub fn wait_for(&self, len: u64, deadline: Option<Instant>) -> u64 {
+        let mut changes = self.changes.lock().expect("the change log is not poisoned");
+        while (changes.len() as u64) < len {
+            let Some(remaining) = deadline.and_then(|deadline| deadline.checked_duration_since(Instant::now())) else {
+                break;
+            };
+            changes = self.appended.wait_timeout(changes, remaining).expect("the change log is not poisoned").0;
+        }
+        changes.len() as u64
+    }
+
+    /// The entries the query matches, oldest first
+    pub fn query(&self, query: &ChangeQuery) -> Vec<Change> {
+        self.changes.lock()
+            .expect("the change log is not poisoned")
+            .iter()
+            .filter(|change| query.matches(change))
+            .cloned()
+            .collect()
+    }
+}
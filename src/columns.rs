@@ -0,0 +1,49 @@
+//! Contexts given as columns, one list of values per attribute, for batches whose requests all
+//! have contexts of the same shape.  The columns are serialized once in Python, rather than a dict
+//! per request, and each request's context is assembled from them here.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use anyhow::{anyhow, Context as _, Result};
+use serde_json::Value;
+
+use crate::RequestArgs;
+
+/// Give each request the context made of its values in `columns_json`, a JSON object of lists
+/// as long as the batch.  A null value leaves the attribute out of that request's context.
+pub fn apply(columns_json: &str, request_args_vec: &mut [RequestArgs]) -> Result<()> {
+    let columns: BTreeMap<String, Value> = serde_json::from_str(columns_json)
+        .context("failed to parse the context columns")?;
+    let mut column_values = Vec::with_capacity(columns.len());
+    for (name, column) in &columns {
+        let Value::Array(values) = column else {
+            return Err(anyhow!("context column '{}' is not a list", name));
+        };
+        if values.len() != request_args_vec.len() {
+            return Err(anyhow!("context column '{}' has {} values, but there are {} requests",
+                               name, values.len(), request_args_vec.len()));
+        }
+        column_values.push((serde_json::to_string(name)?, values));
+    }
+
+    for (index, request_args) in request_args_vec.iter_mut().enumerate() {
+        if request_args.context_json.is_some() {
+            return Err(anyhow!("request {} has a context, which context columns would replace", index));
+        }
+        let mut context = String::from("{");
+        for (name, values) in &column_values {
+            let value = &values[index];
+            if value.is_null() {
+                continue;
+            }
+            if context.len() > 1 {
+                context.push(',');
+            }
+            write!(context, "{}:{}", name, value).expect("writing to a String doesn't fail");
+        }
+        context.push('}');
+        request_args.context_json = Some(context);
+    }
+    Ok(())
+}
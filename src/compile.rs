@@ -0,0 +1,47 @@
+//! Per-policy compilation diagnostics, for finding the policies that make a large policy set
+//! slow to parse.
+
+use std::str::FromStr;
+use std::time::Instant;
+
+use anyhow::{anyhow, Result};
+use cedar_policy::PolicySet;
+use cedar_policy_core::parser::text_to_cst::parse_policies;
+use serde::Serialize;
+
+/// How long one policy of a policy set took to parse, and how large it is
+#[derive(Debug, Serialize)]
+pub struct PolicyParseDiagnostics {
+    /// The id Cedar assigns the policy when parsing the policy set, e.g. `policy0`
+    policy_id: String,
+    /// The line of the policy set the policy starts on, counting from 1
+    line: usize,
+    size_bytes: usize,
+    parse_duration_micros: u128,
+}
+
+/// Parse each policy of `policies` on its own, timing it; the policies are reported in the order
+/// they appear
+pub fn policy_parse_diagnostics(policies: &str) -> Result<Vec<PolicyParseDiagnostics>> {
+    let cst = parse_policies(policies).map_err(|errs| anyhow!("failed to parse policies: {:?}", errs))?;
+    let Some(cst) = cst.as_inner() else {
+        return Ok(vec![]);
+    };
+    Ok(cst.0
+        .iter()
+        .enumerate()
+        .map(|(i, policy)| {
+            let span = policy.info.0.clone();
+            let source = &policies[span.clone()];
+            let t_parse = Instant::now();
+            // parsed as a policy set, so that templates are parsed too
+            let _ = PolicySet::from_str(source);
+            PolicyParseDiagnostics {
+                policy_id: format!("policy{}", i),
+                line: policies[..span.start].matches('\n').count() + 1,
+                size_bytes: source.len(),
+                parse_duration_micros: t_parse.elapsed().as_micros(),
+            }
+        })
+        .collect())
+}
@@ -0,0 +1,137 @@
+//! Schemas parsed once, with `compile_schema`, and passed to `is_authorized` and its variants,
+//! `validate_policies`, `CedarEngine`, and `EntityStore` in place of the schema's JSON, so a large
+//! schema, which takes milliseconds to parse, is parsed at startup rather than on every call.  Like
+//! a policy set's handle, a schema's handle is immutable, so it may be shared by any number of
+//! calls and threads.  Calls with a handle report the same hashes and fingerprints as calls with
+//! the schema's text would.
+
+// pyo3 0.19's `#[pymethods]` expansion trips this lint on newer compilers
+#![allow(non_local_definitions)]
+
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use cedar_policy::Schema;
+use pyo3::exceptions::PyTypeError;
+use pyo3::prelude::*;
+
+use crate::errors::{self, ErrorCode};
+use crate::{fingerprint, hash_str};
+
+/// A parsed schema, with the text it was parsed from and its hash and fingerprint
+pub struct CompiledSchema {
+    pub text: String,
+    pub schema: Schema,
+    pub hash: u64,
+    pub fingerprint: u64,
+    pub parse_duration: Duration,
+}
+
+impl CompiledSchema {
+    pub fn compile(text: String) -> Result<Self> {
+        let t_parse = Instant::now();
+        let schema = Schema::from_str(&text)
+            .map_err(|e| errors::coded(ErrorCode::Schema, format!("invalid schema: {e}")))?;
+        Ok(CompiledSchema {
+            hash: hash_str(&text),
+            fingerprint: fingerprint::of(&text),
+            text,
+            schema,
+            parse_duration: t_parse.elapsed(),
+        })
+    }
+}
+
+/// A schema compiled by `compile_schema`
+#[pyclass(frozen)]
+pub struct SchemaHandle {
+    compiled: Arc<CompiledSchema>,
+}
+
+#[pymethods]
+impl SchemaHandle {
+    /// The fingerprint of the schema's text, as reported in the results of a `CedarEngine` with it
+    fn fingerprint(&self) -> String {
+        format!("{:016x}", self.compiled.fingerprint)
+    }
+
+    /// How long the schema took to parse when it was compiled
+    fn parse_duration_micros(&self) -> u128 {
+        self.compiled.parse_duration.as_micros()
+    }
+}
+
+/// Parse the schema, in JSON form, into a handle that can be passed in place of its text
+#[pyfunction]
+pub fn compile_schema(py: Python<'_>, schema: String) -> PyResult<SchemaHandle> {
+    // the text is kept to locate the error in, should there be one
+    let compiled = py.allow_threads(|| CompiledSchema::compile(schema.clone()))
+        .map_err(|e| errors::locate(errors::value_error(ErrorCode::Schema, errors::message(&e)), &schema))?;
+    Ok(SchemaHandle { compiled: Arc::new(compiled) })
+}
+
+/// The schema of a call: its text, or a compiled schema
+#[derive(Clone)]
+pub enum SchemaSource {
+    Text(String),
+    Compiled(Arc<CompiledSchema>),
+}
+
+impl SchemaSource {
+    /// The schema, parsing it unless it was compiled already
+    pub fn parse(&self) -> Result<Schema> {
+        match self {
+            SchemaSource::Text(text) => Schema::from_str(text)
+                .map_err(|e| errors::coded(ErrorCode::Schema, format!("invalid schema: {e}"))),
+            SchemaSource::Compiled(compiled) => Ok(compiled.schema.clone()),
+        }
+    }
+
+    /// The schema's JSON text
+    pub fn text(&self) -> &str {
+        match self {
+            SchemaSource::Text(text) => text,
+            SchemaSource::Compiled(compiled) => &compiled.text,
+        }
+    }
+
+    /// The hash of the schema's text, as `JsonSource::content_hash` has it
+    pub fn hash(&self) -> u64 {
+        match self {
+            SchemaSource::Text(text) => hash_str(text),
+            SchemaSource::Compiled(compiled) => compiled.hash,
+        }
+    }
+
+    pub fn fingerprint(&self) -> u64 {
+        match self {
+            SchemaSource::Text(text) => fingerprint::of(text),
+            SchemaSource::Compiled(compiled) => compiled.fingerprint,
+        }
+    }
+}
+
+impl From<serde_json::Value> for SchemaSource {
+    /// A JSON string is the schema's text, and any other value the schema itself, e.g. a batch
+    /// document's `"schema"`, which is kept as its text
+    fn from(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::String(text) => SchemaSource::Text(text),
+            value => SchemaSource::Text(value.to_string()),
+        }
+    }
+}
+
+impl<'a> FromPyObject<'a> for SchemaSource {
+    fn extract(ob: &'a PyAny) -> PyResult<Self> {
+        if let Ok(handle) = ob.extract::<PyRef<SchemaHandle>>() {
+            return Ok(SchemaSource::Compiled(handle.compiled.clone()));
+        }
+        ob.extract().map(SchemaSource::Text).map_err(|_| {
+            PyTypeError::new_err(format!("schema must be a str or a SchemaHandle, not {}",
+                                         ob.get_type().name().unwrap_or("unknown")))
+        })
+    }
+}
@@ -0,0 +1,234 @@
+//! What a policy editor may offer at a position in a policy: the entity types, actions,
+//! attributes, and extension functions valid there, according to the schema.
+//!
+//! The position is described by a cursor scope rather than found by parsing the policy being
+//! edited, which is rarely valid mid-edit: the slot the cursor is in, what the policy's scope has
+//! constrained so far, and, in a condition, the expression being completed, e.g.
+//! `resource.owner`.  The names in the scope are parsed with Cedar's parser, so a name the editor
+//! offers is one evaluation accepts.
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use cedar_policy::{EntityTypeName, EntityUid};
+use serde::{Deserialize, Serialize};
+
+use crate::schema::{Action, Attribute, SchemaModel};
+
+/// The slot of a policy the cursor is in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Slot {
+    Principal,
+    Action,
+    Resource,
+    /// A `when` or `unless` condition
+    Condition,
+}
+
+/// Where the cursor is, and what the policy's scope has constrained so far
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CursorScope {
+    slot: Slot,
+    principal_type: Option<String>,
+    resource_type: Option<String>,
+    /// The actions of the policy's action scope
+    #[serde(default)]
+    actions: Vec<String>,
+    /// In a condition, the expression before the cursor's `.`, e.g. `resource.owner`
+    path: Option<String>,
+}
+
+/// An extension function or method
+#[derive(Debug, Serialize)]
+pub struct Function {
+    name: &'static str,
+    /// `function`, called as `ip("10.0.0.1")`, or `method`, called as `x.contains(y)`
+    kind: &'static str,
+}
+
+/// An attribute that may follow the cursor's `.`
+#[derive(Debug, Serialize)]
+pub struct AttributeCompletion {
+    name: String,
+    #[serde(rename = "type")]
+    type_name: String,
+    required: bool,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct CompletionContext {
+    entity_types: Vec<String>,
+    actions: Vec<String>,
+    attributes: Vec<AttributeCompletion>,
+    functions: Vec<Function>,
+}
+
+const FUNCTIONS: &[&str] = &["ip", "decimal"];
+const SET_METHODS: &[&str] = &["contains", "containsAll", "containsAny"];
+const IPADDR_METHODS: &[&str] = &["isIpv4", "isIpv6", "isLoopback", "isMulticast", "isInRange"];
+const DECIMAL_METHODS: &[&str] = &["lessThan", "lessThanOrEqual", "greaterThan", "greaterThanOrEqual"];
+
+/// What an expression may be followed by: the attributes of an entity type, action context, or
+/// record, whose rows are under `prefix`, or the methods of a value's type
+enum Target<'m> {
+    Attributes { rows: &'m [Attribute], prefix: Vec<String> },
+    Value(&'m str),
+}
+
+/// The completions valid at the cursor, given the schema in JSON form
+pub fn completion_context(schema: &str, cursor_scope: &str) -> Result<CompletionContext> {
+    let model = SchemaModel::from_json(schema)?;
+    let scope: CursorScope = serde_json::from_str(cursor_scope).map_err(|e| anyhow!("invalid cursor scope: {e}"))?;
+    let principal_type = scope.principal_type.as_deref().map(|name| entity_type(&model, name)).transpose()?;
+    let resource_type = scope.resource_type.as_deref().map(|name| entity_type(&model, name)).transpose()?;
+    let scoped_actions = scope.actions.iter()
+        .map(|action| {
+            let uid = EntityUid::from_str(action).map_err(|e| anyhow!("invalid action {action}: {e}"))?.to_string();
+            model.actions.iter()
+                .find(|candidate| candidate.name == uid)
+                .ok_or_else(|| anyhow!("the action {uid} is not in the schema"))
+        })
+        .collect::<Result<Vec<&Action>>>()?;
+
+    // the actions the cursor's policy may be for, given the rest of its scope
+    let actions: Vec<&Action> = model.actions.iter()
+        .filter(|action| scoped_actions.is_empty() || scoped_actions.iter().any(|scoped| scoped.name == action.name))
+        .filter(|action| applies(&action.principal_types, principal_type.as_deref()))
+        .filter(|action| applies(&action.resource_types, resource_type.as_deref()))
+        .collect();
+    let all_entity_types = || model.entity_types.iter().map(|entity_type| entity_type.name.clone()).collect();
+    let mut completions = CompletionContext::default();
+    let principal_types = |actions: &[&Action]| applicable_types(&model, actions, |action| &action.principal_types);
+    let resource_types = |actions: &[&Action]| applicable_types(&model, actions, |action| &action.resource_types);
+    match scope.slot {
+        Slot::Principal => completions.entity_types = principal_types(&actions),
+        Slot::Resource => completions.entity_types = resource_types(&actions),
+        Slot::Action => completions.actions = actions.iter().map(|action| action.name.clone()).collect(),
+        Slot::Condition => match scope.path.as_deref().map(str::trim).filter(|path| !path.is_empty()) {
+            None => {
+                completions.entity_types = all_entity_types();
+                completions.actions = actions.iter().map(|action| action.name.clone()).collect();
+                completions.functions = functions(FUNCTIONS, "function");
+            }
+            Some(path) => {
+                let principal_types = principal_type.map_or_else(|| principal_types(&actions), |name| vec![name]);
+                let resource_types = resource_type.map_or_else(|| resource_types(&actions), |name| vec![name]);
+                let mut segments = path.trim_end_matches('.').split('.').map(str::trim);
+                let mut targets: Vec<Target> = match segments.next() {
+                    Some("principal") => entity_targets(&model, &principal_types),
+                    Some("resource") => entity_targets(&model, &resource_types),
+                    Some("context") => actions.iter()
+                        .map(|action| Target::Attributes { rows: &action.context, prefix: vec![] })
+                        .collect(),
+                    Some("action") => vec![],
+                    _ => return Err(anyhow!("invalid path '{path}'; expected one starting with principal, action, \
+                                             resource, or context")),
+                };
+                for segment in segments {
+                    targets = targets.iter().flat_map(|target| follow(&model, target, segment)).collect();
+                }
+                complete(&targets, &mut completions);
+            }
+        },
+    }
+    Ok(completions)
+}
+
+/// The qualified name of an entity type in the schema
+fn entity_type(model: &SchemaModel, name: &str) -> Result<String> {
+    let name = EntityTypeName::from_str(name).map_err(|e| anyhow!("invalid entity type {name}: {e}"))?.to_string();
+    if model.entity_types.iter().any(|entity_type| entity_type.name == name) {
+        Ok(name)
+    } else {
+        Err(anyhow!("the entity type {name} is not in the schema"))
+    }
+}
+
+/// Whether an action applies to `entity_type`, when it's known; types the schema leaves
+/// unspecified apply to any
+fn applies(types: &Option<Vec<String>>, entity_type: Option<&str>) -> bool {
+    match (types, entity_type) {
+        (Some(types), Some(entity_type)) => types.iter().any(|name| name == entity_type),
+        _ => true,
+    }
+}
+
+/// The entity types, in schema order, that the actions apply to in one position
+fn applicable_types(model: &SchemaModel,
+                    actions: &[&Action],
+                    types: impl Fn(&Action) -> &Option<Vec<String>>) -> Vec<String> {
+    model.entity_types.iter()
+        .map(|entity_type| &entity_type.name)
+        .filter(|name| actions.iter().any(|action| types(action).as_ref().is_none_or(|types| types.contains(name))))
+        .cloned()
+        .collect()
+}
+
+fn entity_targets<'m>(model: &'m SchemaModel, names: &[String]) -> Vec<Target<'m>> {
+    model.entity_types.iter()
+        .filter(|entity_type| names.contains(&entity_type.name))
+        .map(|entity_type| Target::Attributes { rows: &entity_type.attributes, prefix: vec![] })
+        .collect()
+}
+
+/// What the attribute `name` of a target is, if it has one
+fn follow<'m>(model: &'m SchemaModel, target: &Target<'m>, name: &str) -> Option<Target<'m>> {
+    let Target::Attributes { rows, prefix } = target else {
+        return None;
+    };
+    let row = children(rows, prefix).find(|row| row.path[prefix.len()] == name)?;
+    if row.type_name == "Record" {
+        return Some(Target::Attributes { rows, prefix: row.path.clone() });
+    }
+    match row.type_name.strip_prefix("Entity<").and_then(|name| name.strip_suffix('>')) {
+        Some(entity_type) => entity_targets(model, &[entity_type.to_owned()]).pop(),
+        None => Some(Target::Value(&row.type_name)),
+    }
+}
+
+/// The attributes of the targets, and their methods, without duplicates
+fn complete(targets: &[Target], completions: &mut CompletionContext) {
+    for target in targets {
+        match target {
+            Target::Attributes { rows, prefix } => {
+                for row in children(rows, prefix) {
+                    let name = &row.path[prefix.len()];
+                    if !completions.attributes.iter().any(|attribute| &attribute.name == name) {
+                        completions.attributes.push(AttributeCompletion {
+                            name: name.clone(),
+                            type_name: row.type_name.clone(),
+                            required: row.required,
+                        });
+                    }
+                }
+            }
+            Target::Value(type_name) => {
+                let methods = if type_name.starts_with("Set<") {
+                    SET_METHODS
+                } else {
+                    match *type_name {
+                        "Extension<ipaddr>" => IPADDR_METHODS,
+                        "Extension<decimal>" => DECIMAL_METHODS,
+                        _ => &[],
+                    }
+                };
+                for method in functions(methods, "method") {
+                    if !completions.functions.iter().any(|function| function.name == method.name) {
+                        completions.functions.push(method);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The rows of the attributes directly under `prefix`
+fn children<'r: 'p, 'p>(rows: &'r [Attribute], prefix: &'p [String]) -> impl Iterator<Item = &'r Attribute> + 'p {
+    rows.iter().filter(move |row| row.path.len() == prefix.len() + 1 && row.path.starts_with(prefix))
+}
+
+fn functions(names: &[&'static str], kind: &'static str) -> Vec<Function> {
+    names.iter().map(|&name| Function { name, kind }).collect()
+}
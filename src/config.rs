@@ -0,0 +1,155 @@
+//! Deployment-wide defaults, read from `CEDARPY_*` environment variables when the module is
+//! initialized, so operators can tune cedarpy without changing the code that calls it.
+//!
+//! | variable | default |
+//! |---|---|
+//! | `CEDARPY_ENTITY_VALIDATION` | `strict` |
+//! | `CEDARPY_UNKNOWN_ENTITIES` | `ignore` |
+//! | `CEDARPY_POLICY_ERRORS` | `skip` |
+//! | `CEDARPY_VERBOSE` | `false` |
+//! | `CEDARPY_THREADS` | `1` |
+//! | `CEDARPY_MAX_THREADS` | the number of CPUs |
+//! | `CEDARPY_REQUEST_CACHE_SIZE` | `1024` |
+//! | `CEDARPY_GENERATE_CORRELATION_IDS` | `false` |
+//! | `CEDARPY_MAX_INPUT_BYTES` | `1073741824` (1 GiB) |
+//!
+//! The defaults apply when a caller doesn't pass the corresponding argument.
+
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use anyhow::{Context, Error, Result};
+use serde::Serialize;
+
+use crate::{EntityValidation, PolicyErrors, UnknownEntities};
+
+static DEFAULTS: OnceLock<Defaults> = OnceLock::new();
+
+#[derive(Debug, Serialize)]
+pub struct Defaults {
+    #[serde(serialize_with = "serialize_debug")]
+    pub entity_validation: EntityValidation,
+    #[serde(serialize_with = "serialize_debug")]
+    pub unknown_entities: UnknownEntities,
+    #[serde(serialize_with = "serialize_debug")]
+    pub policy_errors: PolicyErrors,
+    pub verbose: bool,
+    /// the number of threads batches are evaluated on, of the `max_threads` the pool has
+    pub threads: usize,
+    /// the size of the thread pool every batch shares, and so the most threads a batch uses
+    pub max_threads: usize,
+    pub request_cache_size: usize,
+    /// whether requests without a correlation id are given a generated one
+    pub generate_correlation_ids: bool,
+    /// the most bytes a compressed input may decompress to
+    pub max_input_bytes: usize,
+}
+
+/// Serialize an option by its lowercased variant name, i.e. as it's passed to cedarpy
+fn serialize_debug<T: std::fmt::Debug, S: serde::Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("{:?}", value).to_lowercase())
+}
+
+impl Default for Defaults {
+    fn default() -> Self {
+        Defaults {
+            entity_validation: EntityValidation::default(),
+            unknown_entities: UnknownEntities::default(),
+            policy_errors: PolicyErrors::default(),
+            verbose: false,
+            threads: 1,
+            max_threads: std::thread::available_parallelism().map_or(1, usize::from),
+            request_cache_size: 1024,
+            generate_correlation_ids: false,
+            max_input_bytes: 1 << 30,
+        }
+    }
+}
+
+impl Defaults {
+    fn from_env(var: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        fn parse<T: FromStr>(value: Option<String>, name: &str, default: T) -> Result<T>
+            where T::Err: Into<Error> {
+            match value {
+                None => Ok(default),
+                Some(value) => value.trim().parse().map_err(Into::into).with_context(|| format!("invalid {name}")),
+            }
+        }
+        fn parse_bool(value: Option<String>, name: &str, default: bool) -> Result<bool> {
+            match value.map(|value| value.trim().to_lowercase()).as_deref() {
+                None => Ok(default),
+                Some("1" | "true" | "yes" | "on") => Ok(true),
+                Some("0" | "false" | "no" | "off" | "") => Ok(false),
+                Some(other) => Err(Error::msg(format!("invalid {name} '{other}'; expected one of: true, false"))),
+            }
+        }
+        let builtin = Defaults::default();
+        let verbose = parse_bool(var("CEDARPY_VERBOSE"), "CEDARPY_VERBOSE", builtin.verbose)?;
+        let threads = parse(var("CEDARPY_THREADS"), "CEDARPY_THREADS", builtin.threads)?;
+        if threads == 0 {
+            return Err(Error::msg("invalid CEDARPY_THREADS: there must be at least 1 thread"));
+        }
+        let max_threads = parse(var("CEDARPY_MAX_THREADS"), "CEDARPY_MAX_THREADS", builtin.max_threads)?;
+        if max_threads == 0 {
+            return Err(Error::msg("invalid CEDARPY_MAX_THREADS: there must be at least 1 thread"));
+        }
+        Ok(Defaults {
+            entity_validation: parse(var("CEDARPY_ENTITY_VALIDATION"), "CEDARPY_ENTITY_VALIDATION",
+                                     builtin.entity_validation)?,
+            unknown_entities: parse(var("CEDARPY_UNKNOWN_ENTITIES"), "CEDARPY_UNKNOWN_ENTITIES",
+                                    builtin.unknown_entities)?,
+            policy_errors: parse(var("CEDARPY_POLICY_ERRORS"), "CEDARPY_POLICY_ERRORS", builtin.policy_errors)?,
+            verbose,
+            threads,
+            max_threads,
+            request_cache_size: parse(var("CEDARPY_REQUEST_CACHE_SIZE"), "CEDARPY_REQUEST_CACHE_SIZE",
+                                      builtin.request_cache_size)?,
+            generate_correlation_ids: parse_bool(var("CEDARPY_GENERATE_CORRELATION_IDS"),
+                                                 "CEDARPY_GENERATE_CORRELATION_IDS",
+                                                 builtin.generate_correlation_ids)?,
+            max_input_bytes: parse(var("CEDARPY_MAX_INPUT_BYTES"), "CEDARPY_MAX_INPUT_BYTES",
+                                   builtin.max_input_bytes)?,
+        })
+    }
+}
+
+/// Read the defaults from the environment; called when the module is initialized, so that an
+/// invalid variable fails the import rather than a later call
+pub fn init() -> Result<()> {
+    let defaults = Defaults::from_env(|name| std::env::var(name).ok())?;
+    let _ = DEFAULTS.set(defaults);
+    Ok(())
+}
+
+/// The defaults, as read by `init`
+pub fn defaults() -> &'static Defaults {
+    DEFAULTS.get_or_init(Defaults::default)
+}
+
+/// An option whose default can be configured by an environment variable
+pub trait Configured: Sized {
+    fn configured() -> Self;
+}
+
+impl Configured for EntityValidation {
+    fn configured() -> Self {
+        defaults().entity_validation
+    }
+}
+
+impl Configured for UnknownEntities {
+    fn configured() -> Self {
+        defaults().unknown_entities
+    }
+}
+
+impl Configured for PolicyErrors {
+    fn configured() -> Self {
+        defaults().policy_errors
+    }
+}
+
+/// The verbosity of a call, defaulting to `CEDARPY_VERBOSE`
+pub fn verbose(verbose: Option<bool>) -> bool {
+    verbose.unwrap_or(defaults().verbose)
+}
@@ -0,0 +1,228 @@
+//! A data-quality report of how well an export of entities conforms to a schema, summarized per
+//! entity type, for teams syncing entities from upstream systems.
+//!
+//! Cedar stops validating entities at the first error; the report instead checks every entity
+//! and counts how often each attribute is missing, unexpected, or of the wrong type.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::permissive::parse_entity_uid;
+use crate::schema::{Attribute, EntityType, SchemaModel};
+
+/// How the entities of one type conform to the schema
+#[derive(Debug, Serialize)]
+pub struct EntityTypeConformance {
+    entity_type: String,
+    /// Whether the schema declares the entity type, or it's an action of the schema
+    in_schema: bool,
+    entities: usize,
+    /// The number of entities with no problems
+    conforming: usize,
+    /// The number of entities missing each required attribute
+    missing_attributes: BTreeMap<String, usize>,
+    /// The number of entities with each attribute the schema doesn't declare
+    extra_attributes: BTreeMap<String, usize>,
+    /// The number of entities with each attribute of the wrong type
+    type_mismatches: BTreeMap<String, usize>,
+    /// The number of entities with a parent of a type the schema doesn't allow
+    invalid_parents: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConformanceReport {
+    entities: usize,
+    conforming: usize,
+    /// The number of entities whose uid is invalid, which aren't reported by type
+    invalid_uids: usize,
+    /// The entity types, in the order their first entity appears
+    entity_types: Vec<EntityTypeConformance>,
+}
+
+/// Report how `entities`, in Cedar's JSON entity format, conform to `schema`, in JSON form
+pub fn conformance_report(entities: &Value, schema: &str) -> Result<ConformanceReport> {
+    let entities = entities.as_array().ok_or_else(|| anyhow!("entities must be a JSON array"))?;
+    let model = SchemaModel::from_json(schema)?;
+    let entity_types: HashMap<&str, &EntityType> = model.entity_types.iter()
+        .map(|entity_type| (entity_type.name.as_str(), entity_type))
+        .collect();
+    let actions: HashSet<&str> = model.actions.iter().map(|action| action.name.as_str()).collect();
+
+    let mut report = ConformanceReport {
+        entities: entities.len(),
+        conforming: 0,
+        invalid_uids: 0,
+        entity_types: vec![],
+    };
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for entity in entities {
+        let Some(uid) = entity.get("uid").and_then(parse_entity_uid) else {
+            report.invalid_uids += 1;
+            continue;
+        };
+        let type_name = uid.type_name().to_string();
+        let position = *positions.entry(type_name.clone()).or_insert_with(|| {
+            report.entity_types.push(EntityTypeConformance {
+                in_schema: entity_types.contains_key(type_name.as_str()),
+                entity_type: type_name.clone(),
+                entities: 0,
+                conforming: 0,
+                missing_attributes: BTreeMap::new(),
+                extra_attributes: BTreeMap::new(),
+                type_mismatches: BTreeMap::new(),
+                invalid_parents: 0,
+            });
+            report.entity_types.len() - 1
+        });
+        let conformance = &mut report.entity_types[position];
+        conformance.entities += 1;
+        let conforms = match entity_types.get(type_name.as_str()) {
+            Some(entity_type) => check_entity(entity, entity_type, conformance),
+            None if actions.contains(uid.to_string().as_str()) => {
+                conformance.in_schema = true;
+                true
+            }
+            None => false,
+        };
+        if conforms {
+            conformance.conforming += 1;
+            report.conforming += 1;
+        }
+    }
+    Ok(report)
+}
+
+/// Check an entity of a type the schema declares, counting its problems; whether it has none
+fn check_entity(entity: &Value, entity_type: &EntityType, conformance: &mut EntityTypeConformance) -> bool {
+    let empty = serde_json::Map::new();
+    let attrs = entity.get("attrs").and_then(Value::as_object).unwrap_or(&empty);
+    let mut conforms = true;
+    for problem in attribute_problems(attrs, &entity_type.attributes, entity_type.additional_attributes) {
+        let (counts, name) = match problem {
+            AttributeProblem::Missing(attribute) => (&mut conformance.missing_attributes, attribute.name.clone()),
+            AttributeProblem::Extra(name) => (&mut conformance.extra_attributes, name),
+            AttributeProblem::TypeMismatch(attribute) => (&mut conformance.type_mismatches, attribute.name.clone()),
+        };
+        *counts.entry(name).or_default() += 1;
+        conforms = false;
+    }
+
+    let parents = entity.get("parents").and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+    let invalid_parent = parents.iter().any(|parent| {
+        parse_entity_uid(parent).is_none_or(|parent| {
+            !entity_type.member_of_types.contains(&parent.type_name().to_string())
+        })
+    });
+    if invalid_parent {
+        conformance.invalid_parents += 1;
+        conforms = false;
+    }
+    conforms
+}
+
+/// A problem with an attribute of a record whose attributes the schema declares
+pub(crate) enum AttributeProblem<'a> {
+    /// A required attribute is missing
+    Missing(&'a Attribute),
+    /// The path of an attribute the schema doesn't declare
+    Extra(String),
+    TypeMismatch(&'a Attribute),
+}
+
+/// The problems of a record's attributes, `attrs`, with the `declared` attributes
+pub(crate) fn attribute_problems<'a>(attrs: &serde_json::Map<String, Value>,
+                                     declared: &'a [Attribute],
+                                     additional_attributes: bool) -> Vec<AttributeProblem<'a>> {
+    let mut problems = vec![];
+    for attribute in declared {
+        match lookup(attrs, &attribute.path) {
+            Lookup::Found(value) if !has_type(value, &attribute.type_name) => {
+                problems.push(AttributeProblem::TypeMismatch(attribute))
+            }
+            Lookup::Missing if attribute.required => problems.push(AttributeProblem::Missing(attribute)),
+            _ => {}
+        }
+    }
+    if !additional_attributes {
+        problems.extend(extra_attributes(attrs, declared).into_iter().map(AttributeProblem::Extra));
+    }
+    problems
+}
+
+enum Lookup<'a> {
+    Found(&'a Value),
+    Missing,
+    /// A record on the way to the attribute is missing or isn't a record, which is reported
+    /// for that record
+    Unreachable,
+}
+
+fn lookup<'a>(attrs: &'a serde_json::Map<String, Value>, path: &[String]) -> Lookup<'a> {
+    let Some((last, records)) = path.split_last() else { return Lookup::Unreachable };
+    let mut record = attrs;
+    for name in records {
+        match record.get(name).and_then(Value::as_object) {
+            Some(inner) if !is_escape(inner) => record = inner,
+            _ => return Lookup::Unreachable,
+        }
+    }
+    record.get(last).map_or(Lookup::Missing, Lookup::Found)
+}
+
+/// The paths of the attributes, and of the attributes of records, that the schema doesn't declare
+fn extra_attributes(attrs: &serde_json::Map<String, Value>, declared: &[Attribute]) -> Vec<String> {
+    let declared: HashMap<&[String], &str> = declared.iter()
+        .map(|attribute| (attribute.path.as_slice(), attribute.type_name.as_str()))
+        .collect();
+    let mut extra = vec![];
+    let mut pending: Vec<(Vec<String>, &serde_json::Map<String, Value>)> = vec![(vec![], attrs)];
+    while let Some((prefix, record)) = pending.pop() {
+        for (name, value) in record {
+            let path = [prefix.as_slice(), std::slice::from_ref(name)].concat();
+            match declared.get(path.as_slice()) {
+                None => extra.push(path.join(".")),
+                Some(&"Record") => {
+                    if let Some(inner) = value.as_object().filter(|inner| !is_escape(inner)) {
+                        pending.push((path, inner));
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+    }
+    extra
+}
+
+/// Whether an object is an escape, e.g. `__entity`, rather than a record
+fn is_escape(object: &serde_json::Map<String, Value>) -> bool {
+    object.len() == 1 && object.keys().next().is_some_and(|key| key.starts_with("__"))
+}
+
+/// Whether an attribute value has the type the schema model names, e.g. `Set<Entity<User>>`;
+/// values given as `__expr` expressions aren't evaluated and are assumed to
+fn has_type(value: &Value, type_name: &str) -> bool {
+    if value.get("__expr").is_some() {
+        return true;
+    }
+    let inner = |prefix: &str| type_name.strip_prefix(prefix).and_then(|inner| inner.strip_suffix('>'));
+    if let Some(element) = inner("Set<") {
+        return value.as_array().is_some_and(|values| values.iter().all(|value| has_type(value, element)));
+    }
+    if let Some(entity_type) = inner("Entity<") {
+        return parse_entity_uid(value).is_some_and(|uid| uid.type_name().to_string() == entity_type);
+    }
+    if inner("Extension<").is_some() {
+        return value.get("__extn").is_some();
+    }
+    match type_name {
+        "String" => value.is_string(),
+        "Long" => value.is_i64(),
+        "Boolean" => value.is_boolean(),
+        "Record" => value.as_object().is_some_and(|object| !is_escape(object)),
+        // a type the model doesn't know how to check
+        _ => true,
+    }
+}
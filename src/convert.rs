@@ -0,0 +1,389 @@
+//! Conversion of policies and templates between Cedar text and Cedar's JSON form (the EST), and
+//! checks that the conversions round-trip.
+//!
+//! Text is parsed with Cedar's lossless EST, which keeps the structure of the source; JSON is
+//! converted to text through Cedar's AST, whose rendering parenthesizes subexpressions and
+//! writes attribute accesses as `resource["owner"]`.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Context, Result};
+use cedar_policy_core::ast::{PolicyID, Template};
+use cedar_policy_core::est;
+use cedar_policy_core::parser::text_to_cst::parse_policies;
+use cedar_policy_core::parser::{parse_policy_template_to_est_and_ast, parse_policyset_to_ests_and_pset};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::bisect::policy_sources;
+use crate::errors::{self, ErrorCode};
+
+/// The policies and templates of a policy set in JSON form, in the order they appear, by the id
+/// Cedar assigns each when parsing the policy set, e.g. `policy0`
+pub fn text_to_ests(text: &str) -> Result<Vec<(String, est::Policy)>> {
+    let (ests, _) = parse_policyset_to_ests_and_pset(text)
+        .map_err(|e| errors::coded(ErrorCode::PolicyParse, format!("policy parse errors:\n{}", e)))?;
+    let mut ests: Vec<(String, est::Policy)> = ests.into_iter().map(|(id, est)| (id.to_string(), est)).collect();
+    ests.sort_by_key(|(id, _)| position(id));
+    Ok(ests)
+}
+
+/// The position of a policy in its policy set, from the id Cedar assigns it
+fn position(id: &str) -> usize {
+    id.trim_start_matches("policy").parse().unwrap_or(usize::MAX)
+}
+
+/// Parse a policy or template in JSON form
+pub fn parse_est(policy: Value) -> Result<est::Policy> {
+    serde_json::from_value(policy)
+        .map_err(|e| errors::coded(ErrorCode::PolicyParse, format!("policy parse errors:\ninvalid JSON policy: {e}")))
+}
+
+/// Cedar text for a policy or template in JSON form
+pub fn est_to_text(id: &str, est: est::Policy) -> Result<String> {
+    Ok(est_to_template(id, est)?.to_string())
+}
+
+fn est_to_template(id: &str, est: est::Policy) -> Result<Template> {
+    est.try_into_ast_template(Some(PolicyID::from_string(id)))
+        .map_err(|e| errors::coded(ErrorCode::PolicyParse, format!("policy parse errors:\n{id} is invalid: {e}")))
+}
+
+/// Policies in JSON form, either one policy object or an array of them, with the ids
+/// `policy0`, `policy1`, ... in the order given; the array may instead hold `{"id", "policy"}`
+/// entries, as `text_to_json` returns, to give each policy its id
+pub fn parse_ests(policies: &str) -> Result<Vec<(String, est::Policy)>> {
+    json_policies(policies)?
+        .into_iter()
+        .enumerate()
+        .map(|(i, policy)| parse_entry(i, policy))
+        .collect()
+}
+
+fn json_policies(policies: &str) -> Result<Vec<Value>> {
+    let policies: Value = serde_json::from_str(policies).context("failed to parse the JSON policies")?;
+    match policies {
+        Value::Array(policies) => Ok(policies),
+        policy @ Value::Object(_) => Ok(vec![policy]),
+        _ => Err(anyhow!("JSON policies must be a policy object or an array of them")),
+    }
+}
+
+/// The `i`th policy of an array of JSON policies, either a policy or an `{"id", "policy"}` entry
+fn parse_entry(i: usize, policy: Value) -> Result<(String, est::Policy)> {
+    let (id, policy) = match policy {
+        Value::Object(mut entry) if entry.contains_key("policy") && !entry.contains_key("effect") => {
+            let id = match entry.remove("id") {
+                Some(Value::String(id)) => id,
+                None => format!("policy{i}"),
+                Some(other) => return Err(anyhow!("invalid id {other} for JSON policy {i}; expected a string")),
+            };
+            (id, entry.remove("policy").unwrap_or_default())
+        }
+        policy => (format!("policy{i}"), policy),
+    };
+    let est = parse_est(policy).with_context(|| id.clone())?;
+    Ok((id, est))
+}
+
+/// A policy or template in both forms, by its id
+#[derive(Debug, Serialize)]
+pub struct ConvertedPolicy {
+    pub id: String,
+    pub text: String,
+    pub policy: Value,
+}
+
+/// Convert Cedar text to JSON, keeping each policy's source text and the id Cedar assigns it
+pub fn text_to_json(text: &str) -> Result<Vec<ConvertedPolicy>> {
+    let ests = text_to_ests(text)?;
+    let sources = policy_sources(text)?;
+    ests.into_iter()
+        .map(|(id, est)| Ok(ConvertedPolicy {
+            text: sources.get(&id).copied().unwrap_or_default().to_owned(),
+            policy: serde_json::to_value(&est)
+                .context(errors::context(ErrorCode::Serialization, "failed to serialize the JSON policy"))?,
+            id,
+        }))
+        .collect()
+}
+
+/// Convert policies in JSON form, as `parse_ests` takes them, to Cedar text, keeping their ids
+pub fn json_to_text(policies: &str) -> Result<Vec<ConvertedPolicy>> {
+    parse_ests(policies)?
+        .into_iter()
+        .map(|(id, est)| converted(id, est))
+        .collect()
+}
+
+fn converted(id: String, est: est::Policy) -> Result<ConvertedPolicy> {
+    Ok(ConvertedPolicy {
+        policy: serde_json::to_value(&est)
+            .context(errors::context(ErrorCode::Serialization, "failed to serialize the JSON policy"))?,
+        text: est_to_text(&id, est)?,
+        id,
+    })
+}
+
+/// A template-linked policy: the template it links and the entities its slots are filled with
+#[derive(Debug, Serialize)]
+pub struct LinkDefinition {
+    id: String,
+    template_id: String,
+    /// the entity for each slot, e.g. `?principal`, as given
+    values: Map<String, Value>,
+}
+
+/// The static policies, templates, and template links of a policy source
+#[derive(Debug, Default, Serialize)]
+pub struct SplitPolicySource {
+    static_policies: Vec<ConvertedPolicy>,
+    templates: Vec<ConvertedPolicy>,
+    links: Vec<LinkDefinition>,
+}
+
+impl SplitPolicySource {
+    fn add(&mut self, policy: ConvertedPolicy) {
+        if slots(&policy.policy).is_empty() {
+            self.static_policies.push(policy);
+        } else {
+            self.templates.push(policy);
+        }
+    }
+
+    fn add_link(&mut self, link: Value) -> Result<()> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase", deny_unknown_fields)]
+        struct TemplateLink {
+            template_id: String,
+            new_id: String,
+            values: Map<String, Value>,
+        }
+        let link: TemplateLink = serde_json::from_value(link).context("invalid template link")?;
+        let template = self.templates.iter()
+            .find(|template| template.id == link.template_id)
+            .ok_or_else(|| {
+                anyhow!("template link {} links {}, which isn't a template", link.new_id, link.template_id)
+            })?;
+        let mut expected = slots(&template.policy);
+        let mut given: Vec<&str> = link.values.keys().map(String::as_str).collect();
+        expected.sort_unstable();
+        given.sort_unstable();
+        if expected != given {
+            return Err(anyhow!("template link {} gives values for {}; template {} has the slots {}",
+                               link.new_id, given.join(", "), link.template_id, expected.join(", ")));
+        }
+        self.links.push(LinkDefinition { id: link.new_id, template_id: link.template_id, values: link.values });
+        Ok(())
+    }
+}
+
+/// The slots of a policy in JSON form, which may only appear in its scope
+fn slots(policy: &Value) -> Vec<&str> {
+    ["principal", "resource"]
+        .iter()
+        .filter_map(|var| policy.get(var)?.get("slot")?.as_str())
+        .collect()
+}
+
+/// Split Cedar text into its static policies and templates; text can't define template links
+pub fn split_text(text: &str) -> Result<SplitPolicySource> {
+    let mut split = SplitPolicySource::default();
+    for policy in text_to_json(text)? {
+        split.add(policy);
+    }
+    Ok(split)
+}
+
+/// Split policies in JSON form into static policies, templates, and template links.  The
+/// policies are either an array as `parse_ests` takes it, which may also hold template links,
+/// `{"templateId", "newId", "values"}`, or a policy set object with `staticPolicies` and
+/// `templates` objects of policies by id and a `templateLinks` array
+pub fn split_json(policies: &str) -> Result<SplitPolicySource> {
+    let mut split = SplitPolicySource::default();
+    let mut links = vec![];
+    let value: Value = serde_json::from_str(policies).context("failed to parse the JSON policies")?;
+    let is_policy_set = |set: &Map<String, Value>| {
+        ["staticPolicies", "templates", "templateLinks"].iter().any(|key| set.contains_key(*key))
+    };
+    match value {
+        Value::Object(mut set) if is_policy_set(&set) => {
+            for section in ["staticPolicies", "templates"] {
+                let Some(section_policies) = set.remove(section) else { continue };
+                let Value::Object(section_policies) = section_policies else {
+                    return Err(anyhow!("{section} must be an object of policies by id"));
+                };
+                for (id, policy) in section_policies {
+                    let est = parse_est(policy).with_context(|| id.clone())?;
+                    let policy = converted(id, est)?;
+                    if (section == "templates") == slots(&policy.policy).is_empty() {
+                        return Err(anyhow!("{} is in {section}, but {}", policy.id,
+                                           if section == "templates" { "has no slots" } else { "has slots" }));
+                    }
+                    split.add(policy);
+                }
+            }
+            match set.remove("templateLinks") {
+                Some(Value::Array(set_links)) => links = set_links,
+                None => {}
+                Some(_) => return Err(anyhow!("templateLinks must be an array of template links")),
+            }
+            if let Some(key) = set.keys().next() {
+                return Err(anyhow!(
+                    "unexpected {key} in the JSON policy set; expected staticPolicies, templates, or templateLinks"));
+            }
+        }
+        _ => {
+            for (i, policy) in json_policies(policies)?.into_iter().enumerate() {
+                if policy.get("templateId").is_some() {
+                    links.push(policy);
+                } else {
+                    let (id, est) = parse_entry(i, policy)?;
+                    split.add(converted(id, est)?);
+                }
+            }
+        }
+    }
+    for link in links {
+        split.add_link(link)?;
+    }
+    Ok(split)
+}
+
+/// Something a conversion between text and JSON loses or changes
+#[derive(Debug, Serialize)]
+pub struct RoundTripIssue {
+    policy_id: String,
+    /// `comments`, `id`, `annotations`, `json`, `text`, or `conversion`
+    kind: &'static str,
+    message: String,
+}
+
+impl RoundTripIssue {
+    fn new(policy_id: &str, kind: &'static str, message: String) -> Self {
+        RoundTripIssue { policy_id: policy_id.to_owned(), kind, message }
+    }
+}
+
+/// Convert Cedar text to JSON and back, and JSON to text and back, reporting what changes
+pub fn check_text_round_trip(text: &str) -> Result<Vec<RoundTripIssue>> {
+    let ests = text_to_ests(text)?;
+    let sources = policy_sources(text)?;
+    let regions = policy_regions(text)?;
+    let mut issues = vec![];
+    for (i, (id, est)) in ests.into_iter().enumerate() {
+        let source = sources.get(&id).copied().unwrap_or_default();
+        if regions.get(i).is_some_and(|region| has_comment(region)) {
+            issues.push(RoundTripIssue::new(&id, "comments", format!(
+                "{id} has comments, which its JSON form doesn't keep")));
+        }
+        let (_, template) = parse_policy_template_to_est_and_ast(Some(id.clone()), source)
+            .map_err(|e| errors::coded(ErrorCode::PolicyParse, format!("policy parse errors:\n{}", e)))?;
+        check_round_trip(&id, est, Some(template), &mut issues);
+    }
+    Ok(issues)
+}
+
+/// Convert JSON policies to text and back, reporting what changes
+pub fn check_json_round_trip(policies: &str) -> Result<Vec<RoundTripIssue>> {
+    let mut issues = vec![];
+    for (id, est) in parse_ests(policies)? {
+        check_round_trip(&id, est, None, &mut issues);
+    }
+    Ok(issues)
+}
+
+fn check_round_trip(id: &str, est: est::Policy, template: Option<Template>, issues: &mut Vec<RoundTripIssue>) {
+    let json = serde_json::to_value(&est).expect("policies serialize to JSON");
+    if !annotations(&json).contains_key("id") {
+        issues.push(RoundTripIssue::new(id, "id", format!(
+            "{id} is identified by its position in the policy set, which its JSON form doesn't keep; \
+             an @id annotation would keep it")));
+    }
+    let text = match est_to_text(id, est.clone()) {
+        Ok(text) => text,
+        Err(e) => {
+            issues.push(RoundTripIssue::new(id, "conversion", format!("{e:#}")));
+            return;
+        }
+    };
+    let (est_back, template_back) = match parse_policy_template_to_est_and_ast(Some(id.to_owned()), &text) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            issues.push(RoundTripIssue::new(id, "conversion", format!(
+                "the text converted from {id}'s JSON form doesn't parse: {e}")));
+            return;
+        }
+    };
+    let template = template.unwrap_or_else(|| est_to_template(id, est).expect("the policy converted to text"));
+    let json_back = serde_json::to_value(&est_back).expect("policies serialize to JSON");
+    if annotations(&json) != annotations(&json_back) {
+        issues.push(RoundTripIssue::new(id, "annotations", format!(
+            "{id}'s annotations changed converting its JSON form to text and back")));
+    }
+    // the AST's equality includes source locations, so the ASTs are compared by their rendering
+    if template.to_string() != template_back.to_string() {
+        issues.push(RoundTripIssue::new(id, "text", format!(
+            "{id} means something different after converting it to JSON and back to text")));
+    } else if normalize(json) != normalize(json_back) {
+        // the same meaning, written differently, e.g. `unless { c }` as `when { !(c) }`
+        issues.push(RoundTripIssue::new(id, "json", format!(
+            "{id}'s JSON form is written differently after converting it to text and back")));
+    }
+}
+
+fn annotations(policy: &Value) -> HashMap<String, Value> {
+    policy.get("annotations")
+        .and_then(Value::as_object)
+        .map(|annotations| annotations.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+        .unwrap_or_default()
+}
+
+/// A policy's JSON form without its annotations, which are compared on their own, and without
+/// `when { true }` conditions, which the AST renders for a policy without conditions
+fn normalize(mut policy: Value) -> Value {
+    if let Value::Object(fields) = &mut policy {
+        fields.remove("annotations");
+        if let Some(Value::Array(conditions)) = fields.get_mut("conditions") {
+            conditions.retain(|condition| *condition != json!({"kind": "when", "body": {"Value": true}}));
+        }
+    }
+    policy
+}
+
+/// The text of each policy along with the comments around it: from the start of the text for the
+/// first policy, or the end of the previous policy, to the end of the policy's line
+fn policy_regions(text: &str) -> Result<Vec<&str>> {
+    let cst = parse_policies(text).map_err(|errs| anyhow!("failed to parse policies: {:?}", errs))?;
+    let Some(cst) = cst.as_inner() else {
+        return Ok(vec![]);
+    };
+    let mut start = 0;
+    Ok(cst.0
+        .iter()
+        .map(|policy| {
+            let end = policy.info.0.end;
+            let end = text[end..].find('\n').map_or(text.len(), |newline| end + newline);
+            let region = &text[start.min(end)..end];
+            start = end;
+            region
+        })
+        .collect())
+}
+
+/// Whether policy source has a `//` comment outside of its string literals
+fn has_comment(source: &str) -> bool {
+    let mut chars = source.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if in_string => {
+                chars.next();
+            }
+            '"' => in_string = !in_string,
+            '/' if !in_string && chars.peek() == Some(&'/') => return true,
+            _ => {}
+        }
+    }
+    false
+}
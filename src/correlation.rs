@@ -0,0 +1,21 @@
+//! Correlation ids generated for the requests that lack one, so that every result can be joined
+//! to its audit record even when a caller forgot to supply ids.
+//!
+//! Ids are UUIDv7s (RFC 9562), made by the `uuid` crate: a millisecond Unix timestamp followed by
+//! random bits from the operating system, so they sort by the millisecond they were generated in.
+
+use uuid::Uuid;
+
+use crate::RequestArgs;
+
+/// Give each request without a correlation id a generated one
+pub fn fill_missing(requests: &mut [RequestArgs]) {
+    for request in requests.iter_mut().filter(|request| request.correlation_id.is_none()) {
+        request.correlation_id = Some(uuid_v7());
+    }
+}
+
+/// A new UUIDv7, formatted as usual, e.g. `01890a5d-ac96-774b-bcce-b302099a8057`
+pub fn uuid_v7() -> String {
+    Uuid::now_v7().to_string()
+}
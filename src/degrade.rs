@@ -0,0 +1,92 @@
+//! What an engine answers once a reload of its policies, schema, or entities fails, so that the
+//! outage policy is set once, on the engine, rather than ad hoc in every caller.
+//!
+//! Failing open is limited to the actions of policies annotated `@fail_open`, which are meant to
+//! be the low-risk ones, e.g. reading a public profile.
+
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::time::Instant;
+
+use anyhow::{Error, Result};
+use cedar_policy::{ActionConstraint, Decision, Entities, EntityUid, PolicySet, Response};
+
+use crate::errors::ErrorCode;
+use crate::{AuthzOptions, AuthzResponse, RequestArgs};
+
+/// How an engine decides requests after a reload fails
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Degradation {
+    /// Deny every request, with the failure as the cause
+    #[default]
+    FailClosed,
+    /// Allow requests for the actions of the policies annotated `@fail_open`, and deny the rest
+    FailOpen,
+    /// Keep deciding requests with the inputs of the last successful load, with a warning
+    ServeStale,
+}
+
+impl FromStr for Degradation {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "fail_closed" => Ok(Degradation::FailClosed),
+            "fail_open" => Ok(Degradation::FailOpen),
+            "serve_stale" => Ok(Degradation::ServeStale),
+            _ => Err(Error::msg(format!(
+                "invalid degradation mode '{}'; expected one of: fail_closed, fail_open, serve_stale", s))),
+        }
+    }
+}
+
+/// A failed reload
+#[derive(Debug, Clone)]
+pub struct Failure {
+    pub cause: String,
+    pub at: Instant,
+}
+
+impl Failure {
+    /// The warning for a response decided with the inputs the reload failed to replace
+    pub fn stale_warning(&self) -> String {
+        format!("serving stale policies: a reload failed {}s ago: {}", self.at.elapsed().as_secs(), self.cause)
+    }
+
+    fn unavailable(&self) -> String {
+        format!("policy store unavailable: a reload failed {}s ago: {}", self.at.elapsed().as_secs(), self.cause)
+    }
+}
+
+/// The actions named by the action scopes of the policies annotated `@fail_open`; a policy for
+/// any action designates none
+pub fn fail_open_actions(policy_set: &PolicySet) -> HashSet<EntityUid> {
+    policy_set.policies()
+        .filter(|policy| policy.annotation("fail_open").is_some_and(|value| value != "false"))
+        .flat_map(|policy| match policy.action_constraint() {
+            ActionConstraint::Any => vec![],
+            ActionConstraint::In(actions) => actions,
+            ActionConstraint::Eq(action) => vec![action],
+        })
+        .collect()
+}
+
+/// Decide a request without evaluating it, for an engine whose reload failed: allow it when
+/// failing open for its action, or one of its action groups, and deny it otherwise
+pub fn degraded_response(request: &RequestArgs,
+                         failure: &Failure,
+                         fail_open: Option<(&HashSet<EntityUid>, &Entities)>,
+                         options: &AuthzOptions) -> AuthzResponse {
+    let action: Option<EntityUid> = request.action.as_deref().and_then(|action| action.parse().ok());
+    let allowed = fail_open.zip(action).is_some_and(|((actions, entities), action)| {
+        actions.contains(&action)
+            || entities.ancestors(&action).into_iter().flatten().any(|group| actions.contains(group))
+    });
+    let decision = if allowed { Decision::Allow } else { Decision::Deny };
+    let response = Response::new(decision, HashSet::new(), HashSet::from([failure.unavailable()]));
+    let mut ans = AuthzResponse::new(response, HashMap::new(), request.correlation_id.clone(), options);
+    // a decision made without the policies mustn't outlive the outage
+    ans.cache_ttl = Some(0);
+    ans.diagnostics_code = ErrorCode::PolicyStoreUnavailable;
+    ans
+}
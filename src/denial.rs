@@ -0,0 +1,152 @@
+//! An approximate classification of a denial in the schema's terms, to point the user at the kind
+//! of fix it needs: a forbid to reconsider, a permit to write for a combination of types no permit
+//! covers, a scope to widen to the request's entities, or a condition that isn't satisfied.
+//!
+//! Only the policies' scopes are analyzed, as in the authorization surface: a permit covers the
+//! request's types when its scope could include entities of the principal's and resource's types,
+//! by the schema's membership of types, and the request's action, by the schema's action groups.
+
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use cedar_policy::{ActionConstraint, Diagnostics, Effect, Entities, EntityUid, PolicySet, PrincipalConstraint,
+                   ResourceConstraint};
+use serde::Serialize;
+
+use crate::schema::SchemaModel;
+use crate::RequestArgs;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DenialClass {
+    /// A forbid was satisfied
+    Forbidden,
+    /// No permit's scope can ever cover the principal's type, the action, and the resource's type
+    Structural,
+    /// A permit's scope covers the types, but none's includes the request's entities
+    UnmatchedScope,
+    /// A permit's scope includes the request, but its conditions weren't satisfied or errored
+    Conditional,
+}
+
+/// A principal or resource constraint of a policy's scope
+enum Constraint {
+    Any,
+    In(EntityUid),
+    Eq(EntityUid),
+}
+
+impl From<PrincipalConstraint> for Constraint {
+    fn from(constraint: PrincipalConstraint) -> Self {
+        match constraint {
+            PrincipalConstraint::Any => Constraint::Any,
+            PrincipalConstraint::In(uid) => Constraint::In(uid),
+            PrincipalConstraint::Eq(uid) => Constraint::Eq(uid),
+        }
+    }
+}
+
+impl From<ResourceConstraint> for Constraint {
+    fn from(constraint: ResourceConstraint) -> Self {
+        match constraint {
+            ResourceConstraint::Any => Constraint::Any,
+            ResourceConstraint::In(uid) => Constraint::In(uid),
+            ResourceConstraint::Eq(uid) => Constraint::Eq(uid),
+        }
+    }
+}
+
+impl Constraint {
+    /// Whether the constraint could include entities of `entity_type`, whose ancestor types are
+    /// `ancestor_types`
+    fn covers_type(&self, entity_type: &str, ancestor_types: &HashSet<&str>) -> bool {
+        match self {
+            Constraint::Any => true,
+            Constraint::Eq(uid) => uid.type_name().to_string() == entity_type,
+            Constraint::In(uid) => ancestor_types.contains(uid.type_name().to_string().as_str()),
+        }
+    }
+
+    fn includes(&self, uid: &EntityUid, entities: &Entities) -> bool {
+        match self {
+            Constraint::Any => true,
+            Constraint::Eq(constraint) => constraint == uid,
+            Constraint::In(constraint) => is_in(uid, constraint, entities),
+        }
+    }
+}
+
+/// The class of a denial of the request, or `None` when its principal, action, or resource isn't
+/// an entity uid
+pub fn denial_class(request_args: &RequestArgs,
+                    diagnostics: &Diagnostics,
+                    policy_set: &PolicySet,
+                    entities: &Entities,
+                    model: &SchemaModel) -> Option<DenialClass> {
+    // the reasons for a denial are the forbids that were satisfied
+    if diagnostics.reason().next().is_some() {
+        return Some(DenialClass::Forbidden);
+    }
+    let uid = |uid: &Option<String>| uid.as_deref().and_then(|uid| EntityUid::from_str(uid).ok());
+    let (principal, action, resource) = (uid(&request_args.principal)?, uid(&request_args.action)?,
+                                         uid(&request_args.resource)?);
+    let type_parents: HashMap<&str, &[String]> = model.entity_types.iter()
+        .map(|entity_type| (entity_type.name.as_str(), entity_type.member_of_types.as_slice()))
+        .collect();
+    let action_parents: HashMap<&str, &[String]> = model.actions.iter()
+        .map(|action| (action.name.as_str(), action.member_of.as_slice()))
+        .collect();
+    let (principal_type, resource_type) = (principal.type_name().to_string(), resource.type_name().to_string());
+    let principal_ancestor_types = ancestors(&principal_type, &type_parents);
+    let resource_ancestor_types = ancestors(&resource_type, &type_parents);
+    let action_name = action.to_string();
+    let action_ancestors = ancestors(&action_name, &action_parents);
+
+    let mut class = DenialClass::Structural;
+    for permit in policy_set.policies().filter(|policy| policy.effect() == Effect::Permit) {
+        let principal_constraint = Constraint::from(permit.principal_constraint());
+        let resource_constraint = Constraint::from(permit.resource_constraint());
+        let action_constraint = permit.action_constraint();
+        let covers_action = match &action_constraint {
+            ActionConstraint::Any => true,
+            ActionConstraint::Eq(uid) => uid == &action,
+            ActionConstraint::In(uids) => uids.iter().any(|uid| action_ancestors.contains(uid.to_string().as_str())),
+        };
+        if !(covers_action
+            && principal_constraint.covers_type(&principal_type, &principal_ancestor_types)
+            && resource_constraint.covers_type(&resource_type, &resource_ancestor_types)) {
+            continue;
+        }
+        let includes_action = match &action_constraint {
+            ActionConstraint::Any => true,
+            ActionConstraint::Eq(uid) => uid == &action,
+            ActionConstraint::In(uids) => uids.iter().any(|uid| is_in(&action, uid, entities)),
+        };
+        if includes_action
+            && principal_constraint.includes(&principal, entities)
+            && resource_constraint.includes(&resource, entities) {
+            return Some(DenialClass::Conditional);
+        }
+        class = DenialClass::UnmatchedScope;
+    }
+    Some(class)
+}
+
+/// Whether `uid in ancestor`, which, unlike `Entities::is_ancestor_of`, holds for the uid itself
+fn is_in(uid: &EntityUid, ancestor: &EntityUid, entities: &Entities) -> bool {
+    uid == ancestor || entities.is_ancestor_of(ancestor, uid)
+}
+
+/// The ancestors of a node, including itself, of a hierarchy of edges from nodes to parents
+fn ancestors<'a>(node: &'a str, parents: &HashMap<&'a str, &'a [String]>) -> HashSet<&'a str> {
+    let mut seen = HashSet::from([node]);
+    let mut pending = vec![node];
+    while let Some(node) = pending.pop() {
+        for parent in parents.get(node).copied().into_iter().flatten() {
+            if seen.insert(parent.as_str()) {
+                pending.push(parent.as_str());
+            }
+        }
+    }
+    seen
+}
@@ -0,0 +1,203 @@
+//! Separation-of-duty checks: pairs of actions, toxic combinations, that no principal may both be
+//! allowed on the same resource, e.g. submitting and approving the same expense report.
+//!
+//! Each pair is checked over the entities: every principal and resource among them that both
+//! actions apply to is evaluated with each action, in one batch, and a principal allowed both on a
+//! resource is a violation.  The schema's `appliesTo`, when there's a schema, and the types the
+//! caller gives narrow which entities are principals and resources; otherwise every entity but the
+//! actions is both.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{anyhow, Error};
+use cedar_policy::{Decision, EntityUid};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::compiled_schema::SchemaSource;
+use crate::config::{self, Configured};
+use crate::errors::{self, ErrorCode};
+use crate::permissive::parse_entity_uid;
+use crate::policy_set::PolicySource;
+use crate::schema::{Action, SchemaModel};
+use crate::{AuthzOptions, EntityValidation, JsonSource, PolicyErrors, PreparedInputs, RequestArgs, UnknownEntities};
+
+/// Which requests each conflict is checked with
+pub struct Scope {
+    /// The entity types principals may have, or None for any
+    pub principal_types: Option<Vec<String>>,
+    /// The entity types resources may have, or None for any
+    pub resource_types: Option<Vec<String>>,
+    /// The context of every request, as a JSON object
+    pub context: Option<String>,
+    /// The most requests the check may evaluate
+    pub max_requests: usize,
+}
+
+/// A principal allowed both actions of a conflict on a resource, and the policies that allowed each
+#[derive(Debug, Serialize)]
+pub struct Violation {
+    principal: String,
+    resource: String,
+    actions: [String; 2],
+    reasons: [Vec<String>; 2],
+}
+
+#[derive(Debug, Serialize)]
+pub struct SeparationOfDutyReport {
+    /// Whether no principal is allowed both actions of any conflict on the same resource
+    holds: bool,
+    requests_checked: usize,
+    /// How many requests couldn't be evaluated or encountered errors evaluating policies
+    errored: usize,
+    /// The violations, by conflict, then principal and resource in the order of the entities
+    violations: Vec<Violation>,
+}
+
+/// Check that no principal among the entities is allowed both actions of any of the `conflicts`,
+/// pairs of action uids, on the same resource
+pub fn check_separation_of_duty(conflicts: &[[String; 2]],
+                                policies: &str,
+                                entities: JsonSource,
+                                schema: Option<SchemaSource>,
+                                scope: &Scope,
+                                verbose: bool) -> Result<SeparationOfDutyReport, Vec<Error>> {
+    let uids = entity_uids(&entities)?;
+    let model = schema_model(&schema)?;
+
+    // every request any conflict needs, each evaluated once, and each conflict's principals and resources
+    let mut requests: Vec<RequestArgs> = vec![];
+    let mut request_index: HashMap<(&EntityUid, &str, &EntityUid), usize> = HashMap::new();
+    let mut checks = vec![];
+    for conflict in conflicts {
+        let principals = applies_to(&uids, &model, conflict, &scope.principal_types, |action| &action.principal_types)?;
+        let resources = applies_to(&uids, &model, conflict, &scope.resource_types, |action| &action.resource_types)?;
+        for principal in &principals {
+            for resource in &resources {
+                for action in conflict {
+                    request_index.entry((*principal, action.as_str(), *resource)).or_insert_with(|| {
+                        requests.push(RequestArgs {
+                            principal: Some(principal.to_string()),
+                            action: Some(action.clone()),
+                            resource: Some(resource.to_string()),
+                            context_json: scope.context.clone(),
+                            correlation_id: None,
+                            parsed_uids: None,
+                        });
+                        requests.len() - 1
+                    });
+                }
+            }
+            if requests.len() > scope.max_requests {
+                return Err(vec![anyhow!("the conflicts need more than max_requests ({}) requests over the \
+                                         entities; narrow the principal or resource types", scope.max_requests)]);
+            }
+        }
+        checks.push((conflict, principals, resources));
+    }
+
+    let prepared = PreparedInputs::prepare(&PolicySource::Text(policies.to_owned()),
+                                           entities,
+                                           schema,
+                                           EntityValidation::configured(),
+                                           verbose)?;
+    let options = AuthzOptions {
+        include_diagnostics: true,
+        include_metrics: false,
+        entity_validation: EntityValidation::configured(),
+        unknown_entities: UnknownEntities::configured(),
+        policy_errors: PolicyErrors::configured(),
+        validate_request: false,
+        include_policy_results: false,
+        include_denial_class: false,
+        include_attributes: None,
+        include_missing_entities: false,
+        generate_correlation_ids: false,
+        threads: config::defaults().threads,
+    };
+    let requests_checked = requests.len();
+    let results = prepared.evaluate(requests, Duration::ZERO, &options, None, verbose);
+    let errored = results.iter()
+        .filter(|ans| ans.as_ref().map_or(true, |ans| ans.diagnostics().is_some_and(|d| d.errors().next().is_some())))
+        .count();
+    let allowed_by = |principal, action: &String, resource| -> Option<Vec<String>> {
+        let ans = results[request_index[&(principal, action.as_str(), resource)]].as_ref().ok()?;
+        if ans.decision() != Decision::Allow {
+            return None;
+        }
+        let mut reason: Vec<String> = ans.response.diagnostics().reason().map(ToString::to_string).collect();
+        reason.sort_by_key(|id| (id.len(), id.clone()));
+        Some(reason)
+    };
+
+    let mut violations = vec![];
+    for (conflict, principals, resources) in checks {
+        for principal in &principals {
+            for resource in &resources {
+                let [first, second] = conflict;
+                let (Some(first_reason), Some(second_reason)) =
+                    (allowed_by(principal, first, resource), allowed_by(principal, second, resource)) else {
+                    continue;
+                };
+                violations.push(Violation {
+                    principal: principal.to_string(),
+                    resource: resource.to_string(),
+                    actions: conflict.clone(),
+                    reasons: [first_reason, second_reason],
+                });
+            }
+        }
+    }
+    Ok(SeparationOfDutyReport { holds: violations.is_empty(), requests_checked, errored, violations })
+}
+
+/// The uids of the entities, other than actions
+pub(crate) fn entity_uids(entities: &JsonSource) -> Result<Vec<EntityUid>, Vec<Error>> {
+    match entities.to_value() {
+        Ok(Value::Array(entities)) => Ok(entities.iter()
+            .filter_map(|entity| parse_entity_uid(entity.get("uid")?))
+            .filter(|uid| !is_action(uid))
+            .collect()),
+        Ok(_) => Err(vec![errors::coded(ErrorCode::Entities, "failed to parse entities: expected a list of entities")]),
+        Err(e) => Err(vec![errors::coded(ErrorCode::Entities, format!("failed to parse entities: {e}"))]),
+    }
+}
+
+/// The schema's entity types and actions, when there's a schema
+pub(crate) fn schema_model(schema: &Option<SchemaSource>) -> Result<Option<SchemaModel>, Vec<Error>> {
+    schema.as_ref()
+        .map(|schema| SchemaModel::from_json(schema.text()))
+        .transpose()
+        .map_err(|e| vec![e])
+}
+
+/// The uids of the `types`, or any, that all the `actions` apply to as principals or resources, as
+/// `action_types` gives them, when there's a schema
+pub(crate) fn applies_to<'a>(uids: &'a [EntityUid],
+                             model: &Option<SchemaModel>,
+                             actions: &[String],
+                             types: &Option<Vec<String>>,
+                             action_types: fn(&Action) -> &Option<Vec<String>>)
+                             -> Result<Vec<&'a EntityUid>, Vec<Error>> {
+    let mut allowed = vec![types.clone()];
+    for action in actions {
+        EntityUid::from_str(action).map_err(|e| vec![anyhow!("invalid action {action}: {e}")])?;
+        let Some(model) = model else { continue };
+        let declared = model.actions.iter().find(|declared| declared.name == *action)
+            .ok_or_else(|| vec![anyhow!("action {action} is not declared in the schema")])?;
+        allowed.push(action_types(declared).clone());
+    }
+    Ok(uids.iter()
+        .filter(|uid| {
+            let type_name = uid.type_name().to_string();
+            allowed.iter().flatten().all(|types| types.contains(&type_name))
+        })
+        .collect())
+}
+
+fn is_action(uid: &EntityUid) -> bool {
+    let type_name = uid.type_name().to_string();
+    type_name == "Action" || type_name.ends_with("::Action")
+}
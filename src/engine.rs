@@ -0,0 +1,929 @@
+//! A long-lived authorization engine that prepares its policies, schema, and entities once.
+
+// pyo3 0.19's `#[pymethods]` expansion trips this lint on newer compilers
+#![allow(non_local_definitions)]
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex, RwLock, RwLockWriteGuard};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Error};
+use cedar_policy::{Decision, EntityUid, PolicyId, Response};
+use pyo3::prelude::*;
+use serde_json::{json, Map};
+
+use crate::cache::RequestCache;
+use crate::changelog::{ChangeKind, ChangeLog, ChangeQuery, Outcome};
+use crate::compile::{policy_parse_diagnostics, PolicyParseDiagnostics};
+use crate::compiled_schema::SchemaSource;
+use crate::config;
+use crate::correlation;
+use crate::degrade::{degraded_response, fail_open_actions, Degradation, Failure};
+use crate::errors::{self, ErrorCode};
+use crate::extension_allowlist::ExtensionAllowlist;
+use crate::fingerprint::{self, Fingerprints};
+use crate::guardrail::Guardrails;
+use crate::hooks::{Event, Hooks};
+use crate::layers;
+use crate::limit::{ConcurrencyLimit, Lane};
+use crate::policy_set::PolicySource;
+use crate::projection::AttributeProjection;
+use crate::response;
+use crate::resume::ResumeToken;
+use crate::store::EntitySource;
+use crate::transform::ResponseTransform;
+use crate::ttl::{decision_ttl, parse_ttl, policy_ttls};
+use crate::unmodeled::{self, ReferencedActions, UnmodeledActions};
+use crate::update::EngineUpdate;
+use crate::view::EngineView;
+use crate::warnings;
+use crate::{batch_evaluation_values, batch_threads, convert_batch_requests, convert_requests, parse_option,
+            serialize_batch_evaluation};
+use crate::{AuthzOptions, AuthzResponse, BatchEvaluation, EntityValidation, JsonSource, PolicyErrors,
+            PreparedInputs, RequestArgs, UnknownEntities};
+
+/// Stops an evaluation whose deadline has passed
+struct DeadlineExceeded;
+
+/// The inputs a load was loaded from, which an update keeps unless it stages others
+#[derive(Clone)]
+pub(crate) struct Sources {
+    /// the policies, without the engine's guardrails
+    pub(crate) policies: String,
+    pub(crate) entities: Arc<String>,
+    pub(crate) schema: Option<SchemaSource>,
+}
+
+/// The prepared inputs of one load, with the caches that depend on them, replaced together
+/// when the engine reloads
+pub(crate) struct Loaded {
+    pub(crate) prepared: PreparedInputs,
+    pub(crate) sources: Sources,
+    request_cache: Mutex<RequestCache>,
+    /// how long each policy took to parse, when requested
+    policy_diagnostics: Option<Vec<PolicyParseDiagnostics>>,
+    /// the actions allowed when failing open
+    fail_open_actions: HashSet<EntityUid>,
+    /// the actions the policies reference, which aren't unmodeled
+    referenced_actions: ReferencedActions,
+    /// the cache TTL, in seconds, of each policy annotated with one
+    cache_ttls: HashMap<PolicyId, u64>,
+    loaded_at: Instant,
+    /// the fingerprints of the entities and schema, reported with the policies' in every response
+    entities_fingerprint: u64,
+    schema_fingerprint: Option<u64>,
+}
+
+/// The engine's current inputs, and the failure of the last reload if it failed
+struct State {
+    loaded: Arc<Loaded>,
+    failure: Option<Failure>,
+}
+
+/// Holds parsed policies, schema, and entities across calls, so each authorization only builds
+/// and evaluates its requests
+#[pyclass]
+pub struct CedarEngine {
+    state: RwLock<State>,
+    entity_validation: EntityValidation,
+    unknown_entities: UnknownEntities,
+    policy_errors: PolicyErrors,
+    request_cache_size: usize,
+    /// whether each load records policy diagnostics
+    record_policy_diagnostics: bool,
+    /// how requests are decided while the last reload failed
+    degradation: Degradation,
+    /// the cache TTL, in seconds, of decisions no policy with a TTL determined
+    default_cache_ttl: Option<u64>,
+    /// how old the entities may get before responses warn that they're stale
+    max_entities_age: Option<Duration>,
+    /// whether requests without a correlation id are given a generated one
+    generate_correlation_ids: bool,
+    /// how many evaluations may run at once, when capped
+    concurrency_limit: Option<ConcurrencyLimit>,
+    /// the number of threads of the shared pool a batch is split across
+    threads: usize,
+    /// applied to each response before it's returned
+    response_transform: Option<ResponseTransform>,
+    /// the callbacks registered for the engine's events
+    hooks: Hooks,
+    /// the org-wide forbids added to the policies of every load, which reloads don't replace
+    guardrails: Option<Guardrails>,
+    /// the extension functions the policies of every load may call, when they're restricted
+    extension_allowlist: Option<ExtensionAllowlist>,
+    /// how requests for actions no policy references are decided, when configured
+    unmodeled_actions: Option<UnmodeledActions>,
+    /// the engine's load and each reload and update since
+    changelog: ChangeLog,
+    verbose: bool,
+}
+
+#[pymethods]
+impl CedarEngine {
+    #[new]
+    #[pyo3(signature = (policies, entities, schema = None, entity_validation = None,
+                        unknown_entities = None, policy_errors = None, request_cache_size = None, verbose = None,
+                        response_transform = None, policy_diagnostics = false, max_concurrency = None,
+                        max_queue = None, degradation = None, default_cache_ttl = None,
+                        max_entities_age = None, generate_correlation_ids = None, guardrails = None,
+                        actor = None, allowed_extension_functions = None, unmodeled_actions = None,
+                        max_workers = None))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(policies: String,
+           entities: EntitySource,
+           schema: Option<SchemaSource>,
+           entity_validation: Option<&str>,
+           unknown_entities: Option<&str>,
+           policy_errors: Option<&str>,
+           request_cache_size: Option<usize>,
+           verbose: Option<bool>,
+           response_transform: Option<String>,
+           policy_diagnostics: bool,
+           max_concurrency: Option<usize>,
+           max_queue: Option<usize>,
+           degradation: Option<&str>,
+           default_cache_ttl: Option<&str>,
+           max_entities_age: Option<f64>,
+           generate_correlation_ids: Option<bool>,
+           guardrails: Option<String>,
+           actor: Option<String>,
+           allowed_extension_functions: Option<Vec<String>>,
+           unmodeled_actions: Option<HashMap<String, String>>,
+           max_workers: Option<usize>) -> PyResult<Self> {
+        let concurrency_limit = match (max_concurrency, max_queue) {
+            (Some(max_concurrency), max_queue) => Some(ConcurrencyLimit::new(max_concurrency, max_queue)),
+            (None, Some(_)) => Some(Err(anyhow!("max_queue requires max_concurrency"))),
+            (None, None) => None,
+        }
+            .transpose()
+            .map_err(|e| errors::value_error(ErrorCode::InvalidArgument, errors::message(&e)))?;
+        let response_transform = response_transform
+            .map(|spec| ResponseTransform::from_json(&spec))
+            .transpose()
+            .map_err(|e| errors::value_error(ErrorCode::InvalidArgument, errors::message(&e)))?;
+        let entity_validation: EntityValidation = parse_option(entity_validation)?;
+        let unknown_entities: UnknownEntities = parse_option(unknown_entities)?;
+        let policy_errors: PolicyErrors = parse_option(policy_errors)?;
+        let degradation: Degradation = degradation
+            .map_or(Ok(Degradation::default()), str::parse)
+            .map_err(|e: Error| errors::value_error(ErrorCode::InvalidArgument, e))?;
+        let default_cache_ttl = default_cache_ttl
+            .map(parse_ttl)
+            .transpose()
+            .map_err(|e| errors::value_error(ErrorCode::InvalidArgument, e))?;
+        let max_entities_age = max_entities_age
+            .map(|age| Duration::try_from_secs_f64(age)
+                .map_err(|_| errors::value_error(ErrorCode::InvalidArgument,
+                                                 format!("invalid max_entities_age {age}; expected a number of seconds"))))
+            .transpose()?;
+        let extension_allowlist = allowed_extension_functions
+            .map(ExtensionAllowlist::new)
+            .transpose()
+            .map_err(|e| errors::value_error(ErrorCode::InvalidArgument, errors::message(&e)))?;
+        let unmodeled_actions = unmodeled_actions
+            .map(UnmodeledActions::parse)
+            .transpose()
+            .map_err(|e| errors::value_error(ErrorCode::InvalidArgument, errors::message(&e)))?;
+        let guardrails = guardrails
+            .map(|guardrails| Guardrails::parse(&guardrails, extension_allowlist.as_ref()))
+            .transpose()
+            .map_err(|e| errors::value_error(ErrorCode::of(&e), errors::message(&e)))?;
+        let threads = batch_threads(max_workers)?;
+        let request_cache_size = request_cache_size.unwrap_or(config::defaults().request_cache_size);
+        let verbose = config::verbose(verbose);
+        let entities = entities.into_text()
+            .map_err(|e| errors::value_error(ErrorCode::of(&e), errors::message(&e)))?;
+        let loaded = Loaded::load(&policies,
+                                  guardrails.as_ref(),
+                                  extension_allowlist.as_ref(),
+                                  entities,
+                                  schema,
+                                  entity_validation,
+                                  request_cache_size,
+                                  policy_diagnostics,
+                                  verbose)?;
+        let changelog = ChangeLog::default();
+        changelog.append(ChangeKind::Load, actor, loaded.outcome());
+        Ok(CedarEngine {
+            state: RwLock::new(State { loaded: Arc::new(loaded), failure: None }),
+            entity_validation,
+            unknown_entities,
+            policy_errors,
+            request_cache_size,
+            record_policy_diagnostics: policy_diagnostics,
+            degradation,
+            default_cache_ttl,
+            max_entities_age,
+            generate_correlation_ids: generate_correlation_ids
+                .unwrap_or(config::defaults().generate_correlation_ids),
+            concurrency_limit,
+            threads,
+            response_transform,
+            hooks: Hooks::default(),
+            guardrails,
+            extension_allowlist,
+            unmodeled_actions,
+            changelog,
+            verbose,
+        })
+    }
+
+    /// Replace the engine's policies, entities, and schema, keeping its guardrails.  When they can't
+    /// be loaded, the engine keeps the failure and decides requests as its degradation mode says
+    /// until a reload succeeds.  The reload is logged in the engine's changelog, with the actor.
+    #[pyo3(signature = (policies, entities, schema = None, actor = None))]
+    fn reload(&self,
+              py: Python<'_>,
+              policies: String,
+              entities: EntitySource,
+              schema: Option<SchemaSource>,
+              actor: Option<String>) -> PyResult<()> {
+        let t_start = Instant::now();
+        let loaded = py.allow_threads(|| {
+            let entities = entities.into_text()
+                .map_err(|e| errors::value_error(ErrorCode::of(&e), errors::message(&e)))?;
+            self.load_inputs(&policies, entities, schema)
+        });
+        let duration = t_start.elapsed();
+        let mut state = self.state.write().expect("the engine state is not poisoned");
+        match loaded {
+            Ok(loaded) => {
+                self.swap(py, state, loaded, ChangeKind::Reload, actor, duration);
+                Ok(())
+            }
+            Err(e) => {
+                let cause = e.value(py).to_string();
+                let code = e.value(py).getattr("code").and_then(|code| code.extract::<String>()).ok();
+                self.changelog.append(ChangeKind::Reload, actor, Outcome::Failed {
+                    error_code: code.clone(),
+                    error: cause.clone(),
+                });
+                state.failure = Some(Failure { cause: cause.clone(), at: Instant::now() });
+                drop(state);
+                self.hooks.emit(py, Event::Reload, &json!({
+                    "ok": false,
+                    "duration_micros": duration.as_micros(),
+                    "error_code": code,
+                    "error": cause,
+                }));
+                self.hooks.emit(py, Event::Error, &json!({
+                    "source": "reload",
+                    "error_codes": [code],
+                    "errors": [cause],
+                }));
+                Err(e)
+            }
+        }
+    }
+
+    /// Register `callback` for `event` ('reload', 'error', or 'decision'), replacing any callback
+    /// registered for it before, or unregister it when `callback` is `None`
+    #[pyo3(signature = (event, callback))]
+    fn register_hook(&self, event: &str, callback: Option<PyObject>) -> PyResult<()> {
+        let event: Event = event.parse().map_err(|e| errors::value_error(ErrorCode::InvalidArgument, e))?;
+        self.hooks.register(event, callback);
+        Ok(())
+    }
+
+    #[pyo3(signature = (request, include_diagnostics = true, include_metrics = true,
+                        include_missing_entities = false, priority = None, deadline = None,
+                        include_attributes = None, min_version = None))]
+    #[allow(clippy::too_many_arguments)]
+    fn is_authorized(&self,
+                     request: &PyAny,
+                     include_diagnostics: bool,
+                     include_metrics: bool,
+                     include_missing_entities: bool,
+                     priority: Option<&str>,
+                     deadline: Option<f64>,
+                     include_attributes: Option<&str>,
+                     min_version: Option<u64>) -> PyResult<response::AuthzResponse> {
+        let mut options = self.options(include_diagnostics, include_metrics, include_missing_entities);
+        options.include_attributes = AttributeProjection::parse_option(include_attributes)?;
+        let deadline = to_instant(request.py(), deadline)?;
+        let requests = convert_requests(std::iter::once(Ok(request)))?;
+        Ok(self.evaluate_response(request.py(), requests, &options, parse_lane(priority)?, deadline, min_version))
+    }
+
+    /// Whether the request is allowed, decided without the diagnostics, metrics, and serialization
+    /// of a full result, for hot paths that only need the decision; a request that can't be
+    /// decided isn't allowed.  The engine's hooks are called with the decision, as for any other.
+    fn is_authorized_fast(&self, request: &PyAny) -> PyResult<bool> {
+        let py = request.py();
+        let options = self.options(false, false, false);
+        let (mut request_args_vec, convert_input_duration) = convert_requests(std::iter::once(Ok(request)))?;
+        let correlation_ids = self.correlate(&mut request_args_vec, &options);
+        let (loaded, failure) = self.current();
+        let evaluation = match self.evaluate_requests(py, &loaded, failure.as_ref(), request_args_vec,
+                                                      convert_input_duration, &options, Lane::Interactive, None) {
+            Ok(results) => BatchEvaluation { errs: vec![], results },
+            Err(e) => BatchEvaluation { errs: vec![e], results: vec![] },
+        };
+        self.hooks.emit_results(py, &evaluation, &correlation_ids);
+        Ok(matches!(evaluation.results.first(), Some(Ok(ans)) if ans.decision() == Decision::Allow))
+    }
+
+    #[pyo3(signature = (requests, include_diagnostics = true, include_metrics = true,
+                        include_missing_entities = false, priority = None, deadline = None,
+                        context_columns = None, include_attributes = None, min_version = None))]
+    #[allow(clippy::too_many_arguments)]
+    fn is_authorized_batch(&self,
+                           requests: &PyAny,
+                           include_diagnostics: bool,
+                           include_metrics: bool,
+                           include_missing_entities: bool,
+                           priority: Option<&str>,
+                           deadline: Option<f64>,
+                           context_columns: Option<&str>,
+                           include_attributes: Option<&str>,
+                           min_version: Option<u64>) -> PyResult<Vec<String>> {
+        let mut options = self.options(include_diagnostics, include_metrics, include_missing_entities);
+        options.include_attributes = AttributeProjection::parse_option(include_attributes)?;
+        let deadline = to_instant(requests.py(), deadline)?;
+        let requests_args = convert_batch_requests(requests, context_columns)?;
+        Ok(self.evaluate(requests.py(), requests_args, &options, parse_lane(priority)?, deadline, min_version))
+    }
+
+    /// Evaluate a page of a batch, from the start or where `resume_token` says an earlier page
+    /// stopped, until the batch, `max_requests`, or the deadline runs out: the results of the
+    /// requests evaluated, the index of the first, and the token to resume from, unless the batch
+    /// is done
+    #[pyo3(signature = (requests, resume_token = None, max_requests = None, deadline = None,
+                        include_diagnostics = true, include_metrics = true, include_missing_entities = false,
+                        priority = None))]
+    #[allow(clippy::too_many_arguments)]
+    fn is_authorized_batch_resumable(&self,
+                                     requests: &PyAny,
+                                     resume_token: Option<&str>,
+                                     max_requests: Option<i64>,
+                                     deadline: Option<f64>,
+                                     include_diagnostics: bool,
+                                     include_metrics: bool,
+                                     include_missing_entities: bool,
+                                     priority: Option<&str>) -> PyResult<(Vec<String>, usize, Option<String>)> {
+        // a page of no requests would resume where it started, forever
+        let max_requests = max_requests
+            .map(|max| usize::try_from(max).ok().filter(|max| *max >= 1)
+                .ok_or_else(|| errors::value_error(ErrorCode::InvalidArgument,
+                                                   format!("invalid max_requests {max}; expected at least 1"))))
+            .transpose()?;
+        let options = self.options(include_diagnostics, include_metrics, include_missing_entities);
+        let deadline = to_instant(requests.py(), deadline)?;
+        let lane = parse_lane(priority)?;
+        let (mut request_args_vec, convert_input_duration) = convert_requests(requests.iter()?)?;
+        let num_requests = request_args_vec.len();
+        let (loaded, failure) = self.current();
+        let token = ResumeToken::new(&request_args_vec, &loaded.prepared);
+        let start = match resume_token {
+            Some(resume_token) => token.resume(resume_token, num_requests)
+                .map_err(|e| errors::value_error(ErrorCode::InvalidArgument, e))?
+                .offset,
+            None => 0,
+        };
+        let end = max_requests.map_or(num_requests, |max_requests| start.saturating_add(max_requests).min(num_requests));
+        let mut page: Vec<RequestArgs> = request_args_vec.drain(start..end).collect();
+        let correlation_ids = self.correlate(&mut page, &options);
+        // a call that couldn't evaluate any of the page, e.g. because the engine was overloaded,
+        // makes no progress
+        let results = self.evaluate_requests(requests.py(), &loaded, failure.as_ref(), page, convert_input_duration,
+                                             &options, lane, deadline)
+            .unwrap_or_default();
+        let next = start + results.len();
+        let num_results = results.len();
+        let evaluation = BatchEvaluation { errs: vec![], results };
+        self.hooks.emit_results(requests.py(), &evaluation, &correlation_ids);
+        let responses = serialize_batch_evaluation(evaluation, num_results, &options);
+        let resume_token = (next < num_requests).then(|| token.at(next).encode());
+        Ok((self.transform(responses), start, resume_token))
+    }
+
+    /// Statistics about the engine, as a JSON object
+    fn stats(&self) -> String {
+        let (loaded, failure) = self.current();
+        let mut stats = Map::new();
+        stats.insert(String::from("num_policies"), json!(loaded.prepared.policy_set.policies().count()));
+        stats.insert(String::from("num_entities"), json!(loaded.prepared.entities.iter().count()));
+        stats.insert(String::from("fingerprints"), json!(loaded.fingerprints()));
+        loaded.request_cache.lock()
+            .expect("the request cache is not poisoned")
+            .add_stats(&mut stats);
+        stats.insert(String::from("reload_failed"), json!(failure.is_some()));
+        if let Some(failure) = &failure {
+            stats.insert(String::from("reload_failure"), json!(failure.cause));
+            stats.insert(String::from("reload_failed_seconds_ago"), json!(failure.at.elapsed().as_secs()));
+        }
+        if let Some(limit) = &self.concurrency_limit {
+            limit.add_stats(&mut stats);
+        }
+        stats.insert(String::from("hook_failures"), json!(self.hooks.failures()));
+        serde_json::Value::Object(stats).to_string()
+    }
+
+    /// A read-only view of the engine's current policies and entities, with the entity attributes
+    /// named in `redact` redacted
+    #[pyo3(signature = (redact = None))]
+    fn view(&self, redact: Option<Vec<String>>) -> PyResult<EngineView> {
+        EngineView::new(self.current().0, redact.unwrap_or_default())
+    }
+
+    /// The parse duration and size of each policy, as a JSON array, or `null` unless the engine
+    /// was created with `policy_diagnostics`
+    fn policy_diagnostics(&self) -> String {
+        serde_json::to_string(&self.current().0.policy_diagnostics).expect("policy diagnostics serialize to JSON")
+    }
+
+    /// The entries of the engine's changelog that were logged at or after `since`, in seconds since
+    /// the Unix epoch, by `actor`, and of `kind` ('load', 'reload', or 'update'), oldest first, as a
+    /// JSON array
+    #[pyo3(signature = (since = None, actor = None, kind = None))]
+    fn changelog(&self, since: Option<f64>, actor: Option<String>, kind: Option<&str>) -> PyResult<String> {
+        let kind = kind
+            .map(str::parse)
+            .transpose()
+            .map_err(|e: Error| errors::value_error(ErrorCode::InvalidArgument, e))?;
+        let query = ChangeQuery { since, actor, kind };
+        Ok(serde_json::to_string(&self.changelog.query(&query)).expect("changes serialize to JSON"))
+    }
+
+    /// Begin an update of the engine's policies, entities, and schema, which are staged on it and
+    /// then committed together; the commit is logged in the engine's changelog, with the actor
+    #[pyo3(signature = (actor = None))]
+    fn begin_update(slf: PyRef<'_, Self>, actor: Option<String>) -> EngineUpdate {
+        let version = slf.version();
+        let sources = slf.current().0.sources.clone();
+        EngineUpdate::new(slf.into(), version, actor, sources)
+    }
+
+    /// The engine's version: the number of changes in its changelog, which each load, reload, and
+    /// committed update adds one to
+    pub(crate) fn version(&self) -> u64 {
+        self.changelog.len()
+    }
+}
+
+impl Loaded {
+    #[allow(clippy::too_many_arguments)]
+    fn load(policies: &str,
+            guardrails: Option<&Guardrails>,
+            extension_allowlist: Option<&ExtensionAllowlist>,
+            entities: String,
+            schema: Option<SchemaSource>,
+            entity_validation: EntityValidation,
+            request_cache_size: usize,
+            record_policy_diagnostics: bool,
+            verbose: bool) -> PyResult<Self> {
+        let entities_fingerprint = fingerprint::of(&entities);
+        let schema_fingerprint = schema.as_ref().map(SchemaSource::fingerprint);
+        if let Some(allowlist) = extension_allowlist {
+            allowlist.check(policies, str::to_owned).map_err(|e| errors::value_error(ErrorCode::of(&e), errors::message(&e)))?;
+        }
+        let policy_source = match guardrails {
+            Some(guardrails) => PolicySource::Compiled(Arc::new(guardrails.with_tenant_policies(policies)
+                .map_err(|e| errors::value_error(ErrorCode::of(&e), errors::message(&e)))?)),
+            None => PolicySource::Text(policies.to_owned()),
+        };
+        let sources = Sources { policies: policies.to_owned(), entities: Arc::new(entities), schema };
+        let prepared = PreparedInputs::prepare(&policy_source,
+                                               JsonSource::Text(sources.entities.to_string()),
+                                               sources.schema.clone(),
+                                               entity_validation,
+                                               verbose)
+            .map_err(|errs| errors::value_error_for(&errs))?;
+        let policy_diagnostics = record_policy_diagnostics
+            .then(|| policy_parse_diagnostics(policies))
+            .transpose()
+            .map_err(|e| errors::value_error(ErrorCode::PolicyParse, errors::message(&e)))?;
+        let request_cache = Mutex::new(RequestCache::new(request_cache_size, &prepared.entities));
+        let fail_open_actions = fail_open_actions(&prepared.policy_set);
+        let referenced_actions = ReferencedActions::of(&prepared.policy_set);
+        let cache_ttls = policy_ttls(&prepared.policy_set)
+            .map_err(|e| errors::value_error(ErrorCode::InvalidArgument, errors::message(&e)))?;
+        Ok(Loaded {
+            prepared,
+            sources,
+            request_cache,
+            policy_diagnostics,
+            fail_open_actions,
+            referenced_actions,
+            cache_ttls,
+            loaded_at: Instant::now(),
+            entities_fingerprint,
+            schema_fingerprint,
+        })
+    }
+
+    fn outcome(&self) -> Outcome {
+        Outcome::Loaded {
+            num_policies: self.prepared.policy_set.policies().count(),
+            num_entities: self.prepared.entities.iter().count(),
+            fingerprints: self.fingerprints(),
+        }
+    }
+
+    pub(crate) fn fingerprints(&self) -> Fingerprints {
+        Fingerprints {
+            policy_set: self.prepared.policies_fingerprint,
+            entities: Some(self.entities_fingerprint),
+            schema: self.schema_fingerprint,
+        }
+    }
+}
+
+impl CedarEngine {
+    /// The extension functions the engine's policies may call, when they're restricted
+    pub(crate) fn extension_allowlist(&self) -> Option<&ExtensionAllowlist> {
+        self.extension_allowlist.as_ref()
+    }
+
+    /// Load inputs with the engine's settings and guardrails, as a reload does
+    pub(crate) fn load_inputs(&self,
+                              policies: &str,
+                              entities: String,
+                              schema: Option<SchemaSource>) -> PyResult<Loaded> {
+        Loaded::load(policies,
+                     self.guardrails.as_ref(),
+                     self.extension_allowlist.as_ref(),
+                     entities,
+                     schema,
+                     self.entity_validation,
+                     self.request_cache_size,
+                     self.record_policy_diagnostics,
+                     self.verbose)
+    }
+
+    /// Replace the engine's inputs with those of an update, `loaded` while the engine was at
+    /// `version`, unless it has changed since, returning the engine's version with the update
+    pub(crate) fn commit_update(&self,
+                                py: Python<'_>,
+                                loaded: Loaded,
+                                version: u64,
+                                actor: Option<String>,
+                                duration: Duration) -> PyResult<u64> {
+        let state = self.state.write().expect("the engine state is not poisoned");
+        if self.changelog.len() != version {
+            return Err(errors::value_error(ErrorCode::InvalidArgument,
+                                           "failed to commit the update: the engine changed since it began"));
+        }
+        self.swap(py, state, loaded, ChangeKind::Update, actor, duration);
+        Ok(version + 1)
+    }
+
+    /// Make `loaded` the engine's inputs, logging the change before the lock is released
+    fn swap(&self,
+            py: Python<'_>,
+            mut state: RwLockWriteGuard<'_, State>,
+            loaded: Loaded,
+            kind: ChangeKind,
+            actor: Option<String>,
+            duration: Duration) {
+        let payload = json!({
+            "ok": true,
+            "duration_micros": duration.as_micros(),
+            "num_policies": loaded.prepared.policy_set.policies().count(),
+            "num_entities": loaded.prepared.entities.iter().count(),
+            "fingerprints": loaded.fingerprints(),
+        });
+        self.changelog.append(kind, actor, loaded.outcome());
+        *state = State { loaded: Arc::new(loaded), failure: None };
+        drop(state);
+        self.hooks.emit(py, Event::Reload, &payload);
+    }
+
+    /// The engine's current inputs, and the failure of the last reload if it failed
+    fn current(&self) -> (Arc<Loaded>, Option<Failure>) {
+        let state = self.state.read().expect("the engine state is not poisoned");
+        (Arc::clone(&state.loaded), state.failure.clone())
+    }
+
+    fn evaluate_one(&self, request: &PyAny, options: &AuthzOptions) -> PyResult<Result<AuthzResponse, Vec<Error>>> {
+        let (mut request_args_vec, convert_input_duration) = convert_requests(std::iter::once(Ok(request)))?;
+        self.correlate(&mut request_args_vec, options);
+        let (loaded, failure) = self.current();
+        Ok(match self.evaluate_requests(request.py(), &loaded, failure.as_ref(), request_args_vec,
+                                        convert_input_duration, options, Lane::Interactive, None) {
+            Ok(mut results) => results.remove(0),
+            Err(e) => Err(vec![e]),
+        })
+    }
+
+    /// Evaluate requests with `loaded`, or as the degradation mode says after the reload `failure`,
+    /// with the GIL released, so other threads' evaluations run alongside, within the engine's
+    /// concurrency limit in `lane`; an error when the limit's queue is full, or the deadline
+    /// passes while queued.  The requests left when the deadline passes aren't evaluated, so there
+    /// may be fewer results than requests.
+    #[allow(clippy::too_many_arguments)]
+    fn evaluate_requests(&self,
+                         py: Python<'_>,
+                         loaded: &Loaded,
+                         failure: Option<&Failure>,
+                         request_args_vec: Vec<RequestArgs>,
+                         convert_input_duration: Duration,
+                         options: &AuthzOptions,
+                         lane: Lane,
+                         deadline: Option<Instant>) -> Result<Vec<Result<AuthzResponse, Vec<Error>>>, Error> {
+        py.allow_threads(|| {
+            if let Some(failure) = failure.filter(|_| self.degradation != Degradation::ServeStale) {
+                let fail_open = (self.degradation == Degradation::FailOpen)
+                    .then_some((&loaded.fail_open_actions, &*loaded.prepared.entities));
+                return Ok(request_args_vec.iter()
+                    .map(|request| Ok(degraded_response(request, failure, fail_open, options)))
+                    .collect());
+            }
+            let mut permit = self.concurrency_limit.as_ref()
+                .map(|limit| limit.acquire(lane, deadline))
+                .transpose()?;
+            let num_requests = request_args_vec.len();
+            let actions: Option<Vec<Option<String>>> = self.unmodeled_actions.as_ref()
+                .map(|_| request_args_vec.iter().map(|request| request.action.clone()).collect());
+            let mut results = Vec::with_capacity(num_requests);
+            let passed = || deadline.is_some_and(|deadline| Instant::now() >= deadline);
+            if !passed() {
+                // the evaluation stops once the deadline passes
+                let _: Result<(), DeadlineExceeded> =
+                    loaded.prepared.evaluate_each(request_args_vec,
+                                                  convert_input_duration,
+                                                  options,
+                                                  Some(&loaded.request_cache),
+                                                  self.verbose,
+                                                  |ans| {
+                                                      results.push(ans);
+                                                      if results.len() == num_requests {
+                                                          return Ok(());
+                                                      }
+                                                      let held = permit.as_mut()
+                                                          .is_none_or(|permit| permit.yield_to_interactive(deadline));
+                                                      if !held || passed() {
+                                                          return Err(DeadlineExceeded);
+                                                      }
+                                                      Ok(())
+                                                  });
+            }
+            if let (Some(unmodeled), Some(actions)) = (&self.unmodeled_actions, actions) {
+                for (result, action) in results.iter_mut().zip(actions) {
+                    let Some(action) = action else { continue };
+                    let Ok(uid) = action.parse::<EntityUid>() else { continue };
+                    let Ok(ans) = result.as_mut() else { continue };
+                    match unmodeled.outcome(&uid, &loaded.referenced_actions, &loaded.prepared.entities) {
+                        Some(unmodeled::Outcome::Deny) => ans.warnings.push(unmodeled::warning(&action)),
+                        Some(unmodeled::Outcome::NoDecision) => *result = Err(vec![unmodeled::error(&action)]),
+                        None => {}
+                    }
+                }
+            }
+            let stale_entities = self.max_entities_age
+                .and_then(|max_age| warnings::stale_entities(loaded.loaded_at.elapsed(), max_age));
+            for ans in results.iter_mut().filter_map(|ans| ans.as_mut().ok()) {
+                if let Some(failure) = failure {
+                    ans.warnings.push(failure.stale_warning());
+                }
+                ans.warnings.extend(stale_entities.clone());
+                if let Some(fingerprints) = ans.fingerprints.as_mut() {
+                    fingerprints.entities = Some(loaded.entities_fingerprint);
+                    fingerprints.schema = loaded.schema_fingerprint;
+                }
+                ans.cache_ttl = decision_ttl(ans.response.diagnostics().reason(),
+                                             &loaded.cache_ttls,
+                                             self.default_cache_ttl);
+                if let Some(guardrails) = &self.guardrails {
+                    let (reason_guardrails, reason_policies): (Vec<&PolicyId>, Vec<&PolicyId>) =
+                        ans.response.diagnostics().reason().partition(|id| guardrails.is_guardrail(id));
+                    let mut reason_guardrails: Vec<String> = reason_guardrails.into_iter().map(ToString::to_string).collect();
+                    let mut reason_policies: Vec<String> = reason_policies.into_iter().map(ToString::to_string).collect();
+                    reason_guardrails.sort_by_key(|id| (id.len(), id.clone()));
+                    reason_policies.sort_by_key(|id| (id.len(), id.clone()));
+                    let mut reason_by_layer = BTreeMap::new();
+                    if !reason_guardrails.is_empty() {
+                        reason_by_layer.insert(layers::GUARDRAILS.to_owned(), reason_guardrails.clone());
+                    }
+                    if !reason_policies.is_empty() {
+                        reason_by_layer.insert(layers::POLICIES.to_owned(), reason_policies);
+                    }
+                    ans.reason_guardrails = Some(reason_guardrails);
+                    ans.reason_by_layer = Some(reason_by_layer);
+                }
+                if let (Some(permit), Some(metrics)) = (&permit, ans.metrics.as_mut()) {
+                    metrics.insert(String::from("queue_duration_micros"), permit.queue_duration.as_micros());
+                }
+            }
+            Ok(results)
+        })
+    }
+
+    fn options(&self, include_diagnostics: bool, include_metrics: bool, include_missing_entities: bool) -> AuthzOptions {
+        AuthzOptions {
+            include_diagnostics,
+            include_metrics,
+            entity_validation: self.entity_validation,
+            unknown_entities: self.unknown_entities,
+            policy_errors: self.policy_errors,
+            validate_request: false,
+            include_policy_results: false,
+            include_denial_class: false,
+            include_attributes: None,
+            include_missing_entities,
+            generate_correlation_ids: self.generate_correlation_ids,
+            threads: self.threads,
+        }
+    }
+
+    /// Evaluate requests once the engine is at `min_version`, which it waits for until the deadline;
+    /// the requests fail with a `stale_version` error if it isn't by then
+    fn evaluate(&self,
+                py: Python<'_>,
+                requests: (Vec<RequestArgs>, Duration),
+                options: &AuthzOptions,
+                lane: Lane,
+                deadline: Option<Instant>,
+                min_version: Option<u64>) -> Vec<String> {
+        let num_requests = requests.0.len();
+        let evaluation = self.evaluation(py, requests, options, lane, deadline, min_version);
+        self.transform(serialize_batch_evaluation(evaluation, num_requests, options))
+    }
+
+    /// Evaluate a request as `evaluate` does, for a result returned as an `AuthzResponse`
+    fn evaluate_response(&self,
+                         py: Python<'_>,
+                         request: (Vec<RequestArgs>, Duration),
+                         options: &AuthzOptions,
+                         lane: Lane,
+                         deadline: Option<Instant>,
+                         min_version: Option<u64>) -> response::AuthzResponse {
+        let evaluation = self.evaluation(py, request, options, lane, deadline, min_version);
+        let mut response = batch_evaluation_values(evaluation, 1, options).swap_remove(0);
+        if let Some(transform) = &self.response_transform {
+            transform.apply(&mut response);
+        }
+        response::AuthzResponse::new(response)
+    }
+
+    fn evaluation(&self,
+                  py: Python<'_>,
+                  (mut request_args_vec, convert_input_duration): (Vec<RequestArgs>, Duration),
+                  options: &AuthzOptions,
+                  lane: Lane,
+                  deadline: Option<Instant>,
+                  min_version: Option<u64>) -> BatchEvaluation {
+        let num_requests = request_args_vec.len();
+        let correlation_ids = self.correlate(&mut request_args_vec, options);
+        let version = min_version.map(|min_version| {
+            (min_version, py.allow_threads(|| self.changelog.wait_for(min_version, deadline)))
+        });
+        // the version is logged before its state is visible, so it's the state read next
+        let (loaded, failure) = self.current();
+        let evaluated = match version {
+            Some((min_version, version)) if version < min_version => {
+                Err(errors::coded(ErrorCode::StaleVersion,
+                                  format!("stale version: the engine is at version {version}, not yet {min_version}")))
+            }
+            _ => self.evaluate_requests(py, &loaded, failure.as_ref(), request_args_vec, convert_input_duration,
+                                        options, lane, deadline),
+        };
+        let evaluation = match evaluated {
+            Ok(mut results) => {
+                results.resize_with(num_requests, || {
+                    Err(vec![errors::coded(ErrorCode::DeadlineExceeded,
+                                           "deadline exceeded before the request was evaluated")])
+                });
+                BatchEvaluation { errs: vec![], results }
+            }
+            Err(e) => BatchEvaluation { errs: vec![e], results: vec![] },
+        };
+        self.hooks.emit_results(py, &evaluation, &correlation_ids);
+        evaluation
+    }
+
+    /// Give the requests without a correlation id a generated one, if the engine generates them,
+    /// and return the requests' ids for the hooks, which report the ids of requests that couldn't
+    /// be decided; none when no hooks are registered for results
+    fn correlate(&self, requests: &mut [RequestArgs], options: &AuthzOptions) -> Vec<Option<String>> {
+        if options.generate_correlation_ids {
+            correlation::fill_missing(requests);
+        }
+        if !self.hooks.reports_results() {
+            return vec![];
+        }
+        requests.iter().map(|request| request.correlation_id.clone()).collect()
+    }
+
+    fn transform(&self, responses: Vec<String>) -> Vec<String> {
+        match &self.response_transform {
+            Some(transform) => responses.iter().map(|response| transform.apply_to_str(response)).collect(),
+            None => responses,
+        }
+    }
+}
+
+fn parse_lane(priority: Option<&str>) -> PyResult<Lane> {
+    priority.map_or(Ok(Lane::default()), str::parse)
+        .map_err(|e: Error| errors::value_error(ErrorCode::InvalidArgument, e))
+}
+
+/// The instant of a deadline given as a timestamp of Python's `time.monotonic()` clock
+fn to_instant(py: Python<'_>, deadline: Option<f64>) -> PyResult<Option<Instant>> {
+    let Some(deadline) = deadline else { return Ok(None) };
+    let now: f64 = py.import("time")?.getattr("monotonic")?.call0()?.extract()?;
+    let remaining = (deadline - now).max(0.0);
+    Duration::try_from_secs_f64(remaining).ok()
+        .filter(|_| !deadline.is_nan())
+        .and_then(|remaining| Instant::now().checked_add(remaining))
+        .map(Some)
+        .ok_or_else(|| errors::value_error(ErrorCode::InvalidArgument,
+                                           format!("invalid deadline {deadline}; expected a time.monotonic() timestamp")))
+}
+
+/// Evaluate a request against several engines as though their policies were one policy set:
+/// a satisfied forbid in any engine denies the request, and otherwise a satisfied permit in any
+/// engine allows it.  Each engine evaluates the request against its own entities, and the
+/// determining policies are reported by engine in the diagnostics' `reason_by_engine`, and by
+/// layer, with each engine's guardrails a layer of their own, in `reason_by_layer`.
+#[pyfunction]
+#[pyo3(signature = (engines, request, include_diagnostics = true, include_metrics = true))]
+pub fn is_authorized_union(engines: Vec<(String, PyRef<CedarEngine>)>,
+                           request: &PyAny,
+                           include_diagnostics: bool,
+                           include_metrics: bool) -> PyResult<String> {
+    let mut errs: Vec<Error> = vec![];
+    let mut responses: Vec<(&str, AuthzResponse)> = Vec::with_capacity(engines.len());
+    for (name, engine) in &engines {
+        match engine.evaluate_one(request, &engine.options(true, true, false))? {
+            Ok(ans) => responses.push((name, ans)),
+            Err(engine_errs) => errs.extend(engine_errs.into_iter().map(|e| e.context(format!("engine {name}")))),
+        }
+    }
+
+    let options = AuthzOptions {
+        include_diagnostics,
+        include_metrics,
+        entity_validation: EntityValidation::default(),
+        unknown_entities: UnknownEntities::default(),
+        policy_errors: PolicyErrors::default(),
+        validate_request: false,
+        include_policy_results: false,
+        include_denial_class: false,
+        include_attributes: None,
+        include_missing_entities: false,
+        generate_correlation_ids: false,
+        threads: 1,
+    };
+    let result = if errs.is_empty() {
+        Ok(combine_responses(responses, &options))
+    } else {
+        Err(errs)
+    };
+    let mut results = serialize_batch_evaluation(BatchEvaluation { errs: vec![], results: vec![result] }, 1, &options);
+    Ok(results.remove(0))
+}
+
+/// Combine the engines' responses with Cedar's semantics: forbids override permits
+fn combine_responses(responses: Vec<(&str, AuthzResponse)>, options: &AuthzOptions) -> AuthzResponse {
+    let mut forbids: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut permits: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    // the determining policies by layer: each engine's guardrails are a layer of their own
+    let mut forbids_by_layer: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut permits_by_layer: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut reasons = (HashSet::new(), HashSet::new());
+    let mut errors = HashSet::new();
+    let mut warnings = vec![];
+    let mut metrics: HashMap<String, u128> = HashMap::new();
+    let mut correlation_id = None;
+    for (name, ans) in responses {
+        let diagnostics = ans.response.diagnostics();
+        // Cedar reports the satisfied forbids of a denied request, and the satisfied permits of an allowed one
+        let (by_engine, by_layer, ids) = match ans.decision() {
+            Decision::Allow => (&mut permits, &mut permits_by_layer, &mut reasons.1),
+            Decision::Deny => (&mut forbids, &mut forbids_by_layer, &mut reasons.0),
+        };
+        let mut engine_ids: Vec<String> = diagnostics.reason().map(|id| id.to_string()).collect();
+        if !engine_ids.is_empty() {
+            engine_ids.sort();
+            for id in &engine_ids {
+                let layer = match &ans.reason_guardrails {
+                    Some(reason_guardrails) if reason_guardrails.contains(id) => layers::engine_guardrails(name),
+                    _ => name.to_owned(),
+                };
+                by_layer.entry(layer).or_default().push(id.clone());
+            }
+            by_engine.insert(name.to_owned(), engine_ids);
+            ids.extend(diagnostics.reason().cloned());
+        }
+        errors.extend(diagnostics.errors().map(|e| format!("{name}: {e}")));
+        warnings.extend(ans.warnings.iter().map(|w| format!("{name}: {w}")));
+        for (metric, value) in ans.metrics.into_iter().flatten() {
+            *metrics.entry(metric).or_default() += value;
+        }
+        correlation_id = correlation_id.or(ans.correlation_id);
+    }
+
+    let (decision, reason, reason_by_engine, reason_by_layer) = if !forbids.is_empty() {
+        (Decision::Deny, reasons.0, forbids, forbids_by_layer)
+    } else if !permits.is_empty() {
+        (Decision::Allow, reasons.1, permits, permits_by_layer)
+    } else {
+        (Decision::Deny, HashSet::new(), BTreeMap::new(), BTreeMap::new())
+    };
+    let mut combined = AuthzResponse::new(Response::new(decision, reason, errors), metrics, correlation_id, options);
+    combined.warnings = warnings;
+    combined.reason_by_engine = Some(reason_by_engine);
+    combined.reason_by_layer = Some(reason_by_layer);
+    combined
+}
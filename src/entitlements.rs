@@ -0,0 +1,211 @@
+//! Entitlement export: every principal, action, and resource among the entities that the policies
+//! allow, as subject/relation/object tuples, so governance tools that ingest Zanzibar-style
+//! relationships can take what Cedar allows without glue for Cedar's requests and responses.
+//!
+//! The principals and resources of each action are chosen as in `duty`: the schema's `appliesTo`,
+//! when there's a schema, and the types the caller gives narrow them; otherwise every entity but
+//! the actions is both.
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{anyhow, Error, Result};
+use cedar_policy::{Decision, EntityUid};
+use serde::Serialize;
+
+use crate::compiled_schema::SchemaSource;
+use crate::config::{self, Configured};
+use crate::duty::{applies_to, entity_uids, schema_model};
+use crate::policy_set::PolicySource;
+use crate::{AuthzOptions, EntityValidation, JsonSource, PolicyErrors, PreparedInputs, RequestArgs, UnknownEntities};
+
+pub enum EntitlementFormat {
+    Json,
+    Zanzibar,
+}
+
+impl FromStr for EntitlementFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "json" => Ok(EntitlementFormat::Json),
+            "zanzibar" => Ok(EntitlementFormat::Zanzibar),
+            _ => Err(Error::msg(format!("invalid output format '{}'; expected one of: json, zanzibar", s))),
+        }
+    }
+}
+
+/// Which requests the export evaluates
+pub struct Scope {
+    /// The actions to export, or None for every action the schema declares
+    pub actions: Option<Vec<String>>,
+    /// The entity types principals may have, or None for any
+    pub principal_types: Option<Vec<String>>,
+    /// The entity types resources may have, or None for any
+    pub resource_types: Option<Vec<String>>,
+    /// The context of every request, as a JSON object
+    pub context: Option<String>,
+    /// The most requests the export may evaluate
+    pub max_requests: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Object {
+    #[serde(rename = "type")]
+    entity_type: String,
+    id: String,
+}
+
+impl From<&EntityUid> for Object {
+    fn from(uid: &EntityUid) -> Self {
+        Object { entity_type: uid.type_name().to_string(), id: uid.id().as_ref().to_owned() }
+    }
+}
+
+/// A principal, the subject, allowed an action, the relation, on a resource, the object
+#[derive(Debug, Serialize)]
+pub struct Entitlement {
+    subject: Object,
+    /// The action's id, e.g. `view` for `Action::"view"`
+    relation: String,
+    object: Object,
+    /// The policies that allowed the request
+    policies: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Entitlements {
+    /// By action, then principal and resource in the order of the entities
+    entitlements: Vec<Entitlement>,
+    requests_checked: usize,
+    /// How many requests couldn't be evaluated or encountered errors evaluating policies
+    errored: usize,
+}
+
+impl Entitlements {
+    /// A line for each entitlement, `Photo:a.jpg#view@User:alice`, with `/` in place of the `::` of
+    /// namespaced types and `%`, `:`, `#`, `@`, `/`, and whitespace percent-encoded in ids
+    pub fn to_zanzibar(&self) -> String {
+        self.entitlements.iter()
+            .map(|entitlement| format!("{}#{}@{}\n",
+                                       zanzibar_object(&entitlement.object),
+                                       encode(&entitlement.relation),
+                                       zanzibar_object(&entitlement.subject)))
+            .collect()
+    }
+}
+
+fn zanzibar_object(object: &Object) -> String {
+    format!("{}:{}", object.entity_type.replace("::", "/"), encode(&object.id))
+}
+
+fn encode(id: &str) -> String {
+    let mut encoded = String::with_capacity(id.len());
+    for c in id.chars() {
+        if matches!(c, '%' | ':' | '#' | '@' | '/') || c.is_whitespace() {
+            let mut bytes = [0; 4];
+            for byte in c.encode_utf8(&mut bytes).bytes() {
+                encoded.push_str(&format!("%{byte:02X}"));
+            }
+        } else {
+            encoded.push(c);
+        }
+    }
+    encoded
+}
+
+/// Decode the percent-encoding of an id or relation of a tuple
+pub(crate) fn decode(encoded: &str) -> Result<String> {
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut rest = encoded.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = tail.get(..2).and_then(|hex| std::str::from_utf8(hex).ok());
+            let decoded = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| anyhow!("invalid percent-encoding in {encoded}"))?;
+            bytes.push(decoded);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| anyhow!("invalid percent-encoding in {encoded}"))
+}
+
+/// Evaluate every principal, action, and resource of the `scope` among the entities, in one
+/// batch, and export the ones the policies allow
+pub fn export_entitlements(policies: &str,
+                           entities: JsonSource,
+                           schema: Option<SchemaSource>,
+                           scope: &Scope,
+                           verbose: bool) -> Result<Entitlements, Vec<Error>> {
+    let uids = entity_uids(&entities)?;
+    let model = schema_model(&schema)?;
+    let actions = match (&scope.actions, &model) {
+        (Some(actions), _) => actions.clone(),
+        (None, Some(model)) => model.actions.iter().map(|action| action.name.clone()).collect(),
+        (None, None) => return Err(vec![anyhow!("the actions to export are required without a schema")]),
+    };
+
+    let mut requests: Vec<RequestArgs> = vec![];
+    let mut tuples = vec![];
+    for action in &actions {
+        let action_uid = EntityUid::from_str(action).map_err(|e| vec![anyhow!("invalid action {action}: {e}")])?;
+        let action_group = std::slice::from_ref(action);
+        let principals = applies_to(&uids, &model, action_group, &scope.principal_types, |a| &a.principal_types)?;
+        let resources = applies_to(&uids, &model, action_group, &scope.resource_types, |a| &a.resource_types)?;
+        if requests.len() + principals.len() * resources.len() > scope.max_requests {
+            return Err(vec![anyhow!("the export needs more than max_requests ({}) requests over the entities; \
+                                     narrow the actions or the principal or resource types", scope.max_requests)]);
+        }
+        for principal in &principals {
+            for resource in &resources {
+                requests.push(RequestArgs {
+                    principal: Some(principal.to_string()),
+                    action: Some(action.clone()),
+                    resource: Some(resource.to_string()),
+                    context_json: scope.context.clone(),
+                    correlation_id: None,
+                    parsed_uids: None,
+                });
+                tuples.push((*principal, action_uid.id().as_ref().to_owned(), *resource));
+            }
+        }
+    }
+
+    let prepared = PreparedInputs::prepare(&PolicySource::Text(policies.to_owned()),
+                                           entities,
+                                           schema,
+                                           EntityValidation::configured(),
+                                           verbose)?;
+    let options = AuthzOptions {
+        include_diagnostics: true,
+        include_metrics: false,
+        entity_validation: EntityValidation::configured(),
+        unknown_entities: UnknownEntities::configured(),
+        policy_errors: PolicyErrors::configured(),
+        validate_request: false,
+        include_policy_results: false,
+        include_denial_class: false,
+        include_attributes: None,
+        include_missing_entities: false,
+        generate_correlation_ids: false,
+        threads: config::defaults().threads,
+    };
+    let requests_checked = requests.len();
+    let results = prepared.evaluate(requests, Duration::ZERO, &options, None, verbose);
+    let errored = results.iter()
+        .filter(|ans| ans.as_ref().map_or(true, |ans| ans.diagnostics().is_some_and(|d| d.errors().next().is_some())))
+        .count();
+    let entitlements = tuples.into_iter().zip(&results)
+        .filter_map(|((principal, relation, resource), ans)| {
+            let ans = ans.as_ref().ok().filter(|ans| ans.decision() == Decision::Allow)?;
+            let mut policies: Vec<String> = ans.response.diagnostics().reason().map(ToString::to_string).collect();
+            policies.sort_by_key(|id| (id.len(), id.clone()));
+            Some(Entitlement { subject: principal.into(), relation, object: resource.into(), policies })
+        })
+        .collect();
+    Ok(Entitlements { entitlements, requests_checked, errored })
+}
@@ -0,0 +1,272 @@
+//! Formatting of the error messages cedarpy reports, in results, exceptions, and logs.
+//!
+//! Messages are bounded to `MAX_MESSAGE_LEN` bytes, truncated on a character boundary so they
+//! remain valid UTF-8, and inputs quoted in them are bounded to `MAX_EXCERPT_LEN` bytes, so that
+//! a megabyte of invalid entities doesn't become a megabyte log line.  Each error is given a
+//! machine-readable `ErrorCode` where it's made, by `coded` or `context`, which is reported
+//! alongside the messages.
+//!
+//! The exceptions raised are `CedarError`s, a `ValueError`, or one of its subclasses for the kind
+//! of error, so callers can catch a specific class of failure.  Each has its code as its `code`,
+//! and, when the error is in an input whose text is at hand, its `span` in the text and the line
+//! of the text it's on as its `snippet`.
+
+// pyo3 0.19's `create_exception!` expansion checks a cfg that newer compilers don't know
+#![allow(unexpected_cfgs)]
+
+use std::borrow::Cow;
+use std::fmt::Display;
+
+use anyhow::Error;
+use pyo3::create_exception;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::partial::{self, Spans};
+use crate::pyjson;
+
+/// The longest error message reported, in bytes
+pub const MAX_MESSAGE_LEN: usize = 4096;
+/// The longest excerpt of an input quoted in an error message, in bytes
+pub const MAX_EXCERPT_LEN: usize = 256;
+
+create_exception!(cedarpy, CedarError, PyValueError, "An error cedarpy raised, with its code as its `code`");
+create_exception!(cedarpy, PolicyParseError, CedarError, "The policies could not be parsed");
+create_exception!(cedarpy, SchemaParseError, CedarError, "The schema is invalid");
+create_exception!(cedarpy, EntityError, CedarError, "The entities could not be parsed or validated");
+create_exception!(cedarpy, RequestError, CedarError, "A request could not be built");
+create_exception!(cedarpy, ValidationError, CedarError, "The policies failed validation, e.g. against the schema");
+create_exception!(cedarpy, InvalidArgumentError, CedarError, "An argument, e.g. an option or uid, is invalid");
+
+/// Add the exception classes to the module
+pub fn register(py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add("CedarError", py.get_type::<CedarError>())?;
+    m.add("PolicyParseError", py.get_type::<PolicyParseError>())?;
+    m.add("SchemaParseError", py.get_type::<SchemaParseError>())?;
+    m.add("EntityError", py.get_type::<EntityError>())?;
+    m.add("RequestError", py.get_type::<RequestError>())?;
+    m.add("ValidationError", py.get_type::<ValidationError>())?;
+    m.add("InvalidArgumentError", py.get_type::<InvalidArgumentError>())?;
+    Ok(())
+}
+
+/// What kind of problem an error reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// An argument, e.g. an option or uid, is invalid
+    InvalidArgument,
+    /// The policies could not be parsed
+    PolicyParse,
+    /// The entities could not be parsed or validated
+    Entities,
+    /// The schema is invalid
+    Schema,
+    /// A request could not be built, e.g. its context doesn't conform to the schema
+    Request,
+    /// The policies failed validation against the schema, or call disallowed extension functions
+    Validation,
+    /// A request names a principal or resource that isn't in the entities
+    UnknownEntity,
+    /// A request's action is one no policy references, configured to be left undecided
+    UnmodeledAction,
+    /// A policy failed to evaluate
+    PolicyEvaluation,
+    /// A response could not be serialized
+    Serialization,
+    /// The engine was at its concurrency limit with a full queue
+    Overloaded,
+    /// The call's deadline passed before the request was evaluated
+    DeadlineExceeded,
+    /// The engine's reload failed, so the request was decided by its degradation mode
+    PolicyStoreUnavailable,
+    /// The engine hadn't reached the call's `min_version` by its deadline
+    StaleVersion,
+}
+
+impl ErrorCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::InvalidArgument => "invalid_argument",
+            ErrorCode::PolicyParse => "policy_parse_error",
+            ErrorCode::Entities => "entities_error",
+            ErrorCode::Schema => "schema_error",
+            ErrorCode::Request => "request_error",
+            ErrorCode::Validation => "validation_error",
+            ErrorCode::UnknownEntity => "unknown_entity",
+            ErrorCode::UnmodeledAction => "unmodeled_action",
+            ErrorCode::PolicyEvaluation => "policy_evaluation_error",
+            ErrorCode::Serialization => "serialization_error",
+            ErrorCode::Overloaded => "overloaded",
+            ErrorCode::DeadlineExceeded => "deadline_exceeded",
+            ErrorCode::PolicyStoreUnavailable => "policy_store_unavailable",
+            ErrorCode::StaleVersion => "stale_version",
+        }
+    }
+
+    /// Whether a request that failed with this error may be decided if it's resubmitted as it is:
+    /// the engine was busy, the call ran out of time, the policy store was briefly unavailable, or
+    /// the engine hadn't caught up with a change yet.  Other errors are in the request or the
+    /// inputs, and fail the same way every time.
+    pub fn is_retryable(self) -> bool {
+        matches!(self,
+                 ErrorCode::Overloaded | ErrorCode::DeadlineExceeded | ErrorCode::PolicyStoreUnavailable
+                 | ErrorCode::StaleVersion)
+    }
+
+    /// The code an error was given where it was made, by `coded` or a `context` of it, looking
+    /// through contexts added since, such as the engine names that `is_authorized_union` adds;
+    /// `InvalidArgument` for an error made without one
+    pub fn of(err: &Error) -> Self {
+        err.downcast_ref::<Coded>().map_or(ErrorCode::InvalidArgument, |coded| coded.code)
+    }
+}
+
+/// An error message with its code, so the code is read back by `ErrorCode::of` rather than
+/// recovered from the message
+#[derive(Debug)]
+pub struct Coded {
+    code: ErrorCode,
+    message: String,
+}
+
+impl Display for Coded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Coded {}
+
+/// An error with `code`
+pub fn coded(code: ErrorCode, message: impl Display) -> Error {
+    Error::new(context(code, message))
+}
+
+/// A context with `code`, for an error made by Cedar or a parser, e.g.
+/// `.context(errors::context(ErrorCode::Entities, "failed to parse entities"))`
+pub fn context(code: ErrorCode, message: impl Display) -> Coded {
+    Coded { code, message: message.to_string() }
+}
+
+/// Whether a result that failed with errors of `codes` may be decided if it's resubmitted: only
+/// when every error is retryable, since a permanent one fails the request again
+pub fn retryable(codes: impl IntoIterator<Item = ErrorCode>) -> bool {
+    codes.into_iter().all(ErrorCode::is_retryable)
+}
+
+/// Truncate `message` to at most `max_len` bytes, including the note of how much was cut
+fn truncate(message: &str, max_len: usize) -> Cow<'_, str> {
+    if message.len() <= max_len {
+        return Cow::Borrowed(message);
+    }
+    // no more bytes are cut than there are in the message, so the note is no longer than this
+    let note_len = "... ( more bytes)".len() + message.len().to_string().len();
+    let mut end = max_len.saturating_sub(note_len);
+    while !message.is_char_boundary(end) {
+        end -= 1;
+    }
+    Cow::Owned(format!("{}... ({} more bytes)", &message[..end], message.len() - end))
+}
+
+/// An error message, bounded to `MAX_MESSAGE_LEN`
+pub fn bounded(message: &str) -> Cow<'_, str> {
+    truncate(message, MAX_MESSAGE_LEN)
+}
+
+/// An input quoted in an error message, bounded to `MAX_EXCERPT_LEN`
+pub fn excerpt(input: &str) -> Cow<'_, str> {
+    truncate(input, MAX_EXCERPT_LEN)
+}
+
+/// The message of an error followed by those of its causes, e.g. `invalid request: failed to
+/// parse resource bad as entity Uid: ...`, for results, exceptions, and logs.  A cause the message
+/// before it already includes is left out, since Cedar's errors often quote their causes.
+pub fn message(err: &Error) -> String {
+    let mut message = String::new();
+    let mut previous = String::new();
+    for cause in err.chain() {
+        let text = cause.to_string();
+        if !previous.is_empty() && previous.contains(&text) {
+            continue;
+        }
+        if !message.is_empty() {
+            message.push_str(": ");
+        }
+        message.push_str(&text);
+        previous = text;
+    }
+    bounded(&message).into_owned()
+}
+
+/// A `CedarError` of the class for the code, with a bounded message and the code as its `code`
+/// attribute
+pub fn value_error(code: ErrorCode, message: impl Display) -> PyErr {
+    let message = bounded(&message.to_string()).into_owned();
+    let err = match code {
+        ErrorCode::PolicyParse => PolicyParseError::new_err(message),
+        ErrorCode::Schema => SchemaParseError::new_err(message),
+        ErrorCode::Entities | ErrorCode::UnknownEntity => EntityError::new_err(message),
+        ErrorCode::Request | ErrorCode::UnmodeledAction => RequestError::new_err(message),
+        ErrorCode::Validation => ValidationError::new_err(message),
+        ErrorCode::InvalidArgument => InvalidArgumentError::new_err(message),
+        _ => CedarError::new_err(message),
+    };
+    Python::with_gil(|py| {
+        // setting an attribute on a fresh exception instance doesn't fail
+        let value = err.value(py);
+        let _ = value.setattr("code", code.as_str());
+        let _ = value.setattr("span", py.None());
+        let _ = value.setattr("snippet", py.None());
+    });
+    err
+}
+
+/// `err`, a `CedarError` raised for an error in `text`, with where in the text the error is: for
+/// policies that don't parse, the first error the partial parser finds, and for invalid JSON, the
+/// line and column the JSON parser reports
+pub fn locate(err: PyErr, text: &str) -> PyErr {
+    Python::with_gil(|py| {
+        let value = err.value(py);
+        if !value.is_instance_of::<CedarError>() {
+            return;
+        }
+        let span = if value.is_instance_of::<PolicyParseError>() {
+            partial::first_error(text)
+        } else {
+            json_position(&value.to_string())
+                .and_then(|(line, column)| byte_offset(text, line, column))
+                .map(|offset| Spans::new(text).span(offset..offset))
+        };
+        let Some(span) = span else {
+            return;
+        };
+        let snippet = text.lines().nth(span.line() - 1).map(|line| excerpt(line.trim_end()).into_owned());
+        let span = serde_json::to_value(&span).expect("spans serialize to JSON");
+        // setting an attribute on an exception instance doesn't fail
+        let _ = value.setattr("span", pyjson::to_py(py, &span));
+        let _ = value.setattr("snippet", snippet);
+    });
+    err
+}
+
+/// The line and column of a JSON parser's error message, e.g. `... at line 3 column 14`
+fn json_position(message: &str) -> Option<(usize, usize)> {
+    let (_, position) = message.rsplit_once(" at line ")?;
+    let (line, rest) = position.split_once(" column ")?;
+    let column: String = rest.chars().take_while(char::is_ascii_digit).collect();
+    Some((line.parse().ok()?, column.parse().ok()?))
+}
+
+/// The byte offset of a 1-based line and column, where the column counts bytes, as the JSON
+/// parser's do
+fn byte_offset(text: &str, line: usize, column: usize) -> Option<usize> {
+    let line_start: usize = text.split_inclusive('\n').take(line.checked_sub(1)?).map(str::len).sum();
+    Some((line_start + column.saturating_sub(1)).min(text.len()))
+}
+
+/// A `ValueError` reporting all of `errs`, with the code of the first
+pub fn value_error_for(errs: &[Error]) -> PyErr {
+    let code = errs.first().map_or(ErrorCode::InvalidArgument, ErrorCode::of);
+    let messages: Vec<String> = errs.iter().map(message).collect();
+    value_error(code, messages.join("\n"))
+}
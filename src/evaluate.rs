@@ -0,0 +1,70 @@
+//! Evaluating a single Cedar expression against a request and entities, like the CLI's `evaluate`
+//! subcommand, so a policy's conditions can be tested one at a time.
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, Error};
+use cedar_policy::{eval_expression, EntityUid, EvalResult, Expression};
+use serde_json::{json, Map, Value};
+
+use crate::compiled_schema::SchemaSource;
+use crate::config::Configured;
+use crate::errors::{self, ErrorCode};
+use crate::policy_set::PolicySource;
+use crate::store::EntitySource;
+use crate::{EntityValidation, PreparedInputs, RequestArgs};
+
+/// Evaluate the `expression` with the request's principal, action, resource, and context, returning
+/// its value in Cedar's JSON format for attribute values
+pub fn evaluate_expression(expression: &str,
+                           request_args: &RequestArgs,
+                           entities: EntitySource,
+                           schema: Option<SchemaSource>,
+                           verbose: bool) -> Result<Value, Vec<Error>> {
+    let expr = Expression::from_str(expression)
+        .map_err(|e| vec![anyhow!("failed to parse expression {expression}: {e}")])?;
+    let prepared = PreparedInputs::prepare(&PolicySource::Text(String::new()),
+                                           entities,
+                                           schema,
+                                           EntityValidation::configured(),
+                                           verbose)?;
+    let request = request_args.get_request(prepared.schema.as_ref()).map_err(|e| vec![e])?;
+    let value = eval_expression(&request, &prepared.entities, &expr)
+        .map_err(|e| vec![errors::coded(ErrorCode::PolicyEvaluation, format!("policy evaluation error: {e}"))])?;
+    Ok(to_json(&value))
+}
+
+/// The value in Cedar's JSON format: entities in their `__entity` form, and extension values in
+/// their `__extn` form, e.g. `{"__extn": {"fn": "ip", "arg": "10.0.0.1/32"}}`
+pub fn to_json(value: &EvalResult) -> Value {
+    match value {
+        EvalResult::Bool(value) => json!(value),
+        EvalResult::Long(value) => json!(value),
+        EvalResult::String(value) => json!(value),
+        EvalResult::EntityUid(uid) => uid_json(uid),
+        EvalResult::Set(set) => Value::Array(set.iter().map(to_json).collect()),
+        EvalResult::Record(record) => Value::Object(record.iter()
+            .map(|(name, value)| (name.clone(), to_json(value)))
+            .collect::<Map<_, _>>()),
+        EvalResult::ExtensionValue(value) => extension_json(value),
+    }
+}
+
+/// An extension value in its `__extn` form.  Cedar gives only the text of the value, which for an
+/// ip is its address with the prefix length, e.g. `10.0.0.1/32`, and for a decimal its whole part
+/// and the four digits of its fraction as an integer, e.g. `1.500` for `decimal("1.0500")`, so the
+/// digits are padded back; a decimal between -1 and 0 is displayed without its sign, so has none.
+fn extension_json(value: &str) -> Value {
+    let (function, arg) = match value.split_once('.') {
+        Some((whole, fraction)) if !value.contains('/') => match fraction.parse::<u16>() {
+            Ok(fraction) => ("decimal", format!("{whole}.{fraction:04}")),
+            Err(_) => ("decimal", value.to_owned()),
+        },
+        _ => ("ip", value.to_owned()),
+    };
+    json!({ "__extn": { "fn": function, "arg": arg } })
+}
+
+fn uid_json(uid: &EntityUid) -> Value {
+    json!({ "__entity": { "type": uid.type_name().to_string(), "id": uid.id().as_ref() } })
+}
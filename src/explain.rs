@@ -0,0 +1,426 @@
+//! Explain why a request did not get the decision a policy test expected.
+//!
+//! Each policy's scope constraints and conditions are evaluated separately against the request,
+//! so the explanation can point at exactly which parts of the candidate permits were not
+//! satisfied.  With a schema, simple unsatisfied parts (scope constraints and
+//! `principal.attr == value` style conditions) are turned into request and entity changes, which
+//! are only suggested once re-authorizing with them, under the schema, flips the decision.
+
+use std::str::FromStr;
+
+use anyhow::Error;
+use cedar_policy::{Authorizer, Decision, Effect, EntityUid, EvalResult, Expression, Request, eval_expression};
+use cedar_policy_core::parser::cst;
+use cedar_policy_core::parser::text_to_cst::parse_policies;
+use cedar_policy_core::parser::ASTNode;
+use serde_json::{json, Value};
+
+use crate::compiled_schema::SchemaSource;
+use crate::errors::{self, ErrorCode};
+use crate::permissive::parse_entity_uid;
+use crate::policy_set::PolicySource;
+use crate::{AuthzOptions, EntityValidation, JsonSource, PreparedInputs, PolicyErrors, RequestArgs, UnknownEntities,
+            execute_authorization_request};
+
+/// How many of the nearest candidate permits to try turning into a suggestion
+const MAX_SUGGESTION_CANDIDATES: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Var {
+    Principal,
+    Action,
+    Resource,
+    Context,
+}
+
+impl Var {
+    fn from_ident(ident: &cst::Ident) -> Option<Var> {
+        match ident {
+            cst::Ident::Principal => Some(Var::Principal),
+            cst::Ident::Action => Some(Var::Action),
+            cst::Ident::Resource => Some(Var::Resource),
+            cst::Ident::Context => Some(Var::Context),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Var::Principal => "principal",
+            Var::Action => "action",
+            Var::Resource => "resource",
+            Var::Context => "context",
+        }
+    }
+}
+
+/// A change to the request or entities that would satisfy one part of a policy
+#[derive(Debug, Clone)]
+enum Tweak {
+    /// use a different principal, action, or resource
+    Request(Var, EntityUid),
+    /// add a parent to the principal or resource entity
+    Parent(Var, EntityUid),
+    /// set an attribute of the principal or resource entity, or of the context
+    Attr(Var, String, Value),
+}
+
+/// A scope constraint or condition of a policy that the request does not satisfy
+#[derive(Debug)]
+struct Unsatisfied {
+    kind: &'static str,
+    condition: String,
+    error: Option<String>,
+    tweak: Option<Tweak>,
+}
+
+#[derive(Debug)]
+struct PolicyAnalysis {
+    id: String,
+    source: String,
+    unsatisfied: Vec<Unsatisfied>,
+}
+
+/// Explain the decision for a request, compared to the `expected` decision
+pub fn explain_decision(request_args: &RequestArgs,
+                        policies: &str,
+                        entities: JsonSource,
+                        schema: Option<SchemaSource>,
+                        expected: Decision,
+                        verbose: bool) -> Result<Value, Vec<Error>> {
+    let entities_value = entities.to_value()
+        .map_err(|e| vec![Error::new(e).context(errors::context(ErrorCode::Entities, "failed to parse entities"))])?;
+    let prepared = PreparedInputs::prepare(&PolicySource::Text(policies.to_owned()), entities, schema.clone(), EntityValidation::Strict, verbose)?;
+    let request = request_args.get_request(prepared.schema.as_ref()).map_err(|e| vec![e])?;
+    let response = Authorizer::new().is_authorized(&request, &prepared.policy_set, &prepared.entities);
+    let decision = response.decision();
+
+    let analyses = analyze_policies(policies, &prepared, &request).map_err(|e| vec![e])?;
+    let determining_policies: Vec<Value> = response.diagnostics().reason()
+        .filter_map(|id| {
+            let analysis = analyses.iter().find(|analysis| analysis.id == id.to_string())?;
+            let effect = prepared.policy_set.policy(id)?.effect();
+            Some(json!({ "id": analysis.id, "effect": effect_name(effect), "policy": analysis.source }))
+        })
+        .collect();
+
+    // permits that do not apply to the request, nearest (fewest unsatisfied parts) first
+    let mut candidate_permits: Vec<&PolicyAnalysis> = analyses.iter()
+        .filter(|analysis| !analysis.unsatisfied.is_empty() && effect_of(&prepared, analysis) == Some(Effect::Permit))
+        .collect();
+    candidate_permits.sort_by_key(|analysis| analysis.unsatisfied.len());
+
+    let overridden_by_forbid = decision == Decision::Deny && !determining_policies.is_empty();
+    let suggestion = match (&schema, expected, decision) {
+        (Some(_), Decision::Allow, Decision::Deny) if !overridden_by_forbid => candidate_permits.iter()
+            .take(MAX_SUGGESTION_CANDIDATES)
+            .find_map(|candidate| {
+                suggest_tweaks(candidate, request_args, &entities_value, policies, &schema, verbose)
+            }),
+        _ => None,
+    };
+
+    Ok(json!({
+        "decision": format!("{:?}", decision),
+        "expected": format!("{:?}", expected),
+        "determining_policies": determining_policies,
+        "candidate_permits": candidate_permits.iter().map(|candidate| json!({
+            "id": candidate.id,
+            "policy": candidate.source,
+            "unsatisfied": candidate.unsatisfied.iter().map(|unsatisfied| {
+                let mut json = json!({ "kind": unsatisfied.kind, "condition": unsatisfied.condition });
+                if let Some(error) = &unsatisfied.error {
+                    json["error"] = json!(error);
+                }
+                json
+            }).collect::<Vec<_>>(),
+        })).collect::<Vec<_>>(),
+        "suggestion": suggestion,
+    }))
+}
+
+fn effect_name(effect: Effect) -> &'static str {
+    match effect {
+        Effect::Permit => "permit",
+        Effect::Forbid => "forbid",
+    }
+}
+
+fn effect_of(prepared: &PreparedInputs, analysis: &PolicyAnalysis) -> Option<Effect> {
+    let id = cedar_policy::PolicyId::from_str(&analysis.id).ok()?;
+    prepared.policy_set.policy(&id).map(|policy| policy.effect())
+}
+
+/// Evaluate each scope constraint and condition of every static policy against the request
+fn analyze_policies(policies: &str, prepared: &PreparedInputs, request: &Request) -> anyhow::Result<Vec<PolicyAnalysis>> {
+    let cst = parse_policies(policies).map_err(|errs| anyhow::anyhow!("failed to parse policies: {:?}", errs))?;
+    let text = |node_range: &std::ops::Range<usize>| policies[node_range.clone()].to_string();
+    let evaluate = |source: &str| -> Result<EvalResult, String> {
+        let expr = Expression::from_str(source).map_err(|e| e.to_string())?;
+        eval_expression(request, &prepared.entities, &expr).map_err(|e| e.to_string())
+    };
+
+    let mut analyses = vec![];
+    let cst_policies = cst.as_inner().map(|cst| cst.0.as_slice()).unwrap_or_default();
+    for (i, policy_node) in cst_policies.iter().enumerate() {
+        let id = format!("policy{}", i);
+        // templates do not take part in authorization until they are linked
+        let is_static = cedar_policy::PolicyId::from_str(&id).ok()
+            .and_then(|id| prepared.policy_set.policy(&id))
+            .is_some();
+        let policy = match policy_node.as_inner() {
+            Some(policy) if is_static => policy,
+            _ => continue,
+        };
+
+        let mut unsatisfied = vec![];
+        for variable in policy.variables.iter() {
+            let (var, (op, rhs)) = match variable.as_inner() {
+                Some(cst::VariableDef { variable: var, ineq: Some(ineq), .. }) => {
+                    match var.as_inner().and_then(Var::from_ident) {
+                        Some(var) => (var, ineq),
+                        None => continue,
+                    }
+                }
+                _ => continue,
+            };
+            let condition = text(&variable.info.0);
+            let (satisfied, error) = as_satisfied(evaluate(&condition), true);
+            if !satisfied {
+                let tweak = evaluate(&text(&rhs.info.0)).ok()
+                    .and_then(|rhs| scope_tweak(var, *op, rhs));
+                unsatisfied.push(Unsatisfied { kind: "scope", condition, error, tweak });
+            }
+        }
+        for cond in policy.conds.iter().filter_map(ASTNode::as_inner) {
+            let expr = match &cond.expr {
+                Some(expr) => expr,
+                None => continue,
+            };
+            if let Some(cst::Ident::Unless) = cond.cond.as_inner() {
+                let condition = text(&expr.info.0);
+                let (satisfied, error) = as_satisfied(evaluate(&condition), false);
+                if !satisfied {
+                    unsatisfied.push(Unsatisfied { kind: "unless", condition, error, tweak: None });
+                }
+                continue;
+            }
+            for conjunct in conjuncts(expr) {
+                let condition = text(&conjunct.info.0);
+                let (satisfied, error) = as_satisfied(evaluate(&condition), true);
+                if !satisfied {
+                    let tweak = attribute_equality(conjunct).and_then(|(var, attr, rhs)| {
+                        let value = eval_result_to_json(evaluate(&text(&rhs.info.0)).ok()?)?;
+                        Some(Tweak::Attr(var, attr, value))
+                    });
+                    unsatisfied.push(Unsatisfied { kind: "when", condition, error, tweak });
+                }
+            }
+        }
+        analyses.push(PolicyAnalysis { id, source: text(&policy_node.info.0), unsatisfied });
+    }
+    Ok(analyses)
+}
+
+/// Whether an evaluation produced `wanted`, along with the error if it failed
+fn as_satisfied(result: Result<EvalResult, String>, wanted: bool) -> (bool, Option<String>) {
+    match result {
+        Ok(EvalResult::Bool(value)) => (value == wanted, None),
+        Ok(other) => (false, Some(format!("expected a boolean, got {:?}", other))),
+        Err(e) => (false, Some(e)),
+    }
+}
+
+/// The top-level `&&` conjuncts of a `when` condition, which can be reported individually
+fn conjuncts(expr: &ASTNode<Option<cst::Expr>>) -> Vec<&ASTNode<Option<cst::Relation>>> {
+    if let Some(cst::ExprData::Or(or)) = expr.as_inner().map(|expr| expr.expr.as_ref()) {
+        if let Some(or) = or.as_inner().filter(|or| or.extended.is_empty()) {
+            if let Some(and) = or.initial.as_inner() {
+                return std::iter::once(&and.initial).chain(and.extended.iter()).collect();
+            }
+        }
+    }
+    vec![]
+}
+
+fn scope_tweak(var: Var, op: cst::RelOp, rhs: EvalResult) -> Option<Tweak> {
+    let uid = match rhs {
+        EvalResult::EntityUid(uid) => uid,
+        // of a set of actions, any one will do
+        EvalResult::Set(set) => match set.iter().next() {
+            Some(EvalResult::EntityUid(uid)) => uid.clone(),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    match (op, var) {
+        (cst::RelOp::Eq, _) | (cst::RelOp::In, Var::Action) => Some(Tweak::Request(var, uid)),
+        (cst::RelOp::In, _) => Some(Tweak::Parent(var, uid)),
+        _ => None,
+    }
+}
+
+/// Match a condition of the form `principal.attr == value` (or `resource` or `context`)
+fn attribute_equality(relation: &ASTNode<Option<cst::Relation>>) -> Option<(Var, String, &ASTNode<Option<cst::Add>>)> {
+    let (lhs, rhs) = match relation.as_inner()? {
+        cst::Relation::Common { initial, extended } => match extended.as_slice() {
+            [(cst::RelOp::Eq, rhs)] => (initial, rhs),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let lhs = lhs.as_inner().filter(|add| add.extended.is_empty())?;
+    let mult = lhs.initial.as_inner().filter(|mult| mult.extended.is_empty())?;
+    let unary = mult.initial.as_inner().filter(|unary| unary.op.is_none())?;
+    let member = unary.item.as_inner()?;
+    let var = match member.item.as_inner()? {
+        cst::Primary::Name(name) => {
+            let name = name.as_inner().filter(|name| name.path.is_empty())?;
+            Var::from_ident(name.name.as_inner()?).filter(|var| *var != Var::Action)?
+        }
+        _ => return None,
+    };
+    let attr = match member.access.as_slice() {
+        [access] => match access.as_inner()? {
+            cst::MemAccess::Field(field) => match field.as_inner()? {
+                cst::Ident::Ident(attr) => attr.to_string(),
+                _ => return None,
+            },
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some((var, attr, rhs))
+}
+
+fn eval_result_to_json(result: EvalResult) -> Option<Value> {
+    match result {
+        EvalResult::Bool(value) => Some(json!(value)),
+        EvalResult::Long(value) => Some(json!(value)),
+        EvalResult::String(value) => Some(json!(value)),
+        EvalResult::EntityUid(uid) => Some(uid_json(&uid)),
+        _ => None,
+    }
+}
+
+fn uid_json(uid: &EntityUid) -> Value {
+    json!({ "__entity": { "type": uid.type_name().to_string(), "id": uid.id().as_ref() } })
+}
+
+/// Apply the tweaks that satisfy `candidate` and return them as a suggestion if, validated
+/// against the schema, they flip the decision to Allow
+fn suggest_tweaks(candidate: &PolicyAnalysis,
+                  request_args: &RequestArgs,
+                  entities: &Value,
+                  policies: &str,
+                  schema: &Option<SchemaSource>,
+                  verbose: bool) -> Option<Value> {
+    let tweaks: Vec<&Tweak> = candidate.unsatisfied.iter()
+        .map(|unsatisfied| unsatisfied.tweak.as_ref())
+        .collect::<Option<_>>()?;
+
+    let mut principal = request_args.principal.clone();
+    let mut action = request_args.action.clone();
+    let mut resource = request_args.resource.clone();
+    let mut context: Value = match &request_args.context_json {
+        Some(context_json) => serde_json::from_str(context_json).ok()?,
+        None => json!({}),
+    };
+    let mut entities = entities.clone();
+    let mut changes = vec![];
+
+    // change the request's entities first, so entity changes apply to the new ones
+    for tweak in &tweaks {
+        if let Tweak::Request(var, uid) = tweak {
+            match var {
+                Var::Principal => principal = Some(uid.to_string()),
+                Var::Action => action = Some(uid.to_string()),
+                Var::Resource => resource = Some(uid.to_string()),
+                Var::Context => return None,
+            }
+            changes.push(format!("use {} {}", var.name(), uid));
+        }
+    }
+    for tweak in &tweaks {
+        match tweak {
+            Tweak::Request(..) => {}
+            Tweak::Attr(Var::Context, attr, value) => {
+                context.as_object_mut()?.insert(attr.clone(), value.clone());
+                changes.push(format!("set context.{} to {}", attr, value));
+            }
+            Tweak::Parent(var, parent) => {
+                let uid = if *var == Var::Principal { &principal } else { &resource };
+                let entity = find_or_add_entity(&mut entities, uid.as_deref()?)?;
+                entity["parents"].as_array_mut()?.push(uid_json(parent)["__entity"].clone());
+                changes.push(format!("add {} to the parents of {} {}", parent, var.name(), uid.as_deref()?));
+            }
+            Tweak::Attr(var, attr, value) => {
+                let uid = if *var == Var::Principal { &principal } else { &resource };
+                let entity = find_or_add_entity(&mut entities, uid.as_deref()?)?;
+                entity["attrs"].as_object_mut()?.insert(attr.clone(), value.clone());
+                changes.push(format!("set {}.{} to {}", var.name(), attr, value));
+            }
+        }
+    }
+
+    let tweaked_request = RequestArgs {
+        principal,
+        action,
+        resource,
+        context_json: Some(context.to_string()),
+        correlation_id: None,
+        parsed_uids: None,
+    };
+    let prepared = PreparedInputs::prepare(&PolicySource::Text(policies.to_owned()),
+                                           JsonSource::Value(entities),
+                                           schema.clone(),
+                                           EntityValidation::Strict,
+                                           verbose).ok()?;
+    let options = AuthzOptions {
+        include_diagnostics: false,
+        include_metrics: false,
+        entity_validation: EntityValidation::Strict,
+        unknown_entities: UnknownEntities::Ignore,
+        policy_errors: PolicyErrors::Skip,
+        validate_request: false,
+        include_policy_results: false,
+        include_denial_class: false,
+        include_attributes: None,
+        include_missing_entities: false,
+        generate_correlation_ids: false,
+        threads: 1,
+    };
+    let response = execute_authorization_request(&tweaked_request,
+                                                 &prepared.policy_set,
+                                                 &prepared.entities,
+                                                 &prepared.schema,
+                                                 &options,
+                                                 None,
+                                                 verbose).ok()?;
+    match response.decision() {
+        Decision::Allow => Some(json!({ "policy_id": candidate.id, "changes": changes })),
+        Decision::Deny => None,
+    }
+}
+
+/// The entity with `uid` in a list of entities in Cedar's JSON entity format, added if missing
+fn find_or_add_entity<'a>(entities: &'a mut Value, uid: &str) -> Option<&'a mut Value> {
+    let uid = EntityUid::from_str(uid).ok()?;
+    let entities = entities.as_array_mut()?;
+    let position = entities.iter()
+        .position(|entity| entity.get("uid").and_then(parse_entity_uid).as_ref() == Some(&uid));
+    let position = match position {
+        Some(position) => position,
+        None => {
+            entities.push(json!({ "uid": uid_json(&uid)["__entity"], "attrs": {}, "parents": [] }));
+            entities.len() - 1
+        }
+    };
+    let entity = &mut entities[position];
+    for (key, empty) in [("attrs", json!({})), ("parents", json!([]))] {
+        if entity.get(key).is_none() {
+            entity[key] = empty;
+        }
+    }
+    Some(entity)
+}
@@ -0,0 +1,98 @@
+//! An allowlist of the Cedar extension functions policies may call, so operators can keep a
+//! deployment's policies to the functions they support, e.g. without `ip` and its methods.
+//!
+//! A policy's calls are found in its AST, where methods such as `isInRange` and `lessThan` are
+//! extension functions too, so each function and method is allowed by name.  An engine with an
+//! allowlist rejects policies that call other functions when they're loaded, and validation
+//! reports each call as an error.
+
+use std::collections::BTreeSet;
+use std::ops::Range;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use cedar_policy_core::ast::{ExprKind, Name};
+use cedar_policy_core::extensions::Extensions;
+use cedar_policy_core::parser::parse_policyset;
+
+use crate::errors::{self, ErrorCode};
+
+#[derive(Debug, Clone)]
+pub struct ExtensionAllowlist {
+    allowed: BTreeSet<String>,
+}
+
+/// A call of an extension function that isn't allowed
+#[derive(Debug)]
+pub struct Violation {
+    pub policy_id: String,
+    pub function: String,
+    /// where in the policies' text the call is, when Cedar reports it
+    pub range: Option<Range<usize>>,
+}
+
+impl Violation {
+    pub fn message(&self) -> String {
+        format!("the extension function '{}' isn't allowed", self.function)
+    }
+}
+
+impl ExtensionAllowlist {
+    /// An allowlist of the named functions, each one of Cedar's extension functions
+    pub fn new(functions: Vec<String>) -> Result<Self> {
+        let extensions = Extensions::all_available();
+        for function in &functions {
+            let known = Name::from_str(function).is_ok_and(|name| extensions.func(&name).is_ok());
+            if !known {
+                return Err(anyhow!("invalid allowed_extension_functions: '{function}' isn't a Cedar extension function"));
+            }
+        }
+        Ok(ExtensionAllowlist { allowed: functions.into_iter().collect() })
+    }
+
+    /// The calls of functions the allowlist doesn't allow, in the order of the policies
+    pub fn violations(&self, policies: &str) -> Result<Vec<Violation>> {
+        let ast = parse_policyset(policies)
+            .map_err(|errs| errors::coded(ErrorCode::PolicyParse, format!("policy parse errors:\n{:?}", errs)))?;
+        let mut violations = vec![];
+        for template in ast.all_templates() {
+            let condition = template.condition();
+            for expr in condition.subexpressions() {
+                let ExprKind::ExtensionFunctionApp { op, .. } = expr.expr_kind() else { continue };
+                let function = op.function_name.to_string();
+                if !self.allowed.contains(&function) {
+                    violations.push(Violation {
+                        policy_id: template.id().to_string(),
+                        function,
+                        range: expr.source_info().as_ref().map(|info| info.range_start()..info.range_end()),
+                    });
+                }
+            }
+        }
+        violations.sort_by_key(|violation| {
+            let policy = violation.policy_id.strip_prefix("policy").and_then(|i| i.parse().ok()).unwrap_or(usize::MAX);
+            (policy, violation.range.as_ref().map_or(0, |range| range.start))
+        });
+        Ok(violations)
+    }
+
+    /// Check that the policies call only allowed functions, naming the policies in the error by
+    /// `policy_id` of the ids Cedar assigns them
+    pub fn check(&self, policies: &str, policy_id: impl Fn(&str) -> String) -> Result<()> {
+        let violations = self.violations(policies)?;
+        if violations.is_empty() {
+            return Ok(());
+        }
+        let calls: Vec<String> = violations.iter()
+            .map(|violation| format!("{} calls '{}'", policy_id(&violation.policy_id), violation.function))
+            .collect();
+        let allowed = if self.allowed.is_empty() {
+            "none".to_owned()
+        } else {
+            self.allowed.iter().cloned().collect::<Vec<_>>().join(", ")
+        };
+        Err(errors::coded(ErrorCode::Validation,
+                          format!("disallowed extension functions: {}; the allowed extension functions are: \
+                                   {allowed}", calls.join(", "))))
+    }
+}
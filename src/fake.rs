@@ -0,0 +1,230 @@
+//! A test double for `CedarEngine` that decides requests by matching them against scripted rules
+//! instead of evaluating policies, so tests of code that enforces decisions don't need policies.
+//!
+//! Its responses are built and serialized the way the engine's are, so they have the same shape.
+
+// pyo3 0.19's `#[pymethods]` expansion trips this lint on newer compilers
+#![allow(non_local_definitions)]
+
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use anyhow::{Context, Error, Result};
+use cedar_policy::{Decision, PolicyId, Response};
+use pyo3::prelude::*;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::errors::{self, ErrorCode};
+use crate::fingerprint::{self, Fingerprints};
+use crate::{convert_requests, serialize_batch_evaluation};
+use crate::{AuthzOptions, AuthzResponse, BatchEvaluation, EntityValidation, PolicyErrors, RequestArgs,
+            UnknownEntities};
+
+/// What a rule, or the default, answers
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Scripted {
+    #[serde(default = "deny", deserialize_with = "deserialize_decision")]
+    decision: Decision,
+    /// The ids of the policies reported as determining the decision
+    #[serde(default)]
+    reason: Vec<String>,
+    /// Policy evaluation errors reported in the diagnostics
+    #[serde(default)]
+    errors: Vec<String>,
+    /// An error that fails the request, as an invalid request would
+    #[serde(default)]
+    error: Option<String>,
+}
+
+fn deny() -> Decision {
+    Decision::Deny
+}
+
+fn deserialize_decision<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Decision, D::Error> {
+    match String::deserialize(deserializer)?.as_str() {
+        "Allow" => Ok(Decision::Allow),
+        "Deny" => Ok(Decision::Deny),
+        other => Err(serde::de::Error::custom(format!("invalid decision '{other}'; expected one of: Allow, Deny"))),
+    }
+}
+
+/// A rule: the requests it matches, by pattern, and what it answers them
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Rule {
+    #[serde(default)]
+    principal: Option<String>,
+    #[serde(default)]
+    action: Option<String>,
+    #[serde(default)]
+    resource: Option<String>,
+    /// Attributes the request's context must have, with these values
+    #[serde(default)]
+    context: Option<Map<String, Value>>,
+    #[serde(flatten)]
+    scripted: Scripted,
+}
+
+impl Rule {
+    fn matches(&self, request: &RequestArgs, context: &Map<String, Value>) -> bool {
+        let matches = |pattern: &Option<String>, value: &Option<String>| {
+            pattern.as_deref().is_none_or(|pattern| glob_matches(pattern, value.as_deref().unwrap_or_default()))
+        };
+        matches(&self.principal, &request.principal)
+            && matches(&self.action, &request.action)
+            && matches(&self.resource, &request.resource)
+            && self.context.iter().flatten().all(|(name, value)| context.get(name) == Some(value))
+    }
+}
+
+/// Whether `value` matches `pattern`, in which `*` matches any run of characters
+fn glob_matches(pattern: &str, value: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = value.strip_prefix(first) else { return false };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else { return rest.is_empty() };
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// Decides each request with the first rule that matches it, or the default
+#[pyclass]
+pub struct FakeEngine {
+    rules: Vec<Rule>,
+    default: Scripted,
+    /// the requests decided, for assertions on what was asked
+    requests: Mutex<Vec<Value>>,
+    /// the rules' fingerprint, reported as the policy set's
+    rules_fingerprint: u64,
+}
+
+#[pymethods]
+impl FakeEngine {
+    #[new]
+    #[pyo3(signature = (rules, default = None))]
+    fn new(rules: String, default: Option<String>) -> PyResult<Self> {
+        let parse = || -> Result<Self> {
+            let rules_fingerprint = fingerprint::of(&rules);
+            let rules = serde_json::from_str(&rules).context("invalid fake engine rules")?;
+            let default = match default {
+                Some(default) => serde_json::from_str(&default).context("invalid fake engine default")?,
+                None => Scripted { decision: Decision::Deny, reason: vec![], errors: vec![], error: None },
+            };
+            Ok(FakeEngine { rules, default, requests: Mutex::new(vec![]), rules_fingerprint })
+        };
+        parse().map_err(|e| errors::value_error(ErrorCode::InvalidArgument, errors::message(&e)))
+    }
+
+    #[pyo3(signature = (request, include_diagnostics = true, include_metrics = true,
+                        include_missing_entities = false))]
+    fn is_authorized(&self,
+                     request: &PyAny,
+                     include_diagnostics: bool,
+                     include_metrics: bool,
+                     include_missing_entities: bool) -> PyResult<String> {
+        let mut results = self.evaluate(std::iter::once(Ok(request)),
+                                        &options(include_diagnostics, include_metrics, include_missing_entities))?;
+        Ok(results.remove(0))
+    }
+
+    #[pyo3(signature = (requests, include_diagnostics = true, include_metrics = true,
+                        include_missing_entities = false))]
+    fn is_authorized_batch(&self,
+                           requests: &PyAny,
+                           include_diagnostics: bool,
+                           include_metrics: bool,
+                           include_missing_entities: bool) -> PyResult<Vec<String>> {
+        self.evaluate(requests.iter()?, &options(include_diagnostics, include_metrics, include_missing_entities))
+    }
+
+    /// The requests the engine has decided, as a JSON array
+    fn requests(&self) -> String {
+        Value::Array(self.requests.lock().expect("the requests are not poisoned").clone()).to_string()
+    }
+}
+
+fn options(include_diagnostics: bool, include_metrics: bool, include_missing_entities: bool) -> AuthzOptions {
+    AuthzOptions {
+        include_diagnostics,
+        include_metrics,
+        entity_validation: EntityValidation::default(),
+        unknown_entities: UnknownEntities::default(),
+        policy_errors: PolicyErrors::default(),
+        validate_request: false,
+        include_policy_results: false,
+        include_denial_class: false,
+        include_attributes: None,
+        include_missing_entities,
+        generate_correlation_ids: false,
+        threads: 1,
+    }
+}
+
+impl FakeEngine {
+    fn evaluate<'py>(&self,
+                     requests: impl Iterator<Item = PyResult<&'py PyAny>>,
+                     options: &AuthzOptions) -> PyResult<Vec<String>> {
+        let (request_args_vec, convert_input_duration) = convert_requests(requests)?;
+        let num_requests = request_args_vec.len();
+        let results = request_args_vec
+            .iter()
+            .map(|request| {
+                let t_decide = Instant::now();
+                let mut ans = self.decide(request, options);
+                if let Ok(ans) = ans.as_mut() {
+                    if let Some(metrics) = ans.metrics.as_mut() {
+                        metrics.insert(String::from("authz_duration_micros"), t_decide.elapsed().as_micros());
+                        metrics.insert(String::from("convert_input_duration_micros"),
+                                       convert_input_duration.as_micros());
+                    }
+                }
+                ans
+            })
+            .collect();
+        Ok(serialize_batch_evaluation(BatchEvaluation { errs: vec![], results }, num_requests, options))
+    }
+
+    fn decide(&self, request: &RequestArgs, options: &AuthzOptions) -> Result<AuthzResponse, Vec<Error>> {
+        let context: Map<String, Value> = match request.context_json.as_deref().map(serde_json::from_str) {
+            Some(Ok(Value::Object(context))) => context,
+            Some(_) => return Err(vec![errors::coded(ErrorCode::Request, "invalid request: invalid context")]),
+            None => Map::new(),
+        };
+        self.requests.lock().expect("the requests are not poisoned").push(serde_json::json!({
+            "principal": request.principal,
+            "action": request.action,
+            "resource": request.resource,
+            "context": context,
+            "correlation_id": request.correlation_id,
+        }));
+
+        let scripted = self.rules.iter()
+            .find(|rule| rule.matches(request, &context))
+            .map_or(&self.default, |rule| &rule.scripted);
+        if let Some(error) = &scripted.error {
+            return Err(vec![errors::coded(ErrorCode::Request, error)]);
+        }
+        let reason: HashSet<PolicyId> = scripted.reason.iter()
+            .map(|id| PolicyId::from_str(id).expect("any string is a policy id"))
+            .collect();
+        let errors: HashSet<String> = scripted.errors.iter().cloned().collect();
+        // the metrics an engine reports, which measure nothing for a fake
+        let metrics = HashMap::from([(String::from("build_request_duration_micros"), 0)]);
+        let mut ans = AuthzResponse::new(Response::new(scripted.decision, reason, errors),
+                                         metrics,
+                                         request.correlation_id.clone(),
+                                         options);
+        ans.fingerprints = Some(Fingerprints::of_policies(self.rules_fingerprint));
+        Ok(ans)
+    }
+}
@@ -0,0 +1,44 @@
+//! Fingerprints of the inputs that decided a request, reported in its response so that a decision
+//! log can be matched to the exact policy version, and for an engine the entities and schema, that
+//! produced each decision.
+//!
+//! Fingerprints are the XXH64, seeded with 0, of the inputs' source text, as 16 hex digits.  Unlike the
+//! standard library's hashers, XXH64 is a fixed algorithm, so a fingerprint doesn't change across
+//! cedarpy versions; it does change with any edit to the source, whitespace included.
+
+use serde::{Serialize, Serializer};
+use xxhash_rust::xxh64::xxh64;
+
+/// The fingerprints of the inputs that decided a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Fingerprints {
+    #[serde(serialize_with = "hex")]
+    pub policy_set: u64,
+    /// reported for the requests an engine decides, whose entities are loaded once
+    #[serde(serialize_with = "hex_option", skip_serializing_if = "Option::is_none")]
+    pub entities: Option<u64>,
+    #[serde(serialize_with = "hex_option", skip_serializing_if = "Option::is_none")]
+    pub schema: Option<u64>,
+}
+
+impl Fingerprints {
+    pub fn of_policies(policy_set: u64) -> Self {
+        Fingerprints { policy_set, entities: None, schema: None }
+    }
+}
+
+/// The fingerprint of an input's source
+pub fn of(source: &str) -> u64 {
+    xxh64(source.as_bytes(), 0)
+}
+
+fn hex<S: Serializer>(fingerprint: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&format_args!("{:016x}", fingerprint))
+}
+
+fn hex_option<S: Serializer>(fingerprint: &Option<u64>, serializer: S) -> Result<S::Ok, S::Error> {
+    match fingerprint {
+        Some(fingerprint) => hex(fingerprint, serializer),
+        None => serializer.serialize_none(),
+    }
+}
@@ -0,0 +1,65 @@
+//! Options for pretty-printing policies, validated before they reach Cedar's formatter, which
+//! misbehaves on values such as a negative indent, and conversion of policies in Cedar's JSON
+//! form (the EST) to text for formatting.
+
+use anyhow::{anyhow, Context, Result};
+use cedar_policy_formatter::Config;
+use serde::{Deserialize, Serialize};
+
+use crate::convert;
+
+const MAX_LINE_WIDTH: usize = 10_000;
+const MAX_INDENT_WIDTH: usize = 64;
+
+/// The formatter's options; new options added to Cedar's formatter belong here too
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct FormatterOptions {
+    /// The maximum length of a line, in characters
+    pub line_width: usize,
+    /// The number of spaces per level of indentation
+    pub indent_width: usize,
+}
+
+impl Default for FormatterOptions {
+    fn default() -> Self {
+        FormatterOptions { line_width: 80, indent_width: 2 }
+    }
+}
+
+impl FormatterOptions {
+    /// Parse options from a JSON object, with the options it leaves out at their defaults
+    pub fn from_json(options: &str) -> Result<Self> {
+        serde_json::from_str(options).context("failed to parse the formatter options")
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if !(1..=MAX_LINE_WIDTH).contains(&self.line_width) {
+            return Err(anyhow!("invalid line_width {}; expected 1 to {}", self.line_width, MAX_LINE_WIDTH));
+        }
+        if self.indent_width > MAX_INDENT_WIDTH.min(self.line_width) {
+            return Err(anyhow!("invalid indent_width {}; expected 0 to {}",
+                               self.indent_width, MAX_INDENT_WIDTH.min(self.line_width)));
+        }
+        Ok(())
+    }
+
+    /// Cedar's formatter config for the options, which are validated first
+    pub fn to_config(self) -> Result<Config> {
+        self.validate()?;
+        Ok(Config {
+            line_width: self.line_width,
+            indent_width: self.indent_width as isize,
+        })
+    }
+}
+
+/// Convert policies in Cedar's JSON form, either one policy object or an array of them, to Cedar
+/// text, in the order given
+pub fn est_to_text(policies: &str) -> Result<String> {
+    let texts: Vec<String> = convert::json_to_text(policies)?
+        .into_iter()
+        .map(|converted| converted.text)
+        .collect();
+    Ok(texts.join("\n\n"))
+}
@@ -0,0 +1,154 @@
+//! The entities as a graph, for visualizing and sanity-checking an entity model with standard
+//! graph tooling: a node for each entity, an edge from each entity to each of its parents, and an
+//! edge for each attribute that refers to an entity, in records and sets too.  Entities that are
+//! referred to but aren't in the entities are nodes too, marked as missing.
+
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Error, Result};
+use cedar_policy::EntityUid;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::permissive::parse_entity_uid;
+
+/// How the graph is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    Json,
+    Dot,
+}
+
+impl FromStr for GraphFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "json" => Ok(GraphFormat::Json),
+            "dot" => Ok(GraphFormat::Dot),
+            _ => Err(Error::msg(format!("invalid output format '{}'; expected one of: json, dot", s))),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Node {
+    id: String,
+    #[serde(rename = "type")]
+    entity_type: String,
+    /// Whether the entity is referred to, as a parent or by an attribute, but isn't in the entities
+    missing: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Edge {
+    source: String,
+    target: String,
+    /// `parent`, or `attribute` for an attribute that refers to the target
+    kind: &'static str,
+    /// The path of the attribute, e.g. `owner` or `department.manager`, for an attribute edge
+    attribute: Option<String>,
+}
+
+/// The nodes, in the order the entities appear followed by the missing entities, and the edges
+#[derive(Debug, Default, Serialize)]
+pub struct EntityGraph {
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+}
+
+/// The graph of entities in Cedar's JSON entity format
+pub fn entity_graph(entities: &Value) -> Result<EntityGraph> {
+    let entities = entities.as_array().ok_or_else(|| anyhow!("entities must be a JSON array"))?;
+    let mut graph = EntityGraph::default();
+    for entity in entities {
+        let uid_json = entity.get("uid").unwrap_or(&Value::Null);
+        let uid = parse_entity_uid(uid_json).ok_or_else(|| anyhow!("the entity uid {uid_json} is not valid"))?;
+        let source = uid.to_string();
+        let parents = entity.get("parents").and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+        for parent in parents {
+            let parent = parse_entity_uid(parent)
+                .ok_or_else(|| anyhow!("{source} has a parent that is not a valid uid: {parent}"))?;
+            graph.edges.push(Edge { source: source.clone(), target: parent.to_string(), kind: "parent", attribute: None });
+        }
+        if let Some(Value::Object(attrs)) = entity.get("attrs") {
+            for (name, value) in attrs {
+                attribute_edges(&source, name, value, &mut graph.edges);
+            }
+        }
+        graph.nodes.push(node(&uid, false));
+    }
+
+    let present: HashSet<String> = graph.nodes.iter().map(|node| node.id.clone()).collect();
+    let mut missing: HashSet<&str> = HashSet::new();
+    let mut missing_nodes = vec![];
+    for edge in &graph.edges {
+        if !present.contains(&edge.target) && missing.insert(&edge.target) {
+            let uid = EntityUid::from_str(&edge.target).expect("edge targets are valid uids");
+            missing_nodes.push(node(&uid, true));
+        }
+    }
+    graph.nodes.extend(missing_nodes);
+    Ok(graph)
+}
+
+fn node(uid: &EntityUid, missing: bool) -> Node {
+    Node { id: uid.to_string(), entity_type: uid.type_name().to_string(), missing }
+}
+
+/// An edge for each entity the attribute `path`'s value refers to, looking into records and sets
+fn attribute_edges(source: &str, path: &str, value: &Value, edges: &mut Vec<Edge>) {
+    match value {
+        Value::Object(fields) if fields.contains_key("__entity") || fields.contains_key("__expr") => {
+            if let Some(target) = parse_entity_uid(value.get("__entity").unwrap_or(value)) {
+                edges.push(Edge {
+                    source: source.to_owned(),
+                    target: target.to_string(),
+                    kind: "attribute",
+                    attribute: Some(path.to_owned()),
+                });
+            }
+        }
+        // extension values, e.g. `{"__extn": {"fn": "ip", "arg": "10.0.0.1"}}`, refer to no entity
+        Value::Object(fields) if fields.contains_key("__extn") => {}
+        Value::Object(fields) => {
+            for (name, value) in fields {
+                attribute_edges(source, &format!("{path}.{name}"), value, edges);
+            }
+        }
+        Value::Array(elements) => {
+            for element in elements {
+                attribute_edges(source, path, element, edges);
+            }
+        }
+        _ => {}
+    }
+}
+
+impl EntityGraph {
+    /// The graph in Graphviz's DOT language: missing entities are dashed, and attribute edges are
+    /// dashed and labeled with their attribute
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph entities {\n");
+        for node in &self.nodes {
+            let style = if node.missing { " [style=dashed]" } else { "" };
+            let _ = writeln!(dot, "    {}{};", quote(&node.id), style);
+        }
+        for edge in &self.edges {
+            let attributes = match &edge.attribute {
+                Some(attribute) => format!(" [label={}, style=dashed]", quote(attribute)),
+                None => String::new(),
+            };
+            let _ = writeln!(dot, "    {} -> {}{};", quote(&edge.source), quote(&edge.target), attributes);
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// A DOT quoted string
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
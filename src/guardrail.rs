@@ -0,0 +1,76 @@
+//! Guardrails: an engine's privileged layer of org-wide forbids, kept apart from the tenant-managed
+//! policies it's given and reloaded with, so no tenant policy can override or replace them.
+//!
+//! Cedar's forbids override its permits, so a guardrail that's a forbid holds whatever the tenant
+//! policies permit; a permit could be overridden by a tenant forbid, so guardrails may only forbid.
+//! They're parsed once, when the engine is created, with the ids `guardrail0`, `guardrail1`, ...,
+//! and added to the tenant policies of each load.  With an extension allowlist, the guardrails
+//! may only call the extension functions it allows, as the tenant policies may.
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context as _, Result};
+use cedar_policy::{Effect, PolicyId, PolicySet};
+
+use crate::extension_allowlist::ExtensionAllowlist;
+use crate::policy_set::{self, CompiledPolicies};
+use crate::{fingerprint, hash_str};
+
+pub struct Guardrails {
+    /// the guardrails, with their ids
+    policy_set: PolicySet,
+    hash: u64,
+    fingerprint: u64,
+}
+
+impl Guardrails {
+    pub fn parse(guardrails: &str, extension_allowlist: Option<&ExtensionAllowlist>) -> Result<Self> {
+        let parsed = policy_set::parse(guardrails).context("failed to parse guardrails")?;
+        if let Some(template) = parsed.templates().next() {
+            return Err(anyhow!("invalid guardrail {}: guardrails may not be templates",
+                               guardrail_id(&template.id().to_string())));
+        }
+        if let Some(allowlist) = extension_allowlist {
+            allowlist.check(guardrails, guardrail_id).context("invalid guardrails")?;
+        }
+        let mut policy_set = PolicySet::new();
+        for policy in parsed.policies() {
+            let id = PolicyId::from_str(&guardrail_id(&policy.id().to_string()))?;
+            if policy.effect() != Effect::Forbid {
+                return Err(anyhow!("invalid guardrail {id}: guardrails may only forbid"));
+            }
+            policy_set.add(policy.new_id(id.clone()))
+                .map_err(|e| anyhow!("failed to add guardrail {id}: {e}"))?;
+        }
+        Ok(Guardrails { policy_set, hash: hash_str(guardrails), fingerprint: fingerprint::of(guardrails) })
+    }
+
+    /// Parse the tenant policies and add the guardrails to them; the hash and fingerprint are of both
+    pub fn with_tenant_policies(&self, policies: &str) -> Result<CompiledPolicies> {
+        let tenant = CompiledPolicies::compile(policies)?;
+        let mut policy_set = (*tenant.policy_set).clone();
+        for guardrail in self.policy_set.policies() {
+            policy_set.add(guardrail.clone())
+                .map_err(|e| anyhow!("failed to add guardrail {} to the policies: {e}", guardrail.id()))?;
+        }
+        Ok(CompiledPolicies {
+            policy_set: Arc::new(policy_set),
+            hash: hash_str(&format!("{:016x}\n{:016x}", self.hash, tenant.hash)),
+            fingerprint: fingerprint::of(&format!("{:016x}\n{:016x}", self.fingerprint, tenant.fingerprint)),
+            parse_duration: tenant.parse_duration,
+        })
+    }
+
+    pub fn is_guardrail(&self, id: &PolicyId) -> bool {
+        self.policy_set.policy(id).is_some()
+    }
+}
+
+/// `guardrail0` for the guardrail parsed as `policy0`
+fn guardrail_id(id: &str) -> String {
+    match id.strip_prefix("policy") {
+        Some(index) => format!("guardrail{index}"),
+        None => id.to_owned(),
+    }
+}
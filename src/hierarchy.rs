@@ -0,0 +1,173 @@
+//! Checks of and queries on the entity hierarchy.
+//!
+//! Cedar rejects some hierarchy problems (cycles, duplicate uids) only when the entities are
+//! loaded, with a single error, and silently accepts others (dangling parents); checking the JSON
+//! directly finds all of them at once.  Membership queries are answered by the loaded entity
+//! store, whose ancestors are its transitive closure, so they agree with Cedar's `in`.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{anyhow, Context, Result};
+use cedar_policy::{Entities, EntityUid};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::permissive::parse_entity_uid;
+
+/// A problem found in the entity hierarchy
+#[derive(Debug, Serialize)]
+pub struct Finding {
+    /// The kind of problem, e.g. `cycle`
+    kind: &'static str,
+    /// The entity the problem was found on, as it appears in the entities when it isn't a valid uid
+    entity: String,
+    /// A description of the problem
+    message: String,
+}
+
+impl Finding {
+    fn new(kind: &'static str, entity: &str, message: String) -> Self {
+        Finding { kind, entity: entity.to_owned(), message }
+    }
+}
+
+/// Check the entities for hierarchy cycles, entities that are their own parent, duplicate uids,
+/// parents that aren't in the entities, invalid uids, and entities with more than `max_parents` parents
+pub fn check_entities(entities: &Value, max_parents: usize) -> Result<Vec<Finding>> {
+    let entities = entities.as_array().ok_or_else(|| anyhow!("entities must be a JSON array"))?;
+    let mut findings = vec![];
+
+    // parents of each entity, in the order the entities appear
+    let mut hierarchy: Vec<(String, Vec<String>)> = Vec::with_capacity(entities.len());
+    let mut seen: HashSet<String> = HashSet::with_capacity(entities.len());
+    for entity in entities {
+        let uid_json = entity.get("uid").unwrap_or(&Value::Null);
+        let Some(uid) = parse_entity_uid(uid_json).map(|uid| uid.to_string()) else {
+            findings.push(Finding::new("invalid_uid", &uid_json.to_string(), String::from("the entity's uid is not valid")));
+            continue;
+        };
+        if !seen.insert(uid.clone()) {
+            findings.push(Finding::new("duplicate_uid", &uid, format!("{uid} appears more than once")));
+            continue;
+        }
+        let parents_json = entity.get("parents").and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+        if parents_json.len() > max_parents {
+            findings.push(Finding::new("fan_out", &uid,
+                                       format!("{uid} has {} parents, more than {max_parents}", parents_json.len())));
+        }
+        let mut parents = Vec::with_capacity(parents_json.len());
+        for parent_json in parents_json {
+            match parse_entity_uid(parent_json).map(|parent| parent.to_string()) {
+                Some(parent) if parent == uid => {
+                    findings.push(Finding::new("self_parent", &uid, format!("{uid} is its own parent")));
+                }
+                Some(parent) => parents.push(parent),
+                None => findings.push(Finding::new("invalid_uid", &uid,
+                                                   format!("{uid} has a parent that is not a valid uid: {parent_json}"))),
+            }
+        }
+        hierarchy.push((uid, parents));
+    }
+
+    for (uid, parents) in &hierarchy {
+        for parent in parents.iter().filter(|parent| !seen.contains(*parent)) {
+            findings.push(Finding::new("dangling_parent", uid,
+                                       format!("{uid} has parent {parent}, which is not in the entities")));
+        }
+    }
+
+    for cycle in find_cycles(&hierarchy) {
+        findings.push(Finding::new("cycle", &cycle[0],
+                                   format!("the hierarchy has a cycle: {} -> {}", cycle.join(" -> "), cycle[0])));
+    }
+    Ok(findings)
+}
+
+/// Find cycles in the hierarchy, one for each back edge of a depth-first search of it, so that
+/// a hierarchy with cycles has at least one reported
+fn find_cycles(hierarchy: &[(String, Vec<String>)]) -> Vec<Vec<String>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        Unvisited,
+        OnPath,
+        Done,
+    }
+
+    let index: HashMap<&str, usize> = hierarchy.iter().enumerate().map(|(i, (uid, _))| (uid.as_str(), i)).collect();
+    let parents: Vec<Vec<usize>> = hierarchy
+        .iter()
+        .map(|(_, parents)| parents.iter().filter_map(|parent| index.get(parent.as_str()).copied()).collect())
+        .collect();
+
+    let mut state = vec![State::Unvisited; hierarchy.len()];
+    let mut cycles = vec![];
+    for start in 0..hierarchy.len() {
+        if state[start] != State::Unvisited {
+            continue;
+        }
+        // depth-first search with an explicit stack of (entity, next parent to visit), so that deep
+        // hierarchies don't overflow the call stack
+        let mut path: Vec<(usize, usize)> = vec![(start, 0)];
+        state[start] = State::OnPath;
+        while let Some((node, next)) = path.last_mut() {
+            let node = *node;
+            match parents[node].get(*next) {
+                Some(&parent) => {
+                    *next += 1;
+                    match state[parent] {
+                        State::Unvisited => {
+                            state[parent] = State::OnPath;
+                            path.push((parent, 0));
+                        }
+                        State::OnPath => {
+                            let from = path.iter().position(|(n, _)| *n == parent).expect("entities on the path are in it");
+                            cycles.push(path[from..].iter().map(|(n, _)| hierarchy[*n].0.clone()).collect());
+                        }
+                        State::Done => {}
+                    }
+                }
+                None => {
+                    state[node] = State::Done;
+                    path.pop();
+                }
+            }
+        }
+    }
+    cycles
+}
+
+/// Whether `uid` is `ancestor` or one of its descendants, i.e. Cedar's `uid in ancestor`
+pub fn is_in(entities: &Entities, uid: &EntityUid, ancestor: &EntityUid) -> bool {
+    uid == ancestor || entities.is_ancestor_of(ancestor, uid)
+}
+
+/// The entities that are descendants of `group`, sorted by uid; with `transitive` false, only the
+/// entities with `group` among the parents listed in `entities_json`
+pub fn members_of(entities: &Entities,
+                  entities_json: &Value,
+                  group: &EntityUid,
+                  transitive: bool) -> Result<Vec<String>> {
+    let mut members: Vec<String> = if transitive {
+        entities
+            .iter()
+            .map(|entity| entity.uid())
+            .filter(|uid| uid != group && entities.is_ancestor_of(group, uid))
+            .map(|uid| uid.to_string())
+            .collect()
+    } else {
+        entities_json
+            .as_array()
+            .context("entities must be a JSON array")?
+            .iter()
+            .filter(|entity| {
+                entity.get("parents")
+                    .and_then(Value::as_array)
+                    .is_some_and(|parents| parents.iter().any(|parent| parse_entity_uid(parent).as_ref() == Some(group)))
+            })
+            .filter_map(|entity| parse_entity_uid(entity.get("uid")?))
+            .map(|uid| uid.to_string())
+            .collect()
+    };
+    members.sort();
+    Ok(members)
+}
@@ -0,0 +1,226 @@
+//! Python callbacks for an engine's events — reloads, errors, and decisions — so integrations
+//! can update metrics, raise alerts, and invalidate caches as things happen, rather than by
+//! polling `stats()`.  Each callback is passed a JSON object describing the event.
+//!
+//! A callback can't break the call that triggered it: an exception it raises is reported to
+//! `sys.unraisablehook` and otherwise ignored, and a callback that raises
+//! `MAX_CONSECUTIVE_FAILURES` times in a row is unregistered.
+//!
+//! Callbacks run once the call's evaluation is done, on the calling thread and with the GIL held,
+//! so a slow callback delays its caller and every other thread waiting for the GIL.  Other
+//! threads' evaluations carry on, since they run with the GIL released, but they wait for the
+//! GIL to return their results.
+//!
+//! Decision and error events are emitted for each result, except when a call fails before any of
+//! its requests is evaluated, e.g. because the engine is overloaded: every request fails with the
+//! same errors, so each event is emitted once for the call, with `num_requests` and the
+//! `correlation_ids` of all of its requests.
+
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use anyhow::{Error, Result};
+use pyo3::prelude::*;
+use serde_json::{json, Value};
+
+use crate::errors::{self, ErrorCode};
+use crate::{AuthzResponse, BatchEvaluation};
+
+/// How many times in a row a callback may raise before it's unregistered
+pub const MAX_CONSECUTIVE_FAILURES: u32 = 10;
+
+/// An engine event that callbacks may be registered for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// A reload, whether it succeeded or failed
+    Reload,
+    /// A failed reload, or a result with errors
+    Error,
+    /// A result
+    Decision,
+}
+
+impl FromStr for Event {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "reload" => Ok(Event::Reload),
+            "error" => Ok(Event::Error),
+            "decision" => Ok(Event::Decision),
+            _ => Err(Error::msg(format!("invalid event '{}'; expected one of: reload, error, decision", s))),
+        }
+    }
+}
+
+struct Hook {
+    callback: PyObject,
+    consecutive_failures: u32,
+}
+
+/// The callbacks registered for each event
+#[derive(Default)]
+pub struct Hooks {
+    hooks: Mutex<[Option<Hook>; 3]>,
+    /// how many times callbacks have raised
+    failures: AtomicU64,
+}
+
+impl Hooks {
+    /// Register `callback` for `event`, replacing any registered before, or unregister it
+    pub fn register(&self, event: Event, callback: Option<PyObject>) {
+        self.lock()[event as usize] = callback.map(|callback| Hook { callback, consecutive_failures: 0 });
+    }
+
+    pub fn is_registered(&self, event: Event) -> bool {
+        self.lock()[event as usize].is_some()
+    }
+
+    /// How many times callbacks have raised
+    pub fn failures(&self) -> u64 {
+        self.failures.load(Ordering::Relaxed)
+    }
+
+    /// Call the callback registered for `event`, if any, with `payload`
+    pub fn emit(&self, py: Python<'_>, event: Event, payload: &Value) {
+        // the callback runs without the lock, so it may register callbacks itself
+        let Some(callback) = self.lock()[event as usize].as_ref().map(|hook| hook.callback.clone_ref(py)) else {
+            return;
+        };
+        let result = callback.call1(py, (payload.to_string(),));
+        let result = {
+            let mut hooks = self.lock();
+            // the callback may have been replaced while it ran
+            let slot = &mut hooks[event as usize];
+            let Some(hook) = slot.as_mut().filter(|hook| hook.callback.is(&callback)) else {
+                return;
+            };
+            match result {
+                Ok(_) => {
+                    hook.consecutive_failures = 0;
+                    Ok(())
+                }
+                Err(e) => {
+                    self.failures.fetch_add(1, Ordering::Relaxed);
+                    hook.consecutive_failures += 1;
+                    let unregistered = hook.consecutive_failures >= MAX_CONSECUTIVE_FAILURES;
+                    if unregistered {
+                        *slot = None;
+                    }
+                    Err((e, unregistered))
+                }
+            }
+        };
+        if let Err((e, unregistered)) = result {
+            e.write_unraisable(py, Some(callback.as_ref(py)));
+            if unregistered {
+                let message = format!("the {:?} callback raised {} times in a row, so it was unregistered",
+                                      event, MAX_CONSECUTIVE_FAILURES);
+                pyo3::exceptions::PyRuntimeWarning::new_err(message).write_unraisable(py, None);
+            }
+        }
+    }
+
+    /// Whether callbacks are registered for the events of results
+    pub fn reports_results(&self) -> bool {
+        self.is_registered(Event::Decision) || self.is_registered(Event::Error)
+    }
+
+    /// Emit the decision and error events of an evaluation of requests with `correlation_ids`
+    pub fn emit_results(&self, py: Python<'_>, evaluation: &BatchEvaluation, correlation_ids: &[Option<String>]) {
+        let (decisions, errors) = (self.is_registered(Event::Decision), self.is_registered(Event::Error));
+        if !decisions && !errors {
+            return;
+        }
+        if !evaluation.errs.is_empty() {
+            let codes: Vec<ErrorCode> = evaluation.errs.iter().map(ErrorCode::of).collect();
+            let messages: Vec<String> = evaluation.errs.iter().map(errors::message).collect();
+            self.emit_failed_call(py, correlation_ids, &codes, &messages);
+            return;
+        }
+        for (result, correlation_id) in evaluation.results.iter().zip(correlation_ids) {
+            match result {
+                Ok(ans) => {
+                    if decisions {
+                        self.emit(py, Event::Decision, &decision_payload(ans));
+                    }
+                    let messages: Vec<String> = ans.response.diagnostics().errors()
+                        .map(|e| errors::bounded(&e.to_string()).into_owned())
+                        .collect();
+                    if errors && !messages.is_empty() {
+                        let codes = vec![ans.diagnostics_code; messages.len()];
+                        self.emit(py, Event::Error, &request_error_payload(ans.correlation_id.as_deref(),
+                                                                           &codes,
+                                                                           &messages));
+                    }
+                }
+                Err(errs) => {
+                    let codes: Vec<ErrorCode> = errs.iter().map(ErrorCode::of).collect();
+                    let messages: Vec<String> = errs.iter().map(errors::message).collect();
+                    self.emit_errored(py, correlation_id.as_deref(), &codes, &messages);
+                }
+            }
+        }
+    }
+
+    /// Emit the events of a request that couldn't be decided
+    fn emit_errored(&self, py: Python<'_>, correlation_id: Option<&str>, codes: &[ErrorCode], messages: &[String]) {
+        self.emit(py, Event::Decision, &json!({
+            "decision": "NoDecision",
+            "correlation_id": correlation_id,
+            "reason": [],
+        }));
+        self.emit(py, Event::Error, &request_error_payload(correlation_id, codes, messages));
+    }
+
+    /// Emit the events of a call none of whose requests could be evaluated, once for all of them;
+    /// the `correlation_id` is the request's, when there's only one
+    fn emit_failed_call(&self,
+                        py: Python<'_>,
+                        correlation_ids: &[Option<String>],
+                        codes: &[ErrorCode],
+                        messages: &[String]) {
+        let correlation_id = match correlation_ids {
+            [correlation_id] => correlation_id.as_deref(),
+            _ => None,
+        };
+        let mut decision = json!({
+            "decision": "NoDecision",
+            "correlation_id": correlation_id,
+            "reason": [],
+        });
+        let mut error = request_error_payload(correlation_id, codes, messages);
+        for payload in [&mut decision, &mut error] {
+            payload["num_requests"] = json!(correlation_ids.len());
+            payload["correlation_ids"] = json!(correlation_ids);
+        }
+        self.emit(py, Event::Decision, &decision);
+        self.emit(py, Event::Error, &error);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, [Option<Hook>; 3]> {
+        self.hooks.lock().expect("the hooks are not poisoned")
+    }
+}
+
+/// The payload of a decision event: the decision, correlation id, determining policies, and
+/// fingerprints of the inputs that decided it, whether or not the result includes diagnostics
+fn decision_payload(ans: &AuthzResponse) -> Value {
+    json!({
+        "decision": ans.decision(),
+        "correlation_id": ans.correlation_id,
+        "reason": ans.response.diagnostics().reason().collect::<Vec<_>>(),
+        "fingerprints": ans.fingerprints,
+    })
+}
+
+fn request_error_payload(correlation_id: Option<&str>, codes: &[ErrorCode], messages: &[String]) -> Value {
+    json!({
+        "source": "request",
+        "correlation_id": correlation_id,
+        "error_codes": codes.iter().map(|code| code.as_str()).collect::<Vec<_>>(),
+        "errors": messages,
+        "retryable": errors::retryable(codes.iter().copied()),
+    })
+}
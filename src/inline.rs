@@ -0,0 +1,205 @@
+//! Tests that policies carry in their annotations, so a policy's test cases live next to it:
+//!
+//! ```cedar
+//! @test_allow("User::\"alice\", Action::\"view\", Photo::\"vacation.jpg\"")
+//! @test_deny("User::\"bob\", Action::\"view\", Photo::\"vacation.jpg\"; User::\"alice\", Action::\"delete\", Photo::\"vacation.jpg\"")
+//! permit(principal == User::"alice", action == Action::"view", resource);
+//! ```
+//!
+//! A case is a principal, action, and resource, and optionally a context as a JSON object,
+//! separated by commas; an annotation may hold several cases, separated by semicolons.  Since a
+//! policy may only have one annotation of each name, `test_allow_` and `test_deny_` followed by
+//! anything are test annotations too.  Each case is evaluated against the whole policy set.
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Error, Result};
+use cedar_policy::Decision;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::compiled_schema::SchemaSource;
+use crate::config::Configured;
+use crate::policy_set::PolicySource;
+use crate::{AuthzOptions, EntityValidation, JsonSource, PolicyErrors, PreparedInputs, RequestArgs, UnknownEntities};
+
+#[derive(Debug, Serialize)]
+pub struct InlineTest {
+    policy_id: String,
+    annotation: String,
+    principal: String,
+    action: String,
+    resource: String,
+    context: Value,
+    expected: String,
+    /// The decision the request got, or None when it couldn't be evaluated
+    decision: Option<String>,
+    passed: bool,
+    /// The policies that determined the decision
+    reason: Vec<String>,
+    errors: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InlineTestReport {
+    passed: usize,
+    failed: usize,
+    /// The tests, in the order of their policies and annotations
+    tests: Vec<InlineTest>,
+}
+
+/// A test case of an annotation, before it's evaluated
+struct Case {
+    policy_id: String,
+    annotation: String,
+    expected: Decision,
+    parts: Result<[String; 3]>,
+    context: Result<Value>,
+}
+
+/// Extract the test cases from the policies' annotations and evaluate them
+pub fn run_inline_tests(policies: &str,
+                        entities: JsonSource,
+                        schema: Option<SchemaSource>,
+                        verbose: bool) -> Result<InlineTestReport, Vec<Error>> {
+    let prepared = PreparedInputs::prepare(&PolicySource::Text(policies.to_owned()), entities, schema, EntityValidation::configured(), verbose)?;
+    let mut policies: Vec<_> = prepared.policy_set.policies().collect();
+    // policy ids are numbered in the order the policies appear
+    policies.sort_by_key(|policy| (policy.id().to_string().len(), policy.id().to_string()));
+
+    let mut cases = vec![];
+    for policy in policies {
+        let mut annotations: Vec<(&str, &str)> = policy.annotations().collect();
+        annotations.sort();
+        for (key, value) in annotations {
+            let Some(expected) = expected_decision(key) else { continue };
+            for case in split_outside_quotes(value, ';').into_iter().filter(|case| !case.trim().is_empty()) {
+                let (parts, context) = parse_case(case);
+                cases.push(Case {
+                    policy_id: policy.id().to_string(),
+                    annotation: key.to_owned(),
+                    expected,
+                    parts,
+                    context,
+                });
+            }
+        }
+    }
+
+    let options = AuthzOptions {
+        include_diagnostics: true,
+        include_metrics: false,
+        entity_validation: EntityValidation::configured(),
+        unknown_entities: UnknownEntities::configured(),
+        policy_errors: PolicyErrors::configured(),
+        validate_request: false,
+        include_policy_results: false,
+        include_denial_class: false,
+        include_attributes: None,
+        include_missing_entities: false,
+        generate_correlation_ids: false,
+        threads: 1,
+    };
+    let mut tests = vec![];
+    for case in cases {
+        let [principal, action, resource] = match &case.parts {
+            Ok(parts) => parts.clone(),
+            Err(_) => Default::default(),
+        };
+        let mut test = InlineTest {
+            policy_id: case.policy_id,
+            annotation: case.annotation,
+            principal,
+            action,
+            resource,
+            context: case.context.as_ref().cloned().unwrap_or(Value::Null),
+            expected: format!("{:?}", case.expected),
+            decision: None,
+            passed: false,
+            reason: vec![],
+            errors: vec![],
+        };
+        if let Some(e) = case.parts.as_ref().err().or(case.context.as_ref().err()) {
+            test.errors.push(format!("invalid test case: {e}"));
+            tests.push(test);
+            continue;
+        }
+        let request = RequestArgs {
+            principal: Some(test.principal.clone()),
+            action: Some(test.action.clone()),
+            resource: Some(test.resource.clone()),
+            context_json: Some(test.context.to_string()),
+            correlation_id: None,
+            parsed_uids: None,
+        };
+        match prepared.evaluate(vec![request], Duration::ZERO, &options, None, verbose).remove(0) {
+            Ok(ans) => {
+                let diagnostics = ans.response.diagnostics();
+                let mut reason: Vec<String> = diagnostics.reason().map(ToString::to_string).collect();
+                reason.sort_by_key(|id| (id.len(), id.clone()));
+                test.decision = Some(format!("{:?}", ans.decision()));
+                test.passed = ans.decision() == case.expected;
+                test.reason = reason;
+                test.errors = diagnostics.errors().map(|e| e.to_string()).collect();
+            }
+            Err(errs) => test.errors = errs.iter().map(crate::errors::message).collect(),
+        }
+        tests.push(test);
+    }
+    let passed = tests.iter().filter(|test| test.passed).count();
+    Ok(InlineTestReport { passed, failed: tests.len() - passed, tests })
+}
+
+fn expected_decision(key: &str) -> Option<Decision> {
+    let is_test = |name: &str| key == name || key.strip_prefix(name).is_some_and(|rest| rest.starts_with('_'));
+    if is_test("test_allow") {
+        Some(Decision::Allow)
+    } else if is_test("test_deny") {
+        Some(Decision::Deny)
+    } else {
+        None
+    }
+}
+
+/// The principal, action, and resource of a case, and its context, which defaults to empty
+fn parse_case(case: &str) -> (Result<[String; 3]>, Result<Value>) {
+    let parts: Vec<&str> = split_outside_quotes(case, ',').into_iter().map(str::trim).collect();
+    let (uids, context) = match parts.as_slice() {
+        [principal, action, resource] => ([principal, action, resource], None),
+        [principal, action, resource, context @ ..] => ([principal, action, resource], Some(context.join(","))),
+        _ => return (Err(anyhow!("expected a principal, action, and resource, and optionally a context, in '{}'",
+                                 case.trim())),
+                     Ok(Value::Null)),
+    };
+    let context = match context {
+        None => Ok(Value::Object(Map::new())),
+        Some(context) => match serde_json::from_str(&context) {
+            Ok(Value::Object(context)) => Ok(Value::Object(context)),
+            Ok(_) => Err(anyhow!("the context must be a JSON object, not '{context}'")),
+            Err(e) => Err(anyhow!("the context '{context}' is not valid JSON: {e}")),
+        },
+    };
+    (Ok(uids.map(|uid| (*uid).to_owned())), context)
+}
+
+/// Split on `delimiter`, except inside quoted strings and JSON objects and arrays
+fn split_outside_quotes(s: &str, delimiter: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let (mut start, mut depth, mut in_quotes, mut escaped) = (0, 0usize, false, false);
+    for (i, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_quotes => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            '{' | '[' if !in_quotes => depth += 1,
+            '}' | ']' if !in_quotes => depth = depth.saturating_sub(1),
+            c if c == delimiter && !in_quotes && depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
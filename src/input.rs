@@ -0,0 +1,114 @@
+//! Inputs given as bytes or files rather than Python objects, e.g. entity snapshots and NDJSON
+//! request batches, which are often stored gzip- or zstd-compressed.  The compression is detected
+//! from the data itself, so a file's name needn't say how it's compressed.
+//!
+//! Compressed inputs are decompressed as a stream and rejected once they pass
+//! `CEDARPY_MAX_INPUT_BYTES`, so a small input can't decompress to exhaust memory.  Like the JSON
+//! in them, they're decompressed and read in Rust, with the GIL released.
+
+use std::fs;
+use std::io::Read;
+
+use anyhow::{anyhow, Context as _, Error, Result};
+use flate2::read::MultiGzDecoder;
+use pyo3::exceptions::{PyIOError, PyTypeError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyList, PyString};
+
+use crate::errors::{self, ErrorCode};
+use crate::{config, pyjson, BatchDocumentRequest, RequestArgs};
+
+/// The bytes every gzip stream starts with
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+/// The bytes every zstd frame starts with
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Read a file, which may be compressed, as text
+pub fn read_file(path: &str) -> Result<String> {
+    let data = fs::read(path).with_context(|| format!("failed to read {}", path))?;
+    decode(&data).with_context(|| format!("failed to read {}", path))
+}
+
+/// Decompress `data` if it's compressed, and decode it as UTF-8
+pub fn decode(data: &[u8]) -> Result<String> {
+    let data = if data.starts_with(&GZIP_MAGIC) {
+        // several concatenated members, as `cat a.gz b.gz` makes, are read as one stream
+        decompress(MultiGzDecoder::new(data), "gzip")?
+    } else if data.starts_with(&ZSTD_MAGIC) {
+        let decoder = zstd::stream::read::Decoder::with_buffer(data)
+            .map_err(|e| anyhow!("invalid zstd data: {e}"))?;
+        decompress(decoder, "zstd")?
+    } else {
+        data.to_vec()
+    };
+    String::from_utf8(data).map_err(|e| Error::msg(format!("the input is not UTF-8: {}", e.utf8_error())))
+}
+
+/// Read all of a decompressing `reader`, up to `CEDARPY_MAX_INPUT_BYTES`
+fn decompress(reader: impl Read, codec: &str) -> Result<Vec<u8>> {
+    let limit = config::defaults().max_input_bytes;
+    let mut data = Vec::new();
+    reader.take(limit as u64 + 1).read_to_end(&mut data).map_err(|e| anyhow!("invalid {codec} data: {e}"))?;
+    if data.len() > limit {
+        return Err(anyhow!("the {codec} input decompresses to more than {limit} bytes, the most \
+                            CEDARPY_MAX_INPUT_BYTES allows"));
+    }
+    Ok(data)
+}
+
+/// Read text, e.g. JSON, given as a str, or as bytes or an `os.PathLike`, either of which may be
+/// compressed, or `None` when `source` is none of those
+pub fn read_text(source: &PyAny) -> PyResult<Option<String>> {
+    if let Ok(text) = source.downcast::<PyString>() {
+        return Ok(Some(text.to_str()?.to_owned()));
+    }
+    let text = if let Ok(data) = source.downcast::<PyBytes>() {
+        let data = data.as_bytes();
+        source.py().allow_threads(|| decode(data))
+    } else if source.hasattr("__fspath__")? {
+        let path: String = source.call_method0("__fspath__")?.extract()?;
+        source.py().allow_threads(|| read_file(&path))
+    } else {
+        return Ok(None);
+    };
+    text.map(Some).map_err(error)
+}
+
+/// Text given as a str, or read from bytes or a file by `read_text`, or the JSON of a list, e.g. of
+/// entities, converted by `pyjson` rather than with `json.dumps`
+pub struct TextInput(pub String);
+
+impl<'a> FromPyObject<'a> for TextInput {
+    fn extract(ob: &'a PyAny) -> PyResult<Self> {
+        if ob.downcast::<PyList>().is_ok() {
+            return Ok(TextInput(pyjson::to_json(ob)?.to_string()));
+        }
+        read_text(ob)?.map(TextInput).ok_or_else(|| {
+            PyTypeError::new_err(format!("expected a str, a list, bytes, or an os.PathLike, not {}",
+                                         ob.get_type().name().unwrap_or("unknown")))
+        })
+    }
+}
+
+/// An `OSError` for an input that couldn't be read, or a `ValueError` for one that's invalid
+pub fn error(err: Error) -> PyErr {
+    match err.downcast_ref::<std::io::Error>() {
+        Some(io_err) if io_err.kind() != std::io::ErrorKind::InvalidData => {
+            PyIOError::new_err(errors::message(&err))
+        }
+        _ => errors::value_error(ErrorCode::InvalidArgument, errors::message(&err)),
+    }
+}
+
+/// Parse NDJSON requests, one JSON request object per line; blank lines are skipped
+pub fn parse_requests(text: &str) -> Result<Vec<RequestArgs>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            let request: BatchDocumentRequest = serde_json::from_str(line)
+                .with_context(|| format!("failed to parse the request on line {}", index + 1))?;
+            Ok(RequestArgs::from(request))
+        })
+        .collect()
+}
@@ -0,0 +1,35 @@
+//! Attribution of a decision to the layers of a composed policy set: an engine's guardrails and its
+//! own policies, or the engines of `is_authorized_union`, so an operator can see at a glance whose
+//! policies decided a request, e.g. "denied by guardrails", and route an incident to its owners.
+
+use std::collections::BTreeMap;
+
+use cedar_policy::Decision;
+
+/// The layer of an engine's guardrails
+pub const GUARDRAILS: &str = "guardrails";
+/// The layer of an engine's own, e.g. tenant-managed, policies
+pub const POLICIES: &str = "policies";
+
+/// The name of the guardrails layer of an engine of a union
+pub fn engine_guardrails(engine: &str) -> String {
+    format!("{engine}.{GUARDRAILS}")
+}
+
+/// A summary of which layers determined the decision, e.g. "denied by guardrails", "allowed by
+/// app", or "denied by default" when no policy did
+pub fn decided_by(decision: Decision, reason_by_layer: &BTreeMap<String, Vec<String>>) -> String {
+    let verb = match decision {
+        Decision::Allow => "allowed",
+        Decision::Deny => "denied",
+    };
+    let layers: Vec<&str> = reason_by_layer.iter()
+        .filter(|(_, reason)| !reason.is_empty())
+        .map(|(layer, _)| layer.as_str())
+        .collect();
+    match layers.as_slice() {
+        [] => format!("{verb} by default"),
+        [layer] => format!("{verb} by {layer}"),
+        [layers @ .., last] => format!("{verb} by {} and {last}", layers.join(", ")),
+    }
+}
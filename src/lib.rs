@@ -1,14 +1,99 @@
-use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
 use std::str::FromStr;
-use std::time::Instant;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
 
 use anyhow::{Context as _, Error, Result};
 use cedar_policy::*;
-use cedar_policy_formatter::{Config, policies_str_to_pretty};
+use cedar_policy_formatter::policies_str_to_pretty;
+use pyo3::exceptions::PyIOError;
 use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyString};
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
+use crate::cache::RequestCache;
+use crate::compiled_schema::SchemaSource;
+use crate::config::Configured;
+use crate::denial::DenialClass;
+use crate::errors::ErrorCode;
+use crate::extension_allowlist::ExtensionAllowlist;
+use crate::fingerprint::Fingerprints;
+use crate::formatter::FormatterOptions;
+use crate::input::TextInput;
+use crate::permissive::DeclaredAttributes;
+use crate::policy_results::{policy_results, PolicyResult};
+use crate::policy_set::PolicySource;
+use crate::projection::AttributeProjection;
+use crate::rewrite::{Namespace, Renames};
+use crate::schema::SchemaModel;
+use crate::store::EntitySource;
+
+mod bisect;
+mod cache;
+mod changelog;
+mod columns;
+mod compile;
+mod compiled_schema;
+mod completion;
+mod config;
+mod conformance;
+mod convert;
+mod correlation;
+mod degrade;
+mod denial;
+mod duty;
+mod engine;
+mod entitlements;
+mod errors;
+mod evaluate;
+mod extension_allowlist;
+mod explain;
+mod fake;
+mod fingerprint;
+mod formatter;
+mod graph;
+mod guardrail;
+mod hierarchy;
+mod hooks;
+mod inline;
+mod input;
+mod layers;
+mod limit;
+mod load;
+mod mining;
+mod oracle;
+mod partial;
+mod permissive;
+mod policy_results;
+mod policy_set;
+mod pool;
+mod profile;
+mod projection;
+mod pyjson;
+mod relationships;
+mod request_validation;
+mod residual;
+mod response;
+mod resume;
+mod rewrite;
+mod schema;
+mod schema_syntax;
+mod sink;
+mod store;
+mod surface;
+mod table;
+mod transform;
+mod ttl;
+mod unmodeled;
+mod update;
+mod validate;
+mod view;
+mod warnings;
+
 /// Echo (return) the input string
 #[pyfunction]
 #[pyo3(signature = (s))]
@@ -16,21 +101,436 @@ fn echo(s: String) -> PyResult<String> {
     Ok(s)
 }
 
-// Pretty-print the input policy according to the input parameters.
+// Pretty-print the input policy according to the input parameters; with `input_format` "json"
+// the input is policies in Cedar's JSON form, which are converted to text first.
+#[pyfunction]
+#[pyo3(signature = (s, line_width = None, indent_width = None, options = None, input_format = "cedar"))]
+fn format_policies(py: Python<'_>,
+                   s: String,
+                   line_width: Option<i64>,
+                   indent_width: Option<i64>,
+                   options: Option<String>,
+                   input_format: &str) -> PyResult<String> {
+    let config = formatter_options_with_overrides(options, line_width, indent_width)?
+        .to_config()
+        .map_err(to_value_error)?;
+    if !matches!(input_format, "cedar" | "json") {
+        return Err(errors::value_error(ErrorCode::InvalidArgument, format!(
+            "invalid input format '{input_format}'; expected one of: cedar, json")));
+    }
+    py.allow_threads(|| {
+        let text = match input_format {
+            "json" => formatter::est_to_text(&s).map_err(|e| errors::locate(to_value_error(e), &s))?,
+            _ => s,
+        };
+        policies_str_to_pretty(&text, &config)
+            .map_err(|e| errors::locate(errors::value_error(ErrorCode::PolicyParse, e), &text))
+    })
+}
+
+/// Validate formatter options, given as a JSON object and overridden by `line_width` and
+/// `indent_width`, returning them with the defaults filled in as a JSON object
+#[pyfunction]
+#[pyo3(signature = (options = None, line_width = None, indent_width = None))]
+fn formatter_options(options: Option<String>, line_width: Option<i64>, indent_width: Option<i64>) -> PyResult<String> {
+    let options = formatter_options_with_overrides(options, line_width, indent_width)?;
+    options.validate().map_err(to_value_error)?;
+    Ok(serde_json::to_string(&options).expect("formatter options serialize to JSON"))
+}
+
+fn formatter_options_with_overrides(options: Option<String>,
+                                    line_width: Option<i64>,
+                                    indent_width: Option<i64>) -> PyResult<FormatterOptions> {
+    let mut options = match options {
+        Some(options) => FormatterOptions::from_json(&options).map_err(to_value_error)?,
+        None => FormatterOptions::default(),
+    };
+    let width = |name: &str, value: i64| {
+        usize::try_from(value).map_err(|_| errors::value_error(
+            ErrorCode::InvalidArgument, format!("invalid {name} {value}; expected a non-negative integer")))
+    };
+    if let Some(line_width) = line_width {
+        options.line_width = width("line_width", line_width)?;
+    }
+    if let Some(indent_width) = indent_width {
+        options.indent_width = width("indent_width", indent_width)?;
+    }
+    Ok(options)
+}
+
+/// Parse policies that may be incomplete, e.g. as they're typed, returning a JSON object of what
+/// structure could be made of each policy and diagnostics with spans
 #[pyfunction]
-#[pyo3(signature = (s, line_width, indent_width))]
-fn format_policies(s: String, line_width: usize, indent_width: isize) -> PyResult<String> {
-    let config = Config {
-        line_width,
-        indent_width,
+#[pyo3(signature = (text))]
+fn parse_policy_partial(py: Python<'_>, text: String) -> String {
+    let parse = py.allow_threads(|| partial::parse_policy_partial(&text));
+    serde_json::to_string(&parse).expect("partial parses serialize to JSON")
+}
+
+/// Check that policies parse, returning a JSON array of their parse errors, each with the policy
+/// it's in, its span, and the text of the span
+#[pyfunction]
+#[pyo3(signature = (policies))]
+fn check_parse(py: Python<'_>, policies: String) -> String {
+    let issues = py.allow_threads(|| partial::check_parse(&policies));
+    serde_json::to_string(&issues).expect("parse issues serialize to JSON")
+}
+
+/// Validate policies against a schema with Cedar's validator in `mode`, "strict" or
+/// "permissive", returning a JSON report of the errors and warnings; with
+/// `allowed_extension_functions`, calls of other extension functions are errors too
+#[pyfunction]
+#[pyo3(signature = (policies, schema, mode = "strict", allowed_extension_functions = None))]
+fn validate_policies(py: Python<'_>,
+                     policies: String,
+                     schema: SchemaSource,
+                     mode: &str,
+                     allowed_extension_functions: Option<Vec<String>>) -> PyResult<String> {
+    let mode = validate::parse_mode(mode).map_err(to_value_error)?;
+    let allowlist = allowed_extension_functions.map(ExtensionAllowlist::new).transpose().map_err(to_value_error)?;
+    let report = py.allow_threads(|| validate::validate_policies(&policies, &schema, mode, allowlist.as_ref()))
+        .map_err(to_value_error)?;
+    Ok(serde_json::to_string(&report).expect("validation reports serialize to JSON"))
+}
+
+/// Convert Cedar text to Cedar's JSON form, returning a JSON array of the policies, or with
+/// `structured` of `{"id", "text", "policy"}` entries, by the ids Cedar assigns the policies
+#[pyfunction]
+#[pyo3(signature = (policies, structured = false))]
+fn policies_to_json(policies: String, structured: bool) -> PyResult<String> {
+    let converted = convert::text_to_json(&policies).map_err(|e| errors::locate(to_value_error(e), &policies))?;
+    let converted = if structured {
+        serde_json::to_value(converted)
+    } else {
+        Ok(converted.into_iter().map(|converted| converted.policy).collect())
     };
+    Ok(converted.expect("converted policies serialize to JSON").to_string())
+}
 
-    match policies_str_to_pretty(&s, &config) {
-        Ok(s) => Ok(s),
-        Err(e) => Err(pyo3::exceptions::PyValueError::new_err(e.to_string())),
+/// Convert policies in Cedar's JSON form to Cedar text, returning the text, or with `structured`
+/// a JSON array of `{"id", "text", "policy"}` entries; policies given as `{"id", "policy"}`
+/// entries keep their ids, others are identified by position
+#[pyfunction]
+#[pyo3(signature = (policies, structured = false))]
+fn policies_from_json(policies: String, structured: bool) -> PyResult<String> {
+    let converted = convert::json_to_text(&policies).map_err(|e| errors::locate(to_value_error(e), &policies))?;
+    if structured {
+        return Ok(serde_json::to_string(&converted).expect("converted policies serialize to JSON"));
     }
+    let texts: Vec<String> = converted.into_iter().map(|converted| converted.text).collect();
+    Ok(texts.join("\n\n"))
 }
 
+/// Translate a schema in Cedar's schema syntax to its JSON form, returning the JSON
+#[pyfunction]
+#[pyo3(signature = (cedar_schema))]
+fn schema_to_json_str(cedar_schema: String) -> PyResult<String> {
+    let schema = schema_syntax::cedar_to_json(&cedar_schema).map_err(to_value_error)?;
+    Ok(schema.to_string())
+}
+
+/// Translate a schema in JSON form to Cedar's schema syntax, returning the text
+#[pyfunction]
+#[pyo3(signature = (json_schema))]
+fn schema_from_json_str(json_schema: String) -> PyResult<String> {
+    schema_syntax::json_to_cedar(&json_schema).map_err(to_value_error)
+}
+
+/// Document a schema, given in JSON form, returning a JSON model of its entity types' attributes,
+/// its actions' principal and resource types and context, and its hierarchies
+#[pyfunction]
+#[pyo3(signature = (schema))]
+fn schema_docs(schema: String) -> PyResult<String> {
+    let docs = schema::schema_docs(&schema).map_err(to_value_error)?;
+    Ok(serde_json::to_string(&docs).expect("schema docs serialize to JSON"))
+}
+
+/// The entity types, actions, attributes, and extension functions valid at a position in a
+/// policy, described by `cursor_scope`, according to `schema`, as a JSON object
+#[pyfunction]
+#[pyo3(signature = (schema, cursor_scope))]
+fn completion_context(schema: String, cursor_scope: String) -> PyResult<String> {
+    let completions = completion::completion_context(&schema, &cursor_scope).map_err(to_value_error)?;
+    Ok(serde_json::to_string(&completions).expect("completions serialize to JSON"))
+}
+
+/// Report every combination of principal type, action, and resource type that `schema` allows,
+/// with the policies whose scopes include it, as a JSON object
+#[pyfunction]
+#[pyo3(signature = (schema, policies))]
+fn authorization_surface(schema: String, policies: String) -> PyResult<String> {
+    let surface = surface::authorization_surface(&schema, &policies).map_err(to_value_error)?;
+    Ok(serde_json::to_string(&surface).expect("the authorization surface serializes to JSON"))
+}
+
+/// Generate a reproducible corpus of requests and entities for a schema and policies, for
+/// benchmarking, as a JSON object
+#[pyfunction]
+#[pyo3(signature = (schema, policies, n_requests, seed = 0, entities_per_type = 10))]
+fn generate_load(schema: String,
+                 policies: String,
+                 n_requests: usize,
+                 seed: u64,
+                 entities_per_type: usize) -> PyResult<String> {
+    let corpus = load::generate_load(&schema, &policies, n_requests, seed, entities_per_type)
+        .map_err(to_value_error)?;
+    Ok(serde_json::to_string(&corpus).expect("the corpus serializes to JSON"))
+}
+
+/// Split policies into their static policies, templates, and template links, returned as a JSON
+/// object of the three; with `input_format` "json" the input is policies in Cedar's JSON form,
+/// which may define template links
+#[pyfunction]
+#[pyo3(signature = (policies, input_format = "cedar"))]
+fn split_policy_source(policies: String, input_format: &str) -> PyResult<String> {
+    let split = match input_format {
+        "cedar" => convert::split_text(&policies),
+        "json" => convert::split_json(&policies),
+        other => return Err(errors::value_error(ErrorCode::InvalidArgument, format!(
+            "invalid input format '{other}'; expected one of: cedar, json"))),
+    }.map_err(to_value_error)?;
+    Ok(serde_json::to_string(&split).expect("split policies serialize to JSON"))
+}
+
+/// Suggest templates for the clusters of at least `min_cluster_size` static policies that differ only
+/// in the entities of their principal and resource scopes, returned as a JSON array of suggestions,
+/// each with the template and a template link for each policy it replaces
+#[pyfunction]
+#[pyo3(signature = (policies, min_cluster_size = 2))]
+fn suggest_templates(policies: String, min_cluster_size: usize) -> PyResult<String> {
+    let suggestions = mining::suggest_templates(&policies, min_cluster_size).map_err(to_value_error)?;
+    Ok(serde_json::to_string(&suggestions).expect("template suggestions serialize to JSON"))
+}
+
+/// Rewrite the static policies that `template` matches to links of it, returned as a JSON object of
+/// the rewritten policy set, in Cedar's JSON form, and the ids of the policies linked.  With
+/// `requests`, the rewritten policies' decisions for them, given `entities` and `schema`, are checked
+/// against the original policies'.
+#[pyfunction]
+#[pyo3(signature = (policies, template, requests = None, entities = None, schema = None, verbose = None))]
+fn templatize(policies: String,
+              template: &str,
+              requests: Option<&PyAny>,
+              entities: Option<TextInput>,
+              schema: Option<SchemaSource>,
+              verbose: Option<bool>) -> PyResult<String> {
+    let mut templatized = mining::templatize(&policies, template).map_err(to_value_error)?;
+    if let Some(requests) = requests {
+        let (request_args, _) = convert_requests(requests.iter()?)?;
+        let prepared = PreparedInputs::prepare(&PolicySource::Text(policies),
+                                               JsonSource::Text(entities.map(|entities| entities.0).unwrap_or_else(|| String::from("[]"))),
+                                               schema,
+                                               EntityValidation::default(),
+                                               config::verbose(verbose))
+            .map_err(|errs| errors::value_error_for(&errs))?;
+        let requests = request_args.iter()
+            .enumerate()
+            .map(|(i, request)| {
+                request.get_request(prepared.schema.as_ref())
+                    .with_context(|| errors::context(ErrorCode::Request, format!("invalid request {i}")))
+            })
+            .collect::<Result<Vec<Request>>>()
+            .map_err(|e| errors::value_error(ErrorCode::InvalidArgument, errors::message(&e)))?;
+        templatized.verify(&prepared.policy_set, &requests, &prepared.entities);
+    }
+    Ok(serde_json::to_string(&templatized).expect("templatized policies serialize to JSON"))
+}
+
+/// Check that policies convert between Cedar text and Cedar's JSON form without losing anything,
+/// returning a JSON report of what's lost; with `input_format` "json" the input is policies in
+/// JSON form, which are converted to text and back
+#[pyfunction]
+#[pyo3(signature = (policies, input_format = "cedar"))]
+fn check_round_trip(policies: String, input_format: &str) -> PyResult<String> {
+    let issues = match input_format {
+        "cedar" => convert::check_text_round_trip(&policies),
+        "json" => convert::check_json_round_trip(&policies),
+        other => return Err(errors::value_error(ErrorCode::InvalidArgument, format!(
+            "invalid input format '{other}'; expected one of: cedar, json"))),
+    }.map_err(to_value_error)?;
+    let report = json!({
+        "lossless": issues.is_empty(),
+        "issues": issues,
+    });
+    Ok(report.to_string())
+}
+
+fn to_value_error(e: Error) -> PyErr {
+    errors::value_error(ErrorCode::of(&e), errors::message(&e))
+}
+
+/// Check that the policies allow no request matching `request_pattern`, a JSON object, by
+/// evaluating every request the schema allows that it matches over a bounded entity model: the
+/// `entities`, or `entities_per_type` generated entities of each type and those the policies name.
+/// Returns a JSON report with the first request allowed, if there's one.
+#[pyfunction]
+#[pyo3(signature = (request_pattern, policies, schema, entities = None, entities_per_type = 3,
+                    contexts_per_request = 4, seed = 0, max_requests = 100000, verbose = None))]
+#[allow(clippy::too_many_arguments)]
+fn assert_never_allowed(py: Python<'_>,
+                        request_pattern: String,
+                        policies: String,
+                        schema: String,
+                        entities: Option<TextInput>,
+                        entities_per_type: usize,
+                        contexts_per_request: usize,
+                        seed: u64,
+                        max_requests: usize,
+                        verbose: Option<bool>) -> PyResult<String> {
+    let verbose = config::verbose(verbose);
+    let report = py.allow_threads(|| -> Result<oracle::NeverAllowedReport, Vec<Error>> {
+        let (entities_json, generated) = match entities {
+            Some(entities) => {
+                let entities: Vec<serde_json::Value> = serde_json::from_str(&entities.0)
+                    .context(errors::context(ErrorCode::Entities, "failed to parse entities"))
+                    .map_err(|e| vec![e])?;
+                (entities, false)
+            }
+            None => (oracle::model_entities(&schema, &policies, seed, entities_per_type).map_err(|e| vec![e])?, true),
+        };
+        let entities = serde_json::Value::from(entities_json.clone()).to_string();
+        let prepared = PreparedInputs::prepare(&PolicySource::Text(policies),
+                                               JsonSource::Text(entities),
+                                               Some(SchemaSource::Text(schema.clone())),
+                                               EntityValidation::default(),
+                                               verbose)?;
+        let parsed_schema = prepared.schema.as_ref().expect("a schema was provided");
+        let bounds = oracle::Bounds { contexts_per_request, seed, max_requests };
+        let mut report = oracle::check_never_allowed(&request_pattern, &prepared.policy_set, &schema, parsed_schema,
+                                                     &entities_json, &prepared.entities, &bounds)
+            .map_err(|e| vec![e])?;
+        if generated {
+            report.entities = Some(entities_json);
+        }
+        Ok(report)
+    }).map_err(|errs| errors::value_error_for(&errs))?;
+    Ok(serde_json::to_string(&report).expect("never-allowed reports serialize to JSON"))
+}
+
+/// Qualify the unqualified entity types referenced by policies with `namespace`,
+/// or with `unqualify`, strip `namespace` from the entity types qualified with it
+#[pyfunction]
+#[pyo3(signature = (policies, namespace, unqualify = false))]
+fn qualify_policies(policies: String, namespace: String, unqualify: bool) -> PyResult<String> {
+    let namespace = Namespace::new(&namespace, unqualify).map_err(to_value_error)?;
+    rewrite::rewrite_policies(&policies, &namespace).map_err(to_value_error)
+}
+
+/// Qualify (or unqualify) the entity types referenced by entities in Cedar's JSON entity format
+#[pyfunction]
+#[pyo3(signature = (entities, namespace, unqualify = false))]
+fn qualify_entities(entities: TextInput, namespace: String, unqualify: bool) -> PyResult<String> {
+    let namespace = Namespace::new(&namespace, unqualify).map_err(to_value_error)?;
+    rewrite_json(&entities.0, "entities", |entities| rewrite::rewrite_entities(entities, &namespace))
+}
+
+/// Qualify (or unqualify) the entity types referenced by a request given as a JSON object
+#[pyfunction]
+#[pyo3(signature = (request, namespace, unqualify = false))]
+fn qualify_request(request: String, namespace: String, unqualify: bool) -> PyResult<String> {
+    let namespace = Namespace::new(&namespace, unqualify).map_err(to_value_error)?;
+    rewrite_json(&request, "request", |request| rewrite::rewrite_request(request, &namespace))
+}
+
+/// Rename entity types and entities such as actions throughout a policy set,
+/// e.g. `{"User": "Person", "Action::\"view\"": "Action::\"read\""}`
+#[pyfunction]
+#[pyo3(signature = (policies, renames))]
+fn rewrite_policies(policies: String, renames: HashMap<String, String>) -> PyResult<String> {
+    let renames = Renames::from_pairs(renames.iter().map(|(from, to)| (from.as_str(), to.as_str())))
+        .map_err(to_value_error)?;
+    rewrite::rewrite_policies(&policies, &renames).map_err(to_value_error)
+}
+
+/// Rename entity types and entities in the uids, parents, and entity-reference attributes
+/// of entities in Cedar's JSON entity format, using the same renames as `rewrite_policies`
+#[pyfunction]
+#[pyo3(signature = (entities, renames))]
+fn rewrite_entities(entities: TextInput, renames: HashMap<String, String>) -> PyResult<String> {
+    let renames = Renames::from_pairs(renames.iter().map(|(from, to)| (from.as_str(), to.as_str())))
+        .map_err(to_value_error)?;
+    rewrite_json(&entities.0, "entities", |entities| rewrite::rewrite_entities(entities, &renames))
+}
+
+/// Check entities in Cedar's JSON entity format for problems in their hierarchy, returning the
+/// findings as a JSON array
+#[pyfunction]
+#[pyo3(signature = (entities, max_parents = 10000))]
+fn check_entities(entities: TextInput, max_parents: usize) -> PyResult<String> {
+    let entities: serde_json::Value = serde_json::from_str(&entities.0)
+        .context(errors::context(ErrorCode::Entities, "failed to parse entities"))
+        .map_err(to_value_error)?;
+    let findings = hierarchy::check_entities(&entities, max_parents).map_err(to_value_error)?;
+    Ok(serde_json::to_string(&findings).expect("findings serialize to JSON"))
+}
+
+/// Report how entities in Cedar's JSON entity format conform to `schema`, per entity type, as a
+/// JSON object
+#[pyfunction]
+#[pyo3(signature = (entities, schema))]
+fn conformance_report(entities: TextInput, schema: String) -> PyResult<String> {
+    let entities: serde_json::Value = serde_json::from_str(&entities.0)
+        .context(errors::context(ErrorCode::Entities, "failed to parse entities"))
+        .map_err(to_value_error)?;
+    let report = conformance::conformance_report(&entities, &schema).map_err(to_value_error)?;
+    Ok(serde_json::to_string(&report).expect("the conformance report serializes to JSON"))
+}
+
+/// Whether the entity `uid` is `ancestor_uid` or one of its descendants in the entities,
+/// with the semantics of Cedar's `in`
+#[pyfunction]
+fn is_in(entities: TextInput, uid: String, ancestor_uid: String) -> PyResult<bool> {
+    let entities = Entities::from_json_str(&entities.0, None)
+        .context(errors::context(ErrorCode::Entities, "failed to parse entities"))
+        .map_err(to_value_error)?;
+    Ok(hierarchy::is_in(&entities, &parse_uid_arg(&uid)?, &parse_uid_arg(&ancestor_uid)?))
+}
+
+/// The uids of the entities that are members of `group_uid`: its descendants, or with
+/// `transitive` false only the entities that list it as a parent
+#[pyfunction]
+#[pyo3(signature = (entities, group_uid, transitive = true))]
+fn members_of(entities: TextInput, group_uid: String, transitive: bool) -> PyResult<Vec<String>> {
+    let entities_json: serde_json::Value = serde_json::from_str(&entities.0)
+        .context(errors::context(ErrorCode::Entities, "failed to parse entities"))
+        .map_err(to_value_error)?;
+    let store = Entities::from_json_value(entities_json.clone(), None)
+        .context(errors::context(ErrorCode::Entities, "failed to parse entities"))
+        .map_err(to_value_error)?;
+    hierarchy::members_of(&store, &entities_json, &parse_uid_arg(&group_uid)?, transitive).map_err(to_value_error)
+}
+
+/// The entities as a graph of their parents and the entities their attributes refer to: a JSON
+/// object of the nodes and edges, or with `output_format` "dot" the graph in Graphviz's DOT language
+#[pyfunction]
+#[pyo3(signature = (entities, output_format = "json"))]
+fn entity_graph(entities: TextInput, output_format: &str) -> PyResult<String> {
+    let output_format: graph::GraphFormat = output_format.parse().map_err(to_value_error)?;
+    let entities: serde_json::Value = serde_json::from_str(&entities.0)
+        .context(errors::context(ErrorCode::Entities, "failed to parse entities"))
+        .map_err(to_value_error)?;
+    let graph = graph::entity_graph(&entities).map_err(to_value_error)?;
+    Ok(match output_format {
+        graph::GraphFormat::Json => serde_json::to_string(&graph).expect("entity graphs serialize to JSON"),
+        graph::GraphFormat::Dot => graph.to_dot(),
+    })
+}
+
+fn parse_uid_arg(uid: &str) -> PyResult<EntityUid> {
+    EntityUid::from_str(uid)
+        .with_context(|| format!("failed to parse {uid} as entity Uid"))
+        .map_err(to_value_error)
+}
+
+fn rewrite_json(json: &str, what: &str, rewrite: impl FnOnce(&mut serde_json::Value) -> Result<()>) -> PyResult<String> {
+    let mut value: serde_json::Value = serde_json::from_str(json)
+        .with_context(|| format!("failed to parse {}", what))
+        .map_err(to_value_error)?;
+    rewrite(&mut value).map_err(to_value_error)?;
+    Ok(value.to_string())
+}
+
+#[derive(Hash)]
 pub struct RequestArgs {
     /// Principal for the request, e.g., User::"alice"
     pub principal: Option<String>,
@@ -44,35 +544,41 @@ pub struct RequestArgs {
 
     /// An optional correlation id that will be copied to the AuthzResponse
     pub correlation_id: Option<String>,
+
+    /// The principal, action, and resource already parsed, when they're shared by many requests
+    /// and were parsed once for all of them, e.g. by `expand_requests`
+    pub parsed_uids: Option<ParsedUids>,
+}
+
+/// Entity uids parsed ahead of a request's evaluation.  A uid that's `None` is parsed from the
+/// request's text as usual, which reports its error if it's invalid.
+#[derive(Hash)]
+pub struct ParsedUids {
+    pub principal: Option<EntityUid>,
+    pub action: Option<EntityUid>,
+    pub resource: Option<EntityUid>,
 }
 
 impl RequestArgs {
     /// Turn this `RequestArgs` into the appropriate `Request` object
     fn get_request(&self, schema: Option<&Schema>) -> Result<Request> {
-        let principal = self
-            .principal
-            .as_ref()
-            .map(|s| {
-                s.parse()
-                    .context(format!("failed to parse principal {s} as entity Uid"))
-            })
-            .transpose()?;
-        let action = self
-            .action
-            .as_ref()
-            .map(|s| {
-                s.parse()
-                    .context(format!("failed to parse action {s} as entity Uid"))
-            })
-            .transpose()?;
-        let resource = self
-            .resource
-            .as_ref()
-            .map(|s| {
-                s.parse()
-                    .context(format!("failed to parse resource {s} as entity Uid"))
-            })
-            .transpose()?;
+        Ok(self.get_request_parts_with(schema, &mut |s| s.parse())?.into_request())
+    }
+
+    /// Parse the entity uids and context of this `RequestArgs`, parsing each uid with `parse_uid`
+    fn get_request_parts_with(&self,
+                              schema: Option<&Schema>,
+                              parse_uid: &mut dyn FnMut(&str) -> Result<EntityUid, ParseErrors>) -> Result<RequestParts> {
+        let mut parse = |what: &str, uid: &Option<String>, parsed: Option<&EntityUid>| match parsed {
+            Some(parsed) => Ok(Some(parsed.clone())),
+            None => uid.as_ref()
+                .map(|s| parse_uid(s).context(format!("failed to parse {what} {s} as entity Uid")))
+                .transpose(),
+        };
+        let parsed = self.parsed_uids.as_ref();
+        let principal = parse("principal", &self.principal, parsed.and_then(|p| p.principal.as_ref()))?;
+        let action = parse("action", &self.action, parsed.and_then(|p| p.action.as_ref()))?;
+        let resource = parse("resource", &self.resource, parsed.and_then(|p| p.resource.as_ref()))?;
         let context: Context = match &self.context_json {
             None => Context::empty(),
             Some(context_json_str) => {
@@ -81,180 +587,1846 @@ impl RequestArgs {
                                        schema.and_then(|s| Some((s, action.as_ref()?))))?
             },
         };
-        Ok(Request::new(principal, action, resource, context))
+        Ok(RequestParts { principal, action, resource, context })
+    }
+}
+
+/// The parsed parts of a `Request`, which (unlike a `Request`) can be kept and reused
+#[derive(Debug, Clone)]
+pub struct RequestParts {
+    principal: Option<EntityUid>,
+    action: Option<EntityUid>,
+    resource: Option<EntityUid>,
+    context: Context,
+}
+
+impl RequestParts {
+    fn into_request(self) -> Request {
+        Request::new(self.principal, self.action, self.resource, self.context)
     }
 }
 
 #[pyfunction]
-#[pyo3(signature = (request, policies, entities, schema = None, verbose = false,))]
-fn is_authorized(request: HashMap<String, String>,
-                 policies: String,
-                 entities: String,
-                 schema: Option<String>,
-                 verbose: Option<bool>)
-                 -> String {
-    is_authorized_batch(vec![request], policies, entities, schema, verbose)[0].clone()
+#[pyo3(signature = (request, policies, entities, schema = None, verbose = None,
+                    include_diagnostics = true, include_metrics = true, entity_validation = None,
+                    unknown_entities = None, policy_errors = None,
+                    include_missing_entities = false, validate_request = false, include_policy_results = false,
+                    include_attributes = None, include_denial_class = false,))]
+#[allow(clippy::too_many_arguments)]
+fn is_authorized(py: Python<'_>,
+                 request: &PyAny,
+                 policies: PolicySource,
+                 entities: EntitySource,
+                 schema: Option<SchemaSource>,
+                 verbose: Option<bool>,
+                 include_diagnostics: Option<bool>,
+                 include_metrics: Option<bool>,
+                 entity_validation: Option<&str>,
+                 unknown_entities: Option<&str>,
+                 policy_errors: Option<&str>,
+                 include_missing_entities: bool,
+                 validate_request: bool,
+                 include_policy_results: bool,
+                 include_attributes: Option<&str>,
+                 include_denial_class: bool)
+                 -> PyResult<response::AuthzResponse> {
+    let (request_args_vec, convert_input_duration) = convert_requests(std::iter::once(Ok(request)))?;
+    let options = AuthzOptions {
+        include_diagnostics: include_diagnostics.unwrap_or(true),
+        include_metrics: include_metrics.unwrap_or(true),
+        entity_validation: parse_option(entity_validation)?,
+        unknown_entities: parse_option(unknown_entities)?,
+        policy_errors: parse_option(policy_errors)?,
+        validate_request,
+        include_policy_results,
+        include_denial_class,
+        include_attributes: AttributeProjection::parse_option(include_attributes)?,
+        include_missing_entities,
+        generate_correlation_ids: config::defaults().generate_correlation_ids,
+        threads: 1,
+    };
+    let verbose = config::verbose(verbose);
+    Ok(py.allow_threads(|| {
+        let evaluation = evaluate_batch(request_args_vec,
+                                        convert_input_duration,
+                                        policies,
+                                        entities,
+                                        schema,
+                                        &options,
+                                        verbose);
+        response::AuthzResponse::new(batch_evaluation_values(evaluation, 1, &options).swap_remove(0))
+    }))
 }
 
 #[pyfunction]
-#[pyo3(signature = (requests, policies, entities, schema = None, verbose = false,))]
-fn is_authorized_batch(requests: Vec<HashMap<String, String>>,
-                       policies: String,
-                       entities: String,
-                       schema: Option<String>,
-                       verbose: Option<bool>)
-                       -> Vec<String> {
-    // CLI AuthorizeArgs: https://github.com/cedar-policy/cedar/blob/main/cedar-policy-cli/src/lib.rs#L183
-    let verbose = verbose.unwrap_or(false);
-    if verbose {
-        //println!("requests: {}", requests);
-        println!("policies: {}", policies);
-        println!("entities: {}", entities);
-        println!("schema: {}", schema.clone().unwrap_or(String::from("<none>")));
+#[pyo3(signature = (requests, policies, entities, schema = None, verbose = None,
+                    include_diagnostics = true, include_metrics = true, entity_validation = None,
+                    unknown_entities = None, policy_errors = None,
+                    include_missing_entities = false, on_result = None, context_columns = None,
+                    max_workers = None, validate_request = false, include_policy_results = false,
+                    include_attributes = None, include_denial_class = false,))]
+#[allow(clippy::too_many_arguments)]
+fn is_authorized_batch(py: Python<'_>,
+                       requests: &PyAny,
+                       policies: PolicySource,
+                       entities: EntitySource,
+                       schema: Option<SchemaSource>,
+                       verbose: Option<bool>,
+                       include_diagnostics: Option<bool>,
+                       include_metrics: Option<bool>,
+                       entity_validation: Option<&str>,
+                       unknown_entities: Option<&str>,
+                       policy_errors: Option<&str>,
+                       include_missing_entities: bool,
+                       on_result: Option<&PyAny>,
+                       context_columns: Option<&str>,
+                       max_workers: Option<usize>,
+                       validate_request: bool,
+                       include_policy_results: bool,
+                       include_attributes: Option<&str>,
+                       include_denial_class: bool)
+                       -> PyResult<Vec<String>> {
+    let (request_args_vec, convert_input_duration) = convert_batch_requests(requests, context_columns)?;
+    let num_requests = request_args_vec.len();
+    let options = AuthzOptions {
+        include_diagnostics: include_diagnostics.unwrap_or(true),
+        include_metrics: include_metrics.unwrap_or(true),
+        entity_validation: parse_option(entity_validation)?,
+        unknown_entities: parse_option(unknown_entities)?,
+        policy_errors: parse_option(policy_errors)?,
+        validate_request,
+        include_policy_results,
+        include_denial_class,
+        include_attributes: AttributeProjection::parse_option(include_attributes)?,
+        include_missing_entities,
+        generate_correlation_ids: config::defaults().generate_correlation_ids,
+        threads: batch_threads(max_workers)?,
+    };
+    let verbose = config::verbose(verbose);
+    if let Some(on_result) = on_result {
+        stream_batch(py,
+                     request_args_vec,
+                     convert_input_duration,
+                     policies,
+                     entities,
+                     schema,
+                     &options,
+                     verbose,
+                     on_result.into())?;
+        return Ok(vec![]);
     }
-    let mut errs: Vec<Error> = vec![];
+    Ok(py.allow_threads(|| {
+        let evaluation = evaluate_batch(request_args_vec,
+                                        convert_input_duration,
+                                        policies,
+                                        entities,
+                                        schema,
+                                        &options,
+                                        verbose);
+        serialize_batch_evaluation(evaluation, num_requests, &options)
+    }))
+}
 
-    // probably need to deconstruct execute_authorization_request so that we can reuse the
-    // expensive parts (policies, entities, schema):
-    // parse policies
-    let t_parse_policies = Instant::now();
-    let policy_set = match PolicySet::from_str(&policies) {
-        Ok(pset) => pset,
-        Err(parse_errors) => {
-            let err_message = format!("policy parse errors:\n{:#}",
-                                      parse_errors.errors_as_strings().join(""));
-            println!("{:#}", err_message);
-            errs.push(Error::msg(err_message));
-            PolicySet::new()
-        }
+/// Evaluate a batch as `is_authorized_batch` does, writing the results to `output_path` as NDJSON,
+/// optionally gzip-compressed, and returning only a JSON summary of them.  The evaluation and
+/// writing run with the GIL released.
+#[pyfunction]
+#[pyo3(signature = (requests, policies, entities, output_path, schema = None, verbose = None,
+                    include_diagnostics = true, include_metrics = true, entity_validation = None,
+                    unknown_entities = None, policy_errors = None,
+                    include_missing_entities = false, output_compression = None, context_columns = None,
+                    max_workers = None, validate_request = false, include_policy_results = false,
+                    include_attributes = None, include_denial_class = false,))]
+#[allow(clippy::too_many_arguments)]
+fn is_authorized_batch_to_file(py: Python<'_>,
+                               requests: &PyAny,
+                               policies: PolicySource,
+                               entities: EntitySource,
+                               output_path: String,
+                               schema: Option<SchemaSource>,
+                               verbose: Option<bool>,
+                               include_diagnostics: bool,
+                               include_metrics: bool,
+                               entity_validation: Option<&str>,
+                               unknown_entities: Option<&str>,
+                               policy_errors: Option<&str>,
+                               include_missing_entities: bool,
+                               output_compression: Option<&str>,
+                               context_columns: Option<&str>,
+                               max_workers: Option<usize>,
+                               validate_request: bool,
+                               include_policy_results: bool,
+                               include_attributes: Option<&str>,
+                               include_denial_class: bool) -> PyResult<String> {
+    let (request_args_vec, convert_input_duration) = convert_batch_requests(requests, context_columns)?;
+    let options = AuthzOptions {
+        include_diagnostics,
+        include_metrics,
+        entity_validation: parse_option(entity_validation)?,
+        unknown_entities: parse_option(unknown_entities)?,
+        policy_errors: parse_option(policy_errors)?,
+        validate_request,
+        include_policy_results,
+        include_denial_class,
+        include_attributes: AttributeProjection::parse_option(include_attributes)?,
+        include_missing_entities,
+        generate_correlation_ids: config::defaults().generate_correlation_ids,
+        threads: batch_threads(max_workers)?,
+    };
+    let compression = match output_compression {
+        None => sink::Compression::for_path(&output_path),
+        Some(compression) => compression.parse()
+            .map_err(|e: Error| errors::value_error(ErrorCode::InvalidArgument, e))?,
+    };
+    let verbose = config::verbose(verbose);
+    let summary = py.allow_threads(|| -> std::io::Result<sink::BatchSummary> {
+        let mut file = sink::ResultFile::create(&output_path, compression)?;
+        let prepared = match PreparedInputs::prepare(&policies,
+                                                     entities,
+                                                     schema,
+                                                     options.entity_validation,
+                                                     verbose) {
+            Ok(prepared) => prepared,
+            Err(errs) => {
+                let result = make_authz_result_for_errors(&errs, &options);
+                let retryable = errors::retryable(errs.iter().map(ErrorCode::of));
+                for _ in 0..request_args_vec.len() {
+                    file.write_no_decision(&result, retryable)?;
+                }
+                return file.finish();
+            }
+        };
+        prepared.evaluate_each(request_args_vec, convert_input_duration, &options, None, verbose, |mut result| {
+            if let Some(metrics) = result.as_mut().ok().and_then(|ans| ans.metrics.as_mut()) {
+                prepared.add_metrics(metrics);
+            }
+            match result {
+                Ok(ans) => file.write_response(&ans, |e| {
+                    let err = Error::from(e)
+                        .context(errors::context(ErrorCode::Serialization, "failed to serialize the response"));
+                    make_authz_result_for_errors(&[err], &options)
+                }),
+                Err(errs) => file.write_no_decision(&make_authz_result_for_errors(&errs, &options),
+                                                    errors::retryable(errs.iter().map(ErrorCode::of))),
+            }
+        })?;
+        file.finish()
+    })
+        .map_err(|e| PyIOError::new_err(format!("failed to write results to {output_path}: {e}")))?;
+    Ok(serde_json::to_string(&summary).expect("batch summaries serialize to JSON"))
+}
+
+/// Evaluate every combination of one principal with lists of actions and resources, ordered by
+/// action and then by resource, e.g. to compute a user's permissions for a page render
+#[pyfunction]
+#[pyo3(signature = (principal, actions, resources, policies, entities, schema = None, context = None,
+                    verbose = None, include_diagnostics = true, include_metrics = true,
+                    entity_validation = None, unknown_entities = None, policy_errors = None,
+                    include_missing_entities = false,
+                    max_requests = 10000))]
+#[allow(clippy::too_many_arguments)]
+fn is_authorized_expanded(py: Python<'_>,
+                          principal: String,
+                          actions: Vec<String>,
+                          resources: Vec<String>,
+                          policies: PolicySource,
+                          entities: EntitySource,
+                          schema: Option<SchemaSource>,
+                          context: Option<String>,
+                          verbose: Option<bool>,
+                          include_diagnostics: Option<bool>,
+                          include_metrics: Option<bool>,
+                          entity_validation: Option<&str>,
+                          unknown_entities: Option<&str>,
+                          policy_errors: Option<&str>,
+                          include_missing_entities: bool,
+                          max_requests: usize)
+                          -> PyResult<Vec<String>> {
+    let t_convert_input = Instant::now();
+    let request_args_vec = expand_requests(&principal, &actions, &resources, context, max_requests)?;
+    let num_requests = request_args_vec.len();
+    let options = AuthzOptions {
+        include_diagnostics: include_diagnostics.unwrap_or(true),
+        include_metrics: include_metrics.unwrap_or(true),
+        entity_validation: parse_option(entity_validation)?,
+        unknown_entities: parse_option(unknown_entities)?,
+        policy_errors: parse_option(policy_errors)?,
+        validate_request: false,
+        include_policy_results: false,
+        include_denial_class: false,
+        include_attributes: None,
+        include_missing_entities,
+        generate_correlation_ids: config::defaults().generate_correlation_ids,
+        threads: config::defaults().threads,
+    };
+    let convert_input_duration = t_convert_input.elapsed();
+    let verbose = config::verbose(verbose);
+    Ok(py.allow_threads(|| {
+        let evaluation = evaluate_batch(request_args_vec,
+                                        convert_input_duration,
+                                        policies,
+                                        entities,
+                                        schema,
+                                        &options,
+                                        verbose);
+        serialize_batch_evaluation(evaluation, num_requests, &options)
+    }))
+}
+
+/// Allowed decisions by action and resource, and the (action, resource) indices of errored cells
+type PermissionGrid = (Vec<Vec<bool>>, Vec<(usize, usize)>);
+
+/// Evaluate every combination of one principal with lists of actions and resources and lay the
+/// decisions out as a grid: `allowed[i][j]` is whether the principal may perform `actions[i]` on
+/// `resources[j]`.  The (action, resource) indices of combinations that could not be evaluated
+/// or encountered evaluation errors are returned alongside the grid.
+#[pyfunction]
+#[pyo3(signature = (principal, actions, resources, policies, entities, schema = None, context = None,
+                    verbose = None, entity_validation = None, unknown_entities = None,
+                    max_requests = 10000))]
+#[allow(clippy::too_many_arguments)]
+fn permission_matrix(principal: String,
+                     actions: Vec<String>,
+                     resources: Vec<String>,
+                     policies: String,
+                     entities: TextInput,
+                     schema: Option<SchemaSource>,
+                     context: Option<String>,
+                     verbose: Option<bool>,
+                     entity_validation: Option<&str>,
+                     unknown_entities: Option<&str>,
+                     max_requests: usize)
+                     -> PyResult<PermissionGrid> {
+    let t_convert_input = Instant::now();
+    let request_args_vec = expand_requests(&principal, &actions, &resources, context, max_requests)?;
+    let options = AuthzOptions {
+        include_diagnostics: true,
+        include_metrics: false,
+        entity_validation: parse_option(entity_validation)?,
+        unknown_entities: parse_option(unknown_entities)?,
+        policy_errors: PolicyErrors::Skip,
+        validate_request: false,
+        include_policy_results: false,
+        include_denial_class: false,
+        include_attributes: None,
+        include_missing_entities: false,
+        generate_correlation_ids: false,
+        threads: config::defaults().threads,
     };
-    let t_parse_policies_duration = t_parse_policies.elapsed();
+    let evaluation = evaluate_batch(request_args_vec,
+                                    t_convert_input.elapsed(),
+                                    PolicySource::Text(policies),
+                                    JsonSource::Text(entities.0),
+                                    schema,
+                                    &options,
+                                    config::verbose(verbose));
 
-    // parse schema
-    let t_start_schema = Instant::now();
-    let schema = make_schema(&schema, verbose);
-    let t_parse_schema_duration = t_start_schema.elapsed();
+    let mut allowed = vec![vec![false; resources.len()]; actions.len()];
+    let mut errored = Vec::new();
+    if !evaluation.errs.is_empty() {
+        let cells = (0..actions.len()).flat_map(|i| (0..resources.len()).map(move |j| (i, j)));
+        return Ok((allowed, cells.collect()));
+    }
+    for (k, ans) in evaluation.results.iter().enumerate() {
+        let (i, j) = (k / resources.len(), k % resources.len());
+        match ans {
+            Ok(ans) => {
+                allowed[i][j] = ans.decision() == Decision::Allow;
+                if ans.diagnostics().is_some_and(|d| d.errors().next().is_some()) {
+                    errored.push((i, j));
+                }
+            }
+            _ => errored.push((i, j)),
+        }
+    }
+    Ok((allowed, errored))
+}
 
-    // load entities
-    let t_load_entities = Instant::now();
-    let entities = make_entities(entities, &schema, &mut errs);
-    let t_load_entities_duration = t_load_entities.elapsed();
+/// Expand the cross product of actions and resources for one principal into requests,
+/// refusing to expand more than `max_requests`.  Each distinct uid is parsed once here, rather
+/// than once per request it's in, and the requests carry the parsed uids.
+fn expand_requests(principal: &str,
+                   actions: &[String],
+                   resources: &[String],
+                   context: Option<String>,
+                   max_requests: usize) -> PyResult<Vec<RequestArgs>> {
+    let num_requests = actions.len().saturating_mul(resources.len());
+    if num_requests > max_requests {
+        return Err(errors::value_error(ErrorCode::InvalidArgument, format!(
+            "{} actions and {} resources expand to {} requests, more than max_requests ({})",
+            actions.len(), resources.len(), num_requests, max_requests)));
+    }
+    let parse = |uid: &str| EntityUid::from_str(uid).ok();
+    let parsed_principal = parse(principal);
+    let parsed_resources: Vec<Option<EntityUid>> = resources.iter().map(|resource| parse(resource)).collect();
+    let mut request_args_vec = Vec::with_capacity(num_requests);
+    for action in actions {
+        let parsed_action = parse(action);
+        for (resource, parsed_resource) in resources.iter().zip(&parsed_resources) {
+            request_args_vec.push(RequestArgs {
+                principal: Some(principal.to_string()),
+                action: Some(action.clone()),
+                resource: Some(resource.clone()),
+                context_json: context.clone(),
+                correlation_id: None,
+                parsed_uids: Some(ParsedUids {
+                    principal: parsed_principal.clone(),
+                    action: parsed_action.clone(),
+                    resource: parsed_resource.clone(),
+                }),
+            });
+        }
+    }
+    Ok(request_args_vec)
+}
 
-    // build a list of RequestArgs
+/// Convert Python request dicts into `RequestArgs`, also returning the time the conversion took
+fn convert_requests<'py>(requests: impl Iterator<Item = PyResult<&'py PyAny>>)
+                         -> PyResult<(Vec<RequestArgs>, Duration)> {
+    let t_convert_input = Instant::now();
     let mut request_args_vec: Vec<RequestArgs> = Vec::new();
-    requests.iter().for_each(|request: &HashMap<String, String>| {
-        request_args_vec.push(to_request_args(request));
+    for request in requests {
+        let request: HashMap<String, &PyAny> = request?.extract()?;
+        let mut fields = HashMap::with_capacity(request.len());
+        for (key, value) in request {
+            if value.is_none() {
+                continue;
+            }
+            // a context may be a dict, converted here rather than with json.dumps
+            let value = if key == "context" && !value.is_instance_of::<PyString>() {
+                pyjson::to_json(value)?.to_string()
+            } else {
+                value.extract()?
+            };
+            fields.insert(key, value);
+        }
+        request_args_vec.push(to_request_args(&fields)?);
+    }
+    Ok((request_args_vec, t_convert_input.elapsed()))
+}
+
+/// Convert a batch's requests: a sequence of request dicts, or NDJSON requests as bytes or the path
+/// of a file, either of which may be compressed.  NDJSON is decoded with the GIL released.  The
+/// requests' contexts may be given as `context_columns`; see `columns::apply`.
+fn convert_batch_requests(requests: &PyAny, context_columns: Option<&str>)
+                          -> PyResult<(Vec<RequestArgs>, Duration)> {
+    let t_convert_input = Instant::now();
+    let parse = |text: Result<String>| text.and_then(|text| input::parse_requests(&text));
+    let mut request_args_vec = if let Ok(data) = requests.downcast::<PyBytes>() {
+        let data = data.as_bytes();
+        requests.py().allow_threads(|| parse(input::decode(data))).map_err(input::error)?
+    } else if let Ok(path) = requests.downcast::<PyString>() {
+        let path = path.to_str()?;
+        requests.py().allow_threads(|| parse(input::read_file(path))).map_err(input::error)?
+    } else {
+        convert_requests(requests.iter()?)?.0
+    };
+    if let Some(context_columns) = context_columns {
+        columns::apply(context_columns, &mut request_args_vec)
+            .map_err(|e| errors::value_error(ErrorCode::InvalidArgument, errors::message(&e)))?;
+    }
+    Ok((request_args_vec, t_convert_input.elapsed()))
+}
+
+/// Parse JSON given as text, or as bytes or an `os.PathLike`, either of which may be compressed,
+/// into Python objects, as `json.loads` makes them.  It's read and parsed with the GIL released.
+#[pyfunction]
+fn load_json(py: Python<'_>, source: input::TextInput) -> PyResult<PyObject> {
+    let value: serde_json::Value = py.allow_threads(|| serde_json::from_str(&source.0))
+        .map_err(|e| errors::value_error(ErrorCode::InvalidArgument, format!("failed to parse JSON: {e}")))?;
+    Ok(pyjson::to_py(py, &value))
+}
+
+/// Find a minimal subset of the policies that reproduces the decision for a request, to explain
+/// a surprising decision.  `decision` ("Allow" or "Deny") defaults to the decision the full
+/// policy set produces.  For a denial the subset holds the forbids that override the permits,
+/// and is empty when no permit applies.
+#[pyfunction]
+#[pyo3(signature = (request, policies, entities, schema = None, decision = None, verbose = None))]
+fn find_minimal_policy_subset(request: &PyAny,
+                              policies: String,
+                              entities: TextInput,
+                              schema: Option<SchemaSource>,
+                              decision: Option<&str>,
+                              verbose: Option<bool>) -> PyResult<String> {
+    let verbose = config::verbose(verbose);
+    let (request_args, _) = convert_requests(std::iter::once(Ok(request)))?;
+    let prepared = PreparedInputs::prepare(&PolicySource::Text(policies.clone()),
+                                           JsonSource::Text(entities.0),
+                                           schema,
+                                           EntityValidation::default(),
+                                           verbose)
+        .map_err(|errs| errors::value_error_for(&errs))?;
+    let request = request_args[0].get_request(prepared.schema.as_ref()).map_err(to_value_error)?;
+
+    let authorizer = Authorizer::new();
+    let decide = |policies: &[Policy]| {
+        let policy_set = PolicySet::from_policies(policies.iter().cloned())
+            .expect("policies from a valid policy set form a valid policy set");
+        authorizer.is_authorized(&request, &policy_set, &prepared.entities).decision()
+    };
+    let all_policies: Vec<Policy> = prepared.policy_set.policies().cloned().collect();
+    let actual = decide(&all_policies);
+    let expected = match decision {
+        None => actual,
+        Some(decision) => parse_decision(decision)?,
+    };
+    if actual != expected {
+        return Err(errors::value_error(ErrorCode::InvalidArgument,
+            format!("the policies produce the decision {:?}, not {:?}", actual, expected)));
+    }
+
+    // every subset of policies denies by default, so a denial is explained by the forbids that
+    // override the permits instead
+    let (fixed, candidates): (Vec<Policy>, Vec<Policy>) = match expected {
+        Decision::Allow => (vec![], all_policies),
+        Decision::Deny => all_policies.into_iter().partition(|policy| policy.effect() == Effect::Permit),
+    };
+    let subset = bisect::minimal_subset(&candidates, |subset| {
+        decide(&[&fixed[..], subset].concat()) == expected
     });
+    let sources = bisect::policy_sources(&policies).map_err(to_value_error)?;
+    let mut policy_ids: Vec<String> = subset.iter().map(|policy| policy.id().to_string()).collect();
+    // report policies in the order they appear in the policy set
+    policy_ids.sort_by_key(|id| id.trim_start_matches("policy").parse::<usize>().unwrap_or(usize::MAX));
+    let subset_sources: Vec<&str> = policy_ids.iter().filter_map(|id| sources.get(id).copied()).collect();
+    Ok(json!({
+        "decision": format!("{:?}", expected),
+        "policy_ids": policy_ids,
+        "policies": subset_sources.join("\n\n"),
+    }).to_string())
+}
 
-    let mut responses_vec: Vec<String> = Vec::new();
+/// Explain why a request did not get the `expected` decision ("Allow" or "Deny"): the policies
+/// that determined the decision, the unsatisfied parts of the permits that did not apply, and,
+/// with a schema, request and entity changes that would flip a denial to Allow
+#[pyfunction]
+#[pyo3(signature = (request, policies, entities, schema = None, expected = "Allow", verbose = None))]
+fn explain_decision(request: &PyAny,
+                    policies: String,
+                    entities: TextInput,
+                    schema: Option<SchemaSource>,
+                    expected: &str,
+                    verbose: Option<bool>) -> PyResult<String> {
+    let verbose = config::verbose(verbose);
+    let (request_args, _) = convert_requests(std::iter::once(Ok(request)))?;
+    let expected = parse_decision(expected)?;
+    explain::explain_decision(&request_args[0],
+                              &policies,
+                              JsonSource::Text(entities.0),
+                              schema,
+                              expected,
+                              verbose)
+        .map(|explanation| explanation.to_string())
+        .map_err(|errs| errors::value_error_for(&errs))
+}
 
-    // evaluate access one at a time (future work: eval in parallel)
-    for request_args in request_args_vec.iter() {
-        if errs.is_empty() {
-            let ans = execute_authorization_request(&request_args,
-                                                    &policy_set,
-                                                    &entities,
-                                                    &schema,
-                                                    verbose);
-            let response_string: String = match ans {
-                Ok(mut ans) => {
-                    ans.metrics.insert(String::from("parse_policies_duration_micros"),
-                                       t_parse_policies_duration.as_micros());
-                    ans.metrics.insert(String::from("parse_schema_duration_micros"),
-                                       t_parse_schema_duration.as_micros());
-                    ans.metrics.insert(String::from("load_entities_duration_micros"),
-                                       t_load_entities_duration.as_micros());
-
-                    let to_json_str_result = serde_json::to_string(&ans);
-                    match to_json_str_result {
-                        Ok(json_str) => { json_str }
-                        Err(err) => {
-                            println!("{:#}", err);
-                            make_authz_result_for_errors(&vec![Error::from(err)])
-                        }
-                    }
+/// Evaluate a Cedar expression against the request and entities, returning its value as JSON
+#[pyfunction]
+#[pyo3(signature = (expression, request, entities, schema = None, verbose = None))]
+fn evaluate_expression(py: Python<'_>,
+                       expression: String,
+                       request: &PyAny,
+                       entities: EntitySource,
+                       schema: Option<SchemaSource>,
+                       verbose: Option<bool>) -> PyResult<String> {
+    let verbose = config::verbose(verbose);
+    let (request_args, _) = convert_requests(std::iter::once(Ok(request)))?;
+    py.allow_threads(|| evaluate::evaluate_expression(&expression,
+                                                      &request_args[0],
+                                                      entities,
+                                                      schema,
+                                                      verbose))
+        .map(|value| value.to_string())
+        .map_err(|errs| errors::value_error_for(&errs))
+}
+
+/// Check that no principal among the entities is allowed both actions of any of the `conflicts`, a
+/// JSON list of pairs of action uids, on the same resource, returning a JSON report of the violations
+#[pyfunction]
+#[pyo3(signature = (conflicts, policies, entities, schema = None, principal_types = None, resource_types = None,
+                    context = None, max_requests = 100000, verbose = None))]
+#[allow(clippy::too_many_arguments)]
+fn check_separation_of_duty(py: Python<'_>,
+                            conflicts: &str,
+                            policies: String,
+                            entities: TextInput,
+                            schema: Option<SchemaSource>,
+                            principal_types: Option<Vec<String>>,
+                            resource_types: Option<Vec<String>>,
+                            context: Option<String>,
+                            max_requests: usize,
+                            verbose: Option<bool>) -> PyResult<String> {
+    let conflicts: Vec<[String; 2]> = serde_json::from_str(conflicts).map_err(|e| errors::value_error(
+        ErrorCode::InvalidArgument, format!("invalid conflicts, expected a list of pairs of actions: {e}")))?;
+    let scope = duty::Scope { principal_types, resource_types, context, max_requests };
+    let verbose = config::verbose(verbose);
+    py.allow_threads(|| duty::check_separation_of_duty(&conflicts,
+                                                        &policies,
+                                                        JsonSource::Text(entities.0),
+                                                        schema,
+                                                        &scope,
+                                                        verbose))
+        .map(|report| serde_json::to_string(&report).expect("separation of duty reports serialize to JSON"))
+        .map_err(|errs| errors::value_error_for(&errs))
+}
+
+/// Authorize a request whose principal, action, resource, or context may be None, unknown, returning
+/// a JSON object of the decision, or the residual policies that remain when it depends on the unknowns
+#[pyfunction]
+#[pyo3(signature = (principal, action, resource, context, policies, entities, schema = None, verbose = None))]
+#[allow(clippy::too_many_arguments)]
+fn is_authorized_partial(py: Python<'_>,
+                         principal: Option<String>,
+                         action: Option<String>,
+                         resource: Option<String>,
+                         context: Option<String>,
+                         policies: PolicySource,
+                         entities: EntitySource,
+                         schema: Option<SchemaSource>,
+                         verbose: Option<bool>) -> PyResult<String> {
+    let request = residual::PartialRequest { principal, action, resource, context_json: context };
+    let verbose = config::verbose(verbose);
+    py.allow_threads(|| residual::is_authorized_partial(&request,
+                                                        &policies,
+                                                        entities,
+                                                        schema,
+                                                        verbose))
+        .map(|result| serde_json::to_string(&result).expect("partial authorization results serialize to JSON"))
+        .map_err(|errs| errors::value_error_for(&errs))
+}
+
+/// Export what the policies allow among the entities as entitlements: a JSON object of the allowed
+/// subject, relation, and object tuples, or with `output_format` "zanzibar" a line for each tuple
+#[pyfunction]
+#[pyo3(signature = (policies, entities, schema = None, actions = None, principal_types = None, resource_types = None,
+                    context = None, output_format = "json", max_requests = 100000, verbose = None))]
+#[allow(clippy::too_many_arguments)]
+fn export_entitlements(py: Python<'_>,
+                       policies: String,
+                       entities: TextInput,
+                       schema: Option<SchemaSource>,
+                       actions: Option<Vec<String>>,
+                       principal_types: Option<Vec<String>>,
+                       resource_types: Option<Vec<String>>,
+                       context: Option<String>,
+                       output_format: &str,
+                       max_requests: usize,
+                       verbose: Option<bool>) -> PyResult<String> {
+    let output_format: entitlements::EntitlementFormat = output_format.parse().map_err(to_value_error)?;
+    let scope = entitlements::Scope { actions, principal_types, resource_types, context, max_requests };
+    let verbose = config::verbose(verbose);
+    let exported = py.allow_threads(|| entitlements::export_entitlements(&policies,
+                                                                         JsonSource::Text(entities.0),
+                                                                         schema,
+                                                                         &scope,
+                                                                         verbose))
+        .map_err(|errs| errors::value_error_for(&errs))?;
+    Ok(match output_format {
+        entitlements::EntitlementFormat::Json => serde_json::to_string(&exported)
+            .expect("entitlements serialize to JSON"),
+        entitlements::EntitlementFormat::Zanzibar => exported.to_zanzibar(),
+    })
+}
+
+/// Import relationship tuples, a JSON list, into the entities as the JSON `mapping` says, returning
+/// a JSON object of the entities and the template links to compile the policies with
+#[pyfunction]
+#[pyo3(signature = (tuples, mapping, entities = None))]
+fn import_relationships(tuples: &str, mapping: &str, entities: Option<TextInput>) -> PyResult<String> {
+    let tuples: Vec<serde_json::Value> = serde_json::from_str(tuples).map_err(|e| errors::value_error(
+        ErrorCode::InvalidArgument, format!("invalid relationship tuples, expected a list: {e}")))?;
+    let mapping: relationships::Mapping = serde_json::from_str(mapping).map_err(|e| errors::value_error(
+        ErrorCode::InvalidArgument, format!("invalid relationship mapping: {e}")))?;
+    let imported = relationships::import_relationships(&tuples, &mapping, entities.as_ref().map(|e| e.0.as_str()))
+        .map_err(|e| errors::value_error(ErrorCode::InvalidArgument, errors::message(&e)))?;
+    Ok(serde_json::to_string(&imported).expect("imported relationships serialize to JSON"))
+}
+
+/// Run the tests the policies carry in `@test_allow` and `@test_deny` annotations, returning a
+/// JSON report of each test and how many passed and failed
+#[pyfunction]
+#[pyo3(signature = (policies, entities, schema = None, verbose = None))]
+fn run_inline_tests(policies: String,
+                    entities: TextInput,
+                    schema: Option<SchemaSource>,
+                    verbose: Option<bool>) -> PyResult<String> {
+    inline::run_inline_tests(&policies,
+                             JsonSource::Text(entities.0),
+                             schema,
+                             config::verbose(verbose))
+        .map(|report| serde_json::to_string(&report).expect("inline test reports serialize to JSON"))
+        .map_err(|errs| errors::value_error_for(&errs))
+}
+
+fn parse_decision(decision: &str) -> PyResult<Decision> {
+    match decision {
+        "Allow" => Ok(Decision::Allow),
+        "Deny" => Ok(Decision::Deny),
+        other => Err(errors::value_error(ErrorCode::InvalidArgument,
+            format!("invalid decision '{}'; expected one of: Allow, Deny", other))),
+    }
+}
+
+/// Evaluate a batch of requests provided as a single JSON document.
+///
+/// The document is an object with `requests`, `policies`, `entities`, and (optional) `schema` members,
+/// e.g. `{"requests": [{"principal": ..., "action": ..., "resource": ..., "context": {...}}], ...}`,
+/// and is parsed once in Rust rather than converted request-by-request from Python objects.
+#[pyfunction]
+#[pyo3(signature = (document, verbose = None, include_diagnostics = true, include_metrics = true,
+                    entity_validation = None, unknown_entities = None, policy_errors = None,
+                    include_missing_entities = false,))]
+#[allow(clippy::too_many_arguments)]
+fn is_authorized_batch_document(document: &PyAny,
+                                verbose: Option<bool>,
+                                include_diagnostics: Option<bool>,
+                                include_metrics: Option<bool>,
+                                entity_validation: Option<&str>,
+                                unknown_entities: Option<&str>,
+                                policy_errors: Option<&str>,
+                                include_missing_entities: bool)
+                                -> PyResult<Vec<String>> {
+    let options = AuthzOptions {
+        include_diagnostics: include_diagnostics.unwrap_or(true),
+        include_metrics: include_metrics.unwrap_or(true),
+        entity_validation: parse_option(entity_validation)?,
+        unknown_entities: parse_option(unknown_entities)?,
+        policy_errors: parse_option(policy_errors)?,
+        validate_request: false,
+        include_policy_results: false,
+        include_denial_class: false,
+        include_attributes: None,
+        include_missing_entities,
+        generate_correlation_ids: config::defaults().generate_correlation_ids,
+        threads: config::defaults().threads,
+    };
+    let t_convert_input = Instant::now();
+    let parse_result: serde_json::Result<BatchDocument> = match document.extract::<&str>() {
+        Ok(document) => serde_json::from_str(document),
+        Err(_) => serde_json::from_slice(document.extract::<&[u8]>()?),
+    };
+    let batch_document = parse_result.map_err(|e| {
+        errors::value_error(ErrorCode::InvalidArgument, format!("failed to parse batch document: {}", e))
+    })?;
+
+    let num_requests = batch_document.requests.len();
+    let request_args_vec: Vec<RequestArgs> = batch_document.requests.into_iter().map(RequestArgs::from).collect();
+    let convert_input_duration = t_convert_input.elapsed();
+    let evaluation = evaluate_batch(request_args_vec,
+                                    convert_input_duration,
+                                    PolicySource::Text(batch_document.policies),
+                                    JsonSource::from(batch_document.entities),
+                                    batch_document.schema.map(SchemaSource::from),
+                                    &options,
+                                    config::verbose(verbose));
+    Ok(serialize_batch_evaluation(evaluation, num_requests, &options))
+}
+
+/// Serialize the results of a batch evaluation to one JSON string per request
+fn serialize_batch_evaluation(evaluation: BatchEvaluation,
+                              num_requests: usize,
+                              options: &AuthzOptions)
+                              -> Vec<String> {
+    if !evaluation.errs.is_empty() {
+        return vec![make_authz_result_for_errors(&evaluation.errs, options); num_requests];
+    }
+
+    let mut buffer = BatchResponseBuffer::with_capacity(num_requests);
+    for ans in evaluation.results {
+        match ans {
+            Ok(ans) => {
+                if let Err(err) = buffer.push_response(&ans) {
+                    let err = Error::from(err)
+                        .context(errors::context(ErrorCode::Serialization, "failed to serialize the response"));
+                    println!("{}", errors::message(&err));
+                    buffer.push_str(&make_authz_result_for_errors(&[err], options));
+                }
+            }
+            Err(errs) => {
+                for err in &errs {
+                    println!("{}", errors::message(err));
                 }
-                Err(errs) => {
-                    for err in &errs {
-                        println!("{:#}", err);
+                buffer.push_str(&make_authz_result_for_errors(&errs, options));
+            }
+        };
+    }
+
+    buffer.into_strings()
+}
+
+/// The results of a batch evaluation as one JSON value per request, for results returned as
+/// `AuthzResponse` objects rather than serialized
+fn batch_evaluation_values(evaluation: BatchEvaluation,
+                           num_requests: usize,
+                           options: &AuthzOptions)
+                           -> Vec<serde_json::Value> {
+    if !evaluation.errs.is_empty() {
+        return vec![authz_value_for_errors(&evaluation.errs, options); num_requests];
+    }
+
+    evaluation.results.into_iter().map(|ans| match ans {
+        Ok(ans) => serde_json::to_value(&ans).unwrap_or_else(|err| {
+            let err = Error::from(err)
+                .context(errors::context(ErrorCode::Serialization, "failed to serialize the response"));
+            println!("{}", errors::message(&err));
+            authz_value_for_errors(&[err], options)
+        }),
+        Err(errs) => {
+            for err in &errs {
+                println!("{}", errors::message(err));
+            }
+            authz_value_for_errors(&errs, options)
+        }
+    }).collect()
+}
+
+/// Serializes the responses for a batch into a single buffer that is reused across batch items,
+/// rather than allocating and growing a fresh `String` per response.
+struct BatchResponseBuffer {
+    buf: Vec<u8>,
+    /// offset of the end of each response in `buf`
+    ends: Vec<usize>,
+}
+
+impl BatchResponseBuffer {
+    /// Typical size of a serialized response, used to pre-size the buffer
+    const RESPONSE_SIZE_HINT: usize = 256;
+
+    fn with_capacity(num_responses: usize) -> Self {
+        Self {
+            buf: Vec::with_capacity(num_responses * Self::RESPONSE_SIZE_HINT),
+            ends: Vec::with_capacity(num_responses),
+        }
+    }
+
+    /// Serialize `response` into the buffer; on error, the buffer is left as it was
+    fn push_response(&mut self, response: &AuthzResponse) -> serde_json::Result<()> {
+        let start = self.buf.len();
+        match serde_json::to_writer(&mut self.buf, response) {
+            Ok(()) => {
+                self.ends.push(self.buf.len());
+                Ok(())
+            }
+            Err(err) => {
+                self.buf.truncate(start);
+                Err(err)
+            }
+        }
+    }
+
+    fn push_str(&mut self, response: &str) {
+        self.buf.extend_from_slice(response.as_bytes());
+        self.ends.push(self.buf.len());
+    }
+
+    /// The serialized responses, as slices of the buffer
+    fn slices(&self) -> impl Iterator<Item = &[u8]> {
+        let starts = std::iter::once(0).chain(self.ends.iter().copied());
+        starts.zip(self.ends.iter().copied()).map(|(start, end)| &self.buf[start..end])
+    }
+
+    fn into_strings(self) -> Vec<String> {
+        self.slices()
+            .map(|slice| String::from_utf8_lossy(slice).into_owned())
+            .collect()
+    }
+}
+
+/// Evaluate a batch of requests and return the decisions as a compact bitset.
+///
+/// Bit `i` (least-significant bit first within each byte) is set when request `i` is allowed.
+/// The indices of requests that could not be evaluated or encountered evaluation errors
+/// are returned alongside the bitset.
+#[pyfunction]
+#[pyo3(signature = (requests, policies, entities, schema = None, verbose = None, entity_validation = None,
+                    unknown_entities = None,))]
+#[allow(clippy::too_many_arguments)]
+fn is_authorized_batch_decisions(py: Python<'_>,
+                                 requests: &PyAny,
+                                 policies: PolicySource,
+                                 entities: EntitySource,
+                                 schema: Option<SchemaSource>,
+                                 verbose: Option<bool>,
+                                 entity_validation: Option<&str>,
+                                 unknown_entities: Option<&str>)
+                                 -> PyResult<(PyObject, Vec<usize>)> {
+    let options = AuthzOptions {
+        include_diagnostics: true,
+        include_metrics: false,
+        entity_validation: parse_option(entity_validation)?,
+        unknown_entities: parse_option(unknown_entities)?,
+        policy_errors: PolicyErrors::Skip,
+        validate_request: false,
+        include_policy_results: false,
+        include_denial_class: false,
+        include_attributes: None,
+        include_missing_entities: false,
+        generate_correlation_ids: false,
+        threads: config::defaults().threads,
+    };
+    let (request_args_vec, convert_input_duration) = convert_requests(requests.iter()?)?;
+    let num_requests = request_args_vec.len();
+    let evaluation = evaluate_batch(request_args_vec,
+                                    convert_input_duration,
+                                    policies,
+                                    entities,
+                                    schema,
+                                    &options,
+                                    config::verbose(verbose));
+
+    let mut bitset: Vec<u8> = vec![0; num_requests.div_ceil(8)];
+    let errored: Vec<usize> = if !evaluation.errs.is_empty() {
+        (0..num_requests).collect()
+    } else {
+        let mut errored = Vec::new();
+        for (i, ans) in evaluation.results.iter().enumerate() {
+            match ans {
+                Ok(ans) => {
+                    if ans.decision() == Decision::Allow {
+                        bitset[i / 8] |= 1 << (i % 8);
+                    }
+                    if ans.diagnostics().is_some_and(|d| d.errors().next().is_some()) {
+                        errored.push(i);
                     }
-                    make_authz_result_for_errors(&errs)
                 }
-            };
-            responses_vec.push(response_string);
-        } else {
-            responses_vec.push(make_authz_result_for_errors(&errs))
+                Err(_) => errored.push(i),
+            }
         }
+        errored
+    };
+
+    Ok((PyBytes::new(py, &bitset).into(), errored))
+}
+
+/// The outcome of evaluating a batch of requests against shared policies, entities, and schema
+struct BatchEvaluation {
+    /// Errors encountered while preparing the shared inputs; when present, no request was evaluated
+    errs: Vec<Error>,
+    /// Per-request results, in the same order as the requests
+    results: Vec<Result<AuthzResponse, Vec<Error>>>,
+}
+
+fn evaluate_batch(request_args_vec: Vec<RequestArgs>,
+                  convert_input_duration: Duration,
+                  policies: PolicySource,
+                  entities: impl Into<EntitySource>,
+                  schema: Option<SchemaSource>,
+                  options: &AuthzOptions,
+                  verbose: bool)
+                  -> BatchEvaluation {
+    let prepared = match PreparedInputs::prepare(&policies, entities, schema, options.entity_validation, verbose) {
+        Ok(prepared) => prepared,
+        Err(errs) => return BatchEvaluation { errs, results: vec![] },
+    };
+
+    let mut results = prepared.evaluate(request_args_vec, convert_input_duration, options, None, verbose);
+    for ans in results.iter_mut().flatten() {
+        if let Some(metrics) = ans.metrics.as_mut() {
+            prepared.add_metrics(metrics);
+        }
+    }
+    BatchEvaluation { errs: vec![], results }
+}
+
+/// Evaluate a batch like `evaluate_batch`, but pass each serialized result to `on_result` as soon
+/// as it's evaluated instead of collecting them, so the caller can overlap its own processing of
+/// the results with their evaluation.  An exception raised by `on_result` stops the evaluation.
+/// The evaluation runs with the GIL released, which is taken again for each call of `on_result`.
+#[allow(clippy::too_many_arguments)]
+fn stream_batch(py: Python<'_>,
+                request_args_vec: Vec<RequestArgs>,
+                convert_input_duration: Duration,
+                policies: PolicySource,
+                entities: EntitySource,
+                schema: Option<SchemaSource>,
+                options: &AuthzOptions,
+                verbose: bool,
+                on_result: PyObject) -> PyResult<()> {
+    let call = |result: &str| Python::with_gil(|py| on_result.call1(py, (result,)).map(drop));
+    py.allow_threads(|| {
+        let prepared = match PreparedInputs::prepare(&policies, entities, schema, options.entity_validation, verbose) {
+            Ok(prepared) => prepared,
+            Err(errs) => {
+                let result = make_authz_result_for_errors(&errs, options);
+                for _ in 0..request_args_vec.len() {
+                    call(&result)?;
+                }
+                return Ok(());
+            }
+        };
+        prepared.evaluate_each(request_args_vec, convert_input_duration, options, None, verbose, |mut result| {
+            if let Some(metrics) = result.as_mut().ok().and_then(|ans| ans.metrics.as_mut()) {
+                prepared.add_metrics(metrics);
+            }
+            let evaluation = BatchEvaluation { errs: vec![], results: vec![result] };
+            call(&serialize_batch_evaluation(evaluation, 1, options).swap_remove(0))
+        })
+    })
+}
 
+/// Drop the attributes of the request's context that the schema doesn't declare,
+/// returning a warning for each attribute that was dropped
+fn retain_declared_context_attrs(declared_attributes: &DeclaredAttributes,
+                                 request_args: &mut RequestArgs) -> Vec<String> {
+    let (Some(action), Some(context_json)) = (&request_args.action, &request_args.context_json) else {
+        return vec![];
+    };
+    let Ok(mut context) = serde_json::from_str::<serde_json::Value>(context_json) else {
+        // leave invalid json for Cedar to report
+        return vec![];
+    };
+    let dropped = declared_attributes.retain_declared_context_attrs(action, &mut context);
+    if !dropped.is_empty() {
+        request_args.context_json = Some(context.to_string());
     }
+    dropped.into_iter()
+        .map(|dropped| format!("dropped attribute not declared in the schema: {}", dropped))
+        .collect()
+}
 
-    return responses_vec;
+/// How many requests each thread evaluates per chunk of a batch evaluated on several threads; a
+/// chunk's results are held until they're all done, so this bounds how far they lag their sink
+const REQUESTS_PER_THREAD_CHUNK: usize = 256;
+
+/// The parsed policies, schema, and entities shared by the requests of an authorization call,
+/// along with hashes identifying the source inputs and the time taken to prepare each of them.
+struct PreparedInputs {
+    /// shared with the policy set's handle, when the policies were compiled
+    policy_set: Arc<PolicySet>,
+    schema: Option<Schema>,
+    /// the schema's text, and its model, read when a request is first validated against it
+    schema_source: Option<SchemaSource>,
+    schema_model: OnceLock<Option<SchemaModel>>,
+    /// why the schema couldn't be parsed, when it couldn't
+    schema_error: Option<String>,
+    entities: Arc<Entities>,
+    /// the attributes declared by the schema, when undeclared attributes are dropped from request contexts
+    declared_attributes: Option<DeclaredAttributes>,
+    /// non-fatal problems encountered while preparing the inputs
+    warnings: Vec<String>,
+
+    policies_hash: u64,
+    schema_hash: Option<u64>,
+    entities_hash: u64,
+    /// the policies' fingerprint, reported in every response
+    policies_fingerprint: u64,
+
+    parse_policies_duration: Duration,
+    parse_schema_duration: Duration,
+    load_entities_duration: Duration,
 }
 
-fn make_authz_result_for_errors(errs: &Vec<Error>) -> String {
-    let json_obj = json!(
+impl PreparedInputs {
+    /// Parse the policies, schema, and entities; the errors encountered are returned when any input is invalid
+    fn prepare(policies: &PolicySource,
+               entities: impl Into<EntitySource>,
+               schema: Option<SchemaSource>,
+               entity_validation: EntityValidation,
+               verbose: bool) -> std::result::Result<Self, Vec<Error>> {
+        let entities: EntitySource = entities.into();
+        // CLI AuthorizeArgs: https://github.com/cedar-policy/cedar/blob/main/cedar-policy-cli/src/lib.rs#L183
+        if verbose {
+            println!("policies: {}", policies);
+            println!("entities: {}", entities);
+            match &schema {
+                Some(schema) => println!("schema: {}", schema.text()),
+                None => println!("schema: <none>"),
+            }
+        }
+        let mut errs: Vec<Error> = vec![];
+
+        let entities_hash = entities.content_hash();
+        let schema_hash = schema.as_ref().map(SchemaSource::hash);
+
+        // parse policies, unless they were compiled already
+        let t_parse_policies = Instant::now();
+        let (policy_set, policies_hash, policies_fingerprint) = match policies {
+            PolicySource::Text(text) => {
+                let policy_set = policy_set::parse(text).unwrap_or_else(|e| {
+                    println!("{}", errors::message(&e));
+                    errs.push(e);
+                    PolicySet::new()
+                });
+                (Arc::new(policy_set), hash_str(text), fingerprint::of(text))
+            }
+            PolicySource::Compiled(compiled) => (compiled.policy_set.clone(), compiled.hash, compiled.fingerprint),
+        };
+        let parse_policies_duration = t_parse_policies.elapsed();
+
+        // parse schema
+        let t_start_schema = Instant::now();
+        let schema_provided = schema.is_some();
+        let mut declared_attributes = match (&schema, entity_validation) {
+            (Some(schema_src), EntityValidation::Permissive) => {
+                serde_json::from_str(schema_src.text()).ok()
+                    .map(|schema| DeclaredAttributes::from_schema_json(&schema))
+            }
+            _ => None,
+        };
+        let (schema_source, (schema, schema_error)) = match make_schema(&schema, verbose) {
+            Ok(parsed) => (schema, (parsed, None)),
+            Err(e) => (None, (None, Some(errors::message(&e)))),
+        };
+        let schema_invalid = schema_provided && schema.is_none();
+        if schema_invalid {
+            declared_attributes = None;
+        }
+        let parse_schema_duration = t_start_schema.elapsed();
+
+        // an invalid schema fails the requests, unless entities needn't be validated against it
+        let mut warnings: Vec<String> = vec![];
+        if let Some(schema_error) = &schema_error {
+            match entity_validation {
+                EntityValidation::Strict | EntityValidation::Permissive => {
+                    errs.push(errors::coded(ErrorCode::Schema, schema_error))
+                }
+                EntityValidation::Warn | EntityValidation::None => {
+                    warnings.push(format!("the schema was ignored: {schema_error}"))
+                }
+            }
+        }
+
+        // load entities
+        let t_load_entities = Instant::now();
+        let entities = match entities {
+            // a store's entities were validated against its own schema as they were added
+            EntitySource::Store(stored) => match stored.entities(&schema, schema_hash) {
+                Ok((entities, missing_parents)) => {
+                    warnings.extend(missing_parents);
+                    entities
+                }
+                Err(e) => {
+                    errs.push(e);
+                    Arc::new(Entities::empty())
+                }
+            },
+            EntitySource::Json(entities) => {
+                let entities = match &declared_attributes {
+                    Some(declared_attributes) => match entities.into_value() {
+                        Ok(mut entities_value) => {
+                            for dropped in declared_attributes.retain_declared_entity_attrs(&mut entities_value) {
+                                warnings.push(format!("dropped attribute not declared in the schema: {}", dropped));
+                            }
+                            JsonSource::Value(entities_value)
+                        }
+                        Err(entities) => entities,
+                    },
+                    None => entities,
+                };
+                warnings.extend(warnings::deprecated_entities_syntax(&entities));
+                let entities = make_entities(entities, &schema, schema_invalid, entity_validation,
+                                             &mut errs, &mut warnings);
+                warnings.extend(warnings::missing_parents(&entities));
+                Arc::new(entities)
+            }
+        };
+        let load_entities_duration = t_load_entities.elapsed();
+
+        if !errs.is_empty() {
+            return Err(errs);
+        }
+
+        let prepared = Self {
+            policy_set,
+            schema,
+            schema_source,
+            schema_model: OnceLock::new(),
+            schema_error,
+            entities,
+            declared_attributes,
+            warnings,
+            policies_hash,
+            schema_hash,
+            entities_hash,
+            policies_fingerprint,
+            parse_policies_duration,
+            parse_schema_duration,
+            load_entities_duration,
+        };
+        if verbose {
+            println!("prepared inputs: policies {:016x}, entities {:016x}, schema {}",
+                     prepared.policies_hash,
+                     prepared.entities_hash,
+                     prepared.schema_hash.map_or(String::from("<none>"), |h| format!("{:016x}", h)));
+        }
+        Ok(prepared)
+    }
+
+    /// Record the time taken to prepare the inputs into a response's `metrics`
+    /// Evaluate requests against the prepared inputs, in order
+    fn evaluate(&self,
+                request_args_vec: Vec<RequestArgs>,
+                convert_input_duration: Duration,
+                options: &AuthzOptions,
+                request_cache: Option<&Mutex<RequestCache>>,
+                verbose: bool) -> Vec<Result<AuthzResponse, Vec<Error>>> {
+        let mut results = Vec::with_capacity(request_args_vec.len());
+        let collected: std::result::Result<(), std::convert::Infallible> =
+            self.evaluate_each(request_args_vec, convert_input_duration, options, request_cache, verbose, |ans| {
+                results.push(ans);
+                Ok(())
+            });
+        let Ok(()) = collected;
+        results
+    }
+
+    /// Evaluate each request, passing its result to `sink` as it completes; an error from `sink`
+    /// stops the evaluation and is returned.  With more than one of `options.threads`, the requests
+    /// are evaluated a chunk at a time, split across that many tasks on the shared thread pool, and
+    /// the chunk's results passed to `sink` in order once they're all done.
+    fn evaluate_each<E>(&self,
+                        request_args_vec: Vec<RequestArgs>,
+                        convert_input_duration: Duration,
+                        options: &AuthzOptions,
+                        request_cache: Option<&Mutex<RequestCache>>,
+                        verbose: bool,
+                        mut sink: impl FnMut(Result<AuthzResponse, Vec<Error>>) -> std::result::Result<(), E>)
+                        -> std::result::Result<(), E> {
+        let mut request_args_vec = request_args_vec;
+        if options.generate_correlation_ids {
+            correlation::fill_missing(&mut request_args_vec);
+        }
+        let evaluate = |request_args| {
+            self.evaluate_one(request_args, convert_input_duration, options, request_cache, verbose)
+        };
+        let Some(pool) = (options.threads > 1).then(|| pool::shared(verbose)).flatten() else {
+            for request_args in request_args_vec.into_iter() {
+                sink(evaluate(request_args))?;
+            }
+            return Ok(());
+        };
+
+        let mut requests = request_args_vec.into_iter();
+        loop {
+            let parts: Vec<Vec<RequestArgs>> = (0..options.threads)
+                .map(|_| requests.by_ref().take(REQUESTS_PER_THREAD_CHUNK).collect())
+                .take_while(|part: &Vec<RequestArgs>| !part.is_empty())
+                .collect();
+            if parts.is_empty() {
+                return Ok(());
+            }
+            // a panic in a task is resumed here, as it would be on one thread
+            let results: Vec<Vec<Result<AuthzResponse, Vec<Error>>>> = pool.install(|| {
+                parts.into_par_iter()
+                    .map(|part| part.into_iter().map(evaluate).collect())
+                    .collect()
+            });
+            for ans in results.into_iter().flatten() {
+                sink(ans)?;
+            }
+        }
+    }
+
+    fn evaluate_one(&self,
+                    mut request_args: RequestArgs,
+                    convert_input_duration: Duration,
+                    options: &AuthzOptions,
+                    request_cache: Option<&Mutex<RequestCache>>,
+                    verbose: bool) -> Result<AuthzResponse, Vec<Error>> {
+        if options.validate_request {
+            self.validate_request(&request_args).map_err(|e| vec![e])?;
+        }
+        let mut request_warnings = match &self.declared_attributes {
+            Some(declared_attributes) => retain_declared_context_attrs(declared_attributes, &mut request_args),
+            None => vec![],
+        };
+        if let Some(context_json) = &request_args.context_json {
+            request_warnings.extend(warnings::deprecated_context_syntax(context_json));
+        }
+        // a request asked to be validated, but without a schema to validate it against
+        if options.validate_request && self.schema_source.is_none() && self.schema_error.is_none() {
+            request_warnings.push(warnings::schema_absent());
+        }
+        let mut ans = execute_authorization_request(&request_args,
+                                                    &self.policy_set,
+                                                    &self.entities,
+                                                    &self.schema,
+                                                    options,
+                                                    request_cache,
+                                                    verbose);
+        if let Ok(ans) = ans.as_mut() {
+            ans.fingerprints = Some(Fingerprints::of_policies(self.policies_fingerprint));
+            if options.include_denial_class && ans.decision() == Decision::Deny {
+                ans.denial_class = self.schema_model().and_then(|model| {
+                    denial::denial_class(&request_args, ans.response.diagnostics(), &self.policy_set, &self.entities,
+                                         model)
+                });
+            }
+            if self.policy_set.templates().next().is_some() {
+                let reason_templates: BTreeMap<String, String> = ans.response.diagnostics().reason()
+                    .filter_map(|id| Some((id.to_string(), self.policy_set.policy(id)?.template_id()?.to_string())))
+                    .collect();
+                ans.reason_templates = (!reason_templates.is_empty()).then_some(reason_templates);
+            }
+            let unknown_entity_warnings = std::mem::take(&mut ans.warnings);
+            ans.warnings.clone_from(&self.warnings);
+            ans.warnings.extend(request_warnings);
+            ans.warnings.extend(unknown_entity_warnings);
+            if let Some(metrics) = ans.metrics.as_mut() {
+                metrics.insert(String::from("convert_input_duration_micros"),
+                               convert_input_duration.as_micros());
+            }
+        }
+        ans
+    }
+
+    /// Check that the request conforms to the schema, when there's a schema
+    fn validate_request(&self, request_args: &RequestArgs) -> Result<()> {
+        if let Some(schema_error) = &self.schema_error {
+            return Err(errors::coded(ErrorCode::Schema, schema_error));
+        }
+        match self.schema_model() {
+            Some(model) => request_validation::validate_request(model, request_args),
+            None => Ok(()),
+        }
+    }
+
+    /// The schema's model, read when it's first needed, or `None` without a valid schema
+    fn schema_model(&self) -> Option<&SchemaModel> {
+        let schema_source = self.schema_source.as_ref()?;
+        self.schema_model.get_or_init(|| SchemaModel::from_json(schema_source.text()).ok()).as_ref()
+    }
+
+    fn add_metrics(&self, metrics: &mut HashMap<String, u128>) {
+        metrics.insert(String::from("parse_policies_duration_micros"),
+                       self.parse_policies_duration.as_micros());
+        metrics.insert(String::from("parse_schema_duration_micros"),
+                       self.parse_schema_duration.as_micros());
+        metrics.insert(String::from("load_entities_duration_micros"),
+                       self.load_entities_duration.as_micros());
+    }
+}
+
+fn hash_str(s: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    s.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Adapts a `Hasher` to `io::Write`, so JSON values can be hashed by serializing them into the hasher
+struct HashWriter<'a, H: Hasher>(&'a mut H);
+
+impl<H: Hasher> std::io::Write for HashWriter<'_, H> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.write(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn make_authz_result_for_errors(errs: &[Error], options: &AuthzOptions) -> String {
+    authz_value_for_errors(errs, options).to_string()
+}
+
+fn authz_value_for_errors(errs: &[Error], options: &AuthzOptions) -> serde_json::Value {
+    let mut json_obj = json!(
         {
             "decision": "NoDecision",
-            "diagnostics": {
-                "errors": stringify_errors(&errs)
-            }
+            "retryable": errors::retryable(errs.iter().map(ErrorCode::of)),
+        });
+    if options.include_diagnostics {
+        let error_codes: Vec<&str> = errs.iter().map(|e| ErrorCode::of(e).as_str()).collect();
+        json_obj["diagnostics"] = json!({
+            "errors": stringify_errors(errs),
+            "error_codes": error_codes,
         });
+    }
 
-    return json_obj.to_string();
+    json_obj
 }
 
-fn stringify_errors(errs: &Vec<Error>) -> Vec<String> {
-    errs.iter().map(|e| e.to_string()).collect()
+fn stringify_errors(errs: &[Error]) -> Vec<String> {
+    errs.iter().map(errors::message).collect()
 }
 
-fn to_request_args(request: &HashMap<String, String>) -> RequestArgs {
+/// The `RequestArgs` of a request dict, or a `RequestError` when it's missing its principal,
+/// action, or resource
+fn to_request_args(request: &HashMap<String, String>) -> PyResult<RequestArgs> {
     // collect request arguments into a struct compatible with authorization request
-    let principal: String = request.get(String::from("principal").as_str()).unwrap().to_string();
-    let action: String = request.get(String::from("action").as_str()).unwrap().to_string();
-    let resource: String = request.get(String::from("resource").as_str()).unwrap().to_string();
-    let correlation_id: Option<String> = request.get(String::from("correlation_id").as_str()).cloned();
-
-    let context_option = request.get(String::from("context").as_str());
-    let context_json_option: Option<String> = match context_option {
-        None => None, // context member not present
-        Some(context) => Some(context.to_string())
-    };
+    let required = |name: &str| request.get(name).cloned().ok_or_else(|| {
+        errors::value_error(ErrorCode::Request, format!("invalid request: the request has no {name}"))
+    });
+    let principal = required("principal")?;
+    let action = required("action")?;
+    let resource = required("resource")?;
+    let correlation_id: Option<String> = request.get("correlation_id").cloned();
+
+    // context member may not be present
+    let context_json_option: Option<String> = request.get("context").cloned();
 
-    RequestArgs {
+    Ok(RequestArgs {
         principal: Some(principal),
         action: Some(action),
         resource: Some(resource),
         context_json: context_json_option,
         correlation_id,
+        parsed_uids: None,
+    })
+}
+
+/// A JSON document provided either as source text or as an already-parsed JSON value
+#[derive(Clone)]
+enum JsonSource {
+    Text(String),
+    Value(serde_json::Value),
+}
+
+impl From<serde_json::Value> for JsonSource {
+    /// JSON strings are treated as embedded source text, e.g. `"entities": "[...]"`
+    fn from(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::String(text) => JsonSource::Text(text),
+            value => JsonSource::Value(value),
+        }
+    }
+}
+
+impl JsonSource {
+    /// The JSON content as a value, parsing it if necessary
+    fn to_value(&self) -> serde_json::Result<serde_json::Value> {
+        match self {
+            JsonSource::Text(text) => serde_json::from_str(text),
+            JsonSource::Value(value) => Ok(value.clone()),
+        }
+    }
+
+    /// The JSON content as a value, parsing it if necessary, or the source when it isn't JSON
+    fn into_value(self) -> std::result::Result<serde_json::Value, JsonSource> {
+        match self {
+            JsonSource::Text(text) => serde_json::from_str(&text).map_err(|_| JsonSource::Text(text)),
+            JsonSource::Value(value) => Ok(value),
+        }
+    }
+
+    /// A hash of the JSON content, e.g. to identify the source of a prepared input
+    fn content_hash(&self) -> u64 {
+        match self {
+            JsonSource::Text(text) => hash_str(text),
+            JsonSource::Value(value) => {
+                let mut hasher = DefaultHasher::new();
+                serde_json::to_writer(HashWriter(&mut hasher), value)
+                    .expect("serializing a JSON value into a hasher cannot fail");
+                hasher.finish()
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for JsonSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JsonSource::Text(text) => write!(f, "{}", text),
+            JsonSource::Value(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// A batch of authorization requests and their shared inputs, as a single JSON document
+#[derive(Deserialize)]
+struct BatchDocument {
+    requests: Vec<BatchDocumentRequest>,
+    policies: String,
+    entities: serde_json::Value,
+    #[serde(default)]
+    schema: Option<serde_json::Value>,
+}
+
+/// A request within a `BatchDocument`; `context` may be an object or a json-formatted string
+#[derive(Deserialize)]
+struct BatchDocumentRequest {
+    principal: String,
+    action: String,
+    resource: String,
+    #[serde(default)]
+    context: Option<serde_json::Value>,
+    #[serde(default)]
+    correlation_id: Option<String>,
+}
+
+impl From<BatchDocumentRequest> for RequestArgs {
+    fn from(request: BatchDocumentRequest) -> Self {
+        let context_json = request.context.and_then(|context| match context {
+            serde_json::Value::Null => None,
+            serde_json::Value::String(context_json) => Some(context_json),
+            context => Some(context.to_string()),
+        });
+        RequestArgs {
+            principal: Some(request.principal),
+            action: Some(request.action),
+            resource: Some(request.resource),
+            context_json,
+            correlation_id: request.correlation_id,
+            parsed_uids: None,
+        }
+    }
+}
+
+/// How entities are validated against the schema when they are loaded
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum EntityValidation {
+    /// Entities must conform to the schema; a schema that fails to parse is an error
+    #[default]
+    Strict,
+    /// Entities are validated against the schema when possible, falling back to loading them
+    /// unvalidated with a warning when the schema fails to parse or the entities don't conform
+    Warn,
+    /// Entities and request contexts are validated against the parts of the schema they match;
+    /// attributes the schema doesn't declare are dropped with a warning instead of failing validation
+    Permissive,
+    /// Entities are loaded without validating them against the schema
+    None,
+}
+
+impl FromStr for EntityValidation {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "strict" => Ok(EntityValidation::Strict),
+            "warn" => Ok(EntityValidation::Warn),
+            "permissive" => Ok(EntityValidation::Permissive),
+            "none" => Ok(EntityValidation::None),
+            _ => Err(Error::msg(format!(
+                "invalid entity validation mode '{}'; expected one of: strict, warn, permissive, none", s))),
+        }
     }
 }
 
+/// How a request whose principal or resource isn't in the entities is handled
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownEntities {
+    /// The request is evaluated as if the entity had no attributes or parents
+    #[default]
+    Ignore,
+    /// The request is evaluated, with a warning in its diagnostics
+    Warn,
+    /// The request is not evaluated and fails with an error
+    Error,
+}
+
+impl FromStr for UnknownEntities {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "ignore" => Ok(UnknownEntities::Ignore),
+            "warn" => Ok(UnknownEntities::Warn),
+            "error" => Ok(UnknownEntities::Error),
+            _ => Err(Error::msg(format!(
+                "invalid unknown entities mode '{}'; expected one of: ignore, warn, error", s))),
+        }
+    }
+}
+
+/// How errors evaluating individual policies are handled
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyErrors {
+    /// Policies that fail to evaluate are skipped, and the errors reported in the diagnostics, as Cedar does
+    #[default]
+    Skip,
+    /// Any policy that fails to evaluate fails the whole request with an error, rather than deciding it
+    Propagate,
+}
+
+impl FromStr for PolicyErrors {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "skip" => Ok(PolicyErrors::Skip),
+            "propagate" => Ok(PolicyErrors::Propagate),
+            _ => Err(Error::msg(format!(
+                "invalid policy errors mode '{}'; expected one of: skip, propagate", s))),
+        }
+    }
+}
+
+/// Parse an optional string argument into an option value, using the configured default when not provided
+fn parse_option<T: FromStr<Err = Error> + Configured>(value: Option<&str>) -> PyResult<T> {
+    match value {
+        None => Ok(T::configured()),
+        Some(value) => value.parse()
+            .map_err(|e: Error| errors::value_error(ErrorCode::InvalidArgument, e)),
+    }
+}
+
+/// The number of threads to evaluate a batch on: `max_workers`, or the configured default, up to
+/// the size of the shared pool
+fn batch_threads(max_workers: Option<usize>) -> PyResult<usize> {
+    let threads = match max_workers {
+        None => config::defaults().threads,
+        Some(0) => return Err(errors::value_error(ErrorCode::InvalidArgument, "max_workers must be at least 1")),
+        Some(threads) => threads,
+    };
+    // the shared pool has no more threads than this
+    Ok(threads.min(config::defaults().max_threads))
+}
+
+/// Options controlling how authorization results are reported
+pub struct AuthzOptions {
+    /// Include the `diagnostics` section in responses
+    pub include_diagnostics: bool,
+    /// Include the `metrics` section in responses
+    pub include_metrics: bool,
+    /// How entities are validated against the schema
+    pub entity_validation: EntityValidation,
+    /// How requests naming a principal or resource that isn't in the entities are handled
+    pub unknown_entities: UnknownEntities,
+    /// How errors evaluating individual policies are handled
+    pub policy_errors: PolicyErrors,
+    /// Fail requests that don't conform to the schema, and every request when the schema is invalid
+    pub validate_request: bool,
+    /// Report whether each policy was satisfied, didn't apply, or errored, in each response's diagnostics
+    pub include_policy_results: bool,
+    /// Classify each denial, when there's a schema, by the kind of fix it needs
+    pub include_denial_class: bool,
+    /// Copy the selected attributes of each request's entities into its response
+    pub include_attributes: Option<AttributeProjection>,
+    /// Report the entities each request refers to that aren't in the entities, in its diagnostics
+    pub include_missing_entities: bool,
+    /// Give requests without a correlation id a generated one, returned in their responses
+    pub generate_correlation_ids: bool,
+    /// The number of threads a batch's requests are evaluated on
+    pub threads: usize,
+}
+
 /// Authorization response returned from the `Authorizer`
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+///
+/// The Cedar `Response` is moved into the `AuthzResponse` and serialized in place, so the
+/// (potentially many) determining policy ids and errors in its diagnostics are never cloned.
+#[derive(Debug, PartialEq, Clone)]
 struct AuthzResponse {
-    /// Authorization decision
-    decision: Decision,
+    /// Cedar's response, holding the authorization decision and diagnostics
+    response: Response,
 
     /// (Optional) id to correlate this response to the request
     correlation_id: Option<String>,
 
-    /// Diagnostics providing more information on how this decision was reached
-    diagnostics: Diagnostics,
+    /// Whether diagnostics are serialized; omitted when the caller opts out of diagnostics
+    include_diagnostics: bool,
+
+    /// Non-fatal problems encountered while evaluating the request, reported apart from its errors
+    /// and diagnostics
+    warnings: Vec<String>,
+
+    /// The entities the request refers to that aren't in the entities, reported with the diagnostics
+    /// when the caller opts in
+    missing_entities: Option<Vec<String>>,
+
+    /// The determining policies of each engine, reported with the diagnostics of a request
+    /// evaluated against several engines
+    reason_by_engine: Option<BTreeMap<String, Vec<String>>>,
+
+    /// The template of each determining policy that's a template link, by the link's id
+    reason_templates: Option<BTreeMap<String, String>>,
+
+    /// The determining policies that are guardrails, reported with the diagnostics of a request
+    /// evaluated by an engine with guardrails
+    reason_guardrails: Option<Vec<String>>,
+
+    /// The determining policies of each layer of a composed policy set, by layer, reported with the
+    /// diagnostics, along with a summary of the layers that decided the request
+    reason_by_layer: Option<BTreeMap<String, Vec<String>>>,
+
+    /// The result of each policy, reported with the diagnostics when the caller opts in
+    policy_results: Option<Vec<PolicyResult>>,
+
+    /// The selected attributes of the request's entities, by their role in the request, when the
+    /// caller opts in
+    attributes: Option<BTreeMap<&'static str, serde_json::Map<String, serde_json::Value>>>,
+
+    /// How long, in seconds, the decision may be cached by the code enforcing it, when known
+    cache_ttl: Option<u64>,
 
-    /// Metrics providing timing information on the authorization decision
-    metrics: HashMap<String, u128>,
+    /// The class of a denial, when the caller opts in and there's a schema
+    denial_class: Option<DenialClass>,
+
+    /// The fingerprints of the inputs that decided the request, to match it to their versions
+    fingerprints: Option<Fingerprints>,
+
+    /// Metrics providing timing information on the authorization decision;
+    /// omitted when the caller opts out of metrics
+    metrics: Option<HashMap<String, u128>>,
+
+    /// The code of the errors in the diagnostics: Cedar's evaluation errors, unless cedarpy
+    /// decided the request without evaluating it
+    diagnostics_code: ErrorCode,
 }
 
 impl AuthzResponse {
-    /// Create a new `AuthzResponse`
-    pub fn new(response: Response, metrics: HashMap<String, u128>, correlation_id: Option<String>) -> Self {
+    /// Create a new `AuthzResponse`, including only the sections requested in `options`
+    pub fn new(response: Response,
+               metrics: HashMap<String, u128>,
+               correlation_id: Option<String>,
+               options: &AuthzOptions) -> Self {
         Self {
-            decision: response.decision(),
+            response,
             correlation_id,
-            diagnostics: response.diagnostics().clone(),
-            metrics,
+            include_diagnostics: options.include_diagnostics,
+            warnings: vec![],
+            missing_entities: None,
+            reason_by_engine: None,
+            reason_templates: None,
+            reason_guardrails: None,
+            reason_by_layer: None,
+            policy_results: None,
+            attributes: None,
+            cache_ttl: None,
+            denial_class: None,
+            fingerprints: None,
+            metrics: options.include_metrics.then_some(metrics),
+            diagnostics_code: ErrorCode::PolicyEvaluation,
         }
     }
+
+    /// Authorization decision
+    pub fn decision(&self) -> Decision {
+        self.response.decision()
+    }
+
+    /// Diagnostics providing more information on how this decision was reached, if included
+    pub fn diagnostics(&self) -> Option<&Diagnostics> {
+        self.include_diagnostics.then(|| self.response.diagnostics())
+    }
+
+    /// Whether resubmitting the request may decide it without the errors in its diagnostics
+    pub fn retryable(&self) -> bool {
+        errors::retryable(self.response.diagnostics().errors().map(|_| self.diagnostics_code))
+    }
+}
+
+impl Serialize for AuthzResponse {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let t_serialize_output = Instant::now();
+        let mut state = serializer.serialize_struct("AuthzResponse", 4)?;
+        state.serialize_field("decision", &self.decision())?;
+        state.serialize_field("correlation_id", &self.correlation_id)?;
+        match self.cache_ttl {
+            Some(cache_ttl) => state.serialize_field("cache_ttl_seconds", &cache_ttl)?,
+            None => state.skip_field("cache_ttl_seconds")?,
+        }
+        // like the decision, a denial's class is reported whether or not diagnostics are
+        match &self.denial_class {
+            Some(denial_class) => state.serialize_field("denial_class", denial_class)?,
+            None => state.skip_field("denial_class")?,
+        }
+        // warnings are reported whether or not diagnostics are, since they never change the decision
+        if self.warnings.is_empty() {
+            state.skip_field("warnings")?
+        } else {
+            state.serialize_field("warnings", &self.warnings)?
+        }
+        // like warnings, whether a result with errors may be retried is reported without diagnostics,
+        // so that a job resubmitting failed requests doesn't need them
+        if self.response.diagnostics().errors().next().is_none() {
+            state.skip_field("retryable")?
+        } else {
+            state.serialize_field("retryable", &self.retryable())?
+        }
+        match &self.fingerprints {
+            Some(fingerprints) => state.serialize_field("fingerprints", fingerprints)?,
+            None => state.skip_field("fingerprints")?,
+        }
+        // like warnings, the attributes are reported whether or not diagnostics are
+        match &self.attributes {
+            Some(attributes) => state.serialize_field("attributes", attributes)?,
+            None => state.skip_field("attributes")?,
+        }
+        match self.diagnostics() {
+            Some(diagnostics) => {
+                let diagnostics = DiagnosticsWithDetails {
+                    diagnostics: BoundedDiagnostics(diagnostics, self.diagnostics_code),
+                    missing_entities: self.missing_entities.as_deref(),
+                    reason_by_engine: self.reason_by_engine.as_ref(),
+                    reason_templates: self.reason_templates.as_ref(),
+                    reason_guardrails: self.reason_guardrails.as_deref(),
+                    reason_by_layer: self.reason_by_layer.as_ref(),
+                    decided_by: self.reason_by_layer.as_ref()
+                        .map(|reason_by_layer| layers::decided_by(self.decision(), reason_by_layer)),
+                    policy_results: self.policy_results.as_deref(),
+                };
+                state.serialize_field("diagnostics", &diagnostics)?
+            }
+            None => state.skip_field("diagnostics")?,
+        }
+        match &self.metrics {
+            Some(metrics) => {
+                // metrics are serialized last so that the serialization duration covers the rest of the response
+                let metrics = MetricsWithSerializeDuration {
+                    metrics,
+                    serialize_output_duration: t_serialize_output.elapsed(),
+                };
+                state.serialize_field("metrics", &metrics)?
+            }
+            None => state.skip_field("metrics")?,
+        }
+        state.end()
+    }
+}
+
+/// Cedar's diagnostics, with bounded error messages, plus any missing entities, determining
+/// policies by engine, templates of the determining links, determining guardrails,
+/// determining policies by layer and a summary of the layers that decided, and the result of each
+/// policy
+#[derive(Serialize)]
+struct DiagnosticsWithDetails<'a> {
+    #[serde(flatten)]
+    diagnostics: BoundedDiagnostics<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    missing_entities: Option<&'a [String]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason_by_engine: Option<&'a BTreeMap<String, Vec<String>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason_templates: Option<&'a BTreeMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason_guardrails: Option<&'a [String]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason_by_layer: Option<&'a BTreeMap<String, Vec<String>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    decided_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    policy_results: Option<&'a [PolicyResult]>,
+}
+
+/// Cedar's diagnostics, serialized with each error message bounded and the errors' code
+struct BoundedDiagnostics<'a>(&'a Diagnostics, ErrorCode);
+
+impl Serialize for BoundedDiagnostics<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+
+        let errors: Vec<String> = self.0.errors().map(|e| errors::bounded(&e.to_string()).into_owned()).collect();
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("reason", &self.0.reason().collect::<Vec<_>>())?;
+        map.serialize_entry("errors", &errors)?;
+        if !errors.is_empty() {
+            let error_codes = vec![self.1.as_str(); errors.len()];
+            map.serialize_entry("error_codes", &error_codes)?;
+        }
+        map.end()
+    }
+}
+
+/// A response's metrics, plus the time taken to serialize the rest of the response
+struct MetricsWithSerializeDuration<'a> {
+    metrics: &'a HashMap<String, u128>,
+    serialize_output_duration: Duration,
+}
+
+impl Serialize for MetricsWithSerializeDuration<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let serialize_output_duration = ("serialize_output_duration_micros",
+                                         self.serialize_output_duration.as_micros());
+        serializer.collect_map(self.metrics.iter()
+            .map(|(name, value)| (name.as_str(), *value))
+            .chain(std::iter::once(serialize_output_duration)))
+    }
 }
 
 /// This uses the Cedar API to call the authorization engine.
@@ -263,31 +2435,72 @@ fn execute_authorization_request(
     policy_set: &PolicySet,
     entities: &Entities,
     schema: &Option<Schema>,
+    options: &AuthzOptions,
+    request_cache: Option<&Mutex<RequestCache>>,
     verbose: bool
 ) -> Result<AuthzResponse, Vec<Error>> {
     let mut errs: Vec<Error> = vec![];
     let t_build_request = Instant::now();
 
     // may want to create request in calling method; then we could get relocate errs
-    let request = match request_args.get_request(schema.as_ref()) {
+    let request = match request_cache {
+        Some(request_cache) => request_cache.lock()
+            .expect("the request cache is not poisoned")
+            .get_request(request_args, schema.as_ref()),
+        None => request_args.get_request(schema.as_ref()),
+    };
+    let request = match request {
         Ok(q) => Some(q),
         Err(e) => {
-            errs.push(e.context("failed to parse schema from request"));
+            errs.push(e.context(errors::context(ErrorCode::Request, "invalid request")));
             None
         }
     };
+    let mut warnings = vec![];
+    if let Some(request) = &request {
+        let unknown = unknown_request_entities(request, entities);
+        match options.unknown_entities {
+            UnknownEntities::Ignore => {}
+            UnknownEntities::Warn => warnings = unknown,
+            UnknownEntities::Error => {
+                errs.extend(unknown.into_iter().map(|unknown| errors::coded(ErrorCode::UnknownEntity, unknown)))
+            }
+        }
+    }
     let build_request_duration = t_build_request.elapsed();
     if errs.is_empty() {
         let request = request.expect("if no errors, we should have a valid request");
         let authorizer = Authorizer::new();
         let t_authz = Instant::now();
-        let ans = authorizer.is_authorized(&request, &policy_set, &entities);
+        let ans = authorizer.is_authorized(&request, policy_set, entities);
+        if options.policy_errors == PolicyErrors::Propagate && ans.diagnostics().errors().next().is_some() {
+            let errs: Vec<Error> = ans.diagnostics()
+                .errors()
+                .map(|e| errors::coded(ErrorCode::PolicyEvaluation, format!("policy evaluation error: {}", e)))
+                .collect();
+            if verbose {
+                println!("encountered errors while evaluating policies. \nerrs: {:#?} ", errs);
+            }
+            return Err(errs);
+        }
         let metrics = HashMap::from([
             (String::from("build_request_duration_micros"), build_request_duration.as_micros()),
             (String::from("authz_duration_micros"), t_authz.elapsed().as_micros()),
         ]);
-        let authz_response = AuthzResponse::new(ans, metrics,
-                                                request_args.correlation_id.clone());
+        let mut authz_response = AuthzResponse::new(ans, metrics,
+                                                    request_args.correlation_id.clone(),
+                                                    options);
+        authz_response.warnings = warnings;
+        if options.include_missing_entities {
+            authz_response.missing_entities = Some(missing_entities(&request, &authz_response.response, entities,
+                                                                    schema));
+        }
+        if options.include_policy_results {
+            authz_response.policy_results = Some(policy_results(&request, policy_set, entities));
+        }
+        if let Some(projection) = &options.include_attributes {
+            authz_response.attributes = Some(projection.project(&request, entities));
+        }
         Ok(authz_response)
     } else {
         if verbose {
@@ -297,8 +2510,77 @@ fn execute_authorization_request(
     }
 }
 
-fn make_entities(entities_str: String, schema: &Option<Schema>, errs: &mut Vec<Error>) -> Entities {
-    let entities = match load_entities(entities_str, schema.as_ref()) {
+/// Describe the principal and resource of `request` that aren't in `entities`
+fn unknown_request_entities(request: &Request, entities: &Entities) -> Vec<String> {
+    [("principal", request.principal()), ("resource", request.resource())]
+        .into_iter()
+        .filter_map(|(what, uid)| {
+            let uid = uid?;
+            entities.get(uid).is_none().then(|| format!("the request's {what} {uid} is not in the entities"))
+        })
+        .collect()
+}
+
+/// The entities `request` refers to that aren't in `entities`: its principal and resource, its
+/// action when there's a schema, which declares the actions as entities, and any entity Cedar could
+/// not find while evaluating the policies for it
+fn missing_entities(request: &Request,
+                    response: &Response,
+                    entities: &Entities,
+                    schema: &Option<Schema>) -> Vec<String> {
+    let no_such_entity = no_such_entity();
+    let dereferenced = response.diagnostics().errors().filter_map(|e| {
+        let message = e.to_string();
+        let uid = &message[message.find(no_such_entity)? + no_such_entity.len()..];
+        uid.parse::<EntityUid>().ok()
+    });
+    let action = request.action().filter(|_| schema.is_some());
+    let mut missing: Vec<String> = [request.principal(), action, request.resource()]
+        .into_iter()
+        .flatten()
+        .cloned()
+        .chain(dereferenced)
+        .filter(|uid| entities.get(uid).is_none())
+        .map(|uid| uid.to_string())
+        .collect();
+    missing.sort();
+    missing.dedup();
+    missing
+}
+
+/// The text before the uid in the error Cedar reports for an entity that isn't in the entities.
+/// Cedar's responses report evaluation errors only as text, so it's taken from Cedar's own error
+/// for a missing entity rather than copied, and can't drift from Cedar's wording.
+fn no_such_entity() -> &'static str {
+    static NO_SUCH_ENTITY: OnceLock<String> = OnceLock::new();
+    NO_SUCH_ENTITY.get_or_init(|| {
+        let uid = cedar_policy_core::ast::EntityUID::with_eid_and_type("Missing", "missing")
+            .expect("Missing::\"missing\" is a uid");
+        let message = cedar_policy_core::evaluator::EvaluationError::EntityDoesNotExist(Arc::new(uid.clone()))
+            .to_string();
+        message.strip_suffix(&uid.to_string()).expect("Cedar's error names the missing entity last").to_owned()
+    })
+}
+
+fn make_entities(entities_src: JsonSource,
+                 schema: &Option<Schema>,
+                 schema_invalid: bool,
+                 entity_validation: EntityValidation,
+                 errs: &mut Vec<Error>,
+                 warnings: &mut Vec<String>) -> Entities {
+    let loaded = match entity_validation {
+        // the schema's error, or warning, was reported when it was parsed
+        _ if schema_invalid => load_entities(entities_src, None),
+        EntityValidation::Strict | EntityValidation::Permissive => load_entities(entities_src, schema.as_ref()),
+        // the entities are loaded again without the schema when they don't validate against it
+        EntityValidation::Warn if schema.is_some() => load_entities(entities_src.clone(), schema.as_ref()).or_else(|e| {
+            warnings.push(format!("entities were loaded without validation: {:#}", e));
+            load_entities(entities_src, None)
+        }),
+        EntityValidation::Warn => load_entities(entities_src, None),
+        EntityValidation::None => load_entities(entities_src, None),
+    };
+    let entities = match loaded {
         Ok(entities) => entities,
         Err(e) => {
             errs.push(e);
@@ -307,44 +2589,41 @@ fn make_entities(entities_str: String, schema: &Option<Schema>, errs: &mut Vec<E
     };
     // load actions from the schema and append into entities
     // we could/may integrate this into the load_entities match
-    let entities = match load_actions_from_schema(entities, schema) {
+    match load_actions_from_schema(entities, schema) {
         Ok(entities) => entities,
         Err(e) => {
             errs.push(e);
             Entities::empty()
         }
-    };
-    entities
+    }
 }
 
-fn make_schema(schema_str: &Option<String>, verbose: bool) -> Option<Schema> {
-    let schema: Option<Schema> = match &schema_str {
-        None => None,
-        Some(schema_src) => {
-            if verbose {
-                println!("schema: {}", schema_src.as_str());
-            }
-            match Schema::from_str(&schema_src) {
-                Ok(schema) => Some(schema),
-                Err(e) => {
-                    // TODO: record this error
-                    // errs.push(e);
-                    if verbose {
-                        println!("!!! error constructing schema: {}", e);
-                    }
-                    None
-                }
-            }
+/// Parse the schema, if there is one.  An invalid schema fails every request, except with the
+/// `warn` and `none` entity validations, with which requests are evaluated without it and warned
+/// about, unless they're validated against it.
+fn make_schema(schema_src: &Option<SchemaSource>, verbose: bool) -> Result<Option<Schema>> {
+    let Some(schema_src) = schema_src else { return Ok(None) };
+    if verbose {
+        println!("schema: {}", schema_src.text());
+    }
+    schema_src.parse().map(Some).inspect_err(|e| {
+        if verbose {
+            println!("!!! error constructing schema: {}", errors::message(e));
         }
-    };
-    schema
+    })
 }
 
-/// Load an `Entities` object from the given JSON string and optional schema.
-fn load_entities(entities_str: String, schema: Option<&Schema>) -> Result<Entities> {
-    return Entities::from_json_str(&entities_str, schema).context(format!(
-        "failed to parse entities from:\n{}", entities_str
-    ));
+/// Load an `Entities` object from the given JSON and optional schema.
+fn load_entities(entities_src: JsonSource, schema: Option<&Schema>) -> Result<Entities> {
+    match entities_src {
+        JsonSource::Text(entities_str) => Entities::from_json_str(&entities_str, schema).with_context(|| {
+            errors::context(ErrorCode::Entities,
+                            format!("failed to parse entities from:\n{}", errors::excerpt(&entities_str)))
+        }),
+        // Cedar takes the value, so a list of entities has no excerpt; its errors name the entity
+        JsonSource::Value(entities_value) => Entities::from_json_value(entities_value, schema)
+            .context(errors::context(ErrorCode::Entities, "failed to parse entities")),
+    }
 }
 
 fn load_actions_from_schema(entities: Entities, schema: &Option<Schema>) -> Result<Entities> {
@@ -356,20 +2635,95 @@ fn load_actions_from_schema(entities: Entities, schema: &Option<Schema>) -> Resu
                     .cloned()
                     .chain(action_entities.iter().cloned()),
             )
-            .context("failed to merge action entities into Entities"),
-            Err(e) => Err(e).context("failed to construct action entities"),
+            .context(errors::context(ErrorCode::Entities, "failed to merge action entities into Entities")),
+            Err(e) => Err(e).context(errors::context(ErrorCode::Entities, "failed to construct action entities")),
         },
         None => Ok(entities),
     }
 }
 
+/// The defaults read from `CEDARPY_*` environment variables when the module was initialized, as a JSON object
+#[pyfunction]
+fn defaults() -> String {
+    serde_json::to_string(config::defaults()).expect("defaults serialize to JSON")
+}
+
+/// The version of cedar-policy resolved in Cargo.lock, which the Python tests check against it
+const CEDAR_VERSION: &str = "2.2.0";
+
+/// The versions of cedarpy and of the Cedar it's built with, as a JSON object
+#[pyfunction]
+fn versions() -> String {
+    json!({"cedarpy": env!("CARGO_PKG_VERSION"), "cedar": CEDAR_VERSION}).to_string()
+}
+
 
 /// A Python module implemented in Rust.
 #[pymodule]
-fn _internal(_py: Python, m: &PyModule) -> PyResult<()> {
+fn _internal(py: Python, m: &PyModule) -> PyResult<()> {
+    config::init().map_err(|e| errors::value_error(ErrorCode::InvalidArgument, errors::message(&e)))?;
+    errors::register(py, m)?;
     m.add_function(wrap_pyfunction!(echo, m)?)?;
+    m.add_function(wrap_pyfunction!(defaults, m)?)?;
+    m.add_function(wrap_pyfunction!(versions, m)?)?;
+    m.add_class::<engine::CedarEngine>()?;
+    m.add_class::<fake::FakeEngine>()?;
+    m.add_class::<view::EngineView>()?;
+    m.add_class::<update::EngineUpdate>()?;
+    m.add_class::<policy_set::PolicySetHandle>()?;
+    m.add_class::<compiled_schema::SchemaHandle>()?;
+    m.add_class::<store::EntityStore>()?;
+    m.add_class::<response::AuthzResponse>()?;
+    m.add_class::<response::ResponseDiagnostics>()?;
+    m.add_function(wrap_pyfunction!(engine::is_authorized_union, m)?)?;
+    m.add_function(wrap_pyfunction!(profile::profile_batch, m)?)?;
+    m.add_function(wrap_pyfunction!(policy_set::compile_policies, m)?)?;
+    m.add_function(wrap_pyfunction!(compiled_schema::compile_schema, m)?)?;
     m.add_function(wrap_pyfunction!(is_authorized, m)?)?;
     m.add_function(wrap_pyfunction!(is_authorized_batch, m)?)?;
+    m.add_function(wrap_pyfunction!(is_authorized_batch_to_file, m)?)?;
+    m.add_function(wrap_pyfunction!(is_authorized_batch_decisions, m)?)?;
+    m.add_function(wrap_pyfunction!(is_authorized_batch_document, m)?)?;
+    m.add_function(wrap_pyfunction!(load_json, m)?)?;
+    m.add_function(wrap_pyfunction!(table::entities_from_csv_table, m)?)?;
+    m.add_function(wrap_pyfunction!(table::entities_from_column_table, m)?)?;
+    m.add_function(wrap_pyfunction!(is_authorized_expanded, m)?)?;
+    m.add_function(wrap_pyfunction!(permission_matrix, m)?)?;
+    m.add_function(wrap_pyfunction!(find_minimal_policy_subset, m)?)?;
+    m.add_function(wrap_pyfunction!(evaluate_expression, m)?)?;
+    m.add_function(wrap_pyfunction!(explain_decision, m)?)?;
+    m.add_function(wrap_pyfunction!(run_inline_tests, m)?)?;
+    m.add_function(wrap_pyfunction!(is_authorized_partial, m)?)?;
+    m.add_function(wrap_pyfunction!(check_separation_of_duty, m)?)?;
+    m.add_function(wrap_pyfunction!(export_entitlements, m)?)?;
+    m.add_function(wrap_pyfunction!(import_relationships, m)?)?;
     m.add_function(wrap_pyfunction!(format_policies, m)?)?;
+    m.add_function(wrap_pyfunction!(formatter_options, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_policy_partial, m)?)?;
+    m.add_function(wrap_pyfunction!(check_parse, m)?)?;
+    m.add_function(wrap_pyfunction!(validate_policies, m)?)?;
+    m.add_function(wrap_pyfunction!(policies_to_json, m)?)?;
+    m.add_function(wrap_pyfunction!(policies_from_json, m)?)?;
+    m.add_function(wrap_pyfunction!(split_policy_source, m)?)?;
+    m.add_function(wrap_pyfunction!(suggest_templates, m)?)?;
+    m.add_function(wrap_pyfunction!(templatize, m)?)?;
+    m.add_function(wrap_pyfunction!(schema_to_json_str, m)?)?;
+    m.add_function(wrap_pyfunction!(schema_from_json_str, m)?)?;
+    m.add_function(wrap_pyfunction!(schema_docs, m)?)?;
+    m.add_function(wrap_pyfunction!(completion_context, m)?)?;
+    m.add_function(wrap_pyfunction!(authorization_surface, m)?)?;
+    m.add_function(wrap_pyfunction!(generate_load, m)?)?;
+    m.add_function(wrap_pyfunction!(check_round_trip, m)?)?;
+    m.add_function(wrap_pyfunction!(qualify_policies, m)?)?;
+    m.add_function(wrap_pyfunction!(qualify_entities, m)?)?;
+    m.add_function(wrap_pyfunction!(qualify_request, m)?)?;
+    m.add_function(wrap_pyfunction!(rewrite_policies, m)?)?;
+    m.add_function(wrap_pyfunction!(rewrite_entities, m)?)?;
+    m.add_function(wrap_pyfunction!(check_entities, m)?)?;
+    m.add_function(wrap_pyfunction!(conformance_report, m)?)?;
+    m.add_function(wrap_pyfunction!(is_in, m)?)?;
+    m.add_function(wrap_pyfunction!(members_of, m)?)?;
+    m.add_function(wrap_pyfunction!(entity_graph, m)?)?;
+    m.add_function(wrap_pyfunction!(assert_never_allowed, m)?)?;
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,198 @@
+//! A limit on how many evaluations an engine runs at once, with a bounded queue for the
+//! evaluations that arrive while it's at the limit, so a burst of batch jobs can't take every
+//! thread from the interactive authorizations sharing the process.
+//!
+//! Evaluations wait for the limit with the GIL released, since the evaluations holding it need
+//! the GIL to return their results.
+//!
+//! Each call is in a lane.  Queued interactive calls run before queued batch calls, and a batch
+//! call gives up its place between requests while interactive calls are waiting, so a long batch
+//! delays an interactive call by at most one request.
+//!
+//! A call with a deadline leaves the queue once it passes.
+
+use std::str::FromStr;
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Error, Result};
+use serde_json::{json, Map, Value};
+
+use crate::errors::{self, ErrorCode};
+
+/// The priority of a call's evaluations
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Lane {
+    /// Latency-sensitive calls, which run first
+    #[default]
+    Interactive,
+    /// Throughput-oriented calls, which run when no interactive calls are waiting
+    Batch,
+}
+
+impl FromStr for Lane {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "interactive" => Ok(Lane::Interactive),
+            "batch" => Ok(Lane::Batch),
+            _ => Err(Error::msg(format!("invalid priority '{s}'; expected one of: interactive, batch"))),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    active: usize,
+    queued_interactive: usize,
+    queued_batch: usize,
+    rejected: u64,
+}
+
+impl State {
+    fn queued(&self) -> usize {
+        self.queued_interactive + self.queued_batch
+    }
+
+    fn queued_mut(&mut self, lane: Lane) -> &mut usize {
+        match lane {
+            Lane::Interactive => &mut self.queued_interactive,
+            Lane::Batch => &mut self.queued_batch,
+        }
+    }
+
+    /// Whether a call in `lane` must wait for another to finish, or one with priority to start
+    fn must_wait(&self, lane: Lane, max_concurrency: usize) -> bool {
+        self.active >= max_concurrency || (lane == Lane::Batch && self.queued_interactive > 0)
+    }
+}
+
+#[derive(Debug)]
+pub struct ConcurrencyLimit {
+    max_concurrency: usize,
+    max_queue: Option<usize>,
+    state: Mutex<State>,
+    available: Condvar,
+}
+
+/// Permission to run an evaluation, given back when dropped
+pub struct Permit<'a> {
+    limit: &'a ConcurrencyLimit,
+    lane: Lane,
+    /// false once the deadline passed while the permit was given up
+    held: bool,
+    /// How long the evaluation waited in the queue
+    pub queue_duration: Duration,
+}
+
+impl Permit<'_> {
+    /// Between the requests of a batch call, give the permit to any waiting interactive call and
+    /// wait to get it back; false when the deadline passes first
+    pub fn yield_to_interactive(&mut self, deadline: Option<Instant>) -> bool {
+        if self.lane != Lane::Batch {
+            return true;
+        }
+        let limit = self.limit;
+        let mut state = limit.lock();
+        if state.queued_interactive == 0 {
+            return true;
+        }
+        let t_queued = Instant::now();
+        state.active -= 1;
+        limit.available.notify_all();
+        state.queued_batch += 1;
+        let (mut state, timed_out) = limit.wait(state, Lane::Batch, deadline);
+        state.queued_batch -= 1;
+        self.queue_duration += t_queued.elapsed();
+        if timed_out {
+            self.held = false;
+            return false;
+        }
+        state.active += 1;
+        true
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if self.held {
+            self.limit.lock().active -= 1;
+            self.limit.available.notify_all();
+        }
+    }
+}
+
+impl ConcurrencyLimit {
+    /// A limit of `max_concurrency` evaluations at once, queueing at most `max_queue` more, or
+    /// any number when None
+    pub fn new(max_concurrency: usize, max_queue: Option<usize>) -> Result<Self> {
+        if max_concurrency == 0 {
+            return Err(anyhow!("invalid max_concurrency 0; expected at least 1"));
+        }
+        Ok(ConcurrencyLimit { max_concurrency, max_queue, state: Mutex::default(), available: Condvar::new() })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect("the concurrency limit is not poisoned")
+    }
+
+    /// Wait until a call in `lane` may run, or the deadline passes; whether it passed
+    fn wait<'a>(&self,
+                state: MutexGuard<'a, State>,
+                lane: Lane,
+                deadline: Option<Instant>) -> (MutexGuard<'a, State>, bool) {
+        let must_wait = |state: &mut State| state.must_wait(lane, self.max_concurrency);
+        match deadline {
+            None => (self.available.wait_while(state, must_wait).expect("the concurrency limit is not poisoned"),
+                     false),
+            Some(deadline) => {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                let (state, result) = self.available
+                    .wait_timeout_while(state, timeout, must_wait)
+                    .expect("the concurrency limit is not poisoned");
+                (state, result.timed_out())
+            }
+        }
+    }
+
+    /// Wait until an evaluation in `lane` may run, or fail when the queue is full or the deadline
+    /// passes first
+    pub fn acquire(&self, lane: Lane, deadline: Option<Instant>) -> Result<Permit<'_>> {
+        let t_queued = Instant::now();
+        let mut state = self.lock();
+        // calls in the same lane run in about the order they arrive, so a call only starts
+        // straight away when none of its lane are already waiting
+        if state.must_wait(lane, self.max_concurrency) || *state.queued_mut(lane) > 0 {
+            if self.max_queue.is_some_and(|max_queue| state.queued() >= max_queue) {
+                state.rejected += 1;
+                return Err(errors::coded(ErrorCode::Overloaded,
+                                         format!("engine overloaded: {} evaluations are running and {} are queued",
+                                                 state.active, state.queued())));
+            }
+            *state.queued_mut(lane) += 1;
+            let timed_out;
+            (state, timed_out) = self.wait(state, lane, deadline);
+            *state.queued_mut(lane) -= 1;
+            if timed_out {
+                // batch calls may have been waiting for this one to leave
+                self.available.notify_all();
+                return Err(errors::coded(ErrorCode::DeadlineExceeded,
+                                         "deadline exceeded while queued for the engine"));
+            }
+        }
+        state.active += 1;
+        Ok(Permit { limit: self, lane, held: true, queue_duration: t_queued.elapsed() })
+    }
+
+    pub fn add_stats(&self, stats: &mut Map<String, Value>) {
+        let state = self.lock();
+        stats.insert(String::from("max_concurrency"), json!(self.max_concurrency));
+        stats.insert(String::from("max_queue"), json!(self.max_queue));
+        stats.insert(String::from("active_evaluations"), json!(state.active));
+        stats.insert(String::from("queued_evaluations"), json!(state.queued()));
+        stats.insert(String::from("queued_interactive_evaluations"), json!(state.queued_interactive));
+        stats.insert(String::from("queued_batch_evaluations"), json!(state.queued_batch));
+        stats.insert(String::from("rejected_evaluations"), json!(state.rejected));
+    }
+}
@@ -0,0 +1,248 @@
+//! Generation of a synthetic, reproducible corpus of entities and requests for a schema and
+//! policies, for benchmarking a policy set with requests shaped like real ones.
+//!
+//! The corpus depends only on its inputs and seed.  The random numbers come from splitmix64,
+//! which is small, fast, and stable across platforms and releases, unlike a general-purpose RNG.
+
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::convert::text_to_json;
+use crate::permissive::parse_entity_uid;
+use crate::schema::{Attribute, SchemaModel};
+
+/// The most requests a corpus may hold
+const MAX_REQUESTS: usize = 10_000_000;
+/// The most entities of each type a corpus may hold
+const MAX_ENTITIES_PER_TYPE: usize = 100_000;
+
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number below `n`, which must be positive
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn chance(&mut self, percent: u64) -> bool {
+        self.next() % 100 < percent
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        (!items.is_empty()).then(|| &items[self.below(items.len())])
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Corpus {
+    seed: u64,
+    entities: Vec<Value>,
+    requests: Vec<Value>,
+}
+
+/// Generate `n_requests` requests, and the entities they refer to, for `schema`, in JSON form.
+/// Each entity type gets `entities_per_type` entities, plus any entities the scopes of
+/// `policies` name, which about half of the requests are drawn from so that the policies apply.
+pub fn generate_load(schema: &str,
+                     policies: &str,
+                     n_requests: usize,
+                     seed: u64,
+                     entities_per_type: usize) -> Result<Corpus> {
+    if n_requests > MAX_REQUESTS {
+        return Err(anyhow!("invalid n_requests {n_requests}; expected at most {MAX_REQUESTS}"));
+    }
+    let model = SchemaModel::from_json(schema)?;
+    let mut generator = Generator::new(&model, policies, seed, entities_per_type)?;
+    let entities = generator.entities(&model);
+
+    let actions: Vec<_> = model.actions.iter()
+        .filter(|action| {
+            let has_types = |types: &Option<Vec<String>>| types.as_ref().is_some_and(|types| {
+                types.iter().any(|entity_type| generator.ids.contains_key(entity_type.as_str()))
+            });
+            has_types(&action.principal_types) && has_types(&action.resource_types)
+        })
+        .collect();
+    if actions.is_empty() && n_requests > 0 {
+        return Err(anyhow!("the schema has no actions with principal and resource types to generate requests for"));
+    }
+    let pick_entity = |types: &Option<Vec<String>>, generator: &mut Generator| -> String {
+        let types: Vec<&String> = types.iter()
+            .flatten()
+            .filter(|entity_type| generator.ids.contains_key(entity_type.as_str()))
+            .collect();
+        let entity_type = types[generator.rng.below(types.len())];
+        let pool = match generator.named.get(entity_type.as_str()) {
+            Some(named_ids) if generator.rng.chance(50) => named_ids,
+            _ => &generator.ids[entity_type.as_str()],
+        };
+        let id = &pool[generator.rng.below(pool.len())];
+        format!("{}::{}", entity_type, Value::String(id.clone()))
+    };
+    let requests = (0..n_requests)
+        .map(|_| {
+            let action = actions[generator.rng.below(actions.len())];
+            let principal = pick_entity(&action.principal_types, &mut generator);
+            let resource = pick_entity(&action.resource_types, &mut generator);
+            json!({
+                "principal": principal,
+                "action": action.name,
+                "resource": resource,
+                "context": generator.record(&action.context),
+            })
+        })
+        .collect();
+    Ok(Corpus { seed, entities, requests })
+}
+
+/// Generates entities and attribute values for a schema model
+pub struct Generator {
+    /// the ids of each type's entities, generated ones first, then the ones the policies name
+    ids: BTreeMap<String, Vec<String>>,
+    named: BTreeMap<String, Vec<String>>,
+    rng: SplitMix64,
+}
+
+impl Generator {
+    /// A generator of `entities_per_type` entities of each type, plus any entities the scopes of
+    /// `policies` name
+    pub fn new(model: &SchemaModel, policies: &str, seed: u64, entities_per_type: usize) -> Result<Self> {
+        if entities_per_type == 0 || entities_per_type > MAX_ENTITIES_PER_TYPE {
+            return Err(anyhow!("invalid entities_per_type {entities_per_type}; expected 1 to {MAX_ENTITIES_PER_TYPE}"));
+        }
+        let mut ids: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut named: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for entity_type in &model.entity_types {
+            let generated = (0..entities_per_type).map(|i| format!("{}-{i}", short_name(&entity_type.name)));
+            ids.insert(entity_type.name.clone(), generated.collect());
+        }
+        for policy in text_to_json(policies)? {
+            for var in ["principal", "resource"] {
+                let Some(entity) = policy.policy.get(var).and_then(|constraint| constraint.get("entity")) else {
+                    continue;
+                };
+                let entity = entity.get("__entity").unwrap_or(entity);
+                let (Some(entity_type), Some(id)) = (entity.get("type").and_then(Value::as_str),
+                                                     entity.get("id").and_then(Value::as_str)) else {
+                    continue;
+                };
+                let Some(type_ids) = ids.get_mut(entity_type) else { continue };
+                if !type_ids.iter().any(|existing| existing == id) {
+                    type_ids.push(id.to_owned());
+                    named.entry(entity_type.to_owned()).or_default().push(id.to_owned());
+                }
+            }
+        }
+        Ok(Generator { ids, named, rng: SplitMix64(seed) })
+    }
+
+    /// A generator of attribute values that refer to the entities, in Cedar's JSON entity format
+    pub fn for_entities(entities: &[Value], seed: u64) -> Self {
+        let mut ids: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for uid in entities.iter().filter_map(|entity| parse_entity_uid(entity.get("uid")?)) {
+            ids.entry(uid.type_name().to_string()).or_default().push(uid.id().as_ref().to_owned());
+        }
+        Generator { ids, named: BTreeMap::new(), rng: SplitMix64(seed) }
+    }
+
+    /// The entities, in Cedar's JSON entity format, with generated attributes and parents
+    pub fn entities(&mut self, model: &SchemaModel) -> Vec<Value> {
+        let mut entities = vec![];
+        for entity_type in &model.entity_types {
+            for (i, id) in self.ids[entity_type.name.as_str()].iter().enumerate() {
+                let attrs = generate_record(&entity_type.attributes, &self.ids, &mut self.rng);
+                // a type may be a member of itself, so parents are only drawn from earlier entities,
+                // keeping the hierarchy acyclic
+                let parents: Vec<Value> = entity_type.member_of_types.iter()
+                    .filter_map(|parent_type| {
+                        let parent_ids = self.ids.get(parent_type.as_str())?;
+                        let candidates = if *parent_type == entity_type.name { &parent_ids[..i] } else { parent_ids };
+                        let parent_id = self.rng.pick(candidates)?;
+                        self.rng.chance(75).then(|| entity_uid(parent_type, parent_id))
+                    })
+                    .collect();
+                entities.push(json!({"uid": entity_uid(&entity_type.name, id), "attrs": attrs, "parents": parents}));
+            }
+        }
+        entities
+    }
+
+    /// A record with values for the attributes, e.g. an action's context
+    pub fn record(&mut self, attributes: &[Attribute]) -> Value {
+        generate_record(attributes, &self.ids, &mut self.rng)
+    }
+}
+
+fn short_name(entity_type: &str) -> &str {
+    entity_type.rsplit("::").next().unwrap_or(entity_type)
+}
+
+fn entity_uid(entity_type: &str, id: &str) -> Value {
+    json!({"__entity": {"type": entity_type, "id": id}})
+}
+
+/// A record with values for the attributes, which are flattened as the schema model has them;
+/// optional attributes are left out about half of the time
+fn generate_record(attributes: &[Attribute], ids: &BTreeMap<String, Vec<String>>, rng: &mut SplitMix64) -> Value {
+    let mut record = Map::new();
+    for attribute in attributes {
+        let Some((name, records)) = attribute.path.split_last() else { continue };
+        if !attribute.required && rng.chance(50) {
+            continue;
+        }
+        let Some(value) = generate_value(&attribute.type_name, ids, rng) else { continue };
+        // the values of records' attributes go in the record, when it was generated
+        if let Some(parent) = record_at(&mut record, records) {
+            parent.insert(name.clone(), value);
+        }
+    }
+    Value::Object(record)
+}
+
+fn record_at<'a>(record: &'a mut Map<String, Value>, path: &[String]) -> Option<&'a mut Map<String, Value>> {
+    match path.split_first() {
+        None => Some(record),
+        Some((name, rest)) => record_at(record.get_mut(name)?.as_object_mut()?, rest),
+    }
+}
+
+/// A value of the type the schema model names, or None for an entity type without entities
+fn generate_value(type_name: &str, ids: &BTreeMap<String, Vec<String>>, rng: &mut SplitMix64) -> Option<Value> {
+    const WORDS: &[&str] = &["alpha", "bravo", "charlie", "delta", "echo", "foxtrot", "golf", "hotel"];
+    let inner = |prefix: &str| type_name.strip_prefix(prefix).and_then(|inner| inner.strip_suffix('>'));
+    if let Some(element) = inner("Set<") {
+        let len = rng.below(4);
+        return Some(Value::Array((0..len).filter_map(|_| generate_value(element, ids, rng)).collect()));
+    }
+    if let Some(entity_type) = inner("Entity<") {
+        let id = rng.pick(ids.get(entity_type)?)?;
+        return Some(entity_uid(entity_type, id));
+    }
+    Some(match inner("Extension<") {
+        Some("ipaddr") => {
+            json!({"__extn": {"fn": "ip", "arg": format!("10.0.{}.{}", rng.below(256), rng.below(256))}})
+        }
+        Some("decimal") => {
+            json!({"__extn": {"fn": "decimal", "arg": format!("{}.{:02}", rng.below(100), rng.below(100))}})
+        }
+        Some(_) => return None,
+        None => match type_name {
+            "String" => json!(WORDS[rng.below(WORDS.len())]),
+            "Long" => json!(rng.below(1000)),
+            "Boolean" => json!(rng.chance(50)),
+            "Record" => json!({}),
+            _ => return None,
+        },
+    })
+}
@@ -0,0 +1,359 @@
+//! Template suggestions for policy sets with many copy-pasted grants: static policies that are the
+//! same but for the entities their principal and resource scopes name are clustered, and each
+//! cluster is suggested as one template, with a slot for each scope whose entity differs, and a
+//! template link per policy, which fills the slots with that policy's entities.
+//!
+//! Policies are compared in Cedar's JSON form, so they cluster when they mean the same whatever
+//! their formatting.  Annotations aren't compared, since each copy of a grant is often annotated
+//! with its own `@id`; a template keeps the annotations all of its policies share.
+//!
+//! A template, suggested or written by hand, can then replace the policies it matches: each is
+//! rewritten to a link of the template, with the policy's `@id` as the link's id, and the
+//! decisions of the rewritten policy set can be checked against the original's over a corpus of
+//! requests.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context, Result};
+use cedar_policy::{Authorizer, Entities, EntityUid, Policy, PolicyId, PolicySet, Request, SlotId, Template};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::convert::{self, text_to_ests};
+use crate::errors::{self, ErrorCode};
+
+/// The scope variables a template may have a slot for
+const SLOTTED: [(&str, &str); 2] = [("principal", "?principal"), ("resource", "?resource")];
+
+/// A template link, in the form of the `templateLinks` of a policy set in Cedar's JSON form
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SuggestedLink {
+    template_id: String,
+    /// The id of the policy the link replaces
+    new_id: String,
+    values: Map<String, Value>,
+}
+
+/// A template that could replace a cluster of static policies, and the links that would
+#[derive(Debug, Serialize)]
+pub struct TemplateSuggestion {
+    template_id: String,
+    text: String,
+    policy: Value,
+    /// The slots of the template, e.g. `?principal`
+    slots: Vec<&'static str>,
+    links: Vec<SuggestedLink>,
+}
+
+/// A policy in JSON form with the entities of its principal and resource scopes taken out, as
+/// `(variable, entity)`
+struct Shape {
+    policy: Value,
+    entities: Vec<(&'static str, Value)>,
+}
+
+impl Shape {
+    fn of(mut policy: Value) -> Self {
+        let mut entities = vec![];
+        for (variable, _) in SLOTTED {
+            let Some(scope) = policy.get_mut(variable).and_then(Value::as_object_mut) else { continue };
+            let constrains = matches!(scope.get("op").and_then(Value::as_str), Some("==" | "in"));
+            if let Some(entity) = scope.remove("entity").filter(|_| constrains) {
+                entities.push((variable, entity));
+            }
+        }
+        Shape { policy, entities }
+    }
+
+    /// What policies must have in common to cluster: everything but their annotations and the
+    /// entities of their scopes
+    fn key(&self) -> String {
+        let mut policy = self.policy.clone();
+        if let Some(policy) = policy.as_object_mut() {
+            policy.remove("annotations");
+        }
+        policy.to_string()
+    }
+
+    fn entity(&self, variable: &str) -> Option<&Value> {
+        self.entities.iter().find(|(name, _)| *name == variable).map(|(_, entity)| entity)
+    }
+}
+
+/// Suggest a template for each cluster of at least `min_cluster_size` static policies of the
+/// Cedar text that differ only in their principal or resource entities, largest cluster first
+pub fn suggest_templates(text: &str, min_cluster_size: usize) -> Result<Vec<TemplateSuggestion>> {
+    let mut clusters: Vec<Vec<(String, Shape)>> = vec![];
+    let mut cluster_of_key: HashMap<String, usize> = HashMap::new();
+    for (id, est) in text_to_ests(text)? {
+        let policy = serde_json::to_value(&est)
+            .context(errors::context(ErrorCode::Serialization, "failed to serialize the JSON policy"))?;
+        let has_slots = SLOTTED.iter().any(|(variable, _)| policy[variable].get("slot").is_some());
+        if has_slots {
+            continue;
+        }
+        let shape = Shape::of(policy);
+        let cluster = *cluster_of_key.entry(shape.key()).or_insert_with(|| {
+            clusters.push(vec![]);
+            clusters.len() - 1
+        });
+        clusters[cluster].push((id, shape));
+    }
+    // clusters are in the order of their first policy, which a stable sort keeps for equal sizes
+    clusters.retain(|cluster| cluster.len() >= min_cluster_size.max(2));
+    clusters.sort_by_key(|cluster| std::cmp::Reverse(cluster.len()));
+
+    let mut suggestions = vec![];
+    for cluster in clusters {
+        let (_, first) = &cluster[0];
+        let slotted: Vec<(&'static str, &'static str)> = SLOTTED.into_iter()
+            .filter(|(variable, _)| cluster.iter().any(|(_, shape)| shape.entity(variable) != first.entity(variable)))
+            .collect();
+        // policies that differ only in their annotations are duplicates, which no template replaces
+        if slotted.is_empty() {
+            continue;
+        }
+        let template_id = format!("template{}", suggestions.len());
+        let mut template = first.policy.clone();
+        for (variable, value) in SLOTTED {
+            let entity = if slotted.iter().any(|(slotted, _)| *slotted == variable) {
+                Value::from(value)
+            } else {
+                match first.entity(variable) {
+                    Some(entity) => entity.clone(),
+                    None => continue,
+                }
+            };
+            let field = if entity.is_string() { "slot" } else { "entity" };
+            template[variable][field] = entity;
+        }
+        set_shared_annotations(&mut template, &cluster);
+        let links = cluster.iter()
+            .map(|(id, shape)| SuggestedLink {
+                template_id: template_id.clone(),
+                new_id: id.clone(),
+                values: slotted.iter()
+                    .filter_map(|(variable, slot)| Some(((*slot).to_owned(), shape.entity(variable)?.clone())))
+                    .collect(),
+            })
+            .collect();
+        suggestions.push(TemplateSuggestion {
+            text: convert::est_to_text(&template_id, convert::parse_est(template.clone())?)?,
+            policy: template,
+            slots: slotted.iter().map(|(_, slot)| *slot).collect(),
+            links,
+            template_id,
+        });
+    }
+    Ok(suggestions)
+}
+
+/// Keep only the annotations of the template that every policy of its cluster has, with the same
+/// value
+fn set_shared_annotations(template: &mut Value, cluster: &[(String, Shape)]) {
+    let Some(annotations) = template.get_mut("annotations").and_then(Value::as_object_mut) else { return };
+    annotations.retain(|key, value| {
+        cluster.iter().all(|(_, shape)| shape.policy.get("annotations").and_then(|a| a.get(key)) == Some(value))
+    });
+    if annotations.is_empty() {
+        template.as_object_mut().expect("policies are JSON objects").remove("annotations");
+    }
+}
+
+/// Policies with those a template matches rewritten to links of it
+#[derive(Debug, Serialize)]
+pub struct Templatized {
+    /// The rewritten policies, as a policy set in Cedar's JSON form
+    policy_set: Value,
+    /// The ids Cedar assigns the policies the links replace, e.g. `policy0`, in the order they appear
+    linked: Vec<String>,
+    /// The decisions of the rewritten policies compared with the original's, when checked
+    verification: Option<Verification>,
+    #[serde(skip)]
+    rewritten: PolicySet,
+    /// The id of each policy in the rewritten policy set, by the id Cedar assigns it
+    #[serde(skip)]
+    new_ids: HashMap<String, String>,
+}
+
+/// Rewrite the static policies of the Cedar text that the template, in Cedar text, matches to links
+/// of it.  A policy matches when filling the template's slots with the entities of its scope gives
+/// the policy, whatever their annotations.  The template's id is its `@id`, or `template0`, and
+/// each policy's id in the rewritten policy set is its `@id`, or the id Cedar assigns it.
+pub fn templatize(text: &str, template: &str) -> Result<Templatized> {
+    let (template_id, template) = match &text_to_ests(template)?[..] {
+        [(_, est)] => {
+            let mut template = serde_json::to_value(est)
+                .context(errors::context(ErrorCode::Serialization, "failed to serialize the JSON template"))?;
+            remove_true_conditions(&mut template);
+            (annotated_id(&template).unwrap_or("template0").to_owned(), template)
+        }
+        templates => return Err(anyhow!("invalid template: expected one template, got {}", templates.len())),
+    };
+    let template_slots: Vec<(&str, &str)> = SLOTTED.into_iter()
+        .filter(|(variable, _)| template[variable].get("slot").is_some())
+        .collect();
+    if template_slots.is_empty() {
+        return Err(anyhow!("invalid template: {template_id} has no slots"));
+    }
+    let template_text = convert::est_to_text(&template_id, convert::parse_est(template.clone())?)?;
+    let mut rewritten = PolicySet::new();
+    rewritten.add_template(Template::parse(Some(template_id.clone()), &template_text)
+        .map_err(|e| anyhow!("invalid template: {e}"))?)
+        .map_err(|e| anyhow!("invalid template: {e}"))?;
+
+    let mut templatized = Templatized {
+        policy_set: Value::Null,
+        linked: vec![],
+        verification: None,
+        rewritten,
+        new_ids: HashMap::new(),
+    };
+    let mut static_policies = Map::new();
+    let mut links = vec![];
+    let mut used_ids: HashSet<String> = HashSet::from([template_id.clone()]);
+    for (id, est) in text_to_ests(text)? {
+        let policy = serde_json::to_value(&est)
+            .context(errors::context(ErrorCode::Serialization, "failed to serialize the JSON policy"))?;
+        let new_id = annotated_id(&policy).unwrap_or(&id).to_owned();
+        if !used_ids.insert(new_id.clone()) {
+            return Err(anyhow!("{id} has the id {new_id}, which another policy or the template has"));
+        }
+        let policy_id = PolicyId::from_str(&new_id).expect("policy ids parse");
+        match fill(&template, &template_slots, &policy) {
+            Some(values) => {
+                let slots = values.iter()
+                    .map(|(slot, entity)| {
+                        let slot = if slot == "?principal" { SlotId::principal() } else { SlotId::resource() };
+                        let entity = EntityUid::from_json(entity.clone()).map_err(|e| anyhow!("{id}: {e}"))?;
+                        Ok((slot, entity))
+                    })
+                    .collect::<Result<_>>()?;
+                let template_policy_id = PolicyId::from_str(&template_id).expect("policy ids parse");
+                templatized.rewritten.link(template_policy_id, policy_id, slots)
+                    .map_err(|e| anyhow!("failed to link {template_id} for {id}: {e}"))?;
+                links.push(SuggestedLink { template_id: template_id.clone(), new_id: new_id.clone(), values });
+                templatized.linked.push(id.clone());
+            }
+            None => {
+                // an existing template stays a template
+                let added = if SLOTTED.iter().any(|(variable, _)| policy[variable].get("slot").is_some()) {
+                    let template = Template::parse(Some(new_id.clone()), convert::est_to_text(&new_id, est)?)
+                        .map_err(|e| anyhow!("{id} is invalid: {e}"))?;
+                    templatized.rewritten.add_template(template)
+                } else {
+                    let policy = Policy::from_json(Some(policy_id), policy.clone())
+                        .map_err(|e| anyhow!("{id} is invalid: {e}"))?;
+                    templatized.rewritten.add(policy)
+                };
+                added.map_err(|e| anyhow!("failed to add {id}: {e}"))?;
+                static_policies.insert(new_id.clone(), policy);
+            }
+        }
+        templatized.new_ids.insert(id, new_id);
+    }
+    let (policies, templates): (Map<String, Value>, Map<String, Value>) = static_policies.into_iter()
+        .partition(|(_, policy)| SLOTTED.iter().all(|(variable, _)| policy[variable].get("slot").is_none()));
+    let mut all_templates = Map::from_iter([(template_id, template)]);
+    all_templates.extend(templates);
+    templatized.policy_set = json!({
+        "staticPolicies": policies,
+        "templates": all_templates,
+        "templateLinks": links,
+    });
+    Ok(templatized)
+}
+
+/// A policy's `@id` annotation
+fn annotated_id(policy: &Value) -> Option<&str> {
+    policy.get("annotations")?.get("id")?.as_str()
+}
+
+/// The entity for each of the template's slots that makes it the policy, if there are some
+fn fill(template: &Value, slots: &[(&str, &str)], policy: &Value) -> Option<Map<String, Value>> {
+    let mut policy = policy.clone();
+    let mut values = Map::new();
+    for (variable, slot) in slots {
+        let scope = policy.get_mut(variable)?.as_object_mut()?;
+        if scope.get("op") != template[variable].get("op") {
+            return None;
+        }
+        values.insert((*slot).to_owned(), scope.remove("entity")?);
+        scope.insert(String::from("slot"), Value::from(*slot));
+    }
+    (comparable(&policy) == comparable(template)).then_some(values)
+}
+
+/// A policy without its annotations or `when { true }` conditions
+fn comparable(policy: &Value) -> Value {
+    let mut policy = policy.clone();
+    policy.as_object_mut().map(|policy| policy.remove("annotations"));
+    remove_true_conditions(&mut policy);
+    policy
+}
+
+/// Remove the `when { true }` conditions that Cedar's text of a policy without conditions has
+fn remove_true_conditions(policy: &mut Value) {
+    if let Some(Value::Array(conditions)) = policy.get_mut("conditions") {
+        let always = json!({"kind": "when", "body": {"Value": true}});
+        conditions.retain(|condition| *condition != always);
+    }
+}
+
+/// A decision and the ids of the policies that decided it
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Outcome {
+    decision: String,
+    reason: BTreeSet<String>,
+}
+
+/// A request that the rewritten policies decide differently
+#[derive(Debug, Serialize)]
+pub struct Mismatch {
+    /// The request's position in the corpus
+    index: usize,
+    /// What the original policies decide, with the ids the policies have in the rewritten policy set
+    original: Outcome,
+    templatized: Outcome,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Verification {
+    requests: usize,
+    /// Whether the rewritten policies decide every request as the original policies do, for the
+    /// same policies
+    equivalent: bool,
+    mismatches: Vec<Mismatch>,
+}
+
+impl Templatized {
+    /// Check that the rewritten policies decide each request as `original`, the policies they were
+    /// rewritten from, do
+    pub fn verify(&mut self, original: &PolicySet, requests: &[Request], entities: &Entities) {
+        let authorizer = Authorizer::new();
+        let outcome = |policy_set: &PolicySet, request: &Request, new_ids: Option<&HashMap<String, String>>| {
+            let response = authorizer.is_authorized(request, policy_set, entities);
+            Outcome {
+                decision: format!("{:?}", response.decision()),
+                reason: response.diagnostics().reason()
+                    .map(|id| {
+                        let id = id.to_string();
+                        new_ids.and_then(|new_ids| new_ids.get(&id)).cloned().unwrap_or(id)
+                    })
+                    .collect(),
+            }
+        };
+        let mismatches: Vec<Mismatch> = requests.iter()
+            .enumerate()
+            .filter_map(|(index, request)| {
+                let original = outcome(original, request, Some(&self.new_ids));
+                let templatized = outcome(&self.rewritten, request, None);
+                (original != templatized).then_some(Mismatch { index, original, templatized })
+            })
+            .collect();
+        let equivalent = mismatches.is_empty();
+        self.verification = Some(Verification { requests: requests.len(), equivalent, mismatches });
+    }
+}
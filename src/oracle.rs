@@ -0,0 +1,198 @@
+//! A negative test oracle: a check that no request matching a pattern is allowed, e.g. that no
+//! principal outside `Group::"admins"` may perform `Action::"deleteTenant"`.
+//!
+//! The check is exhaustive over a bounded entity model rather than a proof: every combination of
+//! principal, action, and resource that the schema allows and the pattern matches is evaluated,
+//! with generated contexts, against the model's entities.  The model is the caller's entities, or
+//! entities generated for the schema, as `generate_load` generates them, along with the entities
+//! the policies name.  The first request allowed is the counterexample.
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context as _, Result};
+use cedar_policy::{Authorizer, Context, Decision, Entities, EntityUid, PolicySet, Request, Schema};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::hierarchy;
+use crate::load::Generator;
+use crate::permissive::parse_entity_uid;
+use crate::schema::SchemaModel;
+
+/// What a request's principal, action, or resource must be to match: the entity, or any entity
+/// of a type, in an entity, and not in another, as each is given
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum EntityPattern {
+    Uid(String),
+    Constraint(Constraint),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Constraint {
+    #[serde(rename = "type")]
+    entity_type: Option<String>,
+    #[serde(rename = "in")]
+    in_entity: Option<String>,
+    not_in: Option<String>,
+}
+
+/// An entity pattern with its uids parsed
+#[derive(Debug, Default)]
+struct Matcher {
+    uid: Option<EntityUid>,
+    entity_type: Option<String>,
+    in_entity: Option<EntityUid>,
+    not_in: Option<EntityUid>,
+}
+
+impl Matcher {
+    fn parse(pattern: Option<EntityPattern>, variable: &str) -> Result<Self> {
+        let uid = |uid: Option<String>| {
+            uid.map(|uid| EntityUid::from_str(&uid).map_err(|e| anyhow!("invalid {variable} pattern {uid}: {e}")))
+                .transpose()
+        };
+        Ok(match pattern {
+            None => Matcher::default(),
+            Some(EntityPattern::Uid(entity)) => Matcher { uid: uid(Some(entity))?, ..Matcher::default() },
+            Some(EntityPattern::Constraint(Constraint { entity_type, in_entity, not_in })) => Matcher {
+                uid: None,
+                entity_type,
+                in_entity: uid(in_entity)?,
+                not_in: uid(not_in)?,
+            },
+        })
+    }
+
+    fn matches(&self, entities: &Entities, uid: &EntityUid) -> bool {
+        self.uid.as_ref().is_none_or(|expected| expected == uid)
+            && self.entity_type.as_ref().is_none_or(|entity_type| *entity_type == uid.type_name().to_string())
+            && self.in_entity.as_ref().is_none_or(|ancestor| hierarchy::is_in(entities, uid, ancestor))
+            && self.not_in.as_ref().is_none_or(|ancestor| !hierarchy::is_in(entities, uid, ancestor))
+    }
+}
+
+/// The requests that must never be allowed; a variable the pattern leaves out matches any entity
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RequestPattern {
+    principal: Option<EntityPattern>,
+    action: Option<EntityPattern>,
+    resource: Option<EntityPattern>,
+}
+
+/// How much of the model is checked
+pub struct Bounds {
+    /// How many contexts each combination of principal, action, and resource is evaluated with,
+    /// for actions whose context has attributes
+    pub contexts_per_request: usize,
+    pub seed: u64,
+    /// The most requests the check may evaluate
+    pub max_requests: usize,
+}
+
+/// A request that's allowed, and the policies that allowed it
+#[derive(Debug, Serialize)]
+pub struct Counterexample {
+    request: Value,
+    reason: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NeverAllowedReport {
+    /// Whether no request the pattern matches is allowed
+    holds: bool,
+    requests_checked: usize,
+    counterexample: Option<Counterexample>,
+    /// The entities of the model, when they were generated, to reproduce the counterexample with
+    pub entities: Option<Vec<Value>>,
+}
+
+/// Entities for the schema, of `entities_per_type` entities of each type plus those the policies
+/// name, in Cedar's JSON entity format
+pub fn model_entities(schema: &str, policies: &str, seed: u64, entities_per_type: usize) -> Result<Vec<Value>> {
+    let model = SchemaModel::from_json(schema)?;
+    Ok(Generator::new(&model, policies, seed, entities_per_type)?.entities(&model))
+}
+
+/// Check that the policies allow no request matching the pattern, a JSON object, over the
+/// model's entities, whose JSON form `entities_json` is, which must include the policies' entities
+#[allow(clippy::too_many_arguments)]
+pub fn check_never_allowed(pattern: &str,
+                           policy_set: &PolicySet,
+                           schema_json: &str,
+                           schema: &Schema,
+                           entities_json: &[Value],
+                           entities: &Entities,
+                           bounds: &Bounds) -> Result<NeverAllowedReport> {
+    let pattern: RequestPattern = serde_json::from_str(pattern).map_err(|e| anyhow!("invalid request pattern: {e}"))?;
+    let principals = Matcher::parse(pattern.principal, "principal")?;
+    let actions = Matcher::parse(pattern.action, "action")?;
+    let resources = Matcher::parse(pattern.resource, "resource")?;
+    let model = SchemaModel::from_json(schema_json)?;
+    let uids: Vec<EntityUid> = entities_json.iter()
+        .filter_map(|entity| parse_entity_uid(entity.get("uid")?))
+        .collect();
+    let of_types = |types: &Option<Vec<String>>, matcher: &Matcher| -> Vec<&EntityUid> {
+        uids.iter()
+            .filter(|uid| types.as_ref().is_none_or(|types| types.contains(&uid.type_name().to_string())))
+            .filter(|uid| matcher.matches(entities, uid))
+            .collect()
+    };
+
+    // each action, with the principals and resources it applies to that the pattern matches
+    let mut combinations = vec![];
+    let mut num_requests: usize = 0;
+    for action in &model.actions {
+        let uid = EntityUid::from_str(&action.name).map_err(|e| anyhow!("invalid action {}: {e}", action.name))?;
+        if !actions.matches(entities, &uid) {
+            continue;
+        }
+        let action_principals = of_types(&action.principal_types, &principals);
+        let action_resources = of_types(&action.resource_types, &resources);
+        let contexts = if action.context.is_empty() { 1 } else { bounds.contexts_per_request.max(1) };
+        num_requests = num_requests.saturating_add(
+            action_principals.len().saturating_mul(action_resources.len()).saturating_mul(contexts));
+        combinations.push((action, uid, action_principals, action_resources, contexts));
+    }
+    if num_requests > bounds.max_requests {
+        return Err(anyhow!("the request pattern matches {num_requests} requests over the entity model, more than \
+                            max_requests ({}); narrow the pattern or the model", bounds.max_requests));
+    }
+
+    let authorizer = Authorizer::new();
+    let mut generator = Generator::for_entities(entities_json, bounds.seed);
+    let mut report = NeverAllowedReport { holds: true, requests_checked: 0, counterexample: None, entities: None };
+    for (action, action_uid, action_principals, action_resources, contexts) in combinations {
+        for principal in &action_principals {
+            for resource in &action_resources {
+                for _ in 0..contexts {
+                    let context_json = generator.record(&action.context);
+                    let context = Context::from_json_value(context_json.clone(), Some((schema, &action_uid)))
+                        .with_context(|| format!("failed to generate a context for {}", action.name))?;
+                    let request = Request::new(Some((*principal).clone()), Some(action_uid.clone()),
+                                               Some((*resource).clone()), context);
+                    let response = authorizer.is_authorized(&request, policy_set, entities);
+                    report.requests_checked += 1;
+                    if response.decision() == Decision::Allow {
+                        let mut reason: Vec<String> = response.diagnostics().reason().map(ToString::to_string).collect();
+                        reason.sort();
+                        report.holds = false;
+                        report.counterexample = Some(Counterexample {
+                            request: json!({
+                                "principal": principal.to_string(),
+                                "action": action.name,
+                                "resource": resource.to_string(),
+                                "context": context_json,
+                            }),
+                            reason,
+                        });
+                        return Ok(report);
+                    }
+                }
+            }
+        }
+    }
+    Ok(report)
+}
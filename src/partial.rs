@@ -0,0 +1,393 @@
+//! Best-effort parsing of policies as they're typed, for editors that validate as the user types.
+//!
+//! Cedar's parser recovers from some errors, but gives up on input that ends mid-policy, which is
+//! most input being typed.  So the text is split into statements at each `;`, each statement is
+//! parsed on its own, and an unfinished last statement is parsed again with its brackets and `;`
+//! supplied, for its structure.  The diagnostics are those of the text as written, with spans.
+
+use std::ops::Range;
+use std::str::FromStr;
+
+use cedar_policy::PolicySet;
+use cedar_policy_core::parser::cst;
+use cedar_policy_core::parser::text_to_cst::grammar::PolicyParser;
+use cedar_policy_core::parser::ASTNode;
+use lalrpop_util::ParseError;
+use serde::Serialize;
+
+/// A range of the text, as character offsets, so that `text[start:end]` is the range in Python,
+/// with the 1-based line and column of each end
+#[derive(Debug, Clone, Serialize)]
+pub struct Span {
+    start: usize,
+    end: usize,
+    line: usize,
+    column: usize,
+    end_line: usize,
+    end_column: usize,
+}
+
+impl Span {
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Diagnostic {
+    severity: &'static str,
+    message: String,
+    span: Span,
+}
+
+/// A constraint of a policy's scope, e.g. `principal == User::"alice"`
+#[derive(Debug, Serialize)]
+pub struct ScopeConstraint {
+    variable: String,
+    text: String,
+    span: Span,
+}
+
+/// A `when` or `unless` condition
+#[derive(Debug, Serialize)]
+pub struct Condition {
+    kind: String,
+    /// The condition's expression, or `None` when it's empty or didn't parse
+    expression: Option<String>,
+    span: Span,
+}
+
+/// What could be made of one policy
+#[derive(Debug, Serialize)]
+pub struct PartialPolicy {
+    /// Whether the policy is finished, i.e. ends with `;`
+    complete: bool,
+    /// `permit` or `forbid`, or `None` when the policy didn't parse
+    effect: Option<String>,
+    annotations: Vec<(String, String)>,
+    scope: Vec<ScopeConstraint>,
+    conditions: Vec<Condition>,
+    span: Span,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PartialParse {
+    policies: Vec<PartialPolicy>,
+    diagnostics: Vec<Diagnostic>,
+    /// Whether the text is a valid policy set
+    valid: bool,
+}
+
+/// A parse error, with the policy it's in and the text of its span
+#[derive(Debug, Serialize)]
+pub struct ParseIssue {
+    /// The id Cedar assigns the policy, e.g. `policy0` for the first
+    policy_id: String,
+    message: String,
+    span: Span,
+    /// The text of the span, which is empty for an error at the end of the input
+    text: String,
+}
+
+/// Converts byte offsets of the text to spans
+pub struct Spans<'a> {
+    text: &'a str,
+}
+
+impl<'a> Spans<'a> {
+    pub fn new(text: &'a str) -> Self {
+        Spans { text }
+    }
+
+    pub fn span(&self, range: Range<usize>) -> Span {
+        let (start, line, column) = self.position(range.start);
+        let (end, end_line, end_column) = self.position(range.end.max(range.start));
+        Span { start, end, line, column, end_line, end_column }
+    }
+
+    /// The character offset, line, and column of a byte offset
+    fn position(&self, offset: usize) -> (usize, usize, usize) {
+        let mut offset = offset.min(self.text.len());
+        while !self.text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let before = &self.text[..offset];
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        (before.chars().count(), before.matches('\n').count() + 1, before[line_start..].chars().count() + 1)
+    }
+}
+
+/// Parse `text` as far as it goes
+pub fn parse_policy_partial(text: &str) -> PartialParse {
+    let spans = Spans::new(text);
+    let mut parse = PartialParse { policies: vec![], diagnostics: vec![], valid: true };
+    for statement in statements(text) {
+        let source = &text[statement.range.clone()];
+        let offset = statement.range.start;
+        let (policy, errors) = parse_statement(source);
+        let parsed = errors.is_empty();
+        for (message, range) in errors {
+            parse.diagnostics.push(Diagnostic {
+                severity: "error",
+                message,
+                span: spans.span(range.start + offset..range.end + offset),
+            });
+        }
+        let policy = match (statement.complete, policy) {
+            (true, policy) => policy,
+            // an unfinished policy's structure is that of the policy it'd be once finished
+            (false, _) => parse_statement(&format!("{}{}", source, closers(source))).0,
+        };
+        if statement.complete && parsed {
+            // the policy's syntax is valid, but it may not be well-formed
+            if let Err(e) = PolicySet::from_str(source) {
+                let range = policy.as_ref().map_or(0..source.len(), |policy| policy.info.0.clone());
+                parse.diagnostics.push(Diagnostic {
+                    severity: "error",
+                    message: e.to_string().trim().to_owned(),
+                    span: spans.span(range.start + offset..range.end + offset),
+                });
+            }
+        }
+        parse.policies.push(partial_policy(policy.as_ref(), source, offset, statement.complete, &spans));
+    }
+    parse.valid = parse.diagnostics.is_empty();
+    parse
+}
+
+/// The span of the first error in `text`, e.g. to locate the error a full parse of it failed with
+pub fn first_error(text: &str) -> Option<Span> {
+    parse_policy_partial(text).diagnostics.into_iter().next().map(|diagnostic| diagnostic.span)
+}
+
+/// The parse errors of `text`, each with the policy it's in and the text of its span
+pub fn check_parse(text: &str) -> Vec<ParseIssue> {
+    let parse = parse_policy_partial(text);
+    parse.diagnostics.into_iter()
+        .map(|diagnostic| {
+            // the policies are in the order of the text, so the error is in the last that starts before it
+            let policy = parse.policies.iter()
+                .rposition(|policy| policy.span.start <= diagnostic.span.start)
+                .unwrap_or(0);
+            let span = diagnostic.span;
+            ParseIssue {
+                policy_id: format!("policy{policy}"),
+                message: diagnostic.message,
+                text: text.chars().skip(span.start).take(span.end - span.start).collect(),
+                span,
+            }
+        })
+        .collect()
+}
+
+/// A statement of the text: the policy before a `;`, or the unfinished one at the end
+struct Statement {
+    range: Range<usize>,
+    complete: bool,
+}
+
+/// Split the text at each `;` outside strings and comments, skipping the whitespace and comments
+/// between statements
+fn statements(text: &str) -> Vec<Statement> {
+    let mut statements = vec![];
+    let mut start = None;
+    let mut scanner = Scanner::default();
+    for (offset, c) in text.char_indices() {
+        let code = scanner.is_code(c);
+        if start.is_none() && code && !c.is_whitespace() {
+            start = Some(offset);
+        } else if scanner.in_comment && start == Some(offset - 1) {
+            // the statement hasn't started: its first `/` began a comment
+            start = None;
+        }
+        if code && c == ';' {
+            statements.push(Statement { range: start.take().unwrap_or(offset)..offset + 1, complete: true });
+        }
+    }
+    if let Some(start) = start {
+        statements.push(Statement { range: start..text.len(), complete: false });
+    }
+    statements
+}
+
+/// Tracks whether each character is in a string, a comment, or code
+#[derive(Default)]
+struct Scanner {
+    in_string: bool,
+    escaped: bool,
+    in_comment: bool,
+    /// whether the previous character was a `/` of code
+    slash: bool,
+}
+
+impl Scanner {
+    /// Whether `c`, the next character, is code; the quotes around a string are
+    fn is_code(&mut self, c: char) -> bool {
+        if self.in_comment {
+            self.in_comment = c != '\n';
+            return false;
+        }
+        if self.in_string {
+            match (self.escaped, c) {
+                (true, _) => self.escaped = false,
+                (false, '\\') => self.escaped = true,
+                (false, '"') => self.in_string = false,
+                _ => {}
+            }
+            return c == '"' && !self.in_string;
+        }
+        if self.slash && c == '/' {
+            self.in_comment = true;
+            self.slash = false;
+            return false;
+        }
+        self.slash = c == '/';
+        self.in_string = c == '"';
+        true
+    }
+}
+
+/// What finishes an unfinished policy: its open string, brackets, and the `;`
+fn closers(source: &str) -> String {
+    let mut scanner = Scanner::default();
+    let mut open = vec![];
+    for c in source.chars() {
+        if !scanner.is_code(c) {
+            continue;
+        }
+        match c {
+            '(' | '[' | '{' => open.push(c),
+            ')' | ']' | '}' => {
+                open.pop();
+            }
+            _ => {}
+        }
+    }
+    let mut closers = String::from(if scanner.in_string { "\"" } else { "" });
+    closers.extend(open.iter().rev().map(|c| match c {
+        '(' => ')',
+        '[' => ']',
+        _ => '}',
+    }));
+    closers.push(';');
+    closers
+}
+
+type PolicyNode = ASTNode<Option<cst::Policy>>;
+
+/// Parse one policy, recovering from the errors the grammar can, with the message and byte
+/// range of each error
+fn parse_statement(source: &str) -> (Option<PolicyNode>, Vec<(String, Range<usize>)>) {
+    let mut recovered = vec![];
+    let result = PolicyParser::new().parse(&mut recovered, source);
+    let mut errors: Vec<(String, Range<usize>)> = recovered.into_iter()
+        .map(|recovery| describe(recovery.error, source))
+        .collect();
+    let policy = match result {
+        Ok(policy) => Some(policy),
+        Err(e) => {
+            errors.push(describe(e, source));
+            None
+        }
+    };
+    (policy, errors)
+}
+
+/// The message and byte range of a parse error
+fn describe<T: std::fmt::Display>(error: ParseError<usize, T, String>, source: &str) -> (String, Range<usize>) {
+    match error {
+        ParseError::InvalidToken { location } => (String::from("invalid token"), location..location + 1),
+        ParseError::UnrecognizedEOF { location, expected } => {
+            (format!("unexpected end of input; expected {}", expected_tokens(&expected)), location..source.len())
+        }
+        ParseError::UnrecognizedToken { token: (start, token, end), expected } => {
+            (format!("unexpected `{}`; expected {}", token, expected_tokens(&expected)), start..end)
+        }
+        ParseError::ExtraToken { token: (start, token, end) } => (format!("unexpected `{}`", token), start..end),
+        ParseError::User { error } => (error, 0..source.len()),
+    }
+}
+
+/// The tokens the grammar expected, as they're written
+fn expected_tokens(expected: &[String]) -> String {
+    let names: Vec<String> = expected.iter()
+        .map(|token| match token.trim_matches('"') {
+            "IDENTIFIER" => String::from("an identifier"),
+            "NUMBER" => String::from("a number"),
+            "STRINGLIT" => String::from("a string"),
+            "PRINCIPAL_SLOT" => String::from("`?principal`"),
+            "RESOURCE_SLOT" => String::from("`?resource`"),
+            // the grammar names its keywords in upper case
+            keyword if keyword.chars().all(|c| c.is_ascii_uppercase()) => format!("`{}`", keyword.to_lowercase()),
+            token => format!("`{}`", token),
+        })
+        .collect();
+    match names.as_slice() {
+        [] => String::from("nothing"),
+        [name] => name.clone(),
+        names => format!("one of {}", names.join(", ")),
+    }
+}
+
+fn partial_policy(policy: Option<&PolicyNode>,
+                  source: &str,
+                  offset: usize,
+                  complete: bool,
+                  spans: &Spans) -> PartialPolicy {
+    // the spans of an unfinished policy's supplied closers are cut back to the text
+    let within = |range: &Range<usize>| range.start.min(source.len())..range.end.min(source.len());
+    let span = |range: &Range<usize>| {
+        let range = within(range);
+        spans.span(range.start + offset..range.end + offset)
+    };
+    let text = |range: &Range<usize>| source[within(range)].to_owned();
+    let mut partial = PartialPolicy {
+        complete,
+        effect: None,
+        annotations: vec![],
+        scope: vec![],
+        conditions: vec![],
+        span: span(&(0..source.len())),
+    };
+    let Some(policy) = policy.and_then(ASTNode::as_inner) else {
+        return partial;
+    };
+    partial.effect = policy.effect.as_inner().map(|_| text(&policy.effect.info.0));
+    partial.annotations = policy.annotations.iter()
+        .filter_map(ASTNode::as_inner)
+        .filter_map(|annotation| {
+            let key = annotation.key.as_inner().map(|_| text(&annotation.key.info.0))?;
+            let cst::Str::String(value) = annotation.value.as_inner()?.clone() else {
+                return None;
+            };
+            Some((key, value.to_string()))
+        })
+        .collect();
+    partial.scope = policy.variables.iter()
+        .filter_map(|variable| {
+            let def = variable.as_inner()?;
+            Some(ScopeConstraint {
+                variable: text(&def.variable.info.0),
+                text: text(&variable.info.0),
+                span: span(&variable.info.0),
+            })
+        })
+        .collect();
+    partial.conditions = policy.conds.iter()
+        .filter_map(|cond| {
+            let inner = cond.as_inner()?;
+            Some(Condition {
+                kind: text(&inner.cond.info.0),
+                expression: inner.expr.as_ref()
+                    .filter(|expr| expr.as_inner().is_some())
+                    .map(|expr| text(&expr.info.0)),
+                span: span(&cond.info.0),
+            })
+        })
+        .collect();
+    partial
+}
@@ -0,0 +1,148 @@
+//! Permissive (open-world) schema validation.
+//!
+//! Cedar rejects entities and contexts that have attributes the schema doesn't declare.  Systems
+//! whose entity exports carry extra attributes can still validate the declared parts by dropping
+//! the undeclared attributes before handing the JSON to Cedar, which is what this module does.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use cedar_policy::{EntityId, EntityTypeName, EntityUid};
+use serde_json::{Map, Value};
+
+/// The attributes a schema declares for a record
+#[derive(Debug)]
+enum Shape {
+    /// A record with exactly these attributes
+    Record(HashMap<String, Shape>),
+    /// Anything else: a primitive, a set, a record allowing additional attributes, or a common type
+    Open,
+}
+
+impl Shape {
+    fn from_schema_type(schema_type: Option<&Value>) -> Shape {
+        let schema_type = match schema_type {
+            Some(Value::Object(schema_type)) => schema_type,
+            _ => return Shape::Open,
+        };
+        if schema_type.get("type").and_then(Value::as_str) != Some("Record")
+            || schema_type.get("additionalAttributes").and_then(Value::as_bool) == Some(true) {
+            return Shape::Open;
+        }
+        let attributes = schema_type.get("attributes")
+            .and_then(Value::as_object)
+            .map(|attributes| {
+                attributes.iter()
+                    .map(|(name, attr_type)| (name.clone(), Shape::from_schema_type(Some(attr_type))))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Shape::Record(attributes)
+    }
+
+    /// Drop attributes of `value` not declared by this shape, recording their paths in `dropped`
+    fn retain_declared(&self, value: &mut Map<String, Value>, path: &str, dropped: &mut Vec<String>) {
+        if let Shape::Record(attributes) = self {
+            value.retain(|name, attr_value| match attributes.get(name) {
+                Some(shape) => {
+                    if let Value::Object(record) = attr_value {
+                        shape.retain_declared(record, &format!("{}.{}", path, name), dropped);
+                    }
+                    true
+                }
+                None => {
+                    dropped.push(format!("{}.{}", path, name));
+                    false
+                }
+            });
+        }
+    }
+}
+
+/// The entity attributes and action contexts declared by a schema
+#[derive(Debug, Default)]
+pub struct DeclaredAttributes {
+    /// shapes of entity attributes, by fully-qualified entity type name
+    entity_types: HashMap<String, Shape>,
+    /// shapes of contexts, by action uid
+    action_contexts: HashMap<String, Shape>,
+}
+
+impl DeclaredAttributes {
+    /// Collect the declared attributes from a schema in Cedar's JSON schema format
+    pub fn from_schema_json(schema: &Value) -> Self {
+        let mut declared = DeclaredAttributes::default();
+        let namespaces = match schema.as_object() {
+            Some(namespaces) => namespaces,
+            None => return declared,
+        };
+        for (namespace, fragment) in namespaces {
+            let qualify = |name: &str| match namespace.as_str() {
+                "" => name.to_string(),
+                namespace => format!("{}::{}", namespace, name),
+            };
+            if let Some(entity_types) = fragment.get("entityTypes").and_then(Value::as_object) {
+                for (name, entity_type) in entity_types {
+                    // entity types without a shape declare no attributes
+                    let shape = match entity_type.get("shape") {
+                        None => Shape::Record(HashMap::new()),
+                        shape => Shape::from_schema_type(shape),
+                    };
+                    declared.entity_types.insert(qualify(name), shape);
+                }
+            }
+            if let Some(actions) = fragment.get("actions").and_then(Value::as_object) {
+                for (name, action) in actions {
+                    let context = action.get("appliesTo").and_then(|applies_to| applies_to.get("context"));
+                    let shape = match context {
+                        None => Shape::Record(HashMap::new()),
+                        context => Shape::from_schema_type(context),
+                    };
+                    if let Ok(action_type) = EntityTypeName::from_str(&qualify("Action")) {
+                        let action_id = EntityId::from_str(name).expect("entity ids are infallible");
+                        let action_uid = EntityUid::from_type_name_and_id(action_type, action_id);
+                        declared.action_contexts.insert(action_uid.to_string(), shape);
+                    }
+                }
+            }
+        }
+        declared
+    }
+
+    /// Drop undeclared attributes from a list of entities in Cedar's JSON entity format,
+    /// returning a description of each attribute that was dropped
+    pub fn retain_declared_entity_attrs(&self, entities: &mut Value) -> Vec<String> {
+        let mut dropped = vec![];
+        if let Value::Array(entities) = entities {
+            for entity in entities.iter_mut() {
+                let uid = entity.get("uid").and_then(parse_entity_uid);
+                let shape = uid.as_ref()
+                    .and_then(|uid| self.entity_types.get(&uid.type_name().to_string()));
+                if let (Some(uid), Some(shape), Some(Value::Object(attrs))) = (uid.as_ref(), shape, entity.get_mut("attrs")) {
+                    shape.retain_declared(attrs, &uid.to_string(), &mut dropped);
+                }
+            }
+        }
+        dropped
+    }
+
+    /// Drop undeclared attributes from the context of a request for `action`,
+    /// returning a description of each attribute that was dropped
+    pub fn retain_declared_context_attrs(&self, action: &str, context: &mut Value) -> Vec<String> {
+        let mut dropped = vec![];
+        let shape = EntityUid::from_str(action).ok()
+            .and_then(|action| self.action_contexts.get(&action.to_string()));
+        if let (Some(shape), Value::Object(context)) = (shape, context) {
+            shape.retain_declared(context, "context", &mut dropped);
+        }
+        dropped
+    }
+}
+
+/// Parse an entity uid in any of the forms accepted in Cedar's JSON entity format
+pub(crate) fn parse_entity_uid(uid: &Value) -> Option<EntityUid> {
+    match uid.get("__expr").and_then(Value::as_str) {
+        Some(expr) => EntityUid::from_str(expr).ok(),
+        None => EntityUid::from_json(uid.clone()).ok(),
+    }
+}
@@ -0,0 +1,60 @@
+//! Per-policy results: whether each policy was satisfied by a request, didn't apply to it, or
+//! errored, and its error, for debugging decisions that the determining policies alone don't
+//! explain, e.g. a Deny whose permits all errored on a missing attribute.
+//!
+//! Cedar's diagnostics name only the determining policies, and only the permits of an Allow or the
+//! forbids of a Deny, so each policy is evaluated against the request on its own.
+
+use cedar_policy::{Authorizer, Entities, Policy, PolicySet, Request};
+use serde::Serialize;
+
+use crate::errors;
+use crate::policy_set;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PolicyResult {
+    id: String,
+    effect: String,
+    /// `satisfied`, `not_applicable`, or `errored`
+    result: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// The result of each policy for the request, in the order of their ids
+pub fn policy_results(request: &Request, policy_set: &PolicySet, entities: &Entities) -> Vec<PolicyResult> {
+    let authorizer = Authorizer::new();
+    let mut results: Vec<PolicyResult> = policy_set.policies()
+        .map(|policy| {
+            let (result, error) = match alone(policy, policy_set) {
+                Some(alone) => {
+                    let response = authorizer.is_authorized(request, &alone, entities);
+                    let error = response.diagnostics().errors().next()
+                        .map(|error| errors::bounded(&error.to_string()).into_owned());
+                    match error {
+                        Some(error) => ("errored", Some(error)),
+                        None if response.diagnostics().reason().next().is_some() => ("satisfied", None),
+                        None => ("not_applicable", None),
+                    }
+                }
+                None => ("errored", Some(format!("failed to evaluate policy {} on its own", policy.id()))),
+            };
+            PolicyResult { id: policy.id().to_string(), effect: policy.effect().to_string(), result, error }
+        })
+        .collect();
+    results.sort_by(|a, b| (a.id.len(), &a.id).cmp(&(b.id.len(), &b.id)));
+    results
+}
+
+/// A policy set of only the policy, with its template when it's a template link
+fn alone(policy: &Policy, policy_set: &PolicySet) -> Option<PolicySet> {
+    let mut alone = PolicySet::new();
+    let Some(template_id) = policy.template_id() else {
+        alone.add(policy.clone()).ok()?;
+        return Some(alone);
+    };
+    let template = policy_set.template(template_id)?;
+    alone.add_template(template.clone()).ok()?;
+    alone.link(template_id.clone(), policy.id().clone(), policy_set::link_values(policy, template)).ok()?;
+    Some(alone)
+}
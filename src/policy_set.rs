@@ -0,0 +1,331 @@
+//! Policy sets parsed once, with `compile_policies`, and passed to `is_authorized` and its batch
+//! variants in place of the policies' text, so a caller whose policies don't change doesn't parse
+//! them again on every call.  A handle is immutable, so it may be shared by any number of calls
+//! and threads.
+//!
+//! The templates among the policies are linked with `template_links` when they're compiled, or
+//! with `link_template`, which makes a new handle with the link, so a handle still never changes.
+//! Likewise `add_policy` and `remove_policy` make a new handle with a policy added or removed, for
+//! a caller whose policies are edited one at a time; only the policy added is parsed.  A linked or
+//! edited handle's fingerprint is of the policies it holds, not of its history, so handles with the
+//! same policies have the same fingerprint however they were edited.
+
+// pyo3 0.19's `#[pymethods]` expansion trips this lint on newer compilers
+#![allow(non_local_definitions)]
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use cedar_policy::{EntityUid, Policy, PolicyId, PolicySet, PrincipalConstraint, ResourceConstraint, SlotId,
+                   Template, TemplatePrincipalConstraint, TemplateResourceConstraint};
+use pyo3::exceptions::PyTypeError;
+use pyo3::prelude::*;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::errors::{self, ErrorCode};
+use crate::permissive::parse_entity_uid;
+use crate::{fingerprint, hash_str};
+
+/// A parsed policy set, with the hash and fingerprint of the text it was parsed from, or of its
+/// content once it's linked or edited
+pub struct CompiledPolicies {
+    pub policy_set: Arc<PolicySet>,
+    pub hash: u64,
+    pub fingerprint: u64,
+    pub parse_duration: Duration,
+}
+
+impl CompiledPolicies {
+    pub fn compile(policies: &str) -> Result<Self> {
+        let t_parse = Instant::now();
+        let policy_set = parse(policies)?;
+        Ok(CompiledPolicies {
+            policy_set: Arc::new(policy_set),
+            hash: hash_str(policies),
+            fingerprint: fingerprint::of(policies),
+            parse_duration: t_parse.elapsed(),
+        })
+    }
+
+    /// The policies with a template linked
+    pub fn link(&self, link: &TemplateLink) -> Result<Self> {
+        let mut values = HashMap::new();
+        let mut slot_values = BTreeMap::new();
+        for (slot, value) in &link.values {
+            let slot_id = match slot.as_str() {
+                "?principal" => SlotId::principal(),
+                "?resource" => SlotId::resource(),
+                _ => return Err(anyhow!("invalid slot {slot} for {}; expected ?principal or ?resource",
+                                        link.new_id)),
+            };
+            let uid = match value {
+                Value::String(uid) => EntityUid::from_str(uid).ok(),
+                value => parse_entity_uid(value),
+            }.ok_or_else(|| anyhow!("invalid entity uid {value} for {slot} of {}", link.new_id))?;
+            slot_values.insert(slot.as_str(), uid.to_string());
+            values.insert(slot_id, uid);
+        }
+        let mut policy_set = (*self.policy_set).clone();
+        policy_set.link(PolicyId::from_str(&link.template_id)?, PolicyId::from_str(&link.new_id)?, values)
+            .map_err(|e| anyhow!("failed to link {} to template {}: {e}", link.new_id, link.template_id))?;
+
+        Ok(self.changed(policy_set, self.parse_duration))
+    }
+
+    /// The policies with a static policy added, with the id `id`, or the first free `policyN`
+    pub fn add_policy(&self, text: &str, id: Option<String>) -> Result<Self> {
+        let id = match id {
+            Some(id) => id,
+            None => (0..)
+                .map(|n| format!("policy{n}"))
+                .find(|id| !self.has_id(id))
+                .expect("some policyN is free"),
+        };
+        if self.has_id(&id) {
+            return Err(anyhow!("failed to add policy {id}: the id is taken"));
+        }
+        let t_parse = Instant::now();
+        let policy = Policy::parse(Some(id.clone()), text).map_err(|parse_errors| {
+            errors::coded(ErrorCode::PolicyParse,
+                          format!("policy parse errors:\n{:#}", parse_errors.errors_as_strings().join("")))
+        })?;
+        let parse_duration = t_parse.elapsed();
+        let mut policy_set = (*self.policy_set).clone();
+        policy_set.add(policy).map_err(|e| anyhow!("failed to add policy {id}: {e}"))?;
+
+        Ok(self.changed(policy_set, parse_duration))
+    }
+
+    /// The policies without the static policy, template link, or unlinked template `id`
+    pub fn remove_policy(&self, id: &str) -> Result<Self> {
+        let policy_id = PolicyId::from_str(id)?;
+        if self.policy_set.template(&policy_id).is_some() {
+            let links: Vec<String> = self.policy_set.policies()
+                .filter(|policy| policy.template_id() == Some(&policy_id))
+                .map(|policy| policy.id().to_string())
+                .collect();
+            if !links.is_empty() {
+                return Err(anyhow!("failed to remove template {id}: it's linked by {}", links.join(", ")));
+            }
+        } else if self.policy_set.policy(&policy_id).is_none() {
+            return Err(anyhow!("failed to remove policy {id}: there's no such policy"));
+        }
+        // Cedar 2.2's policy sets have no way to remove a policy, so the others are cloned into a
+        // new set, and the template links linked again
+        let mut policy_set = PolicySet::new();
+        for template in self.policy_set.templates().filter(|template| template.id() != &policy_id) {
+            policy_set.add_template(template.clone())
+                .map_err(|e| anyhow!("failed to add template {}: {e}", template.id()))?;
+        }
+        for policy in self.policy_set.policies().filter(|policy| policy.id() != &policy_id) {
+            match policy.template_id() {
+                None => policy_set.add(policy.clone()),
+                Some(template_id) => {
+                    let template = self.policy_set.template(template_id)
+                        .ok_or_else(|| anyhow!("template {template_id} of {} is missing", policy.id()))?;
+                    policy_set.link(template_id.clone(), policy.id().clone(), link_values(policy, template))
+                }
+            }.map_err(|e| anyhow!("failed to add policy {}: {e}", policy.id()))?;
+        }
+
+        Ok(self.changed(policy_set, Duration::ZERO))
+    }
+
+    /// Whether a policy or template has the id
+    fn has_id(&self, id: &str) -> bool {
+        PolicyId::from_str(id).is_ok_and(|id| {
+            self.policy_set.policy(&id).is_some() || self.policy_set.template(&id).is_some()
+        })
+    }
+
+    /// These policies changed to `policy_set`, with the hash and fingerprint of its content, so
+    /// that policy sets with the same policies have the same fingerprint whatever edits made them
+    fn changed(&self, policy_set: PolicySet, parse_duration: Duration) -> Self {
+        let content = content(&policy_set);
+        CompiledPolicies {
+            policy_set: Arc::new(policy_set),
+            hash: hash_str(&content),
+            fingerprint: fingerprint::of(&content),
+            parse_duration,
+        }
+    }
+}
+
+/// The canonical text of a policy set's content: its templates, static policies, and template
+/// links, each in the order of their ids, with their ids.  Rendered from the parsed policies, so it
+/// doesn't depend on the text they were parsed from, nor on the order they were added in.
+fn content(policy_set: &PolicySet) -> String {
+    let mut entries: BTreeMap<String, String> = BTreeMap::new();
+    for template in policy_set.templates() {
+        // a template has no text of its own, but a policy set of it does
+        let mut single = PolicySet::new();
+        single.add_template(template.clone()).expect("a template can be added to an empty policy set");
+        entries.insert(format!("template {}", template.id()), single.to_string());
+    }
+    for policy in policy_set.policies() {
+        let text = match policy.template_id().and_then(|id| policy_set.template(id).map(|template| (id, template))) {
+            Some((template_id, template)) => {
+                let values: BTreeMap<String, String> = link_values(policy, template).into_iter()
+                    .map(|(slot, uid)| (format!("{slot:?}"), uid.to_string()))
+                    .collect();
+                format!("link of {template_id}: {values:?}")
+            }
+            None => policy.to_string(),
+        };
+        entries.insert(format!("policy {}", policy.id()), text);
+    }
+    entries.into_iter().map(|(id, text)| format!("{id}\n{text}\n")).collect()
+}
+
+/// The entity of each of a template's slots in a link of it
+pub fn link_values(link: &Policy, template: &Template) -> HashMap<SlotId, EntityUid> {
+    let mut values = HashMap::new();
+    if let TemplatePrincipalConstraint::Eq(None) | TemplatePrincipalConstraint::In(None) = template.principal_constraint() {
+        if let PrincipalConstraint::Eq(uid) | PrincipalConstraint::In(uid) = link.principal_constraint() {
+            values.insert(SlotId::principal(), uid);
+        }
+    }
+    if let TemplateResourceConstraint::Eq(None) | TemplateResourceConstraint::In(None) = template.resource_constraint() {
+        if let ResourceConstraint::Eq(uid) | ResourceConstraint::In(uid) = link.resource_constraint() {
+            values.insert(SlotId::resource(), uid);
+        }
+    }
+    values
+}
+
+/// A link of a template to the entities of its slots, in the form of the `templateLinks` of a
+/// policy set in Cedar's JSON form; an entity is a uid, e.g. `User::"alice"`, or its JSON form
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct TemplateLink {
+    template_id: String,
+    new_id: String,
+    values: BTreeMap<String, Value>,
+}
+
+/// Parse a policy set, with the error message cedarpy reports for policies that don't parse
+pub fn parse(policies: &str) -> Result<PolicySet> {
+    PolicySet::from_str(policies).map_err(|parse_errors| {
+        errors::coded(ErrorCode::PolicyParse,
+                      format!("policy parse errors:\n{:#}", parse_errors.errors_as_strings().join("")))
+    })
+}
+
+/// A policy set compiled by `compile_policies`
+#[pyclass(frozen)]
+pub struct PolicySetHandle {
+    compiled: Arc<CompiledPolicies>,
+}
+
+#[pymethods]
+impl PolicySetHandle {
+    fn num_policies(&self) -> usize {
+        self.compiled.policy_set.policies().count()
+    }
+
+    fn num_templates(&self) -> usize {
+        self.compiled.policy_set.templates().count()
+    }
+
+    /// A handle of these policies with the template linked to the entities of its slots, a JSON
+    /// object such as `{"?principal": "User::\"alice\""}`
+    fn link_template(&self, template_id: String, link_id: String, values: &str) -> PyResult<PolicySetHandle> {
+        let values = serde_json::from_str(values).map_err(|e| errors::value_error(
+            ErrorCode::InvalidArgument, format!("invalid slot values for {link_id}: {e}")))?;
+        let link = TemplateLink { template_id, new_id: link_id, values };
+        self.compiled.link(&link)
+            .map(|compiled| PolicySetHandle { compiled: Arc::new(compiled) })
+            .map_err(|e| errors::value_error(ErrorCode::InvalidArgument, errors::message(&e)))
+    }
+
+    /// A handle of these policies with the static policy `text` added, with the id `id`, or the
+    /// first free `policyN`
+    #[pyo3(signature = (text, id = None))]
+    fn add_policy(&self, text: &str, id: Option<String>) -> PyResult<PolicySetHandle> {
+        self.compiled.add_policy(text, id)
+            .map(|compiled| PolicySetHandle { compiled: Arc::new(compiled) })
+            .map_err(|e| errors::locate(errors::value_error(ErrorCode::of(&e), errors::message(&e)), text))
+    }
+
+    /// A handle of these policies without the static policy, template link, or unlinked template
+    fn remove_policy(&self, id: &str) -> PyResult<PolicySetHandle> {
+        self.compiled.remove_policy(id)
+            .map(|compiled| PolicySetHandle { compiled: Arc::new(compiled) })
+            .map_err(|e| errors::value_error(ErrorCode::InvalidArgument, errors::message(&e)))
+    }
+
+    /// The text of the static policy or template link, if there is one with the id
+    fn get_policy(&self, id: &str) -> Option<String> {
+        let id = PolicyId::from_str(id).ok()?;
+        self.compiled.policy_set.policy(&id).map(ToString::to_string)
+    }
+
+    /// The ids of the static policies and template links, shortest first and then lexically, so the
+    /// ids Cedar gives parsed policies are in the order of their text, e.g. `policy2` before `policy10`
+    fn list_policy_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.compiled.policy_set.policies().map(|policy| policy.id().to_string()).collect();
+        ids.sort_by_key(|id| (id.len(), id.clone()));
+        ids
+    }
+
+    /// The fingerprint of the policies' text, or of the content of a linked or edited handle, as
+    /// reported in the results they decide
+    fn fingerprint(&self) -> String {
+        format!("{:016x}", self.compiled.fingerprint)
+    }
+
+    /// How long the policies took to parse when they were compiled
+    fn parse_duration_micros(&self) -> u128 {
+        self.compiled.parse_duration.as_micros()
+    }
+}
+
+/// Parse the policies into a handle that can be passed in place of their text, linking their
+/// templates with `template_links`, a JSON array of template links
+#[pyfunction]
+#[pyo3(signature = (policies, template_links = None))]
+pub fn compile_policies(policies: &str, template_links: Option<&str>) -> PyResult<PolicySetHandle> {
+    let mut compiled = CompiledPolicies::compile(policies)
+        .map_err(|e| errors::locate(errors::value_error(ErrorCode::PolicyParse, errors::message(&e)), policies))?;
+    let links: Vec<TemplateLink> = match template_links {
+        Some(links) => serde_json::from_str(links).map_err(|e| errors::value_error(
+            ErrorCode::InvalidArgument, format!("invalid template links: {e}")))?,
+        None => vec![],
+    };
+    for link in &links {
+        compiled = compiled.link(link).map_err(|e| errors::value_error(ErrorCode::InvalidArgument, errors::message(&e)))?;
+    }
+    Ok(PolicySetHandle { compiled: Arc::new(compiled) })
+}
+
+/// The policies of a call: their text, or a compiled policy set
+pub enum PolicySource {
+    Text(String),
+    Compiled(Arc<CompiledPolicies>),
+}
+
+impl<'a> FromPyObject<'a> for PolicySource {
+    fn extract(ob: &'a PyAny) -> PyResult<Self> {
+        if let Ok(handle) = ob.extract::<PyRef<PolicySetHandle>>() {
+            return Ok(PolicySource::Compiled(handle.compiled.clone()));
+        }
+        ob.extract().map(PolicySource::Text).map_err(|_| {
+            PyTypeError::new_err(format!("policies must be a str or a PolicySetHandle, not {}",
+                                         ob.get_type().name().unwrap_or("unknown")))
+        })
+    }
+}
+
+impl fmt::Display for PolicySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicySource::Text(text) => f.write_str(text),
+            PolicySource::Compiled(compiled) => write!(f, "<compiled policy set {:016x}>", compiled.fingerprint),
+        }
+    }
+}
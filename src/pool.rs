@@ -0,0 +1,34 @@
+//! The thread pool that batches are evaluated on.  It's shared by every call, so the threads
+//! evaluating requests are bounded by `CEDARPY_MAX_THREADS` however many batches run at once,
+//! rather than each batch spawning threads of its own; a batch's `max_workers` is how many of
+//! them its requests are split across.
+//!
+//! The pool is started the first time a batch uses more than one thread.  If its threads can't be
+//! started, batches are evaluated on the calling thread, and verbose calls log why.
+
+use std::sync::OnceLock;
+
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+
+use crate::config;
+
+static POOL: OnceLock<Result<ThreadPool, ThreadPoolBuildError>> = OnceLock::new();
+
+/// The shared pool, unless its threads couldn't be started
+pub fn shared(verbose: bool) -> Option<&'static ThreadPool> {
+    let pool = POOL.get_or_init(|| {
+        ThreadPoolBuilder::new()
+            .num_threads(config::defaults().max_threads)
+            .thread_name(|index| format!("cedarpy-{index}"))
+            .build()
+    });
+    match pool {
+        Ok(pool) => Some(pool),
+        Err(e) => {
+            if verbose {
+                println!("failed to start the thread pool, so the batch is evaluated on one thread: {e}");
+            }
+            None
+        }
+    }
+}
@@ -0,0 +1,159 @@
+//! Profiles of batch authorization, broken down by stage, for comparing performance across
+//! versions of cedarpy and Cedar.
+
+use std::time::Instant;
+
+use pyo3::prelude::*;
+use serde::Serialize;
+
+use crate::compiled_schema::SchemaSource;
+use crate::input::TextInput;
+use crate::policy_set::PolicySource;
+use crate::{config, errors};
+use crate::{convert_requests, parse_option, serialize_batch_evaluation};
+use crate::{AuthzOptions, BatchEvaluation, JsonSource, PreparedInputs};
+
+/// A batch's profile; its fields are stable so that profiles can be diffed
+#[derive(Debug, Serialize)]
+struct Profile {
+    cedarpy_version: &'static str,
+    num_requests: usize,
+    num_policies: usize,
+    num_entities: usize,
+    num_errored: usize,
+    total_micros: u128,
+    /// time spent in each stage, summed across the requests for per-request stages
+    stages: Stages,
+    /// the distribution of the per-request stages over the requests
+    per_request: PerRequest,
+    /// allocation counts, which need an instrumented allocator that cedarpy isn't built with
+    allocations: Option<()>,
+    /// request cache lookups, which batches don't make; see `CedarEngine.stats()`
+    request_cache: Option<()>,
+    /// batches are evaluated on one thread, so there is no parallel speedup to report
+    parallel_speedup: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+struct Stages {
+    convert_input_micros: u128,
+    parse_policies_micros: u128,
+    parse_schema_micros: u128,
+    load_entities_micros: u128,
+    build_request_micros: u128,
+    authz_micros: u128,
+    serialize_output_micros: u128,
+}
+
+#[derive(Debug, Serialize)]
+struct PerRequest {
+    build_request_micros: Distribution,
+    authz_micros: Distribution,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Distribution {
+    min: u128,
+    p50: u128,
+    p90: u128,
+    p99: u128,
+    max: u128,
+    mean: f64,
+}
+
+impl Distribution {
+    fn of(mut samples: Vec<u128>) -> Self {
+        if samples.is_empty() {
+            return Distribution::default();
+        }
+        samples.sort_unstable();
+        let percentile = |p: usize| samples[(samples.len() - 1) * p / 100];
+        Distribution {
+            min: samples[0],
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: samples[samples.len() - 1],
+            mean: samples.iter().sum::<u128>() as f64 / samples.len() as f64,
+        }
+    }
+}
+
+/// Authorize a batch like `is_authorized_batch` and return a profile of it as a JSON object
+/// rather than its results
+#[pyfunction]
+#[pyo3(signature = (requests, policies, entities, schema = None, verbose = None, entity_validation = None))]
+pub fn profile_batch(requests: &PyAny,
+                     policies: String,
+                     entities: TextInput,
+                     schema: Option<SchemaSource>,
+                     verbose: Option<bool>,
+                     entity_validation: Option<&str>) -> PyResult<String> {
+    let t_total = Instant::now();
+    let verbose = config::verbose(verbose);
+    let (request_args_vec, convert_input_duration) = convert_requests(requests.iter()?)?;
+    let num_requests = request_args_vec.len();
+    let options = AuthzOptions {
+        include_diagnostics: true,
+        include_metrics: true,
+        entity_validation: parse_option(entity_validation)?,
+        unknown_entities: Default::default(),
+        policy_errors: Default::default(),
+        validate_request: false,
+        include_policy_results: false,
+        include_denial_class: false,
+        include_attributes: None,
+        include_missing_entities: false,
+        generate_correlation_ids: false,
+        threads: 1,
+    };
+    let prepared = PreparedInputs::prepare(&PolicySource::Text(policies),
+                                           JsonSource::Text(entities.0),
+                                           schema,
+                                           options.entity_validation,
+                                           verbose)
+        .map_err(|errs| errors::value_error_for(&errs))?;
+
+    let results = prepared.evaluate(request_args_vec, convert_input_duration, &options, None, verbose);
+    let metric = |name: &str| -> Vec<u128> {
+        results.iter()
+            .flatten()
+            .filter_map(|ans| ans.metrics.as_ref()?.get(name).copied())
+            .collect()
+    };
+    let build_request = metric("build_request_duration_micros");
+    let authz = metric("authz_duration_micros");
+    let num_errored = results.iter()
+        .filter(|result| result.as_ref().map_or(true, |ans| ans.response.diagnostics().errors().next().is_some()))
+        .count();
+
+    let t_serialize_output = Instant::now();
+    serialize_batch_evaluation(BatchEvaluation { errs: vec![], results }, num_requests, &options);
+    let serialize_output_duration = t_serialize_output.elapsed();
+
+    let profile = Profile {
+        cedarpy_version: env!("CARGO_PKG_VERSION"),
+        num_requests,
+        num_policies: prepared.policy_set.policies().count(),
+        num_entities: prepared.entities.iter().count(),
+        num_errored,
+        total_micros: t_total.elapsed().as_micros(),
+        stages: Stages {
+            convert_input_micros: convert_input_duration.as_micros(),
+            parse_policies_micros: prepared.parse_policies_duration.as_micros(),
+            parse_schema_micros: prepared.parse_schema_duration.as_micros(),
+            load_entities_micros: prepared.load_entities_duration.as_micros(),
+            build_request_micros: build_request.iter().sum(),
+            authz_micros: authz.iter().sum(),
+            serialize_output_micros: serialize_output_duration.as_micros(),
+        },
+        per_request: PerRequest {
+            build_request_micros: Distribution::of(build_request),
+            authz_micros: Distribution::of(authz),
+        },
+        allocations: None,
+        request_cache: None,
+        parallel_speedup: None,
+    };
+    Ok(serde_json::to_string(&profile).expect("profiles serialize to JSON"))
+}
@@ -0,0 +1,66 @@
+//! Attribute projection: selected attributes of a request's principal, action, and resource, copied
+//! from the entities into its response, so a caller building an audit record or a message from the
+//! decision doesn't need a second lookup of the entities.
+//!
+//! A projection is given as a JSON object of the attributes to copy for each of the request's
+//! entities, e.g. `{"principal": ["department"], "resource": ["owner"]}`, and copied into the
+//! response's `attributes` as `{"principal": {"department": "eng"}, "resource": {"owner": ...}}`.
+//! Values are in Cedar's JSON format.  An entity that isn't in the entities, and an attribute its
+//! entity doesn't have, are left out.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use cedar_policy::{Entities, EntityUid, Request};
+use pyo3::PyResult;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::errors::{self, ErrorCode};
+use crate::evaluate;
+
+/// The attributes to copy of each of a request's entities
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AttributeProjection {
+    #[serde(default)]
+    principal: Vec<String>,
+    #[serde(default)]
+    action: Vec<String>,
+    #[serde(default)]
+    resource: Vec<String>,
+}
+
+impl AttributeProjection {
+    pub fn from_json(spec: &str) -> Result<Self> {
+        serde_json::from_str(spec).context("invalid include_attributes")
+    }
+
+    /// Parse the `include_attributes` argument of an authorization function
+    pub fn parse_option(spec: Option<&str>) -> PyResult<Option<Self>> {
+        spec.map(Self::from_json)
+            .transpose()
+            .map_err(|e| errors::value_error(ErrorCode::InvalidArgument, errors::message(&e)))
+    }
+
+    /// The projected attributes of the request's entities, by the entity's role in the request
+    pub fn project(&self, request: &Request, entities: &Entities) -> BTreeMap<&'static str, Map<String, Value>> {
+        [("principal", request.principal(), &self.principal),
+         ("action", request.action(), &self.action),
+         ("resource", request.resource(), &self.resource)]
+            .into_iter()
+            .filter(|(_, _, attrs)| !attrs.is_empty())
+            .filter_map(|(role, uid, attrs)| Some((role, project_entity(uid?, attrs, entities)?)))
+            .collect()
+    }
+}
+
+fn project_entity(uid: &EntityUid, attrs: &[String], entities: &Entities) -> Option<Map<String, Value>> {
+    let entity = entities.get(uid)?;
+    Some(attrs.iter()
+        .filter_map(|attr| {
+            let value = entity.attr(attr)?.ok()?;
+            Some((attr.clone(), evaluate::to_json(&value)))
+        })
+        .collect())
+}
@@ -0,0 +1,77 @@
+//! Python objects converted straight to JSON values, e.g. entities given as a list of dicts, so
+//! they needn't be serialized with `json.dumps` in Python and parsed again in Rust.  Dicts, lists,
+//! tuples, strs, ints, floats, bools, and None are converted, as `json.dumps` would convert them.
+//! JSON values convert back to the Python objects `json.loads` would make of them.
+
+use pyo3::exceptions::PyTypeError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyFloat, PyList, PyLong, PyString, PyTuple};
+use serde_json::{Map, Number, Value};
+
+use crate::errors::{self, ErrorCode};
+
+/// The JSON value of a Python object
+pub fn to_json(ob: &PyAny) -> PyResult<Value> {
+    if ob.is_none() {
+        Ok(Value::Null)
+    } else if let Ok(s) = ob.downcast::<PyString>() {
+        Ok(Value::String(s.to_str()?.to_owned()))
+    } else if let Ok(b) = ob.downcast::<PyBool>() {
+        // before ints, since bools are ints in Python
+        Ok(Value::Bool(b.is_true()))
+    } else if ob.is_instance_of::<PyLong>() {
+        if let Ok(n) = ob.extract::<i64>() {
+            Ok(Value::from(n))
+        } else {
+            ob.extract::<u64>().map(Value::from).map_err(|_| {
+                errors::value_error(ErrorCode::InvalidArgument, format!("{ob} is too large for a JSON number"))
+            })
+        }
+    } else if let Ok(f) = ob.downcast::<PyFloat>() {
+        Number::from_f64(f.value()).map(Value::Number).ok_or_else(|| {
+            errors::value_error(ErrorCode::InvalidArgument, format!("{ob} is not a JSON number"))
+        })
+    } else if let Ok(dict) = ob.downcast::<PyDict>() {
+        let mut map = Map::with_capacity(dict.len());
+        for (key, value) in dict {
+            let key = key.downcast::<PyString>().map_err(|_| {
+                PyTypeError::new_err(format!("keys must be str, not {}", type_name(key)))
+            })?;
+            map.insert(key.to_str()?.to_owned(), to_json(value)?);
+        }
+        Ok(Value::Object(map))
+    } else if let Ok(list) = ob.downcast::<PyList>() {
+        list.iter().map(to_json).collect::<PyResult<_>>().map(Value::Array)
+    } else if let Ok(tuple) = ob.downcast::<PyTuple>() {
+        tuple.iter().map(to_json).collect::<PyResult<_>>().map(Value::Array)
+    } else {
+        Err(PyTypeError::new_err(format!("Object of type {} is not JSON serializable", type_name(ob))))
+    }
+}
+
+/// The Python object of a JSON value
+pub fn to_py(py: Python<'_>, value: &Value) -> PyObject {
+    match value {
+        Value::Null => py.None(),
+        Value::Bool(b) => b.to_object(py),
+        Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(n), _) => n.to_object(py),
+            (None, Some(n)) => n.to_object(py),
+            (None, None) => n.as_f64().unwrap_or(f64::NAN).to_object(py),
+        },
+        Value::String(s) => s.to_object(py),
+        Value::Array(values) => PyList::new(py, values.iter().map(|value| to_py(py, value))).to_object(py),
+        Value::Object(map) => {
+            let dict = PyDict::new(py);
+            for (key, value) in map {
+                // setting a str key of a fresh dict doesn't fail
+                let _ = dict.set_item(key, to_py(py, value));
+            }
+            dict.to_object(py)
+        }
+    }
+}
+
+fn type_name(ob: &PyAny) -> &str {
+    ob.get_type().name().unwrap_or("unknown")
+}
@@ -0,0 +1,159 @@
+//! Relationship import: the inverse of the entitlement export, converting subject/relation/object
+//! tuples of a relationship-based system into the parents of Cedar entities and the links of policy
+//! templates, so an application migrating to Cedar can carry over its relationships.
+//!
+//! A mapping says, for each relation, whether a tuple makes the object a parent of the subject,
+//! e.g. `Group:eng#member@User:alice`, or links a template with the subject and object in its
+//! slots, e.g. `Doc:plan#viewer@User:alice` linking `permit(principal == ?principal, action ==
+//! Action::"view", resource == ?resource);`.
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{anyhow, Context as _, Result};
+use cedar_policy::EntityUid;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::entitlements;
+use crate::errors::{self, ErrorCode};
+use crate::permissive::parse_entity_uid;
+
+/// How the tuples of each relation are imported, and the entity types the tuples' types are
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Mapping {
+    /// By relation, e.g. `member`, or by object type and relation, e.g. `Doc#viewer`, which takes
+    /// precedence
+    relations: BTreeMap<String, RelationMapping>,
+    /// The Cedar entity types of the tuples' types, e.g. `user` to `User`; others are kept
+    #[serde(default)]
+    types: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RelationMapping {
+    /// Whether the object becomes a parent of the subject
+    #[serde(default)]
+    parent: bool,
+    /// The id of the template to link, e.g. `policy0`
+    template: Option<String>,
+    /// The template's slots, which the subject fills `?principal` and the object `?resource` of;
+    /// both by default
+    slots: Option<Vec<String>>,
+}
+
+/// A link in the form `compile_policies` takes
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedLink {
+    template_id: String,
+    new_id: String,
+    values: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Imported {
+    /// The entities given, with their new parents, then the subjects and parents the tuples add, in
+    /// the order they first appear
+    entities: Vec<Value>,
+    template_links: Vec<ImportedLink>,
+}
+
+/// Import the `tuples`, each a Zanzibar-style `object#relation@subject` str or an entitlement's
+/// JSON `{subject, relation, object}`, into the `entities`, a JSON list, as the `mapping` says
+pub fn import_relationships(tuples: &[Value], mapping: &Mapping, entities: Option<&str>) -> Result<Imported> {
+    let mut entities: Vec<Value> = match entities {
+        Some(entities) => serde_json::from_str(entities)
+            .context(errors::context(ErrorCode::Entities, "failed to parse entities: expected a list \
+                                                                    of entities"))?,
+        None => vec![],
+    };
+    let mut index: HashMap<EntityUid, usize> = entities.iter().enumerate()
+        .filter_map(|(i, entity)| Some((parse_entity_uid(entity.get("uid")?)?, i)))
+        .collect();
+    let mut entity_index = |entities: &mut Vec<Value>, uid: &EntityUid| -> usize {
+        *index.entry(uid.clone()).or_insert_with(|| {
+            entities.push(json!({"uid": uid_json(uid), "attrs": {}, "parents": []}));
+            entities.len() - 1
+        })
+    };
+
+    let mut template_links = vec![];
+    for tuple in tuples {
+        let (subject, relation, object) = parse_tuple(tuple, &mapping.types)
+            .with_context(|| format!("invalid relationship tuple {tuple}"))?;
+        let object_type = object.type_name().to_string();
+        let relation_mapping = mapping.relations.get(&format!("{object_type}#{relation}"))
+            .or_else(|| mapping.relations.get(&relation))
+            .ok_or_else(|| anyhow!("no mapping for relation {relation} of {object_type} in relationship tuple {tuple}"))?;
+        match relation_mapping {
+            RelationMapping { parent: true, template: None, slots: None } => {
+                let subject_index = entity_index(&mut entities, &subject);
+                entity_index(&mut entities, &object);
+                let parents = entities[subject_index].as_object_mut()
+                    .ok_or_else(|| anyhow!("failed to parse entity {subject}: expected an object"))?
+                    .entry("parents")
+                    .or_insert_with(|| json!([]))
+                    .as_array_mut()
+                    .ok_or_else(|| anyhow!("failed to parse the parents of {subject}: expected a list"))?;
+                if !parents.iter().any(|parent| parse_entity_uid(parent).as_ref() == Some(&object)) {
+                    parents.push(uid_json(&object));
+                }
+            }
+            RelationMapping { parent: false, template: Some(template), slots } => {
+                let slots = slots.clone().unwrap_or_else(|| vec!["?principal".to_owned(), "?resource".to_owned()]);
+                let values = slots.into_iter()
+                    .map(|slot| match slot.as_str() {
+                        "?principal" => Ok((slot, subject.to_string())),
+                        "?resource" => Ok((slot, object.to_string())),
+                        _ => Err(anyhow!("invalid slot {slot} in the mapping of {relation}; expected ?principal or \
+                                          ?resource")),
+                    })
+                    .collect::<Result<BTreeMap<_, _>>>()?;
+                let new_id = format!("{template}/{}", values.values().cloned().collect::<Vec<_>>().join("/"));
+                template_links.push(ImportedLink { template_id: template.clone(), new_id, values });
+            }
+            _ => return Err(anyhow!("invalid mapping of {relation}: expected either \"parent\": true or a \
+                                     \"template\", with its \"slots\"")),
+        }
+    }
+    Ok(Imported { entities, template_links })
+}
+
+fn parse_tuple(tuple: &Value, types: &HashMap<String, String>) -> Result<(EntityUid, String, EntityUid)> {
+    let uid = |type_name: &str, id: &str| {
+        let type_name = types.get(type_name).map_or(type_name, String::as_str);
+        parse_entity_uid(&json!({"type": type_name, "id": id}))
+            .ok_or_else(|| anyhow!("invalid entity {type_name}:{id}"))
+    };
+    match tuple {
+        Value::String(tuple) => {
+            let (object, subject) = tuple.split_once('@').ok_or_else(|| anyhow!("expected object#relation@subject"))?;
+            let (object, relation) = object.split_once('#').ok_or_else(|| anyhow!("expected object#relation@subject"))?;
+            // a userset subject, e.g. `Group:eng#member`, is the group
+            let subject = subject.split_once('#').map_or(subject, |(subject, _)| subject);
+            let zanzibar_uid = |object: &str| {
+                let (type_name, id) = object.split_once(':').ok_or_else(|| anyhow!("expected type:id, not {object}"))?;
+                uid(&type_name.replace('/', "::"), &entitlements::decode(id)?)
+            };
+            Ok((zanzibar_uid(subject)?, entitlements::decode(relation)?, zanzibar_uid(object)?))
+        }
+        Value::Object(fields) => {
+            let object_uid = |field: &str| {
+                let object = fields.get(field).ok_or_else(|| anyhow!("expected a {field}"))?;
+                match (object.get("type").and_then(Value::as_str), object.get("id").and_then(Value::as_str)) {
+                    (Some(type_name), Some(id)) => uid(type_name, id),
+                    _ => Err(anyhow!("expected the {field}'s type and id")),
+                }
+            };
+            let relation = fields.get("relation").and_then(Value::as_str).ok_or_else(|| anyhow!("expected a relation"))?;
+            Ok((object_uid("subject")?, relation.to_owned(), object_uid("object")?))
+        }
+        _ => Err(anyhow!("expected a str or an object")),
+    }
+}
+
+fn uid_json(uid: &EntityUid) -> Value {
+    json!({"type": uid.type_name().to_string(), "id": uid.id().as_ref()})
+}
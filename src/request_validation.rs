@@ -0,0 +1,75 @@
+//! Validation of requests against the schema's actions, for callers that would rather a malformed
+//! request fail than be decided: Cedar decides a request with a principal or resource of a type
+//! its action doesn't apply to, or without a context, as it would any other.
+//!
+//! A request conforms when its action is declared, its principal and resource are of the types
+//! the action applies to, and its context has the attributes of the action's context, each of its
+//! declared type, and no others unless the schema allows them.
+
+use std::str::FromStr;
+
+use anyhow::Result;
+use cedar_policy::EntityUid;
+use serde_json::Value;
+
+use crate::conformance::{attribute_problems, AttributeProblem};
+use crate::errors::{self, ErrorCode};
+use crate::schema::SchemaModel;
+use crate::RequestArgs;
+
+/// Check that the request conforms to the schema; uids and contexts that don't parse are left to
+/// fail as they do when the request is built
+pub fn validate_request(model: &SchemaModel, request: &RequestArgs) -> Result<()> {
+    let Some(action) = &request.action else {
+        return Err(errors::coded(ErrorCode::Request,
+                                 "invalid request: the request has no action to validate against the schema"));
+    };
+    let Some(declared) = EntityUid::from_str(action).ok()
+        .and_then(|action| model.actions.iter().find(|declared| declared.name == action.to_string())) else {
+        return Err(errors::coded(ErrorCode::Request,
+                                 format!("invalid request: action {action} is not declared in the schema")));
+    };
+
+    for (what, uid, types) in [("principal", &request.principal, &declared.principal_types),
+                               ("resource", &request.resource, &declared.resource_types)] {
+        let Some(types) = types else { continue };
+        let type_name = match uid {
+            Some(uid) => match EntityUid::from_str(uid) {
+                Ok(uid) => uid.type_name().to_string(),
+                Err(_) => continue,
+            },
+            None => return Err(errors::coded(ErrorCode::Request,
+                                             format!("invalid request: action {action} requires a {what} of type {}",
+                                                     types.join(" or ")))),
+        };
+        if !types.contains(&type_name) {
+            return Err(errors::coded(ErrorCode::Request,
+                                     format!("invalid request: action {action} does not apply to a {what} of type \
+                                              {type_name}; expected {}", types.join(" or "))));
+        }
+    }
+
+    let context = match &request.context_json {
+        Some(context_json) => match serde_json::from_str(context_json) {
+            Ok(Value::Object(context)) => context,
+            _ => return Ok(()),
+        },
+        None => serde_json::Map::new(),
+    };
+    let problems: Vec<String> = attribute_problems(&context, &declared.context, declared.additional_context_attributes)
+        .into_iter()
+        .map(|problem| match problem {
+            AttributeProblem::Missing(attribute) => format!("missing required attribute {}", attribute.name),
+            AttributeProblem::Extra(name) => format!("attribute {name} is not declared"),
+            AttributeProblem::TypeMismatch(attribute) => {
+                format!("attribute {} is not of type {}", attribute.name, attribute.type_name)
+            }
+        })
+        .collect();
+    if !problems.is_empty() {
+        return Err(errors::coded(ErrorCode::Request,
+                                 format!("invalid request: the context does not conform to action {action}: {}",
+                                         problems.join("; "))));
+    }
+    Ok(())
+}
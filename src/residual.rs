@@ -0,0 +1,115 @@
+//! Partial evaluation: authorizing a request some of whose principal, action, resource, or context
+//! is unknown, e.g. the resource of a query that lists what a principal may view, for the policies
+//! that remain, residuals, to be translated into the query's filter.
+//!
+//! Cedar's public `Request` can't leave a variable unknown, so the request is built with the core
+//! crate and cast to the public type, whose representation it is.
+
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context as _, Error};
+use cedar_policy::{Authorizer, Decision, PartialResponse, Request};
+use cedar_policy_core::ast::{self, EntityUIDEntry};
+use ref_cast::RefCast;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::compiled_schema::SchemaSource;
+use crate::config::Configured;
+use crate::policy_set::PolicySource;
+use crate::store::EntitySource;
+use crate::{EntityValidation, PreparedInputs};
+
+/// A request whose parts are None when they're unknown
+pub struct PartialRequest {
+    pub principal: Option<String>,
+    pub action: Option<String>,
+    pub resource: Option<String>,
+    /// A JSON object, whose attributes may be unknown, e.g. `{"__extn": {"fn": "unknown", "arg": "age"}}`
+    pub context_json: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Residual {
+    id: String,
+    effect: String,
+    /// The residual in Cedar's policy syntax, where an unknown is `unknown("resource")`
+    policy: String,
+    /// The residual in Cedar's JSON policy format
+    json: Value,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PartialAuthzResult {
+    /// The decision, or None when it depends on the unknowns
+    decision: Option<String>,
+    /// The policies that remain to decide the request, in the order of their ids, when there's no
+    /// decision
+    residuals: Vec<Residual>,
+    /// The policies that determined the decision, or that are already satisfied when there's none
+    reasons: Vec<String>,
+    errors: Vec<String>,
+}
+
+/// Authorize the request as far as its known parts allow
+pub fn is_authorized_partial(request: &PartialRequest,
+                             policies: &PolicySource,
+                             entities: EntitySource,
+                             schema: Option<SchemaSource>,
+                             verbose: bool) -> Result<PartialAuthzResult, Vec<Error>> {
+    let request = to_core_request(request).map_err(|e| vec![e])?;
+    let prepared = PreparedInputs::prepare(policies, entities, schema, EntityValidation::configured(), verbose)?;
+    let response = Authorizer::new().is_authorized_partial(Request::ref_cast(&request),
+                                                           &prepared.policy_set,
+                                                           &prepared.entities);
+    let (decision, residuals, diagnostics) = match &response {
+        PartialResponse::Concrete(response) => {
+            let decision = match response.decision() {
+                Decision::Allow => "Allow",
+                Decision::Deny => "Deny",
+            };
+            (Some(decision.to_owned()), vec![], response.diagnostics())
+        }
+        PartialResponse::Residual(residual) => {
+            let mut residuals = residual.residuals().policies()
+                .map(|policy| Ok(Residual {
+                    id: policy.id().to_string(),
+                    effect: policy.effect().to_string(),
+                    policy: policy.to_string(),
+                    json: policy.to_json().map_err(|e| anyhow!("failed to convert residual {} to JSON: {e}",
+                                                                policy.id()))?,
+                }))
+                .collect::<Result<Vec<_>, Error>>()
+                .map_err(|e| vec![e])?;
+            residuals.sort_by_key(|residual| (residual.id.len(), residual.id.clone()));
+            (None, residuals, residual.diagnostics())
+        }
+    };
+    let mut reasons: Vec<String> = diagnostics.reason().map(ToString::to_string).collect();
+    reasons.sort_by_key(|id| (id.len(), id.clone()));
+    let errors = diagnostics.errors().map(|e| e.to_string()).collect::<BTreeSet<_>>().into_iter().collect();
+    Ok(PartialAuthzResult { decision, residuals, reasons, errors })
+}
+
+fn to_core_request(request: &PartialRequest) -> anyhow::Result<ast::Request> {
+    let entry = |what: &str, uid: &Option<String>| -> anyhow::Result<EntityUIDEntry> {
+        match uid {
+            Some(uid) => ast::EntityUID::from_str(uid)
+                .map(EntityUIDEntry::concrete)
+                .map_err(|errs| {
+                    let errs: Vec<String> = errs.iter().map(ToString::to_string).collect();
+                    anyhow!("failed to parse {what} {uid} as entity Uid: {}", errs.join("; "))
+                }),
+            None => Ok(EntityUIDEntry::Unknown),
+        }
+    };
+    let context = request.context_json.as_deref()
+        .map(ast::Context::from_json_str)
+        .transpose()
+        .context("failed to parse the context")?;
+    Ok(ast::Request::new_with_unknowns(entry("principal", &request.principal)?,
+                                       entry("action", &request.action)?,
+                                       entry("resource", &request.resource)?,
+                                       context))
+}
@@ -0,0 +1,168 @@
+//! Results returned to Python as objects, by `is_authorized` and `CedarEngine.is_authorized`,
+//! rather than as JSON text that Python parses again.  A result is held as a JSON value in Rust,
+//! and each field is converted to a Python object only when it's read, so a caller that only
+//! reads the decision doesn't pay to convert the diagnostics and metrics.
+//!
+//! Results are read like the dicts they're returned as elsewhere, e.g. `response["decision"]` or
+//! `response.get("warnings", [])`, so `AuthzResult` wraps either.
+
+// pyo3 0.19's `#[pymethods]` expansion trips this lint on newer compilers
+#![allow(non_local_definitions)]
+
+use std::sync::Arc;
+
+use pyo3::exceptions::PyKeyError;
+use pyo3::prelude::*;
+use serde_json::Value;
+
+use crate::pyjson;
+
+/// The result of an authorization request
+#[pyclass(frozen)]
+pub struct AuthzResponse {
+    response: Arc<Value>,
+}
+
+impl AuthzResponse {
+    pub fn new(response: Value) -> Self {
+        AuthzResponse { response: Arc::new(response) }
+    }
+}
+
+#[pymethods]
+impl AuthzResponse {
+    /// "Allow", "Deny", or "NoDecision"
+    #[getter]
+    fn decision(&self) -> Option<&str> {
+        self.response.get("decision").and_then(Value::as_str)
+    }
+
+    #[getter]
+    fn correlation_id(&self) -> Option<&str> {
+        self.response.get("correlation_id").and_then(Value::as_str)
+    }
+
+    /// The diagnostics, which are empty when they weren't requested
+    #[getter]
+    fn diagnostics(&self) -> ResponseDiagnostics {
+        ResponseDiagnostics { response: self.response.clone() }
+    }
+
+    #[getter]
+    fn metrics(&self, py: Python<'_>) -> PyObject {
+        self.field(py, "metrics", &Value::Object(Default::default()))
+    }
+
+    #[getter]
+    fn warnings(&self, py: Python<'_>) -> PyObject {
+        self.field(py, "warnings", &Value::Array(vec![]))
+    }
+
+    #[getter]
+    fn retryable(&self) -> bool {
+        self.response.get("retryable").and_then(Value::as_bool).unwrap_or(false)
+    }
+
+    /// The result as the dict `json.loads` would make of its JSON
+    fn to_dict(&self, py: Python<'_>) -> PyObject {
+        pyjson::to_py(py, &self.response)
+    }
+
+    #[pyo3(signature = (key, default = None))]
+    fn get(&self, py: Python<'_>, key: &str, default: Option<PyObject>) -> PyObject {
+        get(py, &self.response, key, default)
+    }
+
+    fn __getitem__(&self, py: Python<'_>, key: &str) -> PyResult<PyObject> {
+        getitem(py, &self.response, key)
+    }
+
+    fn __contains__(&self, key: &str) -> bool {
+        self.response.get(key).is_some()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("AuthzResponse({})", self.response)
+    }
+}
+
+impl AuthzResponse {
+    fn field(&self, py: Python<'_>, key: &str, default: &Value) -> PyObject {
+        pyjson::to_py(py, self.response.get(key).unwrap_or(default))
+    }
+}
+
+/// The diagnostics of an `AuthzResponse`, read from the response they belong to
+#[pyclass(frozen)]
+pub struct ResponseDiagnostics {
+    response: Arc<Value>,
+}
+
+#[pymethods]
+impl ResponseDiagnostics {
+    /// The ids of the policies that determined the decision
+    #[getter]
+    fn reason(&self, py: Python<'_>) -> PyObject {
+        self.field(py, "reason")
+    }
+
+    #[getter]
+    fn errors(&self, py: Python<'_>) -> PyObject {
+        self.field(py, "errors")
+    }
+
+    #[getter]
+    fn error_codes(&self, py: Python<'_>) -> PyObject {
+        self.field(py, "error_codes")
+    }
+
+    fn to_dict(&self, py: Python<'_>) -> PyObject {
+        pyjson::to_py(py, self.diagnostics().unwrap_or(&Value::Object(Default::default())))
+    }
+
+    #[pyo3(signature = (key, default = None))]
+    fn get(&self, py: Python<'_>, key: &str, default: Option<PyObject>) -> PyObject {
+        match self.diagnostics() {
+            Some(diagnostics) => get(py, diagnostics, key, default),
+            None => default.unwrap_or_else(|| py.None()),
+        }
+    }
+
+    fn __getitem__(&self, py: Python<'_>, key: &str) -> PyResult<PyObject> {
+        match self.diagnostics() {
+            Some(diagnostics) => getitem(py, diagnostics, key),
+            None => Err(PyKeyError::new_err(key.to_owned())),
+        }
+    }
+
+    fn __contains__(&self, key: &str) -> bool {
+        self.diagnostics().and_then(|diagnostics| diagnostics.get(key)).is_some()
+    }
+}
+
+impl ResponseDiagnostics {
+    fn diagnostics(&self) -> Option<&Value> {
+        self.response.get("diagnostics")
+    }
+
+    /// A list field of the diagnostics, or an empty list
+    fn field(&self, py: Python<'_>, key: &str) -> PyObject {
+        match self.diagnostics().and_then(|diagnostics| diagnostics.get(key)) {
+            Some(value) => pyjson::to_py(py, value),
+            None => pyjson::to_py(py, &Value::Array(vec![])),
+        }
+    }
+}
+
+fn get(py: Python<'_>, value: &Value, key: &str, default: Option<PyObject>) -> PyObject {
+    match value.get(key) {
+        Some(field) => pyjson::to_py(py, field),
+        None => default.unwrap_or_else(|| py.None()),
+    }
+}
+
+fn getitem(py: Python<'_>, value: &Value, key: &str) -> PyResult<PyObject> {
+    value.get(key)
+        .map(|field| pyjson::to_py(py, field))
+        .ok_or_else(|| PyKeyError::new_err(key.to_owned()))
+}
@@ -0,0 +1,70 @@
+//! Tokens for resuming a large batch where an earlier call stopped, at a deadline or a limit on
+//! the requests per call.
+//!
+//! A token records how far the batch got, and fingerprints of the batch and of the engine's
+//! inputs, so resuming with a different batch, or after a reload, is an error rather than a
+//! silent mix of results.  Resuming with a token is idempotent: the same token with the same
+//! batch always evaluates the same requests.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use anyhow::{anyhow, Result};
+
+use crate::{PreparedInputs, RequestArgs};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResumeToken {
+    /// The index of the first request not yet evaluated
+    pub offset: usize,
+    batch_hash: u64,
+    inputs_hash: u64,
+}
+
+impl ResumeToken {
+    /// The token for the start of a batch
+    pub fn new(requests: &[RequestArgs], prepared: &PreparedInputs) -> Self {
+        ResumeToken { offset: 0, batch_hash: batch_hash(requests), inputs_hash: inputs_hash(prepared) }
+    }
+
+    /// The token for the same batch, from `offset`
+    pub fn at(self, offset: usize) -> Self {
+        ResumeToken { offset, ..self }
+    }
+
+    pub fn encode(&self) -> String {
+        format!("{:x}.{:016x}.{:016x}", self.offset, self.batch_hash, self.inputs_hash)
+    }
+
+    /// Parse a token, checking that it was issued by `self`'s batch and inputs, of `len` requests
+    pub fn resume(self, token: &str, len: usize) -> Result<Self> {
+        let invalid = || anyhow!("invalid resume token '{token}'");
+        let mut parts = token.split('.');
+        let mut next = || parts.next().and_then(|part| u64::from_str_radix(part, 16).ok()).ok_or_else(invalid);
+        let offset = usize::try_from(next()?).map_err(|_| invalid())?;
+        let (batch_hash, inputs_hash) = (next()?, next()?);
+        if parts.next().is_some() || offset > len {
+            return Err(invalid());
+        }
+        let expected = self.at(offset);
+        if batch_hash != expected.batch_hash {
+            return Err(anyhow!("invalid resume token '{token}': it was issued for a different batch"));
+        }
+        if inputs_hash != expected.inputs_hash {
+            return Err(anyhow!("invalid resume token '{token}': the engine has reloaded since it was issued"));
+        }
+        Ok(expected)
+    }
+}
+
+fn batch_hash(requests: &[RequestArgs]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    requests.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn inputs_hash(prepared: &PreparedInputs) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (prepared.policies_hash, prepared.entities_hash, prepared.schema_hash).hash(&mut hasher);
+    hasher.finish()
+}
@@ -0,0 +1,326 @@
+//! Structural rewriting of entity references in policies, entities, and requests.
+//!
+//! References are found with Cedar's own parser rather than with pattern matching, so strings,
+//! comments, and attribute names that merely look like entity uids are left alone.  Policy text
+//! is edited in place, preserving the author's formatting and comments.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use cedar_policy::{EntityTypeName, EntityUid, PolicySet};
+use cedar_policy_core::parser::cst;
+use cedar_policy_core::parser::text_to_cst::parse_policies;
+use cedar_policy_core::parser::ASTNode;
+use serde_json::{json, Map, Value};
+
+/// Decides the replacement, if any, for each entity uid encountered during a rewrite
+pub trait Rename {
+    /// The uid that should replace `uid`, or `None` to leave it unchanged
+    fn rename(&self, uid: &EntityUid) -> Option<EntityUid>;
+}
+
+/// Qualifies unqualified entity types with a namespace, or strips that namespace again
+#[derive(Debug)]
+pub struct Namespace {
+    namespace: String,
+    unqualify: bool,
+}
+
+impl Namespace {
+    pub fn new(namespace: &str, unqualify: bool) -> Result<Self> {
+        // a namespace is valid exactly when a type name inside it is
+        EntityTypeName::from_str(&format!("{}::Type", namespace))
+            .map_err(|e| anyhow!("invalid namespace '{}': {}", namespace, e))?;
+        Ok(Namespace { namespace: namespace.to_string(), unqualify })
+    }
+}
+
+impl Rename for Namespace {
+    fn rename(&self, uid: &EntityUid) -> Option<EntityUid> {
+        let type_name = uid.type_name().to_string();
+        let renamed = if self.unqualify {
+            type_name.strip_prefix(&format!("{}::", self.namespace))?.to_string()
+        } else if type_name.contains("::") {
+            return None;
+        } else {
+            format!("{}::{}", self.namespace, type_name)
+        };
+        let renamed = EntityTypeName::from_str(&renamed).ok()?;
+        Some(EntityUid::from_type_name_and_id(renamed, uid.id().clone()))
+    }
+}
+
+/// Explicit renames of entity types and of individual entities such as actions
+#[derive(Debug, Default)]
+pub struct Renames {
+    types: HashMap<EntityTypeName, EntityTypeName>,
+    uids: HashMap<EntityUid, EntityUid>,
+}
+
+impl Renames {
+    /// Build renames from pairs of old and new names, each either an entity type like `User`
+    /// or an entity uid like `Action::"view"`
+    pub fn from_pairs<'a>(pairs: impl IntoIterator<Item = (&'a str, &'a str)>) -> Result<Self> {
+        let mut renames = Renames::default();
+        for (from, to) in pairs {
+            if let Ok(from_uid) = EntityUid::from_str(from) {
+                let to_uid = EntityUid::from_str(to)
+                    .map_err(|e| anyhow!("invalid rename of '{}': '{}' is not an entity uid: {}", from, to, e))?;
+                renames.uids.insert(from_uid, to_uid);
+            } else {
+                let from_type = EntityTypeName::from_str(from)
+                    .map_err(|e| anyhow!("invalid rename of '{}': not an entity type or uid: {}", from, e))?;
+                let to_type = EntityTypeName::from_str(to)
+                    .map_err(|e| anyhow!("invalid rename of '{}': '{}' is not an entity type: {}", from, to, e))?;
+                renames.types.insert(from_type, to_type);
+            }
+        }
+        Ok(renames)
+    }
+}
+
+impl Rename for Renames {
+    fn rename(&self, uid: &EntityUid) -> Option<EntityUid> {
+        // renaming a single entity takes precedence over renaming its type
+        if let Some(renamed) = self.uids.get(uid) {
+            return Some(renamed.clone());
+        }
+        self.types
+            .get(uid.type_name())
+            .map(|renamed| EntityUid::from_type_name_and_id(renamed.clone(), uid.id().clone()))
+    }
+}
+
+/// Rewrite the entity uids in a policy set, returning the updated policy text
+pub fn rewrite_policies(policies: &str, rename: &impl Rename) -> Result<String> {
+    PolicySet::from_str(policies).map_err(|e| anyhow!("failed to parse policies: {}", e))?;
+    let cst = parse_policies(policies)
+        .map_err(|errs| anyhow!("failed to parse policies: {:?}", errs))?;
+
+    let mut uids = vec![];
+    if let Some(cst) = cst.as_inner() {
+        for policy in cst.0.iter().filter_map(ASTNode::as_inner) {
+            for variable in policy.variables.iter().filter_map(ASTNode::as_inner) {
+                if let Some((_, expr)) = &variable.ineq {
+                    collect_expr_uids(expr, &mut uids);
+                }
+            }
+            for cond in policy.conds.iter().filter_map(ASTNode::as_inner) {
+                if let Some(expr) = &cond.expr {
+                    collect_expr_uids(expr, &mut uids);
+                }
+            }
+        }
+    }
+
+    // the parser reports uids in source order, so the text can be rebuilt in one pass
+    let mut rewritten = String::with_capacity(policies.len());
+    let mut copied_to = 0;
+    for range in uids {
+        let uid = EntityUid::from_str(&policies[range.clone()])
+            .map_err(|e| anyhow!("failed to parse entity uid '{}': {}", &policies[range.clone()], e))?;
+        if let Some(renamed) = rename.rename(&uid) {
+            rewritten.push_str(&policies[copied_to..range.start]);
+            rewritten.push_str(&renamed.to_string());
+            copied_to = range.end;
+        }
+    }
+    rewritten.push_str(&policies[copied_to..]);
+
+    PolicySet::from_str(&rewritten).map_err(|e| anyhow!("rewritten policies are invalid: {}", e))?;
+    Ok(rewritten)
+}
+
+type Uids = Vec<std::ops::Range<usize>>;
+
+fn collect_expr_uids(expr: &ASTNode<Option<cst::Expr>>, uids: &mut Uids) {
+    let expr = match expr.as_inner() {
+        Some(expr) => expr,
+        None => return,
+    };
+    match expr.expr.as_ref() {
+        cst::ExprData::Or(or) => {
+            if let Some(or) = or.as_inner() {
+                for and in std::iter::once(&or.initial).chain(or.extended.iter()) {
+                    collect_and_uids(and, uids);
+                }
+            }
+        }
+        cst::ExprData::If(cond, then, otherwise) => {
+            collect_expr_uids(cond, uids);
+            collect_expr_uids(then, uids);
+            collect_expr_uids(otherwise, uids);
+        }
+    }
+}
+
+fn collect_and_uids(and: &ASTNode<Option<cst::And>>, uids: &mut Uids) {
+    if let Some(and) = and.as_inner() {
+        for relation in std::iter::once(&and.initial).chain(and.extended.iter()) {
+            collect_relation_uids(relation, uids);
+        }
+    }
+}
+
+fn collect_relation_uids(relation: &ASTNode<Option<cst::Relation>>, uids: &mut Uids) {
+    match relation.as_inner() {
+        Some(cst::Relation::Common { initial, extended }) => {
+            collect_add_uids(initial, uids);
+            for (_, add) in extended {
+                collect_add_uids(add, uids);
+            }
+        }
+        Some(cst::Relation::Has { target, field }) => {
+            collect_add_uids(target, uids);
+            collect_add_uids(field, uids);
+        }
+        Some(cst::Relation::Like { target, pattern }) => {
+            collect_add_uids(target, uids);
+            collect_add_uids(pattern, uids);
+        }
+        None => {}
+    }
+}
+
+fn collect_add_uids(add: &ASTNode<Option<cst::Add>>, uids: &mut Uids) {
+    if let Some(add) = add.as_inner() {
+        for mult in std::iter::once(&add.initial).chain(add.extended.iter().map(|(_, mult)| mult)) {
+            if let Some(mult) = mult.as_inner() {
+                for unary in std::iter::once(&mult.initial).chain(mult.extended.iter().map(|(_, unary)| unary)) {
+                    if let Some(unary) = unary.as_inner() {
+                        collect_member_uids(&unary.item, uids);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn collect_member_uids(member: &ASTNode<Option<cst::Member>>, uids: &mut Uids) {
+    let member = match member.as_inner() {
+        Some(member) => member,
+        None => return,
+    };
+    match member.item.as_inner() {
+        Some(cst::Primary::Ref(uid)) => {
+            if let Some(cst::Ref::Uid { .. }) = uid.as_inner() {
+                uids.push(uid.info.0.clone());
+            }
+        }
+        Some(cst::Primary::Expr(expr)) => collect_expr_uids(expr, uids),
+        Some(cst::Primary::EList(exprs)) => {
+            for expr in exprs {
+                collect_expr_uids(expr, uids);
+            }
+        }
+        Some(cst::Primary::RInits(inits)) => {
+            for init in inits.iter().filter_map(ASTNode::as_inner) {
+                collect_expr_uids(&init.0, uids);
+                collect_expr_uids(&init.1, uids);
+            }
+        }
+        _ => {}
+    }
+    for access in member.access.iter().filter_map(ASTNode::as_inner) {
+        match access {
+            cst::MemAccess::Call(args) => {
+                for arg in args {
+                    collect_expr_uids(arg, uids);
+                }
+            }
+            cst::MemAccess::Index(index) => collect_expr_uids(index, uids),
+            cst::MemAccess::Field(_) => {}
+        }
+    }
+}
+
+/// Rewrite the entity uids in a list of entities in Cedar's JSON entity format: each entity's
+/// uid, its parents, and any entity references among its attributes
+pub fn rewrite_entities(entities: &mut Value, rename: &impl Rename) -> Result<()> {
+    let entities = entities
+        .as_array_mut()
+        .ok_or_else(|| anyhow!("entities must be a list"))?;
+    for entity in entities.iter_mut() {
+        if let Some(uid) = entity.get_mut("uid") {
+            rewrite_uid_json(uid, rename);
+        }
+        if let Some(Value::Array(parents)) = entity.get_mut("parents") {
+            for parent in parents.iter_mut() {
+                rewrite_uid_json(parent, rename);
+            }
+        }
+        if let Some(Value::Object(attrs)) = entity.get_mut("attrs") {
+            rewrite_attr_values(attrs, rename);
+        }
+    }
+    Ok(())
+}
+
+/// Rewrite an entity uid given in any of the forms accepted in Cedar's JSON entity format
+fn rewrite_uid_json(uid: &mut Value, rename: &impl Rename) {
+    if let Some(inner) = uid.get_mut("__entity") {
+        return rewrite_uid_json(inner, rename);
+    }
+    if let Some(expr) = uid.get("__expr").and_then(Value::as_str) {
+        // `__expr` may also hold an extension value such as `ip("10.0.0.1")`
+        if let Some(renamed) = EntityUid::from_str(expr).ok().and_then(|uid| rename.rename(&uid)) {
+            *uid = json!({ "__expr": renamed.to_string() });
+        }
+        return;
+    }
+    if let Some(renamed) = EntityUid::from_json(uid.clone()).ok().and_then(|uid| rename.rename(&uid)) {
+        *uid = json!({ "type": renamed.type_name().to_string(), "id": renamed.id().as_ref() });
+    }
+}
+
+/// Rewrite the explicit entity references (`__entity` or `__expr`) among attribute values.
+/// Records with just `type` and `id` attributes are ambiguous without a schema and left alone.
+fn rewrite_attr_values(attrs: &mut Map<String, Value>, rename: &impl Rename) {
+    for value in attrs.values_mut() {
+        rewrite_attr_value(value, rename);
+    }
+}
+
+fn rewrite_attr_value(value: &mut Value, rename: &impl Rename) {
+    match value {
+        Value::Object(record) if record.contains_key("__entity") || record.contains_key("__expr") => {
+            rewrite_uid_json(value, rename)
+        }
+        Value::Object(record) => rewrite_attr_values(record, rename),
+        Value::Array(values) => {
+            for value in values.iter_mut() {
+                rewrite_attr_value(value, rename);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Rewrite an entity uid given in Cedar syntax, e.g. `User::"alice"`
+pub fn rewrite_uid_str(uid: &str, rename: &impl Rename) -> Result<String> {
+    let parsed = EntityUid::from_str(uid)
+        .map_err(|e| anyhow!("failed to parse entity uid '{}': {}", uid, e))?;
+    Ok(match rename.rename(&parsed) {
+        Some(renamed) => renamed.to_string(),
+        None => uid.to_string(),
+    })
+}
+
+/// Rewrite the principal, action, resource, and context entity references of a request given as
+/// a JSON object
+pub fn rewrite_request(request: &mut Value, rename: &impl Rename) -> Result<()> {
+    let request = request
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("request must be an object"))?;
+    for key in ["principal", "action", "resource"] {
+        if let Some(Value::String(uid)) = request.get(key) {
+            let renamed = rewrite_uid_str(uid, rename)?;
+            request.insert(key.to_string(), Value::String(renamed));
+        }
+    }
+    if let Some(Value::Object(context)) = request.get_mut("context") {
+        rewrite_attr_values(context, rename);
+    }
+    Ok(())
+}
@@ -0,0 +1,237 @@
+//! A model of a schema's entity types and actions, read from its JSON form, for documenting a
+//! schema and reporting on the policies and entities that use it.
+//!
+//! The schema is validated by Cedar first, so the model only has to read well-formed JSON.  Names
+//! are qualified with their namespace, and common types are resolved to their definitions.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use cedar_policy::Schema;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::errors::{self, ErrorCode};
+
+/// One attribute of an entity type or action context; attributes of records are flattened into
+/// rows named by their path, e.g. `address.city`
+#[derive(Debug, Clone, Serialize)]
+pub struct Attribute {
+    pub name: String,
+    /// The attribute names on the way to the attribute, ending with its own
+    #[serde(skip)]
+    pub path: Vec<String>,
+    /// The attribute's type, e.g. `String`, `Set<Entity<User>>`, or `Record`
+    #[serde(rename = "type")]
+    pub type_name: String,
+    pub required: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EntityType {
+    pub name: String,
+    pub member_of_types: Vec<String>,
+    pub attributes: Vec<Attribute>,
+    /// Whether entities may have attributes the schema doesn't declare
+    #[serde(skip)]
+    pub additional_attributes: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Action {
+    /// The action's entity uid, e.g. `Action::"view"`
+    pub name: String,
+    pub member_of: Vec<String>,
+    /// The principal types the action applies to, or None when the schema leaves them unspecified
+    pub principal_types: Option<Vec<String>>,
+    /// The resource types the action applies to, or None when the schema leaves them unspecified
+    pub resource_types: Option<Vec<String>>,
+    pub context: Vec<Attribute>,
+    /// Whether contexts may have attributes the schema doesn't declare
+    #[serde(skip)]
+    pub additional_context_attributes: bool,
+}
+
+/// The entity types and actions of a schema, in the order the schema declares them
+#[derive(Debug, Serialize)]
+pub struct SchemaModel {
+    pub entity_types: Vec<EntityType>,
+    pub actions: Vec<Action>,
+}
+
+/// The hierarchies of a schema as adjacency lists, from each entity type or action to the ones
+/// it may be a member of
+#[derive(Debug, Serialize)]
+pub struct Hierarchy {
+    entity_types: BTreeMap<String, Vec<String>>,
+    actions: BTreeMap<String, Vec<String>>,
+}
+
+/// A schema's documentation: its model and hierarchies
+#[derive(Debug, Serialize)]
+pub struct SchemaDocs {
+    #[serde(flatten)]
+    model: SchemaModel,
+    hierarchy: Hierarchy,
+}
+
+impl SchemaModel {
+    pub fn from_json(schema: &str) -> Result<Self> {
+        let schema: Value = serde_json::from_str(schema)
+            .map_err(|e| errors::coded(ErrorCode::Schema, format!("invalid schema: {e}")))?;
+        Schema::from_json_value(schema.clone())
+            .map_err(|e| errors::coded(ErrorCode::Schema, format!("invalid schema: {e}")))?;
+        let Value::Object(namespaces) = schema else {
+            return Err(errors::coded(ErrorCode::Schema, "invalid schema: expected an object of namespaces"));
+        };
+        let mut model = SchemaModel { entity_types: vec![], actions: vec![] };
+        for (namespace, definitions) in &namespaces {
+            let namespace = Namespace { name: namespace, common_types: definitions.get("commonTypes") };
+            for (name, entity_type) in objects(definitions.get("entityTypes")) {
+                model.entity_types.push(EntityType {
+                    name: namespace.qualify(name),
+                    member_of_types: strings(entity_type.get("memberOfTypes"))
+                        .map(|name| namespace.qualify(name))
+                        .collect(),
+                    attributes: entity_type.get("shape").map(|shape| namespace.attributes(shape)).unwrap_or_default(),
+                    additional_attributes: entity_type.get("shape")
+                        .map(|shape| namespace.resolve(shape))
+                        .and_then(|shape| shape.get("additionalAttributes"))
+                        .and_then(Value::as_bool)
+                        .unwrap_or(false),
+                });
+            }
+            for (name, action) in objects(definitions.get("actions")) {
+                let applies_to = action.get("appliesTo");
+                let types = |key: &str| {
+                    applies_to.and_then(|applies_to| applies_to.get(key)).map(|types| {
+                        strings(Some(types)).map(|name| namespace.qualify(name)).collect()
+                    })
+                };
+                model.actions.push(Action {
+                    name: action_uid(&namespace.qualify("Action"), name),
+                    member_of: action.get("memberOf")
+                        .and_then(Value::as_array)
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|parent| {
+                            let id = parent.get("id")?.as_str()?;
+                            let action_type = match parent.get("type").and_then(Value::as_str) {
+                                Some(action_type) => action_type.to_owned(),
+                                None => namespace.qualify("Action"),
+                            };
+                            Some(action_uid(&action_type, id))
+                        })
+                        .collect(),
+                    principal_types: types("principalTypes"),
+                    resource_types: types("resourceTypes"),
+                    context: applies_to
+                        .and_then(|applies_to| applies_to.get("context"))
+                        .map(|context| namespace.attributes(context))
+                        .unwrap_or_default(),
+                    additional_context_attributes: applies_to
+                        .and_then(|applies_to| applies_to.get("context"))
+                        .map(|context| namespace.resolve(context))
+                        .and_then(|context| context.get("additionalAttributes"))
+                        .and_then(Value::as_bool)
+                        .unwrap_or(false),
+                });
+            }
+        }
+        Ok(model)
+    }
+}
+
+/// Document a schema, given in JSON form
+pub fn schema_docs(schema: &str) -> Result<SchemaDocs> {
+    let model = SchemaModel::from_json(schema)?;
+    let hierarchy = Hierarchy {
+        entity_types: model.entity_types.iter()
+            .map(|entity_type| (entity_type.name.clone(), entity_type.member_of_types.clone()))
+            .collect(),
+        actions: model.actions.iter()
+            .map(|action| (action.name.clone(), action.member_of.clone()))
+            .collect(),
+    };
+    Ok(SchemaDocs { model, hierarchy })
+}
+
+struct Namespace<'a> {
+    name: &'a str,
+    common_types: Option<&'a Value>,
+}
+
+impl<'a> Namespace<'a> {
+    fn qualify(&self, name: &str) -> String {
+        if self.name.is_empty() || name.contains("::") {
+            name.to_owned()
+        } else {
+            format!("{}::{}", self.name, name)
+        }
+    }
+
+    /// The attribute rows of a record type, or of a common type defined as one
+    fn attributes(&self, record: &Value) -> Vec<Attribute> {
+        let mut attributes = vec![];
+        self.flatten(&[], self.resolve(record), &mut attributes);
+        attributes
+    }
+
+    fn flatten(&self, prefix: &[String], record: &Value, attributes: &mut Vec<Attribute>) {
+        for (name, attribute) in objects(record.get("attributes")) {
+            let path = [prefix, std::slice::from_ref(name)].concat();
+            let attribute_type = self.resolve(attribute);
+            attributes.push(Attribute {
+                name: path.join("."),
+                path: path.clone(),
+                type_name: self.type_name(attribute_type),
+                // attributes are required unless the schema says otherwise
+                required: attribute.get("required").and_then(Value::as_bool).unwrap_or(true),
+            });
+            if attribute_type.get("type").and_then(Value::as_str) == Some("Record") {
+                self.flatten(&path, attribute_type, attributes);
+            }
+        }
+    }
+
+    /// A type with common type references resolved
+    fn resolve<'v>(&self, mut type_def: &'v Value) -> &'v Value
+        where 'a: 'v {
+        // a common type may refer to another, but never to itself, which Cedar rejects
+        for _ in 0..64 {
+            let Some(name) = type_def.get("type").and_then(Value::as_str) else { break };
+            match self.common_types.and_then(|common_types| common_types.get(name)) {
+                Some(common_type) => type_def = common_type,
+                None => break,
+            }
+        }
+        type_def
+    }
+
+    fn type_name(&self, type_def: &Value) -> String {
+        let type_def = self.resolve(type_def);
+        let name = type_def.get("name").and_then(Value::as_str).unwrap_or_default();
+        match type_def.get("type").and_then(Value::as_str).unwrap_or_default() {
+            "Set" => {
+                let element = type_def.get("element").map(|element| self.type_name(element)).unwrap_or_default();
+                format!("Set<{element}>")
+            }
+            "Entity" => format!("Entity<{}>", self.qualify(name)),
+            "Extension" => format!("Extension<{name}>"),
+            other => other.to_owned(),
+        }
+    }
+}
+
+/// An action's entity uid, e.g. `Action::"view"`
+pub fn action_uid(action_type: &str, id: &str) -> String {
+    format!("{}::{}", action_type, Value::String(id.to_owned()))
+}
+
+fn objects(value: Option<&Value>) -> impl Iterator<Item = (&String, &Value)> {
+    value.and_then(Value::as_object).into_iter().flat_map(Map::iter)
+}
+
+fn strings(value: Option<&Value>) -> impl Iterator<Item = &str> {
+    value.and_then(Value::as_array).into_iter().flatten().filter_map(Value::as_str)
+}
@@ -0,0 +1,680 @@
+//! Translation of schemas between Cedar's schema syntax, the human-readable form of later Cedar
+//! releases, and the JSON form this version of Cedar reads.
+//!
+//! The syntax is Cedar's: `namespace` blocks of `type`, `entity`, and `action` declarations, with
+//! `//` comments.  Both directions keep the order of the declarations, and each result is checked
+//! by Cedar's schema parser, so a translation is only returned when Cedar accepts it.  The syntax
+//! declares an entity type's shape as a record, so a shape defined as a common type is written
+//! as the common type's record.
+//!
+//! A type named in the syntax is resolved as Cedar resolves it: a common type of the namespace,
+//! then an entity type of the namespace, then a primitive or extension type, and otherwise an
+//! entity type of another namespace.
+
+use std::collections::HashSet;
+
+use anyhow::Result;
+use cedar_policy::Schema;
+use serde_json::{json, Map, Value};
+
+use crate::errors::{self, ErrorCode};
+
+/// Translate a schema in Cedar's schema syntax to its JSON form
+pub fn cedar_to_json(text: &str) -> Result<Value> {
+    let (tokens, end) = tokenize(text)?;
+    let namespaces = Parser { tokens, pos: 0, end }.schema()?;
+    let mut schema = Map::new();
+    for namespace in &namespaces {
+        if schema.insert(namespace.name.clone(), namespace.to_json()).is_some() {
+            return Err(errors::coded(ErrorCode::Schema,
+                                     format!("invalid schema: namespace '{}' is declared more than once",
+                                             namespace.name)));
+        }
+    }
+    let schema = Value::Object(schema);
+    Schema::from_json_value(schema.clone())
+        .map_err(|e| errors::coded(ErrorCode::Schema, format!("invalid schema: {e}")))?;
+    Ok(schema)
+}
+
+/// Translate a schema in JSON form to Cedar's schema syntax
+pub fn json_to_cedar(schema: &str) -> Result<String> {
+    let schema: Value = serde_json::from_str(schema)
+        .map_err(|e| errors::coded(ErrorCode::Schema, format!("invalid schema: {e}")))?;
+    Schema::from_json_value(schema.clone())
+        .map_err(|e| errors::coded(ErrorCode::Schema, format!("invalid schema: {e}")))?;
+    let Value::Object(namespaces) = schema else {
+        return Err(errors::coded(ErrorCode::Schema, "invalid schema: expected an object of namespaces"));
+    };
+    let mut out = String::new();
+    for (name, definitions) in &namespaces {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        if name.is_empty() {
+            write_declarations(&mut out, definitions, 0);
+        } else {
+            out.push_str(&format!("namespace {name} {{\n"));
+            write_declarations(&mut out, definitions, 1);
+            out.push_str("}\n");
+        }
+    }
+    Ok(out)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Punct(&'static str),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Ident(ident) => format!("'{ident}'"),
+            Token::Str(s) => quote(s),
+            Token::Punct(punct) => format!("'{punct}'"),
+        }
+    }
+}
+
+/// A token with its line and column, from 1
+struct Spanned {
+    token: Token,
+    line: usize,
+    column: usize,
+}
+
+const PUNCTUATION: &[&str] = &["::", "{", "}", "[", "]", "<", ">", ",", ";", ":", "?", "="];
+
+/// The tokens of the text, and the line and column of its end
+fn tokenize(text: &str) -> Result<(Vec<Spanned>, (usize, usize))> {
+    let chars: Vec<char> = text.chars().collect();
+    let (mut i, mut line, mut column) = (0, 1, 1);
+    let mut tokens = vec![];
+    let advance = |i: &mut usize, line: &mut usize, column: &mut usize, n: usize| {
+        for c in &chars[*i..*i + n] {
+            if *c == '\n' {
+                *line += 1;
+                *column = 1;
+            } else {
+                *column += 1;
+            }
+        }
+        *i += n;
+    };
+    while i < chars.len() {
+        let c = chars[i];
+        let (start_line, start_column) = (line, column);
+        if c.is_whitespace() {
+            advance(&mut i, &mut line, &mut column, 1);
+        } else if c == '/' && chars.get(i + 1) == Some(&'/') {
+            let len = chars[i..].iter().position(|c| *c == '\n').unwrap_or(chars.len() - i);
+            advance(&mut i, &mut line, &mut column, len);
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let len = chars[i..].iter().take_while(|c| c.is_ascii_alphanumeric() || **c == '_').count();
+            let ident: String = chars[i..i + len].iter().collect();
+            advance(&mut i, &mut line, &mut column, len);
+            tokens.push(Spanned { token: Token::Ident(ident), line: start_line, column: start_column });
+        } else if c == '"' {
+            let (s, len) = string_literal(&chars[i..])
+                .ok_or_else(|| syntax_error(start_line, start_column, "an unterminated or invalid string"))?;
+            advance(&mut i, &mut line, &mut column, len);
+            tokens.push(Spanned { token: Token::Str(s), line: start_line, column: start_column });
+        } else if let Some(punct) = PUNCTUATION.iter()
+            .find(|punct| punct.chars().eq(chars[i..].iter().take(punct.len()).copied())) {
+            advance(&mut i, &mut line, &mut column, punct.len());
+            tokens.push(Spanned { token: Token::Punct(punct), line: start_line, column: start_column });
+        } else {
+            return Err(syntax_error(line, column, &format!("an unexpected character '{c}'")));
+        }
+    }
+    Ok((tokens, (line, column)))
+}
+
+/// The value and length of the string literal at the start of `chars`, or None if it's invalid
+fn string_literal(chars: &[char]) -> Option<(String, usize)> {
+    let mut s = String::new();
+    let mut i = 1;
+    loop {
+        match *chars.get(i)? {
+            '"' => return Some((s, i + 1)),
+            '\\' => {
+                i += 1;
+                match *chars.get(i)? {
+                    'n' => s.push('\n'),
+                    'r' => s.push('\r'),
+                    't' => s.push('\t'),
+                    '0' => s.push('\0'),
+                    c @ ('\\' | '"' | '\'') => s.push(c),
+                    'u' if chars.get(i + 1) == Some(&'{') => {
+                        let len = chars[i + 2..].iter().position(|c| *c == '}')?;
+                        let hex: String = chars[i + 2..i + 2 + len].iter().collect();
+                        s.push(char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
+                        i += len + 2;
+                    }
+                    _ => return None,
+                }
+            }
+            c => s.push(c),
+        }
+        i += 1;
+    }
+}
+
+fn syntax_error(line: usize, column: usize, found: &str) -> anyhow::Error {
+    errors::coded(ErrorCode::Schema, format!("invalid schema: syntax error at line {line}, column {column}: {found}"))
+}
+
+/// A type as written, before names are resolved
+#[derive(Clone)]
+enum TypeExpr {
+    Name(String),
+    Set(Box<TypeExpr>),
+    Record(Vec<AttributeDecl>),
+}
+
+#[derive(Clone)]
+struct AttributeDecl {
+    name: String,
+    required: bool,
+    type_expr: TypeExpr,
+}
+
+struct EntityDecl {
+    name: String,
+    member_of_types: Vec<String>,
+    shape: Option<Vec<AttributeDecl>>,
+}
+
+#[derive(Clone)]
+struct AppliesTo {
+    principal_types: Option<Vec<String>>,
+    resource_types: Option<Vec<String>>,
+    context: Option<TypeExpr>,
+}
+
+struct ActionDecl {
+    name: String,
+    /// the parents' ids, with their action types when they're given
+    member_of: Vec<(Option<String>, String)>,
+    applies_to: Option<AppliesTo>,
+}
+
+struct NamespaceDecl {
+    name: String,
+    common_types: Vec<(String, TypeExpr)>,
+    entity_types: Vec<EntityDecl>,
+    actions: Vec<ActionDecl>,
+}
+
+struct Parser {
+    tokens: Vec<Spanned>,
+    pos: usize,
+    /// the line and column of the end of the text
+    end: (usize, usize),
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|spanned| &spanned.token)
+    }
+
+    fn error(&self, expected: &str) -> anyhow::Error {
+        match self.tokens.get(self.pos) {
+            Some(spanned) => syntax_error(spanned.line, spanned.column,
+                                          &format!("expected {expected}, found {}", spanned.token.describe())),
+            None => syntax_error(self.end.0, self.end.1, &format!("expected {expected}, found the end of the schema")),
+        }
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Punct(p)) if *p == punct) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<()> {
+        if self.eat(punct) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("'{punct}'")))
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Ident(ident)) if ident == keyword) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn ident(&mut self) -> Result<String> {
+        match self.peek() {
+            Some(Token::Ident(ident)) => {
+                let ident = ident.clone();
+                self.pos += 1;
+                Ok(ident)
+            }
+            _ => Err(self.error("an identifier")),
+        }
+    }
+
+    /// An identifier or a string
+    fn name(&mut self) -> Result<String> {
+        match self.peek() {
+            Some(Token::Str(s)) => {
+                let s = s.clone();
+                self.pos += 1;
+                Ok(s)
+            }
+            Some(Token::Ident(_)) => self.ident(),
+            _ => Err(self.error("a name")),
+        }
+    }
+
+    fn path(&mut self) -> Result<String> {
+        let mut path = self.ident()?;
+        while matches!(self.tokens.get(self.pos + 1).map(|spanned| &spanned.token), Some(Token::Ident(_)))
+            && self.eat("::") {
+            path = format!("{path}::{}", self.ident()?);
+        }
+        Ok(path)
+    }
+
+    fn schema(&mut self) -> Result<Vec<NamespaceDecl>> {
+        let mut namespaces = vec![];
+        let mut unnamespaced = NamespaceDecl::new(String::new());
+        while self.peek().is_some() {
+            if self.eat_keyword("namespace") {
+                let mut namespace = NamespaceDecl::new(self.path()?);
+                self.expect("{")?;
+                while !self.eat("}") {
+                    self.declaration(&mut namespace)?;
+                }
+                namespaces.push(namespace);
+            } else {
+                self.declaration(&mut unnamespaced)?;
+            }
+        }
+        if !unnamespaced.is_empty() || namespaces.is_empty() {
+            namespaces.insert(0, unnamespaced);
+        }
+        Ok(namespaces)
+    }
+
+    fn declaration(&mut self, namespace: &mut NamespaceDecl) -> Result<()> {
+        if self.eat_keyword("type") {
+            let name = self.ident()?;
+            self.expect("=")?;
+            let type_expr = self.type_expr()?;
+            namespace.common_types.push((name, type_expr));
+        } else if self.eat_keyword("entity") {
+            let mut names = vec![self.ident()?];
+            while self.eat(",") {
+                names.push(self.ident()?);
+            }
+            let member_of_types = if self.eat_keyword("in") { self.types()? } else { vec![] };
+            let has_shape = self.eat("=");
+            let shape = if has_shape || self.peek() == Some(&Token::Punct("{")) { Some(self.record()?) } else { None };
+            for name in names {
+                let shape = shape.clone();
+                namespace.entity_types.push(EntityDecl { name, member_of_types: member_of_types.clone(), shape });
+            }
+        } else if self.eat_keyword("action") {
+            let mut names = vec![self.name()?];
+            while self.eat(",") {
+                names.push(self.name()?);
+            }
+            let member_of = if self.eat_keyword("in") { self.action_refs()? } else { vec![] };
+            let applies_to = if self.eat_keyword("appliesTo") { Some(self.applies_to()?) } else { None };
+            for name in names {
+                let applies_to = applies_to.clone();
+                namespace.actions.push(ActionDecl { name, member_of: member_of.clone(), applies_to });
+            }
+        } else {
+            return Err(self.error("'type', 'entity', or 'action'"));
+        }
+        self.expect(";")
+    }
+
+    /// An entity type, or a bracketed list of them
+    fn types(&mut self) -> Result<Vec<String>> {
+        if !self.eat("[") {
+            return Ok(vec![self.path()?]);
+        }
+        let mut types = vec![];
+        while !self.eat("]") {
+            types.push(self.path()?);
+            if !self.eat(",") {
+                self.expect("]")?;
+                break;
+            }
+        }
+        Ok(types)
+    }
+
+    /// An action, or a bracketed list of them, each a name or a `Type::"id"` uid
+    fn action_refs(&mut self) -> Result<Vec<(Option<String>, String)>> {
+        if !self.eat("[") {
+            return Ok(vec![self.action_ref()?]);
+        }
+        let mut refs = vec![];
+        while !self.eat("]") {
+            refs.push(self.action_ref()?);
+            if !self.eat(",") {
+                self.expect("]")?;
+                break;
+            }
+        }
+        Ok(refs)
+    }
+
+    fn action_ref(&mut self) -> Result<(Option<String>, String)> {
+        if let Some(Token::Str(_)) = self.peek() {
+            return Ok((None, self.name()?));
+        }
+        let mut path = self.ident()?;
+        while self.eat("::") {
+            match self.peek() {
+                Some(Token::Str(_)) => return Ok((Some(path), self.name()?)),
+                _ => path = format!("{path}::{}", self.ident()?),
+            }
+        }
+        Ok((None, path))
+    }
+
+    fn applies_to(&mut self) -> Result<AppliesTo> {
+        let mut applies_to = AppliesTo { principal_types: None, resource_types: None, context: None };
+        self.expect("{")?;
+        while !self.eat("}") {
+            if self.eat_keyword("principal") {
+                self.expect(":")?;
+                applies_to.principal_types = Some(self.types()?);
+            } else if self.eat_keyword("resource") {
+                self.expect(":")?;
+                applies_to.resource_types = Some(self.types()?);
+            } else if self.eat_keyword("context") {
+                self.expect(":")?;
+                applies_to.context = Some(self.type_expr()?);
+            } else {
+                return Err(self.error("'principal', 'resource', or 'context'"));
+            }
+            if !self.eat(",") {
+                self.expect("}")?;
+                break;
+            }
+        }
+        Ok(applies_to)
+    }
+
+    fn record(&mut self) -> Result<Vec<AttributeDecl>> {
+        self.expect("{")?;
+        let mut attributes = vec![];
+        while !self.eat("}") {
+            let name = self.name()?;
+            let required = !self.eat("?");
+            self.expect(":")?;
+            attributes.push(AttributeDecl { name, required, type_expr: self.type_expr()? });
+            if !self.eat(",") {
+                self.expect("}")?;
+                break;
+            }
+        }
+        Ok(attributes)
+    }
+
+    fn type_expr(&mut self) -> Result<TypeExpr> {
+        match self.peek() {
+            Some(Token::Punct("{")) => Ok(TypeExpr::Record(self.record()?)),
+            Some(Token::Ident(ident)) if ident == "Set" => {
+                self.pos += 1;
+                self.expect("<")?;
+                let element = self.type_expr()?;
+                self.expect(">")?;
+                Ok(TypeExpr::Set(Box::new(element)))
+            }
+            Some(Token::Ident(_)) => Ok(TypeExpr::Name(self.path()?)),
+            _ => Err(self.error("a type")),
+        }
+    }
+}
+
+impl NamespaceDecl {
+    fn new(name: String) -> Self {
+        NamespaceDecl { name, common_types: vec![], entity_types: vec![], actions: vec![] }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.common_types.is_empty() && self.entity_types.is_empty() && self.actions.is_empty()
+    }
+
+    fn to_json(&self) -> Value {
+        let names = Names {
+            common_types: self.common_types.iter().map(|(name, _)| name.as_str()).collect(),
+            entity_types: self.entity_types.iter().map(|entity_type| entity_type.name.as_str()).collect(),
+        };
+        let mut definitions = Map::new();
+        if !self.common_types.is_empty() {
+            let common_types = self.common_types.iter()
+                .map(|(name, type_expr)| (name.clone(), names.type_json(type_expr)))
+                .collect();
+            definitions.insert("commonTypes".into(), Value::Object(common_types));
+        }
+        let entity_types = self.entity_types.iter()
+            .map(|entity_type| {
+                let mut json = json!({"memberOfTypes": entity_type.member_of_types});
+                if let Some(shape) = &entity_type.shape {
+                    json["shape"] = names.record_json(shape);
+                }
+                (entity_type.name.clone(), json)
+            })
+            .collect();
+        definitions.insert("entityTypes".into(), Value::Object(entity_types));
+        let actions = self.actions.iter()
+            .map(|action| {
+                let mut json = Map::new();
+                if !action.member_of.is_empty() {
+                    let member_of = action.member_of.iter()
+                        .map(|(action_type, id)| match action_type {
+                            Some(action_type) => json!({"id": id, "type": action_type}),
+                            None => json!({"id": id}),
+                        })
+                        .collect();
+                    json.insert("memberOf".into(), Value::Array(member_of));
+                }
+                if let Some(applies_to) = &action.applies_to {
+                    let mut applies_to_json = Map::new();
+                    if let Some(principal_types) = &applies_to.principal_types {
+                        applies_to_json.insert("principalTypes".into(), json!(principal_types));
+                    }
+                    if let Some(resource_types) = &applies_to.resource_types {
+                        applies_to_json.insert("resourceTypes".into(), json!(resource_types));
+                    }
+                    if let Some(context) = &applies_to.context {
+                        applies_to_json.insert("context".into(), names.type_json(context));
+                    }
+                    json.insert("appliesTo".into(), Value::Object(applies_to_json));
+                }
+                (action.name.clone(), Value::Object(json))
+            })
+            .collect();
+        definitions.insert("actions".into(), Value::Object(actions));
+        Value::Object(definitions)
+    }
+}
+
+/// The names a namespace declares, to resolve the types named in it
+struct Names<'a> {
+    common_types: HashSet<&'a str>,
+    entity_types: HashSet<&'a str>,
+}
+
+impl Names<'_> {
+    fn type_json(&self, type_expr: &TypeExpr) -> Value {
+        match type_expr {
+            TypeExpr::Set(element) => json!({"type": "Set", "element": self.type_json(element)}),
+            TypeExpr::Record(attributes) => self.record_json(attributes),
+            TypeExpr::Name(name) if self.common_types.contains(name.as_str()) => json!({"type": name}),
+            TypeExpr::Name(name) if self.entity_types.contains(name.as_str()) => json!({"type": "Entity", "name": name}),
+            TypeExpr::Name(name) => {
+                match name.strip_prefix("__cedar::").unwrap_or(name) {
+                    "Long" => json!({"type": "Long"}),
+                    "String" => json!({"type": "String"}),
+                    "Bool" | "Boolean" => json!({"type": "Boolean"}),
+                    extension @ ("ipaddr" | "decimal") => json!({"type": "Extension", "name": extension}),
+                    _ => json!({"type": "Entity", "name": name}),
+                }
+            }
+        }
+    }
+
+    fn record_json(&self, attributes: &[AttributeDecl]) -> Value {
+        let attributes: Map<String, Value> = attributes.iter()
+            .map(|attribute| {
+                let mut json = self.type_json(&attribute.type_expr);
+                if !attribute.required {
+                    json["required"] = Value::Bool(false);
+                }
+                (attribute.name.clone(), json)
+            })
+            .collect();
+        json!({"type": "Record", "attributes": attributes})
+    }
+}
+
+fn write_declarations(out: &mut String, definitions: &Value, depth: usize) {
+    let indent = "    ".repeat(depth);
+    for (name, common_type) in objects(definitions.get("commonTypes")) {
+        out.push_str(&format!("{indent}type {name} = {};\n", type_text(common_type, depth)));
+    }
+    for (name, entity_type) in objects(definitions.get("entityTypes")) {
+        out.push_str(&format!("{indent}entity {name}"));
+        let member_of_types = strings(entity_type.get("memberOfTypes"));
+        if !member_of_types.is_empty() {
+            out.push_str(&format!(" in [{}]", member_of_types.join(", ")));
+        }
+        if let Some(shape) = entity_type.get("shape") {
+            out.push_str(&format!(" {}", type_text(resolve(shape, definitions), depth)));
+        }
+        out.push_str(";\n");
+    }
+    for (name, action) in objects(definitions.get("actions")) {
+        out.push_str(&format!("{indent}action {}", quote(name)));
+        let member_of: Vec<String> = action.get("memberOf")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .map(|parent| {
+                let id = quote(parent.get("id").and_then(Value::as_str).unwrap_or_default());
+                match parent.get("type").and_then(Value::as_str) {
+                    Some(action_type) => format!("{action_type}::{id}"),
+                    None => id,
+                }
+            })
+            .collect();
+        if !member_of.is_empty() {
+            out.push_str(&format!(" in [{}]", member_of.join(", ")));
+        }
+        if let Some(applies_to) = action.get("appliesTo") {
+            let inner = "    ".repeat(depth + 1);
+            let mut entries = vec![];
+            for (key, label) in [("principalTypes", "principal"), ("resourceTypes", "resource")] {
+                if let Some(types) = applies_to.get(key) {
+                    entries.push(format!("{inner}{label}: [{}]", strings(Some(types)).join(", ")));
+                }
+            }
+            if let Some(context) = applies_to.get("context") {
+                entries.push(format!("{inner}context: {}", type_text(context, depth + 1)));
+            }
+            if entries.is_empty() {
+                out.push_str(" appliesTo {}");
+            } else {
+                out.push_str(&format!(" appliesTo {{\n{}\n{indent}}}", entries.join(",\n")));
+            }
+        }
+        out.push_str(";\n");
+    }
+}
+
+/// A type in Cedar's schema syntax, with a record's attributes on lines indented past `depth`
+fn type_text(type_def: &Value, depth: usize) -> String {
+    let name = || type_def.get("name").and_then(Value::as_str).unwrap_or_default().to_owned();
+    match type_def.get("type").and_then(Value::as_str).unwrap_or_default() {
+        "Boolean" => "Bool".to_owned(),
+        "Set" => format!("Set<{}>", type_text(type_def.get("element").unwrap_or(&Value::Null), depth)),
+        "Entity" | "Extension" => name(),
+        "Record" => {
+            let indent = "    ".repeat(depth + 1);
+            let mut attributes = vec![];
+            for (name, attribute) in objects(type_def.get("attributes")) {
+                let optional = if attribute.get("required").and_then(Value::as_bool).unwrap_or(true) { "" } else { "?" };
+                attributes.push(format!("{indent}{}{optional}: {}", attribute_name(name), type_text(attribute, depth + 1)));
+            }
+            if attributes.is_empty() {
+                "{}".to_owned()
+            } else {
+                format!("{{\n{}\n{}}}", attributes.join(",\n"), "    ".repeat(depth))
+            }
+        }
+        // Long, String, and common types are written by name
+        other => other.to_owned(),
+    }
+}
+
+/// A type with common type references resolved, by the definitions of its namespace
+fn resolve<'v>(mut type_def: &'v Value, definitions: &'v Value) -> &'v Value {
+    // a common type may refer to another, but never to itself, which Cedar rejects
+    for _ in 0..64 {
+        let Some(name) = type_def.get("type").and_then(Value::as_str) else { break };
+        match definitions.get("commonTypes").and_then(|common_types| common_types.get(name)) {
+            Some(common_type) => type_def = common_type,
+            None => break,
+        }
+    }
+    type_def
+}
+
+/// An attribute's name, quoted unless it's an identifier
+fn attribute_name(name: &str) -> String {
+    let is_ident = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if is_ident {
+        name.to_owned()
+    } else {
+        quote(name)
+    }
+}
+
+/// A string literal, escaped as Cedar escapes them
+fn quote(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            '\0' => quoted.push_str("\\0"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn objects(value: Option<&Value>) -> impl Iterator<Item = (&String, &Value)> {
+    value.and_then(Value::as_object).into_iter().flat_map(Map::iter)
+}
+
+fn strings(value: Option<&Value>) -> Vec<&str> {
+    value.and_then(Value::as_array).into_iter().flatten().filter_map(Value::as_str).collect()
+}
@@ -0,0 +1,134 @@
+//! Files that batch results are written to from Rust, one JSON result per line, so an offline job
+//! deciding millions of requests doesn't build a Python string for each result just to write it.
+//! Only a summary of the results is returned.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::str::FromStr;
+
+use anyhow::{Error, Result};
+use cedar_policy::Decision;
+use flate2::write::GzEncoder;
+use serde::Serialize;
+
+use crate::AuthzResponse;
+
+/// How a result file is compressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    None,
+    Gzip,
+}
+
+impl Compression {
+    /// The compression a file's name implies: gzip for `.gz` files
+    pub fn for_path(path: &str) -> Self {
+        if path.ends_with(".gz") { Compression::Gzip } else { Compression::None }
+    }
+}
+
+impl FromStr for Compression {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "none" => Ok(Compression::None),
+            "gzip" => Ok(Compression::Gzip),
+            _ => Err(Error::msg(format!("invalid output compression '{}'; expected one of: none, gzip", s))),
+        }
+    }
+}
+
+/// What was written to a result file
+#[derive(Debug, Default, Serialize)]
+pub struct BatchSummary {
+    pub requests: usize,
+    pub allowed: usize,
+    pub denied: usize,
+    pub no_decision: usize,
+    /// the decided results with errors in their diagnostics, e.g. from policies that failed to evaluate
+    pub errored: usize,
+    /// the results, decided or not, whose errors are all transient, so resubmitting them may succeed
+    pub retryable: usize,
+    /// the size of the file, after compression
+    pub bytes_written: u64,
+}
+
+enum Output {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+impl Output {
+    fn writer(&mut self) -> &mut dyn Write {
+        match self {
+            Output::Plain(writer) => writer,
+            Output::Gzip(writer) => writer,
+        }
+    }
+}
+
+/// A file of NDJSON results, and a tally of their decisions
+pub struct ResultFile {
+    output: Output,
+    /// the line being written, reused across results
+    line: Vec<u8>,
+    summary: BatchSummary,
+}
+
+impl ResultFile {
+    pub fn create(path: &str, compression: Compression) -> io::Result<Self> {
+        let file = BufWriter::new(File::create(path)?);
+        let output = match compression {
+            Compression::None => Output::Plain(file),
+            Compression::Gzip => Output::Gzip(GzEncoder::new(file, flate2::Compression::default())),
+        };
+        Ok(ResultFile { output, line: Vec::with_capacity(256), summary: BatchSummary::default() })
+    }
+
+    /// Write a decided result, or, when it can't be serialized, `on_error`'s result for the error
+    pub fn write_response(&mut self,
+                          ans: &AuthzResponse,
+                          on_error: impl FnOnce(serde_json::Error) -> String) -> io::Result<()> {
+        self.line.clear();
+        if let Err(e) = serde_json::to_writer(&mut self.line, ans) {
+            return self.write_no_decision(&on_error(e), false);
+        }
+        self.summary.requests += 1;
+        match ans.decision() {
+            Decision::Allow => self.summary.allowed += 1,
+            Decision::Deny => self.summary.denied += 1,
+        }
+        if ans.response.diagnostics().errors().next().is_some() {
+            self.summary.errored += 1;
+            if ans.retryable() {
+                self.summary.retryable += 1;
+            }
+        }
+        self.line.push(b'\n');
+        self.output.writer().write_all(&self.line)
+    }
+
+    /// Write a result that couldn't be decided, which may be retried when `retryable`
+    pub fn write_no_decision(&mut self, result: &str, retryable: bool) -> io::Result<()> {
+        self.summary.requests += 1;
+        self.summary.no_decision += 1;
+        if retryable {
+            self.summary.retryable += 1;
+        }
+        let writer = self.output.writer();
+        writer.write_all(result.as_bytes())?;
+        writer.write_all(b"\n")
+    }
+
+    pub fn finish(self) -> io::Result<BatchSummary> {
+        let mut file = match self.output {
+            Output::Plain(writer) => writer,
+            Output::Gzip(writer) => writer.finish()?,
+        };
+        file.flush()?;
+        let bytes_written = file.get_ref().metadata()?.len();
+        Ok(BatchSummary { bytes_written, ..self.summary })
+    }
+}
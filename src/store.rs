@@ -0,0 +1,270 @@
+//! Entity stores, which hold parsed entities across calls and change an entity at a time, so a
+//! caller with a large entity graph that changes a little between calls doesn't parse all of its
+//! JSON again on every call.  A store is passed to `is_authorized` and its batch variants in place
+//! of the entities' JSON.
+//!
+//! Each entity is parsed once, when it's added, and validated against the store's schema, if it
+//! has one.  Cedar computes the entities' hierarchy when they're gathered into an `Entities`, which
+//! the store does on the first call after a change; calls between changes share it.  Entities are
+//! kept with only their own parents, so removing an entity doesn't leave its ancestors to its
+//! children.
+
+// pyo3 0.19's `#[pymethods]` expansion trips this lint on newer compilers
+#![allow(non_local_definitions)]
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Context as _, Result};
+use cedar_policy::{Entities, Entity, EntityUid, Schema};
+use pyo3::exceptions::PyTypeError;
+use pyo3::prelude::*;
+use pyo3::types::{PyList, PyString};
+use serde_json::Value;
+
+use crate::compiled_schema::SchemaSource;
+use crate::errors::{self, ErrorCode};
+use crate::input::{self, TextInput};
+use crate::{load_actions_from_schema, pyjson, warnings, JsonSource};
+
+/// Distinguishes stores in the hashes of their entities
+static NEXT_STORE_ID: AtomicU64 = AtomicU64::new(0);
+
+struct State {
+    entities: HashMap<EntityUid, Entity>,
+    /// counts the changes to the entities
+    version: u64,
+    /// the entities gathered, with the schema's actions, since the last change, and the warning
+    /// naming their missing parents, if there are any
+    gathered: Option<(Arc<Entities>, Option<String>)>,
+}
+
+pub struct StoredEntities {
+    id: u64,
+    schema: Option<Schema>,
+    schema_hash: Option<u64>,
+    state: Mutex<State>,
+}
+
+impl StoredEntities {
+    /// A hash identifying the store's current entities
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        (self.id, self.state.lock().unwrap().version).hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// The store's entities, along with the actions of `schema` when it isn't the store's own schema,
+    /// whose hash is `schema_hash`, and the warning naming their missing parents
+    pub fn entities(&self, schema: &Option<Schema>, schema_hash: Option<u64>)
+                    -> Result<(Arc<Entities>, Option<String>)> {
+        let (gathered, missing_parents) = {
+            let mut state = self.state.lock().unwrap();
+            match &state.gathered {
+                Some(gathered) => gathered.clone(),
+                None => {
+                    let entities = Entities::from_entities(state.entities.values().cloned())
+                        .context("failed to gather the stored entities")?;
+                    let entities = load_actions_from_schema(entities, &self.schema)?;
+                    let missing_parents = warnings::missing_parents(&entities);
+                    state.gathered = Some((Arc::new(entities), missing_parents));
+                    state.gathered.clone().expect("the entities were just gathered")
+                }
+            }
+        };
+        match schema {
+            Some(_) if schema_hash != self.schema_hash => {
+                Ok((Arc::new(load_actions_from_schema((*gathered).clone(), schema)?), missing_parents))
+            }
+            _ => Ok((gathered, missing_parents)),
+        }
+    }
+
+    /// The store's current entities as Cedar's JSON entities, without the actions of its schema
+    pub fn to_json(&self) -> Result<String> {
+        let entities = Entities::from_entities(self.state.lock().unwrap().entities.values().cloned())
+            .context("failed to gather the stored entities")?;
+        let mut json = vec![];
+        entities.write_to_json(&mut json)
+            .context(errors::context(ErrorCode::Serialization, "failed to serialize the stored entities"))?;
+        Ok(String::from_utf8(json).expect("JSON is UTF-8"))
+    }
+
+    fn parse(&self, entity: Value) -> Result<Entity> {
+        let text = entity.to_string();
+        let excerpt = errors::excerpt(&text);
+        Entities::from_json_value(Value::Array(vec![entity]), self.schema.as_ref())
+            .with_context(|| format!("failed to parse entity from:\n{excerpt}"))?
+            .iter()
+            .next()
+            .cloned()
+            .ok_or_else(|| anyhow!("failed to parse entity from:\n{excerpt}"))
+    }
+
+    fn parse_list(&self, entities: &str) -> Result<Vec<Entity>> {
+        let entities: Vec<Value> = serde_json::from_str(entities)
+            .context(errors::context(ErrorCode::Entities, "failed to parse entities: expected a \
+                                                                     JSON list of entities"))?;
+        entities.into_iter().map(|entity| self.parse(entity)).collect()
+    }
+
+    fn change(&self, change: impl FnOnce(&mut HashMap<EntityUid, Entity>) -> bool) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let changed = change(&mut state.entities);
+        if changed {
+            state.version += 1;
+            state.gathered = None;
+        }
+        changed
+    }
+}
+
+/// Parsed entities that are changed an entity at a time and passed in place of the entities' JSON
+#[pyclass(frozen)]
+pub struct EntityStore {
+    stored: Arc<StoredEntities>,
+}
+
+#[pymethods]
+impl EntityStore {
+    #[new]
+    #[pyo3(signature = (entities = None, schema = None))]
+    fn new(py: Python<'_>, entities: Option<TextInput>, schema: Option<SchemaSource>) -> PyResult<Self> {
+        let schema_hash = schema.as_ref().map(SchemaSource::hash);
+        let schema = schema.map(|schema| schema.parse()).transpose()
+            .map_err(|e| errors::value_error(ErrorCode::Schema, format!("failed to parse the schema: {e:#}")))?;
+        let stored = StoredEntities {
+            id: NEXT_STORE_ID.fetch_add(1, Ordering::Relaxed),
+            schema,
+            schema_hash,
+            state: Mutex::new(State { entities: HashMap::new(), version: 0, gathered: None }),
+        };
+        if let Some(entities) = entities {
+            let entities = py.allow_threads(|| stored.parse_list(&entities.0)).map_err(entities_error)?;
+            add_new(&stored, entities)?;
+        }
+        Ok(EntityStore { stored: Arc::new(stored) })
+    }
+
+    /// Add the entities of a JSON list, none of which may be in the store already
+    fn add_entities(&self, py: Python<'_>, entities: TextInput) -> PyResult<()> {
+        let entities = py.allow_threads(|| self.stored.parse_list(&entities.0)).map_err(entities_error)?;
+        add_new(&self.stored, entities)
+    }
+
+    /// Add an entity, or replace the entity with its uid; returns whether one was replaced
+    fn upsert_entity(&self, entity: &PyAny) -> PyResult<bool> {
+        // an entity's dict is converted here, rather than with json.dumps
+        let entity: Value = match entity.downcast::<PyString>() {
+            Ok(text) => serde_json::from_str(text.to_str()?)
+                .map_err(|e| errors::value_error(ErrorCode::Entities, format!("failed to parse entity: {e}")))?,
+            Err(_) => pyjson::to_json(entity)?,
+        };
+        let entity = self.stored.parse(entity).map_err(entities_error)?;
+        let mut replaced = false;
+        self.stored.change(|entities| {
+            replaced = entities.insert(entity.uid(), entity).is_some();
+            true
+        });
+        Ok(replaced)
+    }
+
+    /// Remove the entity with the uid, e.g. `User::"alice"`; returns whether it was in the store
+    fn remove_entity(&self, uid: &str) -> PyResult<bool> {
+        let uid = EntityUid::from_str(uid).map_err(|e| errors::value_error(ErrorCode::InvalidArgument, format!(
+            "invalid entity uid {uid}: {e}")))?;
+        Ok(self.stored.change(|entities| entities.remove(&uid).is_some()))
+    }
+
+    fn __len__(&self) -> usize {
+        self.stored.state.lock().unwrap().entities.len()
+    }
+
+    fn __contains__(&self, uid: &str) -> bool {
+        EntityUid::from_str(uid).is_ok_and(|uid| self.stored.state.lock().unwrap().entities.contains_key(&uid))
+    }
+}
+
+fn add_new(stored: &StoredEntities, new_entities: Vec<Entity>) -> PyResult<()> {
+    let mut duplicate = None;
+    stored.change(|entities| {
+        let mut uids = std::collections::HashSet::new();
+        duplicate = new_entities.iter()
+            .map(Entity::uid)
+            .find(|uid| entities.contains_key(uid) || !uids.insert(uid.clone()));
+        if duplicate.is_some() || new_entities.is_empty() {
+            return false;
+        }
+        entities.extend(new_entities.into_iter().map(|entity| (entity.uid(), entity)));
+        true
+    });
+    match duplicate {
+        Some(uid) => Err(errors::value_error(ErrorCode::Entities, format!(
+            "entity {uid} is already in the store; upsert_entity replaces an entity"))),
+        None => Ok(()),
+    }
+}
+
+fn entities_error(e: anyhow::Error) -> PyErr {
+    errors::value_error(ErrorCode::Entities, errors::message(&e))
+}
+
+/// The entities of a call: their JSON, or a store's current entities
+pub enum EntitySource {
+    Json(JsonSource),
+    Store(Arc<StoredEntities>),
+}
+
+impl From<JsonSource> for EntitySource {
+    fn from(entities: JsonSource) -> Self {
+        EntitySource::Json(entities)
+    }
+}
+
+impl EntitySource {
+    pub fn content_hash(&self) -> u64 {
+        match self {
+            EntitySource::Json(entities) => entities.content_hash(),
+            EntitySource::Store(stored) => stored.content_hash(),
+        }
+    }
+
+    /// The entities as JSON text, for an engine, which keeps the text of the inputs it loaded; a
+    /// store's are its entities when they're read, so the engine doesn't see later changes to it
+    pub fn into_text(self) -> Result<String> {
+        match self {
+            EntitySource::Json(JsonSource::Text(text)) => Ok(text),
+            EntitySource::Json(JsonSource::Value(value)) => Ok(value.to_string()),
+            EntitySource::Store(stored) => stored.to_json(),
+        }
+    }
+}
+
+impl<'a> FromPyObject<'a> for EntitySource {
+    fn extract(ob: &'a PyAny) -> PyResult<Self> {
+        if let Ok(store) = ob.extract::<PyRef<EntityStore>>() {
+            return Ok(EntitySource::Store(store.stored.clone()));
+        }
+        if ob.downcast::<PyList>().is_ok() {
+            return Ok(EntitySource::Json(JsonSource::Value(pyjson::to_json(ob)?)));
+        }
+        input::read_text(ob)?.map(|entities| EntitySource::Json(JsonSource::Text(entities))).ok_or_else(|| {
+            PyTypeError::new_err(format!("entities must be a str, a list of entities, bytes, an os.PathLike, or an \
+                                          EntityStore, not {}", ob.get_type().name().unwrap_or("unknown")))
+        })
+    }
+}
+
+impl fmt::Display for EntitySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EntitySource::Json(entities) => write!(f, "{}", entities),
+            EntitySource::Store(stored) => write!(f, "<entity store {:016x}>", stored.content_hash()),
+        }
+    }
+}
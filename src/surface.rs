@@ -0,0 +1,152 @@
+//! The authorization surface of a schema and policies: every combination of principal type,
+//! action, and resource type the schema allows, with the policies that could apply to it.
+//!
+//! The analysis is of the policies' scopes only, so a policy listed for a combination may still
+//! not apply to a request because of its conditions; a policy that isn't listed never applies.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::convert::text_to_json;
+use crate::schema::{action_uid, SchemaModel};
+
+/// A principal type, action, and resource type, with the policies whose scopes include them
+#[derive(Debug, Serialize)]
+pub struct Combination {
+    principal_type: String,
+    action: String,
+    resource_type: String,
+    /// The permits that could grant the combination
+    permits: Vec<String>,
+    /// The forbids that could deny the combination
+    forbids: Vec<String>,
+    /// Whether any policy could apply to the combination
+    covered: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuthorizationSurface {
+    combinations: Vec<Combination>,
+    /// The number of combinations no policy could apply to
+    uncovered_count: usize,
+}
+
+/// The ancestors of each node, including itself, of a hierarchy of edges from nodes to parents
+fn ancestors<'a>(parents: &HashMap<&'a str, Vec<&'a str>>) -> HashMap<&'a str, HashSet<&'a str>> {
+    parents.keys()
+        .map(|&node| {
+            let mut seen = HashSet::from([node]);
+            let mut pending = vec![node];
+            while let Some(node) = pending.pop() {
+                for &parent in parents.get(node).into_iter().flatten() {
+                    if seen.insert(parent) {
+                        pending.push(parent);
+                    }
+                }
+            }
+            (node, seen)
+        })
+        .collect()
+}
+
+/// The entity uid of an entity reference in a policy's JSON form, which may be escaped with
+/// `__entity`, as its type and id
+fn entity(reference: &Value) -> Option<(&str, &str)> {
+    let reference = reference.get("__entity").unwrap_or(reference);
+    Some((reference.get("type")?.as_str()?, reference.get("id")?.as_str()?))
+}
+
+/// The analysis of one policy's scope
+struct Scope<'a> {
+    policy: &'a Value,
+}
+
+impl Scope<'_> {
+    /// Whether the principal or resource constraint could include entities of `entity_type`,
+    /// whose ancestor types are `ancestor_types`
+    fn includes_type(&self, var: &str, entity_type: &str, ancestor_types: Option<&HashSet<&str>>) -> bool {
+        let Some(constraint) = self.policy.get(var) else { return true };
+        let referenced_type = constraint.get("entity").and_then(entity).map(|(entity_type, _)| entity_type);
+        match (constraint.get("op").and_then(Value::as_str), referenced_type) {
+            (Some("=="), Some(referenced_type)) => referenced_type == entity_type,
+            (Some("in"), Some(referenced_type)) => {
+                referenced_type == entity_type
+                    || ancestor_types.is_some_and(|ancestors| ancestors.contains(referenced_type))
+            }
+            // `All`, or a template's slot, which may be linked to any entity
+            _ => true,
+        }
+    }
+
+    /// Whether the action constraint includes `action`, whose ancestors are `ancestors`
+    fn includes_action(&self, action: &str, ancestors: Option<&HashSet<&str>>) -> bool {
+        let Some(constraint) = self.policy.get("action") else { return true };
+        let uid = |reference: &Value| entity(reference).map(|(action_type, id)| action_uid(action_type, id));
+        let mut referenced = constraint.get("entity").and_then(uid).into_iter()
+            .chain(constraint.get("entities").and_then(Value::as_array).into_iter().flatten().filter_map(uid));
+        match constraint.get("op").and_then(Value::as_str) {
+            Some("==") => referenced.any(|referenced| referenced == action),
+            Some("in") => referenced.any(|referenced| {
+                referenced == action || ancestors.is_some_and(|ancestors| ancestors.contains(referenced.as_str()))
+            }),
+            _ => true,
+        }
+    }
+}
+
+/// Report the authorization surface of `policies`, in Cedar text, for `schema`, in JSON form.
+/// Actions the schema leaves the principal or resource types of unspecified have no
+/// combinations.
+pub fn authorization_surface(schema: &str, policies: &str) -> Result<AuthorizationSurface> {
+    let model = SchemaModel::from_json(schema)?;
+    let policies = text_to_json(policies)?;
+    let type_parents: HashMap<&str, Vec<&str>> = model.entity_types.iter()
+        .map(|entity_type| {
+            (entity_type.name.as_str(), entity_type.member_of_types.iter().map(String::as_str).collect())
+        })
+        .collect();
+    let type_ancestors = ancestors(&type_parents);
+    let action_parents: HashMap<&str, Vec<&str>> = model.actions.iter()
+        .map(|action| (action.name.as_str(), action.member_of.iter().map(String::as_str).collect()))
+        .collect();
+    let action_ancestors = ancestors(&action_parents);
+
+    let mut combinations = vec![];
+    for action in &model.actions {
+        let (Some(principal_types), Some(resource_types)) = (&action.principal_types, &action.resource_types) else {
+            continue;
+        };
+        for principal_type in principal_types {
+            for resource_type in resource_types {
+                let (mut permits, mut forbids) = (vec![], vec![]);
+                for policy in &policies {
+                    let scope = Scope { policy: &policy.policy };
+                    let applies = scope.includes_action(&action.name, action_ancestors.get(action.name.as_str()))
+                        && scope.includes_type("principal", principal_type,
+                                               type_ancestors.get(principal_type.as_str()))
+                        && scope.includes_type("resource", resource_type, type_ancestors.get(resource_type.as_str()));
+                    if !applies {
+                        continue;
+                    }
+                    match policy.policy.get("effect").and_then(Value::as_str) {
+                        Some("forbid") => forbids.push(policy.id.clone()),
+                        _ => permits.push(policy.id.clone()),
+                    }
+                }
+                combinations.push(Combination {
+                    principal_type: principal_type.clone(),
+                    action: action.name.clone(),
+                    resource_type: resource_type.clone(),
+                    covered: !permits.is_empty() || !forbids.is_empty(),
+                    permits,
+                    forbids,
+                });
+            }
+        }
+    }
+    let uncovered_count = combinations.iter().filter(|combination| !combination.covered).count();
+    Ok(AuthorizationSurface { combinations, uncovered_count })
+}
@@ -0,0 +1,356 @@
+//! Entities loaded from tables, e.g. a CSV export or a Parquet file of a warehouse table, with a
+//! mapping from its columns to each entity's uid, attributes, and parents, and converted to
+//! Cedar's JSON entities here rather than row by row in Python.
+//!
+//! The mapping is a JSON object:
+//!
+//! ```json
+//! {"type": "User", "id": "user_id",
+//!  "attributes": {"department": "dept", "level": "level"},
+//!  "types": {"level": "long"},
+//!  "parents": "groups"}
+//! ```
+//!
+//! Each row's uid is its `type` and the value of its `id` column, or the value of its `uid` column,
+//! e.g. `User::"alice"`.  `attributes` maps each attribute to its column, or is a list of columns
+//! named like their attributes.  A CSV cell is text, so `types` says how to read the text of an
+//! attribute's cells: `string` (the default), `long`, `bool`, `entity` (a uid), or `json`.  The
+//! `parents` column holds uids, separated by `parents_separator`, `;` by default, or a list of
+//! them.  An empty or null cell leaves the attribute out of its entity.
+//!
+//! CSV is read as RFC 4180 describes it: a header row of the column names, then a row per entity,
+//! with fields that hold commas, quotes, or line breaks in double quotes.  Parquet is read with
+//! pyarrow in Python and given here as columns, one list of values per column.
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context as _, Result};
+use cedar_policy::{EntityTypeName, EntityUid};
+use pyo3::prelude::*;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::errors::{self, ErrorCode};
+use crate::input::TextInput;
+use crate::permissive::parse_entity_uid;
+
+/// How the columns of a table map to entities
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TableMapping {
+    /// the type of every row's entity, with `id`
+    #[serde(rename = "type")]
+    entity_type: Option<String>,
+    /// the column of each row's entity id, with `type`
+    id: Option<String>,
+    /// the column of each row's entity uid, instead of `type` and `id`
+    uid: Option<String>,
+    #[serde(default)]
+    attributes: Attributes,
+    /// how the text of each attribute's cells is read
+    #[serde(default)]
+    types: BTreeMap<String, CellType>,
+    /// the column of each row's parents
+    parents: Option<String>,
+    parents_separator: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Attributes {
+    Columns(Vec<String>),
+    ByName(BTreeMap<String, String>),
+}
+
+impl Default for Attributes {
+    fn default() -> Self {
+        Attributes::Columns(vec![])
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum CellType {
+    String,
+    Long,
+    Bool,
+    Entity,
+    Json,
+}
+
+/// Where each row's uid comes from
+enum UidColumns<'a> {
+    TypeAndId(&'a str, &'a str),
+    Uid(&'a str),
+}
+
+impl TableMapping {
+    pub fn from_json(mapping: &str) -> Result<Self> {
+        let mapping: TableMapping = serde_json::from_str(mapping).context("invalid table mapping")?;
+        mapping.uid_columns().context("invalid table mapping")?;
+        for attr in mapping.types.keys() {
+            if !mapping.attribute_columns().any(|(name, _)| name == attr) {
+                return Err(anyhow!("invalid table mapping: types has {attr}, which isn't among the attributes"));
+            }
+        }
+        Ok(mapping)
+    }
+
+    fn uid_columns(&self) -> Result<UidColumns<'_>> {
+        match (&self.entity_type, &self.id, &self.uid) {
+            (Some(entity_type), Some(id), None) => {
+                EntityTypeName::from_str(entity_type).map_err(|_| anyhow!("invalid entity type {entity_type}"))?;
+                Ok(UidColumns::TypeAndId(entity_type, id))
+            }
+            (None, None, Some(uid)) => Ok(UidColumns::Uid(uid)),
+            _ => Err(anyhow!("expected a type and an id column, or a uid column")),
+        }
+    }
+
+    /// Each attribute and its column
+    fn attribute_columns(&self) -> Box<dyn Iterator<Item = (&str, &str)> + '_> {
+        match &self.attributes {
+            Attributes::Columns(columns) => Box::new(columns.iter().map(|column| (column.as_str(), column.as_str()))),
+            Attributes::ByName(by_name) => Box::new(by_name.iter().map(|(attr, column)| (attr.as_str(), column.as_str()))),
+        }
+    }
+
+    /// The columns the mapping reads
+    fn columns(&self) -> Vec<&str> {
+        let mut columns: Vec<&str> = match self.uid_columns() {
+            Ok(UidColumns::TypeAndId(_, id)) => vec![id],
+            Ok(UidColumns::Uid(uid)) => vec![uid],
+            Err(_) => vec![],
+        };
+        columns.extend(self.attribute_columns().map(|(_, column)| column));
+        columns.extend(self.parents.as_deref());
+        columns
+    }
+
+    /// The entity of a row, whose cell in each column `cell` gives
+    fn entity<'a>(&self, cell: impl Fn(&str) -> &'a Value) -> Result<Value> {
+        let uid = match self.uid_columns()? {
+            UidColumns::TypeAndId(entity_type, id_column) => {
+                let id = match cell(id_column) {
+                    Value::String(id) if !id.is_empty() => id.clone(),
+                    Value::Number(id) => id.to_string(),
+                    id => return Err(anyhow!("invalid id {id} in column '{id_column}'")),
+                };
+                json!({"type": entity_type, "id": id})
+            }
+            UidColumns::Uid(uid_column) => uid_json(cell(uid_column))
+                .ok_or_else(|| anyhow!("invalid uid {} in column '{uid_column}'", cell(uid_column)))?,
+        };
+        let mut attrs = Map::new();
+        for (attr, column) in self.attribute_columns() {
+            let value = cell(column);
+            if is_empty(value) {
+                continue;
+            }
+            let value = match (self.types.get(attr), value) {
+                (Some(cell_type), Value::String(text)) => read_cell(*cell_type, text),
+                (Some(CellType::Entity), value) => uid_json(value).map(|uid| json!({"__entity": uid})),
+                (_, value) => Some(value.clone()),
+            }.ok_or_else(|| anyhow!("invalid value {value} in column '{column}' for attribute {attr}"))?;
+            attrs.insert(attr.to_owned(), value);
+        }
+        let parents = match &self.parents {
+            Some(parents_column) => {
+                let separator = self.parents_separator.as_deref().unwrap_or(";");
+                let parents: Vec<&Value> = match cell(parents_column) {
+                    Value::Array(parents) => parents.iter().collect(),
+                    value => vec![value],
+                };
+                let mut uids = vec![];
+                for parent in parents.into_iter().filter(|parent| !is_empty(parent)) {
+                    match parent {
+                        Value::String(text) => {
+                            for uid in text.split(separator).map(str::trim).filter(|uid| !uid.is_empty()) {
+                                uids.push(EntityUid::from_str(uid).ok().map(|uid| uid_of(&uid)).ok_or_else(|| {
+                                    anyhow!("invalid parent {uid} in column '{parents_column}'")
+                                })?);
+                            }
+                        }
+                        parent => uids.push(uid_json(parent).ok_or_else(|| {
+                            anyhow!("invalid parent {parent} in column '{parents_column}'")
+                        })?),
+                    }
+                }
+                uids
+            }
+            None => vec![],
+        };
+        Ok(json!({"uid": uid, "attrs": attrs, "parents": parents}))
+    }
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(text) => text.is_empty(),
+        _ => false,
+    }
+}
+
+/// The value of a cell's text
+fn read_cell(cell_type: CellType, text: &str) -> Option<Value> {
+    match cell_type {
+        CellType::String => Some(json!(text)),
+        CellType::Long => text.trim().parse::<i64>().ok().map(|n| json!(n)),
+        CellType::Bool => match text.trim().to_ascii_lowercase().as_str() {
+            "true" => Some(json!(true)),
+            "false" => Some(json!(false)),
+            _ => None,
+        },
+        CellType::Entity => EntityUid::from_str(text.trim()).ok().map(|uid| json!({"__entity": uid_of(&uid)})),
+        CellType::Json => serde_json::from_str(text).ok(),
+    }
+}
+
+/// A uid, given as its text or its JSON form, in its JSON form
+fn uid_json(value: &Value) -> Option<Value> {
+    let uid = match value {
+        Value::String(text) => EntityUid::from_str(text.trim()).ok()?,
+        value => parse_entity_uid(value)?,
+    };
+    Some(uid_of(&uid))
+}
+
+fn uid_of(uid: &EntityUid) -> Value {
+    json!({"type": uid.type_name().to_string(), "id": uid.id().as_ref()})
+}
+
+/// The entities of a CSV table, in Cedar's JSON form
+pub fn entities_from_csv(csv: &str, mapping: &TableMapping) -> Result<Vec<Value>> {
+    let mut rows = parse_csv(csv)
+        .context(errors::context(ErrorCode::Entities, "failed to parse entities from CSV"))?.into_iter();
+    let header = rows.next().unwrap_or_default();
+    let mut indices = BTreeMap::new();
+    for column in mapping.columns() {
+        let index = header.iter().position(|name| name == column)
+            .ok_or_else(|| errors::coded(ErrorCode::Entities,
+                                         format!("failed to parse entities from CSV: there's no column '{column}'")))?;
+        indices.insert(column, index);
+    }
+    rows.enumerate()
+        .map(|(index, row)| {
+            if row.len() != header.len() {
+                return Err(errors::coded(ErrorCode::Entities,
+                                         format!("failed to parse entities from CSV: row {} has {} fields, but the \
+                                                  header has {}", index + 1, row.len(), header.len())));
+            }
+            let cells: Vec<Value> = row.into_iter().map(Value::String).collect();
+            mapping.entity(|column| &cells[indices[column]])
+                .with_context(|| errors::context(ErrorCode::Entities,
+                                                 format!("failed to parse entities from CSV: row {}", index + 1)))
+        })
+        .collect()
+}
+
+/// The entities of a table given as columns, a JSON object of equally long lists, in Cedar's JSON form
+pub fn entities_from_columns(columns_json: &str, mapping: &TableMapping) -> Result<Vec<Value>> {
+    let columns: BTreeMap<String, Value> = serde_json::from_str(columns_json)
+        .context(errors::context(ErrorCode::Entities, "failed to parse entities from columns"))?;
+    let mut values = BTreeMap::new();
+    let mut num_rows = None;
+    for column in mapping.columns() {
+        let Some(Value::Array(column_values)) = columns.get(column) else {
+            return Err(errors::coded(ErrorCode::Entities,
+                                     format!("failed to parse entities from columns: there's no column '{column}'")));
+        };
+        match num_rows {
+            Some(num_rows) if num_rows != column_values.len() => {
+                return Err(errors::coded(ErrorCode::Entities,
+                                         format!("failed to parse entities from columns: column '{column}' has {} \
+                                                  values, but the other columns have {num_rows}",
+                                                 column_values.len())));
+            }
+            _ => num_rows = Some(column_values.len()),
+        }
+        values.insert(column, column_values);
+    }
+    (0..num_rows.unwrap_or(0))
+        .map(|index| {
+            mapping.entity(|column| &values[column][index])
+                .with_context(|| errors::context(ErrorCode::Entities,
+                                                 format!("failed to parse entities from columns: row {}", index + 1)))
+        })
+        .collect()
+}
+
+/// The rows of a CSV document, each a list of its fields; blank lines are skipped
+fn parse_csv(csv: &str) -> Result<Vec<Vec<String>>> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut in_quotes = false;
+    let mut chars = csv.strip_prefix('\u{feff}').unwrap_or(csv).chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                c => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            ',' => {
+                row.push(std::mem::take(&mut field));
+                quoted = false;
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                if !row.is_empty() || !field.is_empty() || quoted {
+                    row.push(std::mem::take(&mut field));
+                    rows.push(std::mem::take(&mut row));
+                }
+                quoted = false;
+            }
+            '"' if field.is_empty() && !quoted => {
+                quoted = true;
+                in_quotes = true;
+            }
+            '"' => return Err(anyhow!("unexpected quote in an unquoted field of row {}", rows.len())),
+            c if quoted => return Err(anyhow!("unexpected {c:?} after a quoted field of row {}", rows.len())),
+            c => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(anyhow!("unterminated quoted field in row {}", rows.len()));
+    }
+    if !row.is_empty() || !field.is_empty() || quoted {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+fn parse_mapping(mapping: &str) -> PyResult<TableMapping> {
+    TableMapping::from_json(mapping)
+        .map_err(|e| errors::value_error(ErrorCode::InvalidArgument, errors::message(&e)))
+}
+
+fn to_entities_json(entities: Result<Vec<Value>>) -> PyResult<String> {
+    let entities = entities.map_err(|e| errors::value_error(ErrorCode::Entities, errors::message(&e)))?;
+    Ok(Value::Array(entities).to_string())
+}
+
+/// The entities of a CSV table, mapped from its columns by `mapping`, as Cedar's JSON entities
+#[pyfunction]
+pub fn entities_from_csv_table(py: Python<'_>, csv: TextInput, mapping: &str) -> PyResult<String> {
+    let mapping = parse_mapping(mapping)?;
+    to_entities_json(py.allow_threads(|| entities_from_csv(&csv.0, &mapping)))
+}
+
+/// The entities of a table given as columns, mapped from them by `mapping`, as Cedar's JSON entities
+#[pyfunction]
+pub fn entities_from_column_table(py: Python<'_>, columns: &str, mapping: &str) -> PyResult<String> {
+    let mapping = parse_mapping(mapping)?;
+    to_entities_json(py.allow_threads(|| entities_from_columns(columns, &mapping)))
+}
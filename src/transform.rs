@@ -0,0 +1,137 @@
+//! Declarative transformations of authorization responses, applied before they are returned.
+//!
+//! A transform is configured once, e.g. on a `CedarEngine`, as a JSON object:
+//!
+//! ```json
+//! {"enrich": [{"from": "diagnostics.reason", "to": "labels", "map": {"policy0": "admins"}}],
+//!  "add": {"service": "photos"},
+//!  "rename": {"correlation_id": "request_id"},
+//!  "drop": ["metrics", "diagnostics.errors"]}
+//! ```
+//!
+//! Fields are addressed by dot-separated paths into nested objects.  The steps are applied in the
+//! order shown, so enrichments see the fields of the original response, and renames and drops
+//! also apply to the fields that were added.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+/// A transformation applied to each serialized response
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ResponseTransform {
+    /// Fields looked up in static maps and added to the response
+    #[serde(default)]
+    enrich: Vec<Enrichment>,
+    /// Fields added to the top level of the response
+    #[serde(default)]
+    add: Map<String, Value>,
+    /// New names for fields, by path
+    #[serde(default)]
+    rename: BTreeMap<String, String>,
+    /// Paths of fields removed from the response
+    #[serde(default)]
+    drop: Vec<String>,
+}
+
+/// Looks up the string at `from`, or each string of the list at `from`, in `map`, and sets the
+/// field at `to` to the result; values missing from the map become `default`, or are left out
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Enrichment {
+    from: String,
+    to: String,
+    map: Map<String, Value>,
+    #[serde(default)]
+    default: Option<Value>,
+}
+
+impl Enrichment {
+    fn lookup(&self, value: &Value) -> Option<Value> {
+        let lookup_one = |value: &Value| {
+            value.as_str()
+                .and_then(|key| self.map.get(key))
+                .or(self.default.as_ref())
+                .cloned()
+        };
+        match value {
+            Value::Array(values) => Some(Value::Array(values.iter().filter_map(lookup_one).collect())),
+            value => lookup_one(value),
+        }
+    }
+}
+
+impl ResponseTransform {
+    pub fn from_json(spec: &str) -> Result<Self> {
+        serde_json::from_str(spec).context("failed to parse the response transform")
+    }
+
+    /// Transform a serialized response, returning it serialized
+    pub fn apply_to_str(&self, response: &str) -> String {
+        match serde_json::from_str(response) {
+            Ok(mut response) => {
+                self.apply(&mut response);
+                response.to_string()
+            }
+            // responses are serialized by cedarpy, so this is unexpected; return it untransformed
+            Err(_) => response.to_owned(),
+        }
+    }
+
+    pub fn apply(&self, response: &mut Value) {
+        for enrichment in &self.enrich {
+            if let Some(enriched) = get(response, &enrichment.from).and_then(|value| enrichment.lookup(value)) {
+                set(response, &enrichment.to, enriched);
+            }
+        }
+        if let Value::Object(fields) = response {
+            fields.extend(self.add.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+        for (path, new_name) in &self.rename {
+            let (parent, name) = split_path(path);
+            if let Some(Value::Object(fields)) = get_mut(response, parent) {
+                if let Some(value) = fields.remove(name) {
+                    fields.insert(new_name.clone(), value);
+                }
+            }
+        }
+        for path in &self.drop {
+            let (parent, name) = split_path(path);
+            if let Some(Value::Object(fields)) = get_mut(response, parent) {
+                fields.remove(name);
+            }
+        }
+    }
+}
+
+/// Split a path into the path of its parent, which is empty for a top-level field, and its name
+fn split_path(path: &str) -> (&str, &str) {
+    path.rsplit_once('.').unwrap_or(("", path))
+}
+
+fn get<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |value, name| value.get(name))
+}
+
+fn get_mut<'a>(value: &'a mut Value, path: &str) -> Option<&'a mut Value> {
+    if path.is_empty() {
+        return Some(value);
+    }
+    path.split('.').try_fold(value, |value, name| value.get_mut(name))
+}
+
+/// Set the field at `path`, adding any missing objects along it
+fn set(value: &mut Value, path: &str, new_value: Value) {
+    let (parent, name) = split_path(path);
+    let mut target = value;
+    for segment in parent.split('.').filter(|segment| !segment.is_empty()) {
+        let Value::Object(fields) = target else { return };
+        target = fields.entry(segment).or_insert_with(|| Value::Object(Map::new()));
+    }
+    if let Value::Object(fields) = target {
+        fields.insert(name.to_owned(), new_value);
+    }
+}
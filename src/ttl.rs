@@ -0,0 +1,49 @@
+//! Hints of how long a decision may be cached by the code enforcing it, from the policies'
+//! `@cache_ttl("30s")` annotations and an engine's default.
+//!
+//! A decision may be cached for as long as its shortest-lived determining policy allows; a
+//! decision no policy determined, e.g. a default deny, has the engine's default.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use cedar_policy::{PolicyId, PolicySet};
+
+/// Parse a TTL such as `30s`, `5m`, `1h`, or `1d`, in seconds
+pub fn parse_ttl(ttl: &str) -> Result<u64> {
+    let invalid = || anyhow!("invalid cache TTL '{ttl}'; expected a number of seconds, minutes, hours, or days, e.g. '30s', '5m', '1h', '1d'");
+    let split = ttl.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+    let (count, unit) = ttl.split_at(split);
+    let count: u64 = count.parse().map_err(|_| invalid())?;
+    let seconds_per_unit = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    count.checked_mul(seconds_per_unit).ok_or_else(invalid)
+}
+
+/// The TTL, in seconds, of each policy annotated with one
+pub fn policy_ttls(policy_set: &PolicySet) -> Result<HashMap<PolicyId, u64>> {
+    policy_set.policies()
+        .filter_map(|policy| {
+            let ttl = policy.annotation("cache_ttl")?;
+            Some(parse_ttl(ttl).map(|ttl| (policy.id().clone(), ttl))
+                     .map_err(|e| e.context(format!("invalid cache_ttl annotation on policy {}", policy.id()))))
+        })
+        .collect()
+}
+
+/// The TTL of a decision determined by the policies `reason`: the shortest of theirs, where a
+/// policy without one has the `default`
+pub fn decision_ttl<'a>(reason: impl Iterator<Item = &'a PolicyId>,
+                        ttls: &HashMap<PolicyId, u64>,
+                        default: Option<u64>) -> Option<u64> {
+    let mut reason = reason.peekable();
+    if reason.peek().is_none() {
+        return default;
+    }
+    reason.filter_map(|id| ttls.get(id).copied().or(default)).min()
+}
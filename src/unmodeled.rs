@@ -0,0 +1,111 @@
+//! Explicit outcomes for requests whose action no policy references, so that an action added to
+//! the schema before any policy is written for it fails closed with a diagnostic saying why,
+//! rather than with a deny that has no reasons, like any other.
+//!
+//! A policy references an action when its action scope names the action or one of its action
+//! groups, or doesn't constrain the action at all.  Outcomes are configured for specific actions,
+//! or with "*" for every action the policies don't reference.
+
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Error, Result};
+use cedar_policy::{ActionConstraint, Entities, EntityUid, PolicySet};
+
+use crate::errors::{self, ErrorCode};
+
+/// What an engine answers for a request whose action no policy references
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// Deny the request, as Cedar does, with a warning naming the action
+    Deny,
+    /// Leave the request undecided, with an error naming the action
+    NoDecision,
+}
+
+impl FromStr for Outcome {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "deny" => Ok(Outcome::Deny),
+            "no_decision" => Ok(Outcome::NoDecision),
+            _ => Err(anyhow!("invalid unmodeled action outcome '{s}'; expected one of: deny, no_decision")),
+        }
+    }
+}
+
+/// The outcomes configured for unmodeled actions
+#[derive(Debug, Clone)]
+pub struct UnmodeledActions {
+    outcomes: HashMap<EntityUid, Outcome>,
+    /// the outcome for the unmodeled actions without one of their own
+    default: Option<Outcome>,
+}
+
+impl UnmodeledActions {
+    /// Parse outcomes by action uid, e.g. `Action::"export"`, or "*" for every action
+    pub fn parse(outcomes: HashMap<String, String>) -> Result<Self> {
+        let mut unmodeled = UnmodeledActions { outcomes: HashMap::new(), default: None };
+        for (action, outcome) in outcomes {
+            let outcome: Outcome = outcome.parse()
+                .map_err(|e: Error| anyhow!("invalid unmodeled_actions: {e}"))?;
+            if action == "*" {
+                unmodeled.default = Some(outcome);
+                continue;
+            }
+            let uid = EntityUid::from_str(&action)
+                .map_err(|_| anyhow!("invalid unmodeled_actions: '{action}' isn't an action's entity uid, or \"*\""))?;
+            unmodeled.outcomes.insert(uid, outcome);
+        }
+        Ok(unmodeled)
+    }
+
+    /// The outcome for a request for `action`, unless the policies reference it or it has none
+    pub fn outcome(&self, action: &EntityUid, referenced: &ReferencedActions, entities: &Entities) -> Option<Outcome> {
+        let outcome = self.outcomes.get(action).copied().or(self.default)?;
+        (!referenced.references(action, entities)).then_some(outcome)
+    }
+}
+
+/// The warning for a request denied as an unmodeled action
+pub fn warning(action: &str) -> String {
+    format!("no policy references the action {action}, so it's denied as an unmodeled action; \
+             write a policy for it, or add it to an action group a policy names")
+}
+
+/// The error for a request left undecided as an unmodeled action
+pub fn error(action: &str) -> Error {
+    errors::coded(ErrorCode::UnmodeledAction,
+                  format!("no policy references the action {action}, so it's undecided as an unmodeled action; \
+                           write a policy for it, or add it to an action group a policy names"))
+}
+
+/// The actions named by the policies' action scopes
+#[derive(Debug, Default)]
+pub struct ReferencedActions {
+    actions: HashSet<EntityUid>,
+    /// whether a policy's scope is any action, referencing every action
+    any: bool,
+}
+
+impl ReferencedActions {
+    pub fn of(policy_set: &PolicySet) -> Self {
+        let mut referenced = ReferencedActions::default();
+        for policy in policy_set.policies() {
+            match policy.action_constraint() {
+                ActionConstraint::Any => referenced.any = true,
+                ActionConstraint::In(actions) => referenced.actions.extend(actions),
+                ActionConstraint::Eq(action) => { referenced.actions.insert(action); }
+            }
+        }
+        referenced
+    }
+
+    /// Whether a policy references the action, or one of its action groups
+    fn references(&self, action: &EntityUid, entities: &Entities) -> bool {
+        self.any
+            || self.actions.contains(action)
+            || entities.ancestors(action).into_iter().flatten().any(|group| self.actions.contains(group))
+    }
+}
@@ -0,0 +1,161 @@
+//! Updates of an engine's policies, entities, and schema that are staged, validated together,
+//! and then committed at once, so that no request is ever decided by new policies with the old
+//! schema or entities.  An update begun with `begin_update` is given its inputs, validated — the
+//! inputs are loaded as a reload would load them, and the policies are validated against the
+//! schema with Cedar's validator — and committed, which swaps the engine's inputs for the
+//! update's in one step.  An update begins with the engine's current inputs, so the inputs it
+//! doesn't stage are kept, e.g. an update of only the policies keeps validating the entities
+//! against the schema.
+//!
+//! A commit fails, and the engine keeps its inputs, when the update's inputs don't load, its
+//! policies have validation errors, or the engine has changed since the update began, e.g. by a
+//! reload or another update, whose changes the update would otherwise silently undo.
+
+// pyo3 0.19's `#[pymethods]` expansion trips this lint on newer compilers
+#![allow(non_local_definitions)]
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use pyo3::prelude::*;
+
+use crate::compiled_schema::SchemaSource;
+use crate::engine::{CedarEngine, Loaded, Sources};
+use crate::errors::{self, ErrorCode};
+use crate::store::EntitySource;
+use crate::validate::{self, ValidationReport};
+
+/// The inputs of a validated update, loaded and ready to commit
+struct Validated {
+    loaded: Loaded,
+    report: ValidationReport,
+    load_duration: Duration,
+}
+
+/// An update of an engine's inputs, staged by `CedarEngine.begin_update`
+#[pyclass]
+pub struct EngineUpdate {
+    engine: Py<CedarEngine>,
+    /// the engine's version when the update began, which it must still be at to commit
+    version: u64,
+    actor: Option<String>,
+    /// the engine's inputs when the update began, with the staged inputs in their place
+    policies: String,
+    entities: Arc<String>,
+    schema: Option<SchemaSource>,
+    /// set by `validate`, and cleared when an input changes
+    validated: Option<Validated>,
+    committed: bool,
+}
+
+impl EngineUpdate {
+    pub(crate) fn new(engine: Py<CedarEngine>, version: u64, actor: Option<String>, sources: Sources) -> Self {
+        EngineUpdate {
+            engine,
+            version,
+            actor,
+            policies: sources.policies,
+            entities: sources.entities,
+            schema: sources.schema,
+            validated: None,
+            committed: false,
+        }
+    }
+
+    fn check_uncommitted(&self) -> PyResult<()> {
+        if self.committed {
+            return Err(errors::value_error(ErrorCode::InvalidArgument, "the update was committed already"));
+        }
+        Ok(())
+    }
+
+    fn stage(&mut self) -> PyResult<()> {
+        self.check_uncommitted()?;
+        self.validated = None;
+        Ok(())
+    }
+
+    fn load(&mut self, py: Python<'_>, mode: &str) -> PyResult<&Validated> {
+        self.check_uncommitted()?;
+        let mode = validate::parse_mode(mode)
+            .map_err(|e| errors::value_error(ErrorCode::InvalidArgument, e))?;
+        let (policies, entities) = (&self.policies, &self.entities);
+        let engine = self.engine.borrow(py);
+        let engine: &CedarEngine = &engine;
+        let schema = &self.schema;
+        let validated = py.allow_threads(|| -> PyResult<Validated> {
+            let t_load = Instant::now();
+            let allowlist = engine.extension_allowlist();
+            let report = match schema {
+                Some(schema) => validate::validate_policies(policies, schema, mode, allowlist),
+                None => validate::validate_policies_without_schema(policies, allowlist),
+            }
+                .map_err(|e| errors::value_error(ErrorCode::of(&e), errors::message(&e)))?;
+            let loaded = engine.load_inputs(policies, entities.to_string(), schema.clone())?;
+            Ok(Validated { loaded, report, load_duration: t_load.elapsed() })
+        })?;
+        Ok(self.validated.insert(validated))
+    }
+}
+
+#[pymethods]
+impl EngineUpdate {
+    /// Stage the policies to replace the engine's with, given as Cedar text
+    fn set_policies(&mut self, policies: String) -> PyResult<()> {
+        self.stage()?;
+        self.policies = policies;
+        Ok(())
+    }
+
+    /// Stage the entities to replace the engine's with, given as JSON or an entity store's current entities
+    fn set_entities(&mut self, py: Python<'_>, entities: EntitySource) -> PyResult<()> {
+        self.stage()?;
+        let entities = py.allow_threads(|| entities.into_text())
+            .map_err(|e| errors::value_error(ErrorCode::of(&e), errors::message(&e)))?;
+        self.entities = Arc::new(entities);
+        Ok(())
+    }
+
+    /// Stage the schema to replace the engine's with, or none to remove it
+    fn set_schema(&mut self, schema: Option<SchemaSource>) -> PyResult<()> {
+        self.stage()?;
+        self.schema = schema;
+        Ok(())
+    }
+
+    /// Load the staged inputs as a reload would, and validate the policies against the schema in
+    /// `mode`, returning the validation report as a JSON object
+    #[pyo3(signature = (mode = "strict"))]
+    fn validate(&mut self, py: Python<'_>, mode: &str) -> PyResult<String> {
+        let validated = self.load(py, mode)?;
+        Ok(serde_json::to_string(&validated.report).expect("validation reports serialize to JSON"))
+    }
+
+    /// Replace the engine's inputs with the update's, validating them first in strict mode unless
+    /// they've been validated since they were staged, returning the engine's version with the
+    /// update, for `min_version`
+    fn commit(&mut self, py: Python<'_>) -> PyResult<u64> {
+        if self.validated.is_none() {
+            self.load(py, "strict")?;
+        }
+        let Some(validated) = self.validated.take() else {
+            unreachable!("the update was validated");
+        };
+        if !validated.report.is_valid() {
+            let message = format!("failed to commit the update: the policies have validation errors: {}",
+                                  validated.report.error_messages().join("; "));
+            self.validated = Some(validated);
+            return Err(errors::value_error(ErrorCode::Validation, message));
+        }
+        let engine = self.engine.borrow(py);
+        let version = engine.commit_update(py, validated.loaded, self.version, self.actor.clone(),
+                                           validated.load_duration)?;
+        self.committed = true;
+        Ok(version)
+    }
+
+    /// Whether the update has been committed
+    fn committed(&self) -> bool {
+        self.committed
+    }
+}
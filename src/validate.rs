@@ -0,0 +1,155 @@
+//! Validation of policies against a schema with Cedar's validator, reporting every error with
+//! the policy and the span of the policy text it's in, and the warnings of Cedar's checks for
+//! strings and identifiers that could be confused for others.
+
+
+use anyhow::{anyhow, Result};
+use cedar_policy::{ValidationErrorKind, ValidationMode, Validator};
+use cedar_policy_core::parser::parse_policyset;
+use cedar_policy_validator::{confusable_string_checks, ValidationWarningKind};
+use serde::Serialize;
+
+use crate::compiled_schema::SchemaSource;
+use crate::errors::{self, ErrorCode};
+use crate::extension_allowlist::ExtensionAllowlist;
+use crate::partial::{Span, Spans};
+use crate::policy_set;
+
+/// Which of Cedar's validation modes policies are validated in
+pub fn parse_mode(mode: &str) -> Result<ValidationMode> {
+    match mode {
+        "strict" => Ok(ValidationMode::Strict),
+        "permissive" => Ok(ValidationMode::Permissive),
+        _ => Err(anyhow!("invalid validation mode '{}'; expected one of: strict, permissive", mode)),
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Issue {
+    policy_id: String,
+    /// What kind of problem it is, e.g. `unrecognized_entity_type`
+    kind: &'static str,
+    message: String,
+    /// Where in the policies' text the problem is, when Cedar reports it
+    span: Option<Span>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ValidationReport {
+    /// Whether the policies have no errors; warnings don't fail validation
+    valid: bool,
+    errors: Vec<Issue>,
+    warnings: Vec<Issue>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.valid
+    }
+
+    /// The errors' messages, each prefixed with the id of the policy it's in
+    pub fn error_messages(&self) -> Vec<String> {
+        self.errors.iter().map(|issue| format!("{}: {}", issue.policy_id, issue.message)).collect()
+    }
+}
+
+/// Validate the policies against the schema, given in JSON form or compiled, and, with an
+/// allowlist, report their calls of extension functions it doesn't allow as errors
+pub fn validate_policies(policies: &str,
+                         schema: &SchemaSource,
+                         mode: ValidationMode,
+                         allowlist: Option<&ExtensionAllowlist>) -> Result<ValidationReport> {
+    let policy_set = policy_set::parse(policies)?;
+    let schema = schema.parse()?;
+    let spans = Spans::new(policies);
+    let validator = Validator::new(schema);
+    let result = validator.validate(&policy_set, mode);
+    let errors = result.validation_errors()
+        .map(|error| {
+            let location = error.location();
+            Issue {
+                policy_id: location.policy_id().to_string(),
+                kind: error_kind(error.error_kind()),
+                message: error.error_kind().to_string(),
+                span: location.range_start().zip(location.range_end()).map(|(start, end)| spans.span(start..end)),
+            }
+        })
+        .chain(disallowed_calls(policies, &spans, allowlist)?)
+        .collect::<Vec<_>>();
+    let mut report = ValidationReport { valid: errors.is_empty(), errors, warnings: warnings(policies)? };
+    // Cedar reports the issues in no particular order, so they're sorted into the order of the policies
+    report.errors.sort_by_key(Issue::order);
+    report.warnings.sort_by_key(Issue::order);
+    Ok(report)
+}
+
+/// Check policies that have no schema to validate against: they're valid if they parse and call
+/// only the extension functions the allowlist allows, and have the warnings of Cedar's
+/// confusable string checks
+pub fn validate_policies_without_schema(policies: &str,
+                                        allowlist: Option<&ExtensionAllowlist>) -> Result<ValidationReport> {
+    policy_set::parse(policies)?;
+    let errors = disallowed_calls(policies, &Spans::new(policies), allowlist)?;
+    let mut report = ValidationReport { valid: errors.is_empty(), errors, warnings: warnings(policies)? };
+    report.warnings.sort_by_key(Issue::order);
+    Ok(report)
+}
+
+/// The errors for the policies' calls of extension functions the allowlist doesn't allow
+fn disallowed_calls(policies: &str, spans: &Spans, allowlist: Option<&ExtensionAllowlist>) -> Result<Vec<Issue>> {
+    let Some(allowlist) = allowlist else {
+        return Ok(vec![]);
+    };
+    Ok(allowlist.violations(policies)?
+        .into_iter()
+        .map(|violation| Issue {
+            message: violation.message(),
+            kind: "disallowed_extension_function",
+            span: violation.range.clone().map(|range| spans.span(range)),
+            policy_id: violation.policy_id,
+        })
+        .collect())
+}
+
+impl Issue {
+    /// The issue's place in the text: the policies are assigned ids `policy0`, `policy1`, ... in
+    /// the order they appear
+    fn order(&self) -> (usize, usize) {
+        let policy = self.policy_id.strip_prefix("policy").and_then(|i| i.parse().ok()).unwrap_or(usize::MAX);
+        (policy, self.span.as_ref().map_or(0, Span::start))
+    }
+}
+
+fn error_kind(kind: &ValidationErrorKind) -> &'static str {
+    match kind {
+        ValidationErrorKind::UnrecognizedEntityType(_) => "unrecognized_entity_type",
+        ValidationErrorKind::UnrecognizedActionId(_) => "unrecognized_action_id",
+        ValidationErrorKind::InvalidActionApplication(_) => "invalid_action_application",
+        ValidationErrorKind::TypeError(_) => "type_error",
+        ValidationErrorKind::UnspecifiedEntity(_) => "unspecified_entity",
+        _ => "validation_error",
+    }
+}
+
+/// The warnings of Cedar's confusable string checks, which `cedar_policy` runs only on templates,
+/// so they're run on the policies' AST, which has a template for each static policy too
+fn warnings(policies: &str) -> Result<Vec<Issue>> {
+    let ast = parse_policyset(policies)
+        .map_err(|errs| errors::coded(ErrorCode::PolicyParse, format!("policy parse errors:\n{:?}", errs)))?;
+    Ok(confusable_string_checks(ast.all_templates())
+        .map(|warning| {
+            let (policy_id, kind) = warning.to_kind_and_location();
+            Issue { policy_id: policy_id.to_string(), kind: warning_kind(&kind), message: kind.to_string(), span: None }
+        })
+        .collect())
+}
+
+fn warning_kind(kind: &ValidationWarningKind) -> &'static str {
+    match kind {
+        ValidationWarningKind::MixedScriptString(_) => "mixed_script_string",
+        ValidationWarningKind::BidiCharsInString(_) => "bidi_chars_in_string",
+        ValidationWarningKind::BidiCharsInIdentifier(_) => "bidi_chars_in_identifier",
+        ValidationWarningKind::MixedScriptIdentifier(_) => "mixed_script_identifier",
+        ValidationWarningKind::ConfusableIdentifier(_) => "confusable_identifier",
+    }
+}
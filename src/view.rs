@@ -0,0 +1,237 @@
+//! Read-only views of an engine's policies and entities, to hand to plugin and report code that
+//! should see the live policy store but never change it.  A view has no methods that change
+//! anything and no way back to its engine, and it's a snapshot: it keeps the inputs the engine
+//! held when it was made, whatever reloads follow.  The entity attributes named when the view was
+//! made are redacted from every entity it returns, at any depth: a name matches the attributes of
+//! records nested in attributes and sets too.  A name that matches no attribute of the schema or
+//! the entities is rejected, since it would redact nothing.
+//!
+//! Only entities are redacted.  Policies are returned as written, so a secret a policy compares an
+//! attribute with, e.g. `principal.ssn == "123-45-6789"`, is in the view's policy text.
+
+// pyo3 0.19's `#[pymethods]` expansion trips this lint on newer compilers
+#![allow(non_local_definitions)]
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
+
+use anyhow::{Context as _, Error};
+use cedar_policy::{Effect, EntityUid, Policy, PolicyId};
+use pyo3::prelude::*;
+use serde_json::{json, Value};
+
+use crate::engine::Loaded;
+use crate::errors::{self, ErrorCode};
+use crate::hierarchy;
+use crate::permissive::parse_entity_uid;
+
+/// What a redacted attribute's value is replaced with
+pub const REDACTED: &str = "<redacted>";
+
+/// The entities as JSON, with attributes redacted, and the index of each by uid
+struct EntitiesJson {
+    entities: Vec<Value>,
+    by_uid: HashMap<String, usize>,
+    /// the names of the entities' attributes, and of the attributes of records nested in them,
+    /// before redaction
+    attributes: HashSet<String>,
+}
+
+/// A read-only snapshot of an engine's policies and entities
+#[pyclass(frozen)]
+pub struct EngineView {
+    loaded: Arc<Loaded>,
+    /// the entity attributes redacted from every entity
+    redact: HashSet<String>,
+    /// built on first use, since only some plugins read the entities
+    entities_json: OnceLock<Result<EntitiesJson, String>>,
+}
+
+impl EngineView {
+    pub(crate) fn new(loaded: Arc<Loaded>, redact: Vec<String>) -> PyResult<Self> {
+        let view = EngineView { loaded, redact: redact.into_iter().collect(), entities_json: OnceLock::new() };
+        view.check_redact()?;
+        Ok(view)
+    }
+
+    /// Reject redacted names that match no attribute, e.g. a typo or `User.ssn`, which would
+    /// otherwise leave the attribute they were meant to redact in the view.  The entities are only
+    /// serialized to check names the schema doesn't declare.
+    fn check_redact(&self) -> PyResult<()> {
+        let mut declared = HashSet::new();
+        if let Some(schema) = &self.loaded.sources.schema {
+            if let Ok(schema) = serde_json::from_str(schema.text()) {
+                schema_attributes(&schema, &mut declared);
+            }
+        }
+        let mut unknown: Vec<&String> = self.redact.iter().filter(|name| !declared.contains(*name)).collect();
+        if unknown.is_empty() {
+            return Ok(());
+        }
+        let attributes = &self.entities_json()?.attributes;
+        unknown.retain(|name| !attributes.contains(*name));
+        if unknown.is_empty() {
+            return Ok(());
+        }
+        unknown.sort();
+        let unknown: Vec<String> = unknown.into_iter().map(|name| format!("'{name}'")).collect();
+        Err(errors::value_error(ErrorCode::InvalidArgument,
+                                format!("invalid redact: {} matches no attribute of the schema or the entities; \
+                                         name attributes as they're declared, e.g. 'ssn' rather than 'User.ssn'",
+                                        unknown.join(", "))))
+    }
+
+    fn entities_json(&self) -> PyResult<&EntitiesJson> {
+        self.entities_json
+            .get_or_init(|| self.write_entities().map_err(|e| errors::message(&e)))
+            .as_ref()
+            .map_err(|e| errors::value_error(ErrorCode::Serialization, e))
+    }
+
+    fn write_entities(&self) -> Result<EntitiesJson, Error> {
+        let mut buf = vec![];
+        self.loaded.prepared.entities.write_to_json(&mut buf)
+            .context(errors::context(ErrorCode::Serialization, "failed to serialize the entities"))?;
+        let entities = serde_json::from_slice(&buf)
+            .context(errors::context(ErrorCode::Serialization, "failed to serialize the entities"))?;
+        let Value::Array(mut entities) = entities else {
+            return Err(errors::coded(ErrorCode::Serialization,
+                                     "failed to serialize the entities: expected a JSON array"));
+        };
+        let mut by_uid = HashMap::with_capacity(entities.len());
+        let mut attributes = HashSet::new();
+        for (index, entity) in entities.iter_mut().enumerate() {
+            if let Some(attrs) = entity.get_mut("attrs") {
+                record_attributes(attrs, &mut attributes);
+                self.redact(attrs);
+            }
+            if let Some(uid) = entity.get("uid").and_then(parse_entity_uid) {
+                by_uid.insert(uid.to_string(), index);
+            }
+        }
+        Ok(EntitiesJson { entities, by_uid, attributes })
+    }
+
+    /// Replace the values of the redacted attributes of a record, and of the records nested in its
+    /// attributes and sets
+    fn redact(&self, value: &mut Value) {
+        match value {
+            Value::Object(record) => {
+                for (name, value) in record.iter_mut() {
+                    if self.redact.contains(name) {
+                        *value = json!(REDACTED);
+                    } else if !is_escape(name) {
+                        self.redact(value);
+                    }
+                }
+            }
+            Value::Array(values) => values.iter_mut().for_each(|value| self.redact(value)),
+            _ => {}
+        }
+    }
+}
+
+/// Whether a key is one of the escapes of Cedar's entity format, e.g. `__entity`, whose fields
+/// aren't attributes
+fn is_escape(name: &str) -> bool {
+    matches!(name, "__entity" | "__extn" | "__expr")
+}
+
+/// Collect the names of a record's attributes, and of the records nested in them
+fn record_attributes(value: &Value, names: &mut HashSet<String>) {
+    match value {
+        Value::Object(record) => {
+            for (name, value) in record.iter().filter(|(name, _)| !is_escape(name)) {
+                names.insert(name.clone());
+                record_attributes(value, names);
+            }
+        }
+        Value::Array(values) => values.iter().for_each(|value| record_attributes(value, names)),
+        _ => {}
+    }
+}
+
+/// Collect the names of the attributes a schema declares: those of every record type, including
+/// entity shapes, common types, and the records nested in them
+fn schema_attributes(value: &Value, names: &mut HashSet<String>) {
+    match value {
+        Value::Object(object) => {
+            if let Some(Value::Object(attributes)) = object.get("attributes") {
+                names.extend(attributes.keys().cloned());
+            }
+            object.values().for_each(|value| schema_attributes(value, names));
+        }
+        Value::Array(values) => values.iter().for_each(|value| schema_attributes(value, names)),
+        _ => {}
+    }
+}
+
+#[pymethods]
+impl EngineView {
+    /// The policies, sorted by id, as a JSON array of their ids, effects, annotations, template
+    /// ids for template-linked policies, and text.  The text isn't redacted.
+    fn policies(&self) -> String {
+        let mut policies: Vec<&Policy> = self.loaded.prepared.policy_set.policies().collect();
+        policies.sort_by_key(|policy| policy.id().to_string());
+        Value::Array(policies.into_iter().map(policy_json).collect()).to_string()
+    }
+
+    /// The policy with the id, as a JSON object like those of `policies`, or `None` when there's
+    /// no such policy
+    fn policy(&self, policy_id: &str) -> Option<String> {
+        let policy_id = PolicyId::from_str(policy_id).ok()?;
+        self.loaded.prepared.policy_set.policy(&policy_id).map(|policy| policy_json(policy).to_string())
+    }
+
+    /// The entities, including the schema's actions, as a JSON array in Cedar's entity format,
+    /// with the redacted attributes' values replaced
+    fn entities(&self) -> PyResult<String> {
+        Ok(Value::Array(self.entities_json()?.entities.clone()).to_string())
+    }
+
+    /// The entity with the uid, as JSON, or `None` when it isn't in the entities
+    fn entity(&self, uid: &str) -> PyResult<Option<String>> {
+        let uid = parse_uid(uid)?;
+        let entities_json = self.entities_json()?;
+        Ok(entities_json.by_uid.get(&uid.to_string()).map(|index| entities_json.entities[*index].to_string()))
+    }
+
+    /// Whether the entity `uid` is `ancestor_uid` or one of its descendants, as for `is_in`
+    fn is_in(&self, uid: &str, ancestor_uid: &str) -> PyResult<bool> {
+        Ok(hierarchy::is_in(&self.loaded.prepared.entities, &parse_uid(uid)?, &parse_uid(ancestor_uid)?))
+    }
+
+    /// The number of policies and entities, the fingerprints of the inputs, and the redacted
+    /// attributes, as a JSON object
+    fn summary(&self) -> String {
+        let mut redacted: Vec<&String> = self.redact.iter().collect();
+        redacted.sort();
+        json!({
+            "num_policies": self.loaded.prepared.policy_set.policies().count(),
+            "num_entities": self.loaded.prepared.entities.iter().count(),
+            "fingerprints": self.loaded.fingerprints(),
+            "redacted_attributes": redacted,
+        }).to_string()
+    }
+}
+
+fn policy_json(policy: &Policy) -> Value {
+    let annotations: BTreeMap<&str, &str> = policy.annotations().collect();
+    json!({
+        "policy_id": policy.id().to_string(),
+        "effect": match policy.effect() {
+            Effect::Permit => "permit",
+            Effect::Forbid => "forbid",
+        },
+        "annotations": annotations,
+        "template_id": policy.template_id().map(ToString::to_string),
+        "text": policy.to_string(),
+    })
+}
+
+fn parse_uid(uid: &str) -> PyResult<EntityUid> {
+    EntityUid::from_str(uid).map_err(|e| {
+        errors::value_error(ErrorCode::InvalidArgument, format!("failed to parse {uid} as entity Uid: {e}"))
+    })
+}
@@ -0,0 +1,78 @@
+//! Warnings for conditions that don't change a decision but that an operator should know about:
+//! a request to validate without a schema, stale entities, entities that are referred to but
+//! missing, and deprecated syntax.  Warnings are reported in each response, apart from its errors
+//! and diagnostics, so they never turn a decision into an error.
+
+use std::time::Duration;
+
+use cedar_policy::{Entities, EntityUid};
+use serde_json::Value;
+
+use crate::JsonSource;
+
+/// How many missing parents a warning names
+const MAX_NAMED_PARENTS: usize = 5;
+
+/// A warning for a request to be validated, with `validate_request`, without a schema to do it
+pub fn schema_absent() -> String {
+    String::from("no schema was provided: the request was not validated")
+}
+
+/// A warning naming the parents of entities that aren't themselves in the entities, which Cedar
+/// accepts and treats as entities without attributes or parents
+pub fn missing_parents(entities: &Entities) -> Option<String> {
+    let mut missing: Vec<&EntityUid> = entities.iter()
+        .filter_map(|entity| entities.ancestors(&entity.uid()))
+        .flatten()
+        .filter(|ancestor| entities.get(ancestor).is_none())
+        .collect();
+    if missing.is_empty() {
+        return None;
+    }
+    missing.sort_by_key(|uid| uid.to_string());
+    missing.dedup();
+    let named: Vec<String> = missing.iter().take(MAX_NAMED_PARENTS).map(ToString::to_string).collect();
+    let more = match missing.len().saturating_sub(MAX_NAMED_PARENTS) {
+        0 => String::new(),
+        more => format!(" and {more} more"),
+    };
+    Some(format!("missing entities ignored: parents not in the entities: {}{more}", named.join(", ")))
+}
+
+/// A warning when the entities use the deprecated `__expr` escape
+pub fn deprecated_entities_syntax(entities: &JsonSource) -> Option<String> {
+    let deprecated = match entities {
+        JsonSource::Text(text) => text_uses_expr_escape(text),
+        JsonSource::Value(value) => uses_expr_escape(value),
+    };
+    deprecated.then(|| deprecated_expr_escape("entities"))
+}
+
+/// A warning when a request's context uses the deprecated `__expr` escape
+pub fn deprecated_context_syntax(context_json: &str) -> Option<String> {
+    text_uses_expr_escape(context_json).then(|| deprecated_expr_escape("request's context"))
+}
+
+fn deprecated_expr_escape(what: &str) -> String {
+    format!("deprecated syntax in the {what}: the __expr escape is deprecated; \
+             use __entity for entity references and __extn for extension values")
+}
+
+/// Whether JSON text has an `__expr` key, which is quoted just so wherever it is
+fn text_uses_expr_escape(text: &str) -> bool {
+    text.contains("\"__expr\"")
+}
+
+fn uses_expr_escape(value: &Value) -> bool {
+    match value {
+        Value::Object(object) => object.contains_key("__expr") || object.values().any(uses_expr_escape),
+        Value::Array(values) => values.iter().any(uses_expr_escape),
+        _ => false,
+    }
+}
+
+/// A warning when entities loaded `age` ago are older than `max_age`
+pub fn stale_entities(age: Duration, max_age: Duration) -> Option<String> {
+    (age > max_age).then(|| format!("entities are stale: they were loaded {}s ago, more than the {}s allowed",
+                                    age.as_secs(), max_age.as_secs_f64()))
+}
@@ -0,0 +1,54 @@
+import unittest
+
+from cedarpy import CedarEngine, is_authorized, is_authorized_batch
+
+
+class IncludeAttributesTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.policies = 'permit(principal, action, resource) when { principal.department == "eng" };'
+        self.entities = [
+            {"uid": {"type": "User", "id": "alice"}, "attrs": {"department": "eng", "level": 7}, "parents": []},
+            {"uid": {"type": "User", "id": "bob"}, "attrs": {}, "parents": []},
+            {"uid": {"type": "Doc", "id": "plan"},
+             "attrs": {"owner": {"__entity": {"type": "User", "id": "bob"}}, "tags": ["q3"]}, "parents": []},
+        ]
+        self.request = {"principal": 'User::"alice"', "action": 'Action::"view"', "resource": 'Doc::"plan"',
+                        "context": {}}
+
+    def test_selected_attributes_are_copied_into_the_result(self):
+        authz_result = is_authorized(self.request, self.policies, self.entities,
+                                     include_attributes={"principal": ["department"], "resource": ["owner", "tags"]})
+
+        self.assertEqual({"principal": {"department": "eng"},
+                          "resource": {"owner": {"__entity": {"type": "User", "id": "bob"}}, "tags": ["q3"]}},
+                         authz_result.attributes)
+        self.assertEqual(authz_result.attributes, authz_result.to_dict()["attributes"])
+
+    def test_missing_entities_and_attributes_are_left_out(self):
+        requests = [self.request, dict(self.request, principal='User::"stacey"')]
+        authz_results = is_authorized_batch(requests, self.policies, self.entities, include_diagnostics=False,
+                                            include_attributes={"principal": ["department", "office"],
+                                                                "action": ["label"]})
+
+        # attributes are reported without diagnostics
+        self.assertEqual({"principal": {"department": "eng"}}, authz_results[0].attributes)
+        self.assertEqual({}, authz_results[1].attributes)
+        self.assertNotIn("attributes", is_authorized(self.request, self.policies, self.entities).to_dict())
+
+    def test_an_engine_copies_attributes_from_its_entities(self):
+        engine = CedarEngine(self.policies, self.entities)
+        projection = {"principal": ("level",)}
+
+        self.assertEqual({"principal": {"level": 7}},
+                         engine.is_authorized(self.request, include_attributes=projection).attributes)
+        engine.reload(self.policies, [dict(self.entities[0], attrs={"level": 8})] + self.entities[1:])
+        self.assertEqual([{"principal": {"level": 8}}],
+                         [r.attributes for r in engine.is_authorized_batch([self.request],
+                                                                             include_attributes=projection)])
+
+    def test_an_invalid_projection_is_an_error(self):
+        with self.assertRaises(ValueError) as cm:
+            is_authorized(self.request, self.policies, self.entities, include_attributes={"owner": ["name"]})
+        self.assertEqual("invalid_argument", cm.exception.code)
+        self.assertIn("invalid include_attributes", str(cm.exception))
@@ -5,7 +5,9 @@ import unittest
 from datetime import timedelta
 from typing import List, Union
 
-from cedarpy import is_authorized, AuthzResult, Decision, is_authorized_batch
+from cedarpy import is_authorized, AuthzResult, Decision, is_authorized_batch, is_authorized_batch_decisions, \
+    is_authorized_batch_document, is_authorized_expanded, is_authorized_many_actions, is_authorized_many_resources, \
+    permission_matrix, profile_batch, is_in
 
 from unit import load_file_as_str, utc_now
 
@@ -230,6 +232,8 @@ class AuthorizeTestCase(unittest.TestCase):
                 'load_entities_duration_micros',
                 'build_request_duration_micros',
                 'authz_duration_micros',
+                'convert_input_duration_micros',
+                'serialize_output_duration_micros',
             ]:
                 self.assertIn(metric_name, metrics)
                 if 'duration' in metric_name:
@@ -435,8 +439,18 @@ class AuthorizeTestCase(unittest.TestCase):
 
         authz_result: AuthzResult = is_authorized(request, policies, entities, schema=schema)
         self.assertEqual(Decision.NoDecision, authz_result.decision)
-        self.assertEqual(["failed to parse schema from request"],
+        self.assertEqual(['invalid request: While parsing Context, expected the record to have an attribute "photo", '
+                          "but it didn't"],
                          authz_result.diagnostics.errors)
+        self.assertEqual(["request_error"], authz_result.diagnostics.error_codes)
+
+    def test_invalid_uids_are_reported_with_their_cause(self):
+        [authz_result] = is_authorized_many_resources('User::"alice"', 'Action::"view"', ["bad"],
+                                                      'permit(principal, action, resource);', [])
+        self.assertEqual(Decision.NoDecision, authz_result.decision)
+        self.assertEqual(["request_error"], authz_result.diagnostics.error_codes)
+        self.assertRegex(authz_result.diagnostics.errors[0],
+                         r"^invalid request: failed to parse resource bad as entity Uid:\s+Unrecognized EOF")
     def test_is_authorized_with_policies_that_errors(self):
         policies = "this is not a real policy"
         entities = load_file_as_str("resources/sandbox_b/entities.json")
@@ -456,6 +470,64 @@ class AuthorizeTestCase(unittest.TestCase):
         self.assertEqual(1, len(authz_result.diagnostics.errors))
         self.assertIn('policy parse errors:\nUnrecognized token', authz_result.diagnostics.errors[0])
 
+    def test_policy_evaluation_errors_may_be_propagated(self):
+        policies = """
+            permit(principal, action == Action::"view", resource);
+            forbid(principal, action == Action::"view", resource) when { context.no_such_attribute };
+        """
+        request = dict(self.request_bob_view_own_photo, context={}, correlation_id="req-1")
+
+        # by default, as in Cedar, the forbid that failed to evaluate is skipped
+        authz_result: AuthzResult = is_authorized(request, policies, self.entities)
+        self.assertEqual(Decision.Allow, authz_result.decision)
+        self.assertEqual(1, len(authz_result.diagnostics.errors))
+
+        authz_result = is_authorized(request, policies, self.entities, policy_errors="propagate")
+        self.assertEqual(Decision.NoDecision, authz_result.decision)
+        self.assertEqual(1, len(authz_result.diagnostics.errors))
+        self.assertTrue(authz_result.diagnostics.errors[0].startswith("policy evaluation error: "))
+        self.assertEqual([], authz_result.diagnostics.reasons)
+
+        authz_result = is_authorized(dict(request, action='Action::"edit"'), policies, self.entities,
+                                     policy_errors="propagate")
+        self.assertEqual(Decision.Deny, authz_result.decision)
+
+        with self.assertRaises(ValueError):
+            is_authorized(request, policies, self.entities, policy_errors="sometimes")
+
+    def test_error_messages_are_bounded_and_coded(self):
+        entities = self.entities + [{"uid": {"__expr": 'User::"x"'}, "attrs": {"padding": "x" * 1_000_000},
+                                     "parents": [{"__expr": "not a uid"}]}]
+        authz_result: AuthzResult = is_authorized(self.request_bob_view_own_photo, self.policies["bob"], entities)
+        self.assertEqual(Decision.NoDecision, authz_result.decision)
+        self.assertEqual(["entities_error"], authz_result.diagnostics.error_codes)
+
+        authz_result = is_authorized(self.request_bob_view_own_photo, "this is not a real policy", self.entities)
+        self.assertEqual(["policy_parse_error"], authz_result.diagnostics.error_codes)
+
+        policies = 'permit(principal, action, resource) when { context.no_such_attribute };'
+        authz_result = is_authorized(dict(self.request_bob_view_own_photo, context={}), policies, self.entities)
+        self.assertEqual(Decision.Deny, authz_result.decision)
+        self.assertEqual(["policy_evaluation_error"], authz_result.diagnostics.error_codes)
+
+        with self.assertRaises(ValueError) as raised:
+            is_authorized(self.request_bob_view_own_photo, self.policies["bob"], self.entities,
+                          entity_validation="sometimes")
+        self.assertEqual("invalid_argument", raised.exception.code)
+
+        with self.assertRaises(ValueError) as raised:
+            is_in(self.entities, "x" * 1_000_000, 'User::"bob"')
+        self.assertEqual("invalid_argument", raised.exception.code)
+        message = str(raised.exception)
+        self.assertLessEqual(len(message.encode()), 4096)
+        self.assertTrue(message.endswith("more bytes)"))
+
+        # the causes Cedar's errors already quote aren't repeated
+        authz_result = is_authorized(self.request_bob_view_own_photo, self.policies["bob"], "{")
+        self.assertEqual(["entities_error"], authz_result.diagnostics.error_codes)
+        [message] = authz_result.diagnostics.errors
+        self.assertEqual(1, message.count("expected a sequence at line 1 column 0"), message)
+
     def test_authorized_batch_perf(self):
         policies = self.policies["alice"]
         entities = load_file_as_str("resources/sandbox_b/entities.json")
@@ -513,3 +585,344 @@ class AuthorizeTestCase(unittest.TestCase):
             self.assert_authz_responses_equal(expect_authz_result, actual_authz_result,
                                               ignore_metric_values=True)
 
+
+    def test_diagnostics_and_metrics_may_be_omitted(self):
+        from cedarpy import _internal
+        request = {
+            "principal": "User::\"bob\"",
+            "action": "Action::\"view\"",
+            "resource": "Photo::\"1234-abcd\"",
+        }
+        entities = json.dumps(self.entities)
+
+        for include_diagnostics, include_metrics in [(True, True), (True, False), (False, True), (False, False)]:
+            authz_resp = _internal.is_authorized(request, self.policies["bob"], entities, None, False,
+                                                 include_diagnostics, include_metrics).to_dict()
+            self.assertEqual("Allow", authz_resp["decision"])
+            self.assertEqual(include_diagnostics, "diagnostics" in authz_resp)
+            self.assertEqual(include_metrics, "metrics" in authz_resp)
+
+        # the AuthzResult wrapper still provides empty diagnostics and metrics when they're omitted
+        actual_authz_result: AuthzResult = is_authorized(request, self.policies["bob"], self.entities,
+                                                         include_diagnostics=False,
+                                                         include_metrics=False)
+        self.assertEqual(Decision.Allow, actual_authz_result.decision)
+        self.assertEqual([], actual_authz_result.diagnostics.reasons)
+        self.assertEqual({}, actual_authz_result.metrics)
+
+    def test_results_are_returned_as_authz_responses(self):
+        from cedarpy import _internal
+        request = dict(self.request_bob_view_own_photo, correlation_id="abc-123")
+        response = _internal.is_authorized(request, self.policies["bob"], self.entities)
+        self.assertIsInstance(response, _internal.AuthzResponse)
+        self.assertEqual("Allow", response.decision)
+        self.assertEqual("abc-123", response.correlation_id)
+        self.assertEqual(["policy0"], response.diagnostics.reason)
+        self.assertEqual([], response.diagnostics.errors)
+        self.assertIn("authz_duration_micros", response.metrics)
+        self.assertFalse(response.retryable)
+
+        # read as the dict the JSON of a batch's result makes
+        [batch_result] = is_authorized_batch([request], self.policies["bob"], self.entities)
+        expected = batch_result.to_dict()
+        actual = response.to_dict()
+        self.assertEqual(expected.keys(), actual.keys())
+        self.assertEqual(expected["diagnostics"], actual["diagnostics"])
+        self.assertEqual("Allow", response["decision"])
+        self.assertEqual("missing", response.get("denial_class", "missing"))
+        self.assertNotIn("denial_class", response)
+        with self.assertRaises(KeyError):
+            response["denial_class"]
+
+        # a result that couldn't be decided has the errors, and the AuthzResult wrapper reads the same fields
+        response = _internal.is_authorized(request, "this is not a real policy", self.entities)
+        self.assertEqual("NoDecision", response.decision)
+        self.assertEqual(["policy_parse_error"], response.diagnostics.error_codes)
+        result = is_authorized(request, "this is not a real policy", self.entities)
+        self.assertEqual(Decision.NoDecision, result.decision)
+        self.assertEqual(response.diagnostics.errors, result.diagnostics.errors)
+        self.assertEqual(response.to_dict(), result.to_dict())
+
+    def test_diagnostics_may_be_omitted_from_error_results(self):
+        results: List[AuthzResult] = is_authorized_batch([self.request_bob_view_own_photo],
+                                                         "this is not a real policy",
+                                                         self.entities,
+                                                         include_diagnostics=False)
+        self.assertEqual(Decision.NoDecision, results[0].decision)
+        self.assertEqual([], results[0].diagnostics.errors)
+
+    def test_authorized_batch_delivers_results_to_on_result(self):
+        requests = [self.request_bob_view_own_photo] * 20
+        requests.append(dict(self.request_bob_view_own_photo, context='{"not json'))
+        expect_authz_results: List[AuthzResult] = is_authorized_batch(requests, self.policies["bob"], self.entities)
+
+        delivered: List[AuthzResult] = []
+        self.assertIsNone(is_authorized_batch(requests, self.policies["bob"], self.entities,
+                                              on_result=delivered.append))
+        self.assertEqual(len(requests), len(delivered))
+        for request, expect_authz_result, actual_authz_result in zip(requests, expect_authz_results, delivered):
+            self.assert_authz_responses_equal(expect_authz_result, actual_authz_result,
+                                              ignore_metric_values=True,
+                                              msg=f"request: {request}")
+        self.assertIn("parse_policies_duration_micros", delivered[0].metrics)
+        self.assertEqual(Decision.NoDecision, delivered[-1].decision)
+
+        delivered = []
+        is_authorized_batch(requests, "this is not a real policy", self.entities, on_result=delivered.append)
+        self.assertEqual([Decision.NoDecision] * len(requests), [result.decision for result in delivered])
+
+    def test_authorized_batch_on_several_workers_keeps_request_order(self):
+        # enough requests for several chunks per worker
+        requests = [dict(self.make_request(), correlation_id=str(i)) for i in range(3000)]
+        expect_authz_results: List[AuthzResult] = is_authorized_batch(requests, self.policies["bob"], self.entities)
+
+        actual_authz_results = is_authorized_batch(requests, self.policies["bob"], self.entities, max_workers=4)
+        delivered: List[AuthzResult] = []
+        is_authorized_batch(requests, self.policies["bob"], self.entities, on_result=delivered.append, max_workers=3)
+
+        for authz_results in [actual_authz_results, delivered]:
+            self.assertEqual(len(requests), len(authz_results))
+            for request, expect_authz_result, actual_authz_result in zip(requests, expect_authz_results,
+                                                                         authz_results):
+                self.assertEqual(request["correlation_id"], actual_authz_result.correlation_id)
+                self.assert_authz_responses_equal(expect_authz_result, actual_authz_result,
+                                                  ignore_metric_values=True,
+                                                  msg=f"request: {request}")
+
+        with self.assertRaisesRegex(ValueError, "max_workers must be at least 1"):
+            is_authorized_batch(requests, self.policies["bob"], self.entities, max_workers=0)
+
+    def test_exception_from_on_result_stops_the_batch(self):
+        delivered: List[AuthzResult] = []
+
+        def on_result(authz_result: AuthzResult):
+            delivered.append(authz_result)
+            if len(delivered) == 2:
+                raise RuntimeError("downstream is unavailable")
+
+        with self.assertRaisesRegex(RuntimeError, "downstream is unavailable"):
+            is_authorized_batch([self.request_bob_view_own_photo] * 5, self.policies["bob"], self.entities,
+                                on_result=on_result)
+        self.assertEqual(2, len(delivered))
+
+    def test_profile_batch_reports_stages(self):
+        requests = [self.request_bob_view_own_photo] * 20
+        requests.append(dict(self.request_bob_view_own_photo, context='{"not json'))
+
+        profile = profile_batch(requests, self.policies["bob"], self.entities)
+        self.assertEqual(len(requests), profile["num_requests"])
+        self.assertEqual(1, profile["num_errored"])
+        self.assertEqual(len(self.entities), profile["num_entities"])
+        self.assertEqual({"convert_input_micros", "parse_policies_micros", "parse_schema_micros",
+                          "load_entities_micros", "build_request_micros", "authz_micros", "serialize_output_micros"},
+                         set(profile["stages"]))
+        self.assertLessEqual(sum(profile["stages"].values()), profile["total_micros"])
+        for stage in ["build_request_micros", "authz_micros"]:
+            distribution = profile["per_request"][stage]
+            self.assertLessEqual(distribution["min"], distribution["p50"])
+            self.assertLessEqual(distribution["p50"], distribution["p99"])
+            self.assertLessEqual(distribution["p99"], distribution["max"])
+        self.assertIsNone(profile["allocations"])
+
+        with self.assertRaises(ValueError):
+            profile_batch(requests, "this is not a real policy", self.entities)
+
+    def test_authorized_batch_decisions_match_batch_results(self):
+        requests = [self.make_request() for _ in range(0, 50)]
+        # include a request that fails to evaluate
+        requests.append({
+            "principal": 'User::"bob"',
+            "action": 'Action::"view"',
+            "resource": 'Photo::"1234-abcd"',
+            "context": '{"not json'
+        })
+
+        expect_authz_results: List[AuthzResult] = is_authorized_batch(requests, self.policies["bob"], self.entities)
+        batch_decisions = is_authorized_batch_decisions(requests, self.policies["bob"], self.entities)
+
+        self.assertEqual(len(requests), len(batch_decisions))
+        self.assertEqual((len(requests) + 7) // 8, len(batch_decisions.bitset))
+        for i, expect_authz_result in enumerate(expect_authz_results):
+            self.assertEqual(expect_authz_result.allowed, batch_decisions[i], msg=f"request {i}: {requests[i]}")
+            self.assertEqual(bool(expect_authz_result.diagnostics.errors),
+                             i in batch_decisions.errored,
+                             msg=f"request {i}: {requests[i]}")
+        self.assertIn(len(requests) - 1, batch_decisions.errored)
+
+    def test_authorized_batch_decisions_with_policies_that_error(self):
+        requests = [self.request_bob_view_own_photo] * 3
+        batch_decisions = is_authorized_batch_decisions(requests, "this is not a real policy", self.entities)
+        self.assertEqual([0, 1, 2], batch_decisions.errored)
+        self.assertEqual(b'\x00', batch_decisions.bitset)
+        self.assertFalse(any(batch_decisions[i] for i in range(0, len(batch_decisions))))
+
+    def test_authorized_batch_document_matches_batch_results(self):
+        requests = [self.make_request() for _ in range(0, 30)]
+        expect_authz_results: List[AuthzResult] = is_authorized_batch(requests, self.policies["bob"], self.entities)
+
+        document = json.dumps({
+            "requests": requests,
+            "policies": self.policies["bob"],
+            "entities": self.entities,
+        })
+        for doc in [document, document.encode("utf-8")]:
+            actual_authz_results = is_authorized_batch_document(doc)
+            self.assertEqual(len(expect_authz_results), len(actual_authz_results))
+            for request, expect_authz_result, actual_authz_result in zip(requests,
+                                                                         expect_authz_results,
+                                                                         actual_authz_results):
+                self.assert_authz_responses_equal(expect_authz_result, actual_authz_result,
+                                                  ignore_metric_values=True,
+                                                  msg=f"request: {request}")
+                self.assertEqual(request.get("correlation_id"), actual_authz_result.correlation_id)
+                self.assertIn("convert_input_duration_micros", actual_authz_result.metrics)
+                self.assertIn("serialize_output_duration_micros", actual_authz_result.metrics)
+
+    def test_authorized_batch_document_with_embedded_json_strings(self):
+        requests = [self.request_bob_view_own_photo]
+        schema_src = load_file_as_str("resources/sandbox_b/schema.json")
+        entities_src = load_file_as_str("resources/sandbox_b/entities.json")
+        request = {
+            "principal": "User::\"alice\"",
+            "action": "Action::\"delete\"",
+            "resource": "Photo::\"alice_w2.jpg\"",
+            "context": json.dumps({"authenticated": True})
+        }
+        expect_authz_result = is_authorized(request, self.policies["alice"], entities_src, schema=schema_src)
+
+        for schema in [schema_src, json.loads(schema_src)]:
+            actual_authz_results = is_authorized_batch_document(json.dumps({
+                "requests": [request],
+                "policies": self.policies["alice"],
+                "entities": entities_src,
+                "schema": schema,
+            }))
+            self.assert_authz_responses_equal(expect_authz_result, actual_authz_results[0],
+                                              ignore_metric_values=True)
+            self.assertEqual(Decision.Allow, actual_authz_results[0].decision)
+
+    def test_authorized_batch_document_that_is_invalid(self):
+        for document in ["not json", '{"requests": []}', b'\xff']:
+            with self.assertRaises(ValueError, msg=f"document: {document}"):
+                is_authorized_batch_document(document)
+
+    def test_authorized_expanded_matches_batch_results(self):
+        actions = ['Action::"view"', 'Action::"edit"', 'Action::"delete"']
+        resources = ['Photo::"1234-abcd"', 'Photo::"prototype_v0.jpg"', 'Photo::"does-not-exist"']
+        context = {"authenticated": True}
+        requests = [{"principal": 'User::"bob"', "action": action, "resource": resource, "context": context}
+                    for action in actions for resource in resources]
+        expect_authz_results: List[AuthzResult] = is_authorized_batch(requests, self.policies["bob"], self.entities)
+
+        actual_authz_results = is_authorized_expanded('User::"bob"', actions, resources, self.policies["bob"],
+                                                      self.entities, context=context)
+
+        self.assertEqual(len(expect_authz_results), len(actual_authz_results))
+        for request, expect_authz_result, actual_authz_result in zip(requests,
+                                                                     expect_authz_results,
+                                                                     actual_authz_results):
+            self.assert_authz_responses_equal(expect_authz_result, actual_authz_result,
+                                              ignore_metric_values=True,
+                                              msg=f"request: {request}")
+        self.assertIn(Decision.Allow, [authz_result.decision for authz_result in actual_authz_results])
+
+    def test_authorized_expanded_is_bounded(self):
+        actions = [f'Action::"action{i}"' for i in range(0, 10)]
+        resources = [f'Photo::"photo{i}"' for i in range(0, 10)]
+        with self.assertRaises(ValueError):
+            is_authorized_expanded('User::"bob"', actions, resources, self.policies["bob"], self.entities,
+                                   max_requests=99)
+        self.assertEqual(100, len(is_authorized_expanded('User::"bob"', actions, resources, self.policies["bob"],
+                                                         self.entities, max_requests=100)))
+
+    def test_authorized_many_resources_matches_batch_results(self):
+        # an invalid resource fails its own request, as it would in a batch
+        resources = ['Photo::"1234-abcd"', 'Photo::"prototype_v0.jpg"', 'Photo::"does-not-exist"', 'not a uid']
+        context = {"authenticated": True}
+        requests = [{"principal": 'User::"bob"', "action": 'Action::"view"', "resource": resource, "context": context}
+                    for resource in resources]
+        expect_authz_results = is_authorized_batch(requests, self.policies["bob"], self.entities)
+
+        actual_authz_results = is_authorized_many_resources('User::"bob"', 'Action::"view"', resources,
+                                                            self.policies["bob"], self.entities, context=context)
+
+        self.assertEqual(len(resources), len(actual_authz_results))
+        for request, expect_authz_result, actual_authz_result in zip(requests,
+                                                                     expect_authz_results,
+                                                                     actual_authz_results):
+            self.assert_authz_responses_equal(expect_authz_result, actual_authz_result,
+                                              ignore_metric_values=True,
+                                              msg=f"request: {request}")
+        self.assertEqual(Decision.Allow, actual_authz_results[0].decision)
+        self.assertEqual(Decision.NoDecision, actual_authz_results[3].decision)
+
+    def test_authorized_many_actions_matches_batch_results(self):
+        actions = ['Action::"view"', 'Action::"edit"', 'Action::"delete"']
+        requests = [{"principal": 'User::"bob"', "action": action, "resource": 'Photo::"1234-abcd"', "context": {}}
+                    for action in actions]
+        expect_authz_results = is_authorized_batch(requests, self.policies["bob"], self.entities)
+
+        actual_authz_results = is_authorized_many_actions('User::"bob"', actions, 'Photo::"1234-abcd"',
+                                                          self.policies["bob"], self.entities, context={})
+
+        self.assertEqual(len(actions), len(actual_authz_results))
+        for request, expect_authz_result, actual_authz_result in zip(requests,
+                                                                     expect_authz_results,
+                                                                     actual_authz_results):
+            self.assert_authz_responses_equal(expect_authz_result, actual_authz_result,
+                                              ignore_metric_values=True,
+                                              msg=f"request: {request}")
+        with self.assertRaises(ValueError):
+            is_authorized_many_actions('User::"bob"', actions, 'Photo::"1234-abcd"', self.policies["bob"],
+                                       self.entities, max_requests=2)
+
+    def test_permission_matrix_matches_expanded_results(self):
+        actions = ['Action::"view"', 'Action::"edit"', 'Action::"delete"']
+        resources = ['Photo::"1234-abcd"', 'Photo::"prototype_v0.jpg"']
+        context = {"authenticated": True}
+        expect_authz_results = is_authorized_expanded('User::"bob"', actions, resources, self.policies["bob"],
+                                                      self.entities, context=context)
+
+        matrix = permission_matrix('User::"bob"', actions, resources, self.policies["bob"], self.entities,
+                                   context=context)
+
+        self.assertEqual(len(actions), len(matrix.allowed))
+        for i, action in enumerate(actions):
+            self.assertEqual(len(resources), len(matrix.allowed[i]))
+            for j, resource in enumerate(resources):
+                expect_authz_result = expect_authz_results[i * len(resources) + j]
+                self.assertEqual(expect_authz_result.allowed, matrix.allowed[i][j], msg=f"{action} {resource}")
+                self.assertEqual(expect_authz_result.allowed, matrix.allows(action, resource))
+                self.assertEqual(bool(expect_authz_result.diagnostics.errors), (i, j) in matrix.errored)
+
+    def test_permission_matrix_reports_errored_cells(self):
+        matrix = permission_matrix('User::"bob"', ['Action::"view"', 'not an action'], ['Photo::"1234-abcd"'],
+                                   self.policies["bob"], self.entities)
+        self.assertEqual([[True], [False]], matrix.allowed)
+        self.assertEqual([(1, 0)], matrix.errored)
+
+        matrix = permission_matrix('User::"bob"', ['Action::"view"'], ['Photo::"1234-abcd"'],
+                                   "this is not a real policy", self.entities)
+        self.assertEqual([[False]], matrix.allowed)
+        self.assertEqual([(0, 0)], matrix.errored)
+
+    def test_authorize_perf_when_decision_determined_by_many_policies(self):
+        import timeit
+
+        num_policies = 500
+        policies = "\n".join([
+            f'permit(principal == User::"bob", action == Action::"view", resource) when {{ {i} >= 0 }};'
+            for i in range(0, num_policies)
+        ])
+        requests = [self.request_bob_view_own_photo] * 20
+
+        authz_results: List[AuthzResult] = is_authorized_batch(requests, policies, self.entities)
+        for authz_result in authz_results:
+            self.assertEqual(Decision.Allow, authz_result.decision)
+            self.assertEqual(num_policies, len(authz_result.diagnostics.reasons))
+
+        num_exec = 10
+        timer = timeit.timeit(lambda: is_authorized_batch(requests, policies, self.entities), number=num_exec)
+        print(f'ALLOW by {num_policies} policies ({num_exec} x {len(requests)} requests): {timer}')
+        t_deadline_seconds = 2.0
+        self.assertLess(timer, t_deadline_seconds)
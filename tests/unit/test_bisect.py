@@ -0,0 +1,66 @@
+import unittest
+
+from textwrap import dedent
+
+from cedarpy import find_minimal_policy_subset, Decision
+
+
+class FindMinimalPolicySubsetTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.entities = [
+            {"uid": {"type": "User", "id": "alice"}, "attrs": {"department": "eng"},
+             "parents": [{"type": "Group", "id": "staff"}]},
+            {"uid": {"type": "Group", "id": "staff"}, "attrs": {}, "parents": []},
+            {"uid": {"type": "Document", "id": "plan"}, "attrs": {"classified": True}, "parents": []},
+        ]
+        self.request = {
+            "principal": 'User::"alice"',
+            "action": 'Action::"read"',
+            "resource": 'Document::"plan"',
+        }
+        # policies that don't apply to this request are interleaved with ones that do
+        unrelated = "\n".join(f'permit(principal == User::"user{i}", action, resource);' for i in range(40))
+        self.policies = dedent("""
+            permit(principal == User::"bob", action, resource);
+        """) + unrelated + dedent("""
+
+            // staff may read everything
+            permit(principal in Group::"staff", action == Action::"read", resource);
+            permit(principal, action == Action::"write", resource);
+        """) + unrelated
+
+    def test_finds_the_permit_that_allowed_a_request(self):
+        subset = find_minimal_policy_subset(self.request, self.policies, self.entities)
+
+        self.assertEqual(Decision.Allow, subset.decision)
+        self.assertEqual(["policy41"], subset.policy_ids)
+        self.assertEqual('permit(principal in Group::"staff", action == Action::"read", resource);',
+                         subset.policies)
+
+    def test_finds_the_forbid_that_denied_a_request(self):
+        policies = self.policies + dedent("""
+            forbid(principal, action, resource) when { resource.classified };
+            forbid(principal, action, resource) when { principal.department == "sales" };
+        """)
+
+        subset = find_minimal_policy_subset(self.request, policies, self.entities, decision=Decision.Deny)
+
+        self.assertEqual(Decision.Deny, subset.decision)
+        self.assertEqual(["policy83"], subset.policy_ids)
+        self.assertEqual('forbid(principal, action, resource) when { resource.classified };', subset.policies)
+
+    def test_default_deny_is_reproduced_by_no_policies(self):
+        request = dict(self.request, action='Action::"delete"')
+
+        subset = find_minimal_policy_subset(request, self.policies, self.entities)
+
+        self.assertEqual(Decision.Deny, subset.decision)
+        self.assertEqual([], subset.policy_ids)
+        self.assertEqual("", subset.policies)
+
+    def test_decision_not_produced_raises_value_error(self):
+        with self.assertRaises(ValueError):
+            find_minimal_policy_subset(self.request, self.policies, self.entities, decision="Deny")
+        with self.assertRaises(ValueError):
+            find_minimal_policy_subset(self.request, self.policies, self.entities, decision="Maybe")
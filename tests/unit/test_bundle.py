@@ -0,0 +1,110 @@
+import json
+import os
+import tempfile
+import unittest
+from pathlib import Path
+
+from cedarpy import export_debug_bundle, replay_debug_bundle, versions, Decision
+
+
+class DebugBundleTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.policies = """
+            permit(principal in Group::"editors", action == Action::"edit", resource);
+            forbid(principal, action, resource) when { context.risk > 5 };
+        """
+        self.entities = [
+            {"uid": {"type": "User", "id": "alice"}, "attrs": {"email": "alice@example.com", "level": 3},
+             "parents": [{"type": "Group", "id": "editors"}]},
+            {"uid": {"type": "Group", "id": "editors"}, "attrs": {}, "parents": []},
+            {"uid": {"type": "Doc", "id": "plan"}, "attrs": {}, "parents": []},
+        ]
+        self.request = {"principal": 'User::"alice"', "action": 'Action::"edit"', "resource": 'Doc::"plan"',
+                        "context": {"risk": 9, "session": "abc123"}}
+
+    def test_a_bundle_replays_its_decision_from_a_file(self):
+        with tempfile.TemporaryDirectory() as tmp:
+            path = os.path.join(tmp, "bundle.json")
+            bundle = export_debug_bundle(self.request, self.policies, self.entities, path=path)
+            with open(path) as f:
+                self.assertEqual(bundle, json.load(f))
+
+            replay = replay_debug_bundle(path)
+
+        self.assertEqual(1, bundle["bundle_version"])
+        self.assertEqual(self.request, bundle["request"])
+        self.assertEqual(self.entities, bundle["entities"])
+        self.assertEqual("strict", bundle["defaults"]["entity_validation"])
+        self.assertEqual("Deny", bundle["result"]["decision"])
+        self.assertEqual(["policy1"], [policy["id"] for policy in bundle["trace"]["determining_policies"]])
+        self.assertEqual(Decision.Deny, replay.recorded.decision)
+        self.assertEqual(Decision.Deny, replay.result.decision)
+        self.assertTrue(replay.matches)
+
+    def test_redacted_attributes_are_left_out_of_the_bundle(self):
+        bundle = export_debug_bundle(self.request, self.policies, self.entities, redact=["email", "risk"])
+
+        self.assertEqual({"email": "<redacted>", "level": 3}, bundle["entities"][0]["attrs"])
+        self.assertEqual({"risk": "<redacted>", "session": "abc123"}, bundle["request"]["context"])
+        self.assertEqual(["email", "risk"], bundle["redacted"])
+        # the forbid errors on the redacted risk, so the replay differs
+        replay = replay_debug_bundle(bundle)
+        self.assertEqual(Decision.Allow, replay.result.decision)
+        self.assertFalse(replay.matches)
+        self.assertEqual(["decision: Deny -> Allow", "reasons: ['policy1'] -> ['policy0']"], replay.changes[:2])
+        self.assertTrue(replay.changes[2].startswith("errors: [] -> ['while evaluating policy policy1"))
+
+    def test_an_undecided_request_is_bundled_with_its_errors(self):
+        request = dict(self.request, principal="alice")
+
+        bundle = export_debug_bundle(request, self.policies, self.entities)
+
+        self.assertEqual("NoDecision", bundle["result"]["decision"])
+        self.assertIsNone(bundle["trace"])
+        self.assertTrue(replay_debug_bundle(bundle).matches)
+
+    def test_only_bundles_of_this_version_replay(self):
+        bundle = export_debug_bundle(self.request, self.policies, self.entities)
+        with self.assertRaises(ValueError) as cm:
+            replay_debug_bundle(dict(bundle, bundle_version=99))
+        self.assertIn("not a debug bundle of version 1", str(cm.exception))
+
+    def test_a_replay_reports_the_versions_and_asserts_on_the_recorded_result(self):
+        bundle = export_debug_bundle(self.request, self.policies, self.entities)
+        self.assertEqual(versions(), bundle["versions"])
+
+        replay = replay_debug_bundle(bundle, expect="recorded")
+        self.assertEqual(bundle["versions"], replay.recorded_versions)
+        self.assertEqual([], replay.changes)
+
+        # a bundle recorded with another version that got another decision
+        bundle = dict(bundle, versions={"cedarpy": "0.1.0", "cedar": "2.0.0"},
+                      result=dict(bundle["result"], decision="Allow"))
+        with self.assertRaises(AssertionError) as cm:
+            replay_debug_bundle(bundle, expect="recorded")
+        self.assertIn("doesn't match the result recorded with 0.1.0: decision: Allow -> Deny", str(cm.exception))
+
+    def test_versions_name_the_cedar_in_the_lockfile(self):
+        try:
+            import tomllib
+        except ImportError:
+            self.skipTest("tomllib is new in Python 3.11")
+        lockfile = Path(__file__).parents[2] / "Cargo.lock"
+        if not lockfile.exists():
+            self.skipTest("Cargo.lock is written by the first build")
+        with open(lockfile, "rb") as f:
+            packages = tomllib.load(f)["package"]
+        self.assertEqual([package["version"] for package in packages if package["name"] == "cedar-policy"],
+                         [versions()["cedar"]])
+
+    def test_a_replay_asserts_on_an_expected_decision(self):
+        bundle = export_debug_bundle(self.request, self.policies, self.entities)
+
+        self.assertEqual(Decision.Deny, replay_debug_bundle(bundle, expect=Decision.Deny).result.decision)
+        with self.assertRaises(AssertionError) as cm:
+            replay_debug_bundle(bundle, expect="Allow")
+        self.assertIn("expected Allow, but the replay got Deny; changes from the recorded result: none",
+                      str(cm.exception))
+        with self.assertRaises(ValueError):
+            replay_debug_bundle(bundle, expect="allowed")
@@ -0,0 +1,73 @@
+import threading
+import time
+import unittest
+
+from cedarpy import CedarEngine
+
+from unit import load_file_as_json, load_file_as_str
+
+
+class EngineChangelogTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.schema = load_file_as_str("resources/sandbox_b/schema.json")
+        self.entities: list = load_file_as_json("resources/sandbox_b/entities.json")
+        self.policies = 'permit(principal == User::"alice", action == Action::"view", resource);'
+
+    def test_loads_and_reloads_are_logged_with_their_actors(self):
+        before = time.time()
+        engine = CedarEngine(self.policies, self.entities, self.schema, actor="deploy-bot")
+        engine.reload(self.policies, self.entities[1:], self.schema, actor="alice")
+        engine.reload(self.policies, self.entities, self.schema)
+
+        changelog = engine.changelog()
+        self.assertEqual([(0, "load", "deploy-bot", True), (1, "reload", "alice", True), (2, "reload", None, True)],
+                         [(c["seq"], c["kind"], c["actor"], c["ok"]) for c in changelog])
+        timestamps = [c["timestamp"] for c in changelog]
+        self.assertEqual(sorted(timestamps), timestamps)
+        self.assertTrue(before <= timestamps[0] <= time.time())
+
+        stats = engine.stats()
+        self.assertEqual((stats["num_policies"], stats["num_entities"], stats["fingerprints"]),
+                         (changelog[2]["num_policies"], changelog[2]["num_entities"], changelog[2]["fingerprints"]))
+        self.assertNotEqual(changelog[1]["fingerprints"]["entities"], changelog[2]["fingerprints"]["entities"])
+
+    def test_failed_reloads_are_logged(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema)
+        with self.assertRaises(ValueError) as cm:
+            engine.reload("permit(", self.entities, self.schema, actor="mallory")
+
+        failed = engine.changelog()[1]
+        self.assertEqual({"seq": 1, "kind": "reload", "actor": "mallory", "ok": False,
+                          "error_code": "policy_parse_error", "error": str(cm.exception)},
+                         {k: v for k, v in failed.items() if k != "timestamp"})
+
+    def test_the_changelog_is_queryable(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema, actor="deploy-bot")
+        engine.reload(self.policies, self.entities, self.schema, actor="alice")
+        middle = time.time()
+        engine.reload(self.policies, self.entities, self.schema, actor="bob")
+        engine.reload(self.policies, self.entities, self.schema, actor="alice")
+
+        self.assertEqual([1, 3], [c["seq"] for c in engine.changelog(actor="alice")])
+        self.assertEqual([2, 3], [c["seq"] for c in engine.changelog(since=middle)])
+        self.assertEqual([3], [c["seq"] for c in engine.changelog(since=middle, actor="alice", kind="reload")])
+        self.assertEqual([0], [c["seq"] for c in engine.changelog(kind="load")])
+        self.assertEqual([], engine.changelog(actor="carol"))
+
+        with self.assertRaises(ValueError) as cm:
+            engine.changelog(kind="upsert")
+        self.assertIn("invalid change kind 'upsert'", str(cm.exception))
+
+    def test_concurrent_reloads_are_each_logged_once_in_order(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema)
+        threads = [threading.Thread(target=engine.reload, args=(self.policies, self.entities, self.schema),
+                                    kwargs={"actor": f"worker-{i}"}) for i in range(8)]
+        for thread in threads:
+            thread.start()
+        for thread in threads:
+            thread.join()
+
+        changelog = engine.changelog()
+        self.assertEqual(list(range(9)), [c["seq"] for c in changelog])
+        self.assertEqual({f"worker-{i}" for i in range(8)}, {c["actor"] for c in changelog[1:]})
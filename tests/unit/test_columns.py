@@ -0,0 +1,61 @@
+import unittest
+
+from cedarpy import is_authorized_batch, CedarEngine, Decision
+
+from unit import load_file_as_json, load_file_as_str
+
+
+class ContextColumnsTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.schema = load_file_as_str("resources/sandbox_b/schema.json")
+        self.entities: list = load_file_as_json("resources/sandbox_b/entities.json")
+        self.policies = 'permit(principal, action == Action::"view", resource) when { context.authenticated };'
+        self.requests = [{
+            "principal": f'User::"{principal}"',
+            "action": 'Action::"view"',
+            "resource": 'Photo::"alice_w2.jpg"',
+        } for principal in ["alice", "bob", "alice", "bob"]]
+        self.authenticated = [True, False, False, True]
+
+    def test_contexts_may_be_columns(self):
+        expect_authz_results = is_authorized_batch([dict(request, context={"authenticated": authenticated})
+                                                    for request, authenticated in zip(self.requests, self.authenticated)],
+                                                   self.policies, self.entities, self.schema)
+        authz_results = is_authorized_batch(self.requests, self.policies, self.entities, self.schema,
+                                            context_columns={"authenticated": self.authenticated})
+
+        self.assertEqual([Decision.Allow, Decision.Deny, Decision.Deny, Decision.Allow],
+                         [authz_result.decision for authz_result in authz_results])
+        self.assertEqual([authz_result.decision for authz_result in expect_authz_results],
+                         [authz_result.decision for authz_result in authz_results])
+
+        # the requests themselves aren't changed
+        self.assertNotIn("context", self.requests[0])
+
+    def test_none_leaves_an_attribute_out(self):
+        authz_results = is_authorized_batch(self.requests[:2], self.policies, self.entities,
+                                            context_columns={"authenticated": (True, None)},
+                                            entity_validation="none")
+
+        self.assertEqual(Decision.Allow, authz_results[0].decision)
+        self.assertEqual(Decision.Deny, authz_results[1].decision)
+        self.assertIn("authenticated", authz_results[1].diagnostics.errors[0])
+
+    def test_engine_accepts_context_columns(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema)
+        authz_results = engine.is_authorized_batch(self.requests, context_columns={"authenticated": self.authenticated})
+
+        self.assertEqual([Decision.Allow, Decision.Deny, Decision.Deny, Decision.Allow],
+                         [authz_result.decision for authz_result in authz_results])
+
+    def test_invalid_columns_are_rejected(self):
+        with self.assertRaisesRegex(ValueError, "context column 'authenticated' has 3 values, but there are 4 requests"):
+            is_authorized_batch(self.requests, self.policies, self.entities, self.schema,
+                                context_columns={"authenticated": self.authenticated[:3]})
+
+        requests = [dict(self.requests[0], context={"authenticated": True})] + self.requests[1:]
+        with self.assertRaisesRegex(ValueError, "request 0 has a context") as cm:
+            is_authorized_batch(requests, self.policies, self.entities, self.schema,
+                                context_columns={"authenticated": self.authenticated})
+        self.assertEqual("invalid_argument", cm.exception.code)
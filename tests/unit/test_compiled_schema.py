@@ -0,0 +1,68 @@
+import json
+import unittest
+
+from cedarpy import (compile_schema, is_authorized, is_authorized_batch, validate_policies, CedarEngine, EntityStore,
+                     Decision, SchemaHandle)
+
+from unit import load_file_as_json, load_file_as_str
+
+
+class CompiledSchemaTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.schema = load_file_as_str("resources/sandbox_b/schema.json")
+        self.entities: list = load_file_as_json("resources/sandbox_b/entities.json")
+        self.policies = 'permit(principal == User::"alice", action == Action::"view", resource);'
+        self.request = {
+            "principal": 'User::"alice"',
+            "action": 'Action::"view"',
+            "resource": 'Photo::"alice_w2.jpg"',
+            "context": {"authenticated": True},
+        }
+
+    def test_a_handle_decides_as_the_schema_text_does(self):
+        schema_handle = compile_schema(self.schema)
+        self.assertIsInstance(schema_handle, SchemaHandle)
+        self.assertGreaterEqual(schema_handle.parse_duration_micros, 0)
+
+        requests = [self.request, dict(self.request, context={"authenticated": "yes"})]
+        with_text = is_authorized_batch(requests, self.policies, self.entities, self.schema, include_metrics=False,
+                                        validate_request=True)
+        with_handle = is_authorized_batch(requests, self.policies, self.entities, schema_handle,
+                                          include_metrics=False, validate_request=True)
+        self.assertEqual([Decision.Allow, Decision.NoDecision], [r.decision for r in with_handle])
+        self.assertEqual([r.to_dict() for r in with_text], [r.to_dict() for r in with_handle])
+
+        # a dict compiles too
+        self.assertEqual(Decision.Allow, is_authorized(self.request, self.policies, self.entities,
+                                                       compile_schema(json.loads(self.schema))).decision)
+
+    def test_policies_validate_against_a_handle(self):
+        policies = self.policies + '\npermit(principal == Usr::"alice", action == Action::"view", resource);'
+        self.assertEqual(validate_policies(policies, self.schema),
+                         validate_policies(policies, compile_schema(self.schema)))
+
+    def test_engines_and_stores_take_a_handle(self):
+        schema_handle = compile_schema(self.schema)
+        engine = CedarEngine(self.policies, self.entities, schema_handle)
+        self.assertEqual(CedarEngine(self.policies, self.entities, self.schema).is_authorized(self.request).fingerprints,
+                         engine.is_authorized(self.request).fingerprints)
+        self.assertEqual(schema_handle.fingerprint, engine.is_authorized(self.request).fingerprints["schema"])
+
+        engine.reload(self.policies, self.entities[1:], schema_handle)
+        self.assertEqual(schema_handle.fingerprint, engine.is_authorized(self.request).fingerprints["schema"])
+
+        store = EntityStore(self.entities, schema_handle)
+        self.assertEqual(Decision.Allow, is_authorized(self.request, self.policies, store, schema_handle).decision)
+        with self.assertRaises(ValueError):
+            store.upsert_entity({"uid": {"type": "User", "id": "carol"}, "attrs": {"age": "old"}, "parents": []})
+
+    def test_invalid_schemas_and_arguments_raise(self):
+        with self.assertRaises(ValueError) as cm:
+            compile_schema('{"": {"entityTypes": {"User": {"memberOfTypes": ["Group"]}}, "actions": {}}}')
+        self.assertEqual("schema_error", cm.exception.code)
+        self.assertIn("invalid schema", str(cm.exception))
+
+        with self.assertRaises(TypeError) as cm:
+            validate_policies(self.policies, 42)
+        self.assertIn("schema must be a str or a SchemaHandle, not int", str(cm.exception))
@@ -0,0 +1,60 @@
+import unittest
+
+from cedarpy import completion_context
+
+from unit import load_file_as_str
+
+
+class CompletionContextTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.schema = load_file_as_str("resources/sandbox_b/schema.json")
+
+    def test_scope_slots_offer_applicable_types_and_actions(self):
+        completions = completion_context(self.schema, {"slot": "resource",
+                                                       "actions": ['Action::"view"', 'Action::"listPhotos"']})
+        self.assertEqual(["Photo", "Album"], completions["entity_types"])
+        self.assertEqual(([], [], []), (completions["actions"], completions["attributes"], completions["functions"]))
+
+        completions = completion_context(self.schema, {"slot": "action", "resource_type": "Album"})
+        self.assertEqual(['Action::"listPhotos"', 'Action::"addPhoto"'], completions["actions"])
+
+        completions = completion_context(self.schema, {"slot": "principal"})
+        self.assertEqual(["User"], completions["entity_types"])
+
+    def test_condition_offers_attributes_that_follow_the_path(self):
+        scope = {"slot": "condition", "actions": ['Action::"view"']}
+        completions = completion_context(self.schema, scope)
+        self.assertIn("AccountGroup", completions["entity_types"])
+        self.assertEqual(['Action::"view"'], completions["actions"])
+        self.assertEqual([{"name": "ip", "kind": "function"}, {"name": "decimal", "kind": "function"}],
+                         completions["functions"])
+
+        completions = completion_context(self.schema, dict(scope, path="resource."))
+        self.assertEqual([("private", "Boolean"), ("account", "Entity<Account>"), ("admins", "Set<Entity<User>>")],
+                         [(attribute["name"], attribute["type"]) for attribute in completions["attributes"]])
+
+        completions = completion_context(self.schema, dict(scope, path="resource.account.owner"))
+        self.assertEqual(["department", "jobLevel"], [attribute["name"] for attribute in completions["attributes"]])
+
+        completions = completion_context(self.schema, dict(scope, path="resource.admins"))
+        self.assertEqual([], completions["attributes"])
+        self.assertEqual(["contains", "containsAll", "containsAny"], [f["name"] for f in completions["functions"]])
+        self.assertEqual({"method"}, {f["kind"] for f in completions["functions"]})
+
+        completions = completion_context(self.schema, {"slot": "condition", "actions": ['Action::"addPhoto"'],
+                                                       "path": "context.photo"})
+        self.assertEqual([{"name": "filesize_mb", "type": "Long", "required": True},
+                          {"name": "filetype", "type": "String", "required": True}], completions["attributes"])
+
+        completions = completion_context(self.schema, dict(scope, path="resource.no_such_attribute"))
+        self.assertEqual([], completions["attributes"])
+
+    def test_invalid_scope_is_rejected(self):
+        for cursor_scope, message in [({"slot": "body"}, "invalid cursor scope"),
+                                      ({"slot": "principal", "principal_type": "Robot"}, "Robot is not in the schema"),
+                                      ({"slot": "action", "actions": ['Action::"fly"']}, "not in the schema"),
+                                      ({"slot": "condition", "path": "request.x"}, "invalid path")]:
+            with self.assertRaisesRegex(ValueError, message) as cm:
+                completion_context(self.schema, cursor_scope)
+            self.assertEqual("invalid_argument", cm.exception.code)
@@ -0,0 +1,126 @@
+import json
+import os
+import subprocess
+import sys
+import unittest
+
+from cedarpy import defaults
+
+
+def import_with_env(**env) -> subprocess.CompletedProcess:
+    """Import cedarpy in a fresh interpreter with the environment variables set, printing its defaults"""
+    return subprocess.run([sys.executable, "-c", "import json, cedarpy; print(json.dumps(cedarpy.defaults()))"],
+                          env=dict(os.environ, PYTHONPATH=os.pathsep.join(sys.path), **env),
+                          capture_output=True, text=True)
+
+
+class DefaultsTestCase(unittest.TestCase):
+
+    def test_builtin_defaults(self):
+        builtin = defaults()
+        # the number of CPUs
+        self.assertGreaterEqual(builtin.pop("max_threads"), 1)
+        self.assertEqual({"entity_validation": "strict",
+                          "unknown_entities": "ignore",
+                          "policy_errors": "skip",
+                          "verbose": False,
+                          "threads": 1,
+                          "request_cache_size": 1024,
+                          "generate_correlation_ids": False,
+                          "max_input_bytes": 1 << 30},
+                         builtin)
+
+    def test_defaults_are_read_from_the_environment(self):
+        completed = import_with_env(CEDARPY_ENTITY_VALIDATION="permissive",
+                                    CEDARPY_UNKNOWN_ENTITIES="error",
+                                    CEDARPY_POLICY_ERRORS="propagate",
+                                    CEDARPY_VERBOSE="1",
+                                    CEDARPY_THREADS="4",
+                                    CEDARPY_MAX_THREADS="8",
+                                    CEDARPY_REQUEST_CACHE_SIZE="0",
+                                    CEDARPY_GENERATE_CORRELATION_IDS="yes",
+                                    CEDARPY_MAX_INPUT_BYTES="1000")
+        self.assertEqual(0, completed.returncode, completed.stderr)
+        self.assertEqual({"entity_validation": "permissive",
+                          "unknown_entities": "error",
+                          "policy_errors": "propagate",
+                          "verbose": True,
+                          "threads": 4,
+                          "max_threads": 8,
+                          "request_cache_size": 0,
+                          "generate_correlation_ids": True,
+                          "max_input_bytes": 1000},
+                         json.loads(completed.stdout))
+
+    def test_environment_defaults_apply_to_calls(self):
+        script = """
+import cedarpy
+result = cedarpy.is_authorized({"principal": 'User::"alice"', "action": 'Action::"view"',
+                                "resource": 'Photo::"a.jpg"'},
+                               'permit(principal, action, resource);', [])
+print(result.diagnostics.errors)
+"""
+        completed = subprocess.run([sys.executable, "-c", script],
+                                   env=dict(os.environ, PYTHONPATH=os.pathsep.join(sys.path),
+                                            CEDARPY_UNKNOWN_ENTITIES="error"),
+                                   capture_output=True, text=True)
+        self.assertEqual(0, completed.returncode, completed.stderr)
+        self.assertIn('is not in the entities', completed.stdout)
+
+    def test_correlation_ids_may_be_generated(self):
+        script = """
+import cedarpy
+request = {"principal": 'User::"alice"', "action": 'Action::"view"', "resource": 'Photo::"a.jpg"'}
+results = cedarpy.is_authorized_batch([request, dict(request, correlation_id="given")],
+                                      'permit(principal, action, resource);', [])
+print(results[0].correlation_id)
+print(results[1].correlation_id)
+"""
+        completed = subprocess.run([sys.executable, "-c", script],
+                                   env=dict(os.environ, PYTHONPATH=os.pathsep.join(sys.path),
+                                            CEDARPY_GENERATE_CORRELATION_IDS="true"),
+                                   capture_output=True, text=True)
+        self.assertEqual(0, completed.returncode, completed.stderr)
+        generated, given = completed.stdout.split()
+        self.assertRegex(generated, r"^[0-9a-f]{8}-[0-9a-f]{4}-7[0-9a-f]{3}-[89ab][0-9a-f]{3}-[0-9a-f]{12}$")
+        self.assertEqual("given", given)
+
+    def test_batches_share_a_bounded_thread_pool(self):
+        script = """
+import threading
+import cedarpy
+policies = 'permit(principal == User::"alice", action, resource);'
+requests = [{"principal": f'User::"{"alice" if i % 3 else "bob"}"', "action": 'Action::"view"',
+             "resource": 'Photo::"a.jpg"', "correlation_id": str(i)} for i in range(2000)]
+expected = [(r.correlation_id, r.decision) for r in cedarpy.is_authorized_batch(requests, policies, [])]
+engine = cedarpy.CedarEngine(policies, [], max_workers=16)
+results = []
+def run(batch):
+    results.append([(r.correlation_id, r.decision) for r in batch()])
+threads = [threading.Thread(target=run, args=(batch,)) for batch in
+           [lambda: cedarpy.is_authorized_batch(requests, policies, [], max_workers=1000)] * 3 +
+           [lambda: engine.is_authorized_batch(requests)] * 3]
+for thread in threads:
+    thread.start()
+for thread in threads:
+    thread.join()
+print(len(results), all(result == expected for result in results))
+"""
+        completed = subprocess.run([sys.executable, "-c", script],
+                                   env=dict(os.environ, PYTHONPATH=os.pathsep.join(sys.path),
+                                            CEDARPY_MAX_THREADS="2"),
+                                   capture_output=True, text=True)
+        self.assertEqual(0, completed.returncode, completed.stderr)
+        self.assertEqual("6 True", completed.stdout.strip())
+
+    def test_invalid_variables_fail_the_import(self):
+        completed = import_with_env(CEDARPY_ENTITY_VALIDATION="sometimes")
+        self.assertNotEqual(0, completed.returncode)
+        self.assertIn("invalid CEDARPY_ENTITY_VALIDATION", completed.stderr)
+
+        completed = import_with_env(CEDARPY_THREADS="0")
+        self.assertNotEqual(0, completed.returncode)
+
+        completed = import_with_env(CEDARPY_MAX_THREADS="0")
+        self.assertNotEqual(0, completed.returncode)
+        self.assertIn("invalid CEDARPY_MAX_THREADS", completed.stderr)
@@ -0,0 +1,143 @@
+import unittest
+
+from textwrap import dedent
+
+from cedarpy import check_round_trip, policies_from_json, policies_to_json, split_policy_source
+
+
+class RoundTripTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+
+    def test_policies_with_ids_round_trip_losslessly(self):
+        policies = dedent("""
+            @id("edit")
+            permit(principal, action == Action::"edit", resource)
+            when { resource.owner == principal };
+
+            @id("view-template")
+            permit(principal == ?principal, action == Action::"view", resource in ?resource);
+        """)
+
+        report = check_round_trip(policies)
+
+        self.assertTrue(report.lossless)
+        self.assertEqual([], report.issues)
+
+    def test_losses_are_reported_per_policy(self):
+        policies = dedent("""
+            // editors may edit
+            @id("edit")
+            permit(principal, action == Action::"edit", resource);
+
+            permit(principal, action, resource) unless { context.url like "https://*" };
+        """)
+
+        report = check_round_trip(policies)
+
+        self.assertFalse(report.lossless)
+        self.assertEqual([("policy0", "comments"), ("policy1", "id"), ("policy1", "json")],
+                         [(issue.policy_id, issue.kind) for issue in report.issues])
+        self.assertIn("comments", report.issues[0].message)
+
+    def test_json_policies_round_trip(self):
+        policy = {
+            "effect": "forbid",
+            "principal": {"op": "All"},
+            "action": {"op": "All"},
+            "resource": {"op": "All"},
+            "conditions": [],
+            "annotations": {"id": "deny-all"},
+        }
+
+        self.assertTrue(check_round_trip([policy]).lossless)
+        self.assertTrue(check_round_trip(policy).lossless)
+
+    def test_invalid_policies_raise(self):
+        with self.assertRaises(ValueError) as cm:
+            check_round_trip("permit(principal, action, resource")
+        self.assertEqual("policy_parse_error", cm.exception.code)
+        with self.assertRaises(ValueError):
+            check_round_trip("{}", input_format="yaml")
+
+
+class ConvertTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.policies = dedent("""
+            permit(principal, action == Action::"edit", resource);
+            forbid(principal, action, resource in ?resource);
+        """)
+
+    def test_policies_convert_to_json(self):
+        policies = policies_to_json(self.policies)
+
+        self.assertEqual(["permit", "forbid"], [policy["effect"] for policy in policies])
+
+    def test_structured_conversions_keep_ids(self):
+        converted = policies_to_json(self.policies, structured=True)
+
+        self.assertEqual(["policy0", "policy1"], [policy["id"] for policy in converted])
+        self.assertEqual('permit(principal, action == Action::"edit", resource);', converted[0]["text"])
+
+        converted[0]["id"] = "edit"
+        back = policies_from_json(list(reversed(converted)), structured=True)
+
+        self.assertEqual(["policy1", "edit"], [policy["id"] for policy in back])
+        self.assertEqual(converted[1]["policy"], back[0]["policy"])
+
+    def test_policies_convert_from_json(self):
+        text = policies_from_json(policies_to_json(self.policies))
+
+        policies = policies_to_json(text)
+        self.assertEqual(["permit", "forbid"], [policy["effect"] for policy in policies])
+        self.assertEqual({"op": "in", "slot": "?resource"}, policies[1]["resource"])
+
+
+class SplitPolicySourceTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.policies = dedent("""
+            permit(principal, action == Action::"edit", resource);
+            permit(principal == ?principal, action == Action::"view", resource in ?resource);
+        """)
+
+    def test_text_is_split_into_static_policies_and_templates(self):
+        split = split_policy_source(self.policies)
+
+        self.assertEqual(["policy0"], [policy["id"] for policy in split.static_policies])
+        self.assertEqual(["policy1"], [template["id"] for template in split.templates])
+        self.assertTrue(split.templates[0]["text"].startswith("permit(principal == ?principal"))
+        self.assertEqual([], split.links)
+
+    def test_json_links_are_split_out(self):
+        link = {
+            "templateId": "view",
+            "newId": "alice-photos",
+            "values": {"?principal": {"type": "User", "id": "alice"}, "?resource": {"type": "Album", "id": "a"}},
+        }
+        converted = policies_to_json(self.policies, structured=True)
+        policy_set = {
+            "staticPolicies": {"edit": converted[0]["policy"]},
+            "templates": {"view": converted[1]["policy"]},
+            "templateLinks": [link],
+        }
+
+        for policies in (policy_set, [{"id": "edit", "policy": converted[0]["policy"]},
+                                      {"id": "view", "policy": converted[1]["policy"]},
+                                      link]):
+            split = split_policy_source(policies)
+            self.assertEqual(["edit"], [policy["id"] for policy in split.static_policies])
+            self.assertEqual(["view"], [template["id"] for template in split.templates])
+            self.assertEqual([{"id": "alice-photos", "template_id": "view", "values": link["values"]}],
+                             split.links)
+
+    def test_links_must_fill_their_templates_slots(self):
+        converted = policies_to_json(self.policies)
+        link = {"templateId": "policy1", "newId": "alice", "values": {"?principal": {"type": "User", "id": "alice"}}}
+
+        with self.assertRaises(ValueError) as cm:
+            split_policy_source(converted + [link])
+        self.assertIn("template policy1 has the slots", str(cm.exception))
+        with self.assertRaises(ValueError):
+            split_policy_source(converted + [dict(link, templateId="policy0")])
@@ -0,0 +1,58 @@
+import unittest
+
+from cedarpy import is_authorized, is_authorized_batch, Decision
+
+from unit import load_file_as_json, load_file_as_str
+
+
+class DenialClassTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.schema = load_file_as_str("resources/sandbox_b/schema.json")
+        self.entities: list = load_file_as_json("resources/sandbox_b/entities.json")
+        self.request = {"principal": 'User::"ahmad"', "action": 'Action::"view"', "resource": 'Photo::"vacation.jpg"',
+                        "context": {"authenticated": True}}
+
+    def denial_class(self, policies: str, request: dict = None):
+        authz_result = is_authorized(request or self.request, policies, self.entities, self.schema,
+                                     include_denial_class=True)
+        return authz_result.decision, authz_result.denial_class
+
+    def test_denials_are_classified_by_the_fix_they_need(self):
+        for policies, denial_class in [
+            ('permit(principal == Administrator::"root", action, resource);\n'
+             'permit(principal, action == Action::"edit", resource);', "structural"),
+            ('permit(principal in UserGroup::"alice_friends", action == Action::"view", '
+             'resource in Album::"alice_vacation");', "unmatched_scope"),
+            ('permit(principal, action in [Action::"view"], resource in Account::"alice") '
+             'when { context.authenticated == false };', "conditional"),
+            ('permit(principal, action, resource);\nforbid(principal == User::"ahmad", action, resource);',
+             "forbidden"),
+        ]:
+            with self.subTest(denial_class=denial_class):
+                self.assertEqual((Decision.Deny, denial_class), self.denial_class(policies))
+
+    def test_a_scope_that_could_cover_the_types_is_not_structural(self):
+        # Photo entities may be members of Albums, and Albums of Accounts, so these scopes cover the request's types
+        for resource in ['Album::"device_prototypes"', 'Account::"stacey"']:
+            policies = f'permit(principal, action == Action::"view", resource in {resource});'
+            with self.subTest(resource=resource):
+                self.assertEqual((Decision.Deny, "unmatched_scope"), self.denial_class(policies))
+        policies = 'permit(principal, action == Action::"view", resource in User::"alice");'
+        self.assertEqual((Decision.Deny, "structural"), self.denial_class(policies))
+
+    def test_only_denials_with_a_schema_are_classified(self):
+        policies = 'permit(principal in UserGroup::"alice_friends", action, resource);'
+        self.assertEqual((Decision.Allow, None), self.denial_class(policies, dict(self.request,
+                                                                                   principal='User::"stacey"')))
+        self.assertIsNone(is_authorized(self.request, policies, self.entities, entity_validation="none",
+                                        include_denial_class=True).denial_class)
+        self.assertIsNone(is_authorized(self.request, policies, self.entities, self.schema).denial_class)
+
+    def test_batch_results_are_classified_without_diagnostics(self):
+        policies = 'permit(principal == User::"stacey", action == Action::"view", resource);'
+        results = is_authorized_batch([self.request, dict(self.request, action='Action::"delete"'),
+                                       dict(self.request, principal='User::"stacey"')],
+                                      policies, self.entities, self.schema, include_diagnostics=False,
+                                      include_denial_class=True)
+        self.assertEqual(["unmatched_scope", "structural", None], [result.denial_class for result in results])
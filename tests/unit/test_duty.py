@@ -0,0 +1,84 @@
+import unittest
+
+from cedarpy import check_separation_of_duty
+
+
+class SeparationOfDutyTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.schema = {
+            "": {
+                "entityTypes": {
+                    "User": {"memberOfTypes": ["Role"], "shape": {"type": "Record", "attributes": {}}},
+                    "Role": {"shape": {"type": "Record", "attributes": {}}},
+                    "Expense": {"shape": {"type": "Record", "attributes": {}}},
+                },
+                "actions": {
+                    "submitExpense": {"appliesTo": {"principalTypes": ["User"], "resourceTypes": ["Expense"]}},
+                    "approveExpense": {"appliesTo": {"principalTypes": ["User"], "resourceTypes": ["Expense"]}},
+                },
+            }
+        }
+        self.policies = """
+            permit(principal, action == Action::"submitExpense", resource);
+            permit(principal in Role::"approvers", action == Action::"approveExpense", resource);
+            permit(principal == User::"mallory", action == Action::"approveExpense", resource == Expense::"offsite");
+        """
+        self.entities = [
+            {"uid": {"type": "User", "id": "alice"}, "attrs": {}, "parents": [{"type": "Role", "id": "approvers"}]},
+            {"uid": {"type": "User", "id": "bob"}, "attrs": {}, "parents": []},
+            {"uid": {"type": "User", "id": "mallory"}, "attrs": {}, "parents": []},
+            {"uid": {"type": "Role", "id": "approvers"}, "attrs": {}, "parents": []},
+            {"uid": {"type": "Expense", "id": "offsite"}, "attrs": {}, "parents": []},
+            {"uid": {"type": "Expense", "id": "travel"}, "attrs": {}, "parents": []},
+        ]
+        self.conflicts = [('Action::"submitExpense"', 'Action::"approveExpense"')]
+
+    def test_principals_allowed_both_actions_on_a_resource_are_violations(self):
+        report = check_separation_of_duty(self.conflicts, self.policies, self.entities, self.schema)
+
+        self.assertFalse(report.holds)
+        self.assertEqual(['User::"alice"', 'User::"mallory"'], report.violating_principals)
+        self.assertEqual([('User::"alice"', 'Expense::"offsite"'),
+                          ('User::"alice"', 'Expense::"travel"'),
+                          ('User::"mallory"', 'Expense::"offsite"')],
+                         [(violation['principal'], violation['resource']) for violation in report.violations])
+        self.assertEqual({"principal": 'User::"mallory"',
+                          "resource": 'Expense::"offsite"',
+                          "actions": ['Action::"submitExpense"', 'Action::"approveExpense"'],
+                          "reasons": [["policy0"], ["policy2"]]},
+                         report.violations[-1])
+        # 3 users and 2 expenses, with both actions
+        self.assertEqual(12, report.requests_checked)
+        self.assertEqual(0, report.errored)
+
+    def test_a_separation_the_policies_keep_holds(self):
+        policies = """
+            permit(principal, action == Action::"submitExpense", resource) unless { principal in Role::"approvers" };
+            permit(principal in Role::"approvers", action == Action::"approveExpense", resource);
+        """
+
+        report = check_separation_of_duty(self.conflicts, policies, self.entities, self.schema)
+
+        self.assertTrue(report.holds)
+        self.assertEqual([], report.violations)
+
+    def test_without_a_schema_the_types_narrow_the_principals_and_resources(self):
+        report = check_separation_of_duty(self.conflicts, self.policies, self.entities,
+                                          principal_types=["User"], resource_types=["Expense"])
+        self.assertEqual(['User::"alice"', 'User::"mallory"'], report.violating_principals)
+        self.assertEqual(12, report.requests_checked)
+
+        # every entity is a principal and a resource
+        report = check_separation_of_duty(self.conflicts, self.policies, self.entities)
+        self.assertEqual(6 * 6 * 2, report.requests_checked)
+
+    def test_invalid_conflicts_raise(self):
+        with self.assertRaises(ValueError) as cm:
+            check_separation_of_duty([('Action::"submitExpense"', 'Action::"deleteExpense"')],
+                                     self.policies, self.entities, self.schema)
+        self.assertIn('Action::"deleteExpense" is not declared in the schema', str(cm.exception))
+
+        with self.assertRaises(ValueError) as cm:
+            check_separation_of_duty(self.conflicts, self.policies, self.entities, max_requests=10)
+        self.assertIn("more than max_requests (10)", str(cm.exception))
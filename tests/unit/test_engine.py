@@ -0,0 +1,604 @@
+import threading
+import time
+import unittest
+
+from cedarpy import is_authorized, AuthzResult, BatchPage, CedarEngine, Decision, FakeEngine, is_authorized_union
+
+from unit import load_file_as_json, load_file_as_str
+
+
+class CedarEngineTestCase(unittest.TestCase):
+
+    def setUp(self) -> None:
+        super().setUp()
+        self.policies = """
+            permit(
+                principal == User::"alice",
+                action == Action::"view",
+                resource
+            );
+            permit(
+                principal,
+                action == Action::"delete",
+                resource
+            )
+            when { context.authenticated };
+        """
+        self.schema = load_file_as_str("resources/sandbox_b/schema.json")
+        self.entities: list = load_file_as_json("resources/sandbox_b/entities.json")
+        self.view_request = {
+            "principal": "User::\"alice\"",
+            "action": "Action::\"view\"",
+            "resource": "Photo::\"alice_w2.jpg\"",
+            "context": {"authenticated": False}
+        }
+        self.delete_request = {
+            "principal": "User::\"stacey\"",
+            "action": "Action::\"delete\"",
+            "resource": "Photo::\"alice_w2.jpg\"",
+            "context": {"authenticated": True}
+        }
+
+    def test_engine_decides_like_is_authorized(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema)
+        for request in [self.view_request, self.delete_request,
+                        dict(self.delete_request, context={"authenticated": False})]:
+            expect_result: AuthzResult = is_authorized(request, self.policies, self.entities, self.schema)
+            actual_result: AuthzResult = engine.is_authorized(request)
+            self.assertEqual(expect_result.decision, actual_result.decision)
+            self.assertEqual(expect_result.diagnostics.reasons, actual_result.diagnostics.reasons)
+
+        results = engine.is_authorized_batch([self.view_request, self.delete_request])
+        self.assertEqual([Decision.Allow, Decision.Allow], [result.decision for result in results])
+
+    def test_fast_path_decides_like_is_authorized(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema)
+        for request in [self.view_request, self.delete_request,
+                        dict(self.delete_request, context={"authenticated": False})]:
+            self.assertEqual(engine.is_authorized(request).allowed, engine.is_authorized_fast(request))
+        self.assertTrue(engine.is_authorized_fast(self.view_request))
+
+        # a request that can't be decided isn't allowed
+        undecided = dict(self.view_request, context={"unknown": 1})
+        self.assertEqual(Decision.NoDecision, engine.is_authorized(undecided).decision)
+        self.assertFalse(engine.is_authorized_fast(undecided))
+
+    def test_engine_reports_request_errors_per_request(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema)
+        results = engine.is_authorized_batch([self.view_request,
+                                              dict(self.view_request, context={"unknown": 1})])
+        self.assertEqual(Decision.Allow, results[0].decision)
+        self.assertEqual(Decision.NoDecision, results[1].decision)
+        self.assertEqual(1, len(results[1].diagnostics.errors))
+
+    def test_engine_may_propagate_policy_evaluation_errors(self):
+        request = dict(self.delete_request, context={})
+        self.assertEqual(Decision.Deny, CedarEngine(self.policies, self.entities).is_authorized(request).decision)
+
+        engine = CedarEngine(self.policies, self.entities, policy_errors="propagate")
+        authz_result: AuthzResult = engine.is_authorized(request)
+        self.assertEqual(Decision.NoDecision, authz_result.decision)
+        self.assertTrue(authz_result.diagnostics.errors[0].startswith("policy evaluation error: "))
+
+    def test_request_cache_counts_hits_and_misses(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema)
+        self.assertEqual(0.0, engine.stats()["request_cache_hit_rate"])
+
+        engine.is_authorized_batch([self.view_request, self.delete_request, self.view_request])
+        engine.is_authorized(self.view_request)
+
+        stats = engine.stats()
+        self.assertEqual(2, stats["num_policies"])
+        # the schema's actions are loaded as entities too
+        self.assertLess(len(self.entities), stats["num_entities"])
+        self.assertEqual(1024, stats["request_cache_capacity"])
+        self.assertEqual(2, stats["request_cache_size"])
+        self.assertEqual(2, stats["request_cache_hits"])
+        self.assertEqual(2, stats["request_cache_misses"])
+        self.assertEqual(0.5, stats["request_cache_hit_rate"])
+
+    def test_request_cache_evicts_least_recently_used(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema, request_cache_size=1)
+        engine.is_authorized_batch([self.view_request, self.delete_request, self.view_request])
+
+        stats = engine.stats()
+        self.assertEqual(1, stats["request_cache_size"])
+        self.assertEqual(0, stats["request_cache_hits"])
+        self.assertEqual(3, stats["request_cache_misses"])
+
+    def test_request_uids_are_interned_from_the_entity_store(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema, request_cache_size=0)
+        stats = engine.stats()
+        self.assertEqual(stats["num_entities"], stats["uid_interner_size"])
+        self.assertEqual(0, stats["uid_interner_bytes_deduplicated"])
+
+        # alice, her photo, and the view action are in the store; bob is not
+        engine.is_authorized(self.view_request)
+        engine.is_authorized(dict(self.view_request, principal="User::\"bob\""))
+
+        stats = engine.stats()
+        self.assertEqual(stats["num_entities"], stats["uid_interner_size"])
+        self.assertEqual(5, stats["uid_interner_hits"])
+        self.assertEqual(1, stats["uid_interner_misses"])
+        self.assertEqual(2 * len('Action::"view"') + 2 * len('Photo::"alice_w2.jpg"') + len('User::"alice"'),
+                         stats["uid_interner_bytes_deduplicated"])
+
+    def test_request_cache_can_be_disabled(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema, request_cache_size=0)
+        self.assertEqual(Decision.Allow, engine.is_authorized(self.view_request).decision)
+        self.assertEqual(Decision.Allow, engine.is_authorized(self.view_request).decision)
+
+        stats = engine.stats()
+        self.assertEqual(0, stats["request_cache_size"])
+        self.assertEqual(0, stats["request_cache_hits"])
+
+    def test_policy_diagnostics_report_each_policy(self):
+        self.assertIsNone(CedarEngine(self.policies, self.entities, self.schema).policy_diagnostics())
+
+        engine = CedarEngine(self.policies, self.entities, self.schema, policy_diagnostics=True)
+        diagnostics = engine.policy_diagnostics()
+        self.assertEqual(["policy0", "policy1"], [policy["policy_id"] for policy in diagnostics])
+        self.assertEqual([2, 7], [policy["line"] for policy in diagnostics])
+        first_policy = self.policies[self.policies.index("permit"):self.policies.index(";") + 1]
+        self.assertEqual(len(first_policy), diagnostics[0]["size_bytes"])
+        for policy in diagnostics:
+            self.assertGreaterEqual(policy["parse_duration_micros"], 0)
+
+    def test_concurrency_limit_queues_calls_and_reports_queue_time(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema, max_concurrency=1)
+        results = []
+
+        def authorize():
+            results.extend(engine.is_authorized_batch([self.view_request] * 200))
+
+        threads = [threading.Thread(target=authorize) for _ in range(4)]
+        for thread in threads:
+            thread.start()
+        for thread in threads:
+            thread.join()
+
+        self.assertEqual([Decision.Allow] * 800, [result.decision for result in results])
+        self.assertTrue(all(result.metrics["queue_duration_micros"] >= 0 for result in results))
+        stats = engine.stats()
+        self.assertEqual(1, stats["max_concurrency"])
+        self.assertIsNone(stats["max_queue"])
+        self.assertEqual(0, stats["active_evaluations"])
+        self.assertEqual(0, stats["queued_evaluations"])
+        self.assertEqual(0, stats["rejected_evaluations"])
+
+    def test_concurrency_limit_rejects_calls_when_its_queue_is_full(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema, max_concurrency=1, max_queue=0)
+        batch = threading.Thread(target=engine.is_authorized_batch, args=([self.view_request] * 100_000,))
+        batch.start()
+        while engine.stats()["active_evaluations"] == 0:
+            pass
+        authz_result: AuthzResult = engine.is_authorized(self.view_request)
+        batch.join()
+
+        self.assertEqual(Decision.NoDecision, authz_result.decision)
+        self.assertEqual(["overloaded"], authz_result.diagnostics.error_codes)
+        self.assertTrue(authz_result.diagnostics.errors[0].startswith("engine overloaded: 1 evaluations are running"))
+        self.assertEqual(1, engine.stats()["rejected_evaluations"])
+        self.assertEqual(Decision.Allow, engine.is_authorized(self.view_request).decision)
+
+    def test_interactive_calls_run_between_the_requests_of_a_batch_call(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema, max_concurrency=1)
+        batch = threading.Thread(target=engine.is_authorized_batch,
+                                 args=([self.view_request] * 100_000,),
+                                 kwargs={"priority": "batch"})
+        batch.start()
+        while engine.stats()["active_evaluations"] == 0:
+            pass
+        authz_result: AuthzResult = engine.is_authorized(self.view_request, priority="interactive")
+        # the interactive call didn't wait for the rest of the batch
+        self.assertTrue(batch.is_alive())
+        batch.join()
+
+        self.assertEqual(Decision.Allow, authz_result.decision)
+        stats = engine.stats()
+        self.assertEqual(0, stats["queued_interactive_evaluations"])
+        self.assertEqual(0, stats["queued_batch_evaluations"])
+
+    def test_priority_must_be_interactive_or_batch(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema, max_concurrency=1)
+        self.assertEqual(Decision.Allow, engine.is_authorized(self.view_request, priority="batch").decision)
+        with self.assertRaises(ValueError):
+            engine.is_authorized(self.view_request, priority="urgent")
+
+    def test_requests_left_when_the_deadline_passes_are_not_evaluated(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema)
+        authz_result: AuthzResult = engine.is_authorized(self.view_request, deadline=time.monotonic() - 1)
+        self.assertEqual(Decision.NoDecision, authz_result.decision)
+        self.assertEqual(["deadline_exceeded"], authz_result.diagnostics.error_codes)
+
+        results = engine.is_authorized_batch([self.view_request] * 100_000, deadline=time.monotonic() + 0.1)
+        decisions = [result.decision for result in results]
+        self.assertEqual(Decision.NoDecision, decisions[-1])
+        # the requests evaluated before the deadline come first
+        num_evaluated = decisions.index(Decision.NoDecision)
+        self.assertEqual([Decision.Allow] * num_evaluated, decisions[:num_evaluated])
+        self.assertTrue(all(result.diagnostics.errors == ["deadline exceeded before the request was evaluated"]
+                            for result in results[num_evaluated:]))
+
+    def test_calls_leave_the_queue_when_their_deadline_passes(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema, max_concurrency=1)
+        batch = threading.Thread(target=engine.is_authorized_batch, args=([self.view_request] * 100_000,))
+        batch.start()
+        while engine.stats()["active_evaluations"] == 0:
+            pass
+        authz_result: AuthzResult = engine.is_authorized(self.view_request, deadline=time.monotonic() + 0.01)
+        self.assertTrue(batch.is_alive())
+        batch.join()
+
+        self.assertEqual(Decision.NoDecision, authz_result.decision)
+        self.assertEqual(["deadline exceeded while queued for the engine"], authz_result.diagnostics.errors)
+        self.assertEqual(0, engine.stats()["queued_evaluations"])
+
+    def test_a_deadline_that_has_not_passed_does_not_change_results(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema)
+        results = engine.is_authorized_batch([self.view_request, self.delete_request], deadline=time.monotonic() + 60)
+        self.assertEqual([Decision.Allow, Decision.Allow], [result.decision for result in results])
+        with self.assertRaises(ValueError):
+            engine.is_authorized(self.view_request, deadline=float("nan"))
+
+    def test_engine_without_a_concurrency_limit_reports_no_queue_time(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema)
+        self.assertNotIn("queue_duration_micros", engine.is_authorized(self.view_request).metrics)
+        self.assertNotIn("max_concurrency", engine.stats())
+
+    def test_reload_replaces_policies_and_entities(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema)
+        self.assertEqual(Decision.Allow, engine.is_authorized(self.view_request).decision)
+
+        engine.reload('forbid(principal, action, resource);', self.entities, self.schema)
+        self.assertEqual(Decision.Deny, engine.is_authorized(self.view_request).decision)
+        self.assertEqual(1, engine.stats()["num_policies"])
+        self.assertFalse(engine.stats()["reload_failed"])
+
+    def test_failed_reload_fails_closed_by_default(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema)
+        with self.assertRaises(ValueError):
+            engine.reload("permit(principal, action, resource)", self.entities, self.schema)
+
+        authz_result: AuthzResult = engine.is_authorized(self.view_request)
+        self.assertEqual(Decision.Deny, authz_result.decision)
+        self.assertEqual(["policy_store_unavailable"], authz_result.diagnostics.error_codes)
+        self.assertTrue(authz_result.diagnostics.errors[0].startswith("policy store unavailable: a reload failed 0s ago: "))
+        stats = engine.stats()
+        self.assertTrue(stats["reload_failed"])
+        self.assertTrue(stats["reload_failure"].startswith("policy parse errors"))
+
+        engine.reload(self.policies, self.entities, self.schema)
+        self.assertEqual(Decision.Allow, engine.is_authorized(self.view_request).decision)
+        self.assertNotIn("reload_failure", engine.stats())
+
+    def test_results_with_errors_say_whether_they_are_retryable(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema)
+        timed_out: AuthzResult = engine.is_authorized(self.view_request, deadline=time.monotonic() - 1,
+                                                      include_diagnostics=False)
+        self.assertTrue(timed_out.retryable)
+
+        requests = [self.view_request, dict(self.view_request, context={"unknown": 1}), self.delete_request]
+        page: BatchPage = engine.is_authorized_batch_resumable(requests)
+        self.assertEqual([False, False, False], [result.retryable for result in page.results])
+        self.assertEqual(["request_error"], page.results[1].diagnostics.error_codes)
+        self.assertEqual([], page.retryable)
+
+        with self.assertRaises(ValueError):
+            engine.reload("permit(principal, action, resource)", self.entities, self.schema)
+        page = engine.is_authorized_batch_resumable(requests[1:], include_diagnostics=False)
+        self.assertEqual([True, True], [result.retryable for result in page.results])
+        self.assertEqual([0, 1], page.retryable)
+
+    def test_failed_reload_fails_open_for_annotated_actions(self):
+        policies = self.policies + """
+            @fail_open("true")
+            permit(principal, action == Action::"view", resource) when { false };
+        """
+        engine = CedarEngine(policies, self.entities, self.schema, degradation="fail_open")
+        with self.assertRaises(ValueError):
+            engine.reload(policies, "not json", self.schema)
+
+        view_result, delete_result = engine.is_authorized_batch([dict(self.view_request, principal='User::"bob"'),
+                                                                self.delete_request])
+        self.assertEqual(Decision.Allow, view_result.decision)
+        self.assertEqual(Decision.Deny, delete_result.decision)
+        self.assertEqual(["policy_store_unavailable"], delete_result.diagnostics.error_codes)
+        self.assertEqual(0, delete_result.cache_ttl_seconds)
+
+    def test_failed_reload_may_serve_stale_policies(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema, degradation="serve_stale")
+        with self.assertRaises(ValueError):
+            engine.reload("permit(principal, action, resource)", self.entities, self.schema)
+
+        authz_result: AuthzResult = engine.is_authorized(self.view_request)
+        self.assertEqual(Decision.Allow, authz_result.decision)
+        self.assertEqual([], authz_result.diagnostics.errors)
+        self.assertTrue(any(warning.startswith("serving stale policies: a reload failed 0s ago: ")
+                            for warning in authz_result.diagnostics.warnings))
+
+    def test_cache_ttl_is_the_shortest_of_the_determining_policies(self):
+        policies = """
+            @cache_ttl("5m")
+            permit(principal == User::"alice", action == Action::"view", resource);
+            @cache_ttl("30s")
+            permit(principal, action == Action::"view", resource) when { resource.account.owner == principal };
+            permit(principal, action == Action::"delete", resource) when { context.authenticated };
+        """
+        engine = CedarEngine(policies, self.entities, self.schema)
+        view_result, delete_result, denied_result = engine.is_authorized_batch([
+            self.view_request, self.delete_request, dict(self.delete_request, context={"authenticated": False})])
+        self.assertEqual(Decision.Allow, view_result.decision)
+        self.assertEqual(30, view_result.cache_ttl_seconds)
+        self.assertIsNone(delete_result.cache_ttl_seconds)
+        self.assertIsNone(denied_result.cache_ttl_seconds)
+
+        engine = CedarEngine(policies, self.entities, self.schema, default_cache_ttl="1h")
+        view_result, delete_result, denied_result = engine.is_authorized_batch([
+            self.view_request, self.delete_request, dict(self.delete_request, context={"authenticated": False})])
+        self.assertEqual(30, view_result.cache_ttl_seconds)
+        self.assertEqual(3600, delete_result.cache_ttl_seconds)
+        self.assertEqual(3600, denied_result.cache_ttl_seconds)
+        self.assertEqual(60, CedarEngine(self.policies, self.entities, self.schema, default_cache_ttl=60)
+                         .is_authorized(self.view_request).cache_ttl_seconds)
+
+    def test_invalid_cache_ttls_raise_value_error(self):
+        with self.assertRaises(ValueError) as cm:
+            CedarEngine('@cache_ttl("soon") permit(principal, action, resource);', self.entities, self.schema)
+        self.assertIn("invalid cache_ttl annotation on policy policy0", str(cm.exception))
+        with self.assertRaises(ValueError):
+            CedarEngine(self.policies, self.entities, self.schema, default_cache_ttl="30 seconds")
+
+    def test_resumable_batch_evaluates_in_pages(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema)
+        batch = [self.view_request, self.delete_request, dict(self.view_request, principal='User::"bob"'),
+                 dict(self.delete_request, context={"authenticated": False}), self.view_request]
+        expected = [result.decision for result in engine.is_authorized_batch(batch)]
+
+        pages = []
+        page: BatchPage = engine.is_authorized_batch_resumable(batch, max_requests=2)
+        pages.append(page)
+        while not page.complete:
+            page = engine.is_authorized_batch_resumable(batch, resume_token=page.resume_token, max_requests=2)
+            pages.append(page)
+
+        self.assertEqual([0, 2, 4], [page.start for page in pages])
+        self.assertEqual(expected, [result.decision for page in pages for result in page.results])
+        self.assertIsNone(pages[-1].resume_token)
+
+        # resuming from the same token evaluates the same requests
+        again = engine.is_authorized_batch_resumable(batch, resume_token=pages[0].resume_token, max_requests=2)
+        self.assertEqual(pages[1].start, again.start)
+        self.assertEqual(pages[1].resume_token, again.resume_token)
+
+    def test_resumable_batch_stops_at_the_deadline(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema)
+        page: BatchPage = engine.is_authorized_batch_resumable([self.view_request] * 3, deadline=time.monotonic() - 1)
+        self.assertEqual([], page.results)
+        page = engine.is_authorized_batch_resumable([self.view_request] * 3, resume_token=page.resume_token)
+        self.assertEqual(0, page.start)
+        self.assertEqual([Decision.Allow] * 3, [result.decision for result in page.results])
+        self.assertTrue(page.complete)
+
+    def test_resume_tokens_are_checked_against_the_batch_and_engine(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema)
+        batch = [self.view_request, self.delete_request, self.view_request]
+        token = engine.is_authorized_batch_resumable(batch, max_requests=1).resume_token
+
+        with self.assertRaisesRegex(ValueError, "issued for a different batch"):
+            engine.is_authorized_batch_resumable(batch[1:], resume_token=token)
+        with self.assertRaisesRegex(ValueError, "invalid resume token"):
+            engine.is_authorized_batch_resumable(batch, resume_token="not a token")
+        engine.reload(self.policies + "forbid(principal, action, resource) when { false };", self.entities, self.schema)
+        with self.assertRaisesRegex(ValueError, "reloaded since it was issued"):
+            engine.is_authorized_batch_resumable(batch, resume_token=token)
+
+    def test_resumable_batch_pages_hold_at_least_one_request(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema)
+        for max_requests in [0, -1]:
+            with self.assertRaisesRegex(ValueError, f"invalid max_requests {max_requests}; expected at least 1"):
+                engine.is_authorized_batch_resumable([self.view_request], max_requests=max_requests)
+
+    def test_engine_may_generate_correlation_ids(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema, generate_correlation_ids=True)
+        results = engine.is_authorized_batch([self.view_request, self.view_request,
+                                              dict(self.view_request, correlation_id="given")])
+
+        generated = [result.correlation_id for result in results[:2]]
+        for correlation_id in generated:
+            self.assertRegex(correlation_id, r"^[0-9a-f]{8}-[0-9a-f]{4}-7[0-9a-f]{3}-[89ab][0-9a-f]{3}-[0-9a-f]{12}$")
+        self.assertNotEqual(generated[0], generated[1])
+        self.assertEqual("given", results[2].correlation_id)
+        # UUIDv7s start with the time they were generated
+        self.assertLessEqual(generated[0][:13], engine.is_authorized(self.view_request).correlation_id[:13])
+
+        # resuming a batch doesn't depend on the generated ids
+        page = engine.is_authorized_batch_resumable([self.view_request, self.view_request], max_requests=1)
+        page = engine.is_authorized_batch_resumable([self.view_request, self.view_request],
+                                                    resume_token=page.resume_token)
+        self.assertTrue(page.complete)
+
+        self.assertIsNone(CedarEngine(self.policies, self.entities, self.schema)
+                          .is_authorized(self.view_request).correlation_id)
+
+    def test_invalid_inputs_raise_value_error(self):
+        with self.assertRaises(ValueError):
+            CedarEngine("permit(principal, action, resource)", self.entities, self.schema)
+        with self.assertRaises(ValueError):
+            CedarEngine(self.policies, "not json", self.schema)
+        with self.assertRaises(ValueError):
+            CedarEngine(self.policies, self.entities, self.schema, entity_validation="sometimes")
+        with self.assertRaises(ValueError):
+            CedarEngine(self.policies, self.entities, self.schema, max_concurrency=0)
+        with self.assertRaises(ValueError):
+            CedarEngine(self.policies, self.entities, self.schema, max_queue=10)
+        with self.assertRaises(ValueError):
+            CedarEngine(self.policies, self.entities, self.schema, degradation="fail_sometimes")
+
+
+class UnionOfEnginesTestCase(unittest.TestCase):
+
+    def setUp(self) -> None:
+        super().setUp()
+        self.entities: list = load_file_as_json("resources/sandbox_b/entities.json")
+        self.org = CedarEngine("""
+            forbid(principal, action == Action::"delete", resource)
+            unless { context.authenticated };
+        """, self.entities)
+        self.app = CedarEngine("""
+            permit(principal == User::"alice", action, resource);
+        """, self.entities)
+        self.request = {
+            "principal": "User::\"alice\"",
+            "action": "Action::\"delete\"",
+            "resource": "Photo::\"alice_w2.jpg\"",
+            "context": {"authenticated": True}
+        }
+
+    def test_permit_in_one_engine_allows(self):
+        authz_result: AuthzResult = is_authorized_union({"org": self.org, "app": self.app}, self.request)
+        self.assertEqual(Decision.Allow, authz_result.decision)
+        self.assertEqual(["policy0"], authz_result.diagnostics.reasons)
+        self.assertEqual({"app": ["policy0"]}, authz_result.diagnostics.reason_by_engine)
+
+    def test_forbid_in_any_engine_wins(self):
+        request = dict(self.request, context={"authenticated": False})
+        authz_result: AuthzResult = is_authorized_union({"org": self.org, "app": self.app}, request)
+        self.assertEqual(Decision.Deny, authz_result.decision)
+        self.assertEqual({"org": ["policy0"]}, authz_result.diagnostics.reason_by_engine)
+
+        self.assertEqual(Decision.Allow, self.app.is_authorized(request).decision)
+
+    def test_no_satisfied_policy_denies(self):
+        request = dict(self.request, principal="User::\"stacey\"")
+        authz_result: AuthzResult = is_authorized_union({"org": self.org, "app": self.app}, request)
+        self.assertEqual(Decision.Deny, authz_result.decision)
+        self.assertEqual([], authz_result.diagnostics.reasons)
+        self.assertEqual({}, authz_result.diagnostics.reason_by_engine)
+
+    def test_errors_are_attributed_to_their_engine(self):
+        request = dict(self.request)
+        del request["context"]
+        authz_result: AuthzResult = is_authorized_union({"org": self.org, "app": self.app}, request)
+        # as in a single policy set, the forbid that failed to evaluate is skipped
+        self.assertEqual(Decision.Allow, authz_result.decision)
+        self.assertEqual(1, len(authz_result.diagnostics.errors))
+        self.assertTrue(authz_result.diagnostics.errors[0].startswith("org: "))
+
+        authz_result = is_authorized_union({"org": self.org, "app": self.app}, dict(request, principal="alice"))
+        self.assertEqual(Decision.NoDecision, authz_result.decision)
+
+
+class ResponseTransformTestCase(unittest.TestCase):
+
+    def setUp(self) -> None:
+        super().setUp()
+        self.entities: list = load_file_as_json("resources/sandbox_b/entities_current_syntax.json")
+        self.policies = """
+            permit(principal == User::"alice", action, resource);
+            permit(principal, action == Action::"view", resource);
+        """
+        self.request = {
+            "principal": "User::\"alice\"",
+            "action": "Action::\"view\"",
+            "resource": "Photo::\"alice_w2.jpg\"",
+            "correlation_id": "req-1"
+        }
+
+    def test_transform_is_applied_to_every_result(self):
+        engine = CedarEngine(self.policies, self.entities, response_transform={
+            "enrich": [{"from": "diagnostics.reason", "to": "labels", "map": {"policy0": "alice-admin"},
+                        "default": "unlabeled"},
+                       {"from": "decision", "to": "audit.allowed", "map": {"Allow": True, "Deny": False}}],
+            "add": {"service": "photos"},
+            "rename": {"correlation_id": "request_id"},
+            "drop": ["metrics", "diagnostics.errors"],
+        })
+        results = engine.is_authorized_batch([self.request, dict(self.request, principal="User::\"bob\"")])
+
+        response = results[0].to_dict()
+        self.assertEqual({"decision", "request_id", "fingerprints", "diagnostics", "labels", "audit", "service"},
+                         set(response))
+        self.assertEqual("req-1", response["request_id"])
+        self.assertEqual(["alice-admin", "unlabeled"], sorted(response["labels"]))
+        self.assertEqual({"allowed": True}, response["audit"])
+        self.assertEqual("photos", response["service"])
+        self.assertEqual({"reason"}, set(response["diagnostics"]))
+        self.assertEqual(Decision.Allow, results[0].decision)
+
+        self.assertEqual(["unlabeled"], results[1].to_dict()["labels"])
+
+        # and to a single request's result, which is an AuthzResponse rather than JSON
+        single = engine.is_authorized(self.request).to_dict()
+        self.assertEqual(set(response), set(single))
+        self.assertEqual(["alice-admin", "unlabeled"], sorted(single["labels"]))
+
+    def test_missing_fields_are_left_alone(self):
+        engine = CedarEngine(self.policies, self.entities, response_transform="""
+            {"enrich": [{"from": "no.such.field", "to": "labels", "map": {}}],
+             "rename": {"no_such_field": "renamed"},
+             "drop": ["diagnostics.no_such_field"]}
+        """)
+        response = engine.is_authorized(self.request, include_metrics=False).to_dict()
+        self.assertEqual({"decision", "correlation_id", "fingerprints", "diagnostics"}, set(response))
+
+    def test_invalid_transform_raises_value_error(self):
+        with self.assertRaises(ValueError):
+            CedarEngine(self.policies, self.entities, response_transform={"remove": ["metrics"]})
+        with self.assertRaises(ValueError):
+            CedarEngine(self.policies, self.entities, response_transform="not json")
+
+
+class FakeEngineTestCase(unittest.TestCase):
+
+    def setUp(self) -> None:
+        super().setUp()
+        self.engine = FakeEngine([
+            {"principal": 'User::"alice"', "action": 'Action::"view"', "decision": "Allow", "reason": ["policy0"]},
+            {"resource": 'Photo::"*.raw"', "context": {"authenticated": False}, "errors": ["policy1 failed"]},
+            {"resource": 'Photo::"corrupt.jpg"', "error": "invalid request: bad context"},
+        ])
+        self.request = {
+            "principal": 'User::"alice"',
+            "action": 'Action::"view"',
+            "resource": 'Photo::"a.jpg"',
+            "context": {"authenticated": False},
+            "correlation_id": "abc",
+        }
+
+    def test_fake_engine_decides_with_the_first_matching_rule(self):
+        authz_result = self.engine.is_authorized(self.request)
+
+        self.assertIsInstance(authz_result, AuthzResult)
+        self.assertTrue(authz_result.allowed)
+        self.assertEqual(["policy0"], authz_result.diagnostics.reasons)
+        self.assertEqual("abc", authz_result.correlation_id)
+        self.assertIn("authz_duration_micros", authz_result.metrics)
+
+        bob = dict(self.request, principal='User::"bob"', resource='Photo::"b.raw"')
+        results = self.engine.is_authorized_batch([bob, dict(bob, context={"authenticated": True})])
+        self.assertEqual([Decision.Deny, Decision.Deny], [result.decision for result in results])
+        self.assertEqual(["policy1 failed"], results[0].diagnostics.errors)
+        self.assertEqual([], results[1].diagnostics.errors)
+
+        self.assertTrue(self.engine.is_authorized_fast(self.request))
+        self.assertFalse(self.engine.is_authorized_fast(bob))
+
+    def test_fake_engine_results_have_the_real_engines_shape(self):
+        engine = CedarEngine('permit(principal, action, resource);', [])
+        real = engine.is_authorized(self.request).to_dict()
+        fake = self.engine.is_authorized(self.request).to_dict()
+
+        self.assertEqual(set(real), set(fake))
+        self.assertEqual(set(real["diagnostics"]), set(fake["diagnostics"]))
+        self.assertEqual(set(real["metrics"]), set(fake["metrics"]))
+
+    def test_fake_engine_scripts_errors_and_records_requests(self):
+        authz_result = self.engine.is_authorized(dict(self.request, resource='Photo::"corrupt.jpg"',
+                                                      principal='User::"bob"'))
+
+        self.assertEqual(Decision.NoDecision, authz_result.decision)
+        self.assertEqual(["request_error"], authz_result.diagnostics.error_codes)
+        self.assertEqual(['User::"bob"'], [request["principal"] for request in self.engine.requests])
+        with self.assertRaises(ValueError):
+            FakeEngine([{"decision": "Maybe"}])
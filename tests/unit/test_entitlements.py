@@ -0,0 +1,75 @@
+import unittest
+
+from cedarpy import export_entitlements
+
+
+class ExportEntitlementsTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.schema = {
+            "": {
+                "entityTypes": {
+                    "User": {"memberOfTypes": ["Group"], "shape": {"type": "Record", "attributes": {}}},
+                    "Group": {"shape": {"type": "Record", "attributes": {}}},
+                    "Photo": {"shape": {"type": "Record", "attributes": {}}},
+                },
+                "actions": {
+                    "view": {"appliesTo": {"principalTypes": ["User"], "resourceTypes": ["Photo"]}},
+                    "delete": {"appliesTo": {"principalTypes": ["User"], "resourceTypes": ["Photo"]}},
+                },
+            }
+        }
+        self.policies = """
+            permit(principal in Group::"friends", action == Action::"view", resource);
+            permit(principal == User::"alice", action, resource == Photo::"a.jpg");
+        """
+        self.entities = [
+            {"uid": {"type": "User", "id": "alice"}, "attrs": {}, "parents": []},
+            {"uid": {"type": "User", "id": "bob smith"}, "attrs": {}, "parents": [{"type": "Group", "id": "friends"}]},
+            {"uid": {"type": "Group", "id": "friends"}, "attrs": {}, "parents": []},
+            {"uid": {"type": "Photo", "id": "a.jpg"}, "attrs": {}, "parents": []},
+        ]
+
+    def test_allowed_requests_are_entitlements(self):
+        exported = export_entitlements(self.policies, self.entities, self.schema)
+
+        self.assertEqual([
+            {"subject": {"type": "User", "id": "alice"}, "relation": "view",
+             "object": {"type": "Photo", "id": "a.jpg"}, "policies": ["policy1"]},
+            {"subject": {"type": "User", "id": "bob smith"}, "relation": "view",
+             "object": {"type": "Photo", "id": "a.jpg"}, "policies": ["policy0"]},
+            {"subject": {"type": "User", "id": "alice"}, "relation": "delete",
+             "object": {"type": "Photo", "id": "a.jpg"}, "policies": ["policy1"]},
+        ], exported["entitlements"])
+        # 2 users and 1 photo, with both actions
+        self.assertEqual(4, exported["requests_checked"])
+        self.assertEqual(0, exported["errored"])
+
+    def test_zanzibar_tuples_encode_the_separators_of_ids(self):
+        exported = export_entitlements(self.policies, self.entities, self.schema, actions=['Action::"view"'],
+                                       output_format="zanzibar")
+
+        self.assertEqual("Photo:a.jpg#view@User:alice\n"
+                         "Photo:a.jpg#view@User:bob%20smith\n", exported)
+
+    def test_without_a_schema_the_actions_are_required(self):
+        with self.assertRaises(ValueError) as cm:
+            export_entitlements(self.policies, self.entities)
+        self.assertIn("the actions to export are required without a schema", str(cm.exception))
+
+        exported = export_entitlements(self.policies, self.entities, actions=['Action::"view"'],
+                                       principal_types=["User"], resource_types=["Photo"])
+        self.assertEqual(2, len(exported["entitlements"]))
+
+    def test_invalid_arguments_raise(self):
+        with self.assertRaises(ValueError) as cm:
+            export_entitlements(self.policies, self.entities, self.schema, actions=['Action::"share"'])
+        self.assertIn('Action::"share" is not declared in the schema', str(cm.exception))
+
+        with self.assertRaises(ValueError) as cm:
+            export_entitlements(self.policies, self.entities, self.schema, output_format="csv")
+        self.assertIn("invalid output format 'csv'", str(cm.exception))
+
+        with self.assertRaises(ValueError) as cm:
+            export_entitlements(self.policies, self.entities, self.schema, max_requests=3)
+        self.assertIn("more than max_requests (3)", str(cm.exception))
@@ -0,0 +1,193 @@
+import json
+import unittest
+
+from cedarpy import is_authorized, AuthzResult, Decision
+
+from unit import load_file_as_json, load_file_as_str
+
+
+class EntityValidationTestCase(unittest.TestCase):
+
+    def setUp(self) -> None:
+        super().setUp()
+        self.policies = """
+            permit(
+                principal == User::"alice",
+                action == Action::"view",
+                resource
+            );
+        """
+        self.schema = load_file_as_str("resources/sandbox_b/schema.json")
+        self.entities: list = load_file_as_json("resources/sandbox_b/entities_current_syntax.json")
+        self.request = {
+            "principal": "User::\"alice\"",
+            "action": "Action::\"view\"",
+            "resource": "Photo::\"alice_w2.jpg\"",
+            "context": {"authenticated": False}
+        }
+
+        # alice's jobLevel should be a Long according to the schema
+        self.nonconforming_entities = json.loads(json.dumps(self.entities))
+        self.nonconforming_entities[0]["attrs"]["jobLevel"] = "five"
+
+        self.invalid_schema = json.dumps({"": {"entityTypes": {"User": {"memberOfTypes": ["DoesNotExist"]}},
+                                               "actions": {}}})
+
+    def test_conforming_entities_are_loaded_in_every_mode(self):
+        for entity_validation in ["strict", "warn", "permissive", "none"]:
+            authz_result: AuthzResult = is_authorized(self.request, self.policies, self.entities, self.schema,
+                                                      entity_validation=entity_validation)
+            self.assertEqual(Decision.Allow, authz_result.decision, msg=f"mode: {entity_validation}")
+            self.assertEqual([], authz_result.diagnostics.errors)
+            self.assertEqual([], authz_result.diagnostics.warnings)
+
+    def test_strict_rejects_nonconforming_entities(self):
+        authz_result: AuthzResult = is_authorized(self.request, self.policies, self.nonconforming_entities,
+                                                  self.schema, entity_validation="strict")
+        self.assertEqual(Decision.NoDecision, authz_result.decision)
+        self.assertEqual(1, len(authz_result.diagnostics.errors))
+        self.assertIn("failed to parse entities", authz_result.diagnostics.errors[0])
+
+    def test_warn_loads_nonconforming_entities_with_a_warning(self):
+        authz_result: AuthzResult = is_authorized(self.request, self.policies, self.nonconforming_entities,
+                                                  self.schema, entity_validation="warn")
+        self.assertEqual(Decision.Allow, authz_result.decision)
+        self.assertEqual(1, len(authz_result.diagnostics.warnings))
+        self.assertIn("entities were loaded without validation", authz_result.diagnostics.warnings[0])
+
+    def test_none_loads_nonconforming_entities_without_a_warning(self):
+        authz_result: AuthzResult = is_authorized(self.request, self.policies, self.nonconforming_entities,
+                                                  self.schema, entity_validation="none")
+        self.assertEqual(Decision.Allow, authz_result.decision)
+        self.assertEqual([], authz_result.diagnostics.warnings)
+
+    def test_invalid_schema_handling_depends_on_mode(self):
+        authz_result: AuthzResult = is_authorized(self.request, self.policies, self.entities,
+                                                  self.invalid_schema, entity_validation="strict")
+        self.assertEqual(Decision.NoDecision, authz_result.decision)
+        self.assertEqual(["schema_error"], authz_result.diagnostics.error_codes)
+        self.assertEqual(1, len(authz_result.diagnostics.errors))
+        self.assertTrue(authz_result.diagnostics.errors[0].startswith("invalid schema: "))
+        parse_error = authz_result.diagnostics.errors[0]
+
+        authz_result = is_authorized(self.request, self.policies, self.entities,
+                                     self.invalid_schema, entity_validation="permissive")
+        self.assertEqual(Decision.NoDecision, authz_result.decision)
+        self.assertEqual([parse_error], authz_result.diagnostics.errors)
+
+        for entity_validation in ["warn", "none"]:
+            authz_result = is_authorized(self.request, self.policies, self.entities,
+                                         self.invalid_schema, entity_validation=entity_validation)
+            self.assertEqual(Decision.Allow, authz_result.decision)
+            self.assertEqual([f"the schema was ignored: {parse_error}"], authz_result.diagnostics.warnings)
+
+    def test_invalid_mode_raises_value_error(self):
+        with self.assertRaises(ValueError):
+            is_authorized(self.request, self.policies, self.entities, self.schema, entity_validation="sometimes")
+
+    def test_permissive_drops_undeclared_entity_attributes(self):
+        entities = json.loads(json.dumps(self.entities))
+        entities[0]["attrs"]["badgeColor"] = "blue"
+
+        authz_result: AuthzResult = is_authorized(self.request, self.policies, entities, self.schema,
+                                                  entity_validation="strict")
+        self.assertEqual(Decision.NoDecision, authz_result.decision)
+
+        authz_result = is_authorized(self.request, self.policies, entities, self.schema,
+                                     entity_validation="permissive")
+        self.assertEqual(Decision.Allow, authz_result.decision)
+        self.assertEqual([], authz_result.diagnostics.errors)
+        self.assertEqual(['dropped attribute not declared in the schema: User::"alice".badgeColor'],
+                         authz_result.diagnostics.warnings)
+
+    def test_permissive_still_validates_declared_entity_attributes(self):
+        authz_result: AuthzResult = is_authorized(self.request, self.policies, self.nonconforming_entities,
+                                                  self.schema, entity_validation="permissive")
+        self.assertEqual(Decision.NoDecision, authz_result.decision)
+
+    def test_permissive_drops_undeclared_context_attributes(self):
+        policies = """
+            permit(principal, action == Action::"delete", resource)
+            when { context.authenticated };
+        """
+        request = {
+            "principal": "User::\"alice\"",
+            "action": "Action::\"delete\"",
+            "resource": "Photo::\"alice_w2.jpg\"",
+            "context": {"authenticated": True, "sessionId": "abc123"}
+        }
+
+        authz_result: AuthzResult = is_authorized(request, policies, self.entities, self.schema,
+                                                  entity_validation="strict")
+        self.assertEqual(Decision.NoDecision, authz_result.decision)
+
+        authz_result = is_authorized(request, policies, self.entities, self.schema,
+                                     entity_validation="permissive")
+        self.assertEqual(Decision.Allow, authz_result.decision)
+        self.assertEqual(['dropped attribute not declared in the schema: context.sessionId'],
+                         authz_result.diagnostics.warnings)
+
+    def test_unknown_request_entities_handling_depends_on_mode(self):
+        request = dict(self.request, resource="Photo::\"missing.jpg\"")
+
+        authz_result: AuthzResult = is_authorized(request, self.policies, self.entities, self.schema)
+        self.assertEqual(Decision.Allow, authz_result.decision)
+        self.assertEqual([], authz_result.diagnostics.warnings)
+
+        authz_result = is_authorized(request, self.policies, self.entities, self.schema, unknown_entities="warn")
+        self.assertEqual(Decision.Allow, authz_result.decision)
+        self.assertEqual(['the request\'s resource Photo::"missing.jpg" is not in the entities'],
+                         authz_result.diagnostics.warnings)
+
+        authz_result = is_authorized(request, self.policies, self.entities, self.schema, unknown_entities="error")
+        self.assertEqual(Decision.NoDecision, authz_result.decision)
+        self.assertEqual(1, len(authz_result.diagnostics.errors))
+        self.assertIn('the request\'s resource Photo::"missing.jpg" is not in the entities',
+                      authz_result.diagnostics.errors[0])
+
+    def test_known_request_entities_are_not_reported(self):
+        authz_result: AuthzResult = is_authorized(self.request, self.policies, self.entities, self.schema,
+                                                  unknown_entities="error")
+        self.assertEqual(Decision.Allow, authz_result.decision)
+        self.assertEqual([], authz_result.diagnostics.warnings)
+
+    def test_invalid_unknown_entities_mode_raises_value_error(self):
+        with self.assertRaises(ValueError):
+            is_authorized(self.request, self.policies, self.entities, self.schema, unknown_entities="sometimes")
+
+    def test_missing_entities_are_reported_when_requested(self):
+        policies = 'permit(principal, action, resource) when { resource.owner.department == "Sales" };'
+        entities = [
+            {"uid": {"__expr": "User::\"alice\""}, "attrs": {}, "parents": []},
+            {"uid": {"__expr": "Photo::\"beach.jpg\""},
+             "attrs": {"owner": {"__entity": {"type": "User", "id": "stacey"}}},
+             "parents": []},
+        ]
+        request = {"principal": "User::\"alice\"", "action": "Action::\"view\"", "resource": "Photo::\"beach.jpg\""}
+
+        authz_result: AuthzResult = is_authorized(request, policies, entities)
+        self.assertEqual(Decision.Deny, authz_result.decision)
+        self.assertEqual([], authz_result.diagnostics.missing_entities)
+        self.assertNotIn("missing_entities", authz_result._authz_resp["diagnostics"])
+
+        # without a schema, actions aren't entities, so only the entities the policies looked up are missing
+        authz_result = is_authorized(request, policies, entities, include_missing_entities=True)
+        self.assertEqual(Decision.Deny, authz_result.decision)
+        self.assertEqual(['User::"stacey"'], authz_result.diagnostics.missing_entities)
+
+        policies += 'permit(principal, action in Action::"read", resource);'
+        authz_result = is_authorized(request, policies, entities, include_missing_entities=True)
+        self.assertEqual(['User::"stacey"'], authz_result.diagnostics.missing_entities)
+
+    def test_missing_entities_include_actions_the_schema_does_not_declare(self):
+        # a request without a context, which would be checked against the action's
+        request = {"principal": 'User::"alice"', "action": 'Action::"undeclared"', "resource": 'Photo::"alice_w2.jpg"'}
+        authz_result: AuthzResult = is_authorized(request, self.policies, self.entities, self.schema,
+                                                  include_missing_entities=True)
+        self.assertEqual(['Action::"undeclared"'], authz_result.diagnostics.missing_entities)
+
+    def test_no_missing_entities_is_reported_as_empty(self):
+        authz_result: AuthzResult = is_authorized(self.request, self.policies, self.entities, self.schema,
+                                                  include_missing_entities=True)
+        self.assertEqual(Decision.Allow, authz_result.decision)
+        self.assertEqual([], authz_result._authz_resp["diagnostics"]["missing_entities"])
@@ -0,0 +1,57 @@
+import json
+import unittest
+
+from cedarpy import evaluate_expression, EntityStore
+
+
+class EvaluateExpressionTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.entities = [
+            {"uid": {"type": "User", "id": "alice"},
+             "attrs": {"level": 7, "manager": {"__entity": {"type": "User", "id": "carol"}}, "tags": ["a", "b"]},
+             "parents": [{"type": "Group", "id": "staff"}]},
+            {"uid": {"type": "User", "id": "carol"}, "attrs": {"level": 9}, "parents": []},
+            {"uid": {"type": "Group", "id": "staff"}, "attrs": {}, "parents": []},
+            {"uid": {"type": "Doc", "id": "plan"}, "attrs": {}, "parents": []},
+        ]
+        self.request = {"principal": 'User::"alice"', "action": 'Action::"view"', "resource": 'Doc::"plan"',
+                        "context": {"authenticated": True, "ip": {"__extn": {"fn": "ip", "arg": "10.0.0.1"}}}}
+
+    def test_a_condition_evaluates_to_its_value(self):
+        self.assertEqual("true", evaluate_expression("principal.level > 5 && context.authenticated",
+                                                     self.request, self.entities))
+        self.assertEqual("false", evaluate_expression('principal in Group::"admins"', self.request, self.entities))
+        self.assertEqual("16", evaluate_expression("principal.level + principal.manager.level",
+                                                   self.request, EntityStore(self.entities)))
+
+    def test_entities_sets_and_records_take_the_entity_attribute_format(self):
+        self.assertEqual({"__entity": {"type": "User", "id": "carol"}},
+                         json.loads(evaluate_expression("principal.manager", self.request, self.entities)))
+        self.assertEqual(["a", "b"], json.loads(evaluate_expression("principal.tags", self.request, self.entities)))
+        self.assertEqual({"doc": {"__entity": {"type": "Doc", "id": "plan"}}, "name": "plan"},
+                         json.loads(evaluate_expression('{doc: resource, name: "plan"}', self.request,
+                                                        self.entities)))
+
+    def test_extension_values_take_the_extension_format(self):
+        self.assertEqual({"__extn": {"fn": "ip", "arg": "10.0.0.1/32"}},
+                         json.loads(evaluate_expression("context.ip", self.request, self.entities)))
+        self.assertEqual({"__extn": {"fn": "ip", "arg": "::1/128"}},
+                         json.loads(evaluate_expression('ip("::1")', self.request, self.entities)))
+        for arg in ["1.0500", "12.3400", "-2.0000", "3.0001"]:
+            self.assertEqual({"__extn": {"fn": "decimal", "arg": arg}},
+                             json.loads(evaluate_expression(f'decimal("{arg}")', self.request, self.entities)))
+        self.assertEqual([{"__extn": {"fn": "decimal", "arg": "1.5000"}}],
+                         json.loads(evaluate_expression('[decimal("1.5")]', self.request, self.entities)))
+        self.assertEqual("true", evaluate_expression('context.ip.isInRange(ip("10.0.0.0/8"))', self.request,
+                                                     self.entities))
+
+    def test_invalid_expressions_raise(self):
+        with self.assertRaises(ValueError) as cm:
+            evaluate_expression("principal.level >", self.request, self.entities)
+        self.assertIn("failed to parse expression", str(cm.exception))
+
+        with self.assertRaises(ValueError) as cm:
+            evaluate_expression("principal.department == \"eng\"", self.request, self.entities)
+        self.assertEqual("policy_evaluation_error", cm.exception.code)
+        self.assertIn("department", str(cm.exception))
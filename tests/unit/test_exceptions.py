@@ -0,0 +1,96 @@
+import unittest
+
+from cedarpy import (compile_policies, compile_schema, format_policies, policies_to_json, is_authorized,
+                     is_authorized_batch, is_authorized_strict, CedarEngine, Decision, EntityStore, CedarError,
+                     PolicyParseError, SchemaParseError, EntityError, RequestError, ValidationError,
+                     InvalidArgumentError)
+
+
+class ExceptionsTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.request = {"principal": 'User::"alice"', "action": 'Action::"view"', "resource": 'Photo::"a"',
+                        "context": {}}
+
+    def test_errors_raise_the_class_of_their_code(self):
+        for raise_error, error_class, code in [
+            (lambda: compile_policies("permit("), PolicyParseError, "policy_parse_error"),
+            (lambda: compile_schema('{"": {"entityTypes": {"User": {"memberOfTypes": ["Group"]}}, "actions": {}}}'),
+             SchemaParseError, "schema_error"),
+            (lambda: EntityStore('[{"uid": 1}]'), EntityError, "entities_error"),
+            (lambda: is_authorized(self.request, "", [], entity_validation="lenient"), InvalidArgumentError,
+             "invalid_argument"),
+            (lambda: is_authorized({"action": 'Action::"view"', "resource": 'Photo::"a"'}, "", []), RequestError,
+             "request_error"),
+        ]:
+            with self.subTest(error_class=error_class.__name__), self.assertRaises(error_class) as cm:
+                raise_error()
+            self.assertEqual(code, cm.exception.code)
+
+        for error_class in [PolicyParseError, SchemaParseError, EntityError, RequestError, ValidationError,
+                            InvalidArgumentError]:
+            self.assertTrue(issubclass(error_class, CedarError))
+        # callers catching ValueError, as before, still catch them
+        self.assertTrue(issubclass(CedarError, ValueError))
+        self.assertEqual("cedarpy.PolicyParseError", f"{PolicyParseError.__module__}.{PolicyParseError.__name__}")
+
+    def test_policy_parse_errors_are_located(self):
+        policy = 'permit(principal, action resource);'
+        policies = 'permit(principal, action, resource);\n' + policy
+        for text, raise_error in [(policies, compile_policies),
+                                  (policies, format_policies),
+                                  (policies, policies_to_json),
+                                  (policy, compile_policies("").add_policy)]:
+            with self.subTest(text=text, raise_error=raise_error), self.assertRaises(PolicyParseError) as cm:
+                raise_error(text)
+            span = cm.exception.span
+            self.assertEqual("resource", text[span["start"]:span["end"]])
+            self.assertEqual((text.count("\n") + 1, 26), (span["line"], span["column"]))
+            self.assertEqual(policy, cm.exception.snippet)
+
+    def test_invalid_schema_json_is_located(self):
+        schema = '{"": {\n  "entityTypes": {,}}}'
+        with self.assertRaises(SchemaParseError) as cm:
+            compile_schema(schema)
+        self.assertEqual('  "entityTypes": {,}}}', cm.exception.snippet)
+        self.assertEqual((2, 19), (cm.exception.span["line"], cm.exception.span["column"]))
+        self.assertEqual(",", schema[cm.exception.span["start"]])
+
+    def test_errors_without_a_location_have_none(self):
+        with self.assertRaises(SchemaParseError) as cm:
+            compile_schema('{"": {"entityTypes": {"User": {"memberOfTypes": ["Group"]}}, "actions": {}}}')
+        self.assertIsNone(cm.exception.span)
+        self.assertIsNone(cm.exception.snippet)
+
+        with self.assertRaises(InvalidArgumentError) as cm:
+            is_authorized(self.request, "", [], unknown_entities="maybe")
+        self.assertEqual((None, None), (cm.exception.span, cm.exception.snippet))
+
+    def test_requests_without_a_principal_action_or_resource_are_rejected(self):
+        for missing in ["principal", "action", "resource"]:
+            request = {key: value for key, value in self.request.items() if key != missing}
+            with self.subTest(missing=missing), self.assertRaises(RequestError) as cm:
+                is_authorized_batch([self.request, request], "", [])
+            self.assertEqual(f"invalid request: the request has no {missing}", str(cm.exception))
+
+    def test_strict_variants_raise_the_errors_of_undecided_requests(self):
+        policies = 'permit(principal, action, resource);'
+        self.assertEqual(Decision.Allow, is_authorized_strict(self.request, policies, []).decision)
+        self.assertEqual(Decision.Allow, CedarEngine(policies, []).is_authorized_strict(self.request).decision)
+
+        with self.assertRaises(EntityError) as cm:
+            is_authorized_strict(self.request, policies, '[{"uid": 1}]')
+        self.assertEqual("entities_error", cm.exception.code)
+        self.assertIn("failed to parse entities", str(cm.exception))
+        self.assertEqual((None, None), (cm.exception.span, cm.exception.snippet))
+
+        with self.assertRaises(EntityError) as cm:
+            CedarEngine(policies, [], unknown_entities="error").is_authorized_strict(self.request)
+        self.assertEqual("unknown_entity", cm.exception.code)
+
+        # a request's evaluation errors are raised only when they leave it undecided
+        policies = 'permit(principal, action, resource) when { context.missing };'
+        self.assertEqual(Decision.Deny, is_authorized_strict(self.request, policies, []).decision)
+        with self.assertRaises(CedarError) as cm:
+            is_authorized_strict(self.request, policies, [], policy_errors="propagate")
+        self.assertEqual("policy_evaluation_error", cm.exception.code)
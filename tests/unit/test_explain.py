@@ -0,0 +1,98 @@
+import unittest
+
+from cedarpy import explain_decision, Decision
+
+from unit import load_file_as_json, load_file_as_str
+
+
+class ExplainDecisionTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.schema = load_file_as_str("resources/sandbox_b/schema.json")
+        self.entities: list = load_file_as_json("resources/sandbox_b/entities.json")
+        self.request = {
+            "principal": "User::\"alice\"",
+            "action": "Action::\"edit\"",
+            "resource": "Photo::\"alice_w2.jpg\"",
+            "context": {"authenticated": False}
+        }
+        self.policies = """
+            permit(principal, action == Action::"edit", resource) when { principal.jobLevel > 10 };
+            permit(
+                principal in UserGroup::"editors",
+                action == Action::"edit",
+                resource
+            ) when { context.authenticated == true && principal.department == "Sales" };
+        """
+
+    def test_reports_unsatisfied_parts_of_candidate_permits(self):
+        explanation = explain_decision(self.request, self.policies, self.entities)
+
+        self.assertEqual(Decision.Deny, explanation.decision)
+        self.assertEqual(Decision.Allow, explanation.expected)
+        self.assertEqual([], explanation.determining_policies)
+        self.assertEqual(["policy0", "policy1"], [permit["id"] for permit in explanation.candidate_permits])
+        self.assertEqual([{"kind": "when", "condition": 'principal.jobLevel > 10'}],
+                         explanation.candidate_permits[0]["unsatisfied"])
+        self.assertEqual([{"kind": "scope", "condition": 'principal in UserGroup::"editors"'},
+                          {"kind": "when", "condition": 'context.authenticated == true'},
+                          {"kind": "when", "condition": 'principal.department == "Sales"'}],
+                         explanation.candidate_permits[1]["unsatisfied"])
+        # suggestions are only made with a schema
+        self.assertIsNone(explanation.suggestion)
+
+    def test_suggests_changes_that_allow_the_request(self):
+        explanation = explain_decision(self.request, self.policies, self.entities, self.schema)
+
+        # the nearest permit has no suggested change for its `>` condition, so the next one is used
+        self.assertEqual({"policy_id": "policy1",
+                          "changes": ['add UserGroup::"editors" to the parents of principal User::"alice"',
+                                      'set context.authenticated to true',
+                                      'set principal.department to "Sales"']},
+                         explanation.suggestion)
+
+    def test_suggests_a_different_request(self):
+        policies = 'permit(principal == User::"bob", action in [Action::"view", Action::"comment"], resource);'
+
+        explanation = explain_decision(self.request, policies, self.entities, self.schema)
+
+        self.assertEqual({"policy_id": "policy0",
+                          "changes": ['use principal User::"bob"', 'use action Action::"comment"']},
+                         explanation.suggestion)
+
+    def test_reports_determining_forbid(self):
+        policies = """
+            permit(principal == User::"alice", action == Action::"edit", resource);
+            forbid(principal, action, resource) unless { context.authenticated };
+        """
+
+        explanation = explain_decision(self.request, policies, self.entities, self.schema)
+
+        self.assertEqual(Decision.Deny, explanation.decision)
+        self.assertEqual([{"id": "policy1", "effect": "forbid",
+                           "policy": "forbid(principal, action, resource) unless { context.authenticated };"}],
+                         explanation.determining_policies)
+        self.assertEqual([], explanation.candidate_permits)
+        self.assertIsNone(explanation.suggestion)
+
+    def test_reports_evaluation_errors(self):
+        policies = 'permit(principal, action, resource) when { principal.nickname == "ali" };'
+
+        explanation = explain_decision(self.request, policies, self.entities)
+
+        unsatisfied = explanation.candidate_permits[0]["unsatisfied"]
+        self.assertEqual(1, len(unsatisfied))
+        self.assertIn("nickname", unsatisfied[0]["error"])
+
+    def test_expected_deny(self):
+        policies = 'permit(principal == User::"alice", action == Action::"edit", resource);'
+
+        explanation = explain_decision(self.request, policies, self.entities, self.schema, expected=Decision.Deny)
+
+        self.assertEqual(Decision.Allow, explanation.decision)
+        self.assertEqual(["policy0"], [policy["id"] for policy in explanation.determining_policies])
+        self.assertIsNone(explanation.suggestion)
+
+    def test_invalid_expected_decision_raises_value_error(self):
+        with self.assertRaises(ValueError):
+            explain_decision(self.request, self.policies, self.entities, expected="Maybe")
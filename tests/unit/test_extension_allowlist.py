@@ -0,0 +1,81 @@
+import unittest
+
+from cedarpy import CedarEngine, Decision, ValidationError, validate_policies
+
+
+class ExtensionAllowlistTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.allowed = ["decimal", "lessThan"]
+        self.decimal_policy = ('permit(principal, action, resource) '
+                               'when { context.amount.lessThan(decimal("100.00")) };')
+        self.ip_policy = 'forbid(principal, action, resource) unless { context.source.isInRange(ip("10.0.0.0/8")) };'
+        self.schema = {"": {
+            "entityTypes": {"User": {}, "Doc": {}},
+            "actions": {"view": {"appliesTo": {
+                "principalTypes": ["User"],
+                "resourceTypes": ["Doc"],
+                "context": {"type": "Record", "attributes": {
+                    "amount": {"type": "Extension", "name": "decimal"},
+                    "source": {"type": "Extension", "name": "ipaddr"},
+                }},
+            }}},
+        }}
+        self.entities = [
+            {"uid": {"type": "User", "id": "alice"}, "attrs": {}, "parents": []},
+            {"uid": {"type": "Doc", "id": "plan"}, "attrs": {}, "parents": []},
+        ]
+        self.request = {"principal": 'User::"alice"', "action": 'Action::"view"', "resource": 'Doc::"plan"',
+                        "context": {"amount": {"__extn": {"fn": "decimal", "arg": "12.50"}},
+                                    "source": {"__extn": {"fn": "ip", "arg": "10.1.2.3"}}}}
+
+    def test_validation_reports_disallowed_calls(self):
+        policies = self.decimal_policy + "\n" + self.ip_policy
+        self.assertTrue(validate_policies(policies, self.schema)["valid"])
+
+        report = validate_policies(policies, self.schema, allowed_extension_functions=self.allowed)
+        self.assertFalse(report["valid"])
+        errors = [(error["policy_id"], error["kind"], error["message"]) for error in report["errors"]]
+        self.assertEqual([("policy1", "disallowed_extension_function", "the extension function 'isInRange' isn't allowed"),
+                          ("policy1", "disallowed_extension_function", "the extension function 'ip' isn't allowed")],
+                         errors)
+        span = report["errors"][1]["span"]
+        self.assertEqual('ip("10.0.0.0/8")', policies[span["start"]:span["end"]])
+
+    def test_engine_rejects_disallowed_calls_when_loading(self):
+        engine = CedarEngine(self.decimal_policy, self.entities, self.schema, allowed_extension_functions=self.allowed)
+        self.assertEqual(Decision.Allow, engine.is_authorized(self.request).decision)
+
+        with self.assertRaises(ValidationError) as cm:
+            CedarEngine(self.ip_policy, self.entities, self.schema, allowed_extension_functions=self.allowed)
+        self.assertIn("disallowed extension functions: policy0 calls 'isInRange', policy0 calls 'ip'; "
+                      "the allowed extension functions are: decimal, lessThan", str(cm.exception))
+
+        with self.assertRaises(ValidationError):
+            CedarEngine(self.decimal_policy, self.entities, self.schema, allowed_extension_functions=[])
+
+    def test_reloads_and_updates_are_checked(self):
+        engine = CedarEngine(self.decimal_policy, self.entities, self.schema, allowed_extension_functions=self.allowed)
+
+        update = engine.begin_update()
+        update.set_policies(self.decimal_policy + "\n" + self.ip_policy)
+        update.set_entities(self.entities)
+        update.set_schema(self.schema)
+        with self.assertRaises(ValidationError):
+            update.commit()
+        self.assertFalse(update.committed)
+        self.assertEqual(1, engine.stats()["num_policies"])
+
+        with self.assertRaises(ValidationError):
+            engine.reload(self.ip_policy, self.entities, self.schema)
+
+    def test_invalid_allowlists_and_guardrails(self):
+        with self.assertRaises(ValueError) as cm:
+            validate_policies(self.decimal_policy, self.schema, allowed_extension_functions=["datetime"])
+        self.assertIn("'datetime' isn't a Cedar extension function", str(cm.exception))
+
+        with self.assertRaises(ValidationError) as cm:
+            CedarEngine(self.decimal_policy, self.entities, self.schema, guardrails=self.ip_policy,
+                        allowed_extension_functions=self.allowed)
+        self.assertIn("invalid guardrails: disallowed extension functions: guardrail0 calls 'isInRange'",
+                      str(cm.exception))
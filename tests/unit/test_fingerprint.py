@@ -0,0 +1,60 @@
+import re
+import unittest
+
+from cedarpy import is_authorized, is_authorized_batch, CedarEngine, Decision
+
+from unit import load_file_as_json, load_file_as_str
+
+
+class FingerprintTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.schema = load_file_as_str("resources/sandbox_b/schema.json")
+        self.entities: list = load_file_as_json("resources/sandbox_b/entities.json")
+        self.policies = 'permit(principal == User::"alice", action == Action::"view", resource);'
+        self.request = {
+            "principal": 'User::"alice"',
+            "action": 'Action::"view"',
+            "resource": 'Photo::"alice_w2.jpg"',
+            "context": {"authenticated": True},
+        }
+
+    def test_fingerprints_are_the_xxh64_of_the_source(self):
+        # pinned, since fingerprints are compared across cedarpy versions
+        for policies, fingerprint in [("", "ef46db3751d8e999"),
+                                      ("permit(principal, action, resource);", "8a0d412d31af8282"),
+                                      ("permit(principal, action, resource) when { context.level > 5 };",
+                                       "a2d0a5e091744178")]:
+            self.assertEqual(fingerprint, is_authorized(self.request, policies, []).fingerprints["policy_set"])
+
+    def test_results_identify_the_policies(self):
+        authz_result = is_authorized(self.request, self.policies, self.entities, self.schema)
+        self.assertEqual(Decision.Allow, authz_result.decision)
+        self.assertEqual({"policy_set"}, set(authz_result.fingerprints))
+        self.assertRegex(authz_result.fingerprints["policy_set"], re.compile(r"^[0-9a-f]{16}$"))
+
+        # the same policies have the same fingerprint, whatever else the request has
+        authz_results = is_authorized_batch([self.request, dict(self.request, principal='User::"bob"')],
+                                            self.policies, [], entity_validation="none")
+        self.assertEqual([authz_result.fingerprints] * 2, [r.fingerprints for r in authz_results])
+
+        changed = is_authorized(self.request, self.policies + "\nforbid(principal, action, resource) when { false };",
+                                self.entities, self.schema)
+        self.assertNotEqual(authz_result.fingerprints["policy_set"], changed.fingerprints["policy_set"])
+
+    def test_engine_results_identify_the_entities_and_schema(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema)
+        authz_result = engine.is_authorized(self.request)
+        self.assertEqual({"policy_set", "entities", "schema"}, set(authz_result.fingerprints))
+        self.assertEqual(engine.stats()["fingerprints"], authz_result.fingerprints)
+        self.assertEqual(is_authorized(self.request, self.policies, self.entities, self.schema).fingerprints["policy_set"],
+                         authz_result.fingerprints["policy_set"])
+
+        engine.reload(self.policies, self.entities[1:], self.schema)
+        reloaded = engine.is_authorized(self.request).fingerprints
+        self.assertEqual(authz_result.fingerprints["policy_set"], reloaded["policy_set"])
+        self.assertEqual(authz_result.fingerprints["schema"], reloaded["schema"])
+        self.assertNotEqual(authz_result.fingerprints["entities"], reloaded["entities"])
+
+        without_schema = CedarEngine(self.policies, self.entities).is_authorized(self.request)
+        self.assertEqual({"policy_set", "entities"}, set(without_schema.fingerprints))
@@ -1,8 +1,9 @@
+import json
 import unittest
 
 from textwrap import dedent
 
-from cedarpy import format_policies
+from cedarpy import format_policies, FormatterConfig
 
 class FormatPolicyTestCase(unittest.TestCase):
     def setUp(self) -> None:
@@ -50,3 +51,68 @@ class FormatPolicyTestCase(unittest.TestCase):
             self.fail("should have failed to parse")
         except ValueError as e:
             pass
+
+    def test_policy_formatting_with_config(self):
+        input_policy = 'permit(principal, action == Action::"edit", resource) when { resource.owner == principal };'
+        expect_result = dedent("""
+            permit (
+                principal,
+                action == Action::"edit",
+                resource
+            )
+            when { resource.owner == principal };
+        """).strip()
+
+        config = FormatterConfig(indent_width=4)
+        self.assertEqual({"line_width": 80, "indent_width": 4}, config.to_dict())
+        self.assertEqual(expect_result, format_policies(input_policy, config=config))
+        self.assertEqual(expect_result, format_policies(input_policy, config={"indent_width": 4}))
+        # arguments override the config
+        self.assertEqual(format_policies(input_policy), format_policies(input_policy, indent_width=2, config=config))
+        self.assertEqual(FormatterConfig(), FormatterConfig(line_width=80, indent_width=2))
+
+    def test_invalid_formatter_options_raise_value_error(self):
+        for options in [dict(indent_width=-1), dict(line_width=0), dict(line_width=10_001), dict(indent_width=65),
+                        dict(line_width=4, indent_width=8)]:
+            with self.subTest(options=options):
+                with self.assertRaises(ValueError):
+                    FormatterConfig(**options)
+                with self.assertRaises(ValueError):
+                    format_policies("permit(principal, action, resource);", **options)
+        with self.assertRaises(ValueError):
+            format_policies("permit(principal, action, resource);", config={"comments": "keep"})
+
+    def test_json_policies_get_formatted(self):
+        est = {"effect": "permit",
+               "principal": {"op": "All"},
+               "action": {"op": "==", "entity": {"type": "Action", "id": "edit"}},
+               "resource": {"op": "All"},
+               "conditions": [{"kind": "when",
+                               "body": {"==": {"left": {".": {"left": {"Var": "resource"}, "attr": "owner"}},
+                                               "right": {"Var": "principal"}}}}]}
+        expect_result = dedent("""
+            permit (
+              principal,
+              action == Action::"edit",
+              resource
+            )
+            when { (resource["owner"]) == principal };
+        """).strip()
+
+        self.assertEqual(expect_result, format_policies(est))
+        self.assertEqual(expect_result, format_policies(json.dumps(est), input_format="json"))
+
+        forbid = dict(est, effect="forbid", annotations={"id": "no-edit"})
+        formatted = format_policies([est, forbid])
+        self.assertTrue(formatted.startswith(expect_result))
+        self.assertIn('@id("no-edit")\nforbid (', formatted)
+        # the text is parsed like any other policies
+        self.assertEqual(formatted, format_policies(formatted))
+
+    def test_invalid_json_policies_raise_value_error(self):
+        for policies in [{"effect": "allow"}, "not json", '"a string"']:
+            with self.subTest(policies=policies):
+                with self.assertRaises(ValueError):
+                    format_policies(policies, input_format="json")
+        with self.assertRaises(ValueError):
+            format_policies("permit(principal, action, resource);", input_format="yaml")
@@ -0,0 +1,68 @@
+import unittest
+
+from cedarpy import CedarEngine, Decision
+
+
+class GuardrailTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.guardrails = """
+            forbid(principal, action == Action::"delete", resource) when { resource.legal_hold };
+            forbid(principal, action, resource) when { context.risk > 8 };
+        """
+        self.tenant_policies = 'permit(principal == User::"alice", action, resource);'
+        self.entities = [
+            {"uid": {"type": "User", "id": "alice"}, "attrs": {}, "parents": []},
+            {"uid": {"type": "Doc", "id": "held"}, "attrs": {"legal_hold": True}, "parents": []},
+            {"uid": {"type": "Doc", "id": "plan"}, "attrs": {"legal_hold": False}, "parents": []},
+        ]
+
+    def request(self, action: str, resource: str, risk: int = 0) -> dict:
+        return {"principal": 'User::"alice"', "action": f'Action::"{action}"', "resource": f'Doc::"{resource}"',
+                "context": {"risk": risk}}
+
+    def test_guardrails_hold_over_tenant_permits(self):
+        engine = CedarEngine(self.tenant_policies, self.entities, guardrails=self.guardrails)
+
+        authz_result = engine.is_authorized(self.request("delete", "held"))
+        self.assertEqual(Decision.Deny, authz_result.decision)
+        self.assertEqual(["guardrail0"], authz_result.diagnostics.reasons)
+        self.assertEqual(["guardrail0"], authz_result.diagnostics.reason_guardrails)
+
+        self.assertEqual(["guardrail1"],
+                         engine.is_authorized(self.request("view", "plan", risk=9)).diagnostics.reason_guardrails)
+
+        authz_result = engine.is_authorized(self.request("delete", "plan"))
+        self.assertEqual(Decision.Allow, authz_result.decision)
+        self.assertEqual(["policy0"], authz_result.diagnostics.reasons)
+        self.assertEqual([], authz_result.diagnostics.reason_guardrails)
+        self.assertEqual(3, engine.stats()["num_policies"])
+
+    def test_reloads_replace_only_the_tenant_policies(self):
+        engine = CedarEngine(self.tenant_policies, self.entities, guardrails=self.guardrails)
+        fingerprint = engine.is_authorized(self.request("view", "plan")).fingerprints["policy_set"]
+
+        engine.reload('permit(principal, action, resource);', self.entities)
+
+        self.assertEqual(Decision.Deny, engine.is_authorized(self.request("delete", "held")).decision)
+        authz_result = engine.is_authorized(self.request("view", "plan"))
+        self.assertEqual(Decision.Allow, authz_result.decision)
+        self.assertNotEqual(fingerprint, authz_result.fingerprints["policy_set"])
+
+    def test_guardrails_may_only_forbid(self):
+        with self.assertRaises(ValueError) as cm:
+            CedarEngine(self.tenant_policies, self.entities,
+                        guardrails=self.guardrails + 'permit(principal, action, resource);')
+        self.assertIn("invalid guardrail guardrail2: guardrails may only forbid", str(cm.exception))
+
+        with self.assertRaises(ValueError) as cm:
+            CedarEngine(self.tenant_policies, self.entities, guardrails="forbid(principal, action, resource")
+        self.assertEqual("policy_parse_error", cm.exception.code)
+        self.assertIn("failed to parse guardrails", str(cm.exception))
+
+    def test_an_engine_without_guardrails_reports_none(self):
+        engine = CedarEngine(self.tenant_policies, self.entities)
+
+        authz_result = engine.is_authorized(self.request("delete", "held"))
+        self.assertEqual(Decision.Allow, authz_result.decision)
+        self.assertNotIn("reason_guardrails", authz_result.to_dict()["diagnostics"])
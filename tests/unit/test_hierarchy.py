@@ -0,0 +1,149 @@
+import unittest
+
+from cedarpy import check_entities, entity_graph, is_in, members_of
+
+from unit import load_file_as_json
+
+
+def entity(uid: str, *parents: str) -> dict:
+    return {"uid": {"__expr": uid},
+            "attrs": {},
+            "parents": [{"__expr": parent} for parent in parents]}
+
+
+class CheckEntitiesTestCase(unittest.TestCase):
+
+    def test_well_formed_entities_have_no_findings(self):
+        self.assertEqual([], check_entities(load_file_as_json("resources/sandbox_b/entities.json")))
+        self.assertEqual([], check_entities("[]"))
+
+    def test_finds_cycles(self):
+        findings = check_entities([
+            entity('User::"alice"', 'Group::"a"'),
+            entity('Group::"a"', 'Group::"b"'),
+            entity('Group::"b"', 'Group::"c"'),
+            entity('Group::"c"', 'Group::"a"'),
+        ])
+        self.assertEqual(1, len(findings))
+        self.assertEqual("cycle", findings[0].kind)
+        self.assertEqual('Group::"a"', findings[0].entity)
+        self.assertEqual('the hierarchy has a cycle: Group::"a" -> Group::"b" -> Group::"c" -> Group::"a"',
+                         findings[0].message)
+
+    def test_finds_self_parenting_duplicates_and_dangling_parents(self):
+        findings = check_entities([
+            entity('User::"alice"', 'User::"alice"', 'Group::"missing"'),
+            {"uid": {"type": "User", "id": "alice"}, "attrs": {}, "parents": []},
+            entity('User::"bob"', 'not a uid'),
+            {"uid": "User::bob", "attrs": {}, "parents": []},
+        ])
+        self.assertEqual([("self_parent", 'User::"alice"'),
+                          ("duplicate_uid", 'User::"alice"'),
+                          ("invalid_uid", 'User::"bob"'),
+                          ("invalid_uid", '"User::bob"'),
+                          ("dangling_parent", 'User::"alice"')],
+                         [(finding.kind, finding.entity) for finding in findings])
+        self.assertIn('Group::"missing"', findings[-1].message)
+
+    def test_finds_eccentric_fan_out(self):
+        groups = [f'Group::"{i}"' for i in range(5)]
+        entities = [entity('User::"alice"', *groups)] + [entity(group) for group in groups]
+
+        self.assertEqual([], check_entities(entities))
+        findings = check_entities(entities, max_parents=4)
+        self.assertEqual([("fan_out", 'User::"alice"')], [(finding.kind, finding.entity) for finding in findings])
+        self.assertEqual('User::"alice" has 5 parents, more than 4', findings[0].message)
+
+    def test_deep_hierarchies_are_checked(self):
+        entities = [entity(f'Group::"{i}"', f'Group::"{i + 1}"') for i in range(20000)]
+        entities.append(entity('Group::"20000"', 'Group::"0"'))
+        findings = check_entities(entities)
+        self.assertEqual(["cycle"], [finding.kind for finding in findings])
+
+    def test_invalid_entities_raise_value_error(self):
+        with self.assertRaises(ValueError):
+            check_entities("not json")
+        with self.assertRaises(ValueError):
+            check_entities('{"uid": {"__expr": "User::\\"alice\\""}}')
+
+
+class MembershipTestCase(unittest.TestCase):
+
+    def setUp(self) -> None:
+        super().setUp()
+        self.entities = [
+            entity('User::"alice"', 'Group::"engineering"'),
+            entity('User::"bob"', 'Group::"hardware"', 'Group::"engineering"'),
+            entity('User::"stacey"', 'Group::"sales"'),
+            entity('Group::"hardware"', 'Group::"engineering"'),
+            entity('Group::"engineering"', 'Group::"everyone"'),
+            entity('Group::"sales"', 'Group::"everyone"'),
+            entity('Group::"everyone"'),
+        ]
+
+    def test_is_in(self):
+        self.assertTrue(is_in(self.entities, 'User::"alice"', 'Group::"engineering"'))
+        self.assertTrue(is_in(self.entities, 'User::"alice"', 'Group::"everyone"'))
+        self.assertTrue(is_in(self.entities, 'User::"alice"', 'User::"alice"'))
+        self.assertFalse(is_in(self.entities, 'User::"alice"', 'Group::"sales"'))
+        self.assertFalse(is_in(self.entities, 'Group::"everyone"', 'Group::"sales"'))
+        self.assertFalse(is_in(self.entities, 'User::"nobody"', 'Group::"everyone"'))
+
+    def test_members_of(self):
+        self.assertEqual(['Group::"engineering"', 'Group::"hardware"', 'Group::"sales"',
+                          'User::"alice"', 'User::"bob"', 'User::"stacey"'],
+                         members_of(self.entities, 'Group::"everyone"'))
+        self.assertEqual(['Group::"engineering"', 'Group::"sales"'],
+                         members_of(self.entities, 'Group::"everyone"', transitive=False))
+        self.assertEqual(['Group::"hardware"', 'User::"alice"', 'User::"bob"'],
+                         members_of(self.entities, 'Group::"engineering"', transitive=False))
+        self.assertEqual([], members_of(self.entities, 'User::"alice"'))
+        self.assertEqual([], members_of(self.entities, 'Group::"nonexistent"'))
+
+    def test_invalid_inputs_raise_value_error(self):
+        with self.assertRaises(ValueError):
+            is_in(self.entities, 'alice', 'Group::"everyone"')
+        with self.assertRaises(ValueError):
+            members_of("not json", 'Group::"everyone"')
+        with self.assertRaises(ValueError):
+            members_of([entity('Group::"a"', 'Group::"a"')], 'Group::"a"')
+
+
+class EntityGraphTestCase(unittest.TestCase):
+
+    def setUp(self) -> None:
+        super().setUp()
+        document = entity('Photo::"vacation.jpg"', 'Album::"trips"')
+        document["attrs"] = {
+            "owner": {"__entity": {"type": "User", "id": "alice"}},
+            "shared": {"with": [{"__expr": 'User::"bob"'}], "at": {"__extn": {"fn": "ip", "arg": "10.0.0.1"}}},
+            "title": "Vacation",
+        }
+        self.entities = [entity('User::"alice"'), document, entity('Album::"trips"')]
+
+    def test_graph_has_parent_and_attribute_edges(self):
+        graph = entity_graph(self.entities)
+        self.assertEqual([
+            {"id": 'User::"alice"', "type": "User", "missing": False},
+            {"id": 'Photo::"vacation.jpg"', "type": "Photo", "missing": False},
+            {"id": 'Album::"trips"', "type": "Album", "missing": False},
+            {"id": 'User::"bob"', "type": "User", "missing": True},
+        ], graph["nodes"])
+        self.assertEqual([
+            {"source": 'Photo::"vacation.jpg"', "target": 'Album::"trips"', "kind": "parent", "attribute": None},
+            {"source": 'Photo::"vacation.jpg"', "target": 'User::"alice"', "kind": "attribute", "attribute": "owner"},
+            {"source": 'Photo::"vacation.jpg"', "target": 'User::"bob"', "kind": "attribute",
+             "attribute": "shared.with"},
+        ], graph["edges"])
+
+    def test_graph_as_dot(self):
+        dot = entity_graph(self.entities, output_format="dot")
+        self.assertTrue(dot.startswith("digraph entities {\n"))
+        self.assertIn('    "User::\\"bob\\"" [style=dashed];\n', dot)
+        self.assertIn('    "Photo::\\"vacation.jpg\\"" -> "Album::\\"trips\\"";\n', dot)
+        self.assertIn('    "Photo::\\"vacation.jpg\\"" -> "User::\\"alice\\"" [label="owner", style=dashed];\n', dot)
+
+        with self.assertRaises(ValueError):
+            entity_graph(self.entities, output_format="svg")
+        with self.assertRaises(ValueError):
+            entity_graph([{"uid": "alice", "attrs": {}, "parents": []}])
@@ -0,0 +1,125 @@
+import sys
+import unittest
+
+from cedarpy import CedarEngine, Decision
+
+from unit import load_file_as_json, load_file_as_str
+
+
+class EngineHooksTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.schema = load_file_as_str("resources/sandbox_b/schema.json")
+        self.entities: list = load_file_as_json("resources/sandbox_b/entities.json")
+        self.policies = 'permit(principal == User::"alice", action == Action::"view", resource);'
+        self.request = {
+            "principal": 'User::"alice"',
+            "action": 'Action::"view"',
+            "resource": 'Photo::"alice_w2.jpg"',
+            "context": {"authenticated": True},
+            "correlation_id": "req-1",
+        }
+        self.engine = CedarEngine(self.policies, self.entities, self.schema)
+        self.events = {"reload": [], "error": [], "decision": []}
+        for event, events in self.events.items():
+            self.engine.register_hook(event, events.append)
+
+    def test_decisions_are_reported(self):
+        self.engine.is_authorized(self.request, include_diagnostics=False)
+        self.engine.is_authorized_batch([dict(self.request, principal='User::"bob"', correlation_id="req-2")])
+
+        self.assertEqual([{
+            "decision": "Allow",
+            "correlation_id": "req-1",
+            "reason": ["policy0"],
+            "fingerprints": self.engine.stats()["fingerprints"],
+        }], self.events["decision"][:1])
+        self.assertEqual([("Deny", "req-2", [])],
+                         [(event["decision"], event["correlation_id"], event["reason"])
+                          for event in self.events["decision"][1:]])
+        self.assertEqual([], self.events["error"])
+
+        page = self.engine.is_authorized_batch_resumable([self.request] * 3, max_requests=2)
+        self.assertEqual(2, len(page.results))
+        self.assertEqual(4, len(self.events["decision"]))
+
+    def test_fast_path_decisions_are_reported(self):
+        self.assertTrue(self.engine.is_authorized_fast(self.request))
+        self.assertEqual([("Allow", "req-1", ["policy0"])],
+                         [(event["decision"], event["correlation_id"], event["reason"])
+                          for event in self.events["decision"]])
+
+    def test_reloads_and_their_failures_are_reported(self):
+        self.engine.reload(self.policies, self.entities[1:], self.schema)
+        self.assertEqual(1, len(self.events["reload"]))
+        reload = self.events["reload"][0]
+        self.assertTrue(reload["ok"])
+        stats = self.engine.stats()
+        self.assertEqual((stats["num_policies"], stats["num_entities"]), (reload["num_policies"], reload["num_entities"]))
+        self.assertEqual(stats["fingerprints"], reload["fingerprints"])
+
+        with self.assertRaises(ValueError):
+            self.engine.reload("permit(", self.entities, self.schema)
+        failed = self.events["reload"][1]
+        self.assertEqual((False, "policy_parse_error"), (failed["ok"], failed["error_code"]))
+        self.assertEqual([{"source": "reload", "error_codes": ["policy_parse_error"], "errors": [failed["error"]]}],
+                         self.events["error"])
+
+        # requests decided while failing closed are errors too
+        authz_result = self.engine.is_authorized(self.request)
+        self.assertEqual(Decision.Deny, authz_result.decision)
+        error = self.events["error"][1]
+        self.assertEqual(("request", "req-1", ["policy_store_unavailable"]),
+                         (error["source"], error["correlation_id"], error["error_codes"]))
+
+    def test_requests_that_could_not_be_decided_are_reported_with_their_ids(self):
+        self.engine.is_authorized(dict(self.request, resource="bad"))
+        self.assertEqual([("NoDecision", "req-1")],
+                         [(event["decision"], event["correlation_id"]) for event in self.events["decision"]])
+        self.assertEqual([("req-1", ["request_error"])],
+                         [(event["correlation_id"], event["error_codes"]) for event in self.events["error"]])
+
+    def test_failed_calls_are_reported_once(self):
+        requests = [dict(self.request, correlation_id=f"req-{i}") for i in range(100)]
+        results = self.engine.is_authorized_batch(requests, min_version=self.engine.version() + 1)
+        self.assertEqual(100, len(results))
+
+        [decision] = self.events["decision"]
+        self.assertEqual(("NoDecision", None, 100), (decision["decision"], decision["correlation_id"],
+                                                     decision["num_requests"]))
+        self.assertEqual([request["correlation_id"] for request in requests], decision["correlation_ids"])
+        [error] = self.events["error"]
+        self.assertEqual((["stale_version"], 100, True), (error["error_codes"], error["num_requests"],
+                                                          error["retryable"]))
+
+        # a single request's events have its id
+        self.engine.is_authorized(self.request, min_version=self.engine.version() + 1)
+        self.assertEqual(("req-1", ["req-1"]), (self.events["decision"][1]["correlation_id"],
+                                                self.events["decision"][1]["correlation_ids"]))
+
+    def test_failing_callbacks_are_contained(self):
+        unraisable = []
+        self.addCleanup(setattr, sys, "unraisablehook", sys.unraisablehook)
+        sys.unraisablehook = unraisable.append
+
+        def fail(event: dict) -> None:
+            raise RuntimeError("the metrics backend is down")
+
+        self.engine.register_hook("decision", fail)
+        for _ in range(12):
+            self.assertEqual(Decision.Allow, self.engine.is_authorized(self.request).decision)
+
+        # the callback is unregistered after 10 failures in a row
+        self.assertEqual(10, self.engine.stats()["hook_failures"])
+        self.assertEqual(11, len(unraisable))
+        self.assertIsInstance(unraisable[0].exc_value, RuntimeError)
+        self.assertIsInstance(unraisable[-1].exc_value, RuntimeWarning)
+
+        self.engine.register_hook("decision", None)
+        self.engine.is_authorized(self.request)
+        self.assertEqual([], self.events["decision"])
+
+    def test_invalid_event_is_rejected(self):
+        with self.assertRaisesRegex(ValueError, "invalid event 'decisions'") as cm:
+            self.engine.register_hook("decisions", print)
+        self.assertEqual("invalid_argument", cm.exception.code)
@@ -0,0 +1,86 @@
+import unittest
+
+from cedarpy import run_inline_tests
+
+from unit import load_file_as_json, load_file_as_str
+
+
+class InlineTestsTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.schema = load_file_as_str("resources/sandbox_b/schema.json")
+        self.entities: list = load_file_as_json("resources/sandbox_b/entities.json")
+
+    def test_runs_allow_and_deny_cases(self):
+        policies = """
+            @test_allow("User::\\"alice\\", Action::\\"view\\", Photo::\\"alice_w2.jpg\\"")
+            @test_deny("User::\\"bob\\", Action::\\"view\\", Photo::\\"alice_w2.jpg\\"; User::\\"alice\\", Action::\\"delete\\", Photo::\\"alice_w2.jpg\\"")
+            permit(principal == User::"alice", action == Action::"view", resource);
+        """
+        report = run_inline_tests(policies, self.entities)
+
+        self.assertEqual(3, report.passed)
+        self.assertEqual(0, report.failed)
+        self.assertTrue(report.ok)
+        self.assertEqual(["test_allow", "test_deny", "test_deny"], [test["annotation"] for test in report.tests])
+        self.assertEqual(["policy0"], report.tests[0]["reason"])
+        self.assertEqual(("User::\"bob\"", "Deny", "Deny"),
+                         (report.tests[1]["principal"], report.tests[1]["expected"], report.tests[1]["decision"]))
+
+    def test_reports_failing_cases(self):
+        policies = """
+            @test_allow("User::\\"bob\\", Action::\\"view\\", Photo::\\"alice_w2.jpg\\", {\\"authenticated\\": true}")
+            permit(principal == User::"alice", action == Action::"view", resource);
+        """
+        report = run_inline_tests(policies, self.entities, self.schema)
+
+        self.assertEqual((0, 1), (report.passed, report.failed))
+        self.assertFalse(report.ok)
+        self.assertEqual([("Allow", "Deny")], [(test["expected"], test["decision"]) for test in report.failures])
+
+    def test_cases_are_evaluated_against_all_policies(self):
+        policies = """
+            @test_deny_forbidden("User::\\"alice\\", Action::\\"view\\", Photo::\\"alice_w2.jpg\\"")
+            permit(principal == User::"alice", action == Action::"view", resource);
+
+            forbid(principal, action, resource) when { context.authenticated == false };
+        """
+        report = run_inline_tests(policies, self.entities)
+
+        # without a context the forbid errors and is skipped, so the permit allows the request
+        self.assertEqual(1, report.failed)
+        self.assertEqual("test_deny_forbidden", report.tests[0]["annotation"])
+        self.assertEqual(1, len(report.tests[0]["errors"]))
+
+    def test_cases_may_have_a_context(self):
+        policies = """
+            @test_allow("User::\\"alice\\", Action::\\"view\\", Photo::\\"alice_w2.jpg\\", {\\"authenticated\\": true}")
+            @test_deny_unauthenticated("User::\\"alice\\", Action::\\"view\\", Photo::\\"alice_w2.jpg\\", {\\"authenticated\\": false}")
+            permit(principal == User::"alice", action == Action::"view", resource) when { context.authenticated };
+        """
+        report = run_inline_tests(policies, self.entities)
+
+        self.assertEqual((2, 0), (report.passed, report.failed))
+        self.assertEqual({"authenticated": False}, report.tests[1]["context"])
+
+    def test_invalid_cases_fail(self):
+        policies = """
+            @test_allow("User::\\"alice\\", Action::\\"view\\"")
+            @test_deny("User::\\"alice\\", Action::\\"view\\", Photo::\\"alice_w2.jpg\\", [1, 2]")
+            permit(principal == User::"alice", action == Action::"view", resource);
+        """
+        report = run_inline_tests(policies, self.entities)
+
+        self.assertEqual(2, report.failed)
+        self.assertIsNone(report.tests[0]["decision"])
+        self.assertIn("expected a principal, action, and resource", report.tests[0]["errors"][0])
+        self.assertIn("the context must be a JSON object", report.tests[1]["errors"][0])
+
+    def test_policies_without_tests_have_no_cases(self):
+        report = run_inline_tests('permit(principal, action, resource);', self.entities)
+
+        self.assertEqual((0, 0, []), (report.passed, report.failed, report.tests))
+
+    def test_invalid_policies_raise_value_error(self):
+        with self.assertRaises(ValueError):
+            run_inline_tests('permit(principal, action, resource', self.entities)
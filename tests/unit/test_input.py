@@ -0,0 +1,119 @@
+import gzip
+import json
+import os
+import subprocess
+import sys
+import tempfile
+import unittest
+from pathlib import Path
+
+from cedarpy import is_authorized, is_authorized_batch, CedarEngine, Decision
+
+from unit import load_file_as_json, load_file_as_str
+
+
+class CompressedInputTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.resources = Path(__file__).parent / "resources" / "sandbox_b"
+        self.schema = load_file_as_str("resources/sandbox_b/schema.json")
+        self.entities: list = load_file_as_json("resources/sandbox_b/entities.json")
+        self.policies = 'permit(principal == User::"alice", action == Action::"view", resource);'
+        self.request = {
+            "principal": 'User::"alice"',
+            "action": 'Action::"view"',
+            "resource": 'Photo::"alice_w2.jpg"',
+            "context": {"authenticated": True},
+        }
+        self.directory = tempfile.TemporaryDirectory()
+
+    def tearDown(self) -> None:
+        self.directory.cleanup()
+        super().tearDown()
+
+    def path(self, name: str) -> Path:
+        return Path(self.directory.name) / name
+
+    def assert_allowed(self, entities) -> None:
+        authz_result = is_authorized(self.request, self.policies, entities, self.schema)
+        self.assertEqual(Decision.Allow, authz_result.decision, msg=authz_result.diagnostics.errors)
+
+    def test_entities_may_be_bytes(self):
+        entities_json = json.dumps(self.entities).encode("utf-8")
+        self.assert_allowed(entities_json)
+        self.assert_allowed(gzip.compress(entities_json))
+        self.assert_allowed((self.resources / "entities.json.zst").read_bytes())
+
+    def test_entities_may_be_paths(self):
+        gzip_path = self.path("entities.json.gz")
+        gzip_path.write_bytes(gzip.compress((self.resources / "entities.json").read_bytes()))
+
+        self.assert_allowed(self.resources / "entities.json")
+        self.assert_allowed(gzip_path)
+        self.assert_allowed(self.resources / "entities.json.zst")
+
+        engine = CedarEngine(self.policies, self.resources / "entities.json.zst", self.schema)
+        self.assertEqual(Decision.Allow, engine.is_authorized(self.request).decision)
+        engine.reload(self.policies, gzip_path, self.schema)
+        self.assertEqual(Decision.Allow, engine.is_authorized(self.request).decision)
+
+    def test_requests_may_be_ndjson(self):
+        denied = dict(self.request, principal='User::"bob"')
+        ndjson = "\n".join(json.dumps(request) for request in [self.request, denied, self.request]) + "\n"
+        expected = [Decision.Allow, Decision.Deny, Decision.Allow]
+
+        for requests in [ndjson.encode("utf-8"), gzip.compress(ndjson.encode("utf-8"))]:
+            authz_results = is_authorized_batch(requests, self.policies, self.entities, self.schema)
+            self.assertEqual(expected, [authz_result.decision for authz_result in authz_results])
+
+        requests_path = self.path("requests.ndjson.gz")
+        requests_path.write_bytes(gzip.compress(ndjson.encode("utf-8")))
+        for requests in [requests_path, str(requests_path)]:
+            authz_results = is_authorized_batch(requests, self.policies, self.entities, self.schema)
+            self.assertEqual(expected, [authz_result.decision for authz_result in authz_results])
+
+        output_path = self.path("results.ndjson")
+        summary = is_authorized_batch(requests_path, self.policies, self.resources / "entities.json.zst", self.schema,
+                                      output_path=output_path)
+        self.assertEqual((3, 2, 1), (summary["requests"], summary["allowed"], summary["denied"]))
+
+    def test_invalid_inputs_are_rejected(self):
+        with self.assertRaisesRegex(ValueError, "invalid gzip data") as cm:
+            self.assert_allowed(gzip.compress(b"[]")[:-4])
+        self.assertEqual("invalid_argument", cm.exception.code)
+
+        corrupt = bytearray((self.resources / "entities.json.zst").read_bytes())
+        corrupt[-6] ^= 0xff
+        with self.assertRaisesRegex(ValueError, "invalid zstd data"):
+            self.assert_allowed(bytes(corrupt))
+
+        with self.assertRaisesRegex(ValueError, "failed to parse the request on line 2"):
+            is_authorized_batch(json.dumps(self.request).encode("utf-8") + b"\n{not json}\n",
+                                self.policies, self.entities, self.schema)
+
+        with self.assertRaises(OSError):
+            self.assert_allowed(self.path("missing.json"))
+        with self.assertRaises(OSError):
+            is_authorized_batch(os.path.join(self.directory.name, "missing.ndjson"),
+                                self.policies, self.entities, self.schema)
+
+    def test_inputs_may_not_decompress_past_the_limit(self):
+        script = """
+import gzip, cedarpy
+entities = gzip.compress(b"[" + b" " * 2000 + b"]")
+request = {"principal": 'User::"alice"', "action": 'Action::"view"', "resource": 'Photo::"a.jpg"'}
+print(cedarpy.is_authorized(request, "", gzip.compress(b"[" + b" " * 500 + b"]")).decision)
+try:
+    cedarpy.is_authorized(request, "", entities)
+except ValueError as e:
+    print(e)
+"""
+        completed = subprocess.run([sys.executable, "-c", script],
+                                   env=dict(os.environ, PYTHONPATH=os.pathsep.join(sys.path),
+                                            CEDARPY_MAX_INPUT_BYTES="1000"),
+                                   capture_output=True, text=True)
+        self.assertEqual(0, completed.returncode, completed.stderr)
+        decision, error = completed.stdout.splitlines()
+        self.assertEqual("Decision.Deny", decision)
+        self.assertEqual("the gzip input decompresses to more than 1000 bytes, the most CEDARPY_MAX_INPUT_BYTES "
+                         "allows", error)
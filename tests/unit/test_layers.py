@@ -0,0 +1,66 @@
+import unittest
+
+from cedarpy import CedarEngine, Decision, is_authorized_union
+
+
+class LayerAttributionTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.guardrails = 'forbid(principal, action == Action::"delete", resource) when { resource.legal_hold };'
+        self.tenant_policies = """
+            permit(principal == User::"alice", action, resource);
+            forbid(principal, action == Action::"share", resource);
+        """
+        self.entities = [
+            {"uid": {"type": "User", "id": "alice"}, "attrs": {}, "parents": []},
+            {"uid": {"type": "User", "id": "bob"}, "attrs": {}, "parents": []},
+            {"uid": {"type": "Doc", "id": "held"}, "attrs": {"legal_hold": True}, "parents": []},
+        ]
+
+    def request(self, principal: str, action: str) -> dict:
+        return {"principal": f'User::"{principal}"', "action": f'Action::"{action}"', "resource": 'Doc::"held"',
+                "context": {}}
+
+    def test_an_engine_attributes_decisions_to_its_guardrails_or_policies(self):
+        engine = CedarEngine(self.tenant_policies, self.entities, guardrails=self.guardrails)
+
+        authz_result = engine.is_authorized(self.request("alice", "delete"))
+        self.assertEqual(Decision.Deny, authz_result.decision)
+        self.assertEqual({"guardrails": ["guardrail0"]}, authz_result.diagnostics.reason_by_layer)
+        self.assertEqual("denied by guardrails", authz_result.diagnostics.decided_by)
+
+        authz_result = engine.is_authorized(self.request("alice", "share"))
+        self.assertEqual({"policies": ["policy1"]}, authz_result.diagnostics.reason_by_layer)
+        self.assertEqual("denied by policies", authz_result.diagnostics.decided_by)
+
+        authz_result = engine.is_authorized(self.request("alice", "view"))
+        self.assertEqual("allowed by policies", authz_result.diagnostics.decided_by)
+
+    def test_a_deny_without_determining_policies_is_denied_by_default(self):
+        engine = CedarEngine(self.tenant_policies, self.entities, guardrails=self.guardrails)
+
+        authz_result = engine.is_authorized(self.request("bob", "view"))
+        self.assertEqual({}, authz_result.diagnostics.reason_by_layer)
+        self.assertEqual("denied by default", authz_result.diagnostics.decided_by)
+
+    def test_a_union_attributes_decisions_to_engines_and_their_guardrails(self):
+        org = CedarEngine(self.tenant_policies, self.entities, guardrails=self.guardrails)
+        app = CedarEngine('forbid(principal, action == Action::"delete", resource);', self.entities)
+
+        authz_result = is_authorized_union({"org": org, "app": app}, self.request("alice", "delete"))
+        self.assertEqual(Decision.Deny, authz_result.decision)
+        self.assertEqual({"app": ["policy0"], "org.guardrails": ["guardrail0"]},
+                         authz_result.diagnostics.reason_by_layer)
+        self.assertEqual("denied by app and org.guardrails", authz_result.diagnostics.decided_by)
+
+        authz_result = is_authorized_union({"org": org, "app": app}, self.request("alice", "view"))
+        self.assertEqual({"org": ["policy0"]}, authz_result.diagnostics.reason_by_layer)
+        self.assertEqual("allowed by org", authz_result.diagnostics.decided_by)
+
+    def test_an_engine_without_layers_reports_none(self):
+        engine = CedarEngine(self.tenant_policies, self.entities)
+
+        authz_result = engine.is_authorized(self.request("alice", "delete"))
+        self.assertEqual({}, authz_result.diagnostics.reason_by_layer)
+        self.assertIsNone(authz_result.diagnostics.decided_by)
+        self.assertNotIn("reason_by_layer", authz_result.to_dict()["diagnostics"])
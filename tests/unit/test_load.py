@@ -0,0 +1,72 @@
+import unittest
+
+from cedarpy import bench, CedarEngine, check_entities, conformance_report, generate_load, soak
+
+from unit import load_file_as_str
+
+
+class GenerateLoadTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.schema = load_file_as_str("resources/sandbox_b/schema.json")
+        self.policies = """
+            permit(principal == User::"alice", action == Action::"view", resource);
+            permit(principal, action == Action::"edit", resource in Album::"vacation")
+            when { context.authenticated };
+        """
+
+    def test_corpus_is_reproducible(self):
+        corpus = generate_load(self.schema, self.policies, 50, seed=7)
+
+        self.assertEqual(corpus, generate_load(self.schema, self.policies, 50, seed=7))
+        self.assertNotEqual(corpus["requests"], generate_load(self.schema, self.policies, 50, seed=8)["requests"])
+        self.assertEqual(50, len(corpus["requests"]))
+
+    def test_corpus_conforms_to_the_schema(self):
+        corpus = generate_load(self.schema, self.policies, 20, entities_per_type=3)
+        uids = [entity["uid"]["__entity"]["id"] for entity in corpus["entities"]]
+
+        self.assertIn("alice", uids)
+        self.assertIn("vacation", uids)
+        report = conformance_report(corpus["entities"], self.schema)
+        self.assertEqual(report["entities"], report["conforming"])
+        self.assertEqual([], check_entities(corpus["entities"]))
+
+    def test_bench_reports_latency_percentiles(self):
+        corpus = generate_load(self.schema, self.policies, 200, seed=1)
+        engine = CedarEngine(self.policies, corpus["entities"], self.schema)
+
+        stats = bench(engine, corpus)
+
+        self.assertEqual(200, stats["requests"])
+        self.assertGreater(stats["allowed"], 0)
+        self.assertLessEqual(stats["p50"], stats["p90"])
+        self.assertLessEqual(stats["p99"], stats["max"])
+
+    def test_invalid_arguments_raise(self):
+        with self.assertRaises(ValueError):
+            generate_load(self.schema, self.policies, 10, entities_per_type=0)
+        with self.assertRaises(ValueError):
+            generate_load({"": {"entityTypes": {}, "actions": {}}}, "", 10)
+
+
+class SoakTestCase(unittest.TestCase):
+    def test_soak_samples_memory_and_reports_growth(self):
+        schema = load_file_as_str("resources/sandbox_b/schema.json")
+        policies = 'permit(principal, action == Action::"view", resource);'
+        corpus = generate_load(schema, policies, 20)
+        engine = CedarEngine(policies, corpus["entities"], schema)
+        hooked = []
+
+        report = soak(engine, corpus, duration_minutes=0.5 / 60, sample_interval_seconds=0.1, on_sample=hooked.append)
+
+        self.assertGreater(report["requests"], 0)
+        self.assertGreaterEqual(len(report["samples"]), 3)
+        self.assertEqual(report["samples"], hooked)
+        self.assertIn("request_cache_size", report["samples"][-1]["engine_stats"])
+        self.assertIsNotNone(report["samples"][-1]["rss_bytes"])
+        self.assertFalse(report["leak_suspected"])
+
+    def test_soak_requires_requests(self):
+        with self.assertRaises(ValueError):
+            soak(CedarEngine("", []), {"requests": []}, duration_minutes=0.01)
@@ -0,0 +1,134 @@
+import unittest
+
+from textwrap import dedent
+
+from cedarpy import Decision, is_authorized, split_policy_source, suggest_templates, templatize
+
+
+class SuggestTemplatesTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.policies = dedent("""
+            @id("alice-view") @team("photos")
+            permit(principal == User::"alice", action == Action::"view", resource in Album::"trips");
+            @id("bob-view") @team("photos")
+            permit(principal == User::"bob", action == Action::"view", resource in Album::"pets");
+            @id("carol-view")
+            permit(principal == User::"carol", action == Action::"view", resource in Album::"trips");
+
+            permit(principal == User::"alice", action == Action::"edit", resource)
+            when { resource.owner == principal };
+            permit(principal == User::"bob", action == Action::"edit", resource)
+            when { resource.owner == principal };
+
+            permit(principal == User::"dave", action == Action::"edit", resource);
+            permit(principal == ?principal, action == Action::"view", resource);
+        """)
+
+    def test_clusters_are_suggested_as_templates_largest_first(self):
+        suggestions = suggest_templates(self.policies)
+
+        self.assertEqual(["template0", "template1"], [suggestion.template_id for suggestion in suggestions])
+        view, edit = suggestions
+        self.assertEqual(["?principal", "?resource"], view.slots)
+        self.assertIn('principal == ?principal', view.text)
+        self.assertIn('resource in ?resource', view.text)
+        # carol's policy has no @team, so the template doesn't either
+        self.assertNotIn('annotations', view.policy)
+        self.assertEqual([("policy0", {"?principal": {"type": "User", "id": "alice"},
+                                       "?resource": {"type": "Album", "id": "trips"}}),
+                          ("policy1", {"?principal": {"type": "User", "id": "bob"},
+                                       "?resource": {"type": "Album", "id": "pets"}}),
+                          ("policy2", {"?principal": {"type": "User", "id": "carol"},
+                                       "?resource": {"type": "Album", "id": "trips"}})],
+                         [(link['newId'], link['values']) for link in view.links])
+
+        self.assertEqual(["?principal"], edit.slots)
+        self.assertEqual(["policy3", "policy4"], [link['newId'] for link in edit.links])
+
+    def test_suggestions_form_a_json_policy_set(self):
+        suggestions = suggest_templates(self.policies)
+
+        split = split_policy_source({
+            "templates": {suggestion.template_id: suggestion.policy for suggestion in suggestions},
+            "templateLinks": [link for suggestion in suggestions for link in suggestion.links],
+        })
+
+        self.assertEqual(["template0", "template1"], [template['id'] for template in split.templates])
+        self.assertEqual(5, len(split.links))
+
+    def test_clusters_smaller_than_min_cluster_size_are_not_suggested(self):
+        suggestions = suggest_templates(self.policies, min_cluster_size=3)
+
+        self.assertEqual(["template0"], [suggestion.template_id for suggestion in suggestions])
+        self.assertEqual(3, len(suggestions[0].links))
+
+
+class TemplatizeTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.policies = dedent("""
+            @id("alice-view")
+            permit(principal == User::"alice", action == Action::"view", resource in Album::"trips");
+            @id("bob-view")
+            permit(principal == User::"bob", action == Action::"view", resource in Album::"pets");
+            permit(principal == User::"carol", action == Action::"edit", resource in Album::"trips");
+            forbid(principal == User::"bob", action, resource in Album::"pets") when { context.late };
+        """)
+        self.template = 'permit(principal == ?principal, action == Action::"view", resource in ?resource);'
+        self.entities = [
+            {"uid": {"type": "Photo", "id": "beach"}, "attrs": {}, "parents": [{"type": "Album", "id": "trips"}]},
+            {"uid": {"type": "Photo", "id": "cat"}, "attrs": {}, "parents": [{"type": "Album", "id": "pets"}]},
+        ]
+        self.requests = [
+            {"principal": 'User::"alice"', "action": 'Action::"view"', "resource": 'Photo::"beach"',
+             "context": {"late": False}},
+            {"principal": 'User::"bob"', "action": 'Action::"view"', "resource": 'Photo::"cat"',
+             "context": {"late": False}},
+            {"principal": 'User::"bob"', "action": 'Action::"view"', "resource": 'Photo::"cat"',
+             "context": {"late": True}},
+            {"principal": 'User::"carol"', "action": 'Action::"view"', "resource": 'Photo::"beach"',
+             "context": {"late": False}},
+        ]
+
+    def test_matched_policies_are_rewritten_to_links_with_their_ids(self):
+        templatized = templatize(self.policies, self.template)
+
+        self.assertEqual(["policy0", "policy1"], templatized.linked)
+        policy_set = templatized.policy_set
+        self.assertEqual(["template0"], list(policy_set['templates']))
+        self.assertEqual(["policy2", "policy3"], sorted(policy_set['staticPolicies']))
+        self.assertEqual([("alice-view", {"?principal": {"type": "User", "id": "alice"},
+                                          "?resource": {"type": "Album", "id": "trips"}}),
+                          ("bob-view", {"?principal": {"type": "User", "id": "bob"},
+                                        "?resource": {"type": "Album", "id": "pets"}})],
+                         [(link['newId'], link['values']) for link in policy_set['templateLinks']])
+        self.assertEqual(2, len(split_policy_source(policy_set).links))
+        self.assertIsNone(templatized.verification)
+
+    def test_rewritten_policies_are_verified_against_a_corpus(self):
+        templatized = templatize(self.policies, self.template, self.requests, self.entities)
+
+        self.assertTrue(templatized.equivalent)
+        self.assertEqual({"requests": 4, "equivalent": True, "mismatches": []}, templatized.verification)
+        self.assertEqual([Decision.Allow, Decision.Allow, Decision.Deny, Decision.Deny],
+                         [is_authorized(request, self.policies, self.entities).decision
+                          for request in self.requests])
+
+    def test_suggestions_can_be_applied(self):
+        suggestion = suggest_templates(self.policies)[0]
+
+        templatized = templatize(self.policies, suggestion, self.requests, self.entities)
+
+        self.assertEqual(["policy0", "policy1"], templatized.linked)
+        self.assertTrue(templatized.equivalent)
+
+    def test_templates_without_slots_and_duplicate_ids_raise(self):
+        with self.assertRaises(ValueError) as cm:
+            templatize(self.policies, 'permit(principal, action, resource);')
+        self.assertIn("has no slots", str(cm.exception))
+
+        with self.assertRaises(ValueError) as cm:
+            templatize('@id("a") permit(principal, action, resource); @id("a") forbid(principal, action, resource);',
+                       self.template)
+        self.assertIn("policy1 has the id a", str(cm.exception))
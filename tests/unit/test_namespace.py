@@ -0,0 +1,98 @@
+import unittest
+
+from textwrap import dedent
+
+from cedarpy import is_authorized, Decision, qualify_policies, unqualify_policies, qualify_entities, \
+    unqualify_entities, qualify_request, unqualify_request
+
+
+class NamespaceTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.policies = dedent("""
+            // alice may view her own photos
+            permit(
+                principal == User::"alice",
+                action in [Action::"view", Other::Action::"view"],
+                resource in Album::"alice_vacation"
+            )
+            when { resource.owner == User::"alice" && context.note != "User::\\"bob\\"" };
+        """).strip()
+        self.entities = [
+            {
+                "uid": {"__expr": "User::\"alice\""},
+                "attrs": {},
+                "parents": [{"type": "Group", "id": "admins"}]
+            },
+            {
+                "uid": {"type": "Photo", "id": "beach.jpg"},
+                "attrs": {"owner": {"__entity": {"type": "User", "id": "alice"}},
+                          "viewers": [{"__expr": "User::\"bob\""}],
+                          "address": {"__expr": "ip(\"10.0.0.1\")"}},
+                "parents": [{"__entity": {"type": "Album", "id": "alice_vacation"}}]
+            },
+        ]
+        self.request = {
+            "principal": "User::\"alice\"",
+            "action": "Action::\"view\"",
+            "resource": "Photo::\"beach.jpg\"",
+            "context": {"referrer": {"__entity": {"type": "User", "id": "bob"}}}
+        }
+
+    def test_qualify_policies(self):
+        expect_result = dedent("""
+            // alice may view her own photos
+            permit(
+                principal == MyApp::User::"alice",
+                action in [MyApp::Action::"view", Other::Action::"view"],
+                resource in MyApp::Album::"alice_vacation"
+            )
+            when { resource.owner == MyApp::User::"alice" && context.note != "User::\\"bob\\"" };
+        """).strip()
+
+        qualified = qualify_policies(self.policies, "MyApp")
+        self.assertEqual(expect_result, qualified)
+        self.assertEqual(self.policies, unqualify_policies(qualified, "MyApp"))
+
+    def test_qualify_policies_with_nested_namespace(self):
+        self.assertEqual('permit(principal == A::B::User::"alice", action, resource);',
+                         qualify_policies('permit(principal == User::"alice", action, resource);', "A::B"))
+
+    def test_qualify_entities(self):
+        qualified = qualify_entities(self.entities, "MyApp")
+
+        self.assertEqual({"__expr": "MyApp::User::\"alice\""}, qualified[0]["uid"])
+        self.assertEqual([{"type": "MyApp::Group", "id": "admins"}], qualified[0]["parents"])
+        self.assertEqual({"type": "MyApp::Photo", "id": "beach.jpg"}, qualified[1]["uid"])
+        self.assertEqual({"type": "MyApp::User", "id": "alice"}, qualified[1]["attrs"]["owner"]["__entity"])
+        self.assertEqual([{"__expr": "MyApp::User::\"bob\""}], qualified[1]["attrs"]["viewers"])
+        self.assertEqual({"__expr": "ip(\"10.0.0.1\")"}, qualified[1]["attrs"]["address"])
+        self.assertEqual(self.entities, unqualify_entities(qualified, "MyApp"))
+
+    def test_qualify_request(self):
+        qualified = qualify_request(self.request, "MyApp")
+
+        self.assertEqual("MyApp::User::\"alice\"", qualified["principal"])
+        self.assertEqual("MyApp::Action::\"view\"", qualified["action"])
+        self.assertEqual("MyApp::Photo::\"beach.jpg\"", qualified["resource"])
+        self.assertEqual({"type": "MyApp::User", "id": "bob"}, qualified["context"]["referrer"]["__entity"])
+        self.assertEqual(self.request, unqualify_request(qualified, "MyApp"))
+
+    def test_qualified_inputs_authorize_like_the_originals(self):
+        policies = 'permit(principal in Group::"admins", action == Action::"view", resource);'
+        request = {"principal": "User::\"alice\"", "action": "Action::\"view\"", "resource": "Photo::\"beach.jpg\""}
+        self.assertEqual(Decision.Allow, is_authorized(request, policies, self.entities).decision)
+
+        self.assertEqual(Decision.Allow, is_authorized(qualify_request(request, "MyApp"),
+                                                       qualify_policies(policies, "MyApp"),
+                                                       qualify_entities(self.entities, "MyApp")).decision)
+
+    def test_invalid_inputs_raise_value_error(self):
+        with self.assertRaises(ValueError):
+            qualify_policies(self.policies, "My App")
+        with self.assertRaises(ValueError):
+            qualify_policies("permit(principal, action, resource)", "MyApp")
+        with self.assertRaises(ValueError):
+            qualify_entities("not json", "MyApp")
+        with self.assertRaises(ValueError):
+            qualify_request({"principal": "alice"}, "MyApp")
@@ -0,0 +1,83 @@
+import datetime
+import json
+import unittest
+from unittest import mock
+
+from cedarpy import is_authorized, is_authorized_batch, CedarEngine, Decision, EntityStore, is_in
+
+
+class NativeInputsTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.policies = ('permit(principal, action, resource) when {\n'
+                         '  principal.level > 5 && principal.active && principal.teams.contains("eng") &&\n'
+                         '  resource.owner == principal && context.mfa && context.device.trusted\n'
+                         '};')
+        self.entities = [
+            {"uid": {"type": "User", "id": "alice"}, "attrs": {"level": 7, "active": True, "teams": ("eng", "ops")},
+             "parents": []},
+            {"uid": {"type": "User", "id": "bob"}, "attrs": {"level": 1, "active": False, "teams": []},
+             "parents": []},
+            {"uid": {"type": "Doc", "id": "plan"}, "attrs": {"owner": {"__entity": {"type": "User", "id": "alice"}}},
+             "parents": []},
+        ]
+        self.request = {"principal": 'User::"alice"', "action": 'Action::"view"', "resource": 'Doc::"plan"',
+                        "context": {"mfa": True, "device": {"trusted": True}}}
+
+    def test_lists_and_dicts_decide_as_their_json_does(self):
+        requests = [self.request, dict(self.request, principal='User::"bob"'),
+                    dict(self.request, context={"mfa": True, "device": {"trusted": 1}})]
+        native = is_authorized_batch(requests, self.policies, self.entities, include_metrics=False)
+        as_json = is_authorized_batch([dict(r, context=json.dumps(r["context"])) for r in requests], self.policies,
+                                      json.dumps(self.entities), include_metrics=False)
+
+        # bools stay bools, though they're ints in Python, so the last request's policy errors
+        self.assertEqual([Decision.Allow, Decision.Deny, Decision.Deny], [r.decision for r in native])
+        self.assertEqual(1, len(native[2].diagnostics.errors))
+        self.assertEqual([r.to_dict() for r in as_json], [r.to_dict() for r in native])
+
+    def test_inputs_are_not_changed(self):
+        entities = json.loads(json.dumps(self.entities))
+        request = json.loads(json.dumps(self.request))
+
+        self.assertEqual(Decision.Allow, is_authorized(request, self.policies, entities).decision)
+        self.assertEqual(json.loads(json.dumps(self.entities)), entities)
+        self.assertEqual(json.loads(json.dumps(self.request)), request)
+
+    def test_values_json_cannot_hold_raise(self):
+        for request, entities, error, message in [
+            (self.request, [dict(self.entities[0], attrs={"since": datetime.date(2024, 1, 1)})], TypeError,
+             "Object of type date is not JSON serializable"),
+            (dict(self.request, context={"tags": {"a", "b"}}), self.entities, TypeError,
+             "Object of type set is not JSON serializable"),
+            (dict(self.request, context={1: True}), self.entities, TypeError, "keys must be str, not int"),
+            (self.request, [dict(self.entities[0], attrs={"level": 2 ** 64})], ValueError,
+             "18446744073709551616 is too large for a JSON number"),
+            (dict(self.request, context={"score": float("nan")}), self.entities, ValueError,
+             "nan is not a JSON number"),
+        ]:
+            with self.subTest(message=message), self.assertRaises(error) as cm:
+                is_authorized(request, self.policies, entities)
+            self.assertIn(message, str(cm.exception))
+
+    def test_every_entry_point_converts_lists_in_rust(self):
+        bob = dict(self.entities[1], attrs={"level": 9, "active": True, "teams": ["eng"]})
+        with mock.patch("cedarpy.json.dumps", side_effect=AssertionError("converted with json.dumps")):
+            engine = CedarEngine(self.policies, self.entities)
+            self.assertEqual(Decision.Allow, engine.is_authorized(self.request).decision)
+
+            engine.reload(self.policies, self.entities[1:])
+            self.assertEqual(Decision.Deny, engine.is_authorized(self.request).decision)
+
+            update = engine.begin_update()
+            update.set_entities(self.entities)
+            update.commit()
+            self.assertEqual(Decision.Allow, engine.is_authorized(self.request).decision)
+
+            store = EntityStore(self.entities[:1])
+            store.add_entities(self.entities[2:])
+            self.assertFalse(store.upsert_entity(bob))
+            self.assertTrue(store.upsert_entity(self.entities[1]))
+            self.assertEqual(3, len(store))
+
+            self.assertFalse(is_in(self.entities, 'User::"alice"', 'User::"bob"'))
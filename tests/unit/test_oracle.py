@@ -0,0 +1,89 @@
+import unittest
+
+from cedarpy import assert_never_allowed, is_authorized, Decision
+
+
+class AssertNeverAllowedTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.schema = {
+            "": {
+                "entityTypes": {
+                    "User": {"memberOfTypes": ["Group"], "shape": {"type": "Record", "attributes": {}}},
+                    "Group": {"shape": {"type": "Record", "attributes": {}}},
+                    "Tenant": {"shape": {"type": "Record", "attributes": {}}},
+                },
+                "actions": {
+                    "admin": {},
+                    "deleteTenant": {
+                        "memberOf": [{"id": "admin"}],
+                        "appliesTo": {
+                            "principalTypes": ["User"],
+                            "resourceTypes": ["Tenant"],
+                            "context": {"type": "Record", "attributes": {"mfa": {"type": "Boolean"}}},
+                        },
+                    },
+                    "viewTenant": {"appliesTo": {"principalTypes": ["User"], "resourceTypes": ["Tenant"]}},
+                },
+            }
+        }
+        self.policies = """
+            permit(principal in Group::"admins", action in Action::"admin", resource);
+            permit(principal, action == Action::"viewTenant", resource);
+        """
+        self.outside_admins = {
+            "principal": {"type": "User", "not_in": 'Group::"admins"'},
+            "action": {"in": 'Action::"admin"'},
+        }
+
+    def test_an_invariant_the_policies_keep_holds(self):
+        report = assert_never_allowed(self.outside_admins, self.policies, self.schema)
+
+        self.assertTrue(report.holds)
+        self.assertIsNone(report.counterexample)
+        self.assertGreater(report.requests_checked, 0)
+
+    def test_a_violation_is_reported_with_a_counterexample(self):
+        policies = self.policies + """
+            permit(principal == User::"mallory", action == Action::"deleteTenant", resource) when { context.mfa };
+        """
+
+        report = assert_never_allowed(self.outside_admins, policies, self.schema)
+
+        self.assertFalse(report.holds)
+        counterexample = report.counterexample
+        self.assertEqual('User::"mallory"', counterexample['request']['principal'])
+        self.assertEqual('Action::"deleteTenant"', counterexample['request']['action'])
+        self.assertEqual({"mfa": True}, counterexample['request']['context'])
+        self.assertEqual(["policy2"], counterexample['reason'])
+        # the counterexample reproduces with the generated model
+        self.assertEqual(Decision.Allow,
+                         is_authorized(counterexample['request'], policies, report.entities, self.schema).decision)
+
+    def test_the_given_entities_are_the_model(self):
+        entities = [
+            {"uid": {"type": "User", "id": "alice"}, "attrs": {}, "parents": [{"type": "Group", "id": "admins"}]},
+            {"uid": {"type": "User", "id": "bob"}, "attrs": {}, "parents": []},
+            {"uid": {"type": "Group", "id": "admins"}, "attrs": {}, "parents": []},
+            {"uid": {"type": "Tenant", "id": "acme"}, "attrs": {}, "parents": []},
+        ]
+
+        report = assert_never_allowed({"principal": 'User::"bob"', "action": 'Action::"deleteTenant"'},
+                                      self.policies, self.schema, entities=entities)
+        self.assertTrue(report.holds)
+        self.assertEqual(4, report.requests_checked)
+        self.assertIsNone(report.entities)
+
+        report = assert_never_allowed({"action": 'Action::"deleteTenant"'}, self.policies, self.schema,
+                                      entities=entities)
+        self.assertFalse(report.holds)
+        self.assertEqual('User::"alice"', report.counterexample['request']['principal'])
+
+    def test_patterns_matching_too_many_requests_raise(self):
+        with self.assertRaises(ValueError) as cm:
+            assert_never_allowed({}, self.policies, self.schema, max_requests=10)
+        self.assertIn("more than max_requests (10)", str(cm.exception))
+
+        with self.assertRaises(ValueError) as cm:
+            assert_never_allowed({"principal": {"kind": "User"}}, self.policies, self.schema)
+        self.assertIn("invalid request pattern", str(cm.exception))
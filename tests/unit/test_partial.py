@@ -0,0 +1,92 @@
+import unittest
+
+from cedarpy import parse_policy_partial, check_parse
+
+
+class ParsePolicyPartialTestCase(unittest.TestCase):
+    def test_valid_policies_are_described(self):
+        text = '@id("alice") permit(principal == User::"alice", action, resource) when { resource.private };'
+        parse = parse_policy_partial(text)
+
+        self.assertTrue(parse["valid"])
+        self.assertEqual([], parse["diagnostics"])
+        policy, = parse["policies"]
+        self.assertTrue(policy["complete"])
+        self.assertEqual(("permit", [["id", "alice"]]), (policy["effect"], policy["annotations"]))
+        self.assertEqual(["principal", "action", "resource"], [constraint["variable"] for constraint in policy["scope"]])
+        self.assertEqual('principal == User::"alice"', policy["scope"][0]["text"])
+        condition, = policy["conditions"]
+        self.assertEqual(("when", "resource.private"), (condition["kind"], condition["expression"]))
+        span = condition["span"]
+        self.assertEqual("when { resource.private }", text[span["start"]:span["end"]])
+
+    def test_unfinished_policy_has_best_effort_structure(self):
+        text = 'permit(principal, action, resource);\n// the next one\nforbid(principal, action in [Action::"delete"'
+        parse = parse_policy_partial(text)
+
+        self.assertFalse(parse["valid"])
+        self.assertEqual([True, False], [policy["complete"] for policy in parse["policies"]])
+        unfinished = parse["policies"][1]
+        self.assertEqual("forbid", unfinished["effect"])
+        self.assertEqual('action in [Action::"delete"', unfinished["scope"][1]["text"])
+        self.assertEqual(3, unfinished["span"]["line"])
+
+        diagnostic, = parse["diagnostics"]
+        self.assertEqual("error", diagnostic["severity"])
+        self.assertRegex(diagnostic["message"], "^unexpected end of input; expected one of .*`]`")
+        self.assertEqual((len(text), 3, 46), (diagnostic["span"]["start"], diagnostic["span"]["line"],
+                                              diagnostic["span"]["column"]))
+
+    def test_diagnostics_have_precise_spans(self):
+        text = 'permit(principal, action, resource) when { principal. };\npermit(principal, action, resource, é);'
+        parse = parse_policy_partial(text)
+
+        self.assertEqual(2, len(parse["diagnostics"]))
+        syntax, well_formedness = parse["diagnostics"]
+        self.assertRegex(syntax["message"], "^unexpected `}`; expected one of .*an identifier")
+        self.assertEqual("}", text[syntax["span"]["start"]:syntax["span"]["end"]])
+        self.assertEqual((1, 55), (syntax["span"]["line"], syntax["span"]["column"]))
+        self.assertEqual([None], [condition["expression"] for condition in parse["policies"][0]["conditions"]])
+
+        # spans are in characters, so they index the text
+        self.assertIn("unexpected `é`", well_formedness["message"])
+        self.assertEqual("é", text[well_formedness["span"]["start"]:well_formedness["span"]["end"]])
+
+    def test_semantic_errors_span_the_policy(self):
+        text = 'permit(principal, action, resource, context);'
+        diagnostic, = parse_policy_partial(text)["diagnostics"]
+        self.assertIn("extra variables", diagnostic["message"])
+        self.assertEqual((0, len(text)), (diagnostic["span"]["start"], diagnostic["span"]["end"]))
+
+    def test_empty_text_has_no_policies(self):
+        self.assertEqual({"policies": [], "diagnostics": [], "valid": True}, parse_policy_partial("  // nothing yet\n"))
+
+
+class CheckParseTestCase(unittest.TestCase):
+    def test_every_error_is_reported_with_its_policy_and_text(self):
+        text = ('permit(principal, action, resource);\n'
+                'permit(principal, action resource);\n'
+                'forbid(principal, action, resource) when { 1 + };')
+        issues = check_parse(text)
+
+        self.assertEqual([("policy1", 2, 26, "resource"), ("policy2", 3, 48, "}")],
+                         [(issue.policy_id, issue.line, issue.column, issue.text) for issue in issues])
+        self.assertTrue(issues[0].message.startswith("unexpected `resource`; expected one of"))
+        for issue in issues:
+            self.assertEqual(issue.text, text[issue.span["start"]:issue.span["end"]])
+            self.assertEqual(issue.message, issue["message"])
+
+    def test_valid_policies_have_no_issues(self):
+        self.assertEqual([], check_parse('permit(principal == User::"alice", action, resource) when { true };'))
+        self.assertEqual([], check_parse(""))
+
+    def test_unfinished_and_poorly_formed_policies_are_reported(self):
+        unfinished, = check_parse("permit(principal, action, resource)")
+        self.assertEqual(("policy0", 1, 36, ""), (unfinished.policy_id, unfinished.line, unfinished.column,
+                                                  unfinished.text))
+        self.assertIn("unexpected end of input", unfinished.message)
+
+        text = 'permit(principal, action, resource);\npermit(principal in User, action, resource);'
+        poorly_formed, = check_parse(text)
+        self.assertEqual(("policy1", 2, 1), (poorly_formed.policy_id, poorly_formed.line, poorly_formed.column))
+        self.assertEqual(text.splitlines()[1], poorly_formed.text)
@@ -0,0 +1,61 @@
+import unittest
+
+from cedarpy import compile_policies, is_authorized, is_authorized_batch, Decision
+
+
+class PolicyResultsTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.policies = """
+            permit(principal, action == Action::"edit", resource) when { principal.level > 5 };
+            permit(principal == User::"alice", action, resource);
+            forbid(principal, action, resource) when { resource.locked };
+            forbid(principal, action == Action::"delete", resource);
+        """
+        self.entities = [
+            {"uid": {"type": "User", "id": "alice"}, "attrs": {}, "parents": []},
+            {"uid": {"type": "Doc", "id": "plan"}, "attrs": {"locked": True}, "parents": []},
+        ]
+        self.request = {"principal": 'User::"alice"', "action": 'Action::"edit"', "resource": 'Doc::"plan"',
+                        "context": {}}
+
+    def results(self, authz_result) -> dict:
+        return {policy["id"]: policy["result"] for policy in authz_result.diagnostics.policy_results}
+
+    def test_each_policy_reports_whether_it_was_satisfied(self):
+        authz_result = is_authorized(self.request, self.policies, self.entities, include_policy_results=True)
+
+        self.assertEqual(Decision.Deny, authz_result.decision)
+        self.assertEqual(["policy2"], authz_result.diagnostics.reasons)
+        # the permits that the forbid overrode are reported too
+        self.assertEqual({"policy0": "errored", "policy1": "satisfied", "policy2": "satisfied",
+                          "policy3": "not_applicable"}, self.results(authz_result))
+        self.assertEqual(["policy0", "policy1", "policy2", "policy3"],
+                         [policy["id"] for policy in authz_result.diagnostics.policy_results])
+        self.assertEqual(["permit", "permit", "forbid", "forbid"],
+                         [policy["effect"] for policy in authz_result.diagnostics.policy_results])
+
+    def test_errored_policies_report_their_error(self):
+        authz_result = is_authorized(self.request, self.policies, self.entities, include_policy_results=True)
+
+        errored = authz_result.diagnostics.policy_results[0]
+        self.assertIn("level", errored["error"])
+        self.assertNotIn("error", authz_result.diagnostics.policy_results[1])
+
+    def test_template_links_are_evaluated_with_their_slots(self):
+        handle = compile_policies('permit(principal == ?principal, action, resource == ?resource);',
+                                  [{"templateId": "policy0", "newId": "alice_plan",
+                                    "values": {"?principal": 'User::"alice"', "?resource": 'Doc::"plan"'}},
+                                   {"templateId": "policy0", "newId": "bob_plan",
+                                    "values": {"?principal": 'User::"bob"', "?resource": 'Doc::"plan"'}}])
+
+        authz_result = is_authorized(self.request, handle, self.entities, include_policy_results=True)
+
+        self.assertEqual(Decision.Allow, authz_result.decision)
+        self.assertEqual({"alice_plan": "satisfied", "bob_plan": "not_applicable"}, self.results(authz_result))
+
+    def test_policy_results_are_left_out_unless_requested(self):
+        authz_results = is_authorized_batch([self.request], self.policies, self.entities)
+
+        self.assertEqual([], authz_results[0].diagnostics.policy_results)
+        self.assertNotIn("policy_results", authz_results[0].to_dict()["diagnostics"])
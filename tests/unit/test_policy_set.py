@@ -0,0 +1,222 @@
+import unittest
+
+from cedarpy import (compile_policies, is_authorized, is_authorized_batch, is_authorized_batch_decisions,
+                     is_authorized_expanded, Decision, PolicySetHandle)
+
+from unit import load_file_as_json, load_file_as_str
+
+
+class PolicySetHandleTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.schema = load_file_as_str("resources/sandbox_b/schema.json")
+        self.entities: list = load_file_as_json("resources/sandbox_b/entities.json")
+        self.policies = """
+            permit(principal == User::"alice", action == Action::"view", resource);
+            forbid(principal, action == Action::"delete", resource) unless { context.authenticated };
+        """
+        self.request = {
+            "principal": 'User::"alice"',
+            "action": 'Action::"view"',
+            "resource": 'Photo::"alice_w2.jpg"',
+            "context": {"authenticated": True},
+        }
+
+    def test_compiled_policies_decide_like_their_text(self):
+        handle: PolicySetHandle = compile_policies(self.policies)
+        self.assertEqual(2, handle.num_policies)
+        self.assertGreaterEqual(handle.parse_duration_micros, 0)
+
+        expected = is_authorized(self.request, self.policies, self.entities, self.schema)
+        actual = is_authorized(self.request, handle, self.entities, self.schema)
+        self.assertEqual(Decision.Allow, actual.decision)
+        self.assertEqual(expected.diagnostics.reasons, actual.diagnostics.reasons)
+        self.assertEqual({"policy_set": handle.fingerprint}, actual.fingerprints)
+        self.assertEqual(expected.fingerprints, actual.fingerprints)
+
+        requests = [self.request, dict(self.request, action='Action::"delete"', context={"authenticated": False})]
+        self.assertEqual([Decision.Allow, Decision.Deny],
+                         [r.decision for r in is_authorized_batch(requests, handle, self.entities, self.schema)])
+        decisions = is_authorized_batch_decisions(requests, handle, self.entities, self.schema)
+        self.assertEqual([True, False], [decisions[i] for i in range(len(requests))])
+        expanded = is_authorized_expanded('User::"alice"', ['Action::"view"'], ['Photo::"alice_w2.jpg"'], handle,
+                                          self.entities, self.schema)
+        self.assertEqual([Decision.Allow], [r.decision for r in expanded])
+
+    def test_invalid_policies_fail_to_compile(self):
+        with self.assertRaises(ValueError) as cm:
+            compile_policies("permit(principal, action, resource)")
+        self.assertEqual("policy_parse_error", cm.exception.code)
+
+        with self.assertRaises(TypeError) as cm:
+            is_authorized(self.request, 42, self.entities, self.schema)
+        self.assertIn("policies must be a str or a PolicySetHandle", str(cm.exception))
+
+
+class TemplateLinkTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.policies = """
+            permit(principal == ?principal, action == Action::"view", resource in ?resource);
+            permit(principal, action == Action::"edit", resource) when { principal == resource.owner };
+        """
+        self.entities = [
+            {"uid": {"type": "Photo", "id": "beach"}, "attrs": {"owner": {"__entity": {"type": "User", "id": "bob"}}},
+             "parents": [{"type": "Album", "id": "trips"}]},
+            {"uid": {"type": "Photo", "id": "cat"}, "attrs": {"owner": {"__entity": {"type": "User", "id": "bob"}}},
+             "parents": [{"type": "Album", "id": "pets"}]},
+        ]
+        self.alice_views_trips = {
+            "templateId": "policy0",
+            "newId": "alice-trips",
+            "values": {"?principal": 'User::"alice"', "?resource": {"type": "Album", "id": "trips"}},
+        }
+
+    def view(self, user: str, photo: str) -> dict:
+        return {"principal": f'User::"{user}"', "action": 'Action::"view"', "resource": f'Photo::"{photo}"',
+                "context": {}}
+
+    def test_templates_decide_nothing_until_linked(self):
+        handle = compile_policies(self.policies)
+        self.assertEqual(1, handle.num_policies)
+        self.assertEqual(1, handle.num_templates)
+        self.assertEqual(Decision.Deny, is_authorized(self.view("alice", "beach"), handle, self.entities).decision)
+
+    def test_linked_templates_decide_requests(self):
+        handle = compile_policies(self.policies, template_links=[self.alice_views_trips])
+        self.assertEqual(2, handle.num_policies)
+
+        authz_result = is_authorized(self.view("alice", "beach"), handle, self.entities)
+        self.assertEqual(Decision.Allow, authz_result.decision)
+        self.assertEqual(["alice-trips"], authz_result.diagnostics.reasons)
+        self.assertEqual({"alice-trips": "policy0"}, authz_result.diagnostics.reason_templates)
+        self.assertEqual(Decision.Deny, is_authorized(self.view("alice", "cat"), handle, self.entities).decision)
+
+        # static policies aren't templates' links
+        edit = dict(self.view("bob", "cat"), action='Action::"edit"')
+        authz_result = is_authorized(edit, handle, self.entities)
+        self.assertEqual(["policy1"], authz_result.diagnostics.reasons)
+        self.assertEqual({}, authz_result.diagnostics.reason_templates)
+
+    def test_link_template_makes_a_new_handle(self):
+        handle = compile_policies(self.policies, template_links=[self.alice_views_trips])
+
+        linked = handle.link_template("policy0", "bob-pets", {"?principal": 'User::"bob"', "?resource": 'Album::"pets"'})
+
+        self.assertEqual(3, linked.num_policies)
+        self.assertEqual(2, handle.num_policies)
+        self.assertNotEqual(handle.fingerprint, linked.fingerprint)
+        requests = [self.view("alice", "beach"), self.view("bob", "cat")]
+        self.assertEqual([Decision.Allow, Decision.Allow],
+                         [r.decision for r in is_authorized_batch(requests, linked, self.entities)])
+        self.assertEqual([Decision.Allow, Decision.Deny],
+                         [r.decision for r in is_authorized_batch(requests, handle, self.entities)])
+
+    def test_invalid_links_raise(self):
+        handle = compile_policies(self.policies)
+        for template_id, link_id, values, message in [
+            ("policy9", "x", {"?principal": 'User::"a"', "?resource": 'Album::"b"'}, "failed to link x"),
+            ("policy0", "policy1", {"?principal": 'User::"a"', "?resource": 'Album::"b"'}, "failed to link policy1"),
+            ("policy0", "x", {"?principal": 'User::"a"'}, "failed to link x"),
+            ("policy0", "x", {"?principal": 'User::"a"', "?owner": 'User::"b"'}, "invalid slot ?owner"),
+            ("policy0", "x", {"?principal": 'alice', "?resource": 'Album::"b"'}, "invalid entity uid"),
+        ]:
+            with self.subTest(message=message), self.assertRaises(ValueError) as cm:
+                handle.link_template(template_id, link_id, values)
+            self.assertIn(message, str(cm.exception))
+            self.assertEqual("invalid_argument", cm.exception.code)
+
+        with self.assertRaises(ValueError) as cm:
+            compile_policies(self.policies, template_links=[{"templateId": "policy0"}])
+        self.assertIn("invalid template links", str(cm.exception))
+
+
+class EditPolicySetHandleTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.policies = """
+            permit(principal == ?principal, action == Action::"view", resource);
+            permit(principal == User::"alice", action == Action::"view", resource);
+        """
+        self.bob_views = {"templateId": "policy0", "newId": "bob-views", "values": {"?principal": 'User::"bob"'}}
+        self.entities = []
+
+    def view(self, user: str) -> dict:
+        return {"principal": f'User::"{user}"', "action": 'Action::"view"', "resource": 'Photo::"beach"',
+                "context": {}}
+
+    def test_add_policy_makes_a_new_handle(self):
+        handle = compile_policies(self.policies)
+
+        added = handle.add_policy('permit(principal == User::"carol", action, resource);', id="carol-all")
+        added = added.add_policy('forbid(principal == User::"alice", action, resource);')
+
+        self.assertEqual(["policy1", "policy2", "carol-all"], added.list_policy_ids())
+        self.assertEqual(["policy1"], handle.list_policy_ids())
+        self.assertNotEqual(handle.fingerprint, added.fingerprint)
+        self.assertEqual([Decision.Deny, Decision.Allow],
+                         [r.decision for r in is_authorized_batch([self.view("alice"), self.view("carol")], added,
+                                                                  self.entities)])
+        self.assertIn('User::"carol"', added.get_policy("carol-all"))
+        self.assertIsNone(handle.get_policy("carol-all"))
+
+    def test_fingerprints_are_of_the_policies_rather_than_the_edits(self):
+        handle = compile_policies(self.policies)
+        carol = 'permit(principal == User::"carol", action, resource);'
+        dave = 'permit(principal == User::"dave", action, resource);'
+
+        carol_then_dave = handle.add_policy(carol, id="carol").add_policy(dave, id="dave")
+        dave_then_carol = handle.add_policy(dave, id="dave").add_policy(carol, id="carol")
+        self.assertEqual(carol_then_dave.fingerprint, dave_then_carol.fingerprint)
+        self.assertEqual(handle.add_policy(carol, id="carol").fingerprint,
+                         carol_then_dave.remove_policy("dave").fingerprint)
+
+        linked = handle.link_template("policy0", "bob-views", {"?principal": 'User::"bob"'})
+        self.assertEqual(linked.fingerprint,
+                         linked.add_policy(carol, id="carol").remove_policy("carol").fingerprint)
+
+        # different policies, ids, or links have different fingerprints
+        swapped_ids = handle.add_policy(carol, id="dave").add_policy(dave, id="carol")
+        self.assertNotEqual(carol_then_dave.fingerprint, swapped_ids.fingerprint)
+        self.assertNotEqual(linked.fingerprint,
+                            handle.link_template("policy0", "bob-views", {"?principal": 'User::"carol"'}).fingerprint)
+        self.assertEqual(carol_then_dave.fingerprint,
+                         is_authorized(self.view("carol"), carol_then_dave, self.entities).fingerprints["policy_set"])
+
+    def test_remove_policy_keeps_the_other_policies_and_links(self):
+        handle = compile_policies(self.policies, template_links=[self.bob_views])
+
+        removed = handle.remove_policy("policy1")
+
+        self.assertEqual(["bob-views"], removed.list_policy_ids())
+        self.assertEqual(Decision.Allow, is_authorized(self.view("bob"), removed, self.entities).decision)
+        self.assertEqual(Decision.Deny, is_authorized(self.view("alice"), removed, self.entities).decision)
+        self.assertEqual(Decision.Allow, is_authorized(self.view("alice"), handle, self.entities).decision)
+
+        unlinked = removed.remove_policy("bob-views").remove_policy("policy0")
+        self.assertEqual([], unlinked.list_policy_ids())
+        self.assertEqual(0, unlinked.num_templates)
+
+    def test_invalid_additions_raise(self):
+        handle = compile_policies(self.policies)
+        for text, id, code, message in [
+            ('permit(principal, action, resource', None, "policy_parse_error", "policy parse errors"),
+            ('permit(principal == ?principal, action, resource);', None, "policy_parse_error", "policy parse errors"),
+            ('permit(principal, action, resource);', "policy1", "invalid_argument", "failed to add policy policy1"),
+            ('permit(principal, action, resource);', "policy0", "invalid_argument", "failed to add policy policy0"),
+        ]:
+            with self.subTest(text=text, id=id), self.assertRaises(ValueError) as cm:
+                handle.add_policy(text, id)
+            self.assertIn(message, str(cm.exception))
+            self.assertEqual(code, cm.exception.code)
+
+    def test_invalid_removals_raise(self):
+        handle = compile_policies(self.policies, template_links=[self.bob_views])
+        for id, message in [
+            ("policy9", "failed to remove policy policy9: there's no such policy"),
+            ("policy0", "failed to remove template policy0: it's linked by bob-views"),
+        ]:
+            with self.subTest(id=id), self.assertRaises(ValueError) as cm:
+                handle.remove_policy(id)
+            self.assertIn(message, str(cm.exception))
+            self.assertEqual("invalid_argument", cm.exception.code)
@@ -0,0 +1,86 @@
+import unittest
+
+from cedarpy import compile_policies, export_entitlements, import_relationships, is_authorized, Decision
+
+
+class ImportRelationshipsTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.mapping = {
+            "relations": {
+                "member": {"parent": True},
+                "viewer": {"template": "policy0"},
+                "Folder#viewer": {"template": "policy1", "slots": ["?resource"]},
+            },
+            "types": {"user": "User", "group": "Group", "doc": "Doc", "folder": "Folder"},
+        }
+        self.policies = """
+            permit(principal in ?principal, action == Action::"view", resource == ?resource);
+            permit(principal, action == Action::"list", resource == ?resource);
+        """
+
+    def view(self, user: str, doc: str) -> dict:
+        return {"principal": f'User::"{user}"', "action": 'Action::"view"', "resource": f'Doc::"{doc}"',
+                "context": {}}
+
+    def test_tuples_become_parents_and_template_links(self):
+        imported = import_relationships(["group:eng#member@user:alice",
+                                         "group:eng#member@user:alice",
+                                         "doc:plan#viewer@group:eng#member",
+                                         "folder:q3#viewer@user:bob"],
+                                        self.mapping)
+
+        self.assertEqual([
+            {"uid": {"type": "User", "id": "alice"}, "attrs": {}, "parents": [{"type": "Group", "id": "eng"}]},
+            {"uid": {"type": "Group", "id": "eng"}, "attrs": {}, "parents": []},
+        ], imported["entities"])
+        self.assertEqual([
+            {"templateId": "policy0", "newId": 'policy0/Group::"eng"/Doc::"plan"',
+             "values": {"?principal": 'Group::"eng"', "?resource": 'Doc::"plan"'}},
+            {"templateId": "policy1", "newId": 'policy1/Folder::"q3"', "values": {"?resource": 'Folder::"q3"'}},
+        ], imported["template_links"])
+
+        policies = compile_policies(self.policies, imported["template_links"])
+        self.assertEqual(Decision.Allow, is_authorized(self.view("alice", "plan"), policies,
+                                                       imported["entities"]).decision)
+        self.assertEqual(Decision.Deny, is_authorized(self.view("bob", "plan"), policies,
+                                                      imported["entities"]).decision)
+
+    def test_parents_are_added_to_the_entities_given(self):
+        entities = [{"uid": {"type": "User", "id": "alice"}, "attrs": {"level": 3},
+                     "parents": [{"type": "Group", "id": "staff"}]}]
+
+        imported = import_relationships([{"subject": {"type": "User", "id": "alice"}, "relation": "member",
+                                          "object": {"type": "Group", "id": "eng"}}],
+                                        self.mapping, entities)
+
+        self.assertEqual({"uid": {"type": "User", "id": "alice"}, "attrs": {"level": 3},
+                          "parents": [{"type": "Group", "id": "staff"}, {"type": "Group", "id": "eng"}]},
+                         imported["entities"][0])
+        self.assertEqual(2, len(imported["entities"]))
+
+    def test_exported_entitlements_import_as_links(self):
+        entities = [{"uid": {"type": "User", "id": "bob smith"}, "attrs": {}, "parents": []},
+                    {"uid": {"type": "Doc", "id": "a:b"}, "attrs": {}, "parents": []}]
+        exported = export_entitlements('permit(principal, action, resource);', entities,
+                                       actions=['Action::"view"'], principal_types=["User"], resource_types=["Doc"],
+                                       output_format="zanzibar")
+
+        imported = import_relationships(exported.splitlines(), {"relations": {"view": {"template": "policy0"}}})
+
+        self.assertEqual([{"?principal": 'User::"bob smith"', "?resource": 'Doc::"a:b"'}],
+                         [link["values"] for link in imported["template_links"]])
+
+    def test_invalid_tuples_and_mappings_raise(self):
+        with self.assertRaises(ValueError) as cm:
+            import_relationships(["doc:plan#owner@user:alice"], self.mapping)
+        self.assertIn("no mapping for relation owner of Doc", str(cm.exception))
+
+        with self.assertRaises(ValueError) as cm:
+            import_relationships(["doc:plan@user:alice"], self.mapping)
+        self.assertIn("invalid relationship tuple", str(cm.exception))
+
+        with self.assertRaises(ValueError) as cm:
+            import_relationships(["doc:plan#viewer@user:alice"], {"relations": {"viewer": {"parent": True,
+                                                                                          "template": "policy0"}}})
+        self.assertIn("invalid mapping of viewer", str(cm.exception))
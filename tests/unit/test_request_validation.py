@@ -0,0 +1,80 @@
+import unittest
+
+from cedarpy import is_authorized, is_authorized_batch, Decision
+
+
+class RequestValidationTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.schema = {
+            "": {
+                "entityTypes": {
+                    "User": {"shape": {"type": "Record", "attributes": {}}},
+                    "Photo": {"shape": {"type": "Record", "attributes": {}}},
+                },
+                "actions": {
+                    "view": {"appliesTo": {"principalTypes": ["User"], "resourceTypes": ["Photo"],
+                                           "context": {"type": "Record", "attributes": {
+                                               "authenticated": {"type": "Boolean"},
+                                               "referrer": {"type": "String", "required": False},
+                                           }}}},
+                },
+            }
+        }
+        self.policies = 'permit(principal, action, resource);'
+        self.entities = [
+            {"uid": {"type": "User", "id": "alice"}, "attrs": {}, "parents": []},
+            {"uid": {"type": "Photo", "id": "a.jpg"}, "attrs": {}, "parents": []},
+        ]
+
+    def request(self, action='Action::"view"', principal='User::"alice"', resource='Photo::"a.jpg"',
+                context=None) -> dict:
+        return {"principal": principal, "action": action, "resource": resource,
+                "context": {"authenticated": True} if context is None else context}
+
+    def assert_invalid(self, request: dict, message: str, code: str = "request_error"):
+        authz_result = is_authorized(request, self.policies, self.entities, self.schema, validate_request=True)
+        self.assertEqual(Decision.NoDecision, authz_result.decision)
+        self.assertEqual([code], authz_result.diagnostics.error_codes)
+        self.assertIn(message, authz_result.diagnostics.errors[0])
+
+    def test_conforming_requests_are_evaluated(self):
+        for context in [{"authenticated": True}, {"authenticated": False, "referrer": "mail"}]:
+            authz_result = is_authorized(self.request(context=context), self.policies, self.entities, self.schema,
+                                         validate_request=True)
+            self.assertEqual(Decision.Allow, authz_result.decision)
+
+    def test_requests_that_do_not_conform_have_no_decision(self):
+        self.assert_invalid(self.request(action='Action::"fly"'), 'action Action::"fly" is not declared in the schema')
+        self.assert_invalid(self.request(principal='Photo::"a.jpg"'),
+                            'action Action::"view" does not apply to a principal of type Photo; expected User')
+        self.assert_invalid(self.request(resource='User::"alice"'),
+                            'does not apply to a resource of type User; expected Photo')
+        self.assert_invalid(self.request(context={"authenticated": "yes", "via": "app"}),
+                            "the context does not conform to action Action::\"view\": attribute authenticated is "
+                            "not of type Boolean; attribute via is not declared")
+        self.assert_invalid(self.request(context={}), "missing required attribute authenticated")
+
+        # without validation, Cedar decides a request with a principal of another type
+        authz_result = is_authorized(self.request(principal='Photo::"a.jpg"'), self.policies, self.entities,
+                                     self.schema)
+        self.assertEqual(Decision.Allow, authz_result.decision)
+
+    def test_an_invalid_schema_fails_every_request(self):
+        schema = {"": {"entityTypes": {"User": {"memberOfTypes": ["Group"]}}, "actions": {}}}
+
+        authz_results = is_authorized_batch([self.request(), self.request()], self.policies, self.entities, schema,
+                                            entity_validation="none", validate_request=True)
+
+        for authz_result in authz_results:
+            self.assertEqual(Decision.NoDecision, authz_result.decision)
+            self.assertEqual(["schema_error"], authz_result.diagnostics.error_codes)
+            self.assertTrue(authz_result.diagnostics.errors[0].startswith("invalid schema"))
+
+        authz_result = is_authorized(self.request(), self.policies, self.entities, schema, entity_validation="none")
+        self.assertEqual(Decision.Allow, authz_result.decision)
+
+    def test_without_a_schema_requests_are_not_validated(self):
+        authz_result = is_authorized(self.request(action='Action::"fly"'), self.policies, self.entities,
+                                     validate_request=True)
+        self.assertEqual(Decision.Allow, authz_result.decision)
@@ -0,0 +1,65 @@
+import unittest
+
+from cedarpy import compile_policies, is_authorized_partial, unknown, Decision
+
+
+class PartialAuthorizationTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.policies = """
+            permit(principal, action == Action::"view", resource) when { resource.owner == principal };
+            permit(principal in Group::"admins", action, resource);
+            forbid(principal, action, resource) when { context.risk > 5 };
+        """
+        self.entities = [
+            {"uid": {"type": "User", "id": "alice"}, "attrs": {}, "parents": []},
+            {"uid": {"type": "User", "id": "root"}, "attrs": {}, "parents": [{"type": "Group", "id": "admins"}]},
+            {"uid": {"type": "Group", "id": "admins"}, "attrs": {}, "parents": []},
+            {"uid": {"type": "Doc", "id": "plan"}, "attrs": {"owner": {"__entity": {"type": "User", "id": "alice"}}},
+             "parents": []},
+        ]
+
+    def test_an_unknown_resource_leaves_residuals(self):
+        result = is_authorized_partial({"principal": 'User::"alice"', "action": 'Action::"view"',
+                                        "context": {"risk": 1}},
+                                       self.policies, self.entities)
+
+        self.assertIsNone(result.decision)
+        self.assertEqual(["policy0"], [residual["id"] for residual in result.residuals])
+        residual = result.residuals[0]
+        self.assertEqual("permit", residual["effect"])
+        self.assertIn('unknown(resource)["owner"]', residual["policy"])
+        self.assertEqual({"Unknown": {"name": "resource"}},
+                         residual["json"]["conditions"][0]["body"]["&&"]["right"]["=="]["left"]["."]["left"])
+        self.assertEqual([], result.errors)
+
+    def test_known_parts_that_decide_the_request_give_a_decision(self):
+        result = is_authorized_partial({"principal": 'User::"root"', "action": 'Action::"delete"',
+                                        "context": {"risk": 1}},
+                                       compile_policies(self.policies), self.entities)
+        self.assertEqual(Decision.Allow, result.decision)
+        self.assertEqual(["policy1"], result.reasons)
+        self.assertEqual([], result.residuals)
+
+        result = is_authorized_partial({"principal": 'User::"alice"', "action": 'Action::"view"',
+                                        "resource": 'Doc::"plan"', "context": {"risk": 9}},
+                                       self.policies, self.entities)
+        self.assertEqual(Decision.Deny, result.decision)
+        self.assertEqual(["policy2"], result.reasons)
+
+    def test_unknown_context_attributes_leave_the_forbids_that_use_them(self):
+        result = is_authorized_partial({"principal": 'User::"alice"', "action": 'Action::"view"',
+                                        "resource": 'Doc::"plan"', "context": {"risk": unknown("risk")}},
+                                       self.policies, self.entities)
+
+        self.assertIsNone(result.decision)
+        # the satisfied permit remains as a trivially true residual alongside the forbid
+        self.assertEqual([("policy0", "permit"), ("policy2", "forbid")],
+                         [(residual["id"], residual["effect"]) for residual in result.residuals])
+        self.assertIn('unknown(risk)', result.residuals[1]["policy"])
+        self.assertEqual(["policy0"], result.reasons)
+
+    def test_invalid_requests_raise(self):
+        with self.assertRaises(ValueError) as cm:
+            is_authorized_partial({"principal": "alice", "action": 'Action::"view"'}, self.policies, self.entities)
+        self.assertIn("failed to parse principal alice as entity Uid", str(cm.exception))
@@ -0,0 +1,127 @@
+import json
+import unittest
+
+from textwrap import dedent
+
+from cedarpy import is_authorized, Decision, rewrite_policies, rewrite_entities
+
+
+class RewritePoliciesTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.policies = dedent("""
+            // User::"alice" is mentioned in this comment
+            @id("view-photos")
+            permit(
+                principal in UserGroup::"family",
+                action in [Action::"view", Action::"comment"],
+                resource
+            )
+            when { resource.owner == User::"alice" || context.tag == "User::\\"alice\\"" };
+
+            forbid(principal, action == Action::"view", resource)
+            unless { principal has user && principal.user == User::"alice" };
+        """).strip()
+
+    def test_rename_entity_type(self):
+        expect_result = dedent("""
+            // User::"alice" is mentioned in this comment
+            @id("view-photos")
+            permit(
+                principal in UserGroup::"family",
+                action in [Action::"view", Action::"comment"],
+                resource
+            )
+            when { resource.owner == Person::"alice" || context.tag == "User::\\"alice\\"" };
+
+            forbid(principal, action == Action::"view", resource)
+            unless { principal has user && principal.user == Person::"alice" };
+        """).strip()
+
+        self.assertEqual(expect_result, rewrite_policies(self.policies, {"User": "Person"}))
+
+    def test_rename_action(self):
+        rewritten = rewrite_policies(self.policies, {'Action::"view"': 'Action::"read"'})
+
+        self.assertIn('action in [Action::"read", Action::"comment"]', rewritten)
+        self.assertIn('forbid(principal, action == Action::"read", resource)', rewritten)
+
+    def test_uid_rename_takes_precedence_over_type_rename(self):
+        rewritten = rewrite_policies(self.policies, {"Action": "Photos::Action",
+                                                     'Action::"view"': 'Photos::Action::"read"'})
+
+        self.assertIn('action in [Photos::Action::"read", Photos::Action::"comment"]', rewritten)
+
+    def test_rename_escapes_ids(self):
+        self.assertEqual('permit(principal == User::"bob \\"the builder\\"", action, resource);',
+                         rewrite_policies('permit(principal == User::"alice", action, resource);',
+                                          {'User::"alice"': 'User::"bob \\"the builder\\""'}))
+
+    def test_no_renames_leaves_policies_unchanged(self):
+        self.assertEqual(self.policies, rewrite_policies(self.policies, {}))
+
+    def test_invalid_inputs_raise_value_error(self):
+        with self.assertRaises(ValueError):
+            rewrite_policies("permit(principal, action, resource)", {"User": "Person"})
+        with self.assertRaises(ValueError):
+            rewrite_policies(self.policies, {"User": "Not A Type"})
+        with self.assertRaises(ValueError):
+            rewrite_policies(self.policies, {'Action::"view"': "Action"})
+
+
+class RewriteEntitiesTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.entities = [
+            {
+                "uid": {"type": "User", "id": "alice"},
+                "attrs": {"manager": {"__entity": {"type": "User", "id": "bob"}},
+                          "profile": {"type": "User", "id": "not an entity reference"}},
+                "parents": [{"__expr": "UserGroup::\"family\""}]
+            },
+            {
+                "uid": {"__entity": {"type": "Photo", "id": "beach.jpg"}},
+                "attrs": {"owner": {"__expr": "User::\"alice\""},
+                          "tagged": [{"__entity": {"type": "User", "id": "carol"}}]},
+                "parents": []
+            },
+        ]
+
+    def test_rename_entity_type(self):
+        rewritten = rewrite_entities(self.entities, {"User": "Person"})
+
+        self.assertEqual({"type": "Person", "id": "alice"}, rewritten[0]["uid"])
+        self.assertEqual({"__entity": {"type": "Person", "id": "bob"}}, rewritten[0]["attrs"]["manager"])
+        self.assertEqual({"type": "User", "id": "not an entity reference"}, rewritten[0]["attrs"]["profile"])
+        self.assertEqual([{"__expr": "UserGroup::\"family\""}], rewritten[0]["parents"])
+        self.assertEqual({"__expr": "Person::\"alice\""}, rewritten[1]["attrs"]["owner"])
+        self.assertEqual([{"__entity": {"type": "Person", "id": "carol"}}], rewritten[1]["attrs"]["tagged"])
+
+    def test_rename_entity(self):
+        rewritten = rewrite_entities(self.entities, {'UserGroup::"family"': 'UserGroup::"relatives"'})
+
+        self.assertEqual([{"__expr": "UserGroup::\"relatives\""}], rewritten[0]["parents"])
+
+    def test_rewrite_entities_as_str(self):
+        rewritten = rewrite_entities('[{"uid": {"type": "User", "id": "alice"}, "attrs": {}, "parents": []}]',
+                                     {"User": "Person"})
+
+        self.assertIsInstance(rewritten, str)
+        self.assertEqual([{"uid": {"type": "Person", "id": "alice"}, "attrs": {}, "parents": []}], json.loads(rewritten))
+
+    def test_rewritten_policies_and_entities_authorize_like_the_originals(self):
+        policies = 'permit(principal in UserGroup::"family", action, resource) when { resource.owner == principal };'
+        request = {"principal": 'User::"alice"', "action": 'Action::"view"', "resource": 'Photo::"beach.jpg"'}
+        self.assertEqual(Decision.Allow, is_authorized(request, policies, self.entities).decision)
+
+        renames = {"User": "Person", "Photo": "Image"}
+        request = {"principal": 'Person::"alice"', "action": 'Action::"view"', "resource": 'Image::"beach.jpg"'}
+        self.assertEqual(Decision.Allow, is_authorized(request,
+                                                       rewrite_policies(policies, renames),
+                                                       rewrite_entities(self.entities, renames)).decision)
+
+    def test_invalid_inputs_raise_value_error(self):
+        with self.assertRaises(ValueError):
+            rewrite_entities('{"not": "a list"}', {"User": "Person"})
+        with self.assertRaises(ValueError):
+            rewrite_entities(self.entities, {"User": "Not A Type"})
@@ -0,0 +1,165 @@
+import unittest
+
+from cedarpy import authorization_surface, conformance_report, schema_docs
+from unit import load_file_as_str
+
+
+class SchemaDocsTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.schema = {
+            "Acme": {
+                "commonTypes": {
+                    "Address": {
+                        "type": "Record",
+                        "attributes": {
+                            "city": {"type": "String"},
+                            "zip": {"type": "String", "required": False},
+                        },
+                    },
+                },
+                "entityTypes": {
+                    "User": {
+                        "memberOfTypes": ["Group"],
+                        "shape": {
+                            "type": "Record",
+                            "attributes": {
+                                "address": {"type": "Address"},
+                                "manager": {"type": "Entity", "name": "User", "required": False},
+                            },
+                        },
+                    },
+                    "Group": {},
+                    "Doc": {},
+                },
+                "actions": {
+                    "read": {
+                        "appliesTo": {
+                            "principalTypes": ["User"],
+                            "resourceTypes": ["Doc"],
+                            "context": {"type": "Record", "attributes": {"ip": {"type": "Extension", "name": "ipaddr"}}},
+                        },
+                    },
+                    "write": {"memberOf": [{"id": "read"}]},
+                },
+            },
+        }
+
+    def test_entity_types_are_documented(self):
+        docs = schema_docs(self.schema)
+
+        self.assertEqual(["Acme::User", "Acme::Group", "Acme::Doc"],
+                         [entity_type["name"] for entity_type in docs["entity_types"]])
+        user = docs["entity_types"][0]
+        self.assertEqual(["Acme::Group"], user["member_of_types"])
+        self.assertEqual([("address", "Record", True),
+                          ("address.city", "String", True),
+                          ("address.zip", "String", False),
+                          ("manager", "Entity<Acme::User>", False)],
+                         [(attribute["name"], attribute["type"], attribute["required"])
+                          for attribute in user["attributes"]])
+
+    def test_actions_and_hierarchy_are_documented(self):
+        docs = schema_docs(self.schema)
+
+        read, write = docs["actions"]
+        self.assertEqual('Acme::Action::"read"', read["name"])
+        self.assertEqual(["Acme::User"], read["principal_types"])
+        self.assertEqual(["Acme::Doc"], read["resource_types"])
+        self.assertEqual([{"name": "ip", "type": "Extension<ipaddr>", "required": True}], read["context"])
+        self.assertIsNone(write["principal_types"])
+        self.assertEqual({'Acme::Action::"read"': [], 'Acme::Action::"write"': ['Acme::Action::"read"']},
+                         docs["hierarchy"]["actions"])
+        self.assertEqual(["Acme::Group"], docs["hierarchy"]["entity_types"]["Acme::User"])
+
+    def test_sandbox_schema_is_documented(self):
+        docs = schema_docs(load_file_as_str("resources/sandbox_b/schema.json"))
+
+        self.assertIn('Action::"view"', [action["name"] for action in docs["actions"]])
+
+    def test_invalid_schema_raises(self):
+        with self.assertRaises(ValueError) as cm:
+            schema_docs({"": {"entityTypes": 3}})
+        self.assertEqual("schema_error", cm.exception.code)
+
+
+class AuthorizationSurfaceTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.schema = {
+            "": {
+                "entityTypes": {
+                    "User": {"memberOfTypes": ["Group"]},
+                    "Group": {},
+                    "Doc": {"memberOfTypes": ["Folder"]},
+                    "Folder": {},
+                },
+                "actions": {
+                    "read": {"appliesTo": {"principalTypes": ["User", "Group"], "resourceTypes": ["Doc"]},
+                             "memberOf": [{"id": "all"}]},
+                    "write": {"appliesTo": {"principalTypes": ["User"], "resourceTypes": ["Doc", "Folder"]},
+                              "memberOf": [{"id": "all"}]},
+                    "all": {},
+                },
+            },
+        }
+        self.policies = """
+            permit(principal in Group::"readers", action == Action::"read", resource);
+            forbid(principal, action in Action::"all", resource in Folder::"secret");
+            permit(principal == ?principal, action == Action::"write", resource == Doc::"notes");
+        """
+
+    def test_combinations_list_the_policies_that_could_apply(self):
+        surface = authorization_surface(self.schema, self.policies)
+
+        self.assertEqual([("User", 'Action::"read"', "Doc", ["policy0"], ["policy1"]),
+                          ("Group", 'Action::"read"', "Doc", ["policy0"], ["policy1"]),
+                          ("User", 'Action::"write"', "Doc", ["policy2"], ["policy1"]),
+                          ("User", 'Action::"write"', "Folder", [], ["policy1"])],
+                         [(c["principal_type"], c["action"], c["resource_type"], c["permits"], c["forbids"])
+                          for c in surface.combinations])
+        self.assertEqual([], surface.uncovered)
+
+    def test_uncovered_combinations_are_highlighted(self):
+        surface = authorization_surface(self.schema, 'permit(principal, action == Action::"read", resource);')
+
+        self.assertEqual([("User", "Doc"), ("User", "Folder")],
+                         [(c["principal_type"], c["resource_type"]) for c in surface.uncovered])
+
+
+class ConformanceReportTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.schema = load_file_as_str("resources/sandbox_b/schema.json")
+
+    def test_conforming_entities_are_counted(self):
+        report = conformance_report(load_file_as_str("resources/sandbox_b/entities.json"), self.schema)
+
+        self.assertEqual(report["entities"], report["conforming"])
+        self.assertEqual(0, report["invalid_uids"])
+
+    def test_problems_are_counted_per_entity_type(self):
+        entities = [
+            {"uid": {"__entity": {"type": "User", "id": "alice"}},
+             "attrs": {"department": "eng", "jobLevel": 5}, "parents": []},
+            {"uid": {"__entity": {"type": "User", "id": "bob"}},
+             "attrs": {"department": 7, "nickname": "b"}, "parents": [{"__entity": {"type": "Photo", "id": "p"}}]},
+            {"uid": {"__entity": {"type": "User", "id": "carol"}},
+             "attrs": {"department": "ops"}, "parents": [{"__entity": {"type": "UserGroup", "id": "g"}}]},
+            {"uid": {"__entity": {"type": "Robot", "id": "r2"}}, "attrs": {}, "parents": []},
+            {"uid": {"__entity": {"type": "Action", "id": "view"}}, "attrs": {}, "parents": []},
+            {"uid": "not a uid", "attrs": {}, "parents": []},
+        ]
+
+        report = conformance_report(entities, self.schema)
+
+        self.assertEqual((6, 2, 1), (report["entities"], report["conforming"], report["invalid_uids"]))
+        user, robot, action = report["entity_types"]
+        self.assertEqual(("User", True, 3, 1),
+                         (user["entity_type"], user["in_schema"], user["entities"], user["conforming"]))
+        self.assertEqual({"jobLevel": 2}, user["missing_attributes"])
+        self.assertEqual({"nickname": 1}, user["extra_attributes"])
+        self.assertEqual({"department": 1}, user["type_mismatches"])
+        self.assertEqual(1, user["invalid_parents"])
+        self.assertEqual(("Robot", False, 0), (robot["entity_type"], robot["in_schema"], robot["conforming"]))
+        self.assertEqual(("Action", True, 1), (action["entity_type"], action["in_schema"], action["conforming"]))
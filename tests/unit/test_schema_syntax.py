@@ -0,0 +1,78 @@
+import json
+import unittest
+
+from cedarpy import schema_docs, schema_from_json_str, schema_to_json_str
+from unit import load_file_as_str
+
+
+class SchemaSyntaxTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.cedar_schema = """
+            // the people and documents of Acme
+            namespace Acme {
+                type Address = { city: String, zip?: String };
+                entity User in [Group] {
+                    address: Address,
+                    manager?: User,
+                    "display name": String,
+                };
+                entity Group, Doc;
+                action "read" appliesTo {
+                    principal: [User],
+                    resource: [Doc],
+                    context: { ip: ipaddr, tags: Set<String> },
+                };
+                action "write" in ["read"];
+            }
+        """
+
+    def test_schema_to_json_str(self):
+        schema = json.loads(schema_to_json_str(self.cedar_schema))
+        acme = schema["Acme"]
+        self.assertEqual({"type": "Record",
+                          "attributes": {"city": {"type": "String"}, "zip": {"type": "String", "required": False}}},
+                         acme["commonTypes"]["Address"])
+        self.assertEqual(["User", "Group", "Doc"], list(acme["entityTypes"]))
+        self.assertEqual(["Group"], acme["entityTypes"]["User"]["memberOfTypes"])
+        self.assertEqual({"address": {"type": "Address"},
+                          "manager": {"type": "Entity", "name": "User", "required": False},
+                          "display name": {"type": "String"}},
+                         acme["entityTypes"]["User"]["shape"]["attributes"])
+        self.assertEqual({"ip": {"type": "Extension", "name": "ipaddr"},
+                          "tags": {"type": "Set", "element": {"type": "String"}}},
+                         acme["actions"]["read"]["appliesTo"]["context"]["attributes"])
+        self.assertEqual([{"id": "read"}], acme["actions"]["write"]["memberOf"])
+        self.assertNotIn("appliesTo", acme["actions"]["write"])
+
+    def test_round_trip(self):
+        schema = load_file_as_str("resources/sandbox_b/schema.json")
+        text = schema_from_json_str(schema)
+        self.assertIn('action "addPhoto" appliesTo {', text)
+        self.assertIn("entity Photo in [Account, Album] {", text)
+        round_tripped = schema_to_json_str(text)
+        self.assertEqual(schema_docs(schema), schema_docs(round_tripped))
+        self.assertEqual(text, schema_from_json_str(round_tripped))
+
+        # the syntax round trips through JSON too
+        self.assertEqual(schema_to_json_str(self.cedar_schema),
+                         schema_to_json_str(schema_from_json_str(schema_to_json_str(self.cedar_schema))))
+
+    def test_invalid_schema_syntax(self):
+        with self.assertRaises(ValueError) as cm:
+            schema_to_json_str("entity User {\n  name String };")
+        self.assertIn("line 2, column 8: expected ':', found 'String'", str(cm.exception))
+
+        # the translation is checked by Cedar, which knows no entity type Group
+        with self.assertRaises(ValueError) as cm:
+            schema_to_json_str("entity User in [Group];")
+        self.assertIn("Group", str(cm.exception))
+
+    def test_common_type_shape_is_written_as_its_record(self):
+        schema = {"": {"commonTypes": {"Named": {"type": "Record", "attributes": {"name": {"type": "String"}}}},
+                       "entityTypes": {"User": {"shape": {"type": "Named"}}},
+                       "actions": {}}}
+        text = schema_from_json_str(schema)
+        self.assertIn("type Named = {\n    name: String\n};", text)
+        self.assertIn("entity User {\n    name: String\n};", text)
+        self.assertEqual(schema_docs(schema), schema_docs(schema_to_json_str(text)))
@@ -0,0 +1,92 @@
+import gzip
+import json
+import os
+import tempfile
+import unittest
+
+from cedarpy import is_authorized_batch, Decision
+
+from unit import load_file_as_json, load_file_as_str
+
+
+class ResultFileTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.schema = load_file_as_str("resources/sandbox_b/schema.json")
+        self.entities: list = load_file_as_json("resources/sandbox_b/entities.json")
+        self.policies = 'permit(principal == User::"alice", action == Action::"view", resource);'
+        allowed = {
+            "principal": 'User::"alice"',
+            "action": 'Action::"view"',
+            "resource": 'Photo::"alice_w2.jpg"',
+            "context": {"authenticated": True},
+        }
+        denied = dict(allowed, principal='User::"bob"')
+        self.requests = [allowed, denied, allowed]
+        self.directory = tempfile.TemporaryDirectory()
+
+    def tearDown(self) -> None:
+        self.directory.cleanup()
+        super().tearDown()
+
+    def path(self, name: str) -> str:
+        return os.path.join(self.directory.name, name)
+
+    def test_writes_results_as_ndjson(self):
+        path = self.path("results.ndjson")
+        summary = is_authorized_batch(self.requests, self.policies, self.entities, self.schema, output_path=path)
+
+        with open(path) as f:
+            written = [json.loads(line) for line in f]
+        self.assertEqual({"requests": 3, "allowed": 2, "denied": 1, "no_decision": 0, "errored": 0, "retryable": 0,
+                          "bytes_written": os.path.getsize(path)}, summary)
+        expected = is_authorized_batch(self.requests, self.policies, self.entities, self.schema)
+        self.assertEqual([result.decision.value for result in expected], [line["decision"] for line in written])
+
+    def test_gzip_is_chosen_by_suffix(self):
+        requests = self.requests * 500
+        path = self.path("results.ndjson.gz")
+        summary = is_authorized_batch(requests, self.policies, self.entities, self.schema,
+                                      output_path=path, include_metrics=False)
+
+        with gzip.open(path, "rt") as f:
+            written = [json.loads(line) for line in f]
+        self.assertEqual(len(requests), len(written))
+        self.assertEqual([Decision.Allow.value, Decision.Deny.value, Decision.Allow.value] * 500,
+                         [line["decision"] for line in written])
+
+        plain_path = self.path("results.ndjson")
+        plain_summary = is_authorized_batch(requests, self.policies, self.entities, self.schema,
+                                            output_path=plain_path, include_metrics=False)
+        self.assertLess(summary["bytes_written"] * 5, plain_summary["bytes_written"])
+        with gzip.open(path, "rb") as f, open(plain_path, "rb") as plain:
+            self.assertEqual(plain.read(), f.read())
+
+    def test_compression_may_be_chosen_explicitly(self):
+        path = self.path("results.gz")
+        is_authorized_batch(self.requests, self.policies, self.entities, self.schema,
+                            output_path=path, output_compression="none")
+        with open(path) as f:
+            self.assertEqual(3, len(f.readlines()))
+
+        with self.assertRaisesRegex(ValueError, "invalid output compression 'zip'"):
+            is_authorized_batch(self.requests, self.policies, self.entities, self.schema,
+                                output_path=self.path("results.zip"), output_compression="zip")
+
+    def test_requests_that_cannot_be_decided_are_written(self):
+        path = self.path("results.ndjson")
+        summary = is_authorized_batch(self.requests, "this is not a real policy", self.entities, self.schema,
+                                      output_path=path)
+
+        self.assertEqual(3, summary["no_decision"])
+        with open(path) as f:
+            self.assertEqual([Decision.NoDecision.value] * 3, [json.loads(line)["decision"] for line in f])
+
+    def test_invalid_destinations_are_rejected(self):
+        with self.assertRaisesRegex(ValueError, "on_result"):
+            is_authorized_batch(self.requests, self.policies, self.entities, self.schema,
+                                on_result=print, output_path=self.path("results.ndjson"))
+
+        with self.assertRaisesRegex(OSError, "failed to write results to"):
+            is_authorized_batch(self.requests, self.policies, self.entities, self.schema,
+                                output_path=self.path("missing/results.ndjson"))
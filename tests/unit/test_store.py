@@ -0,0 +1,124 @@
+import unittest
+
+from cedarpy import (CedarEngine, EntityStore, is_authorized, is_authorized_batch, is_authorized_batch_decisions,
+                     is_authorized_expanded, Decision)
+
+
+class EntityStoreTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.schema = {
+            "": {
+                "entityTypes": {
+                    "User": {"memberOfTypes": ["Group"],
+                             "shape": {"type": "Record", "attributes": {"level": {"type": "Long"}}}},
+                    "Group": {"memberOfTypes": ["Group"], "shape": {"type": "Record", "attributes": {}}},
+                    "Doc": {"shape": {"type": "Record", "attributes": {}}},
+                },
+                "actions": {
+                    "read": {"memberOf": [{"id": "any"}],
+                             "appliesTo": {"principalTypes": ["User"], "resourceTypes": ["Doc"]}},
+                    "any": {},
+                },
+            }
+        }
+        self.policies = """
+            permit(principal in Group::"staff", action in Action::"any", resource);
+            permit(principal, action == Action::"read", resource) when { principal.level > 5 };
+        """
+        self.entities = [
+            {"uid": {"type": "User", "id": "alice"}, "attrs": {"level": 1},
+             "parents": [{"type": "Group", "id": "engineering"}]},
+            {"uid": {"type": "User", "id": "bob"}, "attrs": {"level": 1}, "parents": []},
+            {"uid": {"type": "Group", "id": "engineering"}, "attrs": {},
+             "parents": [{"type": "Group", "id": "staff"}]},
+            {"uid": {"type": "Group", "id": "staff"}, "attrs": {}, "parents": []},
+            {"uid": {"type": "Doc", "id": "plan"}, "attrs": {}, "parents": []},
+        ]
+
+    def read(self, user: str) -> dict:
+        return {"principal": f'User::"{user}"', "action": 'Action::"read"', "resource": 'Doc::"plan"', "context": {}}
+
+    def test_a_store_decides_like_its_entities(self):
+        store = EntityStore(self.entities, self.schema)
+        self.assertEqual(5, len(store))
+        self.assertIn('User::"alice"', store)
+        self.assertNotIn('User::"carol"', store)
+
+        for request in [self.read("alice"), self.read("bob")]:
+            expected = is_authorized(request, self.policies, self.entities, self.schema)
+            actual = is_authorized(request, self.policies, store, self.schema)
+            self.assertEqual(expected.decision, actual.decision)
+            self.assertEqual(expected.diagnostics.reasons, actual.diagnostics.reasons)
+
+        requests = [self.read("alice"), self.read("bob")]
+        self.assertEqual([Decision.Allow, Decision.Deny],
+                         [r.decision for r in is_authorized_batch(requests, self.policies, store, self.schema)])
+        decisions = is_authorized_batch_decisions(requests, self.policies, store, self.schema)
+        self.assertEqual([True, False], [decisions[i] for i in range(len(requests))])
+        expanded = is_authorized_expanded('User::"alice"', ['Action::"read"'], ['Doc::"plan"'], self.policies,
+                                          store, self.schema)
+        self.assertEqual([Decision.Allow], [r.decision for r in expanded])
+
+    def test_changes_apply_to_the_next_call(self):
+        store = EntityStore(self.entities, self.schema)
+
+        self.assertFalse(store.upsert_entity({"uid": {"type": "User", "id": "carol"}, "attrs": {"level": 9},
+                                              "parents": []}))
+        self.assertEqual(Decision.Allow, is_authorized(self.read("carol"), self.policies, store, self.schema).decision)
+
+        self.assertTrue(store.upsert_entity({"uid": {"type": "User", "id": "carol"}, "attrs": {"level": 2},
+                                             "parents": []}))
+        self.assertEqual(Decision.Deny, is_authorized(self.read("carol"), self.policies, store, self.schema).decision)
+
+        # alice is in staff through engineering, and no longer once it's removed
+        self.assertTrue(store.remove_entity('Group::"engineering"'))
+        self.assertFalse(store.remove_entity('Group::"engineering"'))
+        self.assertEqual(Decision.Deny, is_authorized(self.read("alice"), self.policies, store, self.schema).decision)
+
+        store.add_entities([{"uid": {"type": "Group", "id": "engineering"}, "attrs": {},
+                             "parents": [{"type": "Group", "id": "staff"}]}])
+        self.assertEqual(Decision.Allow, is_authorized(self.read("alice"), self.policies, store, self.schema).decision)
+        self.assertEqual(6, len(store))
+
+    def test_invalid_changes_raise_and_change_nothing(self):
+        store = EntityStore(self.entities, self.schema)
+
+        with self.assertRaises(ValueError) as cm:
+            store.add_entities([{"uid": {"type": "User", "id": "dave"}, "attrs": {"level": 3}, "parents": []},
+                                {"uid": {"type": "User", "id": "bob"}, "attrs": {"level": 3}, "parents": []}])
+        self.assertIn('entity User::"bob" is already in the store', str(cm.exception))
+        self.assertEqual("entities_error", cm.exception.code)
+        self.assertNotIn('User::"dave"', store)
+
+        with self.assertRaises(ValueError) as cm:
+            store.upsert_entity({"uid": {"type": "User", "id": "dave"}, "attrs": {"level": "high"}, "parents": []})
+        self.assertIn("failed to parse entity", str(cm.exception))
+
+        with self.assertRaises(ValueError):
+            store.remove_entity("bob")
+        self.assertEqual(5, len(store))
+
+    def test_a_store_without_a_schema_takes_the_calls_actions(self):
+        store = EntityStore(self.entities)
+
+        authz_result = is_authorized(self.read("alice"), self.policies, store, self.schema)
+        self.assertEqual(Decision.Allow, authz_result.decision)
+        self.assertEqual(["policy0"], authz_result.diagnostics.reasons)
+
+    def test_an_engine_copies_a_stores_entities(self):
+        store = EntityStore(self.entities, self.schema)
+        engine = CedarEngine(self.policies, store, self.schema)
+        self.assertEqual(Decision.Allow, engine.is_authorized(self.read("alice")).decision)
+
+        # the engine keeps the entities it loaded until it's reloaded or updated
+        store.remove_entity('Group::"engineering"')
+        self.assertEqual(Decision.Allow, engine.is_authorized(self.read("alice")).decision)
+        engine.reload(self.policies, store, self.schema)
+        self.assertEqual(Decision.Deny, engine.is_authorized(self.read("alice")).decision)
+
+        store.upsert_entity({"uid": {"type": "User", "id": "alice"}, "attrs": {"level": 9}, "parents": []})
+        update = engine.begin_update()
+        update.set_entities(store)
+        update.commit()
+        self.assertEqual(Decision.Allow, engine.is_authorized(self.read("alice")).decision)
@@ -0,0 +1,78 @@
+import json
+import os
+import tempfile
+import unittest
+from pathlib import Path
+
+from cedarpy import entities_from_table, is_authorized, EntityStore, Decision
+
+
+class EntitiesFromTableTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.csv = ('user_id,dept,level,groups,manager\r\n'
+                    'alice,eng,7,"Group::""eng"";Group::""oncall""","User::""carol"""\r\n'
+                    '"bob, jr.","sales\nwest",,,\r\n')
+        self.mapping = {"type": "User", "id": "user_id",
+                        "attributes": {"department": "dept", "level": "level", "manager": "manager"},
+                        "types": {"level": "long", "manager": "entity"},
+                        "parents": "groups"}
+
+    def test_csv_rows_become_entities(self):
+        entities = json.loads(entities_from_table(self.csv, self.mapping))
+
+        self.assertEqual([
+            {"uid": {"type": "User", "id": "alice"},
+             "attrs": {"department": "eng", "level": 7, "manager": {"__entity": {"type": "User", "id": "carol"}}},
+             "parents": [{"type": "Group", "id": "eng"}, {"type": "Group", "id": "oncall"}]},
+            # quoted fields hold commas and line breaks, and empty cells are left out
+            {"uid": {"type": "User", "id": "bob, jr."}, "attrs": {"department": "sales\nwest"}, "parents": []},
+        ], entities)
+
+    def test_entities_from_a_file_decide_requests(self):
+        with tempfile.TemporaryDirectory() as tmp:
+            path = Path(tmp) / "users.csv"
+            path.write_text(self.csv)
+            store = EntityStore(entities_from_table(path, self.mapping))
+
+        self.assertEqual(2, len(store))
+        policies = 'permit(principal in Group::"oncall", action, resource) when { principal.level > 5 };'
+        request = {"principal": 'User::"alice"', "action": 'Action::"page"', "resource": 'Service::"api"',
+                   "context": {}}
+        self.assertEqual(Decision.Allow, is_authorized(request, policies, store).decision)
+
+    def test_columns_become_entities(self):
+        columns = {"uid": ['User::"alice"', 'User::"bob"'], "level": [7, None], "active": [True, False],
+                   "groups": [['Group::"eng"'], []]}
+
+        entities = json.loads(entities_from_table(columns, {"uid": "uid", "attributes": ["level", "active"],
+                                                            "parents": "groups"}))
+
+        self.assertEqual([
+            {"uid": {"type": "User", "id": "alice"}, "attrs": {"level": 7, "active": True},
+             "parents": [{"type": "Group", "id": "eng"}]},
+            {"uid": {"type": "User", "id": "bob"}, "attrs": {"active": False}, "parents": []},
+        ], entities)
+
+    def test_invalid_tables_and_mappings_raise(self):
+        for table, mapping, code, message in [
+            (self.csv, {"type": "User"}, "invalid_argument", "invalid table mapping"),
+            (self.csv, dict(self.mapping, types={"age": "long"}), "invalid_argument", "types has age"),
+            (self.csv, dict(self.mapping, id="email"), "entities_error", "there's no column 'email'"),
+            ('user_id,level\nalice,seven\n', {"type": "User", "id": "user_id", "attributes": ["level"],
+                                              "types": {"level": "long"}},
+             "entities_error", "row 1: invalid value \"seven\" in column 'level'"),
+            ('user_id,level\nalice\n', {"type": "User", "id": "user_id"}, "entities_error",
+             "row 1 has 1 fields, but the header has 2"),
+            ('user_id\n"alice\n', {"type": "User", "id": "user_id"}, "entities_error", "unterminated quoted field"),
+            ({"uid": ['User::"a"'], "level": []}, {"uid": "uid", "attributes": ["level"]}, "entities_error",
+             "column 'level' has 0 values"),
+        ]:
+            with self.subTest(message=message), self.assertRaises(ValueError) as cm:
+                entities_from_table(table, mapping)
+            self.assertIn(message, str(cm.exception))
+            self.assertEqual(code, cm.exception.code)
+
+        with self.assertRaises(ValueError) as cm:
+            entities_from_table(self.csv, self.mapping, format="xlsx")
+        self.assertIn("invalid format xlsx", str(cm.exception))
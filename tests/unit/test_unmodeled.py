@@ -0,0 +1,82 @@
+import unittest
+
+from cedarpy import CedarEngine, Decision
+
+
+class UnmodeledActionsTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.schema = {"": {
+            "entityTypes": {"User": {}, "Doc": {}},
+            "actions": {action: {"appliesTo": {"principalTypes": ["User"], "resourceTypes": ["Doc"]},
+                                 **({"memberOf": [{"id": "read"}]} if action == "view" else {})}
+                        for action in ["read", "view", "edit", "export"]},
+        }}
+        self.entities = [
+            {"uid": {"type": "User", "id": "alice"}, "attrs": {}, "parents": []},
+            {"uid": {"type": "Doc", "id": "plan"}, "attrs": {}, "parents": []},
+        ]
+        self.policies = 'permit(principal, action in [Action::"read"], resource);\n' \
+                        'forbid(principal, action == Action::"edit", resource) when { false };'
+
+    def request(self, action: str) -> dict:
+        return {"principal": 'User::"alice"', "action": f'Action::"{action}"', "resource": 'Doc::"plan"',
+                "context": {}}
+
+    def test_unmodeled_actions_are_denied_with_a_warning(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema, unmodeled_actions={"*": "deny"})
+
+        result = engine.is_authorized(self.request("export"))
+        self.assertEqual(Decision.Deny, result.decision)
+        self.assertEqual(['no policy references the action Action::"export", so it\'s denied as an unmodeled '
+                          'action; write a policy for it, or add it to an action group a policy names'],
+                         result.warnings)
+
+        # a policy naming the action, or one of its groups, references it
+        for action, decision in [("view", Decision.Allow), ("edit", Decision.Deny)]:
+            result = engine.is_authorized(self.request(action))
+            self.assertEqual(decision, result.decision)
+            self.assertEqual([], result.warnings)
+
+        # as with other warnings, without diagnostics too
+        [result] = engine.is_authorized_batch([self.request("export")], include_diagnostics=False)
+        self.assertEqual(1, len(result.warnings))
+
+    def test_configured_actions_are_left_undecided(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema,
+                             unmodeled_actions={'Action::"export"': "no_decision"})
+
+        result = engine.is_authorized(self.request("export"))
+        self.assertEqual(Decision.NoDecision, result.decision)
+        self.assertEqual(["unmodeled_action"], result.diagnostics.error_codes)
+        self.assertEqual('no policy references the action Action::"export", so it\'s undecided as an unmodeled action; '
+                         'write a policy for it, or add it to an action group a policy names',
+                         result.diagnostics.errors[0])
+        self.assertFalse(result.retryable)
+        self.assertFalse(engine.is_authorized_fast(self.request("export")))
+
+        # an unmodeled action without an outcome of its own is decided by Cedar, as it is by default
+        policies = 'permit(principal, action == Action::"view", resource);'
+        engine = CedarEngine(policies, self.entities, self.schema,
+                             unmodeled_actions={'Action::"export"': "no_decision"})
+        result = engine.is_authorized(self.request("edit"))
+        self.assertEqual(Decision.Deny, result.decision)
+        self.assertEqual([], result.warnings)
+
+    def test_unconstrained_action_scope_references_every_action(self):
+        policies = 'permit(principal, action, resource) when { principal == User::"bob" };'
+        engine = CedarEngine(policies, self.entities, self.schema, unmodeled_actions={"*": "no_decision"})
+        self.assertEqual(Decision.Deny, engine.is_authorized(self.request("export")).decision)
+
+        engine.reload(self.policies, self.entities, self.schema)
+        self.assertEqual(Decision.NoDecision, engine.is_authorized(self.request("export")).decision)
+
+    def test_invalid_unmodeled_actions(self):
+        with self.assertRaises(ValueError) as cm:
+            CedarEngine(self.policies, self.entities, self.schema, unmodeled_actions={"*": "allow"})
+        self.assertIn("invalid unmodeled action outcome 'allow'; expected one of: deny, no_decision",
+                      str(cm.exception))
+
+        with self.assertRaises(ValueError) as cm:
+            CedarEngine(self.policies, self.entities, self.schema, unmodeled_actions={"export": "deny"})
+        self.assertIn("'export' isn't an action's entity uid", str(cm.exception))
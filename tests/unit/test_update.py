@@ -0,0 +1,143 @@
+import threading
+import time
+import unittest
+
+from cedarpy import CedarEngine, Decision, InvalidArgumentError, ValidationError
+
+from unit import load_file_as_json, load_file_as_str
+
+
+class EngineUpdateTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.schema = load_file_as_str("resources/sandbox_b/schema.json")
+        self.entities: list = load_file_as_json("resources/sandbox_b/entities_current_syntax.json")
+        self.policies = 'permit(principal == User::"alice", action == Action::"view", resource);'
+        self.request = {"principal": 'User::"ahmad"', "action": 'Action::"view"', "resource": 'Photo::"vacation.jpg"',
+                        "context": {"authenticated": True}}
+        self.engine = CedarEngine(self.policies, self.entities, self.schema, actor="deploy-bot")
+
+    def stage(self, policies: str, entities: list, schema=None):
+        update = self.engine.begin_update(actor="alice")
+        update.set_policies(policies)
+        update.set_entities(entities)
+        update.set_schema(schema if schema is not None else self.schema)
+        return update
+
+    def test_commit_replaces_the_inputs_together(self):
+        self.assertEqual(Decision.Deny, self.engine.is_authorized(self.request).decision)
+        num_entities = self.engine.stats()["num_entities"]
+        update = self.stage('permit(principal == User::"ahmad", action == Action::"view", resource);',
+                            self.entities[1:])
+        self.assertTrue(update.validate()["valid"])
+        # nothing changes until the commit
+        self.assertEqual(Decision.Deny, self.engine.is_authorized(self.request).decision)
+
+        update.commit()
+        self.assertTrue(update.committed)
+        self.assertEqual(Decision.Allow, self.engine.is_authorized(self.request).decision)
+        self.assertEqual(num_entities - 1, self.engine.stats()["num_entities"])
+
+        update_entry = self.engine.changelog(kind="update")
+        self.assertEqual([(1, "update", "alice", True)],
+                         [(c["seq"], c["kind"], c["actor"], c["ok"]) for c in update_entry])
+        self.assertEqual(self.engine.stats()["fingerprints"], update_entry[0]["fingerprints"])
+
+        with self.assertRaises(InvalidArgumentError):
+            update.commit()
+
+    def test_validation_errors_block_the_commit(self):
+        before = self.engine.stats()["fingerprints"]
+        update = self.stage('permit(principal == Robot::"r2", action == Action::"view", resource);', self.entities)
+        report = update.validate()
+        self.assertFalse(report["valid"])
+        self.assertEqual("unrecognized_entity_type", report["errors"][0]["kind"])
+
+        with self.assertRaises(ValidationError) as cm:
+            update.commit()
+        self.assertIn("validation errors", str(cm.exception))
+        self.assertEqual("validation_error", cm.exception.code)
+        self.assertFalse(update.committed)
+        self.assertEqual(before, self.engine.stats()["fingerprints"])
+        self.assertEqual(["load"], [c["kind"] for c in self.engine.changelog()])
+
+        # staging replaces the inputs, and the validation with them
+        update.set_policies(self.policies)
+        update.commit()
+        self.assertEqual(["load", "update"], [c["kind"] for c in self.engine.changelog()])
+
+    def test_commit_fails_when_the_engine_changed_since_the_update_began(self):
+        num_entities = self.engine.stats()["num_entities"]
+        update = self.stage(self.policies, self.entities[1:])
+        self.engine.reload(self.policies, self.entities[2:], self.schema, actor="bob")
+
+        with self.assertRaises(InvalidArgumentError) as cm:
+            update.commit()
+        self.assertIn("the engine changed since it began", str(cm.exception))
+        self.assertEqual(num_entities - 2, self.engine.stats()["num_entities"])
+        self.assertEqual(["load", "reload"], [c["kind"] for c in self.engine.changelog()])
+
+    def test_inputs_that_dont_load_leave_the_engine_as_it_was(self):
+        update = self.stage("permit(", self.entities)
+        with self.assertRaises(ValueError):
+            update.commit()
+        # unlike a failed reload, a failed commit doesn't degrade the engine
+        self.assertFalse(self.engine.stats()["reload_failed"])
+        self.assertEqual(Decision.Allow, self.engine.is_authorized(dict(self.request, principal='User::"alice"',
+                                                                        resource='Photo::"alice_w2.jpg"')).decision)
+        self.assertEqual(["load"], [c["kind"] for c in self.engine.changelog()])
+
+    def test_inputs_that_arent_staged_are_kept(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema, entity_validation="strict")
+        fingerprints = engine.stats()["fingerprints"]
+        update = engine.begin_update()
+        update.set_policies('permit(principal == User::"ahmad", action == Action::"view", resource);')
+        update.commit()
+        self.assertEqual(Decision.Allow, engine.is_authorized(self.request).decision)
+        self.assertEqual(fingerprints["schema"], engine.stats()["fingerprints"]["schema"])
+        self.assertEqual([], engine.is_authorized(self.request).warnings)
+
+        # the entities are still validated against the kept schema
+        update = engine.begin_update()
+        update.set_entities(self.entities + [{"uid": {"type": "User", "id": "zed"}, "attrs": {"bogus": 1}, "parents": []}])
+        with self.assertRaises(ValueError):
+            update.commit()
+
+        # until the schema is removed explicitly
+        update = engine.begin_update()
+        update.set_schema(None)
+        update.commit()
+        self.assertNotIn("schema", engine.stats()["fingerprints"])
+
+    def test_commit_returns_the_version_to_read_its_writes_at(self):
+        self.assertEqual(1, self.engine.version())
+        update = self.stage('permit(principal == User::"ahmad", action == Action::"view", resource);', self.entities)
+        version = update.commit()
+        self.assertEqual((2, 2), (version, self.engine.version()))
+        self.assertEqual(len(self.engine.changelog()), self.engine.version())
+
+        self.assertEqual(Decision.Allow, self.engine.is_authorized(self.request, min_version=version).decision)
+        # a version the engine hasn't reached fails fast without a deadline
+        stale = self.engine.is_authorized(self.request, min_version=version + 1)
+        self.assertEqual(Decision.NoDecision, stale.decision)
+        self.assertEqual(["stale_version"], stale.diagnostics.error_codes)
+        self.assertEqual("stale version: the engine is at version 2, not yet 3", stale.diagnostics.errors[0])
+        self.assertTrue(stale.retryable)
+        self.assertEqual(["stale_version", "stale_version"],
+                         [result.diagnostics.error_codes[0]
+                          for result in self.engine.is_authorized_batch([self.request] * 2, min_version=3)])
+
+    def test_min_version_waits_until_the_deadline(self):
+        update = self.stage('permit(principal == User::"ahmad", action == Action::"view", resource);', self.entities)
+        committer = threading.Timer(0.1, update.commit)
+        committer.start()
+        try:
+            authz_result = self.engine.is_authorized(self.request, min_version=2, deadline=time.monotonic() + 10)
+        finally:
+            committer.join()
+        self.assertEqual(Decision.Allow, authz_result.decision)
+
+        t_start = time.monotonic()
+        stale = self.engine.is_authorized(self.request, min_version=3, deadline=t_start + 0.1)
+        self.assertEqual(["stale_version"], stale.diagnostics.error_codes)
+        self.assertGreaterEqual(time.monotonic() - t_start, 0.1)
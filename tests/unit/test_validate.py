@@ -0,0 +1,48 @@
+import unittest
+
+from cedarpy import validate_policies
+
+from unit import load_file_as_str
+
+
+class ValidatePoliciesTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.schema = load_file_as_str("resources/sandbox_b/schema.json")
+
+    def test_valid_policies_have_no_errors(self):
+        report = validate_policies('permit(principal == User::"alice", action == Action::"view", resource);',
+                                   self.schema)
+        self.assertEqual({"valid": True, "errors": [], "warnings": []}, report)
+
+    def test_errors_are_reported_with_their_policy_and_span(self):
+        policies = ('permit(principal == User::"alice", action == Action::"view", resource);\n'
+                    'permit(principal == Usr::"alice", action == Action::"view", resource);\n'
+                    'permit(principal, action == Action::"delete", resource) when { context.authenticated == 1 };\n')
+        report = validate_policies(policies, self.schema)
+        self.assertFalse(report["valid"])
+        self.assertEqual([("policy1", "unrecognized_entity_type"),
+                          ("policy1", "invalid_action_application"),
+                          ("policy2", "type_error")],
+                         [(error["policy_id"], error["kind"]) for error in report["errors"]])
+        self.assertEqual("Unrecognized entity type Usr, did you mean User?", report["errors"][0]["message"])
+        self.assertIsNone(report["errors"][0]["span"])
+
+        span = report["errors"][2]["span"]
+        self.assertEqual("context.authenticated == 1", policies[span["start"]:span["end"]])
+        self.assertEqual((3, 64), (span["line"], span["column"]))
+
+    def test_confusable_identifiers_are_warnings(self):
+        report = validate_policies('forbid(principal == User::"аlice", action, resource);', self.schema)
+        self.assertTrue(report["valid"])
+        self.assertEqual(["mixed_script_identifier"], [warning["kind"] for warning in report["warnings"]])
+
+    def test_invalid_inputs_raise_value_error(self):
+        with self.assertRaises(ValueError) as cm:
+            validate_policies("permit(principal, action, resource)", self.schema)
+        self.assertEqual("policy_parse_error", cm.exception.code)
+        with self.assertRaises(ValueError) as cm:
+            validate_policies("permit(principal, action, resource);", "{not json")
+        self.assertEqual("schema_error", cm.exception.code)
+        with self.assertRaises(ValueError):
+            validate_policies("permit(principal, action, resource);", self.schema, mode="lenient")
@@ -0,0 +1,92 @@
+import unittest
+
+from cedarpy import CedarEngine
+
+from unit import load_file_as_json, load_file_as_str
+
+
+class EngineViewTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.schema = load_file_as_str("resources/sandbox_b/schema.json")
+        self.entities: list = load_file_as_json("resources/sandbox_b/entities.json")
+        self.policies = '\n'.join([
+            '@id("alice-view") permit(principal == User::"alice", action == Action::"view", resource);',
+            'forbid(principal, action == Action::"delete", resource);',
+        ])
+        self.engine = CedarEngine(self.policies, self.entities, self.schema)
+
+    def test_view_reports_policies_and_entities(self):
+        view = self.engine.view(redact=["jobLevel"])
+
+        policies = view.policies()
+        self.assertEqual(["policy0", "policy1"], [policy["policy_id"] for policy in policies])
+        self.assertEqual({"policy_id": "policy0", "effect": "permit", "annotations": {"id": "alice-view"},
+                          "template_id": None},
+                         {key: value for key, value in policies[0].items() if key != "text"})
+        self.assertIn('User::"alice"', policies[0]["text"])
+        self.assertEqual(policies[1], view.policy("policy1"))
+        self.assertIsNone(view.policy("policy2"))
+
+        alice = view.entity('User::"alice"')
+        self.assertEqual({"department": "HardwareEngineering", "jobLevel": "<redacted>"}, alice["attrs"])
+        self.assertIn(alice, view.entities())
+        self.assertTrue(all(entity["attrs"].get("jobLevel", "<redacted>") == "<redacted>"
+                            for entity in view.entities()))
+        self.assertIsNone(view.entity('User::"nobody"'))
+        self.assertTrue(view.is_in('User::"alice"', 'UserGroup::"AVTeam"'))
+
+        summary = view.summary()
+        self.assertEqual(self.engine.stats()["fingerprints"], summary["fingerprints"])
+        self.assertEqual((2, self.engine.stats()["num_entities"], ["jobLevel"]),
+                         (summary["num_policies"], summary["num_entities"], summary["redacted_attributes"]))
+
+    def test_view_is_a_read_only_snapshot(self):
+        view = self.engine.view()
+        self.assertEqual(5, view.entity('User::"alice"')["attrs"]["jobLevel"])
+
+        # changing what a view returns doesn't change the view
+        view.entity('User::"alice"')["attrs"]["jobLevel"] = 9
+        view.entities().clear()
+        self.assertEqual(5, view.entity('User::"alice"')["attrs"]["jobLevel"])
+
+        # the Rust view has no attributes to set, and no way back to the engine
+        with self.assertRaises(AttributeError):
+            view._view.loaded = None
+        self.assertFalse(any(not name.startswith("__") for name in dir(view._view)
+                             if name not in {"policies", "policy", "entities", "entity", "is_in", "summary"}))
+
+        self.engine.reload('forbid(principal, action, resource);', self.entities[1:], self.schema)
+        self.assertEqual(2, len(view.policies()))
+        self.assertIsNotNone(view.entity('User::"alice"'))
+        self.assertEqual(1, len(self.engine.view().policies()))
+
+    def test_nested_attributes_are_redacted(self):
+        entities = [{"uid": {"type": "User", "id": "zed"}, "parents": [],
+                     "attrs": {"profile": {"name": "Zed", "ssn": "123-45-6789"},
+                               "history": [{"ssn": "987-65-4321"}],
+                               "badge": {"id": "B-7"},
+                               "manager": {"__entity": {"type": "User", "id": "alice"}}}}]
+        engine = CedarEngine('permit(principal, action, resource) when { principal.profile.ssn == "123-45-6789" };',
+                             entities, entity_validation="none")
+        view = engine.view(redact=["ssn", "id"])
+        self.assertEqual({"profile": {"name": "Zed", "ssn": "<redacted>"}, "history": [{"ssn": "<redacted>"}],
+                          "badge": {"id": "<redacted>"},
+                          "manager": {"__entity": {"type": "User", "id": "alice"}}},
+                         view.entity('User::"zed"')["attrs"])
+        # policies aren't redacted
+        self.assertIn("123-45-6789", view.policies()[0]["text"])
+
+    def test_redacted_names_must_match_attributes(self):
+        self.engine.view(redact=["jobLevel", "department"])
+
+        with self.assertRaises(ValueError) as cm:
+            self.engine.view(redact=["User.jobLevel", "jobLevel", "joblevel"])
+        self.assertIn("invalid redact: 'User.jobLevel', 'joblevel' matches no attribute of the schema or the entities",
+                      str(cm.exception))
+        self.assertEqual("invalid_argument", cm.exception.code)
+
+    def test_invalid_uid_is_rejected(self):
+        with self.assertRaisesRegex(ValueError, "failed to parse alice as entity Uid") as cm:
+            self.engine.view().entity("alice")
+        self.assertEqual("invalid_argument", cm.exception.code)
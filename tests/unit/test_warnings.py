@@ -0,0 +1,87 @@
+import time
+import unittest
+
+from cedarpy import is_authorized, CedarEngine, Decision
+
+from unit import load_file_as_json, load_file_as_str
+
+
+class WarningsTestCase(unittest.TestCase):
+    def setUp(self) -> None:
+        super().setUp()
+        self.schema = load_file_as_str("resources/sandbox_b/schema.json")
+        self.entities: list = load_file_as_json("resources/sandbox_b/entities_current_syntax.json")
+        self.policies = 'permit(principal == User::"alice", action == Action::"view", resource);'
+        self.request = {
+            "principal": 'User::"alice"',
+            "action": 'Action::"view"',
+            "resource": 'Photo::"alice_w2.jpg"',
+            "context": {"authenticated": True},
+        }
+
+    def test_conforming_inputs_have_no_warnings(self):
+        authz_result = is_authorized(self.request, self.policies, self.entities, self.schema)
+
+        self.assertEqual(Decision.Allow, authz_result.decision)
+        self.assertEqual([], authz_result.warnings)
+        self.assertNotIn("warnings", authz_result.to_dict())
+
+    def test_warns_when_a_request_to_validate_has_no_schema(self):
+        # without validate_request, a schema is optional
+        authz_result = is_authorized(self.request, self.policies, self.entities)
+        self.assertEqual([], authz_result.warnings)
+
+        authz_result = is_authorized(self.request, self.policies, self.entities, validate_request=True)
+        self.assertEqual(Decision.Allow, authz_result.decision)
+        self.assertEqual(["no schema was provided: the request was not validated"], authz_result.warnings)
+        self.assertEqual([], authz_result.diagnostics.errors)
+
+    def test_warnings_are_reported_apart_from_diagnostics(self):
+        authz_result = is_authorized(self.request, self.policies, self.entities, validate_request=True,
+                                     include_diagnostics=False)
+        self.assertEqual(1, len(authz_result.warnings))
+        self.assertNotIn("diagnostics", authz_result.to_dict())
+
+        authz_result = is_authorized(self.request, self.policies, self.entities, validate_request=True)
+        self.assertEqual(1, len(authz_result.to_dict()["warnings"]))
+        self.assertNotIn("warnings", authz_result.to_dict()["diagnostics"])
+        self.assertEqual(authz_result.warnings, authz_result.diagnostics.warnings)
+
+    def test_warns_of_missing_parents(self):
+        entities = self.entities + [{"uid": {"type": "User", "id": "zoe"}, "attrs": {},
+                                     "parents": [{"type": "UserGroup", "id": "ghosts"}]}]
+        authz_result = is_authorized(self.request, self.policies, entities, entity_validation="none")
+
+        self.assertEqual(Decision.Allow, authz_result.decision)
+        self.assertEqual(['missing entities ignored: parents not in the entities: UserGroup::"ghosts"'],
+                         authz_result.warnings)
+
+    def test_warns_of_deprecated_syntax(self):
+        entities = load_file_as_json("resources/deprecated_syntax/entities.json")
+        authz_result = is_authorized(self.request, self.policies, entities, self.schema)
+        self.assertEqual(Decision.Allow, authz_result.decision)
+        self.assertEqual(1, len(authz_result.warnings))
+        self.assertIn("deprecated syntax in the entities: the __expr escape is deprecated", authz_result.warnings[0])
+
+        request = dict(self.request, context={"authenticated": True, "owner": {"__expr": 'User::"alice"'}})
+        authz_result = is_authorized(request, self.policies, self.entities, entity_validation="none")
+        self.assertEqual(1, len(authz_result.warnings))
+        self.assertIn("deprecated syntax in the request's context", authz_result.warnings[0])
+
+    def test_engine_warns_when_entities_are_stale(self):
+        engine = CedarEngine(self.policies, self.entities, self.schema, max_entities_age=0.2)
+        self.assertEqual([], engine.is_authorized(self.request).warnings)
+        time.sleep(0.3)
+
+        authz_result = engine.is_authorized(self.request)
+        self.assertEqual(Decision.Allow, authz_result.decision)
+        self.assertEqual(1, len(authz_result.warnings))
+        self.assertIn("entities are stale: they were loaded 0s ago, more than the 0.2s allowed",
+                      authz_result.warnings[0])
+
+        # reloading refreshes the entities
+        engine.reload(self.policies, self.entities, self.schema)
+        self.assertEqual([], engine.is_authorized(self.request).warnings)
+
+        with self.assertRaises(ValueError):
+            CedarEngine(self.policies, self.entities, max_entities_age=-1)